@@ -0,0 +1,191 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use aries_askar::{entry::EntryOperation, future::block_on, PassKey, Store, StoreKeyMethod};
+
+const PASS_KEY: &str = "8wHxBXwnLhfw2b7fYcajxNkGoUdGsbLWChU8Fay9Nf2E";
+
+fn askar(db: &Path, args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_askar"))
+        .env("ASKAR_STORE_URI", format!("sqlite://{}", db.display()))
+        .env("ASKAR_KEY_METHOD", "raw")
+        .env("ASKAR_PASS_KEY", PASS_KEY)
+        .env("ASKAR_EXPORT_PASS_KEY", PASS_KEY)
+        .args(args)
+        .output()
+        .expect("Error running askar");
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).expect("Invalid output"),
+    )
+}
+
+fn askar_stdin(db: &Path, args: &[&str], input: &str) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_askar"))
+        .env("ASKAR_STORE_URI", format!("sqlite://{}", db.display()))
+        .env("ASKAR_KEY_METHOD", "raw")
+        .env_remove("ASKAR_PASS_KEY")
+        .env_remove("ASKAR_EXPORT_PASS_KEY")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Error running askar");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().expect("Error running askar");
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).expect("Invalid output"),
+    )
+}
+
+#[test]
+fn cli_profiles_and_export() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("askar-cli");
+    std::fs::create_dir_all(&dir).unwrap();
+    let db = dir.join("store.db");
+    let export = dir.join("store.export");
+    let _ = std::fs::remove_file(&export);
+
+    let (ok, out) = askar(&db, &["provision", "--profile", "main", "--recreate"]);
+    assert!(ok);
+    assert!(out.contains("\"main\""));
+
+    assert!(askar(&db, &["profile", "create", "other"]).0);
+    let (ok, out) = askar(&db, &["profile", "list"]);
+    assert!(ok);
+    assert_eq!(out.lines().collect::<Vec<_>>(), ["main", "other"]);
+
+    assert!(askar(&db, &["profile", "default", "other"]).0);
+    assert_eq!(askar(&db, &["profile", "default"]).1.trim(), "other");
+    assert!(!askar(&db, &["profile", "default", "missing"]).0);
+
+    let (ok, out) = askar(&db, &["check"]);
+    assert!(ok);
+    assert!(out.contains("\"key_method\": \"raw\""));
+
+    assert!(!askar(&db, &["fetch", "category", "name"]).0);
+    assert!(
+        askar(
+            &db,
+            &[
+                "export",
+                export.to_str().unwrap(),
+                "--export-key-method",
+                "raw"
+            ]
+        )
+        .0
+    );
+    assert!(askar(&db, &["scan"]).1.is_empty());
+
+    assert!(askar(&db, &["remove"]).0);
+    assert!(!askar(&db, &["check"]).0);
+}
+
+#[test]
+fn cli_import() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("askar-cli");
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("import-source.db");
+    let target = dir.join("import-target.db");
+    let export = dir.join("import.export");
+    let key_file = dir.join("import.key");
+    let _ = std::fs::remove_file(&export);
+    std::fs::write(&key_file, format!("{}\n", PASS_KEY)).unwrap();
+
+    assert!(askar(&source, &["provision", "--profile", "main", "--recreate"]).0);
+    block_on(async {
+        let store = Store::open(
+            &format!("sqlite://{}", source.display()),
+            Some(StoreKeyMethod::RawKey),
+            PassKey::from(PASS_KEY),
+            None,
+        )
+        .await
+        .unwrap();
+        let mut conn = store.session(None).await.unwrap();
+        conn.update(
+            EntryOperation::Insert,
+            "category",
+            "name",
+            Some(b"value"),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        drop(conn);
+        store.close().await.unwrap();
+    });
+    assert!(
+        askar(
+            &source,
+            &[
+                "export",
+                export.to_str().unwrap(),
+                "--export-key-method",
+                "raw"
+            ]
+        )
+        .0
+    );
+
+    // the store pass key is read from a file and the export key from stdin
+    let (ok, out) = askar_stdin(
+        &target,
+        &[
+            "--pass-key-file",
+            key_file.to_str().unwrap(),
+            "provision",
+            "--profile",
+            "main",
+            "--recreate",
+        ],
+        "",
+    );
+    assert!(ok);
+    assert!(out.contains("\"main\""));
+    let (ok, out) = askar_stdin(
+        &target,
+        &[
+            "--pass-key-file",
+            key_file.to_str().unwrap(),
+            "import",
+            export.to_str().unwrap(),
+            "--export-pass-key-file",
+            "-",
+        ],
+        PASS_KEY,
+    );
+    assert!(ok);
+    assert!(out.contains("\"changes\": 1"));
+    assert!(
+        !askar_stdin(
+            &target,
+            &[
+                "--pass-key-file",
+                "-",
+                "import",
+                export.to_str().unwrap(),
+                "--export-pass-key-file",
+                "-",
+            ],
+            PASS_KEY,
+        )
+        .0
+    );
+
+    let (ok, out) = askar(&target, &["fetch", "category", "name"]);
+    assert!(ok);
+    assert!(out.contains("\"value\": \"value\""));
+}
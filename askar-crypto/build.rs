@@ -0,0 +1,19 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Declare the configuration flags used to select the implementations of the
+    // accelerated primitives. The single colon form is ignored by Cargo versions
+    // which do not support checking configuration flags.
+    for cfg in [
+        "aes_armv8",
+        "aes_force_soft",
+        "chacha20_force_avx2",
+        "chacha20_force_neon",
+        "chacha20_force_soft",
+        "chacha20_force_sse2",
+        "polyval_armv8",
+        "polyval_force_soft",
+    ] {
+        println!("cargo:rustc-check-cfg=cfg({cfg})");
+    }
+}
@@ -0,0 +1,214 @@
+//! Introspection of the hardware acceleration used by cryptographic primitives
+//!
+//! The AES, POLYVAL (used by AES-GCM), ChaCha20 and SHA-2 implementations select
+//! an accelerated backend at runtime according to the detected CPU features. The
+//! portable implementations of all of these primitives are used when the
+//! `force_soft` feature of this crate is enabled. As the AES, POLYVAL and ChaCha20
+//! implementations are selected by configuration flags, this feature requires
+//! building with
+//! `RUSTFLAGS="--cfg aes_force_soft --cfg polyval_force_soft --cfg chacha20_force_soft"`.
+
+use core::fmt::{self, Display, Formatter};
+
+/// An implementation of a cryptographic primitive
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Acceleration {
+    /// The portable software implementation
+    Portable,
+    /// x86 AES-NI instructions
+    AesNi,
+    /// x86 carry-less multiplication instructions
+    Pclmulqdq,
+    /// x86 SHA extensions
+    ShaNi,
+    /// x86 AVX2 vector instructions
+    Avx2,
+    /// x86 SSE2 vector instructions
+    Sse2,
+    /// ARMv8 cryptography extensions
+    Armv8,
+    /// ARM NEON vector instructions
+    Neon,
+}
+
+impl Acceleration {
+    /// Get the identifier of the implementation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Portable => "portable",
+            Self::AesNi => "aes-ni",
+            Self::Pclmulqdq => "pclmulqdq",
+            Self::ShaNi => "sha-ni",
+            Self::Avx2 => "avx2",
+            Self::Sse2 => "sse2",
+            Self::Armv8 => "armv8",
+            Self::Neon => "neon",
+        }
+    }
+
+    /// Check whether the implementation uses hardware acceleration
+    pub fn is_accelerated(&self) -> bool {
+        *self != Self::Portable
+    }
+}
+
+impl Display for Acceleration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The implementations of the cryptographic primitives selected on this platform
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CryptoAcceleration {
+    /// The AES block cipher, used by the AES-GCM, AES-CBC-HMAC and AES-KW algorithms
+    pub aes: Acceleration,
+    /// The POLYVAL universal hash, used by the AES-GCM algorithms
+    pub polyval: Acceleration,
+    /// The ChaCha20 stream cipher, used by the (X)ChaCha20-Poly1305 algorithms
+    /// and for random number generation
+    pub chacha20: Acceleration,
+    /// The SHA-256 hash function
+    pub sha256: Acceleration,
+    /// The SHA-512 hash function
+    pub sha512: Acceleration,
+}
+
+impl CryptoAcceleration {
+    /// Check whether the portable implementation is used for every primitive
+    pub fn is_portable(&self) -> bool {
+        [
+            self.aes,
+            self.polyval,
+            self.chacha20,
+            self.sha256,
+            self.sha512,
+        ]
+        .iter()
+        .all(|accel| !accel.is_accelerated())
+    }
+}
+
+/// Detect the implementations of the cryptographic primitives in use
+pub fn crypto_acceleration() -> CryptoAcceleration {
+    CryptoAcceleration {
+        aes: detect_aes(),
+        polyval: detect_polyval(),
+        chacha20: detect_chacha20(),
+        sha256: detect_sha256(),
+        sha512: detect_sha512(),
+    }
+}
+
+fn detect_aes() -> Acceleration {
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(aes_force_soft)))]
+    if is_x86_feature_detected!("aes") {
+        return Acceleration::AesNi;
+    }
+    #[cfg(all(target_arch = "aarch64", aes_armv8, not(aes_force_soft)))]
+    if std::arch::is_aarch64_feature_detected!("aes") {
+        return Acceleration::Armv8;
+    }
+    Acceleration::Portable
+}
+
+fn detect_polyval() -> Acceleration {
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        not(polyval_force_soft)
+    ))]
+    if is_x86_feature_detected!("pclmulqdq") {
+        return Acceleration::Pclmulqdq;
+    }
+    #[cfg(all(target_arch = "aarch64", polyval_armv8, not(polyval_force_soft)))]
+    // the `aes` feature implies support for PMULL
+    if std::arch::is_aarch64_feature_detected!("aes") {
+        return Acceleration::Armv8;
+    }
+    Acceleration::Portable
+}
+
+fn detect_chacha20() -> Acceleration {
+    if cfg!(chacha20_force_soft) {
+        return Acceleration::Portable;
+    }
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if cfg!(chacha20_force_avx2) {
+            return Acceleration::Avx2;
+        }
+        if cfg!(chacha20_force_sse2) {
+            return Acceleration::Sse2;
+        }
+        #[cfg(not(any(chacha20_force_soft, chacha20_force_avx2, chacha20_force_sse2)))]
+        if is_x86_feature_detected!("avx2") {
+            return Acceleration::Avx2;
+        } else if is_x86_feature_detected!("sse2") {
+            return Acceleration::Sse2;
+        }
+    }
+    if cfg!(all(
+        chacha20_force_neon,
+        target_arch = "aarch64",
+        target_feature = "neon"
+    )) {
+        return Acceleration::Neon;
+    }
+    Acceleration::Portable
+}
+
+fn detect_sha256() -> Acceleration {
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        not(feature = "force_soft")
+    ))]
+    if is_x86_feature_detected!("sha")
+        && is_x86_feature_detected!("sse2")
+        && is_x86_feature_detected!("ssse3")
+        && is_x86_feature_detected!("sse4.1")
+    {
+        return Acceleration::ShaNi;
+    }
+    Acceleration::Portable
+}
+
+fn detect_sha512() -> Acceleration {
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        not(feature = "force_soft")
+    ))]
+    if is_x86_feature_detected!("avx2") {
+        return Acceleration::Avx2;
+    }
+    Acceleration::Portable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acceleration_consistent() {
+        let accel = crypto_acceleration();
+        assert_eq!(accel, crypto_acceleration());
+        assert_eq!(
+            accel.is_portable(),
+            !accel.aes.is_accelerated()
+                && !accel.polyval.is_accelerated()
+                && !accel.chacha20.is_accelerated()
+                && !accel.sha256.is_accelerated()
+                && !accel.sha512.is_accelerated()
+        );
+        if cfg!(any(
+            feature = "force_soft",
+            not(any(
+                target_arch = "x86",
+                target_arch = "x86_64",
+                target_arch = "aarch64"
+            ))
+        )) {
+            assert!(accel.is_portable());
+        }
+        assert_eq!(format!("{}", Acceleration::AesNi), "aes-ni");
+    }
+}
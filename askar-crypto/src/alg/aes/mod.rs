@@ -13,7 +13,7 @@ use crate::{
     encrypt::{KeyAeadInPlace, KeyAeadMeta, KeyAeadParams},
     error::Error,
     generic_array::{typenum::Unsigned, GenericArray},
-    jwk::{JwkEncoder, ToJwk},
+    jwk::{FromJwk, JwkEncoder, JwkParts, ToJwk},
     kdf::{FromKeyDerivation, FromKeyExchange, KeyDerivation, KeyExchange},
     random::KeyMaterial,
     repr::{KeyGen, KeyMeta, KeySecretBytes},
@@ -136,6 +136,24 @@ impl<T: AesType> ToJwk for AesKey<T> {
     }
 }
 
+impl<T: AesType> FromJwk for AesKey<T> {
+    fn from_jwk_parts(jwk: JwkParts<'_>) -> Result<Self, Error> {
+        if jwk.kty != JWK_KEY_TYPE {
+            return Err(err_msg!(InvalidKeyData, "Unsupported key type"));
+        }
+        if jwk.alg != T::JWK_ALG {
+            return Err(err_msg!(InvalidKeyData, "Unsupported key algorithm"));
+        }
+        Ok(Self(KeyType::<T>::try_new_with(|arr| {
+            if jwk.k.decode_base64(arr)? != arr.len() {
+                Err(err_msg!(InvalidKeyData))
+            } else {
+                Ok(())
+            }
+        })?))
+    }
+}
+
 // for direct key agreement (not used currently)
 impl<Lhs, Rhs, T> FromKeyExchange<Lhs, Rhs> for AesKey<T>
 where
@@ -308,4 +326,26 @@ mod tests {
         test_serialize::<A128Kw>();
         test_serialize::<A256Kw>();
     }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn jwk_round_trip() {
+        fn test_jwk<T: AesType>() {
+            let key = AesKey::<T>::random().unwrap();
+            let jwk = key.to_jwk_secret(None).unwrap();
+            let imported = AesKey::<T>::from_jwk_slice(&jwk).unwrap();
+            assert_eq!(imported, key);
+        }
+        test_jwk::<A128Gcm>();
+        test_jwk::<A256Gcm>();
+        test_jwk::<A128CbcHs256>();
+        test_jwk::<A256CbcHs512>();
+        test_jwk::<A128Kw>();
+        test_jwk::<A256Kw>();
+        let jwk = AesKey::<A128Gcm>::random()
+            .unwrap()
+            .to_jwk_secret(None)
+            .unwrap();
+        assert!(AesKey::<A256Gcm>::from_jwk_slice(&jwk).is_err());
+    }
 }
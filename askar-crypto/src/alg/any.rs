@@ -7,7 +7,7 @@ use core::{
 
 #[cfg(feature = "aes")]
 use super::{
-    aes::{A128CbcHs256, A128Gcm, A128Kw, A256CbcHs512, A256Gcm, A256Kw, AesKey},
+    aes::{A128CbcHs256, A128Gcm, A128Kw, A256CbcHs512, A256Gcm, A256Kw, AesKey, AesType},
     AesTypes,
 };
 
@@ -19,7 +19,7 @@ use super::{
 
 #[cfg(feature = "chacha")]
 use super::{
-    chacha20::{Chacha20Key, C20P, XC20P},
+    chacha20::{Chacha20Key, Chacha20Type, C20P, XC20P},
     Chacha20Types,
 };
 
@@ -628,7 +628,38 @@ fn from_jwk_any<R: AllocKey>(jwk: JwkParts<'_>) -> Result<R, Error> {
         ("EC", c) if c == p256::JWK_CURVE => P256KeyPair::from_jwk_parts(jwk).map(R::alloc_key),
         #[cfg(feature = "p384")]
         ("EC", c) if c == p384::JWK_CURVE => P384KeyPair::from_jwk_parts(jwk).map(R::alloc_key),
-        // FIXME implement symmetric keys?
+        #[cfg(feature = "aes")]
+        ("oct", _) if jwk.alg == A128Gcm::JWK_ALG => {
+            AesKey::<A128Gcm>::from_jwk_parts(jwk).map(R::alloc_key)
+        }
+        #[cfg(feature = "aes")]
+        ("oct", _) if jwk.alg == A256Gcm::JWK_ALG => {
+            AesKey::<A256Gcm>::from_jwk_parts(jwk).map(R::alloc_key)
+        }
+        #[cfg(feature = "aes")]
+        ("oct", _) if jwk.alg == A128CbcHs256::JWK_ALG => {
+            AesKey::<A128CbcHs256>::from_jwk_parts(jwk).map(R::alloc_key)
+        }
+        #[cfg(feature = "aes")]
+        ("oct", _) if jwk.alg == A256CbcHs512::JWK_ALG => {
+            AesKey::<A256CbcHs512>::from_jwk_parts(jwk).map(R::alloc_key)
+        }
+        #[cfg(feature = "aes")]
+        ("oct", _) if jwk.alg == A128Kw::JWK_ALG => {
+            AesKey::<A128Kw>::from_jwk_parts(jwk).map(R::alloc_key)
+        }
+        #[cfg(feature = "aes")]
+        ("oct", _) if jwk.alg == A256Kw::JWK_ALG => {
+            AesKey::<A256Kw>::from_jwk_parts(jwk).map(R::alloc_key)
+        }
+        #[cfg(feature = "chacha")]
+        ("oct", _) if jwk.alg == C20P::JWK_ALG => {
+            Chacha20Key::<C20P>::from_jwk_parts(jwk).map(R::alloc_key)
+        }
+        #[cfg(feature = "chacha")]
+        ("oct", _) if jwk.alg == XC20P::JWK_ALG => {
+            Chacha20Key::<XC20P>::from_jwk_parts(jwk).map(R::alloc_key)
+        }
         _ => Err(err_msg!(Unsupported, "Unsupported JWK for key import")),
     }
 }
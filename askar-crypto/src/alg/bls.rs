@@ -77,7 +77,14 @@ impl<Pk: BlsPublicKeyType> Debug for BlsKeyPair<Pk> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("BlsKeyPair")
             .field("crv", &Pk::JWK_CURVE)
-            .field("secret", &self.secret)
+            .field(
+                "secret",
+                if self.secret.is_some() {
+                    &"<secret>"
+                } else {
+                    &"None"
+                },
+            )
             .field("public", &self.public)
             .finish()
     }
@@ -1,5 +1,7 @@
 //! Elliptic curve ECDH and ECDSA support on curve secp256k1
 
+use core::fmt::{self, Debug, Formatter};
+
 use k256::{
     ecdsa::{
         signature::{Signer, Verifier},
@@ -52,7 +54,7 @@ pub static JWK_CURVE: &str = "secp256k1";
 type FieldSize = elliptic_curve::FieldBytesSize<k256::Secp256k1>;
 
 /// A K-256 (secp256k1) public key or keypair
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct K256KeyPair {
     // SECURITY: SecretKey zeroizes on drop
     secret: Option<SecretKey>,
@@ -103,6 +105,22 @@ impl K256KeyPair {
     }
 }
 
+impl Debug for K256KeyPair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("K256KeyPair")
+            .field(
+                "secret",
+                if self.secret.is_some() {
+                    &"<secret>"
+                } else {
+                    &"None"
+                },
+            )
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
 impl HasKeyBackend for K256KeyPair {}
 
 impl HasKeyAlg for K256KeyPair {
@@ -1,6 +1,9 @@
 //! Elliptic curve ECDH and ECDSA support on curve secp256r1
 
-use core::convert::TryFrom;
+use core::{
+    convert::TryFrom,
+    fmt::{self, Debug, Formatter},
+};
 
 use p256::{
     ecdsa::{
@@ -54,7 +57,7 @@ pub static JWK_CURVE: &str = "P-256";
 type FieldSize = elliptic_curve::FieldBytesSize<p256::NistP256>;
 
 /// A P-256 (secp256r1) public key or keypair
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct P256KeyPair {
     // SECURITY: SecretKey zeroizes on drop
     secret: Option<SecretKey>,
@@ -105,6 +108,22 @@ impl P256KeyPair {
     }
 }
 
+impl Debug for P256KeyPair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("P256KeyPair")
+            .field(
+                "secret",
+                if self.secret.is_some() {
+                    &"<secret>"
+                } else {
+                    &"None"
+                },
+            )
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
 impl HasKeyBackend for P256KeyPair {}
 
 impl HasKeyAlg for P256KeyPair {
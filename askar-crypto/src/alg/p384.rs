@@ -1,6 +1,9 @@
 //! Elliptic curve ECDH and ECDSA support on curve secp384r1
 
-use core::convert::{TryFrom, TryInto};
+use core::{
+    convert::{TryFrom, TryInto},
+    fmt::{self, Debug, Formatter},
+};
 
 use p384::{
     ecdsa::{
@@ -53,7 +56,7 @@ pub static JWK_CURVE: &str = "P-384";
 type FieldSize = elliptic_curve::FieldBytesSize<p384::NistP384>;
 
 /// A P-384 (secp384r1) public key or keypair
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct P384KeyPair {
     // SECURITY: SecretKey zeroizes on drop
     secret: Option<SecretKey>,
@@ -105,6 +108,22 @@ impl P384KeyPair {
     }
 }
 
+impl Debug for P384KeyPair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("P384KeyPair")
+            .field(
+                "secret",
+                if self.secret.is_some() {
+                    &"<secret>"
+                } else {
+                    &"None"
+                },
+            )
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
 impl HasKeyBackend for P384KeyPair {}
 
 impl HasKeyAlg for P384KeyPair {
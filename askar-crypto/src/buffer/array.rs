@@ -171,7 +171,7 @@ impl<L: ArrayLength<u8>> Serialize for ArrayKey<L> {
     where
         S: Serializer,
     {
-        serializer.serialize_bytes(self.as_ref())
+        super::serialize_secret(self.as_ref(), serializer)
     }
 }
 
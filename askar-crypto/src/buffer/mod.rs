@@ -4,6 +4,8 @@
 use alloc::vec::Vec;
 use core::{fmt::Debug, ops::Range};
 
+use serde::{ser, Serializer};
+
 use crate::error::Error;
 
 mod array;
@@ -24,6 +26,23 @@ pub use self::string::HexRepr;
 mod writer;
 pub use self::writer::Writer;
 
+/// Serialize secret bytes, refusing human-readable formats such as JSON
+///
+/// Secrets may be written to binary formats for storage, but are never emitted
+/// as text where they could end up in logs or diagnostic output.
+pub(crate) fn serialize_secret<S: Serializer>(
+    secret: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        Err(ser::Error::custom(
+            "Secret values cannot be serialized to a human-readable format",
+        ))
+    } else {
+        serializer.serialize_bytes(secret)
+    }
+}
+
 /// Support for writing to a byte buffer
 pub trait WriteBuffer: Debug {
     /// Append a slice to the buffer
@@ -288,7 +288,7 @@ impl Serialize for SecretBytes {
     where
         S: Serializer,
     {
-        serializer.serialize_bytes(self.as_ref())
+        super::serialize_secret(self.as_ref(), serializer)
     }
 }
 
@@ -6,12 +6,19 @@ use super::{ResizeBuffer, WriteBuffer};
 use crate::error::Error;
 
 /// A structure wrapping a mutable pointer to a buffer
-#[derive(Debug)]
+///
+/// The contents of the buffer are not included in `Debug` output.
 pub struct Writer<'w, B: ?Sized> {
     inner: &'w mut B,
     pos: usize,
 }
 
+impl<B: ?Sized> fmt::Debug for Writer<'_, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer").field("pos", &self.pos).finish()
+    }
+}
+
 impl<B: ?Sized> Writer<'_, B> {
     /// Accessor for the writer position
     pub fn position(&self) -> usize {
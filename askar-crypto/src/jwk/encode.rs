@@ -1,4 +1,4 @@
-use core::fmt::Write;
+use core::fmt::{self, Debug, Formatter, Write};
 
 use serde::{
     ser::{Serialize, SerializeMap},
@@ -66,7 +66,6 @@ pub trait JwkEncoder {
 }
 
 /// A helper structure which writes a JWK to a buffer
-#[derive(Debug)]
 pub struct JwkBufferEncoder<'b, B: WriteBuffer> {
     mode: JwkEncoderMode,
     buffer: &'b mut B,
@@ -76,6 +75,19 @@ pub struct JwkBufferEncoder<'b, B: WriteBuffer> {
     kid: Option<&'b str>,
 }
 
+impl<B: WriteBuffer> Debug for JwkBufferEncoder<'_, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // the buffer may contain private key parameters
+        f.debug_struct("JwkBufferEncoder")
+            .field("mode", &self.mode)
+            .field("empty", &self.empty)
+            .field("alg", &self.alg)
+            .field("key_ops", &self.key_ops)
+            .field("kid", &self.kid)
+            .finish()
+    }
+}
+
 impl<'b, B: WriteBuffer> JwkBufferEncoder<'b, B> {
     /// Create a new instance
     pub fn new(buffer: &'b mut B, mode: JwkEncoderMode) -> Self {
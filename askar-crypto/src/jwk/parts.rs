@@ -15,7 +15,9 @@ use super::ops::{KeyOps, KeyOpsSet};
 use crate::error::Error;
 
 /// A parsed JWK
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// The private key parameters `d` and `k` are not included in `Debug` output.
+#[derive(Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
 pub struct JwkParts<'a> {
     /// Key type
@@ -88,6 +90,23 @@ impl OptAttr<'_> {
     }
 }
 
+impl Debug for JwkParts<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let secret = |attr: &OptAttr<'_>| if attr.is_some() { "<secret>" } else { "None" };
+        f.debug_struct("JwkParts")
+            .field("kty", &self.kty)
+            .field("kid", &self.kid)
+            .field("alg", &self.alg)
+            .field("crv", &self.crv)
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .field("d", &secret(&self.d))
+            .field("k", &secret(&self.k))
+            .field("key_ops", &self.key_ops)
+            .finish()
+    }
+}
+
 impl Debug for OptAttr<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self.0 {
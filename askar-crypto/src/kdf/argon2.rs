@@ -1,5 +1,7 @@
 //! Argon2 key derivation from a password
 
+use core::fmt::{self, Debug, Formatter};
+
 pub use argon2::{Algorithm, Version};
 
 use super::KeyDerivation;
@@ -39,7 +41,6 @@ pub struct Params {
 }
 
 /// Struct wrapping the KDF functionality
-#[derive(Debug)]
 pub struct Argon2<'a> {
     password: &'a [u8],
     salt: &'a [u8],
@@ -60,6 +61,16 @@ impl<'a> Argon2<'a> {
     }
 }
 
+impl Debug for Argon2<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Argon2")
+            .field("password", &"<secret>")
+            .field("salt", &self.salt)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
 impl KeyDerivation for Argon2<'_> {
     fn derive_key_bytes(&mut self, key_output: &mut [u8]) -> Result<(), Error> {
         if key_output.len() > u32::MAX as usize {
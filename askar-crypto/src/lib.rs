@@ -22,6 +22,17 @@ extern crate std;
 #[macro_use]
 extern crate hex_literal;
 
+// The AES, POLYVAL and ChaCha20 implementations are only selected by configuration
+// flags, which cannot be enabled through a crate feature.
+#[cfg(all(
+    feature = "force_soft",
+    not(all(aes_force_soft, polyval_force_soft, chacha20_force_soft))
+))]
+compile_error!(
+    "The `force_soft` feature requires building with \
+    RUSTFLAGS=\"--cfg aes_force_soft --cfg polyval_force_soft --cfg chacha20_force_soft\""
+);
+
 #[macro_use]
 mod error;
 pub use self::error::{Error, ErrorKind};
@@ -29,6 +40,9 @@ pub use self::error::{Error, ErrorKind};
 // re-export
 pub use aead::generic_array;
 
+#[cfg(feature = "std")]
+pub mod accel;
+
 pub mod alg;
 
 pub mod backend;
@@ -0,0 +1,110 @@
+//! Checks that secret material is never emitted through `Debug` or text serialization
+
+#![cfg(all(feature = "any_key", feature = "all_keys", feature = "getrandom"))]
+
+use askar_crypto::{
+    alg::{AesTypes, AnyKey, AnyKeyCreate, BlsCurves, Chacha20Types, EcCurves, KeyAlg},
+    buffer::{ArrayKey, HexRepr, SecretBytes, WriteBuffer, Writer},
+    generic_array::typenum::U32,
+    jwk::{JwkParts, ToJwk},
+    repr::ToSecretBytes,
+};
+use base64::Engine;
+
+const KEY_ALGS: &[KeyAlg] = &[
+    KeyAlg::Aes(AesTypes::A128Gcm),
+    KeyAlg::Aes(AesTypes::A256Gcm),
+    KeyAlg::Aes(AesTypes::A128CbcHs256),
+    KeyAlg::Aes(AesTypes::A256CbcHs512),
+    KeyAlg::Aes(AesTypes::A128Kw),
+    KeyAlg::Aes(AesTypes::A256Kw),
+    KeyAlg::Bls12_381(BlsCurves::G1),
+    KeyAlg::Bls12_381(BlsCurves::G2),
+    KeyAlg::Chacha20(Chacha20Types::C20P),
+    KeyAlg::Chacha20(Chacha20Types::XC20P),
+    KeyAlg::Ed25519,
+    KeyAlg::X25519,
+    KeyAlg::EcCurve(EcCurves::Secp256r1),
+    KeyAlg::EcCurve(EcCurves::Secp256k1),
+    KeyAlg::EcCurve(EcCurves::Secp384r1),
+];
+
+/// Assert that formatted output does not contain any common encoding of a secret
+fn assert_redacted(output: &str, secret: &[u8]) {
+    let mut reversed = secret.to_vec();
+    reversed.reverse();
+    for encoded in [
+        HexRepr(secret).to_string(),
+        HexRepr(&reversed).to_string(),
+        format!("{:?}", secret),
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(secret),
+    ] {
+        assert!(
+            !output.to_lowercase().contains(&encoded),
+            "Secret found in output: {}",
+            output
+        );
+    }
+}
+
+#[test]
+fn key_debug_redacted() {
+    for alg in KEY_ALGS.iter().copied() {
+        let key = Box::<AnyKey>::random(alg).expect("Error creating key");
+        let secret = key.to_secret_bytes().expect("Error exporting secret");
+        assert_redacted(&format!("{:?}", key), secret.as_ref());
+
+        let jwk = key.to_jwk_secret(None).expect("Error encoding secret JWK");
+        let parts = JwkParts::from_slice(jwk.as_ref()).expect("Error parsing JWK");
+        let output = format!("{:?}", parts);
+        assert!(parts.d.is_some() || parts.k.is_some());
+        for attr in [parts.d, parts.k] {
+            if let Some(value) = attr.as_opt_str() {
+                assert!(
+                    !output.contains(value),
+                    "Secret found in output: {}",
+                    output
+                );
+            }
+        }
+        assert_eq!(format!("{:?}", jwk), "<secret>");
+    }
+}
+
+#[test]
+fn buffer_debug_redacted() {
+    let secret = ArrayKey::<U32>::random();
+    assert_redacted(&format!("{:?}", secret), secret.as_ref());
+    let bytes = SecretBytes::from_slice(secret.as_ref());
+    assert_redacted(&format!("{:?}", bytes), secret.as_ref());
+
+    let mut buf = [0u8; 32];
+    let mut writer = Writer::from_slice(&mut buf[..]);
+    writer.buffer_write(secret.as_ref()).unwrap();
+    assert_redacted(&format!("{:?}", writer), secret.as_ref());
+}
+
+#[test]
+#[cfg(feature = "argon2")]
+fn kdf_debug_redacted() {
+    use askar_crypto::kdf::argon2::{Argon2, PARAMS_INTERACTIVE};
+
+    let password = b"my secret password";
+    let kdf = Argon2::new(password, b"testing argon2i salt", PARAMS_INTERACTIVE)
+        .expect("Error creating KDF");
+    let output = format!("{:?}", kdf);
+    assert_redacted(&output, password);
+}
+
+#[test]
+fn secret_text_serialization_refused() {
+    let secret = ArrayKey::<U32>::random();
+    assert!(serde_json::to_string(&secret).is_err());
+    let bytes = SecretBytes::from_slice(secret.as_ref());
+    assert!(serde_json::to_string(&bytes).is_err());
+
+    // binary formats are used for storing keys
+    let enc = serde_cbor::to_vec(&bytes).expect("Error serializing secret");
+    let dec: SecretBytes = serde_cbor::from_slice(&enc).expect("Error deserializing secret");
+    assert_eq!(dec, bytes);
+}
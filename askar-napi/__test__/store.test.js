@@ -0,0 +1,70 @@
+// Run with `node --test askar-napi/__test__` after building the module with
+// `cargo build -p askar-napi`, or set ASKAR_NAPI_MODULE to the library path.
+const assert = require('node:assert/strict')
+const path = require('node:path')
+const test = require('node:test')
+
+function loadModule() {
+  const library = {
+    darwin: 'libaskar_napi.dylib',
+    win32: 'askar_napi.dll',
+  }[process.platform] ?? 'libaskar_napi.so'
+  const modulePath =
+    process.env.ASKAR_NAPI_MODULE ??
+    path.join(__dirname, '..', '..', 'target', 'debug', library)
+  const module = { exports: {} }
+  process.dlopen(module, modulePath)
+  return module.exports
+}
+
+const askar = loadModule()
+
+async function provision() {
+  return askar.Store.provision('sqlite://:memory:', 'raw', askar.generateRawStoreKey())
+}
+
+test('round trip records and keys', async () => {
+  const store = await provision()
+  const txn = await store.transaction()
+  await txn.insert('category', 'name', Buffer.from('value'), [
+    { name: 'tag', value: 'a', plaintext: true },
+  ])
+  await txn.insertKey('key', askar.Key.generate('ed25519'))
+  await txn.commit()
+
+  const session = await store.session()
+  const rows = await session.fetchAll(null, '{"~tag": "a"}')
+  assert.equal(rows.length, 1)
+  assert.equal(rows[0].value.toString(), 'value')
+  assert.deepEqual(rows[0].tags, [{ name: 'tag', value: 'a', plaintext: true }])
+  assert.equal((await session.fetchKey('key')).name, 'key')
+  await session.close()
+  await store.close()
+})
+
+test('async errors carry the error kind', async () => {
+  const store = await provision()
+  const session = await store.session()
+  await session.insert('category', 'name', Buffer.from('value'))
+  await assert.rejects(session.insert('category', 'name', Buffer.from('value')), {
+    code: 'Duplicate',
+  })
+  await assert.rejects(session.count(null, '{"$bad"'), { code: 'Input' })
+  await session.close()
+  await assert.rejects(session.count(), {
+    code: 'Input',
+    message: 'Session has been closed',
+  })
+  await store.close()
+})
+
+test('sync errors carry the error kind', () => {
+  assert.throws(() => askar.Key.generate('unknown'), { code: 'Unsupported' })
+})
+
+test('unreleased sessions are dropped on the library runtime', async () => {
+  // the session is finalized when the module is unloaded, outside of the runtime
+  const store = await provision()
+  const session = await store.session()
+  await session.insert('category', 'name', Buffer.from('value'))
+})
@@ -0,0 +1,64 @@
+use aries_askar::{Error, ErrorKind};
+use napi::{
+    bindgen_prelude::{JsError, ToNapiValue},
+    sys, JsUnknown, NapiValue,
+};
+
+/// The `code` assigned to errors raised in JavaScript, by error kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorCode(pub ErrorKind);
+
+impl AsRef<str> for ErrorCode {
+    fn as_ref(&self) -> &str {
+        match self.0 {
+            ErrorKind::Archived => "Archived",
+            ErrorKind::Backend => "Backend",
+            ErrorKind::Busy => "Busy",
+            ErrorKind::Cancelled => "Cancelled",
+            ErrorKind::Custom => "Custom",
+            ErrorKind::Duplicate => "Duplicate",
+            ErrorKind::Encryption => "Encryption",
+            ErrorKind::Input => "Input",
+            ErrorKind::NotFound => "NotFound",
+            ErrorKind::QuotaExceeded => "QuotaExceeded",
+            ErrorKind::Unexpected => "Unexpected",
+            ErrorKind::Unsupported => "Unsupported",
+        }
+    }
+}
+
+/// The result of an exported function
+pub type Result<T> = napi::Result<T, ErrorCode>;
+
+/// The result of an exported async function
+///
+/// Promises rejected by napi-rs only carry a generic status as their `code`,
+/// so the JavaScript error is created here to preserve the error kind.
+#[derive(Debug)]
+pub struct AsyncResult<T>(pub Result<T>);
+
+impl<T> AsyncResult<T> {
+    /// Convert the successful result value
+    pub fn map<R>(self, f: impl FnOnce(T) -> R) -> AsyncResult<R> {
+        AsyncResult(self.0.map(f))
+    }
+}
+
+impl<T: ToNapiValue> ToNapiValue for AsyncResult<T> {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        match val.0 {
+            Ok(value) => T::to_napi_value(env, value),
+            Err(err) => {
+                // the error value is created in the same env, and is covered by
+                // the rejection tests in `__test__`
+                let err = JsError::from(err).into_value(env);
+                Err(JsUnknown::from_raw_unchecked(env, err).into())
+            }
+        }
+    }
+}
+
+/// Convert a library error into a JavaScript error
+pub fn to_napi(err: Error) -> napi::Error<ErrorCode> {
+    napi::Error::new(ErrorCode(err.kind()), err.to_string())
+}
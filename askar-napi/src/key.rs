@@ -0,0 +1,151 @@
+use std::{str::FromStr, sync::Arc};
+
+use aries_askar::{
+    crypto::alg::KeyAlg,
+    kms::{KeyEntry as AskarKeyEntry, LocalKey},
+    Error,
+};
+use napi::bindgen_prelude::Buffer;
+
+use crate::{
+    error::{to_napi, Result},
+    store::EntryTag,
+};
+
+fn parse_alg(alg: &str) -> Result<KeyAlg> {
+    KeyAlg::from_str(alg).map_err(|err| to_napi(Error::from(err)))
+}
+
+/// A key instance held in memory
+#[napi]
+#[derive(Debug)]
+pub struct Key {
+    pub(crate) key: Arc<LocalKey>,
+}
+
+#[napi]
+impl Key {
+    /// Generate a new random key for an algorithm
+    #[napi(factory)]
+    pub fn generate(alg: String, ephemeral: Option<bool>) -> Result<Self> {
+        let key = LocalKey::generate_with_rng(parse_alg(&alg)?, ephemeral.unwrap_or(false))
+            .map_err(to_napi)?;
+        Ok(Self { key: Arc::new(key) })
+    }
+
+    /// Load a key from a public or secret JWK
+    #[napi(factory)]
+    pub fn from_jwk(jwk: String) -> Result<Self> {
+        let key = LocalKey::from_jwk(&jwk).map_err(to_napi)?;
+        Ok(Self { key: Arc::new(key) })
+    }
+
+    /// Load a public key from its byte representation
+    #[napi(factory)]
+    pub fn from_public_bytes(alg: String, public: Buffer) -> Result<Self> {
+        let key = LocalKey::from_public_bytes(parse_alg(&alg)?, &public).map_err(to_napi)?;
+        Ok(Self { key: Arc::new(key) })
+    }
+
+    /// Load a key from its secret byte representation
+    #[napi(factory)]
+    pub fn from_secret_bytes(alg: String, secret: Buffer) -> Result<Self> {
+        let key = LocalKey::from_secret_bytes(parse_alg(&alg)?, &secret).map_err(to_napi)?;
+        Ok(Self { key: Arc::new(key) })
+    }
+
+    /// The algorithm of the key
+    #[napi(getter)]
+    pub fn algorithm(&self) -> String {
+        self.key.algorithm().as_str().to_string()
+    }
+
+    /// Get the public JWK of the key
+    #[napi]
+    pub fn jwk_public(&self) -> Result<String> {
+        self.key.to_jwk_public(None).map_err(to_napi)
+    }
+
+    /// Get the JWK thumbprint of the key
+    #[napi]
+    pub fn jwk_thumbprint(&self) -> Result<String> {
+        self.key.to_jwk_thumbprint(None).map_err(to_napi)
+    }
+
+    /// Get the byte representation of the public key
+    #[napi]
+    pub fn public_bytes(&self) -> Result<Buffer> {
+        let public = self.key.to_public_bytes().map_err(to_napi)?;
+        Ok(public.to_vec().into())
+    }
+
+    /// Sign a message using the key
+    #[napi]
+    pub fn sign_message(&self, message: Buffer, sig_type: Option<String>) -> Result<Buffer> {
+        let signature = self
+            .key
+            .sign_message(&message, sig_type.as_deref())
+            .map_err(to_napi)?;
+        Ok(signature.into())
+    }
+
+    /// Verify a message signature using the key
+    #[napi]
+    pub fn verify_signature(
+        &self,
+        message: Buffer,
+        signature: Buffer,
+        sig_type: Option<String>,
+    ) -> Result<bool> {
+        self.key
+            .verify_signature(&message, &signature, sig_type.as_deref())
+            .map_err(to_napi)
+    }
+}
+
+/// A key stored in a store profile
+#[napi]
+#[derive(Debug)]
+pub struct KeyEntry {
+    entry: AskarKeyEntry,
+}
+
+impl From<AskarKeyEntry> for KeyEntry {
+    fn from(entry: AskarKeyEntry) -> Self {
+        Self { entry }
+    }
+}
+
+#[napi]
+impl KeyEntry {
+    /// The key name
+    #[napi(getter)]
+    pub fn name(&self) -> String {
+        self.entry.name().to_string()
+    }
+
+    /// The key algorithm, if known
+    #[napi(getter)]
+    pub fn algorithm(&self) -> Option<String> {
+        self.entry.algorithm().map(str::to_string)
+    }
+
+    /// The key metadata
+    #[napi(getter)]
+    pub fn metadata(&self) -> Option<String> {
+        self.entry.metadata().map(str::to_string)
+    }
+
+    /// The key tags
+    #[napi(getter)]
+    pub fn tags(&self) -> Vec<EntryTag> {
+        self.entry.tags_as_slice().iter().map(Into::into).collect()
+    }
+
+    /// Load the stored key
+    #[napi]
+    pub fn load_key(&self) -> Result<Key> {
+        let key = self.entry.load_local_key().map_err(to_napi)?;
+        Ok(Key { key: Arc::new(key) })
+    }
+}
@@ -0,0 +1,31 @@
+//! Node.js bindings for the Aries Askar store and key management interfaces
+//!
+//! The bindings are built as a native module using N-API, and expose the async
+//! library interface directly as promises. Errors raised by the module carry the
+//! library error kind as their `code`.
+
+#![deny(missing_docs, missing_debug_implementations, rust_2018_idioms)]
+// exported functions are not registered with the module in test builds
+#![cfg_attr(test, allow(dead_code))]
+
+#[macro_use]
+extern crate napi_derive;
+
+mod error;
+mod key;
+mod store;
+
+use std::future::Future;
+
+use aries_askar::future::spawn;
+
+pub use self::error::{AsyncResult, ErrorCode, Result};
+
+/// Run a library operation on the library runtime
+async fn run<T, F>(fut: F) -> AsyncResult<T>
+where
+    F: Future<Output = std::result::Result<T, aries_askar::Error>> + Send + 'static,
+    T: Send + 'static,
+{
+    AsyncResult(spawn(fut).await.map_err(error::to_napi))
+}
@@ -0,0 +1,498 @@
+use std::str::FromStr;
+
+use aries_askar::{
+    entry::{Entry as AskarEntry, EntryTag as AskarEntryTag, TagFilter},
+    future::BoxFuture,
+    kms::KeyReference,
+    Error, PassKey, Session as AskarSession, SharedSession, Store as AskarStore, StoreKeyMethod,
+};
+use napi::bindgen_prelude::Buffer;
+
+use crate::{
+    error::{to_napi, AsyncResult, Result},
+    key::{Key, KeyEntry},
+    run,
+};
+
+/// A tag on a store record
+#[napi(object)]
+#[derive(Debug)]
+pub struct EntryTag {
+    /// The tag name
+    pub name: String,
+    /// The tag value
+    pub value: String,
+    /// Whether the tag is stored unencrypted, allowing range queries
+    pub plaintext: Option<bool>,
+}
+
+impl From<&AskarEntryTag> for EntryTag {
+    fn from(tag: &AskarEntryTag) -> Self {
+        Self {
+            name: tag.name().to_string(),
+            value: tag.value().to_string(),
+            plaintext: tag.is_plaintext().then_some(true),
+        }
+    }
+}
+
+impl From<EntryTag> for AskarEntryTag {
+    fn from(tag: EntryTag) -> Self {
+        Self::new(tag.name, tag.value, tag.plaintext.unwrap_or(false))
+    }
+}
+
+/// A record in a store profile
+#[napi(object)]
+pub struct Entry {
+    /// The record category
+    pub category: String,
+    /// The record name
+    pub name: String,
+    /// The record value
+    pub value: Buffer,
+    /// The record tags
+    pub tags: Vec<EntryTag>,
+}
+
+impl std::fmt::Debug for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entry")
+            .field("category", &self.category)
+            .field("name", &self.name)
+            .field("value", &"<secret>")
+            .field("tags", &self.tags)
+            .finish()
+    }
+}
+
+impl From<AskarEntry> for Entry {
+    fn from(entry: AskarEntry) -> Self {
+        Self {
+            tags: entry.tags.iter().map(Into::into).collect(),
+            category: entry.category,
+            name: entry.name,
+            value: entry.value.to_vec().into(),
+        }
+    }
+}
+
+fn parse_tag_filter(tag_filter: Option<String>) -> Result<Option<TagFilter>> {
+    tag_filter
+        .as_deref()
+        .map(TagFilter::from_str)
+        .transpose()
+        .map_err(|err| to_napi(Error::from(err)))
+}
+
+fn convert_tags(tags: Option<Vec<EntryTag>>) -> Option<Vec<AskarEntryTag>> {
+    tags.map(|tags| tags.into_iter().map(Into::into).collect())
+}
+
+/// Generate a new raw store key
+#[napi]
+pub fn generate_raw_store_key(seed: Option<Buffer>) -> Result<String> {
+    let key = AskarStore::new_raw_key(seed.as_deref()).map_err(to_napi)?;
+    Ok(key.to_string())
+}
+
+/// An opened store instance
+#[napi]
+#[derive(Debug)]
+pub struct Store {
+    store: AskarStore,
+}
+
+#[napi]
+impl Store {
+    /// Provision a new store instance using a database URL
+    #[napi]
+    pub async fn provision(
+        spec_uri: String,
+        key_method: Option<String>,
+        pass_key: Option<String>,
+        profile: Option<String>,
+        recreate: Option<bool>,
+    ) -> AsyncResult<Store> {
+        run(async move {
+            let key_method = match key_method.as_deref() {
+                Some(method) => StoreKeyMethod::parse_uri(method)?,
+                None => StoreKeyMethod::default(),
+            };
+            let pass_key = PassKey::from(pass_key.as_deref());
+            AskarStore::provision(
+                &spec_uri,
+                key_method,
+                pass_key,
+                profile,
+                recreate.unwrap_or(false),
+            )
+            .await
+        })
+        .await
+        .map(|store| Self { store })
+    }
+
+    /// Open an existing store instance using a database URL
+    #[napi]
+    pub async fn open(
+        spec_uri: String,
+        key_method: Option<String>,
+        pass_key: Option<String>,
+        profile: Option<String>,
+    ) -> AsyncResult<Store> {
+        run(async move {
+            let key_method = key_method
+                .as_deref()
+                .map(StoreKeyMethod::parse_uri)
+                .transpose()?;
+            let pass_key = PassKey::from(pass_key.as_deref());
+            AskarStore::open(&spec_uri, key_method, pass_key, profile).await
+        })
+        .await
+        .map(|store| Self { store })
+    }
+
+    /// Remove a store instance using a database URL
+    #[napi]
+    pub async fn remove(spec_uri: String) -> AsyncResult<bool> {
+        run(async move { AskarStore::remove(&spec_uri).await }).await
+    }
+
+    /// The name of the profile used by default
+    #[napi(getter)]
+    pub fn profile_name(&self) -> String {
+        self.store.get_active_profile()
+    }
+
+    /// Create a new profile in the store
+    #[napi]
+    pub async fn create_profile(&self, name: Option<String>) -> AsyncResult<String> {
+        let store = self.store.clone();
+        run(async move { store.create_profile(name).await }).await
+    }
+
+    /// Get the names of all profiles in the store
+    #[napi]
+    pub async fn list_profiles(&self) -> AsyncResult<Vec<String>> {
+        let store = self.store.clone();
+        run(async move { store.list_profiles().await }).await
+    }
+
+    /// Remove an existing profile and its records
+    #[napi]
+    pub async fn remove_profile(&self, name: String) -> AsyncResult<bool> {
+        let store = self.store.clone();
+        run(async move { store.remove_profile(name).await }).await
+    }
+
+    /// Get the profile used when none is specified on opening the store
+    #[napi]
+    pub async fn get_default_profile(&self) -> AsyncResult<String> {
+        let store = self.store.clone();
+        run(async move { store.get_default_profile().await }).await
+    }
+
+    /// Set the profile used when none is specified on opening the store
+    #[napi]
+    pub async fn set_default_profile(&self, name: String) -> AsyncResult<()> {
+        let store = self.store.clone();
+        run(async move { store.set_default_profile(name).await }).await
+    }
+
+    /// Create a new session against the store
+    #[napi]
+    pub async fn session(&self, profile: Option<String>) -> AsyncResult<Session> {
+        let store = self.store.clone();
+        run(async move { store.session(profile).await })
+            .await
+            .map(Session::new)
+    }
+
+    /// Create a new transaction against the store
+    #[napi]
+    pub async fn transaction(&self, profile: Option<String>) -> AsyncResult<Session> {
+        let store = self.store.clone();
+        run(async move { store.transaction(profile).await })
+            .await
+            .map(Session::new)
+    }
+
+    /// Close the store instance, waiting for any shutdown procedures to complete
+    #[napi]
+    pub async fn close(&self) -> AsyncResult<()> {
+        let store = self.store.clone();
+        run(async move { store.close().await }).await
+    }
+}
+
+/// An active session or transaction against a store
+///
+/// The session is released once it is closed, committed or rolled back, after
+/// which its methods reject with an `Input` error.
+#[napi]
+#[derive(Debug)]
+pub struct Session {
+    session: SharedSession,
+}
+
+impl Session {
+    fn new(session: AskarSession) -> Self {
+        Self {
+            session: SharedSession::new(session),
+        }
+    }
+
+    /// Run an operation on the active session in the library runtime
+    async fn run<F, T>(&self, f: F) -> AsyncResult<T>
+    where
+        F: for<'s> FnOnce(&'s mut AskarSession) -> BoxFuture<'s, std::result::Result<T, Error>>
+            + Send
+            + 'static,
+        T: Send + 'static,
+    {
+        AsyncResult(self.session.run(f).await.map_err(to_napi))
+    }
+
+    /// Release the session, committing any pending transaction if requested
+    async fn release(&self, commit: bool) -> AsyncResult<()> {
+        AsyncResult(self.session.release(commit).await.map_err(to_napi))
+    }
+}
+
+#[napi]
+impl Session {
+    /// Count the number of records matching a category and tag filter
+    #[napi]
+    pub async fn count(
+        &self,
+        category: Option<String>,
+        tag_filter: Option<String>,
+    ) -> AsyncResult<i64> {
+        let tag_filter = match parse_tag_filter(tag_filter) {
+            Ok(filter) => filter,
+            Err(err) => return AsyncResult(Err(err)),
+        };
+        self.run(move |s| Box::pin(async move { s.count(category.as_deref(), tag_filter).await }))
+            .await
+    }
+
+    /// Fetch a record by category and name
+    #[napi]
+    pub async fn fetch(
+        &self,
+        category: String,
+        name: String,
+        for_update: Option<bool>,
+    ) -> AsyncResult<Option<Entry>> {
+        self.run(move |s| {
+            Box::pin(async move {
+                let entry = s
+                    .fetch(&category, &name, for_update.unwrap_or(false))
+                    .await?;
+                Ok(entry.map(Entry::from))
+            })
+        })
+        .await
+    }
+
+    /// Fetch all records matching a category and tag filter
+    #[napi]
+    pub async fn fetch_all(
+        &self,
+        category: Option<String>,
+        tag_filter: Option<String>,
+        limit: Option<i64>,
+        for_update: Option<bool>,
+    ) -> AsyncResult<Vec<Entry>> {
+        let tag_filter = match parse_tag_filter(tag_filter) {
+            Ok(filter) => filter,
+            Err(err) => return AsyncResult(Err(err)),
+        };
+        self.run(move |s| {
+            Box::pin(async move {
+                let entries = s
+                    .fetch_all(
+                        category.as_deref(),
+                        tag_filter,
+                        limit,
+                        None,
+                        false,
+                        for_update.unwrap_or(false),
+                    )
+                    .await?;
+                Ok(entries.into_iter().map(Entry::from).collect())
+            })
+        })
+        .await
+    }
+
+    /// Insert a new record
+    #[napi]
+    pub async fn insert(
+        &self,
+        category: String,
+        name: String,
+        value: Buffer,
+        tags: Option<Vec<EntryTag>>,
+        expiry_ms: Option<i64>,
+    ) -> AsyncResult<()> {
+        let value = value.to_vec();
+        let tags = convert_tags(tags);
+        self.run(move |s| {
+            Box::pin(async move {
+                s.insert(&category, &name, &value, tags.as_deref(), expiry_ms)
+                    .await
+            })
+        })
+        .await
+    }
+
+    /// Replace the value and tags of an existing record
+    #[napi]
+    pub async fn replace(
+        &self,
+        category: String,
+        name: String,
+        value: Buffer,
+        tags: Option<Vec<EntryTag>>,
+        expiry_ms: Option<i64>,
+    ) -> AsyncResult<()> {
+        let value = value.to_vec();
+        let tags = convert_tags(tags);
+        self.run(move |s| {
+            Box::pin(async move {
+                s.replace(&category, &name, &value, tags.as_deref(), expiry_ms)
+                    .await
+            })
+        })
+        .await
+    }
+
+    /// Remove a record by category and name
+    #[napi]
+    pub async fn remove(&self, category: String, name: String) -> AsyncResult<()> {
+        self.run(move |s| Box::pin(async move { s.remove(&category, &name).await }))
+            .await
+    }
+
+    /// Remove all records matching a category and tag filter
+    #[napi]
+    pub async fn remove_all(
+        &self,
+        category: Option<String>,
+        tag_filter: Option<String>,
+    ) -> AsyncResult<i64> {
+        let tag_filter = match parse_tag_filter(tag_filter) {
+            Ok(filter) => filter,
+            Err(err) => return AsyncResult(Err(err)),
+        };
+        self.run(move |s| {
+            Box::pin(async move { s.remove_all(category.as_deref(), tag_filter).await })
+        })
+        .await
+    }
+
+    /// Insert a new key
+    #[napi]
+    pub async fn insert_key(
+        &self,
+        name: String,
+        key: &Key,
+        metadata: Option<String>,
+        tags: Option<Vec<EntryTag>>,
+        expiry_ms: Option<i64>,
+    ) -> AsyncResult<()> {
+        let key = key.key.clone();
+        let tags = convert_tags(tags);
+        self.run(move |s| {
+            Box::pin(async move {
+                let reference = key
+                    .is_hardware_backed()
+                    .then_some(KeyReference::MobileSecureElement);
+                s.insert_key(
+                    &name,
+                    &key,
+                    metadata.as_deref(),
+                    reference,
+                    tags.as_deref(),
+                    expiry_ms,
+                )
+                .await
+            })
+        })
+        .await
+    }
+
+    /// Fetch a key by name
+    #[napi]
+    pub async fn fetch_key(
+        &self,
+        name: String,
+        for_update: Option<bool>,
+    ) -> AsyncResult<Option<KeyEntry>> {
+        self.run(move |s| {
+            Box::pin(async move {
+                let entry = s.fetch_key(&name, for_update.unwrap_or(false)).await?;
+                Ok(entry.map(KeyEntry::from))
+            })
+        })
+        .await
+    }
+
+    /// Fetch all keys matching an algorithm, thumbprint and tag filter
+    #[napi]
+    pub async fn fetch_all_keys(
+        &self,
+        algorithm: Option<String>,
+        thumbprint: Option<String>,
+        tag_filter: Option<String>,
+        limit: Option<i64>,
+        for_update: Option<bool>,
+    ) -> AsyncResult<Vec<KeyEntry>> {
+        let tag_filter = match parse_tag_filter(tag_filter) {
+            Ok(filter) => filter,
+            Err(err) => return AsyncResult(Err(err)),
+        };
+        self.run(move |s| {
+            Box::pin(async move {
+                let entries = s
+                    .fetch_all_keys(
+                        algorithm.as_deref(),
+                        thumbprint.as_deref(),
+                        tag_filter,
+                        limit,
+                        for_update.unwrap_or(false),
+                    )
+                    .await?;
+                Ok(entries.into_iter().map(KeyEntry::from).collect())
+            })
+        })
+        .await
+    }
+
+    /// Remove a key by name
+    #[napi]
+    pub async fn remove_key(&self, name: String) -> AsyncResult<()> {
+        self.run(move |s| Box::pin(async move { s.remove_key(&name).await }))
+            .await
+    }
+
+    /// Commit the transaction and release the session
+    #[napi]
+    pub async fn commit(&self) -> AsyncResult<()> {
+        self.release(true).await
+    }
+
+    /// Roll back the transaction and release the session
+    #[napi]
+    pub async fn rollback(&self) -> AsyncResult<()> {
+        self.release(false).await
+    }
+
+    /// Release the session, discarding any uncommitted changes
+    #[napi]
+    pub async fn close(&self) -> AsyncResult<()> {
+        self.release(false).await
+    }
+}
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use askar_storage::{postgres::PostgresStoreOptions, sqlite::SqliteStoreOptions, Options};
+
+fuzz_target!(|data: &str| {
+    if let Ok(opts) = Options::parse_uri(data) {
+        let _ = opts.into_uri();
+    }
+    let _ = SqliteStoreOptions::new(data);
+    let _ = PostgresStoreOptions::new(data);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use askar_storage::entry::TagFilter;
+
+fuzz_target!(|data: &str| {
+    let _ = TagFilter::validate(data);
+    if let Ok(filter) = data.parse::<TagFilter>() {
+        // an accepted filter must survive encoding and parsing again
+        let encoded = filter.to_string().expect("Error encoding tag filter");
+        let decoded = encoded
+            .parse::<TagFilter>()
+            .expect("Error parsing encoded tag filter");
+        assert_eq!(filter, decoded);
+    }
+});
@@ -2,19 +2,25 @@
 
 use std::{fmt::Debug, sync::Arc};
 
-use super::{Backend, BackendSession, ManageBackend};
+use super::{Backend, BackendSession, EntrySession, ManageBackend};
 use crate::{
-    backend::OrderBy,
+    backend::{
+        EntryChange, OrderBy, PoolStatus, ProfileExpiryPolicy, ProfileFilter, ProfileQuota,
+        ProfileStats, ProgressFn, RekeyProgress, TraceContext, VersionInfo,
+    },
     entry::{Entry, EntryKind, EntryOperation, EntryTag, Scan, TagFilter},
-    error::Error,
+    error::{Error, ErrorContext},
     future::BoxFuture,
     options::IntoOptions,
-    protect::{PassKey, StoreKeyMethod},
+    protect::{kdf::KdfMethod, KeyCacheStats, PassKey, StoreKeyMethod},
 };
 
 #[cfg(feature = "postgres")]
 use super::postgres;
 
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use crate::backend::db_utils::DbSession;
+
 #[cfg(feature = "sqlite")]
 use super::sqlite;
 
@@ -34,12 +40,28 @@ pub fn into_any_backend(inst: impl Backend + 'static) -> AnyBackend {
 #[derive(Debug)]
 struct WrapBackend<B: Backend>(B);
 
+impl<B: Backend> WrapBackend<B> {
+    #[inline]
+    fn context(&self, operation: &'static str) -> ErrorContext {
+        ErrorContext::new(operation).backend(self.0.backend_kind())
+    }
+}
+
+/// Attach the context of a store operation to a failed result
+fn with_context<'f, T: Send + 'f>(
+    fut: BoxFuture<'f, Result<T, Error>>,
+    context: ErrorContext,
+) -> BoxFuture<'f, Result<T, Error>> {
+    Box::pin(async move { fut.await.map_err(|err| err.with_context(context)) })
+}
+
 impl<B: Backend> Backend for WrapBackend<B> {
     type Session = AnyBackendSession;
 
     #[inline]
     fn create_profile(&self, name: Option<String>) -> BoxFuture<'_, Result<String, Error>> {
-        self.0.create_profile(name)
+        let context = self.context("create_profile").has_profile(name.is_some());
+        with_context(self.0.create_profile(name), context)
     }
 
     #[inline]
@@ -49,22 +71,179 @@ impl<B: Backend> Backend for WrapBackend<B> {
 
     #[inline]
     fn get_default_profile(&self) -> BoxFuture<'_, Result<String, Error>> {
-        self.0.get_default_profile()
+        with_context(
+            self.0.get_default_profile(),
+            self.context("get_default_profile"),
+        )
     }
 
     #[inline]
     fn set_default_profile(&self, profile: String) -> BoxFuture<'_, Result<(), Error>> {
-        self.0.set_default_profile(profile)
+        with_context(
+            self.0.set_default_profile(profile),
+            self.context("set_default_profile").has_profile(true),
+        )
     }
 
     #[inline]
     fn list_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
-        self.0.list_profiles()
+        with_context(self.0.list_profiles(), self.context("list_profiles"))
+    }
+
+    #[inline]
+    fn list_profiles_page(
+        &self,
+        offset: i64,
+        limit: i64,
+    ) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        with_context(
+            self.0.list_profiles_page(offset, limit),
+            self.context("list_profiles"),
+        )
     }
 
     #[inline]
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
-        self.0.remove_profile(name)
+        with_context(
+            self.0.remove_profile(name),
+            self.context("remove_profile").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn remove_profiles<'a>(
+        &'a self,
+        filter: ProfileFilter,
+        batch_size: usize,
+        progress: Option<&'a ProgressFn>,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        with_context(
+            self.0.remove_profiles(filter, batch_size, progress),
+            self.context("remove_profiles"),
+        )
+    }
+
+    #[inline]
+    fn rename_profile(&self, from: String, to: String) -> BoxFuture<'_, Result<bool, Error>> {
+        with_context(
+            self.0.rename_profile(from, to),
+            self.context("rename_profile").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn get_profile_metadata(&self, name: String) -> BoxFuture<'_, Result<Option<String>, Error>> {
+        with_context(
+            self.0.get_profile_metadata(name),
+            self.context("get_profile_metadata").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<String>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(
+            self.0.set_profile_metadata(name, metadata),
+            self.context("set_profile_metadata").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn get_profile_quota(&self, name: String) -> BoxFuture<'_, Result<ProfileQuota, Error>> {
+        with_context(
+            self.0.get_profile_quota(name),
+            self.context("get_profile_quota").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn set_profile_quota(
+        &self,
+        name: String,
+        quota: ProfileQuota,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(
+            self.0.set_profile_quota(name, quota),
+            self.context("set_profile_quota").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn get_profile_expiry_policy(
+        &self,
+        name: String,
+    ) -> BoxFuture<'_, Result<ProfileExpiryPolicy, Error>> {
+        with_context(
+            self.0.get_profile_expiry_policy(name),
+            self.context("get_profile_expiry_policy").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn set_profile_expiry_policy(
+        &self,
+        name: String,
+        policy: ProfileExpiryPolicy,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(
+            self.0.set_profile_expiry_policy(name, policy),
+            self.context("set_profile_expiry_policy").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn set_profile_archived(
+        &self,
+        name: String,
+        archived: bool,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(
+            self.0.set_profile_archived(name, archived),
+            self.context("set_profile_archived").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn list_archived_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        with_context(
+            self.0.list_archived_profiles(),
+            self.context("list_archived_profiles"),
+        )
+    }
+
+    #[inline]
+    fn add_profile_alias(&self, name: String, alias: String) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(
+            self.0.add_profile_alias(name, alias),
+            self.context("add_profile_alias").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn remove_profile_alias(&self, alias: String) -> BoxFuture<'_, Result<bool, Error>> {
+        with_context(
+            self.0.remove_profile_alias(alias),
+            self.context("remove_profile_alias"),
+        )
+    }
+
+    #[inline]
+    fn list_profile_aliases(&self, name: String) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        with_context(
+            self.0.list_profile_aliases(name),
+            self.context("list_profile_aliases").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn profile_stats(&self, name: String) -> BoxFuture<'_, Result<ProfileStats, Error>> {
+        with_context(
+            self.0.profile_stats(name),
+            self.context("profile_stats").has_profile(true),
+        )
     }
 
     #[inline]
@@ -79,16 +258,77 @@ impl<B: Backend> Backend for WrapBackend<B> {
         order_by: Option<OrderBy>,
         descending: bool,
     ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
-        self.0.scan(
-            profile, kind, category, tag_filter, offset, limit, order_by, descending,
+        let context = self
+            .context("scan")
+            .has_profile(profile.is_some())
+            .entry_kind(kind);
+        with_context(
+            self.0.scan(
+                profile, kind, category, tag_filter, offset, limit, order_by, descending,
+            ),
+            context,
+        )
+    }
+
+    #[inline]
+    fn scan_after(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        after: Option<i64>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, (i64, Entry)>, Error>> {
+        let context = self
+            .context("scan_after")
+            .has_profile(profile.is_some())
+            .entry_kind(kind);
+        with_context(
+            self.0.scan_after(
+                profile, kind, category, tag_filter, after, offset, limit, descending,
+            ),
+            context,
+        )
+    }
+
+    #[inline]
+    fn scan_namespace(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        namespace: String,
+        tag_filter: Option<TagFilter>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
+        let context = self
+            .context("scan_namespace")
+            .has_profile(profile.is_some())
+            .entry_kind(kind);
+        with_context(
+            self.0.scan_namespace(
+                profile, kind, namespace, tag_filter, offset, limit, order_by, descending,
+            ),
+            context,
         )
     }
 
     #[inline]
     fn session(&self, profile: Option<String>, transaction: bool) -> Result<Self::Session, Error> {
-        Ok(AnyBackendSession(Box::new(
-            self.0.session(profile, transaction)?,
-        )))
+        let context = self.context("session").has_profile(profile.is_some());
+        Ok(AnyBackendSession {
+            inner: AnySessionInner::new(
+                self.0
+                    .session(profile, transaction)
+                    .map_err(|err| err.with_context(context))?,
+            ),
+            context,
+        })
     }
 
     #[inline]
@@ -97,12 +337,112 @@ impl<B: Backend> Backend for WrapBackend<B> {
         method: StoreKeyMethod,
         key: PassKey<'_>,
     ) -> BoxFuture<'_, Result<(), Error>> {
-        self.0.rekey(method, key)
+        let context = self.context("rekey");
+        with_context(self.0.rekey(method, key), context)
+    }
+
+    #[inline]
+    fn rekey_chunked<'a>(
+        &'a mut self,
+        method: StoreKeyMethod,
+        key: PassKey<'a>,
+        chunk_size: usize,
+        progress: &'a mut (dyn FnMut(RekeyProgress) -> Result<(), Error> + Send),
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        let context = self.context("rekey");
+        with_context(
+            self.0.rekey_chunked(method, key, chunk_size, progress),
+            context,
+        )
+    }
+
+    #[inline]
+    fn upgrade_kdf(
+        &mut self,
+        method: KdfMethod,
+        key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>> {
+        let context = self.context("upgrade_kdf");
+        with_context(self.0.upgrade_kdf(method, key), context)
+    }
+
+    #[inline]
+    fn rekey_profile(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(
+            self.0.rekey_profile(name),
+            self.context("rekey_profile").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn rotate_tag_key(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(
+            self.0.rotate_tag_key(name),
+            self.context("rotate_tag_key").has_profile(true),
+        )
+    }
+
+    #[inline]
+    fn snapshot_to(&self, path: String) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(self.0.snapshot_to(path), self.context("snapshot"))
+    }
+
+    #[inline]
+    fn version_info(&self) -> BoxFuture<'_, Result<VersionInfo, Error>> {
+        with_context(self.0.version_info(), self.context("version_info"))
+    }
+
+    #[inline]
+    fn change_sequence(&self) -> BoxFuture<'_, Result<i64, Error>> {
+        with_context(self.0.change_sequence(), self.context("change_sequence"))
+    }
+
+    #[inline]
+    fn scan_changes(
+        &self,
+        since: i64,
+        until: i64,
+        limit: i64,
+    ) -> BoxFuture<'_, Result<Vec<EntryChange>, Error>> {
+        with_context(
+            self.0.scan_changes(since, until, limit),
+            self.context("scan_changes"),
+        )
+    }
+
+    #[inline]
+    fn prune_changes(&self, until: i64) -> BoxFuture<'_, Result<i64, Error>> {
+        with_context(self.0.prune_changes(until), self.context("prune_changes"))
+    }
+
+    #[inline]
+    fn key_cache_stats(&self) -> BoxFuture<'_, Option<KeyCacheStats>> {
+        self.0.key_cache_stats()
+    }
+
+    #[inline]
+    fn invalidate_profile_key(&self, name: String) -> BoxFuture<'_, ()> {
+        self.0.invalidate_profile_key(name)
+    }
+
+    #[inline]
+    fn pool_status(&self) -> Option<PoolStatus> {
+        self.0.pool_status()
+    }
+
+    #[inline]
+    fn backend_kind(&self) -> &'static str {
+        self.0.backend_kind()
+    }
+
+    #[inline]
+    fn check_pool(&self) -> BoxFuture<'_, Result<(), Error>> {
+        with_context(self.0.check_pool(), self.context("check_pool"))
     }
 
     #[inline]
     fn close(&self) -> BoxFuture<'_, Result<(), Error>> {
-        self.0.close()
+        with_context(self.0.close(), self.context("close"))
     }
 }
 
@@ -135,11 +475,114 @@ impl Backend for AnyBackend {
         self.0.list_profiles()
     }
 
+    #[inline]
+    fn list_profiles_page(
+        &self,
+        offset: i64,
+        limit: i64,
+    ) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        self.0.list_profiles_page(offset, limit)
+    }
+
     #[inline]
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
         self.0.remove_profile(name)
     }
 
+    #[inline]
+    fn remove_profiles<'a>(
+        &'a self,
+        filter: ProfileFilter,
+        batch_size: usize,
+        progress: Option<&'a ProgressFn>,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        self.0.remove_profiles(filter, batch_size, progress)
+    }
+
+    #[inline]
+    fn rename_profile(&self, from: String, to: String) -> BoxFuture<'_, Result<bool, Error>> {
+        self.0.rename_profile(from, to)
+    }
+
+    #[inline]
+    fn get_profile_metadata(&self, name: String) -> BoxFuture<'_, Result<Option<String>, Error>> {
+        self.0.get_profile_metadata(name)
+    }
+
+    #[inline]
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<String>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_metadata(name, metadata)
+    }
+
+    #[inline]
+    fn get_profile_quota(&self, name: String) -> BoxFuture<'_, Result<ProfileQuota, Error>> {
+        self.0.get_profile_quota(name)
+    }
+
+    #[inline]
+    fn set_profile_quota(
+        &self,
+        name: String,
+        quota: ProfileQuota,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_quota(name, quota)
+    }
+
+    #[inline]
+    fn get_profile_expiry_policy(
+        &self,
+        name: String,
+    ) -> BoxFuture<'_, Result<ProfileExpiryPolicy, Error>> {
+        self.0.get_profile_expiry_policy(name)
+    }
+
+    #[inline]
+    fn set_profile_expiry_policy(
+        &self,
+        name: String,
+        policy: ProfileExpiryPolicy,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_expiry_policy(name, policy)
+    }
+
+    #[inline]
+    fn set_profile_archived(
+        &self,
+        name: String,
+        archived: bool,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_archived(name, archived)
+    }
+
+    #[inline]
+    fn list_archived_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        self.0.list_archived_profiles()
+    }
+
+    #[inline]
+    fn add_profile_alias(&self, name: String, alias: String) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.add_profile_alias(name, alias)
+    }
+
+    #[inline]
+    fn remove_profile_alias(&self, alias: String) -> BoxFuture<'_, Result<bool, Error>> {
+        self.0.remove_profile_alias(alias)
+    }
+
+    #[inline]
+    fn list_profile_aliases(&self, name: String) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        self.0.list_profile_aliases(name)
+    }
+
+    #[inline]
+    fn profile_stats(&self, name: String) -> BoxFuture<'_, Result<ProfileStats, Error>> {
+        self.0.profile_stats(name)
+    }
+
     #[inline]
     fn scan(
         &self,
@@ -157,11 +600,43 @@ impl Backend for AnyBackend {
         )
     }
 
+    #[inline]
+    fn scan_after(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        after: Option<i64>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, (i64, Entry)>, Error>> {
+        self.0.scan_after(
+            profile, kind, category, tag_filter, after, offset, limit, descending,
+        )
+    }
+
+    #[inline]
+    fn scan_namespace(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        namespace: String,
+        tag_filter: Option<TagFilter>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
+        self.0.scan_namespace(
+            profile, kind, namespace, tag_filter, offset, limit, order_by, descending,
+        )
+    }
+
     #[inline]
     fn session(&self, profile: Option<String>, transaction: bool) -> Result<Self::Session, Error> {
-        Ok(AnyBackendSession(Box::new(
-            self.0.session(profile, transaction)?,
-        )))
+        self.0.session(profile, transaction)
     }
 
     #[inline]
@@ -178,6 +653,101 @@ impl Backend for AnyBackend {
         }
     }
 
+    #[inline]
+    fn rekey_chunked<'a>(
+        &'a mut self,
+        method: StoreKeyMethod,
+        key: PassKey<'a>,
+        chunk_size: usize,
+        progress: &'a mut (dyn FnMut(RekeyProgress) -> Result<(), Error> + Send),
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        match Arc::get_mut(&mut self.0) {
+            Some(inner) => inner.rekey_chunked(method, key, chunk_size, progress),
+            None => Box::pin(std::future::ready(Err(err_msg!(
+                "Cannot re-key a store with multiple references"
+            )))),
+        }
+    }
+
+    #[inline]
+    fn upgrade_kdf(
+        &mut self,
+        method: KdfMethod,
+        key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>> {
+        match Arc::get_mut(&mut self.0) {
+            Some(inner) => inner.upgrade_kdf(method, key),
+            None => Box::pin(std::future::ready(Err(err_msg!(
+                "Cannot upgrade the store key of a store with multiple references"
+            )))),
+        }
+    }
+
+    #[inline]
+    fn rekey_profile(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.rekey_profile(name)
+    }
+
+    #[inline]
+    fn rotate_tag_key(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.rotate_tag_key(name)
+    }
+
+    #[inline]
+    fn snapshot_to(&self, path: String) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.snapshot_to(path)
+    }
+
+    #[inline]
+    fn version_info(&self) -> BoxFuture<'_, Result<VersionInfo, Error>> {
+        self.0.version_info()
+    }
+
+    #[inline]
+    fn change_sequence(&self) -> BoxFuture<'_, Result<i64, Error>> {
+        self.0.change_sequence()
+    }
+
+    #[inline]
+    fn scan_changes(
+        &self,
+        since: i64,
+        until: i64,
+        limit: i64,
+    ) -> BoxFuture<'_, Result<Vec<EntryChange>, Error>> {
+        self.0.scan_changes(since, until, limit)
+    }
+
+    #[inline]
+    fn prune_changes(&self, until: i64) -> BoxFuture<'_, Result<i64, Error>> {
+        self.0.prune_changes(until)
+    }
+
+    #[inline]
+    fn key_cache_stats(&self) -> BoxFuture<'_, Option<KeyCacheStats>> {
+        self.0.key_cache_stats()
+    }
+
+    #[inline]
+    fn invalidate_profile_key(&self, name: String) -> BoxFuture<'_, ()> {
+        self.0.invalidate_profile_key(name)
+    }
+
+    #[inline]
+    fn pool_status(&self) -> Option<PoolStatus> {
+        self.0.pool_status()
+    }
+
+    #[inline]
+    fn backend_kind(&self) -> &'static str {
+        self.0.backend_kind()
+    }
+
+    #[inline]
+    fn check_pool(&self) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.check_pool()
+    }
+
     #[inline]
     fn close(&self) -> BoxFuture<'_, Result<(), Error>> {
         self.0.close()
@@ -186,7 +756,148 @@ impl Backend for AnyBackend {
 
 /// A dynamic store session instance
 #[derive(Debug)]
-pub struct AnyBackendSession(Box<dyn BackendSession>);
+pub struct AnyBackendSession {
+    inner: AnySessionInner,
+    context: ErrorContext,
+}
+
+/// The session of a built-in backend, which is called without boxing the
+/// futures of record fetches and updates, or the boxed session of another backend
+#[derive(Debug)]
+enum AnySessionInner {
+    #[cfg(feature = "postgres")]
+    Postgres(Box<DbSession<sqlx::Postgres>>),
+    #[cfg(feature = "sqlite")]
+    Sqlite(Box<DbSession<sqlx::Sqlite>>),
+    #[cfg(feature = "odbc")]
+    Odbc(Box<odbc::OdbcSession>),
+    Dyn(Box<dyn BackendSession>),
+}
+
+impl AnySessionInner {
+    fn new<S: BackendSession + 'static>(session: S) -> Self {
+        let mut session = Some(session);
+        #[cfg(feature = "postgres")]
+        if let Some(session) = take_session(&mut session) {
+            return Self::Postgres(Box::new(session));
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(session) = take_session(&mut session) {
+            return Self::Sqlite(Box::new(session));
+        }
+        #[cfg(feature = "odbc")]
+        if let Some(session) = take_session(&mut session) {
+            return Self::Odbc(Box::new(session));
+        }
+        Self::Dyn(Box::new(session.expect("Session already taken")))
+    }
+
+    #[inline]
+    fn as_dyn(&mut self) -> &mut dyn BackendSession {
+        match self {
+            #[cfg(feature = "postgres")]
+            Self::Postgres(session) => session.as_mut(),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(session) => session.as_mut(),
+            #[cfg(feature = "odbc")]
+            Self::Odbc(session) => session.as_mut(),
+            Self::Dyn(session) => session.as_mut(),
+        }
+    }
+}
+
+/// Take a session when it is of the requested type
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+fn take_session<T: 'static>(session: &mut Option<impl std::any::Any>) -> Option<T> {
+    (session as &mut dyn std::any::Any)
+        .downcast_mut::<Option<T>>()
+        .and_then(Option::take)
+}
+
+impl AnyBackendSession {
+    #[inline]
+    fn context(&self, operation: &'static str, kind: Option<EntryKind>) -> ErrorContext {
+        ErrorContext {
+            operation: Some(operation),
+            entry_kind: kind,
+            ..self.context
+        }
+    }
+}
+
+impl EntrySession for AnyBackendSession {
+    async fn fetch_entry<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        for_update: bool,
+    ) -> Result<Option<Entry>, Error> {
+        let context = self.context("fetch", Some(kind));
+        match &mut self.inner {
+            #[cfg(feature = "postgres")]
+            AnySessionInner::Postgres(session) => {
+                session.fetch_entry(kind, category, name, for_update).await
+            }
+            #[cfg(feature = "sqlite")]
+            AnySessionInner::Sqlite(session) => {
+                session.fetch_entry(kind, category, name, for_update).await
+            }
+            #[cfg(feature = "odbc")]
+            AnySessionInner::Odbc(session) => {
+                session.fetch_entry(kind, category, name, for_update).await
+            }
+            AnySessionInner::Dyn(session) => session.fetch(kind, category, name, for_update).await,
+        }
+        .map_err(|err| err.with_context(context))
+    }
+
+    async fn update_entry<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let context = self.context(
+            match operation {
+                EntryOperation::Insert => "insert",
+                EntryOperation::Replace => "replace",
+                EntryOperation::Remove => "remove",
+            },
+            Some(kind),
+        );
+        match &mut self.inner {
+            #[cfg(feature = "postgres")]
+            AnySessionInner::Postgres(session) => {
+                session
+                    .update_entry(kind, operation, category, name, value, tags, expiry_ms)
+                    .await
+            }
+            #[cfg(feature = "sqlite")]
+            AnySessionInner::Sqlite(session) => {
+                session
+                    .update_entry(kind, operation, category, name, value, tags, expiry_ms)
+                    .await
+            }
+            #[cfg(feature = "odbc")]
+            AnySessionInner::Odbc(session) => {
+                session
+                    .update_entry(kind, operation, category, name, value, tags, expiry_ms)
+                    .await
+            }
+            AnySessionInner::Dyn(session) => {
+                session
+                    .update(kind, operation, category, name, value, tags, expiry_ms)
+                    .await
+            }
+        }
+        .map_err(|err| err.with_context(context))
+    }
+}
 
 impl BackendSession for AnyBackendSession {
     /// Count the number of matching records in the store
@@ -196,7 +907,27 @@ impl BackendSession for AnyBackendSession {
         category: Option<&'q str>,
         tag_filter: Option<TagFilter>,
     ) -> BoxFuture<'q, Result<i64, Error>> {
-        self.0.count(kind, category, tag_filter)
+        let context = self.context("count", kind);
+        with_context(
+            self.inner.as_dyn().count(kind, category, tag_filter),
+            context,
+        )
+    }
+
+    /// Count the number of matching records whose category lies within a namespace
+    fn count_namespace<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        namespace: &'q str,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        let context = self.context("count_namespace", kind);
+        with_context(
+            self.inner
+                .as_dyn()
+                .count_namespace(kind, namespace, tag_filter),
+            context,
+        )
     }
 
     /// Fetch a single record from the store by category and name
@@ -207,7 +938,24 @@ impl BackendSession for AnyBackendSession {
         name: &'q str,
         for_update: bool,
     ) -> BoxFuture<'q, Result<Option<Entry>, Error>> {
-        self.0.fetch(kind, category, name, for_update)
+        Box::pin(self.fetch_entry(kind, category, name, for_update))
+    }
+
+    /// Fetch a single record from the store by category and name, without its tags
+    fn fetch_without_tags<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        for_update: bool,
+    ) -> BoxFuture<'q, Result<Option<Entry>, Error>> {
+        let context = self.context("fetch", Some(kind));
+        with_context(
+            self.inner
+                .as_dyn()
+                .fetch_without_tags(kind, category, name, for_update),
+            context,
+        )
     }
 
     /// Fetch all matching records from the store
@@ -221,8 +969,12 @@ impl BackendSession for AnyBackendSession {
         descending: bool,
         for_update: bool,
     ) -> BoxFuture<'q, Result<Vec<Entry>, Error>> {
-        self.0.fetch_all(
-            kind, category, tag_filter, limit, order_by, descending, for_update,
+        let context = self.context("fetch_all", kind);
+        with_context(
+            self.inner.as_dyn().fetch_all(
+                kind, category, tag_filter, limit, order_by, descending, for_update,
+            ),
+            context,
         )
     }
 
@@ -233,7 +985,11 @@ impl BackendSession for AnyBackendSession {
         category: Option<&'q str>,
         tag_filter: Option<TagFilter>,
     ) -> BoxFuture<'q, Result<i64, Error>> {
-        self.0.remove_all(kind, category, tag_filter)
+        let context = self.context("remove_all", kind);
+        with_context(
+            self.inner.as_dyn().remove_all(kind, category, tag_filter),
+            context,
+        )
     }
 
     /// Insert or replace a record in the store
@@ -248,18 +1004,68 @@ impl BackendSession for AnyBackendSession {
         tags: Option<&'q [EntryTag]>,
         expiry_ms: Option<i64>,
     ) -> BoxFuture<'q, Result<(), Error>> {
-        self.0
-            .update(kind, operation, category, name, value, tags, expiry_ms)
+        Box::pin(self.update_entry(kind, operation, category, name, value, tags, expiry_ms))
+    }
+
+    /// Insert or replace a record, storing its value without encryption
+    #[allow(clippy::too_many_arguments)]
+    fn update_unencrypted<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: &'q [u8],
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let context = self.context(
+            match operation {
+                EntryOperation::Insert => "insert_unencrypted",
+                EntryOperation::Replace => "replace_unencrypted",
+                EntryOperation::Remove => "remove",
+            },
+            Some(kind),
+        );
+        with_context(
+            self.inner
+                .as_dyn()
+                .update_unencrypted(kind, operation, category, name, value, tags, expiry_ms),
+            context,
+        )
+    }
+
+    /// Remove a record from the store, overwriting its stored value and tags
+    fn shred<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let context = self.context("shred", Some(kind));
+        with_context(self.inner.as_dyn().shred(kind, category, name), context)
+    }
+
+    /// Attach a trace context to the statements subsequently executed by the session
+    fn set_trace_context(&mut self, trace: Option<TraceContext>) {
+        self.inner.as_dyn().set_trace_context(trace)
+    }
+
+    /// Check whether the profile of the session was created by an update
+    fn take_profile_created(&mut self) -> bool {
+        self.inner.as_dyn().take_profile_created()
     }
 
     /// Test the connection to the store
     fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
-        self.0.ping()
+        let context = self.context("ping", None);
+        with_context(self.inner.as_dyn().ping(), context)
     }
 
     /// Close the current store session
     fn close(&mut self, commit: bool) -> BoxFuture<'_, Result<(), Error>> {
-        self.0.close(commit)
+        let context = self.context(if commit { "commit" } else { "close" }, None);
+        with_context(self.inner.as_dyn().close(commit), context)
     }
 }
 
@@ -275,35 +1081,40 @@ impl<'a> ManageBackend<'a> for &'a str {
         Box::pin(async move {
             let opts = self.into_options()?;
             debug!("Open store with options: {:?}", &opts);
+            let context = scheme_context("open", &opts.scheme).has_profile(profile.is_some());
 
-            match opts.scheme.as_ref() {
-                #[cfg(feature = "postgres")]
-                "postgres" => {
-                    let opts = postgres::PostgresStoreOptions::new(opts)?;
-                    let mgr = opts.open(method, pass_key, profile).await?;
-                    Ok(into_any_backend(mgr))
-                }
+            let result = async move {
+                match opts.scheme.as_ref() {
+                    #[cfg(feature = "postgres")]
+                    "postgres" => {
+                        let opts = postgres::PostgresStoreOptions::new(opts)?;
+                        let mgr = opts.open(method, pass_key, profile).await?;
+                        Ok(into_any_backend(mgr))
+                    }
 
-                #[cfg(feature = "sqlite")]
-                "sqlite" => {
-                    let opts = sqlite::SqliteStoreOptions::new(opts)?;
-                    let mgr = opts.open(method, pass_key, profile).await?;
-                    Ok(into_any_backend(mgr))
-                }
+                    #[cfg(feature = "sqlite")]
+                    "sqlite" => {
+                        let opts = sqlite::SqliteStoreOptions::new(opts)?;
+                        let mgr = opts.open(method, pass_key, profile).await?;
+                        Ok(into_any_backend(mgr))
+                    }
 
-                #[cfg(feature = "odbc")]
-                "odbc" => {
-                    let opts = odbc::OdbcStoreOptions::new(opts)?;
-                    let mgr = opts.open(method, pass_key, profile).await?;
-                    Ok(into_any_backend(mgr))
-                }
+                    #[cfg(feature = "odbc")]
+                    "odbc" => {
+                        let opts = odbc::OdbcStoreOptions::new(opts)?;
+                        let mgr = opts.open(method, pass_key, profile).await?;
+                        Ok(into_any_backend(mgr))
+                    }
 
-                _ => Err(err_msg!(
-                    Unsupported,
-                    "Unsupported backend: {}",
-                    &opts.scheme
-                )),
+                    _ => Err(err_msg!(
+                        Unsupported,
+                        "Unsupported backend: {}",
+                        &opts.scheme
+                    )),
+                }
             }
+            .await;
+            result.map_err(|err| err.with_context(context))
         })
     }
 
@@ -317,35 +1128,40 @@ impl<'a> ManageBackend<'a> for &'a str {
         Box::pin(async move {
             let opts = self.into_options()?;
             debug!("Provision store with options: {:?}", &opts);
+            let context = scheme_context("provision", &opts.scheme).has_profile(profile.is_some());
 
-            match opts.scheme.as_ref() {
-                #[cfg(feature = "postgres")]
-                "postgres" => {
-                    let opts = postgres::PostgresStoreOptions::new(opts)?;
-                    let mgr = opts.provision(method, pass_key, profile, recreate).await?;
-                    Ok(into_any_backend(mgr))
-                }
+            let result = async move {
+                match opts.scheme.as_ref() {
+                    #[cfg(feature = "postgres")]
+                    "postgres" => {
+                        let opts = postgres::PostgresStoreOptions::new(opts)?;
+                        let mgr = opts.provision(method, pass_key, profile, recreate).await?;
+                        Ok(into_any_backend(mgr))
+                    }
 
-                #[cfg(feature = "sqlite")]
-                "sqlite" => {
-                    let opts = sqlite::SqliteStoreOptions::new(opts)?;
-                    let mgr = opts.provision(method, pass_key, profile, recreate).await?;
-                    Ok(into_any_backend(mgr))
-                }
+                    #[cfg(feature = "sqlite")]
+                    "sqlite" => {
+                        let opts = sqlite::SqliteStoreOptions::new(opts)?;
+                        let mgr = opts.provision(method, pass_key, profile, recreate).await?;
+                        Ok(into_any_backend(mgr))
+                    }
 
-                #[cfg(feature = "odbc")]
-                "odbc" => {
-                    let opts = odbc::OdbcStoreOptions::new(opts)?;
-                    let mgr = opts.provision(method, pass_key, profile, recreate).await?;
-                    Ok(into_any_backend(mgr))
-                }
+                    #[cfg(feature = "odbc")]
+                    "odbc" => {
+                        let opts = odbc::OdbcStoreOptions::new(opts)?;
+                        let mgr = opts.provision(method, pass_key, profile, recreate).await?;
+                        Ok(into_any_backend(mgr))
+                    }
 
-                _ => Err(err_msg!(
-                    Unsupported,
-                    "Unsupported backend: {}",
-                    &opts.scheme
-                )),
+                    _ => Err(err_msg!(
+                        Unsupported,
+                        "Unsupported backend: {}",
+                        &opts.scheme
+                    )),
+                }
             }
+            .await;
+            result.map_err(|err| err.with_context(context))
         })
     }
 
@@ -353,32 +1169,102 @@ impl<'a> ManageBackend<'a> for &'a str {
         Box::pin(async move {
             let opts = self.into_options()?;
             debug!("Remove store with options: {:?}", &opts);
+            let context = scheme_context("remove", &opts.scheme);
 
-            match opts.scheme.as_ref() {
-                #[cfg(feature = "postgres")]
-                "postgres" => {
-                    let opts = postgres::PostgresStoreOptions::new(opts)?;
-                    Ok(opts.remove().await?)
-                }
+            let result = async move {
+                match opts.scheme.as_ref() {
+                    #[cfg(feature = "postgres")]
+                    "postgres" => {
+                        let opts = postgres::PostgresStoreOptions::new(opts)?;
+                        Ok(opts.remove().await?)
+                    }
 
-                #[cfg(feature = "sqlite")]
-                "sqlite" => {
-                    let opts = sqlite::SqliteStoreOptions::new(opts)?;
-                    Ok(opts.remove().await?)
-                }
+                    #[cfg(feature = "sqlite")]
+                    "sqlite" => {
+                        let opts = sqlite::SqliteStoreOptions::new(opts)?;
+                        Ok(opts.remove().await?)
+                    }
 
-                #[cfg(feature = "odbc")]
-                "odbc" => {
-                    let opts = odbc::OdbcStoreOptions::new(opts)?;
-                    Ok(opts.remove().await?)
-                }
+                    #[cfg(feature = "odbc")]
+                    "odbc" => {
+                        let opts = odbc::OdbcStoreOptions::new(opts)?;
+                        Ok(opts.remove().await?)
+                    }
 
-                _ => Err(err_msg!(
-                    Unsupported,
-                    "Unsupported backend: {}",
-                    &opts.scheme
-                )),
+                    _ => Err(err_msg!(
+                        Unsupported,
+                        "Unsupported backend: {}",
+                        &opts.scheme
+                    )),
+                }
             }
+            .await;
+            result.map_err(|err| err.with_context(context))
         })
     }
 }
+
+/// Create the context for a store operation on the backend identified by a URI scheme
+fn scheme_context(operation: &'static str, scheme: &str) -> ErrorContext {
+    let context = ErrorContext::new(operation);
+    match scheme {
+        "postgres" => context.backend("postgres"),
+        "sqlite" => context.backend("sqlite"),
+        "odbc" => context.backend("odbc"),
+        _ => context,
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, future::block_on, generate_raw_store_key};
+
+    #[test]
+    fn session_entry_dispatch() {
+        block_on(async {
+            let key = generate_raw_store_key(None).unwrap();
+            let backend = "sqlite://:memory:"
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .unwrap();
+            let mut session = backend.session(None, false).unwrap();
+            assert!(matches!(session.inner, AnySessionInner::Sqlite(_)));
+
+            session
+                .update_entry(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    "name",
+                    Some(b"value"),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            let err = session
+                .update_entry(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    "name",
+                    Some(b"value"),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Duplicate);
+            assert_eq!(err.context().unwrap().operation, Some("insert"));
+            let entry = session
+                .fetch_entry(EntryKind::Item, "category", "name", false)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(entry.value.as_ref(), b"value");
+            session.close(false).await.unwrap();
+            backend.close().await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,480 @@
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Backend, BackendSession, Progress, ProgressFn, ProgressPhase};
+use crate::{
+    crypto::buffer::SecretBytes,
+    entry::{Entry, EntryKind, EntryOperation, EntryTag, TagFilter},
+    error::{Error, ErrorKind},
+    future::unblock,
+    protect::{PassKey, StoreKey, StoreKeyMethod, StoreKeyReference},
+};
+
+const SEGMENT_MAGIC: &[u8; 8] = b"ASKARBK1";
+const SEGMENT_VERSION: u32 = 1;
+const SEGMENT_CHUNK_SIZE: i64 = 512;
+const SEGMENT_MAX_FRAME: usize = 256 * 1024 * 1024;
+
+/// A change to a record, as reported for incremental backups
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryChange {
+    /// The change sequence number
+    pub seq: i64,
+    /// The name of the profile containing the record
+    pub profile: String,
+    /// The current state of the record. The value and tags of a removed
+    /// record are empty
+    pub entry: Entry,
+    /// Whether the record has been removed
+    pub removed: bool,
+}
+
+/// The properties of an incremental backup segment
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BackupSegmentInfo {
+    /// The change sequence number after which changes are included
+    pub since: i64,
+    /// The last change sequence number included in the segment, to be used as
+    /// the checkpoint for the following segment
+    pub until: i64,
+    /// The number of changed records in the segment
+    pub changes: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SegmentHeader {
+    version: u32,
+    key: String,
+    since: i64,
+    until: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SegmentChunk {
+    since: i64,
+    until: i64,
+    index: u32,
+    last: bool,
+    changes: Vec<ChangeRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChangeRecord {
+    profile: String,
+    kind: u8,
+    category: String,
+    name: String,
+    value: Option<SecretBytes>,
+    tags: Vec<(bool, String, String)>,
+}
+
+impl From<EntryChange> for ChangeRecord {
+    fn from(change: EntryChange) -> Self {
+        let entry = change.entry;
+        Self {
+            profile: change.profile,
+            kind: entry.kind as u8,
+            category: entry.category,
+            name: entry.name,
+            value: if change.removed {
+                None
+            } else {
+                Some(entry.value)
+            },
+            tags: entry
+                .tags
+                .into_iter()
+                .map(|tag| match tag {
+                    EntryTag::Encrypted(name, value) => (false, name, value),
+                    EntryTag::Plaintext(name, value) => (true, name, value),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Write an encrypted segment containing the records changed since a checkpoint
+///
+/// Pass a checkpoint of zero to produce a full backup. Each record changed after
+/// the sequence number `since` is included once, in its current state, or as a
+/// removal. The returned `until` sequence number is the checkpoint for the
+/// following segment. The segment is encrypted in chunks with a key resolved
+/// from `method` and `pass_key`, as for a store key; a new salt is generated
+/// for each segment when a key derivation method is used.
+///
+/// Record expiry times and removed profiles are not recorded. When provided,
+/// `progress` is invoked after each chunk is written, with the number of change
+/// sequence numbers in the segment as the estimated total.
+pub async fn backup_changes<B: Backend>(
+    backend: &B,
+    since: i64,
+    method: StoreKeyMethod,
+    pass_key: PassKey<'_>,
+    writer: impl Write,
+    progress: Option<&ProgressFn>,
+) -> Result<BackupSegmentInfo, Error> {
+    if since < 0 {
+        return Err(err_msg!(Input, "Invalid backup checkpoint"));
+    }
+    let until = backend.change_sequence().await?;
+    if until < since {
+        return Err(err_msg!(
+            Input,
+            "Backup checkpoint is ahead of the store changes"
+        ));
+    }
+    let segment_key = resolve_segment_key(method, pass_key).await?;
+    let mut segment = SegmentWriter::new(writer, segment_key, since, until)?;
+    let mut info = BackupSegmentInfo {
+        since,
+        until,
+        changes: 0,
+    };
+    let mut last_seq = since;
+    loop {
+        let changes = backend
+            .scan_changes(last_seq, until, SEGMENT_CHUNK_SIZE)
+            .await?;
+        let last = (changes.len() as i64) < SEGMENT_CHUNK_SIZE;
+        if let Some(change) = changes.last() {
+            last_seq = change.seq;
+        }
+        info.changes += changes.len();
+        segment.write_chunk(changes.into_iter().map(ChangeRecord::from).collect(), last)?;
+        Progress {
+            phase: ProgressPhase::Export,
+            profile: None,
+            processed: info.changes as i64,
+            total: Some(until - since),
+        }
+        .report(progress);
+        if last {
+            break;
+        }
+    }
+    segment.finish()?;
+    Ok(info)
+}
+
+/// Write an encrypted export of the records matching a scan filter
+///
+/// The records of each of `profiles`, or of every profile in the store when no
+/// profiles are given, are filtered by kind, category and tag filter as for
+/// [`Backend::scan`]. The export uses the format of a backup segment, and may be
+/// applied to another store using [`restore_changes`] with a checkpoint of zero.
+/// As it does not reflect all of the changes to the store, its `until` sequence
+/// number is always zero and it cannot be followed by incremental segments.
+/// Record expiry times are not included. When provided, `progress` is invoked
+/// after each page of records is read, with the number of matching records as
+/// the estimated total.
+#[allow(clippy::too_many_arguments)]
+pub async fn export_records<B: Backend>(
+    backend: &B,
+    profiles: Option<Vec<String>>,
+    kind: Option<EntryKind>,
+    category: Option<String>,
+    tag_filter: Option<TagFilter>,
+    method: StoreKeyMethod,
+    pass_key: PassKey<'_>,
+    writer: impl Write,
+    progress: Option<&ProgressFn>,
+) -> Result<BackupSegmentInfo, Error> {
+    let profiles = match profiles {
+        Some(profiles) => profiles,
+        None => backend.list_profiles().await?,
+    };
+    let mut total = None;
+    if progress.is_some() {
+        let mut count = 0;
+        for profile in &profiles {
+            let mut session = backend.session(Some(profile.clone()), false)?;
+            count += session
+                .count(kind, category.as_deref(), tag_filter.clone())
+                .await?;
+            session.close(false).await?;
+        }
+        total = Some(count);
+    }
+    let segment_key = resolve_segment_key(method, pass_key).await?;
+    let mut segment = SegmentWriter::new(writer, segment_key, 0, 0)?;
+    let mut info = BackupSegmentInfo::default();
+    let mut pending = Vec::new();
+    for profile in profiles {
+        let mut scan = backend
+            .scan(
+                Some(profile.clone()),
+                kind,
+                category.clone(),
+                tag_filter.clone(),
+                None,
+                None,
+                None,
+                false,
+            )
+            .await?;
+        while let Some(rows) = scan.fetch_next().await? {
+            info.changes += rows.len();
+            Progress {
+                phase: ProgressPhase::Export,
+                profile: Some(profile.to_string()),
+                processed: info.changes as i64,
+                total,
+            }
+            .report(progress);
+            pending.extend(rows.into_iter().map(|entry| {
+                ChangeRecord::from(EntryChange {
+                    seq: 0,
+                    profile: profile.clone(),
+                    entry,
+                    removed: false,
+                })
+            }));
+            while pending.len() >= SEGMENT_CHUNK_SIZE as usize {
+                let rest = pending.split_off(SEGMENT_CHUNK_SIZE as usize);
+                segment.write_chunk(std::mem::replace(&mut pending, rest), false)?;
+            }
+        }
+    }
+    segment.write_chunk(pending, true)?;
+    segment.finish()?;
+    Ok(info)
+}
+
+/// Apply an encrypted incremental backup segment to a store
+///
+/// Segments must be applied in order: `checkpoint` is the `until` sequence
+/// number of the last segment applied to the target store, or zero for a full
+/// backup. Each chunk of the segment is committed in its own transaction, so a
+/// segment which fails to apply may safely be applied again. The target profiles
+/// are created as required. Returns the properties of the applied segment. When
+/// provided, `progress` is invoked after each chunk is applied.
+pub async fn restore_changes<B: Backend>(
+    backend: &B,
+    mut reader: impl Read,
+    pass_key: PassKey<'_>,
+    checkpoint: i64,
+    progress: Option<&ProgressFn>,
+) -> Result<BackupSegmentInfo, Error> {
+    let mut magic = [0u8; 8];
+    reader
+        .read_exact(&mut magic)
+        .map_err(err_map!(Input, "Error reading backup segment"))?;
+    if &magic != SEGMENT_MAGIC {
+        return Err(err_msg!(Input, "Invalid backup segment"));
+    }
+    let header: SegmentHeader = serde_json::from_slice(&read_frame(&mut reader)?)
+        .map_err(err_map!(Input, "Invalid backup segment header"))?;
+    if header.version != SEGMENT_VERSION {
+        return Err(err_msg!(Unsupported, "Unsupported backup segment version"));
+    }
+    if header.since > checkpoint || header.until < checkpoint {
+        return Err(err_msg!(
+            Input,
+            "Backup segment for changes {} to {} does not follow checkpoint {}",
+            header.since,
+            header.until,
+            checkpoint
+        ));
+    }
+    let key_ref = StoreKeyReference::parse_uri(&header.key)?;
+    let pass_key = pass_key.into_owned();
+    let key = unblock(move || key_ref.resolve(pass_key)).await?;
+
+    let mut info = BackupSegmentInfo {
+        since: header.since,
+        until: header.until,
+        changes: 0,
+    };
+    let mut index = 0;
+    loop {
+        let chunk = read_chunk(&mut reader, &key)?;
+        if chunk.since != header.since || chunk.until != header.until || chunk.index != index {
+            return Err(err_msg!(Input, "Backup segment chunks are out of order"));
+        }
+        info.changes += chunk.changes.len();
+        apply_chunk(backend, chunk.changes).await?;
+        Progress {
+            phase: ProgressPhase::Import,
+            profile: None,
+            processed: info.changes as i64,
+            total: None,
+        }
+        .report(progress);
+        if chunk.last {
+            break;
+        }
+        index += 1;
+    }
+    Ok(info)
+}
+
+/// Resolve the key of a new segment, which may require a costly key derivation
+async fn resolve_segment_key(
+    method: StoreKeyMethod,
+    pass_key: PassKey<'_>,
+) -> Result<(StoreKey, StoreKeyReference), Error> {
+    let pass_key = pass_key.into_owned();
+    unblock(move || method.resolve(pass_key)).await
+}
+
+struct SegmentWriter<W: Write> {
+    writer: W,
+    key: StoreKey,
+    since: i64,
+    until: i64,
+    index: u32,
+}
+
+impl<W: Write> SegmentWriter<W> {
+    fn new(
+        mut writer: W,
+        (key, key_ref): (StoreKey, StoreKeyReference),
+        since: i64,
+        until: i64,
+    ) -> Result<Self, Error> {
+        let header = serde_json::to_vec(&SegmentHeader {
+            version: SEGMENT_VERSION,
+            key: key_ref.into_uri(),
+            since,
+            until,
+        })
+        .map_err(err_map!(Unexpected, "Error encoding backup segment header"))?;
+        writer
+            .write_all(SEGMENT_MAGIC)
+            .map_err(err_map!(Unexpected, "Error writing backup segment"))?;
+        write_frame(&mut writer, &header)?;
+        Ok(Self {
+            writer,
+            key,
+            since,
+            until,
+            index: 0,
+        })
+    }
+
+    fn write_chunk(&mut self, changes: Vec<ChangeRecord>, last: bool) -> Result<(), Error> {
+        let chunk = SegmentChunk {
+            since: self.since,
+            until: self.until,
+            index: self.index,
+            last,
+            changes,
+        };
+        let chunk = serde_cbor::to_vec(&chunk)
+            .map_err(err_map!(Unexpected, "Error encoding backup segment"))?;
+        write_frame(
+            &mut self.writer,
+            &self.key.wrap_data(SecretBytes::from(chunk))?,
+        )?;
+        self.index += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), Error> {
+        self.writer
+            .flush()
+            .map_err(err_map!(Unexpected, "Error writing backup segment"))
+    }
+}
+
+fn read_chunk(reader: &mut impl Read, key: &StoreKey) -> Result<SegmentChunk, Error> {
+    let chunk = key
+        .unwrap_data(read_frame(reader)?)
+        .map_err(err_map!(Encryption, "Error decrypting backup segment"))?;
+    serde_cbor::from_slice(chunk.as_ref()).map_err(err_map!(Input, "Invalid backup segment"))
+}
+
+async fn apply_chunk<B: Backend>(backend: &B, changes: Vec<ChangeRecord>) -> Result<(), Error> {
+    let mut changes = changes.into_iter().peekable();
+    while let Some(profile) = changes.peek().map(|change| change.profile.clone()) {
+        if let Err(err) = backend.create_profile(Some(profile.clone())).await {
+            if err.kind() != ErrorKind::Duplicate {
+                return Err(err);
+            }
+        }
+        let mut txn = backend.session(Some(profile.clone()), true)?;
+        while let Some(change) = changes.next_if(|change| change.profile == profile) {
+            let kind = EntryKind::try_from(change.kind as usize)?;
+            if let Some(value) = change.value {
+                let tags = change
+                    .tags
+                    .into_iter()
+                    .map(|(plaintext, name, value)| {
+                        if plaintext {
+                            EntryTag::Plaintext(name, value)
+                        } else {
+                            EntryTag::Encrypted(name, value)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let mut operation = EntryOperation::Replace;
+                loop {
+                    match txn
+                        .update(
+                            kind,
+                            operation,
+                            &change.category,
+                            &change.name,
+                            Some(value.as_ref()),
+                            Some(tags.as_ref()),
+                            None,
+                        )
+                        .await
+                    {
+                        Err(err)
+                            if err.kind() == ErrorKind::NotFound
+                                && operation == EntryOperation::Replace =>
+                        {
+                            operation = EntryOperation::Insert;
+                        }
+                        res => break res?,
+                    }
+                }
+            } else {
+                match txn
+                    .update(
+                        kind,
+                        EntryOperation::Remove,
+                        &change.category,
+                        &change.name,
+                        None,
+                        None,
+                        None,
+                    )
+                    .await
+                {
+                    Err(err) if err.kind() == ErrorKind::NotFound => (),
+                    res => res?,
+                }
+            }
+        }
+        txn.close(true).await?;
+    }
+    Ok(())
+}
+
+fn write_frame(writer: &mut impl Write, data: &[u8]) -> Result<(), Error> {
+    let len = u32::try_from(data.len())
+        .map_err(|_| err_msg!(Unexpected, "Backup segment chunk too large"))?;
+    writer
+        .write_all(&len.to_be_bytes())
+        .and_then(|_| writer.write_all(data))
+        .map_err(err_map!(Unexpected, "Error writing backup segment"))
+}
+
+fn read_frame(reader: &mut impl Read) -> Result<Vec<u8>, Error> {
+    let mut len = [0u8; 4];
+    reader
+        .read_exact(&mut len)
+        .map_err(err_map!(Input, "Error reading backup segment"))?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > SEGMENT_MAX_FRAME {
+        return Err(err_msg!(Input, "Invalid backup segment"));
+    }
+    let mut data = vec![0u8; len];
+    reader
+        .read_exact(&mut data)
+        .map_err(err_map!(Input, "Error reading backup segment"))?;
+    Ok(data)
+}
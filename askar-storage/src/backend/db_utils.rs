@@ -1,6 +1,15 @@
+#[cfg(feature = "postgres")]
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::future::Future;
+use std::num::NonZeroUsize;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::thread::available_parallelism;
+use std::time::{Duration, Instant};
+
+use chrono::TimeZone;
+use once_cell::sync::Lazy;
 
 use sqlx::{
     database::HasArguments, pool::PoolConnection, Arguments, Database, Encode, Error as SqlxError,
@@ -8,29 +17,266 @@ use sqlx::{
 };
 
 use crate::{
+    crypto::buffer::SecretBytes,
     entry::{EncEntryTag, Entry, EntryKind, EntryTag, TagFilter},
-    error::Error,
-    future::BoxFuture,
-    protect::{EntryEncryptor, KeyCache, PassKey, ProfileId, ProfileKey, StoreKey, StoreKeyMethod},
+    error::{Error, ErrorKind},
+    future::{unblock, unblock_all, BoxFuture},
+    protect::{
+        is_plain_entry_value, EntryEncryptor, KeyCache, NameEncryption, PassKey, ProfileCipher,
+        ProfileId, ProfileKey, StoreKey, StoreKeyMethod,
+    },
     wql::{
         sql::TagSqlEncoder,
         tags::{tag_query, TagQueryEncoder},
     },
 };
 
-use super::OrderBy;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use super::schema::SchemaCompat;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use crate::protect::{
+    kdf::KdfMethod, StoreKeyReference, KEY_GENERATION_CONFIG, PENDING_KEY_CHECK_CONFIG,
+    PENDING_KEY_CONFIG,
+};
+
+use super::{EntryChange, OrderBy, PoolMetrics, PoolStatus, TenantSchemas, TraceContext};
 
 /// cbindgen:ignore
 pub const PAGE_SIZE: usize = 32;
 
+/// Extract the number of entries returned in each page of a scan from the store
+/// URI query parameters
+pub fn page_size_from_query(query: &mut HashMap<String, String>) -> Result<usize, Error> {
+    if let Some(size) = query.remove("page_size") {
+        let size = size
+            .parse()
+            .map_err(err_map!(Input, "Error parsing 'page_size' parameter"))?;
+        if size == 0 {
+            return Err(err_msg!(
+                Input,
+                "The 'page_size' parameter must be positive"
+            ));
+        }
+        Ok(size)
+    } else {
+        Ok(PAGE_SIZE)
+    }
+}
+
+/// Extract whether sessions create missing profiles on demand from the store
+/// URI query parameters
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn auto_create_profiles_from_query(
+    query: &mut HashMap<String, String>,
+    schema_compat: SchemaCompat,
+) -> Result<bool, Error> {
+    let auto_create = if let Some(auto_create) = query.remove("auto_create_profiles") {
+        auto_create.parse().map_err(err_map!(
+            Input,
+            "Error parsing 'auto_create_profiles' parameter"
+        ))?
+    } else {
+        false
+    };
+    if auto_create && schema_compat.is_read_only() {
+        return Err(err_msg!(
+            Input,
+            "The 'auto_create_profiles' parameter is not supported in read-only mode"
+        ));
+    }
+    Ok(auto_create)
+}
+
+/// Extract whether stores in the legacy format, without a MAC of their
+/// configuration, may be upgraded when opened from the store URI query parameters.
+/// Such stores are upgraded unless the parameter is set to `false`
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn upgrade_legacy_config_from_query(
+    query: &mut HashMap<String, String>,
+) -> Result<bool, Error> {
+    if let Some(upgrade) = query.remove("upgrade_legacy_config") {
+        upgrade.parse().map_err(err_map!(
+            Input,
+            "Error parsing 'upgrade_legacy_config' parameter"
+        ))
+    } else {
+        Ok(true)
+    }
+}
+
+/// Logs backend statements which exceed a configured duration
+///
+/// Only the statement identifier, the session profile and trace ID are logged,
+/// never the statement parameters. When enabled, the query plans of slow scan
+/// and count statements are also logged; these may include the encrypted tag
+/// filter values.
+#[derive(Clone, Debug, Default)]
+pub struct SlowQueryLog {
+    pub(crate) threshold: Option<Duration>,
+    pub(crate) explain: bool,
+    profile: Option<Arc<str>>,
+    trace_id: Option<Arc<str>>,
+}
+
+impl SlowQueryLog {
+    /// Create a new instance logging statements which take longer than `threshold`
+    pub fn new(threshold: Option<Duration>) -> Self {
+        Self {
+            threshold,
+            explain: false,
+            profile: None,
+            trace_id: None,
+        }
+    }
+
+    /// Extract the slow statement threshold from the `slow_query_ms` store URI
+    /// query parameter, in milliseconds, and whether to log the plans of slow
+    /// statements from the `slow_query_explain` parameter
+    pub fn from_query(query: &mut HashMap<String, String>) -> Result<Self, Error> {
+        let explain = if let Some(explain) = query.remove("slow_query_explain") {
+            explain.parse().map_err(err_map!(
+                Input,
+                "Error parsing 'slow_query_explain' parameter"
+            ))?
+        } else {
+            false
+        };
+        if let Some(ms) = query.remove("slow_query_ms") {
+            let ms = ms
+                .parse()
+                .map_err(err_map!(Input, "Error parsing 'slow_query_ms' parameter"))?;
+            Ok(Self {
+                explain,
+                ..Self::new(Some(Duration::from_millis(ms)))
+            })
+        } else if explain {
+            Err(err_msg!(
+                Input,
+                "The 'slow_query_explain' parameter requires 'slow_query_ms'"
+            ))
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Attach the profile name of a session to the logged statements
+    pub(crate) fn for_profile(&self, profile: &str) -> Self {
+        Self {
+            threshold: self.threshold,
+            explain: self.explain,
+            profile: self.threshold.map(|_| profile.into()),
+            trace_id: None,
+        }
+    }
+
+    /// Determine whether the plans of slow statements should be logged, in which
+    /// case a copy of the statement parameters must be retained
+    pub(crate) fn explain(&self) -> bool {
+        self.explain
+    }
+
+    /// Attach the trace ID of a session to the logged statements
+    pub(crate) fn set_trace(&mut self, trace: Option<&TraceContext>) {
+        self.trace_id = self
+            .threshold
+            .and(trace)
+            .map(|trace| trace.trace_id().into());
+    }
+
+    /// Begin timing a statement, when logging is enabled
+    pub(crate) fn start(&self) -> Option<Instant> {
+        self.threshold.map(|_| Instant::now())
+    }
+
+    /// Log a statement started by `start` when it has exceeded the threshold,
+    /// returning `true` if the statement was slow
+    pub(crate) fn finish(&self, statement: &str, start: Option<Instant>) -> bool {
+        if let (Some(threshold), Some(start)) = (self.threshold, start) {
+            let elapsed = start.elapsed();
+            if elapsed >= threshold {
+                if let Some(trace_id) = self.trace_id.as_deref() {
+                    warn!(
+                        "Slow statement {} for profile '{}' took {} ms (trace {})",
+                        statement,
+                        self.profile.as_deref().unwrap_or_default(),
+                        elapsed.as_millis(),
+                        trace_id
+                    );
+                } else {
+                    warn!(
+                        "Slow statement {} for profile '{}' took {} ms",
+                        statement,
+                        self.profile.as_deref().unwrap_or_default(),
+                        elapsed.as_millis()
+                    );
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Log the plan of a slow statement, as produced by `EXPLAIN`
+    pub(crate) fn log_plan(&self, statement: &str, plan: Result<Vec<String>, Error>) {
+        match plan {
+            Ok(plan) => warn!(
+                "Query plan for slow statement {} for profile '{}':\n{}",
+                statement,
+                self.profile.as_deref().unwrap_or_default(),
+                plan.join("\n")
+            ),
+            Err(err) => warn!("Error explaining slow statement {}: {}", statement, err),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn annotate_statement<'s>(trace: Option<&TraceContext>, statement: &'s str) -> Cow<'s, str> {
+    match trace {
+        Some(trace) => Cow::Owned(format!("{} {}", statement, trace.sql_comment())),
+        None => Cow::Borrowed(statement),
+    }
+}
+
+/// The number of entries encrypted together when importing records
+pub const IMPORT_BATCH_SIZE: usize = 256;
+
+/// The minimum number of entries assigned to each task in a batch operation
+const MIN_TASK_BATCH: usize = 8;
+
+static BATCH_TASKS: Lazy<usize> =
+    Lazy::new(|| available_parallelism().map_or(1, NonZeroUsize::get));
+
 pub type Expiry = chrono::DateTime<chrono::Utc>;
 
 pub(crate) type Connection<DB> = <DB as Database>::Connection;
 
+/// The encrypted category, name and value of a stored item, the blind index of
+/// its category namespace, its randomized category and name, if any, and its
+/// encrypted expiry time, if any
+pub type EncItemFields = (
+    Vec<u8>,
+    Vec<u8>,
+    Vec<u8>,
+    Vec<u8>,
+    Option<Vec<u8>>,
+    Option<Vec<u8>>,
+);
+
+/// The encrypted category and name of a change log entry, and its randomized
+/// category and name, if any
+pub type EncChangeFields = (Vec<u8>, Vec<u8>, Option<Vec<u8>>);
+
 #[derive(Debug)]
 pub(crate) enum DbSessionState<DB: ExtDatabase> {
-    Active { conn: PoolConnection<DB> },
-    Pending { pool: Pool<DB>, transaction: bool },
+    Active {
+        conn: PoolConnection<DB>,
+    },
+    Pending {
+        pool: Pool<DB>,
+        metrics: Arc<PoolMetrics>,
+        transaction: bool,
+    },
     Closed,
 }
 
@@ -41,25 +287,77 @@ pub struct DbSession<DB: ExtDatabase> {
     profile_key: DbSessionKey,
     state: DbSessionState<DB>,
     txn_depth: usize,
+    slow_query_log: SlowQueryLog,
+    trace_context: Option<TraceContext>,
+    tenant_schemas: Option<TenantSchemas>,
+    auto_create_profile: bool,
+    profile_created: bool,
 }
 
 impl<DB: ExtDatabase> DbSession<DB> {
     pub(crate) fn new(
         pool: Pool<DB>,
+        metrics: Arc<PoolMetrics>,
         cache: Arc<KeyCache>,
         profile: String,
         transaction: bool,
+        slow_query_log: &SlowQueryLog,
     ) -> Self
     where
         DB: Database,
     {
         Self {
+            slow_query_log: slow_query_log.for_profile(&profile),
             profile_key: DbSessionKey::Pending { cache, profile },
-            state: DbSessionState::Pending { pool, transaction },
+            state: DbSessionState::Pending {
+                pool,
+                metrics,
+                transaction,
+            },
             txn_depth: 0,
+            trace_context: None,
+            tenant_schemas: None,
+            auto_create_profile: false,
+            profile_created: false,
         }
     }
 
+    /// Resolve the record tables of the session to the schema of its profile
+    #[cfg(feature = "postgres")]
+    pub(crate) fn with_tenant_schemas(mut self, tenant_schemas: Option<&TenantSchemas>) -> Self {
+        self.tenant_schemas = tenant_schemas.cloned();
+        self
+    }
+
+    /// Create the session profile on the first update if it does not exist
+    pub(crate) fn with_auto_create_profile(mut self, auto_create: bool) -> Self {
+        self.auto_create_profile = auto_create;
+        self
+    }
+
+    /// Determine whether the session profile is created on the first update if
+    /// it does not exist
+    pub(crate) fn auto_create_profile(&self) -> bool {
+        self.auto_create_profile
+    }
+
+    /// Check whether the session profile was created by an update, clearing the
+    /// indicator
+    pub(crate) fn take_profile_created(&mut self) -> bool {
+        std::mem::take(&mut self.profile_created)
+    }
+
+    /// Accessor for the slow statement log of the session
+    pub(crate) fn slow_query_log(&self) -> &SlowQueryLog {
+        &self.slow_query_log
+    }
+
+    /// Attach a trace context to the statements subsequently executed
+    pub(crate) fn set_trace_context(&mut self, trace: Option<TraceContext>) {
+        self.slow_query_log.set_trace(trace.as_ref());
+        self.trace_context = trace;
+    }
+
     #[inline]
     fn connection_mut(&mut self) -> Option<&mut PoolConnection<DB>> {
         if let DbSessionState::Active { conn } = &mut self.state {
@@ -102,10 +400,62 @@ impl<DB: ExtDatabase> DbSession<DB> {
     where
         I: for<'a> GetProfileKey<'a, DB>,
     {
-        if let DbSessionState::Pending { pool, transaction } = &self.state {
+        self.acquire_connection().await?;
+        let profile_id = match &self.profile_key {
+            DbSessionKey::Pending { .. } => {
+                let (profile_id, key) = self.resolve_profile_key(init_key).await?;
+                self.set_profile_key(profile_id, key).await?
+            }
+            DbSessionKey::Active { profile_id, .. } => *profile_id,
+        };
+        Ok(DbSessionActive {
+            inner: self,
+            profile_id,
+        })
+    }
+
+    /// Activate the session before updating records, creating the session
+    /// profile when it does not exist and automatic creation is enabled
+    pub(crate) async fn make_active_for_update<I, C>(
+        &mut self,
+        init_key: I,
+        create_key: C,
+    ) -> Result<DbSessionActive<'_, DB>, Error>
+    where
+        I: for<'a> GetProfileKey<'a, DB>,
+        C: for<'a> GetProfileKey<'a, DB, CreatedProfileKey>,
+    {
+        self.acquire_connection().await?;
+        let profile_id = match &self.profile_key {
+            DbSessionKey::Pending { .. } => {
+                let (profile_id, key) = match self.resolve_profile_key(init_key).await {
+                    Err(err) if err.kind() == ErrorKind::NotFound && self.auto_create_profile => {
+                        let (profile_id, key, created) =
+                            self.resolve_profile_key(create_key).await?;
+                        self.profile_created |= created;
+                        (profile_id, key)
+                    }
+                    result => result?,
+                };
+                self.set_profile_key(profile_id, key).await?
+            }
+            DbSessionKey::Active { profile_id, .. } => *profile_id,
+        };
+        Ok(DbSessionActive {
+            inner: self,
+            profile_id,
+        })
+    }
+
+    async fn acquire_connection(&mut self) -> Result<(), Error> {
+        if let DbSessionState::Pending {
+            pool,
+            metrics,
+            transaction,
+        } = &self.state
+        {
             debug!("Acquire pool connection");
-            let mut conn = pool
-                .acquire()
+            let mut conn = acquire_conn(pool, metrics)
                 .await
                 .map_err(err_map!(Backend, "Error acquiring pool connection"))?;
             if *transaction {
@@ -117,24 +467,40 @@ impl<DB: ExtDatabase> DbSession<DB> {
             }
             self.state = DbSessionState::Active { conn };
         }
-        let profile_id = match &mut self.profile_key {
-            DbSessionKey::Pending { cache, profile } => {
-                let cache = cache.clone();
-                let mut get_profile = String::new();
-                std::mem::swap(profile, &mut get_profile);
-                let in_txn = self.in_transaction();
-                let (profile_id, key) = init_key
-                    .call_once(self.connection_mut().unwrap(), cache, get_profile, in_txn)
-                    .await?;
-                self.profile_key = DbSessionKey::Active { profile_id, key };
-                profile_id
+        Ok(())
+    }
+
+    async fn resolve_profile_key<I, T>(&mut self, init_key: I) -> Result<T, Error>
+    where
+        I: for<'a> GetProfileKey<'a, DB, T>,
+    {
+        let (cache, profile) = match &self.profile_key {
+            DbSessionKey::Pending { cache, profile } => (cache.clone(), profile.clone()),
+            DbSessionKey::Active { .. } => {
+                return Err(err_msg!(Unexpected, "Session profile is already resolved"))
             }
-            DbSessionKey::Active { profile_id, .. } => *profile_id,
         };
-        Ok(DbSessionActive {
-            inner: self,
-            profile_id,
-        })
+        let in_txn = self.in_transaction();
+        init_key
+            .call_once(self.connection_mut().unwrap(), cache, profile, in_txn)
+            .await
+    }
+
+    async fn set_profile_key(
+        &mut self,
+        profile_id: ProfileId,
+        key: Arc<ProfileKey>,
+    ) -> Result<ProfileId, Error> {
+        if let Some(tenant_schemas) = self.tenant_schemas.clone() {
+            // the connection may have been left with the schema of another profile
+            let schema = tenant_schemas.schema(profile_id);
+            let conn = self.connection_mut().unwrap();
+            DB::set_profile_schema(conn, &schema, tenant_schemas.prefix())
+                .await
+                .map_err(err_map!(Backend, "Error selecting profile schema"))?;
+        }
+        self.profile_key = DbSessionKey::Active { profile_id, key };
+        Ok(profile_id)
     }
 
     #[inline]
@@ -183,8 +549,12 @@ impl<DB: ExtDatabase> Drop for DbSession<DB> {
     }
 }
 
-pub(crate) trait GetProfileKey<'a, DB: Database> {
-    type Fut: Future<Output = Result<(ProfileId, Arc<ProfileKey>), Error>>;
+/// A profile key created on demand, and whether the profile was inserted
+/// rather than created concurrently by another session
+pub(crate) type CreatedProfileKey = (ProfileId, Arc<ProfileKey>, bool);
+
+pub(crate) trait GetProfileKey<'a, DB: Database, T = (ProfileId, Arc<ProfileKey>)> {
+    type Fut: Future<Output = Result<T, Error>>;
     fn call_once(
         self,
         conn: &'a mut PoolConnection<DB>,
@@ -194,10 +564,10 @@ pub(crate) trait GetProfileKey<'a, DB: Database> {
     ) -> Self::Fut;
 }
 
-impl<'a, DB: Database, F, Fut> GetProfileKey<'a, DB> for F
+impl<'a, DB: Database, T, F, Fut> GetProfileKey<'a, DB, T> for F
 where
     F: FnOnce(&'a mut PoolConnection<DB>, Arc<KeyCache>, String, bool) -> Fut,
-    Fut: Future<Output = Result<(ProfileId, Arc<ProfileKey>), Error>> + 'a,
+    Fut: Future<Output = Result<T, Error>> + 'a,
 {
     type Fut = Fut;
     fn call_once(
@@ -230,6 +600,20 @@ pub trait ExtDatabase: Database {
     ) -> BoxFuture<'_, Result<(), SqlxError>> {
         <Self as Database>::TransactionManager::begin(conn)
     }
+
+    /// Resolve unqualified table names to a profile schema, followed by the schema
+    /// holding the shared tables of the store
+    fn set_profile_schema<'c>(
+        _conn: &'c mut Connection<Self>,
+        _schema: &'c str,
+        _shared_schema: &'c str,
+    ) -> BoxFuture<'c, Result<(), SqlxError>> {
+        Box::pin(async {
+            Err(SqlxError::Protocol(
+                "Per-profile schemas are not supported by this database".into(),
+            ))
+        })
+    }
 }
 
 pub enum DbSessionRef<'q, DB: ExtDatabase> {
@@ -277,6 +661,24 @@ impl<'q, DB: ExtDatabase> DbSessionActive<'q, DB> {
             .as_mut()
     }
 
+    #[inline]
+    pub fn slow_query_log(&self) -> &SlowQueryLog {
+        &self.inner.slow_query_log
+    }
+
+    /// Append the trace context of the session to a statement as a SQL comment
+    #[cfg(feature = "postgres")]
+    pub fn annotate<'s>(&self, statement: &'s str) -> Cow<'s, str> {
+        annotate_statement(self.inner.trace_context.as_ref(), statement)
+    }
+
+    /// Determine whether statements are annotated with a trace context. Annotated
+    /// statements are unique, and should not be cached by the connection
+    #[cfg(feature = "postgres")]
+    pub fn is_traced(&self) -> bool {
+        self.inner.trace_context.is_some()
+    }
+
     #[allow(unused)]
     pub fn in_transaction(&self) -> bool {
         self.inner.in_transaction()
@@ -335,6 +737,24 @@ impl<'a, DB: ExtDatabase> DbSessionTxn<'a, DB> {
         self.inner.connection_mut().unwrap().as_mut()
     }
 
+    #[inline]
+    pub fn slow_query_log(&self) -> &SlowQueryLog {
+        &self.inner.slow_query_log
+    }
+
+    /// Append the trace context of the session to a statement as a SQL comment
+    #[cfg(feature = "postgres")]
+    pub fn annotate<'s>(&self, statement: &'s str) -> Cow<'s, str> {
+        annotate_statement(self.inner.trace_context.as_ref(), statement)
+    }
+
+    /// Determine whether statements are annotated with a trace context. Annotated
+    /// statements are unique, and should not be cached by the connection
+    #[cfg(feature = "postgres")]
+    pub fn is_traced(&self) -> bool {
+        self.inner.trace_context.is_some()
+    }
+
     pub async fn commit(mut self) -> Result<(), Error> {
         if self.rollback {
             self.rollback = false;
@@ -381,11 +801,40 @@ pub struct EncScanEntry {
     pub name: Vec<u8>,
     pub value: Vec<u8>,
     pub tags: Vec<u8>,
+    pub enc_names: Option<Vec<u8>>,
+}
+
+/// The encrypted fields of a changed record, prepared for decryption
+///
+/// The value is `None` when the record has been removed.
+pub struct EncEntryChange {
+    pub seq: i64,
+    pub profile: String,
+    pub profile_id: ProfileId,
+    pub kind: EntryKind,
+    pub category: Vec<u8>,
+    pub name: Vec<u8>,
+    pub value: Option<Vec<u8>>,
+    pub tags: Vec<u8>,
+    pub enc_names: Option<Vec<u8>>,
+    pub enc_expiry: Option<Vec<u8>>,
+}
+
+/// The encrypted fields of an entry prepared for insertion
+pub struct EncEntry {
+    pub kind: EntryKind,
+    pub category: Vec<u8>,
+    pub name: Vec<u8>,
+    pub value: Vec<u8>,
+    pub namespace: Vec<u8>,
+    pub tags: Vec<EncEntryTag>,
+    pub enc_names: Option<Vec<u8>>,
 }
 
 pub struct QueryParams<'q, DB: Database> {
     args: <DB as HasArguments<'q>>::Arguments,
     count: usize,
+    copy: Option<Box<QueryParams<'q, DB>>>,
 }
 
 impl<'q, DB: Database> QueryParams<'q, DB> {
@@ -393,28 +842,45 @@ impl<'q, DB: Database> QueryParams<'q, DB> {
         Self {
             args: Default::default(),
             count: 0,
+            copy: None,
+        }
+    }
+
+    /// Create a new instance retaining a copy of the parameters when `copy` is set,
+    /// so that the statement may be explained after it has been executed
+    pub fn with_copy(copy: bool) -> Self {
+        Self {
+            copy: copy.then(|| Box::new(Self::new())),
+            ..Self::new()
         }
     }
 
     pub fn extend<I, T>(&mut self, vals: I)
     where
         I: IntoIterator<Item = T>,
-        T: 'q + Send + sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        T: 'q + Send + Clone + sqlx::Encode<'q, DB> + sqlx::Type<DB>,
     {
         for item in vals {
-            self.args.add(item);
-            self.count += 1;
+            self.push(item);
         }
     }
 
     pub fn push<T>(&mut self, val: T)
     where
-        T: 'q + Send + sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+        T: 'q + Send + Clone + sqlx::Encode<'q, DB> + sqlx::Type<DB>,
     {
+        if let Some(copy) = self.copy.as_mut() {
+            copy.push(val.clone());
+        }
         self.args.add(val);
         self.count += 1;
     }
 
+    /// Take the copy of the parameters, if one was retained
+    pub fn take_copy(&mut self) -> Option<Self> {
+        self.copy.take().map(|copy| *copy)
+    }
+
     pub fn len(&self) -> usize {
         self.count
     }
@@ -551,41 +1017,492 @@ pub(crate) fn decode_tags(tags: Vec<u8>) -> Result<Vec<EncEntryTag>, ()> {
     Ok(enc_tags)
 }
 
-pub fn decrypt_scan_batch(
+pub async fn decrypt_scan_batch_parallel(
+    profile_id: ProfileId,
     category: Option<String>,
     enc_rows: Vec<EncScanEntry>,
-    key: &ProfileKey,
+    key: Arc<ProfileKey>,
 ) -> Result<Vec<Entry>, Error> {
-    let mut batch = Vec::with_capacity(enc_rows.len());
-    for enc_entry in enc_rows {
-        batch.push(decrypt_scan_entry(category.as_deref(), enc_entry, key)?);
+    process_batch(enc_rows, move |enc_entry| {
+        decrypt_scan_entry(profile_id, category.as_deref(), enc_entry, &key)
+    })
+    .await
+}
+
+/// Decrypt a batch of scanned records, each paired with its row identifier
+pub async fn decrypt_scan_batch_with_ids(
+    profile_id: ProfileId,
+    category: Option<String>,
+    enc_rows: Vec<(i64, EncScanEntry)>,
+    key: Arc<ProfileKey>,
+) -> Result<Vec<(i64, Entry)>, Error> {
+    process_batch(enc_rows, move |(id, enc_entry)| {
+        Ok((
+            id,
+            decrypt_scan_entry(profile_id, category.as_deref(), enc_entry, &key)?,
+        ))
+    })
+    .await
+}
+
+/// Acquire a pooled connection, recording the outcome in the pool metrics
+pub async fn acquire_conn<DB: Database>(
+    pool: &Pool<DB>,
+    metrics: &PoolMetrics,
+) -> Result<PoolConnection<DB>, SqlxError> {
+    let waited = pool.num_idle() == 0;
+    let start = Instant::now();
+    let result = pool.acquire().await;
+    metrics.record(start.elapsed(), waited, result.is_ok());
+    result
+}
+
+/// Report the state of a connection pool
+pub fn pool_status<DB: Database>(pool: &Pool<DB>, metrics: &PoolMetrics) -> PoolStatus {
+    metrics.status(
+        pool.size(),
+        pool.num_idle() as u32,
+        pool.options().get_max_connections(),
+    )
+}
+
+/// Establish up to `count` pooled connections in advance of their use
+pub async fn warm_pool<DB: ExtDatabase>(pool: &Pool<DB>, count: u32) -> Result<(), SqlxError> {
+    let count = count.min(pool.options().get_max_connections()) as usize;
+    let mut conns = Vec::with_capacity(count);
+    while conns.len() < count {
+        conns.push(pool.acquire().await?);
+    }
+    for mut conn in conns {
+        conn.return_to_pool().await;
+    }
+    Ok(())
+}
+
+pub fn encrypt_entry(
+    entry: Entry,
+    profile_id: ProfileId,
+    key: &ProfileKey,
+) -> Result<EncEntry, Error> {
+    let category = ProfileKey::prepare_input(entry.category.as_bytes());
+    let name = ProfileKey::prepare_input(entry.name.as_bytes());
+    let value = ProfileKey::prepare_input(entry.value.as_ref());
+    let value = key.encrypt_entry_value(
+        profile_id,
+        entry.kind,
+        category.as_ref(),
+        name.as_ref(),
+        value,
+    )?;
+    let enc_names =
+        key.encrypt_entry_names(profile_id, entry.kind, category.as_ref(), name.as_ref())?;
+    Ok(EncEntry {
+        kind: entry.kind,
+        category: key.encrypt_entry_category(category)?,
+        name: key.encrypt_entry_name(name)?,
+        value,
+        namespace: key.category_namespace(&entry.category)?,
+        tags: key.encrypt_entry_tags(prepare_tags(&entry.tags)?)?,
+        enc_names,
+    })
+}
+
+/// Protect a record to be inserted or replaced, leaving its value unencrypted
+/// unless `encrypt_value` is set
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+#[allow(clippy::too_many_arguments)]
+pub async fn encrypt_update(
+    key: Arc<ProfileKey>,
+    profile_id: ProfileId,
+    kind: EntryKind,
+    plain_category: &str,
+    category: SecretBytes,
+    name: SecretBytes,
+    value: &[u8],
+    tags: Option<&[EntryTag]>,
+    encrypt_value: bool,
+) -> Result<EncEntry, Error> {
+    let value = ProfileKey::prepare_input(value);
+    let tags = tags.map(prepare_tags).transpose()?;
+    let namespace = plain_category.to_string();
+    unblock(move || {
+        let value = if encrypt_value {
+            key.encrypt_entry_value(profile_id, kind, category.as_ref(), name.as_ref(), value)?
+        } else {
+            key.encode_entry_value_plain(profile_id, kind, category.as_ref(), name.as_ref(), value)?
+        };
+        let enc_names =
+            key.encrypt_entry_names(profile_id, kind, category.as_ref(), name.as_ref())?;
+        Ok(EncEntry {
+            kind,
+            category: key.encrypt_entry_category(category)?,
+            name: key.encrypt_entry_name(name)?,
+            value,
+            namespace: key.category_namespace(&namespace)?,
+            tags: match tags {
+                Some(tags) => key.encrypt_entry_tags(tags)?,
+                None => Vec::new(),
+            },
+            enc_names,
+        })
+    })
+    .await
+}
+
+/// Compute the range of category namespace indexes selected by a namespace,
+/// returning the inclusive lower bound and the exclusive upper bound, if any
+pub fn encode_namespace_range(
+    key: &ProfileKey,
+    namespace: &str,
+) -> Result<(Vec<u8>, Option<Vec<u8>>), Error> {
+    if namespace.is_empty() {
+        return Err(err_msg!(Input, "Category namespace must not be empty"));
     }
-    Ok(batch)
+    let lower = key.category_namespace(namespace)?;
+    let mut upper = lower.clone();
+    while let Some(last) = upper.pop() {
+        if last < u8::MAX {
+            upper.push(last + 1);
+            return Ok((lower, Some(upper)));
+        }
+    }
+    Ok((lower, None))
+}
+
+/// The name of the config table entry recording that the category namespace
+/// index of existing records must be computed. It is set by a schema migration
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) const NAMESPACE_BACKFILL_CONFIG: &str = "namespace_backfill";
+
+/// The number of records whose category namespace index is computed in each
+/// transaction when updating a store
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) const NAMESPACE_BACKFILL_BATCH_SIZE: i64 = 256;
+
+/// The row identifier, the kind, the encrypted category and name, and the
+/// randomized encryption of the names of a stored item
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) type EncItemNames = (i64, i16, Vec<u8>, Vec<u8>, Option<Vec<u8>>);
+
+/// Compute the category namespace indexes of stored items which predate the index
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn backfill_namespaces(
+    key: &ProfileKey,
+    profile_id: ProfileId,
+    items: Vec<EncItemNames>,
+) -> Result<Vec<(i64, Vec<u8>)>, Error> {
+    items
+        .into_iter()
+        .map(|(id, kind, category, name, enc_names)| {
+            let kind = EntryKind::try_from(kind as usize)?;
+            let (category, _) =
+                key.decrypt_entry_names(profile_id, kind, category, name, enc_names)?;
+            Ok((id, key.category_namespace(&category)?))
+        })
+        .collect()
+}
+
+/// The error returned by namespace queries against a profile containing
+/// records whose category namespace index has not yet been computed
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn namespace_index_incomplete() -> Error {
+    err_msg!(
+        Unsupported,
+        "The category namespace index of the profile is incomplete: \
+        the store must be opened for writing to update it"
+    )
+}
+
+pub async fn encrypt_entry_batch(
+    entries: Vec<Entry>,
+    profile_id: ProfileId,
+    key: Arc<ProfileKey>,
+) -> Result<Vec<EncEntry>, Error> {
+    process_batch(entries, move |entry| encrypt_entry(entry, profile_id, &key)).await
+}
+
+/// Apply an operation to each input on the blocking thread pool, dividing larger
+/// batches between concurrent tasks
+pub async fn process_batch<I, O, F>(inputs: Vec<I>, f: F) -> Result<Vec<O>, Error>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+    F: Fn(I) -> Result<O, Error> + Clone + Send + 'static,
+{
+    let count = inputs.len();
+    let tasks = (*BATCH_TASKS).min(count / MIN_TASK_BATCH).max(1);
+    if tasks == 1 {
+        return unblock(move || inputs.into_iter().map(f).collect()).await;
+    }
+    let chunk_size = count.div_ceil(tasks);
+    let mut inputs = inputs.into_iter();
+    let tasks = (0..tasks)
+        .map(|_| {
+            let chunk = inputs.by_ref().take(chunk_size).collect::<Vec<_>>();
+            let f = f.clone();
+            move || chunk.into_iter().map(f).collect::<Result<Vec<_>, Error>>()
+        })
+        .collect::<Vec<_>>();
+    let mut outputs = Vec::with_capacity(count);
+    for result in unblock_all(tasks).await {
+        outputs.extend(result?);
+    }
+    Ok(outputs)
 }
 
 pub fn decrypt_scan_entry(
+    profile_id: ProfileId,
     category: Option<&str>,
     enc_entry: EncScanEntry,
     key: &ProfileKey,
 ) -> Result<Entry, Error> {
-    let category = match category {
-        Some(c) => c.to_owned(),
-        None => key.decrypt_entry_category(enc_entry.category)?,
+    let (category, name) = match (category, enc_entry.enc_names) {
+        (Some(c), None) => (c.to_owned(), key.decrypt_entry_name(enc_entry.name)?),
+        (_, enc_names) => key.decrypt_entry_names(
+            profile_id,
+            enc_entry.kind,
+            enc_entry.category,
+            enc_entry.name,
+            enc_names,
+        )?,
     };
-    let name = key.decrypt_entry_name(enc_entry.name)?;
-    let value = key.decrypt_entry_value(category.as_bytes(), name.as_bytes(), enc_entry.value)?;
+    let value = key.decrypt_entry_value(
+        profile_id,
+        enc_entry.kind,
+        category.as_bytes(),
+        name.as_bytes(),
+        enc_entry.value,
+    )?;
     let tags = key.decrypt_entry_tags(
         decode_tags(enc_entry.tags).map_err(|_| err_msg!(Unexpected, "Error decoding tags"))?,
     )?;
     Ok(Entry::new(enc_entry.kind, category, name, value, tags))
 }
 
+pub fn decrypt_entry_change(
+    enc_change: EncEntryChange,
+    key: &ProfileKey,
+) -> Result<EntryChange, Error> {
+    let EncEntryChange {
+        seq,
+        profile,
+        profile_id,
+        kind,
+        category,
+        name,
+        value,
+        tags,
+        enc_names,
+        enc_expiry,
+    } = enc_change;
+    let value = if is_expired(
+        key,
+        profile_id,
+        kind,
+        &category,
+        &name,
+        enc_expiry.as_deref(),
+    )? {
+        None
+    } else {
+        value
+    };
+    let (entry, removed) = match value {
+        Some(value) => {
+            let enc_entry = EncScanEntry {
+                kind,
+                category,
+                name,
+                value,
+                tags,
+                enc_names,
+            };
+            (decrypt_scan_entry(profile_id, None, enc_entry, key)?, false)
+        }
+        None => {
+            let (category, name) =
+                key.decrypt_entry_names(profile_id, kind, category, name, enc_names)?;
+            (
+                Entry::new(kind, category, name, Vec::new(), Vec::new()),
+                true,
+            )
+        }
+    };
+    Ok(EntryChange {
+        seq,
+        profile,
+        entry,
+        removed,
+    })
+}
+
+/// Re-encrypt the category, name and value of a stored item under a new profile key
+#[allow(clippy::too_many_arguments)]
+pub fn reencrypt_item(
+    old_key: &ProfileKey,
+    new_key: &ProfileKey,
+    profile_id: ProfileId,
+    kind: EntryKind,
+    category: Vec<u8>,
+    name: Vec<u8>,
+    value: Vec<u8>,
+    enc_names: Option<Vec<u8>>,
+    enc_expiry: Option<Vec<u8>>,
+) -> Result<EncItemFields, Error> {
+    let expiry = enc_expiry
+        .map(|enc| old_key.decrypt_entry_expiry(profile_id, kind, &category, &name, &enc))
+        .transpose()?;
+    let (category, name) =
+        old_key.decrypt_entry_names(profile_id, kind, category, name, enc_names)?;
+    // values stored without encryption remain unencrypted
+    let plain = is_plain_entry_value(&value);
+    let value = old_key.decrypt_entry_value(
+        profile_id,
+        kind,
+        category.as_bytes(),
+        name.as_bytes(),
+        value,
+    )?;
+    let value = if plain {
+        new_key.encode_entry_value_plain(
+            profile_id,
+            kind,
+            category.as_bytes(),
+            name.as_bytes(),
+            value,
+        )?
+    } else {
+        new_key.encrypt_entry_value(
+            profile_id,
+            kind,
+            category.as_bytes(),
+            name.as_bytes(),
+            value,
+        )?
+    };
+    let enc_category =
+        new_key.encrypt_entry_category(ProfileKey::prepare_input(category.as_bytes()))?;
+    let enc_name = new_key.encrypt_entry_name(ProfileKey::prepare_input(name.as_bytes()))?;
+    let enc_expiry = expiry
+        .map(|expiry| {
+            new_key.encrypt_entry_expiry(profile_id, kind, &enc_category, &enc_name, expiry)
+        })
+        .transpose()?;
+    Ok((
+        enc_category,
+        enc_name,
+        value,
+        new_key.category_namespace(&category)?,
+        new_key.encrypt_entry_names(profile_id, kind, category.as_bytes(), name.as_bytes())?,
+        enc_expiry,
+    ))
+}
+
+/// Re-encrypt the category and name of a change log entry under a new profile key
+pub fn reencrypt_change(
+    old_key: &ProfileKey,
+    new_key: &ProfileKey,
+    profile_id: ProfileId,
+    kind: EntryKind,
+    category: Vec<u8>,
+    name: Vec<u8>,
+    enc_names: Option<Vec<u8>>,
+) -> Result<EncChangeFields, Error> {
+    let (category, name) =
+        old_key.decrypt_entry_names(profile_id, kind, category, name, enc_names)?;
+    Ok((
+        new_key.encrypt_entry_category(ProfileKey::prepare_input(category.as_bytes()))?,
+        new_key.encrypt_entry_name(ProfileKey::prepare_input(name.as_bytes()))?,
+        new_key.encrypt_entry_names(profile_id, kind, category.as_bytes(), name.as_bytes())?,
+    ))
+}
+
+/// Re-encrypt a batch of stored item tags under a new profile key
+pub fn reencrypt_tags(
+    old_key: &ProfileKey,
+    new_key: &ProfileKey,
+    tags: Vec<(i64, EncEntryTag)>,
+) -> Result<Vec<(i64, EncEntryTag)>, Error> {
+    // the decrypted tag names and values are only held in zeroized buffers
+    let mut result = Vec::with_capacity(tags.len());
+    for (id, tag) in tags {
+        let name = new_key.encrypt_tag_name(old_key.decrypt_tag_name(tag.name)?)?;
+        let value = if tag.plaintext {
+            tag.value
+        } else {
+            new_key.encrypt_tag_value(old_key.decrypt_tag_value(tag.value)?)?
+        };
+        result.push((
+            id,
+            EncEntryTag {
+                name,
+                value,
+                plaintext: tag.plaintext,
+            },
+        ));
+    }
+    Ok(result)
+}
+
 pub fn expiry_timestamp(expire_ms: i64) -> Result<Expiry, Error> {
     chrono::Utc::now()
         .checked_add_signed(chrono::Duration::milliseconds(expire_ms))
         .ok_or_else(|| err_msg!(Unexpected, "Invalid expiry timestamp"))
 }
 
+/// Prepare the stored expiry time of an entry, and its encrypted expiry time
+///
+/// When the profile key has an expiry bucket, the stored expiry time is rounded
+/// up to the end of its bucket and the exact expiry time is encrypted.
+pub fn encode_expiry(
+    key: &ProfileKey,
+    profile_id: ProfileId,
+    kind: EntryKind,
+    enc_category: &[u8],
+    enc_name: &[u8],
+    expire_ms: Option<i64>,
+) -> Result<(Option<Expiry>, Option<Vec<u8>>), Error> {
+    let expiry = match expire_ms {
+        Some(expire_ms) => expiry_timestamp(expire_ms)?,
+        None => return Ok((None, None)),
+    };
+    if let Some(bucket) = key.expiry_bucket() {
+        let exact = expiry.timestamp_millis();
+        let bucket = i64::from(bucket) * 1000;
+        let bucketed = exact
+            .checked_add(bucket - 1)
+            .and_then(|ts| {
+                chrono::Utc
+                    .timestamp_millis_opt(ts.div_euclid(bucket) * bucket)
+                    .single()
+            })
+            .ok_or_else(|| err_msg!(Unexpected, "Invalid expiry timestamp"))?;
+        let enc_expiry =
+            key.encrypt_entry_expiry(profile_id, kind, enc_category, enc_name, exact)?;
+        Ok((Some(bucketed), Some(enc_expiry)))
+    } else {
+        Ok((Some(expiry), None))
+    }
+}
+
+/// Check whether an entry has passed its encrypted expiry time, if any
+pub fn is_expired(
+    key: &ProfileKey,
+    profile_id: ProfileId,
+    kind: EntryKind,
+    enc_category: &[u8],
+    enc_name: &[u8],
+    enc_expiry: Option<&[u8]>,
+) -> Result<bool, Error> {
+    if let Some(enc_expiry) = enc_expiry {
+        let expiry =
+            key.decrypt_entry_expiry(profile_id, kind, enc_category, enc_name, enc_expiry)?;
+        Ok(expiry <= chrono::Utc::now().timestamp_millis())
+    } else {
+        Ok(false)
+    }
+}
+
 #[allow(clippy::type_complexity)]
 pub fn encode_tag_filter<Q: QueryPrepare>(
     tag_filter: Option<TagFilter>,
@@ -632,25 +1549,55 @@ pub fn prepare_tags(tags: &[EntryTag]) -> Result<Vec<EntryTag>, Error> {
     Ok(result)
 }
 
+/// The ordering and range of the records selected by a query
+#[derive(Debug, Default)]
+pub struct QueryPage {
+    /// Select only the records following this row identifier in the order of the query
+    pub after: Option<i64>,
+    /// The number of records to skip
+    pub offset: Option<i64>,
+    /// The maximum number of records to select
+    pub limit: Option<i64>,
+    /// The ordering of the records
+    pub order_by: Option<OrderBy>,
+    /// Whether the records are selected in descending order
+    pub descending: bool,
+}
+
 pub fn extend_query<'q, Q: QueryPrepare>(
     query: &str,
     args: &mut QueryParams<'q, Q::DB>,
     tag_filter: Option<(String, Vec<Vec<u8>>)>,
-    offset: Option<i64>,
-    limit: Option<i64>,
-    order_by: Option<OrderBy>,
-    descending: bool,
+    page: QueryPage,
 ) -> Result<String, Error>
 where
     i64: for<'e> Encode<'e, Q::DB> + Type<Q::DB>,
     Vec<u8>: for<'e> Encode<'e, Q::DB> + Type<Q::DB>,
 {
+    let QueryPage {
+        after,
+        offset,
+        limit,
+        order_by,
+        descending,
+    } = page;
     let mut query = query.to_string();
     if let Some((filter_clause, filter_args)) = tag_filter {
         args.extend(filter_args);
         query.push_str(" AND "); // assumes WHERE already occurs
         query.push_str(&filter_clause);
     };
+    // resume a scan after the row identifier of the last record returned
+    if let Some(after) = after {
+        let idx = (args.len() + 1) as i64;
+        args.push(after);
+        let clause = if descending {
+            " AND i.id < $$"
+        } else {
+            " AND i.id > $$"
+        };
+        query.push_str(&replace_arg_placeholders::<Q>(clause, idx));
+    }
     // Only add ordering, and limit/offset, if the query starts with SELECT
     if query.trim_start().to_uppercase().starts_with("SELECT") {
         if let Some(order_by_value) = order_by {
@@ -667,6 +1614,9 @@ where
 pub fn init_keys(
     method: StoreKeyMethod,
     pass_key: PassKey<'_>,
+    cipher: ProfileCipher,
+    name_encryption: NameEncryption,
+    expiry_bucket: Option<u32>,
 ) -> Result<(ProfileKey, Vec<u8>, StoreKey, String), Error> {
     if method == StoreKeyMethod::RawKey && pass_key.is_empty() {
         // disallow random key for a new database
@@ -676,7 +1626,7 @@ pub fn init_keys(
         ));
     }
     let (store_key, store_key_ref) = method.resolve(pass_key)?;
-    let profile_key = ProfileKey::new()?;
+    let profile_key = ProfileKey::new_with_options(cipher, name_encryption, expiry_bucket)?;
     let enc_profile_key = encode_profile_key(&profile_key, &store_key)?;
     Ok((
         profile_key,
@@ -686,6 +1636,175 @@ pub fn init_keys(
     ))
 }
 
+/// Parse the recorded generation of the store key, which is absent for stores
+/// whose key has not been replaced since they were provisioned
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn parse_key_generation(value: Option<&str>) -> Result<u64, Error> {
+    value.map_or(Ok(0), |value| {
+        value
+            .parse()
+            .map_err(err_map!(Unexpected, "Invalid store key generation"))
+    })
+}
+
+/// Check that the store key held by an instance has not been replaced by
+/// another instance since the store was opened
+///
+/// Data wrapped or authenticated under a replaced store key could not be read
+/// by other instances, so that a stale instance must be reopened before writing.
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn check_key_generation(expected: u64, recorded: Option<&str>) -> Result<(), Error> {
+    if parse_key_generation(recorded)? != expected {
+        return Err(err_msg!(
+            Busy,
+            "Store key was replaced by another instance, the store must be reopened"
+        ));
+    }
+    Ok(())
+}
+
+/// The configuration of the store key, as read by a backend before replacing it
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+#[derive(Debug, Default)]
+pub(crate) struct StoreKeyConfig {
+    pub key_ref: Option<String>,
+    pub key_generation: Option<String>,
+    pub pending: Option<String>,
+    pub pending_check: Option<String>,
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+impl StoreKeyConfig {
+    /// Collect the store key configuration from the rows of the config table
+    pub fn from_rows(rows: impl IntoIterator<Item = (String, Option<String>)>) -> Self {
+        let mut config = Self::default();
+        for (name, value) in rows {
+            match name.as_str() {
+                "key" => config.key_ref = value,
+                KEY_GENERATION_CONFIG => config.key_generation = value,
+                PENDING_KEY_CONFIG => config.pending = value,
+                PENDING_KEY_CHECK_CONFIG => config.pending_check = value,
+                _ => (),
+            }
+        }
+        config
+    }
+}
+
+/// A replacement store key, derived from the pass key of the store using a
+/// stronger key derivation
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+#[derive(Debug)]
+pub(crate) struct KdfUpgrade {
+    pub store_key: Arc<StoreKey>,
+    pub store_key_ref: String,
+}
+
+/// Prepare the upgrade of the key derivation of the store key
+///
+/// The upgrade is refused while a chunked re-key is pending, or when the store
+/// key was replaced by another instance. The pass key must resolve the current
+/// store key. Returns `None` when the current key derivation is not weaker
+/// than `target`.
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) async fn prepare_kdf_upgrade(
+    key_cache: &KeyCache,
+    config: StoreKeyConfig,
+    target: KdfMethod,
+    pass_key: PassKey<'static>,
+) -> Result<Option<KdfUpgrade>, Error> {
+    if config.pending.is_some() {
+        return Err(err_msg!(
+            Busy,
+            "Cannot upgrade the store key while a chunked re-key is pending"
+        ));
+    }
+    check_key_generation(key_cache.key_generation, config.key_generation.as_deref())?;
+    let key_ref = config
+        .key_ref
+        .ok_or_else(|| err_msg!(Unsupported, "Store key not found"))?;
+    let wrap_ref = StoreKeyReference::parse_uri(&key_ref)?;
+    let current_check = key_cache.store_key.key_check()?;
+    let upgrade = unblock(move || {
+        if wrap_ref.resolve(pass_key.as_ref())?.key_check()? != current_check {
+            return Err(err_msg!(
+                Encryption,
+                "Pass key does not match the store key"
+            ));
+        }
+        wrap_ref.upgrade_kdf(target, pass_key)
+    })
+    .await?;
+    Ok(upgrade.map(|(store_key, store_key_ref)| KdfUpgrade {
+        store_key: Arc::new(store_key),
+        store_key_ref: store_key_ref.into_uri(),
+    }))
+}
+
+/// The replacement store key of a chunked re-key
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+#[derive(Debug)]
+pub(crate) struct ChunkedRekey {
+    pub store_key: Arc<StoreKey>,
+    pub store_key_ref: String,
+    /// The check value of a new store key, to be recorded with its reference
+    /// before any profile key is re-encrypted
+    pub new_key_check: Option<String>,
+}
+
+/// Prepare a chunked re-key of the store, resuming any incomplete re-key
+///
+/// When a re-key is pending, the key method must match the pending store key
+/// reference and the pass key must resolve a key matching the recorded check
+/// value. Otherwise a new store key is resolved from the pass key.
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) async fn prepare_chunked_rekey(
+    config: StoreKeyConfig,
+    method: StoreKeyMethod,
+    pass_key: PassKey<'static>,
+) -> Result<ChunkedRekey, Error> {
+    if let Some(pending) = config.pending {
+        let key_ref = StoreKeyReference::parse_uri(&pending)?;
+        if !key_ref.compare_method(&method) {
+            return Err(err_msg!(
+                Input,
+                "Store key method mismatch for pending re-key"
+            ));
+        }
+        let expected = config
+            .pending_check
+            .ok_or_else(|| err_msg!(Unsupported, "Pending re-key has no key check value"))?;
+        let store_key = unblock(move || {
+            let store_key = key_ref.resolve(pass_key)?;
+            if store_key.key_check()?.unwrap_or_default() != expected {
+                return Err(err_msg!(
+                    Encryption,
+                    "Pass key does not match the pending re-key"
+                ));
+            }
+            Ok(store_key)
+        })
+        .await?;
+        Ok(ChunkedRekey {
+            store_key: Arc::new(store_key),
+            store_key_ref: pending,
+            new_key_check: None,
+        })
+    } else {
+        let (store_key, store_key_ref, key_check) = unblock(move || {
+            let (store_key, store_key_ref) = method.resolve(pass_key)?;
+            let key_check = store_key.key_check()?.unwrap_or_default();
+            Result::<_, Error>::Ok((store_key, store_key_ref, key_check))
+        })
+        .await?;
+        Ok(ChunkedRekey {
+            store_key: Arc::new(store_key),
+            store_key_ref: store_key_ref.into_uri(),
+            new_key_check: Some(key_check),
+        })
+    }
+}
+
 pub fn encode_profile_key(
     profile_key: &ProfileKey,
     store_key: &StoreKey,
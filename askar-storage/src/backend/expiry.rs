@@ -0,0 +1,179 @@
+#[cfg(any(test, feature = "postgres", feature = "sqlite"))]
+use serde::{Deserialize, Serialize};
+
+use crate::entry::EntryKind;
+#[cfg(any(test, feature = "postgres", feature = "sqlite"))]
+use crate::error::Error;
+
+/// A default expiry applied to entries of a profile written without an expiry
+///
+/// When an entry is inserted or replaced without an explicit expiry, the first
+/// matching rule determines its lifetime. Entries which match no rule do not
+/// expire. The policy is not applied to imported entries, which retain their
+/// original expiry. Category names used in rules are stored unencrypted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileExpiryPolicy {
+    /// The rules of the policy, in order of precedence
+    pub rules: Vec<ExpiryRule>,
+}
+
+impl ProfileExpiryPolicy {
+    /// Check whether the policy has no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Get the default lifetime in milliseconds for an entry
+    pub fn default_expiry_ms(&self, kind: EntryKind, category: &str) -> Option<i64> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(kind, category))
+            .map(|rule| rule.expiry_ms)
+    }
+}
+
+#[cfg(any(test, feature = "postgres", feature = "sqlite"))]
+impl ProfileExpiryPolicy {
+    /// Ensure that each rule has a positive lifetime
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.rules.iter().any(|rule| rule.expiry_ms <= 0) {
+            Err(err_msg!(Input, "Expiry policy lifetimes must be positive"))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Result<Option<String>, Error> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let rules = self
+            .rules
+            .iter()
+            .map(|rule| StoredRule {
+                kind: rule.kind.map(|kind| kind as u8),
+                categories: rule.categories.clone(),
+                expiry_ms: rule.expiry_ms,
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&rules)
+            .map(Some)
+            .map_err(err_map!(Unexpected, "Error encoding expiry policy"))
+    }
+
+    pub(crate) fn from_json(policy: Option<&str>) -> Result<Self, Error> {
+        let rules = if let Some(policy) = policy {
+            serde_json::from_str::<Vec<StoredRule>>(policy)
+                .map_err(err_map!(Unexpected, "Error decoding expiry policy"))?
+                .into_iter()
+                .map(|rule| {
+                    Ok(ExpiryRule {
+                        kind: rule
+                            .kind
+                            .map(|kind| EntryKind::try_from(kind as usize))
+                            .transpose()?,
+                        categories: rule.categories,
+                        expiry_ms: rule.expiry_ms,
+                    })
+                })
+                .collect::<Result<_, Error>>()?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { rules })
+    }
+}
+
+/// A rule of a profile expiry policy
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpiryRule {
+    /// Restrict the rule to entries of this kind
+    pub kind: Option<EntryKind>,
+    /// Restrict the rule to entries within these categories, or all categories
+    /// when empty
+    pub categories: Vec<String>,
+    /// The lifetime of matching entries in milliseconds
+    pub expiry_ms: i64,
+}
+
+impl ExpiryRule {
+    /// Create a rule applying to all entries
+    pub fn new(expiry_ms: i64) -> Self {
+        Self {
+            kind: None,
+            categories: Vec::new(),
+            expiry_ms,
+        }
+    }
+
+    /// Restrict the rule to entries of a given kind
+    pub fn with_kind(mut self, kind: EntryKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Restrict the rule to entries within a given category
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    fn matches(&self, kind: EntryKind, category: &str) -> bool {
+        self.kind.map(|k| k == kind).unwrap_or(true)
+            && (self.categories.is_empty() || self.categories.iter().any(|c| c == category))
+    }
+}
+
+#[cfg(any(test, feature = "postgres", feature = "sqlite"))]
+#[derive(Serialize, Deserialize)]
+struct StoredRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<u8>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    categories: Vec<String>,
+    expiry_ms: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn expiry_policy_rules() {
+        let policy = ProfileExpiryPolicy {
+            rules: vec![
+                ExpiryRule::new(1000)
+                    .with_kind(EntryKind::Item)
+                    .with_category("message"),
+                ExpiryRule::new(5000).with_category("session"),
+            ],
+        };
+        policy.validate().unwrap();
+        assert_eq!(
+            policy.default_expiry_ms(EntryKind::Item, "message"),
+            Some(1000)
+        );
+        assert_eq!(policy.default_expiry_ms(EntryKind::Kms, "message"), None);
+        assert_eq!(
+            policy.default_expiry_ms(EntryKind::Kms, "session"),
+            Some(5000)
+        );
+        assert_eq!(policy.default_expiry_ms(EntryKind::Item, "other"), None);
+
+        let json = policy.to_json().unwrap();
+        assert_eq!(
+            ProfileExpiryPolicy::from_json(json.as_deref()).unwrap(),
+            policy
+        );
+        assert_eq!(ProfileExpiryPolicy::default().to_json().unwrap(), None);
+        assert!(ProfileExpiryPolicy::from_json(None).unwrap().is_empty());
+
+        let err = ProfileExpiryPolicy {
+            rules: vec![ExpiryRule::new(0)],
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Input);
+    }
+}
@@ -0,0 +1,105 @@
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// A selection of store profiles by name or metadata
+///
+/// A profile matches when its name starts with the name prefix, if any, and its
+/// metadata is a JSON object having each of the given properties as a string
+/// value. A filter must have at least one criterion.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileFilter {
+    /// Restrict the selection to profile names starting with this prefix
+    pub name_prefix: Option<String>,
+    /// Restrict the selection to profiles with these metadata properties
+    pub metadata: Vec<(String, String)>,
+}
+
+impl ProfileFilter {
+    /// Restrict the filter to profile names starting with a given prefix
+    pub fn with_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Restrict the filter to profiles having a metadata property with a given value
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    /// Check whether the filter has no criteria
+    pub fn is_empty(&self) -> bool {
+        self.name_prefix.is_none() && self.metadata.is_empty()
+    }
+
+    /// Check whether the metadata of a profile is required for matching
+    pub fn has_metadata(&self) -> bool {
+        !self.metadata.is_empty()
+    }
+
+    /// Check whether a profile is selected by the filter
+    pub fn matches(&self, name: &str, metadata: Option<&str>) -> bool {
+        self.matches_name(name) && self.matches_metadata(metadata)
+    }
+
+    /// Check whether a profile name is selected by the filter, ignoring metadata
+    pub(crate) fn matches_name(&self, name: &str) -> bool {
+        self.name_prefix
+            .as_deref()
+            .map(|prefix| name.starts_with(prefix))
+            .unwrap_or(true)
+    }
+
+    /// Check whether profile metadata is selected by the filter, ignoring the name
+    pub(crate) fn matches_metadata(&self, metadata: Option<&str>) -> bool {
+        if self.metadata.is_empty() {
+            return true;
+        }
+        let props = match metadata.and_then(|m| serde_json::from_str::<Value>(m).ok()) {
+            Some(Value::Object(props)) => props,
+            _ => return false,
+        };
+        self.metadata
+            .iter()
+            .all(|(key, value)| props.get(key).and_then(Value::as_str) == Some(value.as_str()))
+    }
+
+    /// Ensure that the filter cannot select every profile
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.is_empty() {
+            Err(err_msg!(
+                Input,
+                "Profile filter must have a name prefix or metadata criteria"
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn profile_filter_matches() {
+        let filter = ProfileFilter::default()
+            .with_name_prefix("tenant-")
+            .with_metadata("group", "trial");
+        assert!(filter.matches("tenant-a", Some(r#"{"group": "trial", "label": "A"}"#)));
+        assert!(!filter.matches("tenant-a", Some(r#"{"group": "paid"}"#)));
+        assert!(!filter.matches("tenant-a", Some(r#"{"group": 1}"#)));
+        assert!(!filter.matches("tenant-a", Some("trial")));
+        assert!(!filter.matches("tenant-a", None));
+        assert!(!filter.matches("other", Some(r#"{"group": "trial"}"#)));
+
+        let filter = ProfileFilter::default().with_name_prefix("tenant-");
+        assert!(filter.matches("tenant-b", None));
+        assert!(!filter.has_metadata());
+
+        let err = ProfileFilter::default().validate().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Input);
+    }
+}
@@ -0,0 +1,224 @@
+use super::{Backend, BackendSession, Progress, ProgressFn, ProgressPhase};
+use crate::{
+    entry::{Entry, EntryKind, EntryOperation},
+    error::{Error, ErrorKind},
+};
+
+const RENAME_MAX_ATTEMPTS: usize = 1000;
+
+/// The handling of imported records which already exist in the target profile
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ImportStrategy {
+    /// Abort the import, leaving the target profile unchanged
+    #[default]
+    Fail,
+    /// Keep the existing record and discard the imported record
+    Skip,
+    /// Replace the existing record with the imported record
+    Overwrite,
+    /// Insert the imported record under a new name, formed by adding a numeric
+    /// suffix to the original name
+    Rename,
+}
+
+/// The outcome of a conflicting record in an import
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// The imported record was discarded
+    Skipped,
+    /// The existing record was replaced
+    Overwritten,
+    /// The imported record was inserted under a new name
+    Renamed(String),
+    /// The record would abort the import, as reported by a dry run
+    Failed,
+}
+
+/// A record of an import which already existed in the target profile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportConflict {
+    /// The record kind
+    pub kind: EntryKind,
+    /// The record category
+    pub category: String,
+    /// The original name of the record
+    pub name: String,
+    /// The handling of the conflict
+    pub resolution: ConflictResolution,
+}
+
+/// The result of a profile import
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// The number of records written to the target profile, including
+    /// overwritten and renamed records
+    pub imported: usize,
+    /// The records which already existed in the target profile
+    pub conflicts: Vec<ImportConflict>,
+    /// The names of imported key entries which cannot be loaded by this library
+    /// build, such as those using unsupported key algorithms. This is only
+    /// populated by callers able to interpret key entries
+    pub unsupported_keys: Vec<String>,
+    /// Whether the import was performed as a dry run, without writing to the
+    /// target store
+    pub dry_run: bool,
+}
+
+/// Insert all records from a given profile, resolving conflicts with existing records
+///
+/// Unlike [`copy_profile`](super::copy_profile), the target profile need not be
+/// empty. Records (including keys) already present in the target are handled
+/// according to `strategy`, and reported in the returned [`ImportReport`]. The
+/// records are written in a single transaction, so that nothing is imported when
+/// the `Fail` strategy encounters a conflict. Record expiry times are not copied.
+///
+/// When `dry_run` is set, the records are written and the transaction is rolled
+/// back, so that the report reflects the changes which would be made. Conflicts
+/// under the `Fail` strategy are then reported rather than aborting the import,
+/// and a missing target profile is not created. When provided, `progress` is
+/// invoked after each page of source records is processed.
+pub async fn import_profile<A: Backend, B: Backend>(
+    from_backend: &A,
+    to_backend: &B,
+    from_profile: &str,
+    to_profile: &str,
+    strategy: ImportStrategy,
+    dry_run: bool,
+    progress: Option<&ProgressFn>,
+) -> Result<ImportReport, Error> {
+    let mut total = None;
+    if progress.is_some() {
+        let mut session = from_backend.session(Some(from_profile.into()), false)?;
+        total = Some(session.count(None, None, None).await?);
+        session.close(false).await?;
+    }
+    let mut processed = 0;
+    let mut scan = from_backend
+        .scan(
+            Some(from_profile.into()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+        .await?;
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+    if dry_run {
+        let profiles = to_backend.list_profiles().await?;
+        if !profiles.iter().any(|name| name == to_profile) {
+            // all records would be inserted into the new profile
+            while let Some(rows) = scan.fetch_next().await? {
+                report.imported += rows.len();
+                processed += rows.len() as i64;
+                Progress {
+                    phase: ProgressPhase::Import,
+                    profile: Some(to_profile.to_string()),
+                    processed,
+                    total,
+                }
+                .report(progress);
+            }
+            return Ok(report);
+        }
+    } else if let Err(e) = to_backend.create_profile(Some(to_profile.into())).await {
+        if e.kind() != ErrorKind::Duplicate {
+            return Err(e);
+        }
+    }
+    let mut txn = to_backend.session(Some(to_profile.into()), true)?;
+    while let Some(rows) = scan.fetch_next().await? {
+        processed += rows.len() as i64;
+        for entry in rows {
+            if let Some(resolution) = import_entry(&mut txn, &entry, strategy, dry_run).await? {
+                if !matches!(
+                    resolution,
+                    ConflictResolution::Skipped | ConflictResolution::Failed
+                ) {
+                    report.imported += 1;
+                }
+                report.conflicts.push(ImportConflict {
+                    kind: entry.kind,
+                    category: entry.category,
+                    name: entry.name,
+                    resolution,
+                });
+            } else {
+                report.imported += 1;
+            }
+        }
+        Progress {
+            phase: ProgressPhase::Import,
+            profile: Some(to_profile.to_string()),
+            processed,
+            total,
+        }
+        .report(progress);
+    }
+    txn.close(!dry_run).await?;
+    Ok(report)
+}
+
+async fn import_entry<S: BackendSession>(
+    txn: &mut S,
+    entry: &Entry,
+    strategy: ImportStrategy,
+    dry_run: bool,
+) -> Result<Option<ConflictResolution>, Error> {
+    match write_entry(txn, entry, &entry.name, EntryOperation::Insert).await {
+        Err(err) if err.kind() == ErrorKind::Duplicate => (),
+        res => return res.map(|_| None),
+    }
+    match strategy {
+        ImportStrategy::Fail if dry_run => Ok(Some(ConflictResolution::Failed)),
+        ImportStrategy::Fail => Err(err_msg!(
+            Duplicate,
+            "Imported record already exists: {}/{}",
+            entry.category,
+            entry.name
+        )),
+        ImportStrategy::Skip => Ok(Some(ConflictResolution::Skipped)),
+        ImportStrategy::Overwrite => {
+            write_entry(txn, entry, &entry.name, EntryOperation::Replace).await?;
+            Ok(Some(ConflictResolution::Overwritten))
+        }
+        ImportStrategy::Rename => {
+            for idx in 1..=RENAME_MAX_ATTEMPTS {
+                let name = format!("{}-{}", entry.name, idx);
+                match write_entry(txn, entry, &name, EntryOperation::Insert).await {
+                    Err(err) if err.kind() == ErrorKind::Duplicate => (),
+                    res => return res.map(|_| Some(ConflictResolution::Renamed(name))),
+                }
+            }
+            Err(err_msg!(
+                Duplicate,
+                "No available name for imported record: {}/{}",
+                entry.category,
+                entry.name
+            ))
+        }
+    }
+}
+
+async fn write_entry<S: BackendSession>(
+    txn: &mut S,
+    entry: &Entry,
+    name: &str,
+    operation: EntryOperation,
+) -> Result<(), Error> {
+    txn.update(
+        entry.kind,
+        operation,
+        &entry.category,
+        name,
+        Some(entry.value.as_ref()),
+        Some(entry.tags.as_ref()),
+        None,
+    )
+    .await
+}
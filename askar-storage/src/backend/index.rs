@@ -0,0 +1,68 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::error::Error;
+
+/// The secondary indexes created when provisioning a store
+///
+/// Additional indexes speed up scans and expiry checks at the cost of extra
+/// work for each write, while fewer indexes favour bulk inserts over queries.
+/// The profile is selected using the `index_profile` query parameter of the
+/// store URI, and only applies to newly provisioned stores.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IndexProfile {
+    /// The unique item index and indexes on tag names and values
+    #[default]
+    Default,
+    /// The default indexes, plus indexes supporting category scans ordered by
+    /// item ID and the identification of expired items
+    ScanHeavy,
+    /// Only the indexes required to enforce uniqueness and cascading deletes.
+    /// Tag filters are evaluated without an index on tag names
+    WriteHeavy,
+}
+
+impl IndexProfile {
+    /// Get the identifier of the index profile
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::ScanHeavy => "scan-heavy",
+            Self::WriteHeavy => "write-heavy",
+        }
+    }
+
+    /// Extract the index profile from the `index_profile` store URI query parameter
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub(crate) fn from_query(
+        query: &mut std::collections::HashMap<String, String>,
+    ) -> Result<Self, Error> {
+        if let Some(profile) = query.remove("index_profile") {
+            Self::from_str(&profile)
+                .map_err(err_map!(Input, "Error parsing 'index_profile' parameter"))
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+impl FromStr for IndexProfile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Self::Default),
+            "scan-heavy" => Ok(Self::ScanHeavy),
+            "write-heavy" => Ok(Self::WriteHeavy),
+            _ => Err(err_msg!(Unsupported, "Unknown index profile")),
+        }
+    }
+}
+
+impl Display for IndexProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,17 +1,69 @@
 //! Storage backends supported by aries-askar
 
-use std::fmt::Debug;
+use std::{fmt::Debug, future::Future};
 
 use crate::{
     entry::{Entry, EntryKind, EntryOperation, EntryTag, Scan, TagFilter},
     error::{Error, ErrorKind},
     future::BoxFuture,
-    protect::{PassKey, StoreKeyMethod},
+    protect::{kdf::KdfMethod, KeyCacheStats, PassKey, StoreKeyMethod},
 };
 
 #[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
 pub(crate) mod db_utils;
 
+mod backup;
+pub use self::backup::{
+    backup_changes, export_records, restore_changes, BackupSegmentInfo, EntryChange,
+};
+
+mod expiry;
+pub use self::expiry::{ExpiryRule, ProfileExpiryPolicy};
+
+mod filter;
+pub use self::filter::ProfileFilter;
+
+mod import;
+pub use self::import::{
+    import_profile, ConflictResolution, ImportConflict, ImportReport, ImportStrategy,
+};
+
+mod index;
+pub use self::index::IndexProfile;
+
+mod pool;
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+pub(crate) use self::pool::PoolMetrics;
+pub use self::pool::PoolStatus;
+
+mod progress;
+pub use self::progress::{Progress, ProgressFn, ProgressPhase};
+
+mod quota;
+pub use self::quota::ProfileQuota;
+
+mod replicate;
+pub use self::replicate::{replicate, ReplicationCheckpoint};
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) mod schema;
+
+mod tenant;
+pub use self::tenant::TenantIsolation;
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+pub(crate) use self::tenant::TenantSchemas;
+#[cfg(any(feature = "postgres", feature = "odbc"))]
+pub(crate) use self::tenant::TENANT_SCHEMA_CONFIG;
+
+mod stats;
+pub use self::stats::ProfileStats;
+
+mod trace;
+pub use self::trace::TraceContext;
+
+mod version;
+pub use self::version::VersionInfo;
+
 #[cfg(feature = "postgres")]
 #[cfg_attr(docsrs, doc(cfg(feature = "postgres")))]
 /// Postgres database support
@@ -47,6 +99,22 @@ pub trait Backend: Debug + Send + Sync {
     /// Create a new profile
     fn create_profile(&self, name: Option<String>) -> BoxFuture<'_, Result<String, Error>>;
 
+    /// Create a profile with the given name unless it already exists, returning
+    /// `true` if the profile was created
+    ///
+    /// Concurrent calls for the same name, including from other processes sharing
+    /// the store, are resolved by the unique constraint on profile names: only one
+    /// caller creates the profile, and the others find the existing profile.
+    fn ensure_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async move {
+            match self.create_profile(Some(name)).await {
+                Ok(_) => Ok(true),
+                Err(err) if err.kind() == ErrorKind::Duplicate => Ok(false),
+                Err(err) => Err(err),
+            }
+        })
+    }
+
     /// Get the name of the active profile
     fn get_active_profile(&self) -> String;
 
@@ -59,9 +127,229 @@ pub trait Backend: Debug + Send + Sync {
     /// Get the details of all store profiles
     fn list_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>>;
 
+    /// Get a page of the store profile names, in ascending order of name
+    fn list_profiles_page(
+        &self,
+        offset: i64,
+        limit: i64,
+    ) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let mut names = self.list_profiles().await?;
+            names.sort();
+            let offset = usize::try_from(offset.max(0)).unwrap_or(usize::MAX);
+            let limit = usize::try_from(limit.max(0)).unwrap_or(usize::MAX);
+            Ok(names.into_iter().skip(offset).take(limit).collect())
+        })
+    }
+
     /// Remove an existing profile
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>>;
 
+    /// Remove the profiles selected by a filter along with their records,
+    /// returning the names of the removed profiles
+    ///
+    /// Profiles are removed in batches of up to `batch_size`, with `progress`
+    /// reporting the number of profiles removed after each batch. A failure leaves
+    /// the batches already processed removed. The active profile of the store is
+    /// never removed. This implementation removes each profile separately, while
+    /// database backends remove each batch in a single transaction.
+    fn remove_profiles<'a>(
+        &'a self,
+        filter: ProfileFilter,
+        batch_size: usize,
+        progress: Option<&'a ProgressFn>,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            filter.validate()?;
+            let batch_size = batch_size.max(1);
+            let active = self.get_active_profile();
+            let mut selected = Vec::new();
+            for name in self.list_profiles().await? {
+                if name == active || !filter.matches_name(&name) {
+                    continue;
+                }
+                let metadata = if filter.has_metadata() {
+                    self.get_profile_metadata(name.clone()).await?
+                } else {
+                    None
+                };
+                if filter.matches_metadata(metadata.as_deref()) {
+                    selected.push(name);
+                }
+            }
+            let total = selected.len() as i64;
+            let mut removed = Vec::with_capacity(selected.len());
+            for batch in selected.chunks(batch_size) {
+                for name in batch {
+                    if self.remove_profile(name.clone()).await? {
+                        removed.push(name.clone());
+                    }
+                }
+                Progress {
+                    phase: ProgressPhase::Remove,
+                    profile: None,
+                    processed: removed.len() as i64,
+                    total: Some(total),
+                }
+                .report(progress);
+            }
+            Ok(removed)
+        })
+    }
+
+    /// Rename an existing profile, returning `false` if it was not found
+    ///
+    /// The default profile setting is updated when it refers to the renamed
+    /// profile. The active profile of the store may not be renamed.
+    fn rename_profile(&self, from: String, to: String) -> BoxFuture<'_, Result<bool, Error>> {
+        let _ = (from, to);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile renaming is not supported by this backend"
+        ))))
+    }
+
+    /// Get the metadata associated with a profile
+    ///
+    /// Profile metadata is stored unencrypted, and should not contain sensitive values.
+    fn get_profile_metadata(&self, name: String) -> BoxFuture<'_, Result<Option<String>, Error>> {
+        let _ = name;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile metadata is not supported by this backend"
+        ))))
+    }
+
+    /// Replace or clear the metadata associated with a profile
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<String>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        let _ = (name, metadata);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile metadata is not supported by this backend"
+        ))))
+    }
+
+    /// Get the limits on the records stored within a profile
+    fn get_profile_quota(&self, name: String) -> BoxFuture<'_, Result<ProfileQuota, Error>> {
+        let _ = name;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile quotas are not supported by this backend"
+        ))))
+    }
+
+    /// Replace the limits on the records stored within a profile
+    ///
+    /// The limits are enforced for subsequent writes to the profile.
+    fn set_profile_quota(
+        &self,
+        name: String,
+        quota: ProfileQuota,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        let _ = (name, quota);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile quotas are not supported by this backend"
+        ))))
+    }
+
+    /// Get the default expiry policy of a profile
+    fn get_profile_expiry_policy(
+        &self,
+        name: String,
+    ) -> BoxFuture<'_, Result<ProfileExpiryPolicy, Error>> {
+        let _ = name;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile expiry policies are not supported by this backend"
+        ))))
+    }
+
+    /// Replace the default expiry policy of a profile
+    ///
+    /// The policy is applied to entries subsequently written without an expiry.
+    /// An empty policy removes any existing policy.
+    fn set_profile_expiry_policy(
+        &self,
+        name: String,
+        policy: ProfileExpiryPolicy,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        let _ = (name, policy);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile expiry policies are not supported by this backend"
+        ))))
+    }
+
+    /// Mark a profile as archived, or restore an archived profile
+    ///
+    /// The records of an archived profile may still be read, but any attempt to
+    /// insert, replace or remove its entries is rejected. The profile itself may
+    /// still be renamed, re-keyed or removed.
+    fn set_profile_archived(
+        &self,
+        name: String,
+        archived: bool,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        let _ = (name, archived);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile archival is not supported by this backend"
+        ))))
+    }
+
+    /// List the names of the archived profiles, in ascending order of name
+    ///
+    /// This may be used to exclude archived profiles when scanning the records
+    /// of each profile in the store.
+    fn list_archived_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        // profiles cannot be archived unless supported by the backend
+        Box::pin(std::future::ready(Ok(Vec::new())))
+    }
+
+    /// Register an alternate name for a profile, which may be used to open sessions
+    ///
+    /// An alias must not match the name of an existing profile or another alias.
+    /// The aliases of a profile are removed along with the profile. If a profile
+    /// is later created or renamed with the same name as an alias, the profile
+    /// name takes precedence.
+    fn add_profile_alias(&self, name: String, alias: String) -> BoxFuture<'_, Result<(), Error>> {
+        let _ = (name, alias);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile aliases are not supported by this backend"
+        ))))
+    }
+
+    /// Remove a profile alias, returning `false` if it was not found
+    fn remove_profile_alias(&self, alias: String) -> BoxFuture<'_, Result<bool, Error>> {
+        let _ = alias;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile aliases are not supported by this backend"
+        ))))
+    }
+
+    /// List the aliases of a profile, in ascending order
+    fn list_profile_aliases(&self, name: String) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        let _ = name;
+        // aliases cannot be registered unless supported by the backend
+        Box::pin(std::future::ready(Ok(Vec::new())))
+    }
+
+    /// Get the usage statistics for the records stored within a profile
+    fn profile_stats(&self, name: String) -> BoxFuture<'_, Result<ProfileStats, Error>> {
+        let _ = name;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile statistics are not supported by this backend"
+        ))))
+    }
+
     /// Create a [`Scan`] against the store
     fn scan(
         &self,
@@ -75,6 +363,63 @@ pub trait Backend: Debug + Send + Sync {
         descending: bool,
     ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>>;
 
+    /// Create a [`Scan`] which resumes after a row identifier, for keyset pagination
+    ///
+    /// Records are returned in order of their row identifiers, each paired with its
+    /// identifier. Passing the identifier of the last record received as `after`
+    /// continues the scan from the following record, such that records inserted or
+    /// removed in the meantime do not cause other records to be skipped or repeated.
+    /// The `offset` and `limit` apply to the records following `after`.
+    #[allow(clippy::too_many_arguments)]
+    fn scan_after(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        after: Option<i64>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, (i64, Entry)>, Error>> {
+        let _ = (
+            profile, kind, category, tag_filter, after, offset, limit, descending,
+        );
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Keyset scans are not supported by this backend"
+        ))))
+    }
+
+    /// Create a [`Scan`] against the records whose category lies within a namespace
+    ///
+    /// Categories are divided into segments by `/`, and a namespace such as
+    /// `credential/w3c` selects the category `credential/w3c` along with any
+    /// category beginning with `credential/w3c/`. The index of records written
+    /// before it was added to the store is computed when the store is next opened
+    /// for writing, and namespace queries against their profile fail until then.
+    #[allow(clippy::too_many_arguments)]
+    fn scan_namespace(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        namespace: String,
+        tag_filter: Option<TagFilter>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
+        let _ = (
+            profile, kind, namespace, tag_filter, offset, limit, order_by,
+        );
+        let _ = descending;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Category namespaces are not supported by this backend"
+        ))))
+    }
+
     /// Create a new session against the store
     fn session(&self, profile: Option<String>, transaction: bool) -> Result<Self::Session, Error>;
 
@@ -85,10 +430,183 @@ pub trait Backend: Debug + Send + Sync {
         key: PassKey<'_>,
     ) -> BoxFuture<'_, Result<(), Error>>;
 
+    /// Replace the wrapping key of the store, re-encrypting profile keys in batches
+    ///
+    /// Each batch of up to `chunk_size` profile keys is encrypted with the new store
+    /// key and committed in its own transaction, alongside the current profile keys,
+    /// so that the store remains usable throughout. The new keys replace the current
+    /// ones in a final transaction. An interrupted operation is resumed by calling
+    /// this method again with the same key method and pass key. `progress` is
+    /// invoked after each batch is committed, and may return an error in order
+    /// to stop the operation.
+    fn rekey_chunked<'a>(
+        &'a mut self,
+        method: StoreKeyMethod,
+        key: PassKey<'a>,
+        chunk_size: usize,
+        progress: &'a mut (dyn FnMut(RekeyProgress) -> Result<(), Error> + Send),
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        let _ = (method, key, chunk_size, progress);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Chunked re-keying is not supported by this backend"
+        ))))
+    }
+
+    /// Derive the store key again from the same pass key when its key derivation
+    /// is weaker than `method`, returning `false` if the store key is unchanged
+    ///
+    /// The pass key must resolve the current store key. The profile keys are
+    /// re-wrapped under the new store key in a single transaction, and the store
+    /// key generation is advanced. The upgrade is refused while a chunked re-key
+    /// is pending. Other instances of the store continue to hold the previous
+    /// store key: they must be reopened, and fail with a `Busy` error when
+    /// creating or re-keying profiles or updating the store configuration.
+    fn upgrade_kdf(
+        &mut self,
+        method: KdfMethod,
+        key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>> {
+        let _ = (method, key);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Key derivation upgrades are not supported by this backend"
+        ))))
+    }
+
+    /// Replace the key of a single profile, re-encrypting all of its records
+    ///
+    /// The entry values of profiles created by earlier versions are bound to the
+    /// profile, kind, category and name of their records under the new key.
+    /// Sessions opened against the profile before the operation completes may
+    /// continue to use the previous key, and should be closed beforehand.
+    fn rekey_profile(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        let _ = name;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Profile re-keying is not supported by this backend"
+        ))))
+    }
+
+    /// Replace the tag HMAC key of a single profile, re-indexing all of its tags
+    ///
+    /// Unlike `rekey_profile`, only the stored tags are re-encrypted. Sessions
+    /// opened against the profile before the operation completes may continue to
+    /// use the previous key, and should be closed beforehand.
+    fn rotate_tag_key(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        let _ = name;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Tag key rotation is not supported by this backend"
+        ))))
+    }
+
+    /// Write a consistent copy of the store to a new file, while the store remains in use
+    fn snapshot_to(&self, path: String) -> BoxFuture<'_, Result<(), Error>> {
+        let _ = path;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Snapshots are not supported by this backend"
+        ))))
+    }
+
+    /// Get the schema version, store key method and ciphers of the store
+    fn version_info(&self) -> BoxFuture<'_, Result<VersionInfo, Error>> {
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Version information is not supported by this backend"
+        ))))
+    }
+
+    /// Get the latest change sequence number of the store, for incremental backups
+    ///
+    /// Pending writes are awaited, so that all changes up to the returned sequence
+    /// number are reported by subsequent calls to `scan_changes`.
+    fn change_sequence(&self) -> BoxFuture<'_, Result<i64, Error>> {
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Change tracking is not supported by this backend"
+        ))))
+    }
+
+    /// Fetch the records changed after the sequence number `since`, up to and
+    /// including `until`, in ascending order of sequence number
+    ///
+    /// Only the latest change to each record is retained, and is reported with
+    /// the current state of the record.
+    fn scan_changes(
+        &self,
+        since: i64,
+        until: i64,
+        limit: i64,
+    ) -> BoxFuture<'_, Result<Vec<EntryChange>, Error>> {
+        let _ = (since, until, limit);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Change tracking is not supported by this backend"
+        ))))
+    }
+
+    /// Discard the change records of removed records, up to and including the
+    /// sequence number `until`
+    ///
+    /// This should only be called once a backup segment ending at `until` has
+    /// been stored, as the removals are no longer reported to segments starting
+    /// from an earlier checkpoint. Returns the number of discarded records.
+    fn prune_changes(&self, until: i64) -> BoxFuture<'_, Result<i64, Error>> {
+        let _ = until;
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Change tracking is not supported by this backend"
+        ))))
+    }
+
+    /// Get usage statistics for the profile key cache, if supported by the backend
+    fn key_cache_stats(&self) -> BoxFuture<'_, Option<KeyCacheStats>> {
+        Box::pin(std::future::ready(None))
+    }
+
+    /// Discard the cached key of a profile, so that it is reloaded on next use
+    ///
+    /// This allows the removal or re-keying of a profile by another process
+    /// sharing the store to be observed. Backends without a key cache do nothing.
+    fn invalidate_profile_key(&self, name: String) -> BoxFuture<'_, ()> {
+        let _ = name;
+        Box::pin(std::future::ready(()))
+    }
+
+    /// Get the state of the connection pool, if supported by the backend
+    fn pool_status(&self) -> Option<PoolStatus> {
+        None
+    }
+
+    /// Check out a connection from the pool and return it, verifying that the
+    /// pool is able to supply connections
+    fn check_pool(&self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Connection pooling is not supported by this backend"
+        ))))
+    }
+
+    /// Get the kind of backend, such as `sqlite` or `postgres`
+    fn backend_kind(&self) -> &'static str {
+        "custom"
+    }
+
     /// Close the store instance
     fn close(&self) -> BoxFuture<'_, Result<(), Error>>;
 }
 
+/// The progress of a chunked store re-key
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RekeyProgress {
+    /// The number of profile keys encrypted with the new store key
+    pub completed: i64,
+    /// The total number of profiles in the store
+    pub total: i64,
+}
+
 /// Create, open, or remove a generic backend implementation
 pub trait ManageBackend<'a> {
     /// The type of backend being managed
@@ -125,6 +643,20 @@ pub trait BackendSession: Debug + Send {
         tag_filter: Option<TagFilter>,
     ) -> BoxFuture<'q, Result<i64, Error>>;
 
+    /// Count the number of matching records whose category lies within a namespace
+    fn count_namespace<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        namespace: &'q str,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        let _ = (kind, namespace, tag_filter);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Category namespaces are not supported by this backend"
+        ))))
+    }
+
     /// Fetch a single record from the store by category and name
     fn fetch<'q>(
         &'q mut self,
@@ -134,6 +666,24 @@ pub trait BackendSession: Debug + Send {
         for_update: bool,
     ) -> BoxFuture<'q, Result<Option<Entry>, Error>>;
 
+    /// Fetch a single record from the store by category and name, without
+    /// retrieving or decrypting its tags
+    fn fetch_without_tags<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        for_update: bool,
+    ) -> BoxFuture<'q, Result<Option<Entry>, Error>> {
+        Box::pin(async move {
+            let entry = self.fetch(kind, category, name, for_update).await?;
+            Ok(entry.map(|mut entry| {
+                entry.tags.clear();
+                entry
+            }))
+        })
+    }
+
     /// Fetch all matching records from the store
     fn fetch_all<'q>(
         &'q mut self,
@@ -191,6 +741,65 @@ pub trait BackendSession: Debug + Send {
         expiry_ms: Option<i64>,
     ) -> BoxFuture<'q, Result<(), Error>>;
 
+    /// Insert or replace a record, storing its value without encryption
+    ///
+    /// The category, name and tags of the record are protected as for other
+    /// records, while the value is stored in plaintext along with a tag binding
+    /// it to the record. This is intended for large values which are not
+    /// sensitive, such as cached public ledger data.
+    #[allow(clippy::too_many_arguments)]
+    fn update_unencrypted<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: &'q [u8],
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let _ = (kind, operation, category, name, value, tags, expiry_ms);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Unencrypted values are not supported by this backend"
+        ))))
+    }
+
+    /// Remove a record from the store, overwriting its stored value and tags
+    /// before deletion and reclaiming the freed space
+    ///
+    /// This is intended for records which must not be recoverable from the
+    /// database file once removed. Within a transaction, the freed space is not
+    /// reclaimed.
+    fn shred<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let _ = (kind, category, name);
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported,
+            "Shredding records is not supported by this backend"
+        ))))
+    }
+
+    /// Attach a trace context to the statements subsequently executed by the session
+    ///
+    /// Backends which do not support trace propagation ignore the context.
+    fn set_trace_context(&mut self, trace: Option<TraceContext>) {
+        let _ = trace;
+    }
+
+    /// Check whether the profile of the session was created on demand by an
+    /// insert or replace, clearing the indicator
+    ///
+    /// Profiles are only created on demand by stores opened with
+    /// `auto_create_profiles=true`.
+    fn take_profile_created(&mut self) -> bool {
+        false
+    }
+
     /// Test the connection to the store
     fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>>;
 
@@ -198,6 +807,35 @@ pub trait BackendSession: Debug + Send {
     fn close(&mut self, commit: bool) -> BoxFuture<'_, Result<(), Error>>;
 }
 
+/// Fetch and update records of a backend session without boxing their futures
+///
+/// The sessions of the built-in backends implement `BackendSession::fetch` and
+/// `BackendSession::update` by boxing these futures, while `AnyBackendSession`
+/// dispatches to them directly.
+pub trait EntrySession: BackendSession {
+    /// Fetch a single record from the store by category and name
+    fn fetch_entry<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        for_update: bool,
+    ) -> impl Future<Output = Result<Option<Entry>, Error>> + Send + 'q;
+
+    /// Insert, replace or remove a record in the store
+    #[allow(clippy::too_many_arguments)]
+    fn update_entry<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'q;
+}
+
 /// Insert all records from a given profile
 pub async fn copy_profile<A: Backend, B: Backend>(
     from_backend: &A,
@@ -232,6 +870,44 @@ pub async fn copy_profile<A: Backend, B: Backend>(
     Ok(())
 }
 
+/// Create a copy of a profile within the same store
+///
+/// All entries of the source profile, including keys, are copied into a new
+/// profile which is encrypted under a newly generated profile key. The target
+/// profile must not already exist, and is removed if the copy fails. Profile
+/// metadata, quotas and the archived status are not copied.
+pub async fn clone_profile<B: Backend>(
+    backend: &B,
+    from_profile: &str,
+    to_profile: &str,
+) -> Result<(), Error> {
+    let scan = backend
+        .scan(
+            Some(from_profile.into()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+        .await?;
+    let to_profile = backend.create_profile(Some(to_profile.into())).await?;
+    let result = async {
+        let mut txn = backend.session(Some(to_profile.clone()), true)?;
+        txn.import_scan(scan).await?;
+        txn.close(true).await
+    }
+    .await;
+    if result.is_err() {
+        if let Err(err) = backend.remove_profile(to_profile).await {
+            warn!("Error removing incomplete profile copy: {}", err);
+        }
+    }
+    result
+}
+
 /// Export an entire Store to another location
 pub async fn copy_store<'m, B: Backend, M: ManageBackend<'m>>(
     source: &B,
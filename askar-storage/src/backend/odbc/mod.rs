@@ -21,8 +21,10 @@
 ///      Security=;\
 ///   ?max_connections=21&min_connections=11&schema_file=/var/schemas/db2.sql"
 
+use std::borrow::Cow;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
+use std::time::Instant;
 use std::collections::BTreeMap;
 use async_stream::try_stream;
 use futures_lite::Stream;
@@ -34,6 +36,7 @@ use odbc_api::{
     parameter::{InputParameter, VarCharArray},
     Preallocated,
 };
+use zeroize::Zeroizing;
 
 use super::{
     db_utils::{
@@ -41,9 +44,11 @@ use super::{
         expiry_timestamp,
         PAGE_SIZE,
         prepare_tags,
-        random_profile_name
+        QueryPage,
+        random_profile_name,
+        SlowQueryLog
     },
-    Backend, BackendSession
+    Backend, BackendSession, EntrySession, PoolMetrics, PoolStatus, TenantSchemas, TraceContext
 };
 use crate::{
     backend::OrderBy,
@@ -61,6 +66,7 @@ use r2d2::PooledConnection;
 
 mod provision;
 pub use self::provision::OdbcStoreOptions;
+use self::provision::{drop_profile_schema, init_profile_schema};
 
 mod r2d2_connection_pool;
 use crate::odbc::r2d2_connection_pool::OdbcConnectionManager;
@@ -112,6 +118,10 @@ pub struct OdbcBackend {
     pool: r2d2::Pool<OdbcConnectionManager>,
     active_profile: String,
     key_cache: Arc<KeyCache>,
+    pool_metrics: PoolMetrics,
+    slow_query_log: SlowQueryLog,
+    tenant_schemas: Option<TenantSchemas>,
+    tenant_schema_file: Option<String>,
 }
 
 impl OdbcBackend {
@@ -119,15 +129,39 @@ impl OdbcBackend {
         pool: r2d2::Pool<OdbcConnectionManager>,
         active_profile: String,
         key_cache: KeyCache,
+        slow_query_log: SlowQueryLog,
     ) -> Self {
         Self {
             pool,
             active_profile,
             key_cache: Arc::new(key_cache),
+            pool_metrics: PoolMetrics::default(),
+            slow_query_log,
+            tenant_schemas: None,
+            tenant_schema_file: None,
         }
     }
 
-    fn create_stream(&self, entries: Vec<Entry>) -> impl Stream<Item = Result<Vec<Entry>, Error>> + 'static {
+    /// Keep the records of each profile in a separate schema.
+    pub(crate) fn with_tenant_schemas(
+        mut self,
+        tenant_schemas: Option<TenantSchemas>,
+        tenant_schema_file: Option<String>,
+    ) -> Self {
+        self.tenant_schemas = tenant_schemas;
+        self.tenant_schema_file = tenant_schema_file;
+        self
+    }
+
+    fn connection(&self) -> Result<PooledConnection<OdbcConnectionManager>, Error> {
+        let waited = self.pool.state().idle_connections == 0;
+        let start = Instant::now();
+        let conn = self.pool.get();
+        self.pool_metrics.record(start.elapsed(), waited, conn.is_ok());
+        Ok(conn?)
+    }
+
+    fn create_stream<T: Send + 'static>(&self, entries: Vec<T>) -> impl Stream<Item = Result<Vec<T>, Error>> + 'static {
         try_stream! {
             yield entries;
         }
@@ -142,6 +176,16 @@ impl Debug for OdbcBackend {
     }
 }
 
+/// Determine whether an ODBC error reports the violation of a unique constraint:
+/// DB2 reports SQLSTATE 23505, while other drivers may only report the class 23000.
+fn is_unique_violation(err: &odbc_api::Error) -> bool {
+    match err {
+        odbc_api::Error::Diagnostics { record, .. } =>
+            matches!(record.state.as_str(), "23000" | "23505"),
+        _ => false,
+    }
+}
+
 impl Backend for OdbcBackend {
     type Session = OdbcSession;
 
@@ -151,9 +195,10 @@ impl Backend for OdbcBackend {
         Box::pin(async move {
             // Create the profile key.
             let store_key = self.key_cache.store_key.clone();
+            let cipher = self.key_cache.profile_cipher;
             let (profile_key, enc_key) = unblock(move || {
-                let profile_key = ProfileKey::new()?;
-                let enc_key = encode_profile_key(&profile_key, &store_key)?;
+                let profile_key = ProfileKey::new_with_cipher(cipher)?;
+                let enc_key = Zeroizing::new(encode_profile_key(&profile_key, &store_key)?);
                 Result::<_, Error>::Ok((profile_key, enc_key))
             })
             .await?;
@@ -162,26 +207,41 @@ impl Backend for OdbcBackend {
 
             // Store the profile name and key.
             {
-                let connection = self.pool.get()?;
+                let connection = self.connection()?;
                 let mut statement = connection.raw().preallocate()?;
 
+                // A concurrent insert of the same name is rejected by the unique index.
                 statement.execute(INSERT_PROFILE,
-                    (&name.clone().into_parameter(), &enc_key.clone().into_parameter()))
-                    .map_err(err_map!(Backend, "Error inserting profile"))?;
-
-                if statement.row_count()?.unwrap() == 0 {
-                    return Err(err_msg!(Duplicate, "Duplicate profile name"));
-                }
+                    (&name.clone().into_parameter(), &enc_key.as_slice().into_parameter()))
+                    .map_err(|err| if is_unique_violation(&err) {
+                        err_msg!(Duplicate, "Duplicate profile name").with_cause(err)
+                    } else {
+                        err_msg!(Backend, "Error inserting profile").with_cause(err)
+                    })?;
 
                 // Retrieve the profile ID from the table.
                 statement.execute(GET_PROFILE_ID,
-                    (&name.clone().into_parameter(), &enc_key.clone().into_parameter()))
+                    (&name.clone().into_parameter(), &enc_key.as_slice().into_parameter()))
                 .map_err(err_map!(Backend, "Error retrieving profile"))?
                 .unwrap()
                 .next_row()
                 .map_err(err_map!(Backend, "Error retrieving profile"))?
                 .unwrap()
                 .get_data(1, &mut pid)?;
+
+                // Create the schema of the profile when each profile is isolated.
+                if let Some(tenant_schemas) = self.tenant_schemas.as_ref() {
+                    if let Err(err) = init_profile_schema(
+                        connection.raw(),
+                        tenant_schemas,
+                        pid,
+                        self.tenant_schema_file.as_deref())
+                    {
+                        // Remove the profile so that its creation may be retried.
+                        let _ = statement.execute(DELETE_PROFILE, &name.clone().into_parameter());
+                        return Err(err);
+                    }
+                }
             }
 
             // Add the details to the key cache.
@@ -201,7 +261,7 @@ impl Backend for OdbcBackend {
         Box::pin(async move {
             let mut profile_buf = Vec::new();
 
-            self.pool.get()?.raw().execute(GET_DEFAULT_PROFILE, ())
+            self.connection()?.raw().execute(GET_DEFAULT_PROFILE, ())
                 .map_err(err_map!(Backend, "Error fetching default profile name"))?
                 .unwrap()
                 .next_row()
@@ -215,7 +275,7 @@ impl Backend for OdbcBackend {
 
     fn set_default_profile(&self, profile: String) -> BoxFuture<'_, Result<(), Error>> {
         Box::pin(async move {
-            self.pool.get()?.raw().execute(UPDATE_CONFIG_PROFILE, &profile.into_parameter())
+            self.connection()?.raw().execute(UPDATE_CONFIG_PROFILE, &profile.into_parameter())
                 .map_err(err_map!(Backend, "Error setting default profile name"))?;
             Ok(())
         })
@@ -225,7 +285,7 @@ impl Backend for OdbcBackend {
         Box::pin(async move {
             let mut names: Vec<String> = Vec::new();
 
-            match self.pool.get()?.raw().execute(GET_PROFILE_NAMES, ()) {
+            match self.connection()?.raw().execute(GET_PROFILE_NAMES, ()) {
                 Ok(cursor) => {
                     let row_set_buffer = RowVec::<(VarCharArray<1024>,)>::new(64);
                     let mut block_cursor = cursor.unwrap().bind_buffer(row_set_buffer)?;
@@ -245,15 +305,48 @@ impl Backend for OdbcBackend {
 
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
         Box::pin(async move {
-            let binding = self.pool.get()?;
-            let mut statement = binding.raw().preallocate()?;
+            let binding = self.connection()?;
+
+            // Remove the profile and its schema in a single transaction, so that the
+            // profile is not left without its records if the schema cannot be dropped.
+            binding.raw().set_autocommit(false)?;
+
+            let result = (|| -> Result<bool, Error> {
+                let mut statement = binding.raw().preallocate()?;
+
+                // Look up the profile schema before the profile is removed.
+                let mut pid: Option<ProfileId> = None;
+                if self.tenant_schemas.is_some() {
+                    if let Some(mut cursor) = statement.execute(GET_PROFILE, &name.clone().into_parameter())
+                        .map_err(err_map!(Backend, "Error fetching profile"))?
+                    {
+                        if let Some(mut row) = cursor.next_row()? {
+                            let mut id: i64 = 0;
+                            row.get_data(1, &mut id)?;
+                            pid = Some(id);
+                        }
+                    }
+                }
+
+                statement.execute(DELETE_PROFILE, &name.clone().into_parameter())
+                    .map_err(err_map!(Backend, "Error removing profile"))?;
+
+                let ret = statement.row_count()?.unwrap_or(0) != 0;
 
-            statement.execute(DELETE_PROFILE, &name.into_parameter())
-                .map_err(err_map!(Backend, "Error removing profile"))?;
+                if let (true, Some(pid), Some(tenant_schemas)) = (ret, pid, self.tenant_schemas.as_ref()) {
+                    drop_profile_schema(binding.raw(), tenant_schemas, pid)?;
+                }
+
+                binding.raw().commit()?;
+                Ok(ret)
+            })();
 
-            let ret = statement.row_count()?.unwrap() != 0;
+            if result.is_err() {
+                let _ = binding.raw().rollback();
+            }
+            let _ = binding.raw().set_autocommit(true);
 
-            Ok(ret)
+            result
         })
     }
 
@@ -267,7 +360,7 @@ impl Backend for OdbcBackend {
         Box::pin(async move {
             let (store_key, store_key_ref) = unblock(move || method.resolve(pass_key)).await?;
             let store_key = Arc::new(store_key);
-            let binding = self.pool.get()?;
+            let binding = self.connection()?;
             let mut upd_keys = BTreeMap::<ProfileId, Vec<u8>>::new();
 
             // Retrieve and temporarily store the current keys for each
@@ -301,8 +394,9 @@ impl Backend for OdbcBackend {
                 })
                 .await?;
 
+                let upd_key = Zeroizing::new(upd_key);
                 binding.raw().execute(UPDATE_PROFILE,
-                    (&upd_key.into_parameter(), &pid.into_parameter()))?;
+                    (&upd_key.as_slice().into_parameter(), &pid.into_parameter()))?;
             }
 
             // We finally need to save the new store key.
@@ -340,18 +434,64 @@ impl Backend for OdbcBackend {
             let mut session = OdbcSession::new(
                 self.key_cache.clone(),
                 profile.unwrap_or_else(|| self.active_profile.clone()),
-                self.pool.get()?,
+                self.connection()?,
                 false,
-            );
+                &self.slow_query_log,
+            ).with_tenant_schemas(self.tenant_schemas.as_ref());
 
             let entries = session.perform_scan(
                 kind,
                 category,
                 tag_filter,
-                offset,
-                limit,
-                order_by,
-                descending).await?;
+                QueryPage {
+                    offset,
+                    limit,
+                    order_by,
+                    descending,
+                    ..Default::default()
+                }).await?;
+
+            let stream = self.create_stream(
+                entries.into_iter().map(|(_, entry)| entry).collect());
+
+            session.close(true).await?;
+
+            Ok(Scan::new(stream, PAGE_SIZE))
+        })
+    }
+
+    fn scan_after(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        after: Option<i64>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, (i64, Entry)>, Error>> {
+        Box::pin(async move {
+            // As with `scan`, the matching records are fetched into memory.
+            let mut session = OdbcSession::new(
+                self.key_cache.clone(),
+                profile.unwrap_or_else(|| self.active_profile.clone()),
+                self.connection()?,
+                false,
+                &self.slow_query_log,
+            ).with_tenant_schemas(self.tenant_schemas.as_ref());
+
+            let entries = session.perform_scan(
+                kind,
+                category,
+                tag_filter,
+                QueryPage {
+                    after,
+                    offset,
+                    limit,
+                    order_by: Some(OrderBy::Id),
+                    descending,
+                }).await?;
 
             let stream = self.create_stream(entries);
 
@@ -361,27 +501,66 @@ impl Backend for OdbcBackend {
         })
     }
 
+    fn scan_namespace(
+        &self,
+        _profile: Option<String>,
+        _kind: Option<EntryKind>,
+        _namespace: String,
+        _tag_filter: Option<TagFilter>,
+        _offset: Option<i64>,
+        _limit: Option<i64>,
+        _order_by: Option<OrderBy>,
+        _descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
+        // The ODBC schema does not include the category namespace index.
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported, "Category namespaces are not supported by the ODBC backend"))))
+    }
+
     fn session(&self, profile: Option<String>, transaction: bool) -> Result<Self::Session, Error> {
         Ok(OdbcSession::new(
             self.key_cache.clone(),
             profile.unwrap_or_else(|| self.active_profile.clone()),
-            self.pool.get()?,
+            self.connection()?,
             transaction,
+            &self.slow_query_log,
+        ).with_tenant_schemas(self.tenant_schemas.as_ref()))
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        let state = self.pool.state();
+        Some(self.pool_metrics.status(
+            state.connections,
+            state.idle_connections,
+            self.pool.max_size(),
         ))
     }
 
+    fn check_pool(&self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move { self.connection().map(drop) })
+    }
+
+    fn backend_kind(&self) -> &'static str {
+        "odbc"
+    }
+
     fn close(&self) -> BoxFuture<'_, Result<(), Error>> {
         Box::pin(async move { Ok(()) })
     }
 }
 
 /// A ODBC session
+///
+/// The session owns its pooled connection, and so may be awaited across the
+/// threads of a multi-threaded executor like the sessions of other backends.
 #[derive(Debug)]
 pub struct OdbcSession {
     cache: Arc<KeyCache>,
     profile: String,
     connection: PooledConnection<OdbcConnectionManager>,
     transaction: bool,
+    slow_query_log: SlowQueryLog,
+    tenant_schemas: Option<TenantSchemas>,
 }
 
 impl OdbcSession {
@@ -390,15 +569,33 @@ impl OdbcSession {
         profile: String,
         connection: PooledConnection<OdbcConnectionManager>,
         transaction: bool,
+        slow_query_log: &SlowQueryLog,
     ) -> Self
     {
         let _ = connection.raw().set_autocommit(!transaction);
 
         Self {
+            slow_query_log: slow_query_log.for_profile(&profile),
             cache: cache,
             profile: profile,
             connection: connection,
             transaction: transaction,
+            tenant_schemas: None,
+        }
+    }
+
+    /// Qualify the record tables of the session with the schema of its profile.
+    pub(crate) fn with_tenant_schemas(mut self, tenant_schemas: Option<&TenantSchemas>) -> Self {
+        self.tenant_schemas = tenant_schemas.cloned();
+        self
+    }
+
+    /// Resolve the record tables of a statement to the schema of the profile, when
+    /// each profile is isolated.
+    fn table_sql<'s>(&self, sql: &'s str, profile_id: ProfileId) -> Cow<'s, str> {
+        match self.tenant_schemas.as_ref() {
+            Some(tenant_schemas) => Cow::Owned(tenant_schemas.qualify(sql, profile_id)),
+            None => Cow::Borrowed(sql),
         }
     }
 
@@ -432,38 +629,39 @@ impl OdbcSession {
         }
     }
 
-    fn get_decoded_tags(&self, item_id: i64, statement: &mut Preallocated<'_>, key: &Arc<ProfileKey>) -> Result<Vec<EntryTag>, Error> {
+    fn get_decoded_tags(&self, item_id: i64, profile_id: ProfileId, statement: &mut Preallocated<'_>, key: &Arc<ProfileKey>) -> Result<Vec<EntryTag>, Error> {
         // Retrieve the tags from the database.
-        if let Some(cursor) = statement.execute(GET_TAGS_FOR_ITEM, &item_id)? {
-            // We use a RowVec buffer to iterate over the rows as it is more efficent
-            // than retrieving the rows one at a time.  This just means that we need
-            // to limit the size of the name and value columns to 1K.
-            type Row = (VarCharArray<1024>, VarCharArray<1024>, i32);
-            let max_rows_in_batch = 64;
-            let buffer = RowVec::<Row>::new(max_rows_in_batch);
-
-            let mut block_cursor = cursor.bind_buffer(buffer)?;
-
-            let mut enc_tags: Vec<EncEntryTag> = Vec::new();
-            while let Some(batch) = block_cursor.fetch()? {
-                // Iterate over each row, retrieving the name, value and
-                // plaintext fields.
-                for (name, value, plaintext) in batch.iter() {
-                    enc_tags.push(EncEntryTag {
-                        name: name.as_bytes().ok_or_else(|| err_msg!(Backend, "Failed to retrieve the tag name"))?.to_vec(),
-                        value: value.as_bytes().ok_or_else(|| err_msg!(Backend, "Failed to retrieve the tag value"))?.to_vec(),
-                        plaintext: (*plaintext == 1),
-                    });
-                }
+        if let Some(mut cursor) = statement.execute(&self.table_sql(GET_TAGS_FOR_ITEM, profile_id), &item_id)? {
+            // The rows are retrieved one at a time into owned buffers rather than
+            // a bound RowVec buffer, which cannot be wiped once the tags have been
+            // decoded.  The buffers are wiped if a later row cannot be retrieved.
+            let mut enc_tags: Zeroizing<Vec<EncEntryTag>> = Zeroizing::new(Vec::new());
+            while let Some(mut row) = cursor.next_row()? {
+                // Retrieve the name, value and plaintext fields.
+                let mut name = Vec::new();
+                row.get_text(1, &mut name)?;
+
+                let mut value = Vec::new();
+                row.get_text(2, &mut value)?;
+
+                let mut plaintext: i32 = 0;
+                row.get_data(3, &mut plaintext)?;
+
+                enc_tags.push(EncEntryTag {
+                    name,
+                    value,
+                    plaintext: (plaintext == 1),
+                });
             }
 
-            Ok(key.decrypt_entry_tags(enc_tags)?)
+            Ok(key.decrypt_entry_tags(std::mem::take(&mut *enc_tags))?)
         } else {
             let tags: Vec<EntryTag> = Vec::new();
             Ok(tags)
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn create_query(
         &mut self,
         query: &str,
@@ -472,8 +670,7 @@ impl OdbcSession {
         kind: Option<EntryKind>,
         category: Option<String>,
         tag_filter: Option<TagFilter>,
-        order_by: Option<OrderBy>,
-        descending: bool,
+        page: &QueryPage,
     ) -> Result<(String, Vec<Box<dyn InputParameter>>), Error>
     {
         // Get an encrypted version of the category and tag filter.
@@ -513,19 +710,27 @@ impl OdbcSession {
             query.push_str(&filter_clause);
         };
 
+        // Resume after the given row identifier, in the direction of the scan.
+        if let Some(after) = page.after {
+            args.push(Box::new(after));
+            query.push_str(if page.descending { " AND i.id < ?" } else { " AND i.id > ?" });
+        }
+
         // Only add ordering if the query starts with SELECT
         if query.trim_start().to_uppercase().starts_with("SELECT") {
-            if let Some(order_by_value) = order_by {
+            if let Some(order_by_value) = page.order_by.as_ref() {
                 query.push_str(" ORDER BY ");
                 match order_by_value {
                     OrderBy::Id => query.push_str("id"),
                 }
-                if descending {
+                if page.descending {
                     query.push_str(" DESC");
                 }
             };
         }
 
+        let query = self.table_sql(&query, profile_id).into_owned();
+
         Ok((query, args))
     }
 
@@ -534,11 +739,8 @@ impl OdbcSession {
         kind: Option<EntryKind>,
         category: Option<String>,
         tag_filter: Option<TagFilter>,
-        offset: Option<i64>,
-        limit: Option<i64>,
-        order_by: Option<OrderBy>,
-        descending: bool,
-    ) -> Result<Vec<Entry>, Error> {
+        page: QueryPage,
+    ) -> Result<Vec<(i64, Entry)>, Error> {
         let (profile_id, key) = self.acquire_key().await?;
 
         // Create the query which is to be executed.
@@ -549,23 +751,26 @@ impl OdbcSession {
             kind,
             category,
             tag_filter,
-            order_by,
-            descending).await?;
+            &page).await?;
 
         // Execute the query.
         let mut statement = self.connection.raw().preallocate()?;
         let mut tag_statement = self.connection.raw().preallocate()?;
 
-        let mut items: Vec<Entry> = Vec::new();
+        let mut items: Vec<(i64, Entry)> = Vec::new();
+
+        let start = self.slow_query_log.start();
+        let cursor = statement.execute(&query, params.as_slice())?;
+        self.slow_query_log.finish("GET_ALL_ITEMS", start);
 
-        if let Some(mut cursor) = statement.execute(&query, params.as_slice())? {
+        if let Some(mut cursor) = cursor {
             // Set up the offset and limit parameters.  It would have been nice
             // to include these parameters in the SQL query - but this is not
             // ansi-sql compliant, and so we need to manually process these
             // values within our for loop.  This is going to be slow, but can't
             // be helped.
-            let offset: i64 = offset.unwrap_or(0);
-            let limit: i64 = limit.unwrap_or(-1);
+            let offset: i64 = page.offset.unwrap_or(0);
+            let limit: i64 = page.limit.unwrap_or(-1);
             let mut row_number: i64 = 0;
 
             // Process each row in the response.
@@ -600,18 +805,19 @@ impl OdbcSession {
                 let mut value_buf = Vec::new();
                 row.get_binary(5, &mut value_buf)?;
 
-                let tags: Vec<EntryTag> = self.get_decoded_tags(item_id, &mut tag_statement, &key)?;
+                let tags: Vec<EntryTag> = self.get_decoded_tags(item_id, profile_id, &mut tag_statement, &key)?;
+                let kind = EntryKind::try_from(kind_buf as usize)?;
                 let category = key.decrypt_entry_category(category_buf)?;
                 let name = key.decrypt_entry_name(name_buf)?;
-                let value = key.decrypt_entry_value(category.as_bytes(), name.as_bytes(), value_buf)?;
+                let value = key.decrypt_entry_value(profile_id, kind, category.as_bytes(), name.as_bytes(), value_buf)?;
 
-                items.push(Entry {
-                    kind: EntryKind::try_from(kind_buf as usize)?,
+                items.push((item_id, Entry {
+                    kind,
                     category,
                     name,
                     value,
                     tags
-                });
+                }));
             }
         }
 
@@ -619,6 +825,238 @@ impl OdbcSession {
     }
 }
 
+impl EntrySession for OdbcSession {
+    async fn fetch_entry<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        _for_update: bool,
+    ) -> Result<Option<Entry>, Error> {
+        let category = category.to_string();
+        let name = name.to_string();
+
+        // Create the 'select' fields.
+        let (pid, key) = self.acquire_key().await?;
+        let (enc_category, enc_name) = unblock({
+            let key = key.clone();
+            let category = ProfileKey::prepare_input(category.as_bytes());
+            let name = ProfileKey::prepare_input(name.as_bytes());
+            move || {
+                Result::<_, Error>::Ok((
+                    key.encrypt_entry_category(category)?,
+                    key.encrypt_entry_name(name)?,
+                ))
+            }
+        })
+        .await?;
+
+        let mut statement = self.connection.raw().preallocate()?;
+
+        // Retrieve the item from the database.
+        let mut item_id: i64 = 0;
+        let mut value: Vec<u8> = Vec::new();
+
+        let start = self.slow_query_log.start();
+        if let Ok(Some(mut row)) = statement.execute(&self.table_sql(GET_ITEM, pid), (
+            &pid.into_parameter(),
+            &(kind as i16).into_parameter(),
+            &enc_category.as_slice().into_parameter(),
+            &enc_name.as_slice().into_parameter()
+        )).map_err(err_map!(Backend, "Error performing fetch query"))?
+        .unwrap().next_row() {
+            row.get_binary(2, &mut value)?;
+            row.get_data(1, &mut item_id)?;
+            self.slow_query_log.finish("GET_ITEM", start);
+        } else {
+            self.slow_query_log.finish("GET_ITEM", start);
+            return Ok(None);
+        }
+
+        // Build up the response.
+        let dvalue = key.decrypt_entry_value(pid, kind, category.as_ref(), name.as_ref(), value)?;
+        let tags: Vec<EntryTag> = self.get_decoded_tags(item_id, pid, &mut statement, &key)?;
+
+        Ok(Some(Entry::new(kind, category, name, dvalue, tags)))
+    }
+
+    async fn update_entry<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+
+        match operation {
+            op @ EntryOperation::Insert | op @ EntryOperation::Replace => {
+                let value = ProfileKey::prepare_input(value.unwrap_or_default());
+                let tags = tags.map(prepare_tags);
+                // Locate the correct key and then encrypt our various fields.
+                let (pid, key) = self.acquire_key().await?;
+                let (enc_category, enc_name, enc_value, enc_tags) = unblock(move || {
+                    let enc_value =
+                        key.encrypt_entry_value(pid, kind, category.as_ref(), name.as_ref(), value)?;
+                    Result::<_, Error>::Ok((
+                        key.encrypt_entry_category(category)?,
+                        key.encrypt_entry_name(name)?,
+                        enc_value,
+                        tags.transpose()?
+                            .map(|t| key.encrypt_entry_tags(t).map(Zeroizing::new))
+                            .transpose()?,
+                    ))
+                })
+                .await?;
+
+                let mut statement = self.connection.raw().preallocate()?;
+
+                // Work out the expiry time.
+                let mut expiry_str: String = String::new();
+
+                if let Some(expiry) = expiry_ms.map(expiry_timestamp).transpose()? {
+                    // ODBC expects the time stamp to be in a string, of the format:
+                    //   YYYY-MM-DD HH:MM:SS.MSEC
+                    expiry_str = format!("{}", expiry.format("%Y-%m-%d %H:%M:%S.%6f"));
+                }
+
+                // Now we need to store the fields in the database.
+                let start = self.slow_query_log.start();
+                if op == EntryOperation::Insert {
+                    if expiry_str.is_empty() {
+                        statement.execute(&self.table_sql(INSERT_ITEM, pid),
+                            (
+                                &pid.into_parameter(),
+                                &(kind as i16).into_parameter(),
+                                &enc_category.as_slice().into_parameter(),
+                                &enc_name.as_slice().into_parameter(),
+                                &enc_value.into_parameter()
+                            )).map_err(err_map!(Backend, "Error inserting new entry"))?;
+                    } else {
+                        statement.execute(&self.table_sql(INSERT_ITEM_WITH_EXPIRY, pid),
+                            (
+                                &pid.into_parameter(),
+                                &(kind as i16).into_parameter(),
+                                &enc_category.as_slice().into_parameter(),
+                                &enc_name.as_slice().into_parameter(),
+                                &enc_value.into_parameter(),
+                                &expiry_str.into_parameter()
+                            )).map_err(err_map!(Backend, "Error inserting new entry"))?;
+                    }
+
+                    self.slow_query_log.finish("INSERT_ITEM", start);
+
+                    if statement.row_count()?.unwrap() == 0 {
+                        return Err(err_msg!(Duplicate, "Duplicate entry"));
+                    }
+                } else {
+                    if expiry_str.is_empty() {
+                        statement.execute(&self.table_sql(UPDATE_ITEM, pid),
+                            (
+                                &enc_value.into_parameter(),
+                                &pid.into_parameter(),
+                                &(kind as i16).into_parameter(),
+                                &enc_category.as_slice().into_parameter(),
+                                &enc_name.as_slice().into_parameter()
+                            )).map_err(|_| err_msg!(NotFound, "Error updating existing entry"))?;
+                    } else {
+                        statement.execute(&self.table_sql(UPDATE_ITEM_WITH_EXPIRY, pid),
+                            (
+                                &enc_value.into_parameter(),
+                                &expiry_str.into_parameter(),
+                                &pid.into_parameter(),
+                                &(kind as i16).into_parameter(),
+                                &enc_category.as_slice().into_parameter(),
+                                &enc_name.as_slice().into_parameter()
+                            )).map_err(|_| err_msg!(NotFound, "Error updating existing entry"))?;
+                    }
+                    self.slow_query_log.finish("UPDATE_ITEM", start);
+
+                    // We also want to delete all existing tags for this
+                    // item.
+
+                    statement.execute(&self.table_sql(DELETE_TAG, pid), &pid.into_parameter())
+                        .map_err(err_map!(Backend, "Error removing existing entry tags"))?;
+                }
+
+                // Now we need to update the tags table.
+                if let Some(tags) = enc_tags {
+                    // Retrieve the item identifier.
+                    let mut item_id: i64 = 0;
+
+                    statement.execute(&self.table_sql(GET_ITEM_ID, pid),
+                        (
+                            &pid.into_parameter(),
+                            &(kind as i16).into_parameter(),
+                            &enc_category.as_slice().into_parameter(),
+                            &enc_name.as_slice().into_parameter()
+                        ))
+                        .map_err(err_map!(Backend, "Error retrieving item"))?
+                        .unwrap()
+                        .next_row()
+                        .map_err(err_map!(Backend, "Error retrieving item"))?
+                        .unwrap()
+                        .get_data(1, &mut item_id)?;
+
+                    // Update each of the tags.
+                    let mut prepared = self.connection.raw().prepare(&self.table_sql(INSERT_TAG, pid))?;
+
+                    for tag in tags.iter() {
+                        prepared.execute(
+                            (
+                                &item_id.into_parameter(),
+                                &tag.name.as_slice().into_parameter(),
+                                &tag.value.as_slice().into_parameter(),
+                                &(tag.plaintext as i16).into_parameter()
+                            )).map_err(err_map!(Backend, "Error inserting entry tags"))?;
+                    }
+                }
+
+                Ok(())
+            }
+
+            EntryOperation::Remove => {
+                // Create the encrypted category and name.
+                let (pid, key) = self.acquire_key().await?;
+                let (enc_category, enc_name) = unblock(move || {
+                    Result::<_, Error>::Ok((
+                        key.encrypt_entry_category(category)?,
+                        key.encrypt_entry_name(name)?,
+                    ))
+                })
+                .await?;
+
+                // Issue the delete.  We don't return an error if the
+                // item doesn't currently exist.
+                let mut statement = self.connection.raw().preallocate()?;
+
+                let start = self.slow_query_log.start();
+                statement.execute(&self.table_sql(DELETE_ITEM, pid),
+                    (
+                        &pid.into_parameter(),
+                        &(kind as i16).into_parameter(),
+                        &enc_category.into_parameter(),
+                        &enc_name.into_parameter()
+                    ))
+                    .map_err(err_map!(Backend, "Error deleting item"))?;
+                self.slow_query_log.finish("DELETE_ITEM", start);
+
+                let deleted = statement.row_count()?.unwrap();
+
+                if deleted == 0 {
+                    return Err(err_msg!(NotFound, "Entry not found"));
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
 impl BackendSession for OdbcSession {
     fn count<'q>(
         &'q mut self,
@@ -638,12 +1076,12 @@ impl BackendSession for OdbcSession {
                 kind,
                 category.map(str::to_string),
                 tag_filter,
-                None,
-                false).await?;
+                &QueryPage::default()).await?;
 
             let mut count: i64 = 0;
 
             // Execute the query.
+            let start = self.slow_query_log.start();
             self.connection.raw().execute(&query, params.as_slice())
                 .map_err(err_map!(Backend, "Error performing count query"))?
                 .unwrap()
@@ -651,62 +1089,31 @@ impl BackendSession for OdbcSession {
                 .map_err(err_map!(Backend, "Error performing count query"))?
                 .unwrap()
                 .get_data(1, &mut count)?;
+            self.slow_query_log.finish("COUNT_ITEMS", start);
 
             Ok(count)
         })
     }
 
-    fn fetch(
-        &mut self,
-        kind: EntryKind,
-        category: &str,
-        name: &str,
-        _for_update: bool,
-    ) -> BoxFuture<'_, Result<Option<Entry>, Error>> {
-        let category = category.to_string();
-        let name = name.to_string();
-
-        Box::pin(async move {
-            // Create the 'select' fields.
-            let (pid, key) = self.acquire_key().await?;
-            let (enc_category, enc_name) = unblock({
-                let key = key.clone();
-                let category = ProfileKey::prepare_input(category.as_bytes());
-                let name = ProfileKey::prepare_input(name.as_bytes());
-                move || {
-                    Result::<_, Error>::Ok((
-                        key.encrypt_entry_category(category)?,
-                        key.encrypt_entry_name(name)?,
-                    ))
-                }
-            })
-            .await?;
-
-            let mut statement = self.connection.raw().preallocate()?;
-
-            // Retrieve the item from the database.
-            let mut item_id: i64 = 0;
-            let mut value: Vec<u8> = Vec::new();
-
-            if let Ok(Some(mut row)) = statement.execute(GET_ITEM, (
-                &pid.into_parameter(),
-                &(kind as i16).into_parameter(),
-                &enc_category.clone().into_parameter(),
-                &enc_name.clone().into_parameter()
-            )).map_err(err_map!(Backend, "Error performing fetch query"))?
-            .unwrap().next_row() {
-                row.get_binary(2, &mut value)?;
-                row.get_data(1, &mut item_id)?;
-            } else {
-                return Ok(None);
-            }
-
-            // Build up the response.
-            let dvalue = key.decrypt_entry_value(category.as_ref(), name.as_ref(), value)?;
-            let tags: Vec<EntryTag> = self.get_decoded_tags(item_id, &mut statement, &key)?;
+    fn count_namespace<'q>(
+        &'q mut self,
+        _kind: Option<EntryKind>,
+        _namespace: &'q str,
+        _tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        // The ODBC schema does not include the category namespace index.
+        Box::pin(std::future::ready(Err(err_msg!(
+            Unsupported, "Category namespaces are not supported by the ODBC backend"))))
+    }
 
-            Ok(Some(Entry::new(kind, category, name, dvalue, tags)))
-        })
+    fn fetch<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        for_update: bool,
+    ) -> BoxFuture<'q, Result<Option<Entry>, Error>> {
+        Box::pin(self.fetch_entry(kind, category, name, for_update))
     }
 
     fn fetch_all<'q>(
@@ -726,11 +1133,13 @@ impl BackendSession for OdbcSession {
                         kind,
                         category.clone(),
                         tag_filter,
-                        None,
-                        limit,
-                        order_by,
-                        descending
-            ).await?)
+                        QueryPage {
+                            limit,
+                            order_by,
+                            descending,
+                            ..Default::default()
+                        }
+            ).await?.into_iter().map(|(_, entry)| entry).collect())
          })
     }
 
@@ -752,13 +1161,14 @@ impl BackendSession for OdbcSession {
                 kind,
                 category.map(str::to_string),
                 tag_filter,
-                None,
-                false).await?;
+                &QueryPage::default()).await?;
 
             // Execute the query.
             let mut statement = self.connection.raw().preallocate()?;
+            let start = self.slow_query_log.start();
             statement.execute(&query, params.as_slice())
                 .map_err(err_map!(Backend, "Error removing entry"))?;
+            self.slow_query_log.finish("DELETE_ALL_ITEMS", start);
 
             let removed = statement.row_count()?.unwrap();
 
@@ -776,166 +1186,12 @@ impl BackendSession for OdbcSession {
         tags: Option<&'q [EntryTag]>,
         expiry_ms: Option<i64>,
     ) -> BoxFuture<'q, Result<(), Error>> {
-        let category = ProfileKey::prepare_input(category.as_bytes());
-        let name = ProfileKey::prepare_input(name.as_bytes());
-
-        match operation {
-            op @ EntryOperation::Insert | op @ EntryOperation::Replace => {
-                let value = ProfileKey::prepare_input(value.unwrap_or_default());
-                let tags = tags.map(prepare_tags);
-                Box::pin(async move {
-                    // Locate the correct key and then encrypt our various fields.
-                    let (pid, key) = self.acquire_key().await?;
-                    let (enc_category, enc_name, enc_value, enc_tags) = unblock(move || {
-                        let enc_value =
-                            key.encrypt_entry_value(category.as_ref(), name.as_ref(), value)?;
-                        Result::<_, Error>::Ok((
-                            key.encrypt_entry_category(category)?,
-                            key.encrypt_entry_name(name)?,
-                            enc_value,
-                            tags.transpose()?
-                                .map(|t| key.encrypt_entry_tags(t))
-                                .transpose()?,
-                        ))
-                    })
-                    .await?;
-
-                    let mut statement = self.connection.raw().preallocate()?;
-
-                    // Work out the expiry time.
-                    let mut expiry_str: String = String::new();
-
-                    if let Some(expiry) = expiry_ms.map(expiry_timestamp).transpose()? {
-                        // ODBC expects the time stamp to be in a string, of the format:
-                        //   YYYY-MM-DD HH:MM:SS.MSEC
-                        expiry_str = format!("{}", expiry.format("%Y-%m-%d %H:%M:%S.%6f"));
-                    }
-
-                    // Now we need to store the fields in the database.
-                    if op == EntryOperation::Insert {
-                        if expiry_str.is_empty() {
-                            statement.execute(INSERT_ITEM,
-                                (
-                                    &pid.into_parameter(),
-                                    &(kind as i16).into_parameter(),
-                                    &enc_category.clone().into_parameter(),
-                                    &enc_name.clone().into_parameter(),
-                                    &enc_value.into_parameter()
-                                )).map_err(err_map!(Backend, "Error inserting new entry"))?;
-                        } else {
-                            statement.execute(INSERT_ITEM_WITH_EXPIRY,
-                                (
-                                    &pid.into_parameter(),
-                                    &(kind as i16).into_parameter(),
-                                    &enc_category.clone().into_parameter(),
-                                    &enc_name.clone().into_parameter(),
-                                    &enc_value.into_parameter(),
-                                    &expiry_str.into_parameter()
-                                )).map_err(err_map!(Backend, "Error inserting new entry"))?;
-                        }
-
-                        if statement.row_count()?.unwrap() == 0 {
-                            return Err(err_msg!(Duplicate, "Duplicate entry"));
-                        }
-                    } else {
-                        if expiry_str.is_empty() {
-                            statement.execute(UPDATE_ITEM,
-                                (
-                                    &enc_value.into_parameter(),
-                                    &pid.into_parameter(),
-                                    &(kind as i16).into_parameter(),
-                                    &enc_category.clone().into_parameter(),
-                                    &enc_name.clone().into_parameter()
-                                )).map_err(|_| err_msg!(NotFound, "Error updating existing entry"))?;
-                        } else {
-                            statement.execute(UPDATE_ITEM_WITH_EXPIRY,
-                                (
-                                    &enc_value.into_parameter(),
-                                    &expiry_str.into_parameter(),
-                                    &pid.into_parameter(),
-                                    &(kind as i16).into_parameter(),
-                                    &enc_category.clone().into_parameter(),
-                                    &enc_name.clone().into_parameter()
-                                )).map_err(|_| err_msg!(NotFound, "Error updating existing entry"))?;
-                        }
-
-                        // We also want to delete all existing tags for this
-                        // item.
-
-                        statement.execute(DELETE_TAG, &pid.into_parameter())
-                            .map_err(err_map!(Backend, "Error removing existing entry tags"))?;
-                    }
-
-                    // Now we need to update the tags table.
-                    if let Some(tags) = enc_tags {
-                        // Retrieve the item identifier.
-                        let mut item_id: i64 = 0;
-
-                        statement.execute(GET_ITEM_ID,
-                            (
-                                &pid.into_parameter(),
-                                &(kind as i16).into_parameter(),
-                                &enc_category.clone().into_parameter(),
-                                &enc_name.clone().into_parameter()
-                            ))
-                            .map_err(err_map!(Backend, "Error retrieving item"))?
-                            .unwrap()
-                            .next_row()
-                            .map_err(err_map!(Backend, "Error retrieving item"))?
-                            .unwrap()
-                            .get_data(1, &mut item_id)?;
-
-                        // Update each of the tags.
-                        let mut prepared = self.connection.raw().prepare(INSERT_TAG)?;
-
-                        for tag in tags {
-                            prepared.execute(
-                                (
-                                    &item_id.into_parameter(),
-                                    &tag.name.into_parameter(),
-                                    &tag.value.into_parameter(),
-                                    &(tag.plaintext as i16).into_parameter()
-                                )).map_err(err_map!(Backend, "Error inserting entry tags"))?;
-                        }
-                    }
-
-                    Ok(())
-                })
-            }
-
-            EntryOperation::Remove => Box::pin(async move {
-                // Create the encrypted category and name.
-                let (pid, key) = self.acquire_key().await?;
-                let (enc_category, enc_name) = unblock(move || {
-                    Result::<_, Error>::Ok((
-                        key.encrypt_entry_category(category)?,
-                        key.encrypt_entry_name(name)?,
-                    ))
-                })
-                .await?;
-
-                // Issue the delete.  We don't return an error if the
-                // item doesn't currently exist.
-                let mut statement = self.connection.raw().preallocate()?;
-
-                statement.execute(DELETE_ITEM,
-                    (
-                        &pid.into_parameter(),
-                        &(kind as i16).into_parameter(),
-                        &enc_category.into_parameter(),
-                        &enc_name.into_parameter()
-                    ))
-                    .map_err(err_map!(Backend, "Error deleting item"))?;
-
-                let deleted = statement.row_count()?.unwrap();
-
-                if deleted == 0 {
-                    return Err(err_msg!(NotFound, "Entry not found"));
-                }
+        Box::pin(self.update_entry(kind, operation, category, name, value, tags, expiry_ms))
+    }
 
-                Ok(())
-            }),
-        }
+    fn set_trace_context(&mut self, trace: Option<TraceContext>) {
+        // statements are not annotated, but the trace ID is logged with slow statements
+        self.slow_query_log.set_trace(trace.as_ref());
     }
 
     fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
@@ -1041,4 +1297,28 @@ mod tests {
             "This ? is ? a ? string!",
         );
     }
+
+    #[test]
+    fn odbc_thread_safety_bounds() {
+        fn assert_send<T: Send>() {}
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<OdbcBackend>();
+        assert_send::<OdbcSession>();
+        assert_send::<PooledConnection<OdbcConnectionManager>>();
+        assert_send_sync::<r2d2_connection_pool::OdbcError>();
+    }
+
+    #[test]
+    fn odbc_unique_violation() {
+        use odbc_api::handles::{Record, State};
+
+        let error = |state: &[u8; 5]| odbc_api::Error::Diagnostics {
+            record: Record { state: State(*state), native_error: -803, message: Vec::new() },
+            function: "SQLExecDirect",
+        };
+        assert!(is_unique_violation(&error(b"23505")));
+        assert!(is_unique_violation(&error(b"23000")));
+        assert!(!is_unique_violation(&error(b"42S02")));
+    }
 }
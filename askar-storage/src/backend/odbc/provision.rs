@@ -1,16 +1,18 @@
 use std::time::Duration;
+use std::fmt::{self, Debug, Formatter};
 use std::fs;
 use odbc_api::{Cursor, IntoParameter};
+use zeroize::Zeroizing;
 
 use crate::{
     backend::{
-        db_utils::{init_keys, random_profile_name},
-        ManageBackend,
+        db_utils::{init_keys, random_profile_name, SlowQueryLog},
+        ManageBackend, TenantIsolation, TenantSchemas, TENANT_SCHEMA_CONFIG,
     },
     error::Error,
     future::{unblock, BoxFuture},
     options::IntoOptions,
-    protect::{KeyCache, PassKey, StoreKeyMethod, StoreKeyReference},
+    protect::{KeyCache, NameEncryption, PassKey, ProfileCipher, ProfileId, StoreKeyMethod, StoreKeyReference},
 };
 
 use super::OdbcBackend;
@@ -36,21 +38,53 @@ const DEFAULT_IDLE_TIMEOUT: u64 = 300;
 const DEFAULT_MAX_LIFETIME: u64 = 300;
 const DEFAULT_MIN_CONNECTIONS: u32 = 0;
 const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+const DEFAULT_TENANT_SCHEMA_PREFIX: &str = "askar";
 
 /// Configuration options for ODBC stores
-#[derive(Debug)]
 pub struct OdbcStoreOptions {
     pub(crate) connect_timeout: Duration,
     pub(crate) idle_timeout: Duration,
     pub(crate) max_lifetime: Duration,
     pub(crate) max_connections: u32,
     pub(crate) min_connections: u32,
-    pub(crate) connection_string: String,
+    pub(crate) warm_connections: u32,
+    pub(crate) connection_string: Zeroizing<String>,
     pub(crate) schema_file: String,
+    pub(crate) slow_query_log: SlowQueryLog,
+    pub(crate) tenant_isolation: TenantIsolation,
+    pub(crate) tenant_schema_file: Option<String>,
+    pub(crate) tenant_schema_prefix: String,
+}
+
+impl Debug for OdbcStoreOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // the connection string may include the account password
+        f.debug_struct("OdbcStoreOptions")
+            .field("connect_timeout", &self.connect_timeout)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("max_lifetime", &self.max_lifetime)
+            .field("max_connections", &self.max_connections)
+            .field("min_connections", &self.min_connections)
+            .field("schema_file", &self.schema_file)
+            .field("tenant_isolation", &self.tenant_isolation)
+            .finish_non_exhaustive()
+    }
 }
 
 impl OdbcStoreOptions {
     /// Initialize `OdbcStoreOptions` from a generic set of options
+    ///
+    /// When `slow_query_ms` is set, record statements taking longer than this many
+    /// milliseconds are logged with their statement identifier and profile name.
+    ///
+    /// Setting `tenant_isolation=schema` provisions a store which keeps the records
+    /// of each profile in a separate schema, named using the `tenant_schema_prefix`
+    /// parameter (by default `askar`) followed by `_p` and the profile ID. The
+    /// `tenant_schema_file` parameter must then name a file containing the statements
+    /// creating the `items` and `items_tags` tables of a profile, in which `{schema}`
+    /// is replaced by the name of the schema.
+    ///
+    /// Any other parameter results in an `Unsupported` error.
     pub fn new<'a, O>(options: O) -> Result<Self, Error>
     where
         O: IntoOptions<'a>,
@@ -91,13 +125,42 @@ impl OdbcStoreOptions {
         } else {
             DEFAULT_MIN_CONNECTIONS
         };
+        let warm_connections = if let Some(warm_conn) = opts.query.remove("warm_connections") {
+            warm_conn
+                .parse()
+                .map_err(err_map!(Input, "Error parsing 'warm_connections' parameter"))?
+        } else {
+            0
+        };
+        let slow_query_log = SlowQueryLog::from_query(&mut opts.query)?;
 
         let schema_file = opts.query.remove("schema_file");
         if !schema_file.is_some() {
             return Err(err_msg!(Input, "Missing 'schema_file' parameter"));
         }
 
-        let mut connection_string = opts.host.to_string();
+        let tenant_isolation = TenantIsolation::from_query(&mut opts.query)?;
+        let tenant_schema_file = opts.query.remove("tenant_schema_file");
+        let tenant_schema_prefix = opts.query.remove("tenant_schema_prefix")
+            .unwrap_or_else(|| DEFAULT_TENANT_SCHEMA_PREFIX.to_string());
+        if tenant_isolation == TenantIsolation::Schema {
+            if tenant_schema_file.is_none() {
+                return Err(err_msg!(Input, "Missing 'tenant_schema_file' parameter"));
+            }
+            TenantSchemas::new(&tenant_schema_prefix)?;
+        }
+
+        // Options of the other backends cannot be honoured here, and are refused
+        // along with any unknown parameters rather than silently ignored.
+        if let Some(name) = opts.query.keys().min() {
+            return Err(err_msg!(
+                Unsupported,
+                "The '{}' parameter is not supported by the ODBC backend", name
+            ));
+        }
+
+        // The connection string may include the database credentials.
+        let mut connection_string = Zeroizing::new(opts.host.to_string());
         connection_string.push_str(&opts.path);
 
         Ok(Self {
@@ -106,8 +169,13 @@ impl OdbcStoreOptions {
             max_lifetime: Duration::from_secs(max_lifetime),
             max_connections,
             min_connections,
+            warm_connections,
             connection_string,
             schema_file: schema_file.unwrap(),
+            slow_query_log,
+            tenant_isolation,
+            tenant_schema_file,
+            tenant_schema_prefix,
         })
     }
 
@@ -137,31 +205,23 @@ impl OdbcStoreOptions {
         // Initialise the key store.
         let (profile_key, enc_profile_key, store_key, store_key_ref) = unblock({
             let pass_key = pass_key.into_owned();
-            move || init_keys(method, pass_key)
+            move || init_keys(method, pass_key, ProfileCipher::default(), NameEncryption::default(), None)
         })
         .await?;
+        let enc_profile_key = Zeroizing::new(enc_profile_key);
 
         // Work out the profile.
         let default_profile = profile.unwrap_or_else(random_profile_name);
 
         // We need to provision the database now.  We do this by applying the database
-        // schema which has been provided in the schema file.  We need to execute each
-        // SQL statement one at a time as the ODBC API does not appear to support the
-        // execution of multiple statements in a single API call.
+        // schema which has been provided in the schema file.
         let schema: String = fs::read_to_string(self.schema_file)
             .map_err(err_map!(Backend, "Failed to read the schema file"))?;
 
         let connection = pool.get()?;
-        let mut statement = connection.raw().preallocate()?;
-
-        let definitions = schema.split(";");
-        for definition in definitions {
-            let trimmed_definition = definition.trim();
+        execute_script(connection.raw(), &schema)?;
 
-            if trimmed_definition.len() > 0 {
-                statement.execute(trimmed_definition, ())?;
-            }
-        }
+        let mut statement = connection.raw().preallocate()?;
 
         // Save the configuration information.
         statement.execute("INSERT INTO config (name, value) VALUES
@@ -176,7 +236,7 @@ impl OdbcStoreOptions {
 
         statement.execute("INSERT INTO profiles (name, profile_key) VALUES (?, ?)", (
             &default_profile.clone().into_parameter(),
-            &enc_profile_key.clone().into_parameter()
+            &enc_profile_key.as_slice().into_parameter()
         ))
         .map_err(err_map!(Backend, "Failed to insert the default profile"))?;
 
@@ -186,7 +246,7 @@ impl OdbcStoreOptions {
         statement.execute(
                 "SELECT id from profiles WHERE name=? and profile_key=?", (
                     &default_profile.clone().into_parameter(),
-                    &enc_profile_key.clone().into_parameter()
+                    &enc_profile_key.as_slice().into_parameter()
                 ))
             .map_err(err_map!(Backend, "Failed to retrieve the default profile"))?
             .unwrap()
@@ -195,6 +255,27 @@ impl OdbcStoreOptions {
             .unwrap()
             .get_data(1, &mut profile_id)?;
 
+        // Create the schema of the default profile when each profile is isolated.
+        let tenant_schemas = if self.tenant_isolation == TenantIsolation::Schema {
+            let tenant_schemas = TenantSchemas::new(&self.tenant_schema_prefix)?;
+
+            statement.execute("INSERT INTO config (name, value) VALUES (?, ?)", (
+                &TENANT_SCHEMA_CONFIG.into_parameter(),
+                &tenant_schemas.prefix().into_parameter()
+            ))
+            .map_err(err_map!(Backend, "Failed to insert the configuration data"))?;
+
+            init_profile_schema(
+                connection.raw(),
+                &tenant_schemas,
+                profile_id,
+                self.tenant_schema_file.as_deref())?;
+
+            Some(tenant_schemas)
+        } else {
+            None
+        };
+
         let mut key_cache = KeyCache::new(store_key);
         key_cache.add_profile_mut(default_profile.clone(), profile_id, profile_key);
 
@@ -203,7 +284,8 @@ impl OdbcStoreOptions {
             pool,
             default_profile,
             key_cache,
-        ))
+            self.slow_query_log,
+        ).with_tenant_schemas(tenant_schemas, self.tenant_schema_file))
     }
 
     /// Open an existing Odbc store from this set of configuration options
@@ -221,9 +303,11 @@ impl OdbcStoreOptions {
         let mut ver_ok = false;
         let mut default_profile: Option<String> = None;
         let mut store_key_ref: Option<String> = None;
+        let mut tenant_schemas: Option<TenantSchemas> = None;
 
         match pool.get()?.raw().execute(
-                "SELECT name, value FROM config WHERE name IN ('default_profile', 'key', 'version')", ()) {
+                "SELECT name, value FROM config
+                WHERE name IN ('default_profile', 'key', 'version', 'tenant_schema_prefix')", ()) {
             Ok(cursor) => {
                 let mut unwrapped = cursor.unwrap();
 
@@ -251,6 +335,9 @@ impl OdbcStoreOptions {
                             }
                             ver_ok = true;
                         }
+                        TENANT_SCHEMA_CONFIG => {
+                            tenant_schemas.replace(TenantSchemas::new(&value)?);
+                        }
                         _ => (),
                     }
                 }
@@ -309,7 +396,8 @@ impl OdbcStoreOptions {
             pool,
             profile,
             key_cache,
-        ))
+            self.slow_query_log,
+        ).with_tenant_schemas(tenant_schemas, self.tenant_schema_file))
     }
 
     /// Remove an existing Odbc store defined by these configuration options
@@ -327,19 +415,64 @@ impl OdbcStoreOptions {
 
     /// Create a pool of connections to the database server.
     async fn open_pool(&self) -> Result<r2d2::Pool<OdbcConnectionManager>, Error> {
-        let manager = OdbcConnectionManager::new(self.connection_string.clone());
-        Ok(r2d2::Pool::builder()
+        let manager = OdbcConnectionManager::new(self.connection_string.as_str());
+        let pool = r2d2::Pool::builder()
             .max_size(self.max_connections)
             .min_idle(Some(self.min_connections))
             .max_lifetime(Some(self.max_lifetime))
             .connection_timeout(self.connect_timeout)
             .idle_timeout(Some(self.idle_timeout))
             .build(manager)
-            .map_err(err_map!(Backend, "Failed to open the database pool"))?)
+            .map_err(err_map!(Backend, "Failed to open the database pool"))?;
+
+        // Establish the requested connections in advance of their use.
+        let warm_count = self.warm_connections.min(pool.max_size());
+        let mut conns = Vec::with_capacity(warm_count as usize);
+        while conns.len() < warm_count as usize {
+            conns.push(
+                pool.get()
+                    .map_err(err_map!(Backend, "Failed to establish a pooled connection"))?,
+            );
+        }
+        drop(conns);
+
+        Ok(pool)
     }
 
     // Drop all of our tables from the database server.
     fn drop_tables(&self, pool: &r2d2::Pool<OdbcConnectionManager>) -> Result<(), Error> {
+        // Drop the schemas of the profiles first, as their tables may refer to the
+        // profiles table.
+        let connection = pool.get()?;
+        let mut prefix_buf = Vec::new();
+
+        if let Some(mut cursor) = connection.raw().execute(
+                "SELECT value FROM config WHERE name = ?", &TENANT_SCHEMA_CONFIG.into_parameter())
+            .map_err(err_map!(Backend, "Failed to retrieve the configuration data"))?
+        {
+            if let Some(mut row) = cursor.next_row()? {
+                row.get_text(1, &mut prefix_buf)?;
+            }
+        }
+
+        if !prefix_buf.is_empty() {
+            let tenant_schemas = TenantSchemas::new(&String::from_utf8(prefix_buf)?)?;
+            let mut profile_ids: Vec<ProfileId> = Vec::new();
+
+            if let Some(mut cursor) = connection.raw().execute("SELECT id FROM profiles", ())? {
+                while let Some(mut row) = cursor.next_row()? {
+                    let mut pid: i64 = 0;
+                    row.get_data(1, &mut pid)?;
+                    profile_ids.push(pid);
+                }
+            }
+
+            for pid in profile_ids {
+                drop_profile_schema(connection.raw(), &tenant_schemas, pid)?;
+            }
+        }
+        drop(connection);
+
         let table_names: [&str; 4] = ["items_tags", "items", "profiles", "config"];
 
         for table_name in &table_names {
@@ -358,6 +491,111 @@ impl OdbcStoreOptions {
 
 }
 
+/// Create the schema holding the records of a profile by executing the statements
+/// of the tenant schema file.
+pub(crate) fn init_profile_schema(
+    connection: &odbc_api::Connection<'_>,
+    tenant_schemas: &TenantSchemas,
+    profile_id: ProfileId,
+    tenant_schema_file: Option<&str>,
+) -> Result<(), Error> {
+    let tenant_schema_file = tenant_schema_file
+        .ok_or_else(|| err_msg!(Input, "Missing 'tenant_schema_file' parameter"))?;
+    let definitions: String = fs::read_to_string(tenant_schema_file)
+        .map_err(err_map!(Backend, "Failed to read the tenant schema file"))?;
+
+    // The schema name is a validated identifier.
+    let definitions = definitions.replace("{schema}", &tenant_schemas.schema(profile_id));
+
+    execute_script(connection, &definitions)
+        .map_err(err_map!(Backend, "Failed to create the profile schema"))
+}
+
+/// Execute the statements of a SQL script in order.
+///
+/// The ODBC API does not support the execution of multiple statements in a
+/// single call, so the script is first split into its statements.
+fn execute_script(connection: &odbc_api::Connection<'_>, script: &str) -> Result<(), odbc_api::Error> {
+    for statement in split_statements(script) {
+        connection.execute(statement, ())?;
+    }
+    Ok(())
+}
+
+/// Split a SQL script into statements separated by `;`.
+///
+/// Separators within quoted strings and identifiers or within comments are
+/// ignored, and statements containing only comments are skipped.
+fn split_statements(script: &str) -> Vec<&str> {
+    let bytes = script.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_content = false;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            // A doubled quote within a string is handled as two adjacent strings.
+            quote @ (b'\'' | b'"') => {
+                has_content = true;
+                idx += 1;
+                while idx < bytes.len() && bytes[idx] != quote {
+                    idx += 1;
+                }
+            }
+            b'-' if bytes.get(idx + 1) == Some(&b'-') => {
+                while idx < bytes.len() && bytes[idx] != b'\n' {
+                    idx += 1;
+                }
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => {
+                idx += 2;
+                while idx < bytes.len() && !bytes[idx..].starts_with(b"*/") {
+                    idx += 1;
+                }
+                idx += 1;
+            }
+            b';' => {
+                if has_content {
+                    statements.push(script[start..idx].trim());
+                }
+                start = idx + 1;
+                has_content = false;
+            }
+            c if !c.is_ascii_whitespace() => {
+                has_content = true;
+            }
+            _ => (),
+        }
+        idx += 1;
+    }
+    if has_content {
+        statements.push(script[start..].trim());
+    }
+
+    statements
+}
+
+/// Remove the schema holding the records of a profile.
+pub(crate) fn drop_profile_schema(
+    connection: &odbc_api::Connection<'_>,
+    tenant_schemas: &TenantSchemas,
+    profile_id: ProfileId,
+) -> Result<(), Error> {
+    let schema = tenant_schemas.schema(profile_id);
+
+    for statement in [
+        format!("DROP TABLE {}.items_tags", schema),
+        format!("DROP TABLE {}.items", schema),
+        format!("DROP SCHEMA {} RESTRICT", schema),
+    ] {
+        connection.execute(&statement, ())
+            .map_err(err_map!(Backend, "Failed to remove the profile schema"))?;
+    }
+
+    Ok(())
+}
+
 impl<'a> ManageBackend<'a> for OdbcStoreOptions {
     type Backend = OdbcBackend;
 
@@ -403,6 +641,7 @@ mod tests {
             &max_lifetime=12\
             &max_connections=13\
             &min_connections=14\
+            &warm_connections=3\
             &schema_file=test.sql";
         let opts = OdbcStoreOptions::new(uri).unwrap();
 
@@ -411,7 +650,83 @@ mod tests {
         assert_eq!(opts.max_lifetime, Duration::from_secs(12));
         assert_eq!(opts.max_connections, 13);
         assert_eq!(opts.min_connections, 14);
-        assert_eq!(opts.connection_string, "test_connection_string");
+        assert_eq!(opts.warm_connections, 3);
+        assert_eq!(opts.connection_string.as_str(), "test_connection_string");
         assert_eq!(opts.schema_file, "test.sql");
+        assert_eq!(opts.tenant_isolation, TenantIsolation::Shared);
+    }
+
+    /// Ensure that the connection string is not included in debug output.
+    #[test]
+    fn odbc_options_debug_redacted() {
+        let opts = OdbcStoreOptions::new(
+            "odbc://Driver=db2;Uid=user;Pwd=secret?schema_file=test.sql").unwrap();
+        let output = format!("{:?}", opts);
+        assert!(output.contains("test.sql"));
+        assert!(!output.contains("secret"));
+        let manager = OdbcConnectionManager::new(opts.connection_string.as_str());
+        assert!(!format!("{:?}", manager).contains("secret"));
+    }
+
+    /// Ensure that the tenant isolation parameters are parsed correctly.
+    #[test]
+    fn odbc_parse_tenant_uri() {
+        let uri = "odbc://test_connection_string\
+            ?schema_file=test.sql\
+            &tenant_isolation=schema\
+            &tenant_schema_file=tenant.sql\
+            &tenant_schema_prefix=wallet";
+        let opts = OdbcStoreOptions::new(uri).unwrap();
+
+        assert_eq!(opts.tenant_isolation, TenantIsolation::Schema);
+        assert_eq!(opts.tenant_schema_file.as_deref(), Some("tenant.sql"));
+        assert_eq!(opts.tenant_schema_prefix, "wallet");
+
+        assert!(OdbcStoreOptions::new(
+            "odbc://test_connection_string?schema_file=test.sql&tenant_isolation=schema").is_err());
+        assert!(OdbcStoreOptions::new(
+            "odbc://test_connection_string?schema_file=test.sql&tenant_isolation=schema\
+            &tenant_schema_file=tenant.sql&tenant_schema_prefix=my-wallet").is_err());
+    }
+
+    /// Ensure that SQL scripts are split at the statement separators only.
+    #[test]
+    fn odbc_split_statements() {
+        let script = "-- The schema; with comments
+            CREATE TABLE config (name VARCHAR(10));
+            /* a block; comment */
+            INSERT INTO config VALUES ('a;b'), ('it''s;');
+            CREATE TABLE \"odd;name\" (id INT)
+            ;
+            -- trailing comment;
+            COMMIT";
+        assert_eq!(split_statements(script), vec![
+            "-- The schema; with comments
+            CREATE TABLE config (name VARCHAR(10))",
+            "/* a block; comment */
+            INSERT INTO config VALUES ('a;b'), ('it''s;')",
+            "CREATE TABLE \"odd;name\" (id INT)",
+            "-- trailing comment;
+            COMMIT",
+        ]);
+        assert!(split_statements(" ; -- only a comment\n").is_empty());
+    }
+
+    /// Ensure that options of the other backends and unknown options are rejected.
+    #[test]
+    fn odbc_unsupported_options() {
+        for name in [
+            "schema_compat",
+            "expiry_bucket",
+            "name_encryption",
+            "strict_key_resolution",
+            "key_cache_ttl",
+            "max_cached_profiles",
+            "unknown_option",
+        ] {
+            let uri = format!("odbc://test_connection_string?schema_file=test.sql&{}=1", name);
+            let err = OdbcStoreOptions::new(uri.as_str()).unwrap_err();
+            assert_eq!(err.kind(), crate::ErrorKind::Unsupported);
+        }
     }
 }
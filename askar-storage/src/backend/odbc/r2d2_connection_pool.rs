@@ -8,6 +8,7 @@ use odbc_api::{
 };
 
 use lazy_static::lazy_static;
+use zeroize::Zeroizing;
 
 // We create a static ODBC environment reference so that
 // it never goes out of scope.
@@ -16,10 +17,11 @@ lazy_static! {
 }
 
 /// Define the OdbcError type, which will handle mappings from
-/// an odbc_api::Error.
+/// an odbc_api::Error.  The error may be raised on the worker threads of the
+/// pool, and so must be thread-safe.
 ///
 #[derive(Debug)]
-pub struct OdbcError(Box<dyn Error>);
+pub struct OdbcError(Box<dyn Error + Send + Sync>);
 
 impl Error for OdbcError {
     fn description(&self) -> &str {
@@ -40,7 +42,9 @@ impl From<odbc_api::Error> for OdbcError {
 }
 
 /// Define the connection, which simply wraps an
-/// odbc_api::Connection object.
+/// odbc_api::Connection object.  The connection is Send but
+/// not Sync: it may move between threads, but is only ever
+/// used by the session which has checked it out of the pool.
 #[derive(Debug)]
 pub struct OdbcConnection<'a>(odbc_api::Connection<'a>);
 
@@ -54,9 +58,15 @@ impl <'a> OdbcConnection<'a> {
 /// pool of connections.  The r2d2::ManageConnection
 /// object is used to actually manage the pool of
 /// connections.
-#[derive(Debug)]
 pub struct OdbcConnectionManager {
-    connection_string: String
+    connection_string: Zeroizing<String>
+}
+
+impl fmt::Debug for OdbcConnectionManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the connection string may include the account password
+        f.debug_struct("OdbcConnectionManager").finish_non_exhaustive()
+    }
 }
 
 impl OdbcConnectionManager {
@@ -64,7 +74,7 @@ impl OdbcConnectionManager {
     pub fn new<S: Into<String>>(connection_string: S) -> OdbcConnectionManager
     {
         OdbcConnectionManager {
-            connection_string: connection_string.into()
+            connection_string: Zeroizing::new(connection_string.into())
         }
     }
 }
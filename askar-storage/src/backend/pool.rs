@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex, MutexGuard,
+};
+
+/// The number of recent acquisitions used to estimate latency percentiles
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+const LATENCY_SAMPLES: usize = 1024;
+
+/// A snapshot of the state of a backend connection pool
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// The number of open connections
+    pub size: u32,
+    /// The number of open connections which are not in use
+    pub idle: u32,
+    /// The number of connections currently in use
+    pub active: u32,
+    /// The maximum number of connections
+    pub max_size: u32,
+    /// The number of successful connection acquisitions
+    pub acquired: u64,
+    /// The number of acquisitions made while no idle connection was available
+    pub waits: u64,
+    /// The number of acquisitions which failed or timed out
+    pub failures: u64,
+    /// The median time taken to acquire a connection
+    pub acquire_p50: Option<Duration>,
+    /// The 95th percentile of the time taken to acquire a connection
+    pub acquire_p95: Option<Duration>,
+    /// The 99th percentile of the time taken to acquire a connection
+    pub acquire_p99: Option<Duration>,
+}
+
+/// Counters recorded for connection acquisitions from a pool
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+#[derive(Debug, Default)]
+pub(crate) struct PoolMetrics {
+    acquired: AtomicU64,
+    waits: AtomicU64,
+    failures: AtomicU64,
+    latencies: Mutex<LatencySamples>,
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+impl PoolMetrics {
+    /// Record the outcome of a connection acquisition
+    pub fn record(&self, latency: Duration, waited: bool, success: bool) {
+        if success {
+            self.acquired.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        if waited {
+            self.waits.fetch_add(1, Ordering::Relaxed);
+        }
+        self.latencies().push(latency);
+    }
+
+    /// Combine the recorded counters with the current state of the pool
+    pub fn status(&self, size: u32, idle: u32, max_size: u32) -> PoolStatus {
+        let mut latencies = self.latencies().samples.clone();
+        latencies.sort_unstable();
+        PoolStatus {
+            size,
+            idle,
+            active: size.saturating_sub(idle),
+            max_size,
+            acquired: self.acquired.load(Ordering::Relaxed),
+            waits: self.waits.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            acquire_p50: percentile(&latencies, 50),
+            acquire_p95: percentile(&latencies, 95),
+            acquire_p99: percentile(&latencies, 99),
+        }
+    }
+
+    // the samples remain usable if a thread panicked while holding the lock
+    fn latencies(&self) -> MutexGuard<'_, LatencySamples> {
+        self.latencies
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+#[derive(Debug, Default)]
+struct LatencySamples {
+    samples: Vec<Duration>,
+    next: usize,
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+impl LatencySamples {
+    fn push(&mut self, latency: Duration) {
+        if self.samples.len() < LATENCY_SAMPLES {
+            self.samples.push(latency);
+        } else {
+            self.samples[self.next] = latency;
+        }
+        self.next = (self.next + 1) % LATENCY_SAMPLES;
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+fn percentile(sorted: &[Duration], pct: usize) -> Option<Duration> {
+    if sorted.is_empty() {
+        None
+    } else {
+        let idx = (sorted.len() * pct).div_ceil(100);
+        Some(sorted[idx.saturating_sub(1).min(sorted.len() - 1)])
+    }
+}
+
+#[cfg(all(test, any(feature = "postgres", feature = "sqlite", feature = "odbc")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_metrics_status() {
+        let metrics = PoolMetrics::default();
+        assert_eq!(metrics.status(2, 2, 4).acquire_p50, None);
+        for ms in 1..=100 {
+            metrics.record(Duration::from_millis(ms), ms > 90, ms != 100);
+        }
+        let status = metrics.status(3, 1, 4);
+        assert_eq!((status.size, status.idle, status.active), (3, 1, 2));
+        assert_eq!(
+            (status.acquired, status.waits, status.failures),
+            (99, 10, 1)
+        );
+        assert_eq!(status.acquire_p50, Some(Duration::from_millis(50)));
+        assert_eq!(status.acquire_p95, Some(Duration::from_millis(95)));
+        assert_eq!(status.acquire_p99, Some(Duration::from_millis(99)));
+    }
+
+    #[test]
+    fn pool_metrics_poisoned() {
+        let metrics = PoolMetrics::default();
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _guard = metrics.latencies.lock().unwrap();
+                    panic!("poison the lock");
+                })
+                .join()
+                .unwrap_err();
+        });
+        assert!(metrics.latencies.is_poisoned());
+        metrics.record(Duration::from_millis(5), false, true);
+        assert_eq!(
+            metrics.status(1, 1, 1).acquire_p50,
+            Some(Duration::from_millis(5))
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
 
@@ -11,29 +11,40 @@ use futures_lite::{
 
 use sqlx::{
     pool::PoolConnection,
-    postgres::{PgPool, Postgres},
-    Acquire, Row,
+    postgres::{PgConnection, PgPool, Postgres},
+    Acquire, Error as SqlxError, Executor, Row,
 };
 
 use super::{
     db_utils::{
-        decode_tags, decrypt_scan_batch, encode_profile_key, encode_tag_filter, expiry_timestamp,
-        extend_query, prepare_tags, random_profile_name, replace_arg_placeholders, DbSession,
-        DbSessionActive, DbSessionRef, DbSessionTxn, EncScanEntry, ExtDatabase, QueryParams,
-        QueryPrepare, PAGE_SIZE,
+        acquire_conn, check_key_generation, decode_tags, decrypt_entry_change,
+        decrypt_scan_batch_parallel, decrypt_scan_batch_with_ids, encode_expiry,
+        encode_namespace_range, encode_profile_key, encode_tag_filter, encrypt_entry_batch,
+        encrypt_update, extend_query, is_expired, namespace_index_incomplete, pool_status,
+        prepare_chunked_rekey, prepare_kdf_upgrade, process_batch, random_profile_name,
+        reencrypt_change, reencrypt_item, reencrypt_tags, replace_arg_placeholders, ChunkedRekey,
+        CreatedProfileKey, DbSession, DbSessionActive, DbSessionRef, DbSessionTxn, EncEntryChange,
+        EncScanEntry, Expiry, ExtDatabase, QueryPage, QueryParams, QueryPrepare, SlowQueryLog,
+        StoreKeyConfig, IMPORT_BATCH_SIZE, PAGE_SIZE,
     },
-    Backend, BackendSession,
+    Backend, BackendSession, EntryChange, EntrySession, IndexProfile, PoolMetrics, PoolStatus,
+    ProfileExpiryPolicy, ProfileFilter, ProfileQuota, ProfileStats, Progress, ProgressFn,
+    ProgressPhase, RekeyProgress, TenantSchemas, TraceContext, VersionInfo,
 };
 use crate::{
     backend::OrderBy,
+    crypto::buffer::SecretBytes,
     entry::{EncEntryTag, Entry, EntryKind, EntryOperation, EntryTag, Scan, TagFilter},
-    error::Error,
+    error::{Error, ErrorKind},
     future::{unblock, BoxFuture},
-    protect::{EntryEncryptor, KeyCache, PassKey, ProfileId, ProfileKey, StoreKeyMethod},
+    protect::{
+        kdf::KdfMethod, EntryEncryptor, KeyCache, KeyCacheStats, PassKey, ProfileId, ProfileKey,
+        StoreKeyMethod, KEY_GENERATION_CONFIG, PENDING_KEY_CHECK_CONFIG, PENDING_KEY_CONFIG,
+    },
 };
 
 mod provision;
-pub use self::provision::PostgresStoreOptions;
+pub use self::provision::{supported_schema_version, PostgresStoreOptions};
 
 #[cfg(any(test, feature = "pg_test"))]
 mod test_db;
@@ -50,42 +61,111 @@ const COUNT_QUERY: &str = "SELECT COUNT(*) FROM items i
     AND (expiry IS NULL OR expiry > CURRENT_TIMESTAMP)";
 const DELETE_QUERY: &str = "DELETE FROM items
     WHERE profile_id = $1 AND kind = $2 AND category = $3 AND name = $4";
-const FETCH_QUERY: &str = "SELECT id, value,
+const FETCH_QUERY: &str = "SELECT id, value, enc_expiry,
     (SELECT ARRAY_TO_STRING(ARRAY_AGG(it.plaintext || ':'
         || ENCODE(it.name, 'hex') || ':' || ENCODE(it.value, 'hex')), ',')
         FROM items_tags it WHERE it.item_id = i.id) tags
     FROM items i
     WHERE profile_id = $1 AND kind = $2 AND category = $3 AND name = $4
     AND (expiry IS NULL OR expiry > CURRENT_TIMESTAMP)";
-const FETCH_QUERY_UPDATE: &str = "SELECT id, value,
+const FETCH_QUERY_UPDATE: &str = "SELECT id, value, enc_expiry,
     (SELECT ARRAY_TO_STRING(ARRAY_AGG(it.plaintext || ':'
         || ENCODE(it.name, 'hex') || ':' || ENCODE(it.value, 'hex')), ',')
         FROM items_tags it WHERE it.item_id = i.id) tags
     FROM items i
     WHERE profile_id = $1 AND kind = $2 AND category = $3 AND name = $4
     AND (expiry IS NULL OR expiry > CURRENT_TIMESTAMP) FOR NO KEY UPDATE";
-const INSERT_QUERY: &str = "INSERT INTO items (profile_id, kind, category, name, value, expiry)
-    VALUES ($1, $2, $3, $4, $5, $6)
+const FETCH_VALUE_QUERY: &str = "SELECT id, value, enc_expiry FROM items i
+    WHERE profile_id = $1 AND kind = $2 AND category = $3 AND name = $4
+    AND (expiry IS NULL OR expiry > CURRENT_TIMESTAMP)";
+const FETCH_VALUE_QUERY_UPDATE: &str = "SELECT id, value, enc_expiry FROM items i
+    WHERE profile_id = $1 AND kind = $2 AND category = $3 AND name = $4
+    AND (expiry IS NULL OR expiry > CURRENT_TIMESTAMP) FOR NO KEY UPDATE";
+const INSERT_QUERY: &str = "INSERT INTO items
+    (profile_id, kind, category, name, value, expiry, namespace, enc_names, enc_expiry)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
     ON CONFLICT DO NOTHING RETURNING id";
-const UPDATE_QUERY: &str = "UPDATE items SET value=$5, expiry=$6
-    WHERE profile_id=$1 AND kind=$2 AND category=$3 AND name=$4
+const UPDATE_QUERY: &str = "UPDATE items SET value=$5, expiry=$6, namespace=$7, enc_names=$8,
+    enc_expiry=$9 WHERE profile_id=$1 AND kind=$2 AND category=$3 AND name=$4
     RETURNING id";
+const PROFILE_STATS_QUERY: &str = "SELECT
+    COALESCE(SUM(CASE WHEN i.kind = $2 THEN 1 ELSE 0 END), 0),
+    COALESCE(SUM(CASE WHEN i.kind = $3 THEN 1 ELSE 0 END), 0),
+    COALESCE(SUM(CASE WHEN i.expiry <= CURRENT_TIMESTAMP THEN 1 ELSE 0 END), 0),
+    COALESCE(SUM(OCTET_LENGTH(i.value)), 0),
+    COALESCE(SUM(OCTET_LENGTH(i.category) + OCTET_LENGTH(i.name)), 0)
+    FROM items i WHERE i.profile_id = $1";
+const PROFILE_TAG_STATS_QUERY: &str = "SELECT
+    COUNT(*), COALESCE(SUM(OCTET_LENGTH(it.name) + OCTET_LENGTH(it.value)), 0)
+    FROM items_tags it JOIN items i ON i.id = it.item_id WHERE i.profile_id = $1";
+const PROFILE_ALIASES_QUERY: &str = "SELECT a.alias FROM profile_aliases a
+    JOIN profiles p ON p.id = a.profile_id
+    WHERE p.name = $1 ORDER BY a.alias";
+const PROFILE_ALIAS_KEY_QUERY: &str = "SELECT p.id, p.name, p.profile_key
+    FROM profile_aliases a JOIN profiles p ON p.id = a.profile_id
+    WHERE a.alias = $1 FOR NO KEY UPDATE OF p";
+const EXPIRY_POLICY_FETCH_QUERY: &str = "SELECT expiry_policy FROM profiles WHERE id = $1";
+// the profile row is locked, so that it cannot be archived before the write
+// is committed. Writes update the usage counters of the profile in any case
+const PROFILE_ARCHIVED_QUERY: &str =
+    "SELECT archived FROM profiles WHERE id = $1 FOR NO KEY UPDATE";
+const QUOTA_FETCH_QUERY: &str =
+    "SELECT archived, max_entries, max_value_bytes FROM profiles WHERE id = $1
+    FOR NO KEY UPDATE";
+const PROFILE_USAGE_QUERY: &str = "SELECT p.max_entries, p.max_value_bytes,
+    p.entry_count, p.value_bytes, (SELECT OCTET_LENGTH(i.value)::BIGINT FROM items i
+        WHERE i.profile_id = p.id AND i.kind = $2 AND i.category = $3 AND i.name = $4)
+    FROM profiles p WHERE p.id = $1 FOR NO KEY UPDATE OF p";
 const SCAN_QUERY: &str = "SELECT id, kind, category, name, value,
     (SELECT ARRAY_TO_STRING(ARRAY_AGG(it.plaintext || ':'
         || ENCODE(it.name, 'hex') || ':' || ENCODE(it.value, 'hex')), ',')
-        FROM items_tags it WHERE it.item_id = i.id) tags
+        FROM items_tags it WHERE it.item_id = i.id) tags, enc_names
     FROM items i WHERE profile_id = $1
     AND (kind = $2 OR $2 IS NULL)
     AND (category = $3 OR $3 IS NULL)
     AND (expiry IS NULL OR expiry > CURRENT_TIMESTAMP)";
+const COUNT_NAMESPACE_QUERY: &str = "SELECT COUNT(*) FROM items i
+    WHERE profile_id = $1
+    AND (kind = $2 OR $2 IS NULL)
+    AND namespace >= $3 AND (namespace < $4 OR $4 IS NULL)
+    AND (expiry IS NULL OR expiry > CURRENT_TIMESTAMP)";
+const NAMESPACE_INCOMPLETE_QUERY: &str = "SELECT EXISTS(SELECT 1 FROM items
+    WHERE profile_id = $1 AND namespace IS NULL)";
+const SCAN_NAMESPACE_QUERY: &str = "SELECT id, kind, category, name, value,
+    (SELECT ARRAY_TO_STRING(ARRAY_AGG(it.plaintext || ':'
+        || ENCODE(it.name, 'hex') || ':' || ENCODE(it.value, 'hex')), ',')
+        FROM items_tags it WHERE it.item_id = i.id) tags, enc_names
+    FROM items i WHERE profile_id = $1
+    AND (kind = $2 OR $2 IS NULL)
+    AND namespace >= $3 AND (namespace < $4 OR $4 IS NULL)
+    AND (expiry IS NULL OR expiry > CURRENT_TIMESTAMP)";
 const DELETE_ALL_QUERY: &str = "DELETE FROM items i
     WHERE profile_id = $1
     AND (kind = $2 OR $2 IS NULL)
     AND (category = $3 OR $3 IS NULL)";
-const TAG_INSERT_QUERY: &str = "INSERT INTO items_tags
-    (item_id, name, value, plaintext) VALUES ($1, $2, $3, $4)";
+const TAG_INSERT_QUERY: &str = "INSERT INTO items_tags (item_id, name, value, plaintext)
+    SELECT $1, * FROM UNNEST($2::BYTEA[], $3::BYTEA[], $4::SMALLINT[])";
+const TAG_UPDATE_QUERY: &str = "UPDATE items_tags it SET name = u.name, value = u.value
+    FROM UNNEST($1::BIGINT[], $2::BYTEA[], $3::BYTEA[]) AS u(id, name, value)
+    WHERE it.id = u.id";
 const TAG_DELETE_QUERY: &str = "DELETE FROM items_tags
     WHERE item_id=$1";
+const TAG_REENCRYPT_BATCH: i64 = 512;
+const CHANGES_QUERY: &str = "SELECT c.seq, p.name, c.kind, c.category, c.name, i.value,
+    (SELECT ARRAY_TO_STRING(ARRAY_AGG(it.plaintext || ':'
+        || ENCODE(it.name, 'hex') || ':' || ENCODE(it.value, 'hex')), ',')
+        FROM items_tags it WHERE it.item_id = i.id) tags, c.enc_names, i.enc_expiry
+    FROM item_changes c
+    JOIN profiles p ON p.id = c.profile_id
+    LEFT JOIN items i ON i.profile_id = c.profile_id AND i.kind = c.kind
+        AND i.category = c.category AND i.name = c.name
+        AND (i.expiry IS NULL OR i.expiry > CURRENT_TIMESTAMP)
+    WHERE c.seq > $1 AND c.seq <= $2
+    ORDER BY c.seq LIMIT $3";
+const PRUNE_CHANGES_QUERY: &str = "DELETE FROM item_changes WHERE seq <= $1
+    AND NOT EXISTS (SELECT 1 FROM items i WHERE i.profile_id = item_changes.profile_id
+        AND i.kind = item_changes.kind AND i.category = item_changes.category
+        AND i.name = item_changes.name)";
 
 /// A PostgreSQL database store
 pub struct PostgresBackend {
@@ -94,6 +174,12 @@ pub struct PostgresBackend {
     key_cache: Arc<KeyCache>,
     host: String,
     name: String,
+    page_size: usize,
+    pool_metrics: Arc<PoolMetrics>,
+    slow_query_log: SlowQueryLog,
+    tenant_schemas: Option<TenantSchemas>,
+    tenant_index_profile: IndexProfile,
+    auto_create_profiles: bool,
 }
 
 impl PostgresBackend {
@@ -103,6 +189,8 @@ impl PostgresBackend {
         key_cache: KeyCache,
         host: String,
         name: String,
+        page_size: usize,
+        slow_query_log: SlowQueryLog,
     ) -> Self {
         Self {
             conn_pool,
@@ -110,6 +198,98 @@ impl PostgresBackend {
             key_cache: Arc::new(key_cache),
             host,
             name,
+            page_size,
+            pool_metrics: Arc::default(),
+            slow_query_log,
+            tenant_schemas: None,
+            tenant_index_profile: IndexProfile::default(),
+            auto_create_profiles: false,
+        }
+    }
+
+    /// Keep the records of each profile in a separate schema
+    pub(crate) fn with_tenant_schemas(
+        mut self,
+        tenant_schemas: TenantSchemas,
+        index_profile: IndexProfile,
+    ) -> Self {
+        self.tenant_schemas = Some(tenant_schemas);
+        self.tenant_index_profile = index_profile;
+        self
+    }
+
+    /// Create missing profiles when a session is opened against them
+    pub(crate) fn with_auto_create_profiles(mut self, auto_create: bool) -> Result<Self, Error> {
+        if auto_create && self.tenant_schemas.is_some() {
+            return Err(err_msg!(
+                Unsupported,
+                "Automatic profile creation is not supported for stores with per-profile schemas"
+            ));
+        }
+        self.auto_create_profiles = auto_create;
+        Ok(self)
+    }
+
+    /// Scan the records matching a category or a category namespace
+    ///
+    /// Each record is passed to `output` along with its row identifier.
+    #[allow(clippy::too_many_arguments)]
+    async fn scan_records<T: Send + 'static>(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+        namespace: Option<String>,
+        tag_filter: Option<TagFilter>,
+        after: Option<i64>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+        output: fn(i64, Entry) -> T,
+    ) -> Result<Scan<'static, T>, Error> {
+        let session = self.session(profile, false)?;
+        let mut active = session.owned_ref();
+        let (profile_id, key) = acquire_key(&mut active).await?;
+        let scan = perform_scan(
+            active,
+            profile_id,
+            key.clone(),
+            kind,
+            category.clone(),
+            namespace,
+            tag_filter,
+            after,
+            offset,
+            limit,
+            order_by,
+            descending,
+            false,
+            self.page_size,
+        );
+        let stream = scan.then(move |enc_rows| {
+            let category = category.clone();
+            let key = key.clone();
+            async move {
+                let rows =
+                    decrypt_scan_batch_with_ids(profile_id, category, enc_rows?, key).await?;
+                Ok(rows
+                    .into_iter()
+                    .map(|(id, entry)| output(id, entry))
+                    .collect())
+            }
+        });
+        Ok(Scan::new(stream, self.page_size).prefetch())
+    }
+
+    fn check_change_tracking(&self) -> Result<(), Error> {
+        if self.tenant_schemas.is_some() {
+            Err(err_msg!(
+                Unsupported,
+                "Change tracking is not supported for stores with per-profile schemas"
+            ))
+        } else {
+            Ok(())
         }
     }
 }
@@ -121,21 +301,46 @@ impl Backend for PostgresBackend {
         let name = name.unwrap_or_else(random_profile_name);
         Box::pin(async move {
             let store_key = self.key_cache.store_key.clone();
+            let (cipher, name_encryption, expiry_bucket) = (
+                self.key_cache.profile_cipher,
+                self.key_cache.name_encryption,
+                self.key_cache.expiry_bucket,
+            );
             let (profile_key, enc_key) = unblock(move || {
-                let profile_key = ProfileKey::new()?;
+                let profile_key =
+                    ProfileKey::new_with_options(cipher, name_encryption, expiry_bucket)?;
                 let enc_key = encode_profile_key(&profile_key, &store_key)?;
                 Result::<_, Error>::Ok((profile_key, enc_key))
             })
             .await?;
-            let mut conn = self.conn_pool.acquire().await?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
+            if self.tenant_schemas.is_some() {
+                // the profile schema references the profiles table, so concurrent
+                // creation of the same profile would otherwise deadlock
+                txn.execute("LOCK TABLE profiles IN SHARE ROW EXCLUSIVE MODE")
+                    .await?;
+            }
             let res = sqlx::query_scalar(
                 "INSERT INTO profiles (name, profile_key) VALUES ($1, $2) 
                 ON CONFLICT DO NOTHING RETURNING id",
             )
             .bind(&name)
             .bind(enc_key)
-            .fetch_optional(conn.as_mut())
+            .fetch_optional(txn.as_mut())
             .await?;
+            // checked once any concurrent replacement of the store key has completed
+            verify_key_generation(txn.as_mut(), &self.key_cache).await?;
+            if let (Some(pid), Some(tenant_schemas)) = (res, self.tenant_schemas.as_ref()) {
+                provision::init_profile_schema(
+                    txn.as_mut(),
+                    tenant_schemas,
+                    pid,
+                    self.tenant_index_profile,
+                )
+                .await?;
+            }
+            txn.commit().await?;
             conn.return_to_pool().await;
             if let Some(pid) = res {
                 self.key_cache
@@ -154,7 +359,7 @@ impl Backend for PostgresBackend {
 
     fn get_default_profile(&self) -> BoxFuture<'_, Result<String, Error>> {
         Box::pin(async move {
-            let mut conn = self.conn_pool.acquire().await?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
             let profile: Option<String> = sqlx::query_scalar(CONFIG_FETCH_QUERY)
                 .bind("default_profile")
                 .fetch_one(conn.as_mut())
@@ -167,13 +372,21 @@ impl Backend for PostgresBackend {
 
     fn set_default_profile(&self, profile: String) -> BoxFuture<'_, Result<(), Error>> {
         Box::pin(async move {
-            let mut conn = self.conn_pool.acquire().await?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
             sqlx::query(CONFIG_UPDATE_QUERY)
                 .bind("default_profile")
                 .bind(profile)
-                .execute(conn.as_mut())
+                .execute(txn.as_mut())
                 .await
                 .map_err(err_map!(Backend, "Error setting default profile name"))?;
+            provision::update_config_mac(
+                txn.as_mut(),
+                &self.key_cache.store_key,
+                self.key_cache.key_generation,
+            )
+            .await?;
+            txn.commit().await?;
             conn.return_to_pool().await;
             Ok(())
         })
@@ -181,7 +394,7 @@ impl Backend for PostgresBackend {
 
     fn list_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
         Box::pin(async move {
-            let mut conn = self.conn_pool.acquire().await?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
             let rows = sqlx::query("SELECT name FROM profiles")
                 .fetch_all(conn.as_mut())
                 .await
@@ -192,14 +405,368 @@ impl Backend for PostgresBackend {
         })
     }
 
+    fn list_profiles_page(
+        &self,
+        offset: i64,
+        limit: i64,
+    ) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let names =
+                sqlx::query_scalar("SELECT name FROM profiles ORDER BY name LIMIT $2 OFFSET $1")
+                    .bind(offset.max(0))
+                    .bind(limit.max(0))
+                    .fetch_all(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching profile list"))?;
+            conn.return_to_pool().await;
+            Ok(names)
+        })
+    }
+
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
         Box::pin(async move {
-            let mut conn = self.conn_pool.acquire().await?;
-            let ret = sqlx::query("DELETE FROM profiles WHERE name=$1")
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
+            let removed: Option<ProfileId> =
+                sqlx::query_scalar("DELETE FROM profiles WHERE name=$1 RETURNING id")
+                    .bind(&name)
+                    .fetch_optional(txn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error removing profile"))?;
+            if let (Some(pid), Some(tenant_schemas)) = (removed, self.tenant_schemas.as_ref()) {
+                provision::drop_profile_schema(txn.as_mut(), tenant_schemas, pid).await?;
+            }
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            let ret = removed.is_some();
+            self.key_cache.remove_profile(&name).await;
+            Ok(ret)
+        })
+    }
+
+    fn remove_profiles<'a>(
+        &'a self,
+        filter: ProfileFilter,
+        batch_size: usize,
+        progress: Option<&'a ProgressFn>,
+    ) -> BoxFuture<'a, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            filter.validate()?;
+            let batch_size = batch_size.max(1) as i64;
+            let mut removed = Vec::new();
+            let mut last_id: ProfileId = 0;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            loop {
+                let mut txn = conn.begin().await?;
+                let rows = sqlx::query(
+                    "SELECT id, name, metadata FROM profiles
+                    WHERE id > $1 AND name != $2
+                    AND ($3::text IS NULL OR LEFT(name, LENGTH($3)) = $3)
+                    ORDER BY id LIMIT $4 FOR UPDATE",
+                )
+                .bind(last_id)
+                .bind(&self.active_profile)
+                .bind(filter.name_prefix.as_deref())
+                .bind(batch_size)
+                .fetch_all(txn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching profile list"))?;
+                if rows.is_empty() {
+                    break;
+                }
+                let mut batch = Vec::with_capacity(rows.len());
+                for row in rows {
+                    last_id = row.try_get(0)?;
+                    let metadata: Option<String> = row.try_get(2)?;
+                    if filter.matches_metadata(metadata.as_deref()) {
+                        batch.push((last_id, row.try_get::<String, _>(1)?));
+                    }
+                }
+                for (pid, _) in &batch {
+                    sqlx::query("DELETE FROM profiles WHERE id = $1")
+                        .bind(pid)
+                        .execute(txn.as_mut())
+                        .await
+                        .map_err(err_map!(Backend, "Error removing profile"))?;
+                    if let Some(tenant_schemas) = self.tenant_schemas.as_ref() {
+                        provision::drop_profile_schema(txn.as_mut(), tenant_schemas, *pid).await?;
+                    }
+                }
+                txn.commit().await?;
+                for (_, name) in batch {
+                    self.key_cache.remove_profile(&name).await;
+                    removed.push(name);
+                }
+                Progress {
+                    phase: ProgressPhase::Remove,
+                    profile: None,
+                    processed: removed.len() as i64,
+                    total: None,
+                }
+                .report(progress);
+            }
+            conn.return_to_pool().await;
+            Ok(removed)
+        })
+    }
+
+    fn rename_profile(&self, from: String, to: String) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async move {
+            if from == self.active_profile {
+                return Err(err_msg!(Input, "Cannot rename the active profile"));
+            }
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
+            let exists: Option<ProfileId> =
+                sqlx::query_scalar("SELECT id FROM profiles WHERE name = $1")
+                    .bind(&to)
+                    .fetch_optional(txn.as_mut())
+                    .await?;
+            if exists.is_some() {
+                return Err(err_msg!(Duplicate, "Duplicate profile name"));
+            }
+            let renamed = sqlx::query("UPDATE profiles SET name = $2 WHERE name = $1")
+                .bind(&from)
+                .bind(&to)
+                .execute(txn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error renaming profile"))?
+                .rows_affected()
+                != 0;
+            if renamed {
+                sqlx::query(
+                    "UPDATE config SET value = $2 WHERE name = 'default_profile' AND value = $1",
+                )
+                .bind(&from)
+                .bind(&to)
+                .execute(txn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error updating default profile name"))?;
+                provision::update_config_mac(
+                    txn.as_mut(),
+                    &self.key_cache.store_key,
+                    self.key_cache.key_generation,
+                )
+                .await?;
+            }
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            self.key_cache.remove_profile(&from).await;
+            Ok(renamed)
+        })
+    }
+
+    fn get_profile_metadata(&self, name: String) -> BoxFuture<'_, Result<Option<String>, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let metadata: Option<Option<String>> =
+                sqlx::query_scalar("SELECT metadata FROM profiles WHERE name = $1")
+                    .bind(&name)
+                    .fetch_optional(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching profile metadata"))?;
+            conn.return_to_pool().await;
+            metadata.ok_or_else(|| err_msg!(NotFound, "Profile not found"))
+        })
+    }
+
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<String>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let updated = sqlx::query("UPDATE profiles SET metadata = $2 WHERE name = $1")
                 .bind(&name)
+                .bind(metadata)
                 .execute(conn.as_mut())
                 .await
-                .map_err(err_map!(Backend, "Error removing profile"))?
+                .map_err(err_map!(Backend, "Error updating profile metadata"))?
+                .rows_affected()
+                != 0;
+            conn.return_to_pool().await;
+            if updated {
+                Ok(())
+            } else {
+                Err(err_msg!(NotFound, "Profile not found"))
+            }
+        })
+    }
+
+    fn get_profile_quota(&self, name: String) -> BoxFuture<'_, Result<ProfileQuota, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let quota: Option<(Option<i64>, Option<i64>)> =
+                sqlx::query_as("SELECT max_entries, max_value_bytes FROM profiles WHERE name = $1")
+                    .bind(&name)
+                    .fetch_optional(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching profile quota"))?;
+            conn.return_to_pool().await;
+            let (max_entries, max_value_bytes) =
+                quota.ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            Ok(ProfileQuota {
+                max_entries,
+                max_value_bytes,
+            })
+        })
+    }
+
+    fn set_profile_quota(
+        &self,
+        name: String,
+        quota: ProfileQuota,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            quota.validate()?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let updated = sqlx::query(
+                "UPDATE profiles SET max_entries = $2, max_value_bytes = $3 WHERE name = $1",
+            )
+            .bind(&name)
+            .bind(quota.max_entries)
+            .bind(quota.max_value_bytes)
+            .execute(conn.as_mut())
+            .await
+            .map_err(err_map!(Backend, "Error updating profile quota"))?
+            .rows_affected()
+                != 0;
+            conn.return_to_pool().await;
+            if updated {
+                Ok(())
+            } else {
+                Err(err_msg!(NotFound, "Profile not found"))
+            }
+        })
+    }
+
+    fn get_profile_expiry_policy(
+        &self,
+        name: String,
+    ) -> BoxFuture<'_, Result<ProfileExpiryPolicy, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let policy: Option<Option<String>> =
+                sqlx::query_scalar("SELECT expiry_policy FROM profiles WHERE name = $1")
+                    .bind(&name)
+                    .fetch_optional(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching profile expiry policy"))?;
+            conn.return_to_pool().await;
+            let policy = policy.ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            ProfileExpiryPolicy::from_json(policy.as_deref())
+        })
+    }
+
+    fn set_profile_expiry_policy(
+        &self,
+        name: String,
+        policy: ProfileExpiryPolicy,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            policy.validate()?;
+            let policy = policy.to_json()?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let updated = sqlx::query("UPDATE profiles SET expiry_policy = $2 WHERE name = $1")
+                .bind(&name)
+                .bind(policy)
+                .execute(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error updating profile expiry policy"))?
+                .rows_affected()
+                != 0;
+            conn.return_to_pool().await;
+            if updated {
+                Ok(())
+            } else {
+                Err(err_msg!(NotFound, "Profile not found"))
+            }
+        })
+    }
+
+    fn set_profile_archived(
+        &self,
+        name: String,
+        archived: bool,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let updated = sqlx::query("UPDATE profiles SET archived = $2 WHERE name = $1")
+                .bind(&name)
+                .bind(archived)
+                .execute(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error updating profile"))?
+                .rows_affected()
+                != 0;
+            conn.return_to_pool().await;
+            if updated {
+                Ok(())
+            } else {
+                Err(err_msg!(NotFound, "Profile not found"))
+            }
+        })
+    }
+
+    fn list_archived_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let names =
+                sqlx::query_scalar("SELECT name FROM profiles WHERE archived ORDER BY name")
+                    .fetch_all(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching profile list"))?;
+            conn.return_to_pool().await;
+            Ok(names)
+        })
+    }
+
+    fn add_profile_alias(&self, name: String, alias: String) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
+            let pid: ProfileId =
+                sqlx::query_scalar("SELECT id FROM profiles WHERE name = $1 FOR NO KEY UPDATE")
+                    .bind(&name)
+                    .fetch_optional(txn.as_mut())
+                    .await?
+                    .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            let exists: Option<ProfileId> =
+                sqlx::query_scalar("SELECT id FROM profiles WHERE name = $1")
+                    .bind(&alias)
+                    .fetch_optional(txn.as_mut())
+                    .await?;
+            if exists.is_some() {
+                return Err(err_msg!(Duplicate, "Duplicate profile name"));
+            }
+            let done = sqlx::query(
+                "INSERT INTO profile_aliases (alias, profile_id) VALUES ($1, $2)
+                ON CONFLICT DO NOTHING",
+            )
+            .bind(&alias)
+            .bind(pid)
+            .execute(txn.as_mut())
+            .await
+            .map_err(err_map!(Backend, "Error adding profile alias"))?;
+            if done.rows_affected() == 0 {
+                return Err(err_msg!(Duplicate, "Duplicate profile alias"));
+            }
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            Ok(())
+        })
+    }
+
+    fn remove_profile_alias(&self, alias: String) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let ret = sqlx::query("DELETE FROM profile_aliases WHERE alias = $1")
+                .bind(&alias)
+                .execute(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error removing profile alias"))?
                 .rows_affected()
                 != 0;
             conn.return_to_pool().await;
@@ -207,6 +774,58 @@ impl Backend for PostgresBackend {
         })
     }
 
+    fn list_profile_aliases(&self, name: String) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let aliases = sqlx::query_scalar(PROFILE_ALIASES_QUERY)
+                .bind(&name)
+                .fetch_all(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching profile aliases"))?;
+            conn.return_to_pool().await;
+            Ok(aliases)
+        })
+    }
+
+    fn profile_stats(&self, name: String) -> BoxFuture<'_, Result<ProfileStats, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let pid: ProfileId = sqlx::query_scalar("SELECT id FROM profiles WHERE name = $1")
+                .bind(&name)
+                .fetch_optional(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching profile"))?
+                .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            if let Some(tenant_schemas) = self.tenant_schemas.as_ref() {
+                let schema = tenant_schemas.schema(pid);
+                Postgres::set_profile_schema(conn.as_mut(), &schema, tenant_schemas.prefix())
+                    .await?;
+            }
+            let (items, keys, expired, value_bytes, entry_bytes) =
+                sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(PROFILE_STATS_QUERY)
+                    .bind(pid)
+                    .bind(EntryKind::Item as i16)
+                    .bind(EntryKind::Kms as i16)
+                    .fetch_one(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching profile statistics"))?;
+            let (tags, tag_bytes) = sqlx::query_as::<_, (i64, i64)>(PROFILE_TAG_STATS_QUERY)
+                .bind(pid)
+                .fetch_one(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching profile statistics"))?;
+            conn.return_to_pool().await;
+            Ok(ProfileStats {
+                items,
+                keys,
+                expired,
+                tags,
+                value_bytes,
+                storage_bytes: entry_bytes + value_bytes + tag_bytes,
+            })
+        })
+    }
+
     fn rekey(
         &mut self,
         method: StoreKeyMethod,
@@ -216,8 +835,9 @@ impl Backend for PostgresBackend {
         Box::pin(async move {
             let (store_key, store_key_ref) = unblock(move || method.resolve(pass_key)).await?;
             let store_key = Arc::new(store_key);
-            let mut conn = self.conn_pool.acquire().await?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
             let mut txn = conn.begin().await?;
+            verify_key_generation(txn.as_mut(), &self.key_cache).await?;
             let mut rows = sqlx::query("SELECT id, profile_key FROM profiles").fetch(txn.as_mut());
             let mut upd_keys = BTreeMap::<ProfileId, Vec<u8>>::new();
             while let Some(row) = rows.next().await {
@@ -234,12 +854,14 @@ impl Backend for PostgresBackend {
             }
             drop(rows);
             for (pid, key) in upd_keys {
-                if sqlx::query("UPDATE profiles SET profile_key=$1 WHERE id=$2")
-                    .bind(key)
-                    .bind(pid)
-                    .execute(txn.as_mut())
-                    .await?
-                    .rows_affected()
+                if sqlx::query(
+                    "UPDATE profiles SET profile_key=$1, profile_key_pending=NULL WHERE id=$2",
+                )
+                .bind(key)
+                .bind(pid)
+                .execute(txn.as_mut())
+                .await?
+                .rows_affected()
                     != 1
                 {
                     return Err(err_msg!(Backend, "Error updating profile key"));
@@ -254,13 +876,503 @@ impl Backend for PostgresBackend {
             {
                 return Err(err_msg!(Backend, "Error updating store key"));
             }
+            // discard any incomplete chunked re-key
+            sqlx::query("DELETE FROM config WHERE name IN ($1, $2)")
+                .bind(PENDING_KEY_CONFIG)
+                .bind(PENDING_KEY_CHECK_CONFIG)
+                .execute(txn.as_mut())
+                .await?;
+            let key_cache = self.key_cache.with_store_key(store_key).await;
+            sqlx::query(CONFIG_UPDATE_QUERY)
+                .bind(KEY_GENERATION_CONFIG)
+                .bind(key_cache.key_generation.to_string())
+                .execute(txn.as_mut())
+                .await?;
+            provision::update_config_mac(
+                txn.as_mut(),
+                &key_cache.store_key,
+                key_cache.key_generation,
+            )
+            .await?;
             txn.commit().await?;
             conn.return_to_pool().await;
-            self.key_cache = Arc::new(KeyCache::new(store_key));
+            self.key_cache = Arc::new(key_cache);
             Ok(())
         })
     }
 
+    fn rekey_chunked<'a>(
+        &'a mut self,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'a>,
+        chunk_size: usize,
+        progress: &'a mut (dyn FnMut(RekeyProgress) -> Result<(), Error> + Send),
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        let pass_key = pass_key.into_owned();
+        Box::pin(async move {
+            let chunk_size = chunk_size.max(1) as i64;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            verify_key_generation(conn.as_mut(), &self.key_cache).await?;
+            let config =
+                sqlx::query_as::<_, (String, Option<String>)>("SELECT name, value FROM config")
+                    .fetch_all(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching store configuration"))?;
+            let ChunkedRekey {
+                store_key,
+                store_key_ref,
+                new_key_check,
+            } = prepare_chunked_rekey(StoreKeyConfig::from_rows(config), method, pass_key).await?;
+            if let Some(key_check) = new_key_check {
+                let mut txn = conn.begin().await?;
+                for (name, value) in [
+                    (PENDING_KEY_CONFIG, &store_key_ref),
+                    (PENDING_KEY_CHECK_CONFIG, &key_check),
+                ] {
+                    sqlx::query(CONFIG_UPDATE_QUERY)
+                        .bind(name)
+                        .bind(value)
+                        .execute(txn.as_mut())
+                        .await?;
+                }
+                txn.commit().await?;
+            }
+
+            let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM profiles")
+                .fetch_one(conn.as_mut())
+                .await?;
+            let mut completed: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM profiles WHERE profile_key_pending IS NOT NULL",
+            )
+            .fetch_one(conn.as_mut())
+            .await?;
+            loop {
+                let rows = sqlx::query(
+                    "SELECT id, profile_key FROM profiles WHERE profile_key_pending IS NULL
+                    ORDER BY id LIMIT $1",
+                )
+                .bind(chunk_size)
+                .fetch_all(conn.as_mut())
+                .await?
+                .into_iter()
+                .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+                .collect::<Result<Vec<(ProfileId, Vec<u8>)>, Error>>()?;
+                if rows.is_empty() {
+                    // replace the profile keys, unless profiles were created concurrently
+                    let mut txn = conn.begin().await?;
+                    // prevent the creation of profiles until the transaction completes
+                    txn.execute("LOCK TABLE profiles IN SHARE ROW EXCLUSIVE MODE")
+                        .await?;
+                    verify_key_generation(txn.as_mut(), &self.key_cache).await?;
+                    sqlx::query(CONFIG_UPDATE_QUERY)
+                        .bind("key")
+                        .bind(&store_key_ref)
+                        .execute(txn.as_mut())
+                        .await?;
+                    let remaining: i64 = sqlx::query_scalar(
+                        "SELECT COUNT(*) FROM profiles WHERE profile_key_pending IS NULL",
+                    )
+                    .fetch_one(txn.as_mut())
+                    .await?;
+                    if remaining > 0 {
+                        txn.rollback().await?;
+                        continue;
+                    }
+                    sqlx::query(
+                        "UPDATE profiles
+                        SET profile_key = profile_key_pending, profile_key_pending = NULL",
+                    )
+                    .execute(txn.as_mut())
+                    .await?;
+                    sqlx::query("DELETE FROM config WHERE name IN ($1, $2)")
+                        .bind(PENDING_KEY_CONFIG)
+                        .bind(PENDING_KEY_CHECK_CONFIG)
+                        .execute(txn.as_mut())
+                        .await?;
+                    sqlx::query(CONFIG_UPDATE_QUERY)
+                        .bind(KEY_GENERATION_CONFIG)
+                        .bind((self.key_cache.key_generation + 1).to_string())
+                        .execute(txn.as_mut())
+                        .await?;
+                    provision::update_config_mac(
+                        txn.as_mut(),
+                        &store_key,
+                        self.key_cache.key_generation + 1,
+                    )
+                    .await?;
+                    txn.commit().await?;
+                    break;
+                }
+
+                let prev_keys = rows.iter().map(|(_, key)| key.clone()).collect::<Vec<_>>();
+                let keys = self
+                    .key_cache
+                    .rewrap_profile_keys(store_key.clone(), rows)
+                    .await?;
+                let mut txn = conn.begin().await?;
+                for ((pid, key), prev_key) in keys.into_iter().zip(prev_keys) {
+                    // skip profiles which were re-keyed concurrently
+                    completed += sqlx::query(
+                        "UPDATE profiles SET profile_key_pending = $1
+                        WHERE id = $2 AND profile_key = $3",
+                    )
+                    .bind(key)
+                    .bind(pid)
+                    .bind(prev_key)
+                    .execute(txn.as_mut())
+                    .await?
+                    .rows_affected() as i64;
+                }
+                txn.commit().await?;
+                progress(RekeyProgress {
+                    completed,
+                    total: total.max(completed),
+                })?;
+            }
+            conn.return_to_pool().await;
+            self.key_cache = Arc::new(self.key_cache.with_store_key(store_key).await);
+            Ok(())
+        })
+    }
+
+    fn upgrade_kdf(
+        &mut self,
+        method: KdfMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>> {
+        let pass_key = pass_key.into_owned();
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
+            // prevent the creation or re-keying of profiles until the transaction completes
+            txn.execute("LOCK TABLE profiles IN SHARE ROW EXCLUSIVE MODE")
+                .await?;
+            let config = sqlx::query_as::<_, (String, Option<String>)>(
+                "SELECT name, value FROM config FOR UPDATE",
+            )
+            .fetch_all(txn.as_mut())
+            .await
+            .map_err(err_map!(Backend, "Error fetching store configuration"))?;
+            let upgrade = match prepare_kdf_upgrade(
+                &self.key_cache,
+                StoreKeyConfig::from_rows(config),
+                method,
+                pass_key,
+            )
+            .await?
+            {
+                Some(upgrade) => upgrade,
+                None => return Ok(false),
+            };
+            let rows = sqlx::query("SELECT id, profile_key FROM profiles")
+                .fetch_all(txn.as_mut())
+                .await?
+                .into_iter()
+                .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+                .collect::<Result<Vec<(ProfileId, Vec<u8>)>, Error>>()?;
+            let keys = self
+                .key_cache
+                .rewrap_profile_keys(upgrade.store_key.clone(), rows)
+                .await?;
+            for (pid, key) in keys {
+                sqlx::query("UPDATE profiles SET profile_key = $1 WHERE id = $2")
+                    .bind(key)
+                    .bind(pid)
+                    .execute(txn.as_mut())
+                    .await?;
+            }
+            let key_cache = self.key_cache.with_store_key(upgrade.store_key).await;
+            sqlx::query(CONFIG_UPDATE_QUERY)
+                .bind("key")
+                .bind(upgrade.store_key_ref)
+                .execute(txn.as_mut())
+                .await?;
+            sqlx::query(CONFIG_UPDATE_QUERY)
+                .bind(KEY_GENERATION_CONFIG)
+                .bind(key_cache.key_generation.to_string())
+                .execute(txn.as_mut())
+                .await?;
+            provision::update_config_mac(
+                txn.as_mut(),
+                &key_cache.store_key,
+                key_cache.key_generation,
+            )
+            .await?;
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            self.key_cache = Arc::new(key_cache);
+            Ok(true)
+        })
+    }
+
+    fn rekey_profile(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
+            let row =
+                sqlx::query("SELECT id, profile_key FROM profiles WHERE name = $1 FOR UPDATE")
+                    .bind(&name)
+                    .fetch_optional(txn.as_mut())
+                    .await?
+                    .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            verify_key_generation(txn.as_mut(), &self.key_cache).await?;
+            let pid: ProfileId = row.try_get(0)?;
+            if let Some(tenant_schemas) = self.tenant_schemas.as_ref() {
+                let schema = tenant_schemas.schema(pid);
+                Postgres::set_profile_schema(txn.as_mut(), &schema, tenant_schemas.prefix())
+                    .await?;
+            }
+            let old_key = Arc::new(self.key_cache.load_key(row.try_get(1)?).await?);
+            let new_key = Arc::new(ProfileKey::new_with_options(
+                old_key.cipher(),
+                old_key.name_encryption(),
+                old_key.expiry_bucket(),
+            )?);
+            let enc_key = encode_profile_key(&new_key, &self.key_cache.store_key)?;
+
+            let items = sqlx::query(
+                "SELECT id, kind, category, name, value, enc_names, enc_expiry FROM items
+                WHERE profile_id = $1",
+            )
+            .bind(pid)
+            .fetch_all(txn.as_mut())
+            .await?
+            .into_iter()
+            .map(|row| {
+                let kind: i16 = row.try_get(1)?;
+                Ok((
+                    row.try_get(0)?,
+                    EntryKind::try_from(kind as usize)?,
+                    row.try_get(2)?,
+                    row.try_get(3)?,
+                    row.try_get(4)?,
+                    row.try_get(5)?,
+                    row.try_get(6)?,
+                ))
+            })
+            .collect::<Result<
+                Vec<(
+                    i64,
+                    EntryKind,
+                    Vec<u8>,
+                    Vec<u8>,
+                    Vec<u8>,
+                    Option<Vec<u8>>,
+                    Option<Vec<u8>>,
+                )>,
+                Error,
+            >>()?;
+            let items = unblock({
+                let (old_key, new_key) = (old_key.clone(), new_key.clone());
+                move || {
+                    items
+                        .into_iter()
+                        .map(|(id, kind, category, name, value, enc_names, enc_expiry)| {
+                            Ok((
+                                id,
+                                reencrypt_item(
+                                    &old_key, &new_key, pid, kind, category, name, value,
+                                    enc_names, enc_expiry,
+                                )?,
+                            ))
+                        })
+                        .collect::<Result<Vec<_>, Error>>()
+                }
+            })
+            .await?;
+            for (id, (category, name, value, namespace, enc_names, enc_expiry)) in items {
+                sqlx::query(
+                    "UPDATE items SET category = $1, name = $2, value = $3, namespace = $4,
+                    enc_names = $5, enc_expiry = $6 WHERE id = $7",
+                )
+                .bind(category)
+                .bind(name)
+                .bind(value)
+                .bind(namespace)
+                .bind(enc_names)
+                .bind(enc_expiry)
+                .bind(id)
+                .execute(txn.as_mut())
+                .await?;
+            }
+
+            reencrypt_profile_changes(txn.as_mut(), pid, old_key.clone(), new_key.clone()).await?;
+            reencrypt_profile_tags(txn.as_mut(), pid, old_key, new_key.clone()).await?;
+
+            sqlx::query(
+                "UPDATE profiles SET profile_key = $1, profile_key_pending = NULL WHERE id = $2",
+            )
+            .bind(enc_key)
+            .bind(pid)
+            .execute(txn.as_mut())
+            .await?;
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            self.key_cache.add_profile(name, pid, new_key).await;
+            Ok(())
+        })
+    }
+
+    fn rotate_tag_key(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
+            let row =
+                sqlx::query("SELECT id, profile_key FROM profiles WHERE name = $1 FOR UPDATE")
+                    .bind(&name)
+                    .fetch_optional(txn.as_mut())
+                    .await?
+                    .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            verify_key_generation(txn.as_mut(), &self.key_cache).await?;
+            let pid: ProfileId = row.try_get(0)?;
+            if let Some(tenant_schemas) = self.tenant_schemas.as_ref() {
+                let schema = tenant_schemas.schema(pid);
+                Postgres::set_profile_schema(txn.as_mut(), &schema, tenant_schemas.prefix())
+                    .await?;
+            }
+            let old_key = Arc::new(self.key_cache.load_key(row.try_get(1)?).await?);
+            let new_key = Arc::new(old_key.with_new_tags_hmac_key()?);
+            let enc_key = encode_profile_key(&new_key, &self.key_cache.store_key)?;
+            reencrypt_profile_tags(txn.as_mut(), pid, old_key, new_key.clone()).await?;
+            sqlx::query(
+                "UPDATE profiles SET profile_key = $1, profile_key_pending = NULL WHERE id = $2",
+            )
+            .bind(enc_key)
+            .bind(pid)
+            .execute(txn.as_mut())
+            .await?;
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            self.key_cache.add_profile(name, pid, new_key).await;
+            Ok(())
+        })
+    }
+
+    fn version_info(&self) -> BoxFuture<'_, Result<VersionInfo, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let info = provision::fetch_version_info(conn.as_mut()).await?;
+            conn.return_to_pool().await;
+            Ok(info)
+        })
+    }
+
+    fn change_sequence(&self) -> BoxFuture<'_, Result<i64, Error>> {
+        Box::pin(async move {
+            self.check_change_tracking()?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let mut txn = conn.begin().await?;
+            // wait for concurrent writes to commit, so that no lower sequence
+            // number can become visible afterwards
+            txn.execute("LOCK TABLE item_changes IN SHARE MODE")
+                .await
+                .map_err(err_map!(Backend, "Error locking change sequence"))?;
+            let seq = sqlx::query_scalar(
+                "SELECT CASE WHEN is_called THEN last_value ELSE 0 END FROM item_changes_seq_seq",
+            )
+            .fetch_one(txn.as_mut())
+            .await
+            .map_err(err_map!(Backend, "Error fetching change sequence"))?;
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            Ok(seq)
+        })
+    }
+
+    fn scan_changes(
+        &self,
+        since: i64,
+        until: i64,
+        limit: i64,
+    ) -> BoxFuture<'_, Result<Vec<EntryChange>, Error>> {
+        Box::pin(async move {
+            self.check_change_tracking()?;
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let rows = sqlx::query(CHANGES_QUERY)
+                .bind(since)
+                .bind(until)
+                .bind(limit)
+                .fetch_all(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching changed records"))?;
+            let mut keys = HashMap::new();
+            let mut changes = Vec::with_capacity(rows.len());
+            for row in rows {
+                let profile: String = row.try_get(1)?;
+                let (profile_id, key) = match keys.get(&profile) {
+                    Some((profile_id, key)) => (*profile_id, Arc::clone(key)),
+                    None => {
+                        let (profile_id, key) = resolve_profile_key(
+                            &mut conn,
+                            self.key_cache.clone(),
+                            profile.clone(),
+                            false,
+                        )
+                        .await?;
+                        keys.insert(profile.clone(), (profile_id, key.clone()));
+                        (profile_id, key)
+                    }
+                };
+                let kind: i16 = row.try_get(2)?;
+                let change = EncEntryChange {
+                    seq: row.try_get(0)?,
+                    profile,
+                    profile_id,
+                    kind: EntryKind::try_from(kind as usize)?,
+                    category: row.try_get(3)?,
+                    name: row.try_get(4)?,
+                    value: row.try_get(5)?,
+                    tags: row
+                        .try_get::<Option<String>, _>(6)?
+                        .map(String::into_bytes)
+                        .unwrap_or_default(),
+                    enc_names: row.try_get(7)?,
+                    enc_expiry: row.try_get(8)?,
+                };
+                changes.push((change, key));
+            }
+            conn.return_to_pool().await;
+            process_batch(changes, |(change, key)| decrypt_entry_change(change, &key)).await
+        })
+    }
+
+    fn prune_changes(&self, until: i64) -> BoxFuture<'_, Result<i64, Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            let done = sqlx::query(PRUNE_CHANGES_QUERY)
+                .bind(until)
+                .execute(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error pruning removed records"))?;
+            conn.return_to_pool().await;
+            Ok(done.rows_affected() as i64)
+        })
+    }
+
+    fn key_cache_stats(&self) -> BoxFuture<'_, Option<KeyCacheStats>> {
+        Box::pin(async move { Some(self.key_cache.stats().await) })
+    }
+
+    fn invalidate_profile_key(&self, name: String) -> BoxFuture<'_, ()> {
+        Box::pin(async move { self.key_cache.remove_profile(&name).await })
+    }
+
+    fn pool_status(&self) -> Option<PoolStatus> {
+        Some(pool_status(&self.conn_pool, &self.pool_metrics))
+    }
+
+    fn check_pool(&self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = acquire_conn(&self.conn_pool, &self.pool_metrics).await?;
+            conn.return_to_pool().await;
+            Ok(())
+        })
+    }
+
+    fn backend_kind(&self) -> &'static str {
+        "postgres"
+    }
+
     fn scan(
         &self,
         profile: Option<String>,
@@ -272,39 +1384,84 @@ impl Backend for PostgresBackend {
         order_by: Option<OrderBy>,
         descending: bool,
     ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
-        Box::pin(async move {
-            let session = self.session(profile, false)?;
-            let mut active = session.owned_ref();
-            let (profile_id, key) = acquire_key(&mut active).await?;
-            let scan = perform_scan(
-                active,
-                profile_id,
-                key.clone(),
-                kind,
-                category.clone(),
-                tag_filter,
-                offset,
-                limit,
-                order_by,
-                descending,
-                false,
-            );
-            let stream = scan.then(move |enc_rows| {
-                let category = category.clone();
-                let key = key.clone();
-                unblock(move || decrypt_scan_batch(category, enc_rows?, &key))
-            });
-            Ok(Scan::new(stream, PAGE_SIZE))
-        })
+        Box::pin(self.scan_records(
+            profile,
+            kind,
+            category,
+            None,
+            tag_filter,
+            None,
+            offset,
+            limit,
+            order_by,
+            descending,
+            |_, entry| entry,
+        ))
+    }
+
+    fn scan_after(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        after: Option<i64>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, (i64, Entry)>, Error>> {
+        Box::pin(self.scan_records(
+            profile,
+            kind,
+            category,
+            None,
+            tag_filter,
+            after,
+            offset,
+            limit,
+            Some(OrderBy::Id),
+            descending,
+            |id, entry| (id, entry),
+        ))
+    }
+
+    fn scan_namespace(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        namespace: String,
+        tag_filter: Option<TagFilter>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+    ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
+        Box::pin(self.scan_records(
+            profile,
+            kind,
+            None,
+            Some(namespace),
+            tag_filter,
+            None,
+            offset,
+            limit,
+            order_by,
+            descending,
+            |_, entry| entry,
+        ))
     }
 
     fn session(&self, profile: Option<String>, transaction: bool) -> Result<Self::Session, Error> {
         Ok(DbSession::new(
             self.conn_pool.clone(),
+            self.pool_metrics.clone(),
             self.key_cache.clone(),
             profile.unwrap_or_else(|| self.active_profile.clone()),
             transaction,
-        ))
+            &self.slow_query_log,
+        )
+        .with_tenant_schemas(self.tenant_schemas.as_ref())
+        .with_auto_create_profile(self.auto_create_profiles))
     }
 
     fn close(&self) -> BoxFuture<'_, Result<(), Error>> {
@@ -325,109 +1482,116 @@ impl Debug for PostgresBackend {
     }
 }
 
-impl BackendSession for DbSession<Postgres> {
-    fn count<'q>(
+impl EntrySession for DbSession<Postgres> {
+    async fn fetch_entry<'q>(
         &'q mut self,
-        kind: Option<EntryKind>,
-        category: Option<&'q str>,
-        tag_filter: Option<TagFilter>,
-    ) -> BoxFuture<'q, Result<i64, Error>> {
-        let enc_category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        for_update: bool,
+    ) -> Result<Option<Entry>, Error> {
+        perform_fetch(
+            self,
+            kind,
+            category.to_string(),
+            name.to_string(),
+            for_update,
+            true,
+        )
+        .await
+    }
 
-        Box::pin(async move {
-            let (profile_id, key) = acquire_key(&mut *self).await?;
-            let mut params = QueryParams::new();
-            params.push(profile_id);
-            params.push(kind.map(|k| k as i16));
-            let (enc_category, tag_filter) = unblock({
-                let params_len = params.len() + 1; // plus category
-                move || {
+    async fn update_entry<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let plain_category = category;
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+
+        match operation {
+            op @ EntryOperation::Insert | op @ EntryOperation::Replace => {
+                perform_update(
+                    self,
+                    kind,
+                    plain_category,
+                    category,
+                    name,
+                    value.unwrap_or_default(),
+                    tags,
+                    expiry_ms,
+                    op == EntryOperation::Insert,
+                    true,
+                )
+                .await
+            }
+
+            EntryOperation::Remove => {
+                let (_, key) = acquire_key(&mut *self).await?;
+                let (enc_category, enc_name) = unblock(move || {
                     Result::<_, Error>::Ok((
-                        enc_category
-                            .map(|c| key.encrypt_entry_category(c))
-                            .transpose()?,
-                        encode_tag_filter::<PostgresBackend>(tag_filter, &key, params_len)?,
+                        key.encrypt_entry_category(category)?,
+                        key.encrypt_entry_name(name)?,
                     ))
-                }
-            })
-            .await?;
-            params.push(enc_category);
-            let query = extend_query::<PostgresBackend>(
-                COUNT_QUERY,
-                &mut params,
-                tag_filter,
-                None,
-                None,
-                None,
-                false,
-            )?;
-            let mut active = acquire_session(&mut *self).await?;
-            let count = sqlx::query_scalar_with(query.as_str(), params)
-                .fetch_one(active.connection_mut())
-                .await
-                .map_err(err_map!(Backend, "Error performing count query"))?;
-            Ok(count)
-        })
+                })
+                .await?;
+                let mut active = acquire_session(&mut *self).await?;
+                perform_remove(&mut active, kind, &enc_category, &enc_name, false).await
+            }
+        }
+    }
+}
+
+impl BackendSession for DbSession<Postgres> {
+    fn count<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        Box::pin(perform_count(self, kind, category, None, tag_filter))
     }
 
-    fn fetch(
-        &mut self,
+    fn count_namespace<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        namespace: &'q str,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        Box::pin(perform_count(self, kind, None, Some(namespace), tag_filter))
+    }
+
+    fn fetch<'q>(
+        &'q mut self,
         kind: EntryKind,
-        category: &str,
-        name: &str,
+        category: &'q str,
+        name: &'q str,
         for_update: bool,
-    ) -> BoxFuture<'_, Result<Option<Entry>, Error>> {
-        let category = category.to_string();
-        let name = name.to_string();
+    ) -> BoxFuture<'q, Result<Option<Entry>, Error>> {
+        Box::pin(self.fetch_entry(kind, category, name, for_update))
+    }
 
-        Box::pin(async move {
-            let (profile_id, key) = acquire_key(&mut *self).await?;
-            let (enc_category, enc_name) = unblock({
-                let key = key.clone();
-                let category = ProfileKey::prepare_input(category.as_bytes());
-                let name = ProfileKey::prepare_input(name.as_bytes());
-                move || {
-                    Result::<_, Error>::Ok((
-                        key.encrypt_entry_category(category)?,
-                        key.encrypt_entry_name(name)?,
-                    ))
-                }
-            })
-            .await?;
-            let mut active = acquire_session(&mut *self).await?;
-            if let Some(row) = sqlx::query(if for_update && active.in_transaction() {
-                FETCH_QUERY_UPDATE
-            } else {
-                FETCH_QUERY
-            })
-            .bind(profile_id)
-            .bind(kind as i16)
-            .bind(enc_category)
-            .bind(enc_name)
-            .fetch_optional(active.connection_mut())
-            .await
-            .map_err(err_map!(Backend, "Error performing fetch query"))?
-            {
-                let value = row.try_get(1)?;
-                let tags = row.try_get::<Option<String>, _>(2)?.map(String::into_bytes);
-                let (category, name, value, tags) = unblock(move || {
-                    let value = key.decrypt_entry_value(category.as_ref(), name.as_ref(), value)?;
-                    let tags = if let Some(enc_tags) = tags {
-                        key.decrypt_entry_tags(
-                            decode_tags(enc_tags)
-                                .map_err(|_| err_msg!(Unexpected, "Error decoding tags"))?,
-                        )?
-                    } else {
-                        Vec::new()
-                    };
-                    Result::<_, Error>::Ok((category, name, value, tags))
-                })
-                .await?;
-                Ok(Some(Entry::new(kind, category, name, value, tags)))
-            } else {
-                Ok(None)
-            }
-        })
+    fn fetch_without_tags<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        for_update: bool,
+    ) -> BoxFuture<'q, Result<Option<Entry>, Error>> {
+        Box::pin(perform_fetch(
+            self,
+            kind,
+            category.to_string(),
+            name.to_string(),
+            for_update,
+            false,
+        ))
     }
 
     fn fetch_all<'q>(
@@ -451,19 +1615,64 @@ impl BackendSession for DbSession<Postgres> {
                 key.clone(),
                 kind,
                 category.clone(),
+                None,
                 tag_filter,
                 None,
+                None,
                 limit,
                 order_by,
                 descending,
                 for_update,
+                PAGE_SIZE,
             );
             pin!(scan);
             let mut enc_rows = vec![];
             while let Some(rows) = scan.try_next().await? {
-                enc_rows.extend(rows)
+                enc_rows.extend(rows.into_iter().map(|(_, row)| row))
             }
-            unblock(move || decrypt_scan_batch(category, enc_rows, &key)).await
+            decrypt_scan_batch_parallel(profile_id, category, enc_rows, key).await
+        })
+    }
+
+    fn import_scan<'q>(
+        &'q mut self,
+        mut scan: Scan<'q, Entry>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key_for_update(&mut *self).await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let mut txn = active.as_transaction().await?;
+            let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+            loop {
+                let rows = scan.fetch_next().await?;
+                let done = rows.is_none();
+                batch.extend(rows.into_iter().flatten());
+                if batch.len() >= IMPORT_BATCH_SIZE || (done && !batch.is_empty()) {
+                    let enc_rows =
+                        encrypt_entry_batch(batch.split_off(0), profile_id, key.clone()).await?;
+                    for enc in enc_rows {
+                        perform_insert(
+                            &mut txn,
+                            enc.kind,
+                            &enc.category,
+                            &enc.name,
+                            &enc.value,
+                            &enc.namespace,
+                            enc.enc_names.as_deref(),
+                            Some(enc.tags),
+                            None,
+                            None,
+                            true,
+                        )
+                        .await?;
+                    }
+                }
+                if done {
+                    break;
+                }
+            }
+            txn.commit().await?;
+            Ok(())
         })
     }
 
@@ -497,18 +1706,22 @@ impl BackendSession for DbSession<Postgres> {
                 DELETE_ALL_QUERY,
                 &mut params,
                 tag_filter,
-                None,
-                None,
-                None,
-                false,
+                QueryPage::default(),
             )?;
 
             let mut active = acquire_session(&mut *self).await?;
-            let removed = sqlx::query_with(query.as_str(), params)
-                .execute(active.connection_mut())
-                .await?
-                .rows_affected();
-            Ok(removed as i64)
+            let mut txn = active.as_transaction().await?;
+            check_archived(&mut txn).await?;
+            let start = txn.slow_query_log().start();
+            let query = txn.annotate(&query);
+            let removed = sqlx::query_with(&query, params)
+                .persistent(!txn.is_traced())
+                .execute(txn.connection_mut())
+                .await;
+            txn.slow_query_log().finish("DELETE_ALL_QUERY", start);
+            let removed = removed?.rows_affected() as i64;
+            txn.commit().await?;
+            Ok(removed)
         })
     }
 
@@ -522,64 +1735,55 @@ impl BackendSession for DbSession<Postgres> {
         tags: Option<&'q [EntryTag]>,
         expiry_ms: Option<i64>,
     ) -> BoxFuture<'q, Result<(), Error>> {
-        let category = ProfileKey::prepare_input(category.as_bytes());
-        let name = ProfileKey::prepare_input(name.as_bytes());
-
-        match operation {
-            op @ EntryOperation::Insert | op @ EntryOperation::Replace => {
-                let value = ProfileKey::prepare_input(value.unwrap_or_default());
-                let tags = tags.map(prepare_tags);
-                Box::pin(async move {
-                    let (_, key) = acquire_key(&mut *self).await?;
-                    let (enc_category, enc_name, enc_value, enc_tags) = unblock(move || {
-                        let enc_value =
-                            key.encrypt_entry_value(category.as_ref(), name.as_ref(), value)?;
-                        Result::<_, Error>::Ok((
-                            key.encrypt_entry_category(category)?,
-                            key.encrypt_entry_name(name)?,
-                            enc_value,
-                            tags.transpose()?
-                                .map(|t| key.encrypt_entry_tags(t))
-                                .transpose()?,
-                        ))
-                    })
-                    .await?;
-                    let mut active = acquire_session(&mut *self).await?;
-                    let mut txn = active.as_transaction().await?;
-                    perform_insert(
-                        &mut txn,
-                        kind,
-                        &enc_category,
-                        &enc_name,
-                        &enc_value,
-                        enc_tags,
-                        expiry_ms,
-                        op == EntryOperation::Insert,
-                    )
-                    .await?;
-                    txn.commit().await?;
-                    Ok(())
-                })
-            }
+        Box::pin(self.update_entry(kind, operation, category, name, value, tags, expiry_ms))
+    }
 
-            EntryOperation::Remove => Box::pin(async move {
-                let (_, key) = acquire_key(&mut *self).await?;
-                let (enc_category, enc_name) = unblock(move || {
-                    Result::<_, Error>::Ok((
-                        key.encrypt_entry_category(category)?,
-                        key.encrypt_entry_name(name)?,
-                    ))
-                })
-                .await?;
-                let mut active = acquire_session(&mut *self).await?;
-                perform_remove(&mut active, kind, &enc_category, &enc_name, false).await
-            }),
+    fn update_unencrypted<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: &'q [u8],
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        if operation == EntryOperation::Remove {
+            return Box::pin(std::future::ready(Err(err_msg!(
+                Input,
+                "Unencrypted values may only be inserted or replaced"
+            ))));
         }
+        Box::pin(perform_update(
+            self,
+            kind,
+            category,
+            ProfileKey::prepare_input(category.as_bytes()),
+            ProfileKey::prepare_input(name.as_bytes()),
+            value,
+            tags,
+            expiry_ms,
+            operation == EntryOperation::Insert,
+            false,
+        ))
+    }
+
+    fn set_trace_context(&mut self, trace: Option<TraceContext>) {
+        DbSession::set_trace_context(self, trace)
+    }
+
+    fn take_profile_created(&mut self) -> bool {
+        DbSession::take_profile_created(self)
     }
 
     fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
         Box::pin(async move {
-            let mut sess = acquire_session(&mut *self).await?;
+            let auto_create = self.auto_create_profile();
+            let mut sess = match acquire_session(&mut *self).await {
+                // the profile is created by the first update of the session
+                Err(err) if auto_create && err.kind() == ErrorKind::NotFound => return Ok(()),
+                result => result?,
+            };
             if sess.in_transaction() {
                 // the profile row is locked, perform a typical ping
                 sqlx::Connection::ping(sess.connection_mut())
@@ -604,7 +1808,20 @@ impl BackendSession for DbSession<Postgres> {
     }
 }
 
-impl ExtDatabase for Postgres {}
+impl ExtDatabase for Postgres {
+    fn set_profile_schema<'c>(
+        conn: &'c mut PgConnection,
+        schema: &'c str,
+        shared_schema: &'c str,
+    ) -> BoxFuture<'c, Result<(), SqlxError>> {
+        Box::pin(async move {
+            // NB: schema names are validated identifiers
+            conn.execute(format!(r#"SET search_path TO "{schema}", "{shared_schema}""#).as_str())
+                .await?;
+            Ok(())
+        })
+    }
+}
 
 impl QueryPrepare for PostgresBackend {
     type DB = Postgres;
@@ -646,13 +1863,24 @@ async fn acquire_session(
     session.make_active(&resolve_profile_key).await
 }
 
+async fn acquire_key_for_update(
+    session: &mut DbSession<Postgres>,
+) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
+    session
+        .make_active_for_update(&resolve_profile_key, &create_session_profile)
+        .await?;
+    Ok(session.profile_and_key().unwrap())
+}
+
 async fn resolve_profile_key(
     conn: &mut PoolConnection<Postgres>,
     cache: Arc<KeyCache>,
     profile: String,
     in_txn: bool,
 ) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
-    if let Some((pid, key)) = cache.get_profile(profile.as_str()).await {
+    if cache.strict {
+        resolve_profile_key_strict(conn, cache, profile).await
+    } else if let Some((pid, key)) = cache.get_profile(profile.as_str()).await {
         if in_txn {
             // lock the profile row to prevent it from being removed
             let check: Option<i64> =
@@ -675,11 +1903,340 @@ async fn resolve_profile_key(
         let key = Arc::new(cache.load_key(row.try_get(1)?).await?);
         cache.add_profile(profile, pid, key.clone()).await;
         Ok((pid, key))
+    } else if let Some(row) = sqlx::query(PROFILE_ALIAS_KEY_QUERY)
+        .bind(profile.as_str())
+        .fetch_optional(conn.as_mut())
+        .await?
+    {
+        // keys are cached under the profile name, so that they are not retained
+        // after the alias is removed
+        let name: String = row.try_get(1)?;
+        if let Some((pid, key)) = cache.get_profile(name.as_str()).await {
+            Ok((pid, key))
+        } else {
+            let pid = row.try_get(0)?;
+            let key = Arc::new(cache.load_key(row.try_get(2)?).await?);
+            cache.add_profile(name, pid, key.clone()).await;
+            Ok((pid, key))
+        }
     } else {
         Err(err_msg!(NotFound, "Profile not found"))
     }
 }
 
+/// Resolve a profile key from the database rather than the cache, failing when
+/// the profile is ambiguous or its key cannot be loaded
+async fn resolve_profile_key_strict(
+    conn: &mut PoolConnection<Postgres>,
+    cache: Arc<KeyCache>,
+    profile: String,
+) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
+    let mut rows: Vec<(ProfileId, String, Vec<u8>)> = sqlx::query_as(
+        "SELECT id, name, profile_key FROM profiles WHERE name=$1 FOR NO KEY UPDATE",
+    )
+    .bind(profile.as_str())
+    .fetch_all(conn.as_mut())
+    .await
+    .map_err(err_map!(Backend, "Error fetching profile key"))?;
+    rows.extend(
+        sqlx::query_as(PROFILE_ALIAS_KEY_QUERY)
+            .bind(profile.as_str())
+            .fetch_all(conn.as_mut())
+            .await
+            .map_err(err_map!(Backend, "Error fetching profile key"))?,
+    );
+    cache.resolve_profile_strict(rows).await
+}
+
+/// Check that the store key of this instance is current before wrapping a
+/// profile key or authenticating the store configuration under it
+async fn verify_key_generation(conn: &mut PgConnection, cache: &KeyCache) -> Result<(), Error> {
+    let recorded = sqlx::query_scalar::<_, Option<String>>(CONFIG_FETCH_QUERY)
+        .bind(KEY_GENERATION_CONFIG)
+        .fetch_optional(conn)
+        .await
+        .map_err(err_map!(Backend, "Error fetching store key generation"))?
+        .flatten();
+    check_key_generation(cache.key_generation, recorded.as_deref())
+}
+
+async fn create_session_profile(
+    conn: &mut PoolConnection<Postgres>,
+    cache: Arc<KeyCache>,
+    profile: String,
+    in_txn: bool,
+) -> Result<CreatedProfileKey, Error> {
+    let store_key = cache.store_key.clone();
+    let (cipher, name_encryption, expiry_bucket) = (
+        cache.profile_cipher,
+        cache.name_encryption,
+        cache.expiry_bucket,
+    );
+    let (profile_key, enc_key) = unblock(move || {
+        let profile_key = ProfileKey::new_with_options(cipher, name_encryption, expiry_bucket)?;
+        let enc_key = encode_profile_key(&profile_key, &store_key)?;
+        Result::<_, Error>::Ok((profile_key, enc_key))
+    })
+    .await?;
+    let created: Option<ProfileId> = sqlx::query_scalar(
+        "INSERT INTO profiles (name, profile_key) VALUES ($1, $2)
+        ON CONFLICT DO NOTHING RETURNING id",
+    )
+    .bind(profile.as_str())
+    .bind(enc_key)
+    .fetch_optional(conn.as_mut())
+    .await
+    .map_err(err_map!(Backend, "Error creating profile"))?;
+    if created.is_some() {
+        verify_key_generation(conn.as_mut(), &cache).await?;
+    }
+    let (pid, key) = if let Some(pid) = created {
+        (pid, Arc::new(profile_key))
+    } else {
+        // the profile was created by another session
+        let row =
+            sqlx::query("SELECT id, profile_key FROM profiles WHERE name=$1 FOR NO KEY UPDATE")
+                .bind(profile.as_str())
+                .fetch_one(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching profile key"))?;
+        (
+            row.try_get(0)?,
+            Arc::new(cache.load_key(row.try_get(1)?).await?),
+        )
+    };
+    if !in_txn {
+        // within a transaction, the profile is discarded if the transaction is rolled back
+        cache.add_profile(profile, pid, key.clone()).await;
+    }
+    Ok((pid, key, created.is_some()))
+}
+
+async fn perform_count(
+    session: &mut DbSession<Postgres>,
+    kind: Option<EntryKind>,
+    category: Option<&str>,
+    namespace: Option<&str>,
+    tag_filter: Option<TagFilter>,
+) -> Result<i64, Error> {
+    let enc_category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+    let namespace = namespace.map(str::to_string);
+    let (profile_id, key) = acquire_key(&mut *session).await?;
+    let mut params = QueryParams::with_copy(session.slow_query_log().explain());
+    params.push(profile_id);
+    params.push(kind.map(|k| k as i16));
+    let (enc_category, namespace_range, tag_filter) = unblock({
+        // plus the category or the namespace bounds
+        let params_len = params.len() + if namespace.is_some() { 2 } else { 1 };
+        move || {
+            Result::<_, Error>::Ok((
+                enc_category
+                    .map(|c| key.encrypt_entry_category(c))
+                    .transpose()?,
+                namespace
+                    .map(|ns| encode_namespace_range(&key, &ns))
+                    .transpose()?,
+                encode_tag_filter::<PostgresBackend>(tag_filter, &key, params_len)?,
+            ))
+        }
+    })
+    .await?;
+    let by_namespace = namespace_range.is_some();
+    let (statement_id, statement) = if let Some((lower, upper)) = namespace_range {
+        params.push(lower);
+        params.push(upper);
+        ("COUNT_NAMESPACE_QUERY", COUNT_NAMESPACE_QUERY)
+    } else {
+        params.push(enc_category);
+        ("COUNT_QUERY", COUNT_QUERY)
+    };
+    let query =
+        extend_query::<PostgresBackend>(statement, &mut params, tag_filter, QueryPage::default())?;
+    let explain = params.take_copy();
+    let mut active = acquire_session(&mut *session).await?;
+    if by_namespace {
+        check_namespace_index(active.connection_mut(), profile_id).await?;
+    }
+    let start = active.slow_query_log().start();
+    let statement = active.annotate(&query);
+    let count = sqlx::query_scalar_with(&statement, params)
+        .persistent(!active.is_traced())
+        .fetch_one(active.connection_mut())
+        .await;
+    let slow = active.slow_query_log().finish(statement_id, start);
+    if let (true, Ok(_), Some(explain)) = (slow, &count, explain) {
+        let plan = fetch_query_plan(
+            active.connection_mut(),
+            &format!("EXPLAIN {}", query),
+            explain,
+        )
+        .await;
+        active.slow_query_log().log_plan(statement_id, plan);
+    }
+    count.map_err(err_map!(Backend, "Error performing count query"))
+}
+
+/// Ensure that the category namespace index covers every record of a profile
+async fn check_namespace_index(
+    conn: &mut PgConnection,
+    profile_id: ProfileId,
+) -> Result<(), Error> {
+    let incomplete: bool = sqlx::query_scalar(NAMESPACE_INCOMPLETE_QUERY)
+        .bind(profile_id)
+        .fetch_one(conn)
+        .await
+        .map_err(err_map!(
+            Backend,
+            "Error checking the category namespace index"
+        ))?;
+    if incomplete {
+        Err(namespace_index_incomplete())
+    } else {
+        Ok(())
+    }
+}
+
+async fn perform_fetch(
+    session: &mut DbSession<Postgres>,
+    kind: EntryKind,
+    category: String,
+    name: String,
+    for_update: bool,
+    tags: bool,
+) -> Result<Option<Entry>, Error> {
+    let (profile_id, key) = acquire_key(&mut *session).await?;
+    let (enc_category, enc_name) = unblock({
+        let key = key.clone();
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+        move || {
+            Result::<_, Error>::Ok((
+                key.encrypt_entry_category(category)?,
+                key.encrypt_entry_name(name)?,
+            ))
+        }
+    })
+    .await?;
+    let mut active = acquire_session(&mut *session).await?;
+    let for_update = for_update && active.in_transaction();
+    let (statement_id, statement) = match (tags, for_update) {
+        (true, true) => ("FETCH_QUERY_UPDATE", FETCH_QUERY_UPDATE),
+        (true, false) => ("FETCH_QUERY", FETCH_QUERY),
+        (false, true) => ("FETCH_VALUE_QUERY_UPDATE", FETCH_VALUE_QUERY_UPDATE),
+        (false, false) => ("FETCH_VALUE_QUERY", FETCH_VALUE_QUERY),
+    };
+    let statement = active.annotate(statement);
+    let start = active.slow_query_log().start();
+    let row = sqlx::query(&statement)
+        .persistent(!active.is_traced())
+        .bind(profile_id)
+        .bind(kind as i16)
+        .bind(&enc_category)
+        .bind(&enc_name)
+        .fetch_optional(active.connection_mut())
+        .await;
+    active.slow_query_log().finish(statement_id, start);
+    if let Some(row) = row.map_err(err_map!(Backend, "Error performing fetch query"))? {
+        let entry = unblock(move || {
+            if is_expired(
+                &key,
+                profile_id,
+                kind,
+                &enc_category,
+                &enc_name,
+                row.try_get(2)?,
+            )? {
+                return Ok(None);
+            }
+            let value = key.decrypt_entry_value_slice(
+                profile_id,
+                kind,
+                category.as_ref(),
+                name.as_ref(),
+                row.try_get(1)?,
+            )?;
+            let tags = if tags {
+                row.try_get::<Option<String>, _>(3)?.map(String::into_bytes)
+            } else {
+                None
+            };
+            let tags = if let Some(enc_tags) = tags {
+                key.decrypt_entry_tags(
+                    decode_tags(enc_tags)
+                        .map_err(|_| err_msg!(Unexpected, "Error decoding tags"))?,
+                )?
+            } else {
+                Vec::new()
+            };
+            Result::<_, Error>::Ok(Some(Entry::new(kind, category, name, value, tags)))
+        })
+        .await?;
+        Ok(entry)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Protect and insert or replace a record, optionally leaving its value unencrypted
+#[allow(clippy::too_many_arguments)]
+async fn perform_update(
+    session: &mut DbSession<Postgres>,
+    kind: EntryKind,
+    plain_category: &str,
+    category: SecretBytes,
+    name: SecretBytes,
+    value: &[u8],
+    tags: Option<&[EntryTag]>,
+    expiry_ms: Option<i64>,
+    insert: bool,
+    encrypt_value: bool,
+) -> Result<(), Error> {
+    let (profile_id, key) = acquire_key_for_update(&mut *session).await?;
+    let enc_entry = encrypt_update(
+        key.clone(),
+        profile_id,
+        kind,
+        plain_category,
+        category,
+        name,
+        value,
+        tags,
+        encrypt_value,
+    )
+    .await?;
+    let mut active = acquire_session(&mut *session).await?;
+    let mut txn = active.as_transaction().await?;
+    let expiry_ms = if expiry_ms.is_none() {
+        default_expiry(&mut txn, kind, plain_category).await?
+    } else {
+        expiry_ms
+    };
+    let (expiry, enc_expiry) = encode_expiry(
+        &key,
+        profile_id,
+        kind,
+        &enc_entry.category,
+        &enc_entry.name,
+        expiry_ms,
+    )?;
+    perform_insert(
+        &mut txn,
+        kind,
+        &enc_entry.category,
+        &enc_entry.name,
+        &enc_entry.value,
+        &enc_entry.namespace,
+        enc_entry.enc_names.as_deref(),
+        Some(enc_entry.tags),
+        expiry,
+        enc_expiry.as_deref(),
+        insert,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn perform_insert(
     active: &mut DbSessionTxn<'_, Postgres>,
@@ -687,34 +2244,52 @@ async fn perform_insert(
     enc_category: &[u8],
     enc_name: &[u8],
     enc_value: &[u8],
+    enc_namespace: &[u8],
+    enc_names: Option<&[u8]>,
     enc_tags: Option<Vec<EncEntryTag>>,
-    expiry_ms: Option<i64>,
+    expiry: Option<Expiry>,
+    enc_expiry: Option<&[u8]>,
     new_row: bool,
 ) -> Result<(), Error> {
+    check_quota(active, kind, enc_category, enc_name, enc_value.len()).await?;
     let row_id = if new_row {
         trace!("Insert entry");
-        sqlx::query_scalar(INSERT_QUERY)
+        let statement = active.annotate(INSERT_QUERY);
+        let start = active.slow_query_log().start();
+        let row_id = sqlx::query_scalar(&statement)
+            .persistent(!active.is_traced())
             .bind(active.profile_id)
             .bind(kind as i16)
             .bind(enc_category)
             .bind(enc_name)
             .bind(enc_value)
-            .bind(expiry_ms.map(expiry_timestamp).transpose()?)
+            .bind(expiry)
+            .bind(enc_namespace)
+            .bind(enc_names)
+            .bind(enc_expiry)
             .fetch_optional(active.connection_mut())
-            .await?
-            .ok_or_else(|| err_msg!(Duplicate, "Duplicate entry"))?
+            .await;
+        active.slow_query_log().finish("INSERT_QUERY", start);
+        row_id?.ok_or_else(|| err_msg!(Duplicate, "Duplicate entry"))?
     } else {
         trace!("Update entry");
-        let row_id: i64 = sqlx::query_scalar(UPDATE_QUERY)
+        let statement = active.annotate(UPDATE_QUERY);
+        let start = active.slow_query_log().start();
+        let row_id = sqlx::query_scalar::<_, i64>(&statement)
+            .persistent(!active.is_traced())
             .bind(active.profile_id)
             .bind(kind as i16)
             .bind(enc_category)
             .bind(enc_name)
             .bind(enc_value)
-            .bind(expiry_ms.map(expiry_timestamp).transpose()?)
+            .bind(expiry)
+            .bind(enc_namespace)
+            .bind(enc_names)
+            .bind(enc_expiry)
             .fetch_one(active.connection_mut())
-            .await
-            .map_err(|_| err_msg!(NotFound, "Error updating existing entry"))?;
+            .await;
+        active.slow_query_log().finish("UPDATE_QUERY", start);
+        let row_id = row_id.map_err(|_| err_msg!(NotFound, "Error updating existing entry"))?;
         sqlx::query(TAG_DELETE_QUERY)
             .bind(row_id)
             .execute(active.connection_mut())
@@ -722,21 +2297,111 @@ async fn perform_insert(
             .map_err(err_map!(Backend, "Error removing existing entry tags"))?;
         row_id
     };
-    if let Some(tags) = enc_tags {
+    if let Some(tags) = enc_tags.filter(|tags| !tags.is_empty()) {
+        let (mut names, mut values, mut plaintext) = (
+            Vec::with_capacity(tags.len()),
+            Vec::with_capacity(tags.len()),
+            Vec::with_capacity(tags.len()),
+        );
         for tag in tags {
-            sqlx::query(TAG_INSERT_QUERY)
-                .bind(row_id)
-                .bind(&tag.name)
-                .bind(&tag.value)
-                .bind(tag.plaintext as i16)
-                .execute(active.connection_mut())
-                .await
-                .map_err(err_map!(Backend, "Error inserting entry tags"))?;
+            names.push(tag.name);
+            values.push(tag.value);
+            plaintext.push(tag.plaintext as i16);
         }
+        sqlx::query(TAG_INSERT_QUERY)
+            .bind(row_id)
+            .bind(names)
+            .bind(values)
+            .bind(plaintext)
+            .execute(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error inserting entry tags"))?;
     }
     Ok(())
 }
 
+/// Check that writing an entry is permitted for the profile, and would not
+/// exceed the limits configured for the profile
+async fn check_quota(
+    active: &mut DbSessionTxn<'_, Postgres>,
+    kind: EntryKind,
+    enc_category: &[u8],
+    enc_name: &[u8],
+    value_len: usize,
+) -> Result<(), Error> {
+    let (archived, max_entries, max_value_bytes) =
+        sqlx::query_as::<_, (bool, Option<i64>, Option<i64>)>(QUOTA_FETCH_QUERY)
+            .bind(active.profile_id)
+            .fetch_optional(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error fetching profile quota"))?
+            .unwrap_or_default();
+    if archived {
+        return Err(err_msg!(
+            Archived,
+            "Profile is archived and cannot be modified"
+        ));
+    }
+    if max_entries.is_none() && max_value_bytes.is_none() {
+        return Ok(());
+    }
+    // the profile is locked, so concurrent writers are checked in sequence. The
+    // usage counters of the profile are maintained by triggers on the items table
+    let (max_entries, max_value_bytes, entries, value_bytes, existing_len) =
+        sqlx::query_as::<_, (Option<i64>, Option<i64>, i64, i64, Option<i64>)>(PROFILE_USAGE_QUERY)
+            .bind(active.profile_id)
+            .bind(kind as i16)
+            .bind(enc_category)
+            .bind(enc_name)
+            .fetch_optional(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error fetching profile usage"))?
+            .unwrap_or_default();
+    let quota = ProfileQuota {
+        max_entries,
+        max_value_bytes,
+    };
+    // any existing entry being replaced is excluded from the usage
+    match existing_len {
+        Some(len) => quota.check(entries, value_bytes - len + value_len as i64),
+        None => quota.check(entries + 1, value_bytes + value_len as i64),
+    }
+}
+
+/// Determine the lifetime of an entry written without an explicit expiry
+async fn default_expiry(
+    active: &mut DbSessionTxn<'_, Postgres>,
+    kind: EntryKind,
+    category: &str,
+) -> Result<Option<i64>, Error> {
+    let policy: Option<String> = sqlx::query_scalar(EXPIRY_POLICY_FETCH_QUERY)
+        .bind(active.profile_id)
+        .fetch_optional(active.connection_mut())
+        .await
+        .map_err(err_map!(Backend, "Error fetching profile expiry policy"))?
+        .flatten();
+    Ok(ProfileExpiryPolicy::from_json(policy.as_deref())?.default_expiry_ms(kind, category))
+}
+
+/// Check that removing entries is permitted for the profile
+///
+/// The profile remains locked against archival until the transaction completes.
+async fn check_archived(txn: &mut DbSessionTxn<'_, Postgres>) -> Result<(), Error> {
+    let archived: Option<bool> = sqlx::query_scalar(PROFILE_ARCHIVED_QUERY)
+        .bind(txn.profile_id)
+        .fetch_optional(txn.connection_mut())
+        .await
+        .map_err(err_map!(Backend, "Error fetching profile"))?;
+    if archived.unwrap_or_default() {
+        Err(err_msg!(
+            Archived,
+            "Profile is archived and cannot be modified"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 async fn perform_remove<'q>(
     active: &mut DbSessionActive<'q, Postgres>,
     kind: EntryKind,
@@ -744,20 +2409,146 @@ async fn perform_remove<'q>(
     enc_name: &[u8],
     ignore_error: bool,
 ) -> Result<(), Error> {
+    let mut txn = active.as_transaction().await?;
+    check_archived(&mut txn).await?;
     trace!("Remove entry");
-    let done = sqlx::query(DELETE_QUERY)
-        .bind(active.profile_id)
+    let statement = txn.annotate(DELETE_QUERY);
+    let start = txn.slow_query_log().start();
+    let done = sqlx::query(&statement)
+        .persistent(!txn.is_traced())
+        .bind(txn.profile_id)
         .bind(kind as i16)
         .bind(enc_category)
         .bind(enc_name)
-        .execute(active.connection_mut())
-        .await
-        .map_err(err_map!(Backend, "Error removing entry"))?;
+        .execute(txn.connection_mut())
+        .await;
+    txn.slow_query_log().finish("DELETE_QUERY", start);
+    let done = done.map_err(err_map!(Backend, "Error removing entry"))?;
     if done.rows_affected() == 0 && !ignore_error {
-        Err(err_msg!(NotFound, "Entry not found"))
-    } else {
-        Ok(())
+        return Err(err_msg!(NotFound, "Entry not found"));
+    }
+    txn.commit().await
+}
+
+/// Re-encrypt the change log entries of a profile under a new profile key
+async fn reencrypt_profile_changes(
+    conn: &mut PgConnection,
+    pid: ProfileId,
+    old_key: Arc<ProfileKey>,
+    new_key: Arc<ProfileKey>,
+) -> Result<(), Error> {
+    let changes = sqlx::query(
+        "SELECT seq, kind, category, name, enc_names FROM item_changes WHERE profile_id = $1",
+    )
+    .bind(pid)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok((
+            row.try_get(0)?,
+            EntryKind::try_from(row.try_get::<i16, _>(1)? as usize)?,
+            row.try_get(2)?,
+            row.try_get(3)?,
+            row.try_get(4)?,
+        ))
+    })
+    .collect::<Result<Vec<(i64, EntryKind, Vec<u8>, Vec<u8>, Option<Vec<u8>>)>, Error>>()?;
+    let changes = process_batch(changes, move |(seq, kind, category, name, enc_names)| {
+        Ok((
+            seq,
+            reencrypt_change(&old_key, &new_key, pid, kind, category, name, enc_names)?,
+        ))
+    })
+    .await?;
+    for (seq, (category, name, enc_names)) in changes {
+        sqlx::query(
+            "UPDATE item_changes SET category = $1, name = $2, enc_names = $3 WHERE seq = $4",
+        )
+        .bind(category)
+        .bind(name)
+        .bind(enc_names)
+        .bind(seq)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Re-encrypt the tags of all items in a profile under a new profile key
+///
+/// Tags are processed in batches in order to limit memory usage for large profiles.
+async fn reencrypt_profile_tags(
+    conn: &mut PgConnection,
+    pid: ProfileId,
+    old_key: Arc<ProfileKey>,
+    new_key: Arc<ProfileKey>,
+) -> Result<(), Error> {
+    let mut last_id = 0i64;
+    loop {
+        let tags = sqlx::query(
+            "SELECT it.id, it.name, it.value, it.plaintext FROM items_tags it
+            JOIN items i ON i.id = it.item_id WHERE i.profile_id = $1 AND it.id > $2
+            ORDER BY it.id LIMIT $3",
+        )
+        .bind(pid)
+        .bind(last_id)
+        .bind(TAG_REENCRYPT_BATCH)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok((
+                row.try_get(0)?,
+                EncEntryTag {
+                    name: row.try_get(1)?,
+                    value: row.try_get(2)?,
+                    plaintext: row.try_get::<i16, _>(3)? != 0,
+                },
+            ))
+        })
+        .collect::<Result<Vec<(i64, EncEntryTag)>, Error>>()?;
+        last_id = match tags.last() {
+            Some((id, _)) => *id,
+            None => break,
+        };
+        let tags = unblock({
+            let (old_key, new_key) = (old_key.clone(), new_key.clone());
+            move || reencrypt_tags(&old_key, &new_key, tags)
+        })
+        .await?;
+        let (mut ids, mut names, mut values) = (
+            Vec::with_capacity(tags.len()),
+            Vec::with_capacity(tags.len()),
+            Vec::with_capacity(tags.len()),
+        );
+        for (id, tag) in tags {
+            ids.push(id);
+            names.push(tag.name);
+            values.push(tag.value);
+        }
+        sqlx::query(TAG_UPDATE_QUERY)
+            .bind(ids)
+            .bind(names)
+            .bind(values)
+            .execute(&mut *conn)
+            .await?;
     }
+    Ok(())
+}
+
+async fn fetch_query_plan<'q>(
+    conn: &mut PgConnection,
+    explain: &'q str,
+    params: QueryParams<'q, Postgres>,
+) -> Result<Vec<String>, Error> {
+    let rows = sqlx::query_with(explain, params)
+        .persistent(false)
+        .fetch_all(conn)
+        .await?;
+    rows.iter()
+        .map(|row| row.try_get(0).map_err(Error::from))
+        .collect()
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -767,51 +2558,82 @@ fn perform_scan(
     key: Arc<ProfileKey>,
     kind: Option<EntryKind>,
     category: Option<String>,
+    namespace: Option<String>,
     tag_filter: Option<TagFilter>,
+    after: Option<i64>,
     offset: Option<i64>,
     limit: Option<i64>,
     order_by: Option<OrderBy>,
     descending: bool,
     for_update: bool,
-) -> impl Stream<Item = Result<Vec<EncScanEntry>, Error>> + '_ {
+    page_size: usize,
+) -> impl Stream<Item = Result<Vec<(i64, EncScanEntry)>, Error>> + '_ {
     try_stream! {
-        let mut params = QueryParams::new();
+        let mut params = QueryParams::with_copy(active.slow_query_log().explain());
         params.push(profile_id);
         params.push(kind.map(|k| k as i16));
-        let (enc_category, tag_filter) = unblock({
+        let (enc_category, namespace_range, tag_filter) = unblock({
             let key = key.clone();
             let enc_category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
-            let params_len = params.len() + 1; // plus category
+            // plus the category or the namespace bounds
+            let params_len = params.len() + if namespace.is_some() { 2 } else { 1 };
             move || {
                 Result::<_, Error>::Ok((
                     enc_category
                         .map(|c| key.encrypt_entry_category(c))
                         .transpose()?,
+                    namespace.map(|ns| encode_namespace_range(&key, &ns)).transpose()?,
                     encode_tag_filter::<PostgresBackend>(tag_filter, &key, params_len)?
                 ))
             }
         }).await?;
-        params.push(enc_category);
-        let mut query = extend_query::<PostgresBackend>(SCAN_QUERY, &mut params, tag_filter, offset, limit, order_by, descending)?;
+        let by_namespace = namespace_range.is_some();
+        let (statement_id, statement) = if let Some((lower, upper)) = namespace_range {
+            params.push(lower);
+            params.push(upper);
+            ("SCAN_NAMESPACE_QUERY", SCAN_NAMESPACE_QUERY)
+        } else {
+            params.push(enc_category);
+            ("SCAN_QUERY", SCAN_QUERY)
+        };
+        let mut query = extend_query::<PostgresBackend>(statement, &mut params, tag_filter, QueryPage { after, offset, limit, order_by, descending })?;
         if for_update {
             query.push_str(" FOR NO KEY UPDATE");
         }
-        let mut batch = Vec::with_capacity(PAGE_SIZE);
+        let explain = params.take_copy();
+        let mut batch = Vec::with_capacity(page_size);
 
         let mut acquired = acquire_session(&mut active).await?;
-        let mut rows = sqlx::query_with(query.as_str(), params).fetch(acquired.connection_mut());
+        if by_namespace {
+            check_namespace_index(acquired.connection_mut(), profile_id).await?;
+        }
+        // the statement is timed until the first row is returned
+        let slow_query_log = acquired.slow_query_log().clone();
+        let statement = acquired.annotate(&query).into_owned();
+        let persistent = !acquired.is_traced();
+        let mut start = slow_query_log.start();
+        let mut slow = false;
+        let mut rows = sqlx::query_with(statement.as_str(), params).persistent(persistent).fetch(acquired.connection_mut());
         while let Some(row) = rows.try_next().await? {
+            slow |= slow_query_log.finish(statement_id, start.take());
             let tags = row.try_get::<Option<String>, _>(5)?.map(String::into_bytes).unwrap_or_default();
             let kind: i16 = row.try_get(1)?;
             let kind = EntryKind::try_from(kind as usize)?;
-            batch.push(EncScanEntry {
-                kind, category: row.try_get(2)?, name: row.try_get(3)?, value: row.try_get(4)?, tags
-            });
-            if batch.len() == PAGE_SIZE {
+            let (category, name): (Vec<u8>, Vec<u8>) = (row.try_get(2)?, row.try_get(3)?);
+            batch.push((row.try_get(0)?, EncScanEntry {
+                kind, category, name, value: row.try_get(4)?, tags,
+                enc_names: row.try_get(6)?
+            }));
+            if batch.len() == page_size {
                 yield batch.split_off(0);
             }
         }
+        slow |= slow_query_log.finish(statement_id, start);
         drop(rows);
+        if let (true, Some(explain)) = (slow, explain) {
+            let plan = fetch_query_plan(acquired.connection_mut(), &format!("EXPLAIN {}", query), explain).await;
+            slow_query_log.log_plan(statement_id, plan);
+        }
         if active.is_owned() {
             active.close(false).await?;
         }
@@ -6,17 +6,21 @@ use sqlx::{
 };
 use std::time::Duration;
 
-use super::provision::{init_db, reset_db, PostgresStoreOptions};
+use super::provision::{
+    apply_schema_migrations, init_db, init_tenant_schemas, reset_db, update_config_mac,
+    PostgresStoreOptions, SCHEMA_MIGRATIONS,
+};
 use super::PostgresBackend;
 use crate::{
     any::{into_any_backend, AnyBackend},
     backend::{
         db_utils::{init_keys, random_profile_name},
-        Backend,
+        schema::SchemaCompat,
+        Backend, TenantIsolation,
     },
     error::Error,
     future::{sleep, spawn_ok, timeout, unblock},
-    protect::{generate_raw_store_key, KeyCache, StoreKeyMethod},
+    protect::{generate_raw_store_key, KeyCache, NameEncryption, ProfileCipher, StoreKeyMethod},
 };
 
 #[derive(Debug)]
@@ -36,8 +40,16 @@ impl TestDB {
     /// This method blocks until the database lock can be acquired.
     pub async fn provision(db_url: &str) -> Result<TestDB, Error> {
         let key = generate_raw_store_key(None)?;
-        let (profile_key, enc_profile_key, store_key, store_key_ref) =
-            unblock(|| init_keys(StoreKeyMethod::RawKey, key)).await?;
+        let (profile_key, enc_profile_key, store_key, store_key_ref) = unblock(|| {
+            init_keys(
+                StoreKeyMethod::RawKey,
+                key,
+                ProfileCipher::default(),
+                NameEncryption::default(),
+                None,
+            )
+        })
+        .await?;
         let default_profile = random_profile_name();
 
         let opts = PostgresStoreOptions::new(db_url)?;
@@ -71,19 +83,49 @@ impl TestDB {
             &default_profile,
             store_key_ref,
             enc_profile_key,
+            ProfileCipher::default(),
+            NameEncryption::default(),
+            None,
+            &opts.unlock_policy,
+            opts.index_profile,
             &opts.username,
         )
         .await?;
 
+        let mut conn = conn_pool.acquire().await?;
+        apply_schema_migrations(conn.as_mut(), SCHEMA_MIGRATIONS, SchemaCompat::Strict).await?;
+
+        let tenant_schemas = if opts.tenant_isolation == TenantIsolation::Schema {
+            Some(
+                init_tenant_schemas(
+                    conn.as_mut(),
+                    &opts.username,
+                    profile_id,
+                    opts.index_profile,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+        update_config_mac(conn.as_mut(), &store_key, 0).await?;
+
         let mut key_cache = KeyCache::new(store_key);
         key_cache.add_profile_mut(default_profile.clone(), profile_id, profile_key);
-        let inst = into_any_backend(PostgresBackend::new(
+        let backend = PostgresBackend::new(
             conn_pool,
             default_profile,
             key_cache,
             opts.host,
             opts.name,
-        ));
+            opts.page_size,
+            opts.slow_query_log,
+        );
+        let inst = into_any_backend(if let Some(tenant_schemas) = tenant_schemas {
+            backend.with_tenant_schemas(tenant_schemas, opts.index_profile)
+        } else {
+            backend
+        });
 
         Ok(TestDB {
             inst: Some(inst),
@@ -0,0 +1,59 @@
+/// A long-running store operation reporting its progress
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProgressPhase {
+    /// Records are being exported or backed up
+    Export,
+    /// Records are being imported or restored
+    Import,
+    /// Records of an Indy-SDK wallet are being migrated
+    Migrate,
+    /// Profile keys are being encrypted with a new store key
+    Rekey,
+    /// Records are being copied to another store
+    Copy,
+    /// Profiles are being removed
+    Remove,
+}
+
+impl ProgressPhase {
+    /// Convert the phase to a string reference
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Export => "export",
+            Self::Import => "import",
+            Self::Migrate => "migrate",
+            Self::Rekey => "rekey",
+            Self::Copy => "copy",
+            Self::Remove => "remove",
+        }
+    }
+}
+
+/// The progress of a long-running store operation
+///
+/// Progress is reported after each batch of items is processed. The items are
+/// records, with the exception of re-keying and profile removal where they
+/// are profiles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// The operation being performed
+    pub phase: ProgressPhase,
+    /// The profile currently being processed, if applicable
+    pub profile: Option<String>,
+    /// The number of items processed so far
+    pub processed: i64,
+    /// An estimate of the total number of items, when available
+    pub total: Option<i64>,
+}
+
+impl Progress {
+    /// Deliver the report to an optional progress callback
+    pub fn report(&self, progress: Option<&ProgressFn>) {
+        if let Some(progress) = progress {
+            progress(self);
+        }
+    }
+}
+
+/// A callback receiving progress reports
+pub type ProgressFn = dyn Fn(&Progress) + Send + Sync;
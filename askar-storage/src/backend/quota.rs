@@ -0,0 +1,89 @@
+use crate::error::Error;
+
+/// Limits on the records stored within a profile
+///
+/// Limits are enforced when an entry is inserted or replaced, so that one
+/// profile of a shared store cannot exhaust the storage available to the
+/// others. A write which would exceed a limit fails with a
+/// [`QuotaExceeded`](crate::ErrorKind::QuotaExceeded) error. Existing records
+/// are not affected when the limits of a profile are reduced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ProfileQuota {
+    /// The maximum number of entries, including keys and expired entries
+    /// which have not yet been purged
+    pub max_entries: Option<i64>,
+    /// The maximum total size in bytes of the stored entry values, which
+    /// includes the encryption overhead of each value
+    pub max_value_bytes: Option<i64>,
+}
+
+impl ProfileQuota {
+    /// Check whether no limits are configured
+    pub fn is_unlimited(&self) -> bool {
+        self.max_entries.is_none() && self.max_value_bytes.is_none()
+    }
+
+    /// Ensure that the configured limits are not negative
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.max_entries.unwrap_or_default() < 0 || self.max_value_bytes.unwrap_or_default() < 0
+        {
+            Err(err_msg!(Input, "Profile quota limits must not be negative"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check the usage of a profile following a write against the configured limits
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub(crate) fn check(&self, entries: i64, value_bytes: i64) -> Result<(), Error> {
+        if let Some(max) = self.max_entries.filter(|max| entries > *max) {
+            return Err(err_msg!(
+                QuotaExceeded,
+                "Profile entry limit of {} exceeded",
+                max
+            ));
+        }
+        if let Some(max) = self.max_value_bytes.filter(|max| value_bytes > *max) {
+            return Err(err_msg!(
+                QuotaExceeded,
+                "Profile value storage limit of {} bytes exceeded",
+                max
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, any(feature = "postgres", feature = "sqlite")))]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn check_profile_quota() {
+        let unlimited = ProfileQuota::default();
+        assert!(unlimited.is_unlimited());
+        unlimited.check(i64::MAX, i64::MAX).unwrap();
+
+        let quota = ProfileQuota {
+            max_entries: Some(2),
+            max_value_bytes: Some(100),
+        };
+        assert!(!quota.is_unlimited());
+        quota.validate().unwrap();
+        quota.check(2, 100).unwrap();
+        let err = quota.check(3, 10).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::QuotaExceeded);
+        let err = quota.check(1, 101).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::QuotaExceeded);
+
+        let err = ProfileQuota {
+            max_entries: Some(-1),
+            max_value_bytes: None,
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Input);
+    }
+}
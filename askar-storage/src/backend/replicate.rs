@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Backend, BackendSession};
+use crate::{
+    entry::EntryOperation,
+    error::{Error, ErrorKind},
+};
+
+/// The progress of a store replication
+///
+/// A checkpoint is reported after each batch of records is committed to the
+/// target store, and may be persisted by the caller (it implements `Serialize`)
+/// in order to resume an interrupted replication.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationCheckpoint {
+    /// The profiles which have been fully replicated
+    pub completed: Vec<String>,
+    /// The profile currently being replicated
+    pub profile: Option<String>,
+    /// The number of records of the current profile copied to the target
+    pub copied: i64,
+    /// The row identifier of the last record of the current profile copied to the target
+    #[serde(default)]
+    pub last_id: Option<i64>,
+    /// The total number of records copied to the target, across all profiles
+    pub total: i64,
+    /// Whether all profiles of the source store have been replicated
+    #[serde(default)]
+    pub complete: bool,
+}
+
+impl ReplicationCheckpoint {
+    /// Check whether all profiles of the source store have been replicated
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+}
+
+/// Copy all profiles and records of one store into another, resumably
+///
+/// The target store may use a different backend, and must already be provisioned.
+/// Records of each profile (including keys) are read in order of their row
+/// identifiers and written in batches, one transaction per page of the source
+/// scan. The checkpoint records the identifier of the last record copied, so
+/// that resuming continues after it even when records of the source have been
+/// inserted or removed in the meantime. After each batch, `progress` is invoked with the updated checkpoint:
+/// returning an error, for instance when the checkpoint could not be persisted,
+/// stops the replication. Passing the last reported checkpoint resumes an
+/// interrupted replication: existing records in the target are replaced, so any
+/// batch which was committed but not reported is safely written again.
+///
+/// Record expiry times are not replicated. Once all profiles are copied, the
+/// default profile of the target is updated to match the source.
+pub async fn replicate<A: Backend, B: Backend>(
+    source: &A,
+    target: &B,
+    checkpoint: Option<ReplicationCheckpoint>,
+    mut progress: impl FnMut(&ReplicationCheckpoint) -> Result<(), Error> + Send,
+) -> Result<ReplicationCheckpoint, Error> {
+    let mut checkpoint = checkpoint.unwrap_or_default();
+    let mut profiles = source.list_profiles().await?;
+    profiles.sort();
+
+    for profile in profiles {
+        if checkpoint.completed.contains(&profile) {
+            continue;
+        }
+        if checkpoint.profile.as_ref() != Some(&profile) {
+            checkpoint.profile = Some(profile.clone());
+            checkpoint.copied = 0;
+            checkpoint.last_id = None;
+        }
+        if let Err(err) = target.create_profile(Some(profile.clone())).await {
+            if err.kind() != ErrorKind::Duplicate {
+                return Err(err);
+            }
+        }
+
+        let mut scan = source
+            .scan_after(
+                Some(profile.clone()),
+                None,
+                None,
+                None,
+                checkpoint.last_id,
+                None,
+                None,
+                false,
+            )
+            .await?;
+        while let Some(rows) = scan.fetch_next().await? {
+            let mut txn = target.session(Some(profile.clone()), true)?;
+            for (_, entry) in rows.iter() {
+                let mut operation = EntryOperation::Insert;
+                loop {
+                    match txn
+                        .update(
+                            entry.kind,
+                            operation,
+                            entry.category.as_str(),
+                            entry.name.as_str(),
+                            Some(entry.value.as_ref()),
+                            Some(entry.tags.as_ref()),
+                            None,
+                        )
+                        .await
+                    {
+                        // the record was copied before the replication was interrupted
+                        Err(err)
+                            if err.kind() == ErrorKind::Duplicate
+                                && operation == EntryOperation::Insert =>
+                        {
+                            operation = EntryOperation::Replace;
+                        }
+                        res => break res?,
+                    }
+                }
+            }
+            txn.close(true).await?;
+            checkpoint.last_id = rows.last().map(|(id, _)| *id);
+            checkpoint.copied += rows.len() as i64;
+            checkpoint.total += rows.len() as i64;
+            progress(&checkpoint)?;
+        }
+
+        checkpoint.profile = None;
+        checkpoint.copied = 0;
+        checkpoint.last_id = None;
+        checkpoint.completed.push(profile);
+        progress(&checkpoint)?;
+    }
+
+    target
+        .set_default_profile(source.get_default_profile().await?)
+        .await?;
+    checkpoint.complete = true;
+    progress(&checkpoint)?;
+    Ok(checkpoint)
+}
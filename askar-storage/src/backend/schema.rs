@@ -0,0 +1,175 @@
+//! Versioned schema migrations for database backends
+
+use std::{collections::HashMap, str::FromStr};
+
+use crate::error::Error;
+
+/// The name of the config table entry identifying the library version which
+/// last applied a schema migration
+pub(crate) const SCHEMA_WRITER_CONFIG: &str = "schema_writer";
+
+/// The identifier of this library version, recorded when applying migrations
+pub(crate) fn schema_writer() -> String {
+    format!("askar-storage {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// The handling of stores whose schema was upgraded by a newer library version
+///
+/// The mode is selected using the `schema_compat` query parameter of the
+/// store URI.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) enum SchemaCompat {
+    /// Refuse to open a store with unknown schema migrations
+    #[default]
+    Strict,
+    /// Open the store in read-only mode, permitting unknown schema migrations
+    ReadOnly,
+}
+
+impl SchemaCompat {
+    /// Extract the compatibility mode from the `schema_compat` store URI query parameter
+    pub(crate) fn from_query(query: &mut HashMap<String, String>) -> Result<Self, Error> {
+        if let Some(mode) = query.remove("schema_compat") {
+            Self::from_str(&mode)
+                .map_err(err_map!(Input, "Error parsing 'schema_compat' parameter"))
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Check whether the store is opened in read-only mode
+    pub(crate) fn is_read_only(&self) -> bool {
+        *self == Self::ReadOnly
+    }
+}
+
+impl FromStr for SchemaCompat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "read_only" => Ok(Self::ReadOnly),
+            _ => Err(err_msg!(Unsupported, "Unknown schema compatibility mode")),
+        }
+    }
+}
+
+/// A change to the database schema of a store
+///
+/// Migrations are listed per backend in ascending order of version, and each
+/// pending migration is applied in its own transaction when a store is
+/// provisioned or opened. The versions of the applied migrations are recorded
+/// in the `schema_migrations` table, so a migration must never be modified or
+/// removed once released: subsequent changes require a new migration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SchemaMigration {
+    /// The unique, positive version number of the migration
+    pub version: i64,
+    /// A short description recorded alongside the version
+    pub description: &'static str,
+    /// The SQL statements to execute, separated by semicolons
+    pub statements: &'static str,
+}
+
+/// Determine the migrations which have not yet been applied, in order of version
+pub(crate) fn pending_migrations<'m>(
+    migrations: &'m [SchemaMigration],
+    applied: &[i64],
+) -> Result<Vec<&'m SchemaMigration>, Error> {
+    let mut prev = 0;
+    for migration in migrations {
+        if migration.version <= prev {
+            return Err(err_msg!(
+                Unexpected,
+                "Schema migrations must be listed in ascending order of version"
+            ));
+        }
+        prev = migration.version;
+    }
+    Ok(migrations
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .collect())
+}
+
+/// Find the latest applied migration which is unknown to this library, if any
+pub(crate) fn newer_schema_version(migrations: &[SchemaMigration], applied: &[i64]) -> Option<i64> {
+    applied
+        .iter()
+        .copied()
+        .filter(|version| !migrations.iter().any(|m| m.version == *version))
+        .max()
+}
+
+/// Describe a store schema which is newer than supported by this library
+pub(crate) fn newer_schema_error(
+    migrations: &[SchemaMigration],
+    version: i64,
+    writer: Option<&str>,
+) -> Error {
+    err_msg!(
+        Unsupported,
+        "Store schema version {} (written by {}) is newer than the latest supported \
+        version {}: open the store with schema_compat=read_only, or upgrade {}",
+        version,
+        writer.unwrap_or("an unknown version"),
+        migrations.last().map(|m| m.version).unwrap_or_default(),
+        schema_writer()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIGRATIONS: &[SchemaMigration] = &[
+        SchemaMigration {
+            version: 1,
+            description: "first",
+            statements: "",
+        },
+        SchemaMigration {
+            version: 2,
+            description: "second",
+            statements: "",
+        },
+    ];
+
+    #[test]
+    fn schema_pending_migrations() {
+        let pending = pending_migrations(MIGRATIONS, &[]).unwrap();
+        assert_eq!(pending, vec![&MIGRATIONS[0], &MIGRATIONS[1]]);
+        let pending = pending_migrations(MIGRATIONS, &[1]).unwrap();
+        assert_eq!(pending, vec![&MIGRATIONS[1]]);
+        assert!(pending_migrations(MIGRATIONS, &[1, 2]).unwrap().is_empty());
+
+        let unordered = [MIGRATIONS[1], MIGRATIONS[0]];
+        assert!(pending_migrations(&unordered, &[]).is_err());
+    }
+
+    #[test]
+    fn schema_newer_version() {
+        assert_eq!(newer_schema_version(MIGRATIONS, &[]), None);
+        assert_eq!(newer_schema_version(MIGRATIONS, &[1, 2]), None);
+        assert_eq!(newer_schema_version(MIGRATIONS, &[1, 2, 4, 3]), Some(4));
+        let err = newer_schema_error(MIGRATIONS, 4, Some("askar-storage 9.0.0"));
+        assert!(err
+            .message()
+            .unwrap()
+            .contains("version 4 (written by askar-storage 9.0.0)"));
+    }
+
+    #[test]
+    fn schema_compat_parse() {
+        let mut query = HashMap::new();
+        assert_eq!(
+            SchemaCompat::from_query(&mut query).unwrap(),
+            SchemaCompat::Strict
+        );
+        query.insert("schema_compat".to_string(), "read_only".to_string());
+        assert!(SchemaCompat::from_query(&mut query).unwrap().is_read_only());
+        query.insert("schema_compat".to_string(), "other".to_string());
+        assert!(SchemaCompat::from_query(&mut query).is_err());
+    }
+}
@@ -1,26 +1,42 @@
 use std::{
-    borrow::Cow, fs::remove_file, io::ErrorKind as IoErrorKind, str::FromStr,
+    borrow::Cow, fs::remove_file, io::ErrorKind as IoErrorKind, str::FromStr, sync::Arc,
     thread::available_parallelism, time::Duration,
 };
 
 use sqlx::{
     sqlite::{
-        SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode, SqliteLockingMode, SqlitePool,
-        SqlitePoolOptions, SqliteSynchronous,
+        SqliteAutoVacuum, SqliteConnectOptions, SqliteConnection, SqliteJournalMode,
+        SqliteLockingMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
     },
-    ConnectOptions, Error as SqlxError, Row,
+    ConnectOptions, Connection, Error as SqlxError, Row,
 };
 
-use super::SqliteBackend;
+use super::{snapshot, SqliteBackend};
 use crate::{
     backend::{
-        db_utils::{init_keys, random_profile_name},
-        ManageBackend,
+        db_utils::{
+            auto_create_profiles_from_query, backfill_namespaces, check_key_generation, init_keys,
+            page_size_from_query, parse_key_generation, random_profile_name,
+            upgrade_legacy_config_from_query, warm_pool, EncItemNames, SlowQueryLog,
+            NAMESPACE_BACKFILL_BATCH_SIZE, NAMESPACE_BACKFILL_CONFIG,
+        },
+        schema::{
+            newer_schema_error, newer_schema_version, pending_migrations, schema_writer,
+            SchemaCompat, SchemaMigration, SCHEMA_WRITER_CONFIG,
+        },
+        Backend, IndexProfile, ManageBackend, VersionInfo,
     },
-    error::Error,
+    error::{Error, ErrorKind, StoreNotFound},
     future::{sleep, unblock, BoxFuture},
     options::{IntoOptions, Options},
-    protect::{KeyCache, PassKey, StoreKeyMethod, StoreKeyReference},
+    protect::{
+        expiry_bucket_from_query, key_cache_ttl_from_query, max_cached_profiles_from_query,
+        parse_expiry_bucket, strict_key_resolution_from_query, KeyCache, NameEncryption, PassKey,
+        ProfileCipher, ProfilePreload, StoreKey, StoreKeyMethod, StoreKeyReference, UnlockFailures,
+        UnlockPolicy, CONFIG_MAC_CONFIG, EXPIRY_BUCKET_CONFIG, KEY_GENERATION_CONFIG,
+        LEGACY_STORE_VERSION, NAME_ENCRYPTION_CONFIG, PROFILE_CIPHER_CONFIG, STORE_VERSION,
+        UNLOCK_FAILURES_CONFIG, UNLOCK_POLICY_CONFIG,
+    },
 };
 
 const DEFAULT_MIN_CONNECTIONS: usize = 1;
@@ -30,6 +46,219 @@ const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
 const DEFAULT_JOURNAL_MODE: SqliteJournalMode = SqliteJournalMode::Wal;
 const DEFAULT_LOCKING_MODE: SqliteLockingMode = SqliteLockingMode::Normal;
 const DEFAULT_SYNCHRONOUS: SqliteSynchronous = SqliteSynchronous::Full;
+const DEFAULT_STATEMENT_CACHE_CAPACITY: usize = 100;
+
+// The schema migration which enables the reclaiming of free pages by `shred`
+const INCREMENTAL_VACUUM_MIGRATION: i64 = 14;
+
+/// The schema migrations applied to SQLite stores, in ascending order of version
+const SCHEMA_MIGRATIONS: &[SchemaMigration] = &[
+    SchemaMigration {
+        version: 1,
+        description: "add pending profile keys for chunked re-keying",
+        statements: "ALTER TABLE profiles ADD COLUMN profile_key_pending BLOB NULL;",
+    },
+    SchemaMigration {
+        version: 2,
+        description: "track item changes for incremental backups",
+        statements: "
+        CREATE TABLE item_changes (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id INTEGER NOT NULL,
+            kind INTEGER NOT NULL,
+            category BLOB NOT NULL,
+            name BLOB NOT NULL
+        );
+        CREATE UNIQUE INDEX ix_item_changes_item
+            ON item_changes (profile_id, kind, category, name);
+        CREATE TRIGGER item_changes_insert AFTER INSERT ON items BEGIN
+            INSERT OR REPLACE INTO item_changes (profile_id, kind, category, name)
+            VALUES (NEW.profile_id, NEW.kind, NEW.category, NEW.name);
+        END;
+        CREATE TRIGGER item_changes_update AFTER UPDATE OF value, expiry ON items
+            WHEN NEW.category = OLD.category AND NEW.name = OLD.name BEGIN
+            INSERT OR REPLACE INTO item_changes (profile_id, kind, category, name)
+            VALUES (NEW.profile_id, NEW.kind, NEW.category, NEW.name);
+        END;
+        CREATE TRIGGER item_changes_delete AFTER DELETE ON items
+            WHEN EXISTS (SELECT 1 FROM profiles WHERE id = OLD.profile_id) BEGIN
+            INSERT OR REPLACE INTO item_changes (profile_id, kind, category, name)
+            VALUES (OLD.profile_id, OLD.kind, OLD.category, OLD.name);
+        END;
+        CREATE TRIGGER item_changes_profile_delete AFTER DELETE ON profiles BEGIN
+            DELETE FROM item_changes WHERE profile_id = OLD.id;
+        END;
+        INSERT INTO item_changes (profile_id, kind, category, name)
+            SELECT profile_id, kind, category, name FROM items ORDER BY id;
+        ",
+    },
+    SchemaMigration {
+        version: 3,
+        description: "add profile metadata",
+        statements: "ALTER TABLE profiles ADD COLUMN metadata TEXT NULL;",
+    },
+    SchemaMigration {
+        version: 4,
+        description: "add profile quotas",
+        statements: "
+        ALTER TABLE profiles ADD COLUMN max_entries INTEGER NULL;
+        ALTER TABLE profiles ADD COLUMN max_value_bytes INTEGER NULL;
+        ",
+    },
+    SchemaMigration {
+        version: 5,
+        description: "add profile archival",
+        statements: "ALTER TABLE profiles ADD COLUMN archived BOOLEAN NOT NULL DEFAULT 0;",
+    },
+    SchemaMigration {
+        version: 6,
+        description: "add profile aliases",
+        statements: "
+        CREATE TABLE profile_aliases (
+            alias TEXT NOT NULL,
+            profile_id INTEGER NOT NULL,
+            PRIMARY KEY(alias),
+            FOREIGN KEY(profile_id) REFERENCES profiles(id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+        CREATE INDEX ix_profile_aliases_profile_id ON profile_aliases(profile_id);
+        ",
+    },
+    SchemaMigration {
+        version: 7,
+        description: "add profile expiry policies",
+        statements: "ALTER TABLE profiles ADD COLUMN expiry_policy TEXT NULL;",
+    },
+    SchemaMigration {
+        version: 8,
+        description: "add category namespace index",
+        statements: "
+        ALTER TABLE items ADD COLUMN namespace BLOB NULL;
+        CREATE INDEX ix_items_namespace ON items (profile_id, namespace);
+        ",
+    },
+    SchemaMigration {
+        version: 9,
+        description: "add randomized entry names",
+        statements: "
+        ALTER TABLE items ADD COLUMN enc_names BLOB NULL;
+        ALTER TABLE item_changes ADD COLUMN enc_names BLOB NULL;
+        DROP TRIGGER item_changes_insert;
+        DROP TRIGGER item_changes_update;
+        DROP TRIGGER item_changes_delete;
+        CREATE TRIGGER item_changes_insert AFTER INSERT ON items BEGIN
+            INSERT OR REPLACE INTO item_changes (profile_id, kind, category, name, enc_names)
+            VALUES (NEW.profile_id, NEW.kind, NEW.category, NEW.name, NEW.enc_names);
+        END;
+        CREATE TRIGGER item_changes_update AFTER UPDATE OF value, expiry ON items
+            WHEN NEW.category = OLD.category AND NEW.name = OLD.name BEGIN
+            INSERT OR REPLACE INTO item_changes (profile_id, kind, category, name, enc_names)
+            VALUES (NEW.profile_id, NEW.kind, NEW.category, NEW.name, NEW.enc_names);
+        END;
+        CREATE TRIGGER item_changes_delete AFTER DELETE ON items
+            WHEN EXISTS (SELECT 1 FROM profiles WHERE id = OLD.profile_id) BEGIN
+            INSERT OR REPLACE INTO item_changes (profile_id, kind, category, name, enc_names)
+            VALUES (OLD.profile_id, OLD.kind, OLD.category, OLD.name, OLD.enc_names);
+        END;
+        ",
+    },
+    SchemaMigration {
+        version: 10,
+        description: "add encrypted expiry times",
+        statements: "ALTER TABLE items ADD COLUMN enc_expiry BLOB NULL;",
+    },
+    SchemaMigration {
+        version: 11,
+        description: "schedule the category namespace index of existing records",
+        statements: "
+        INSERT OR REPLACE INTO config (name, value)
+            SELECT 'namespace_backfill', '1'
+            WHERE EXISTS (SELECT 1 FROM items WHERE namespace IS NULL);
+        ",
+    },
+    SchemaMigration {
+        version: 12,
+        description: "track profile usage for quotas",
+        statements: "
+        ALTER TABLE profiles ADD COLUMN entry_count INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE profiles ADD COLUMN value_bytes INTEGER NOT NULL DEFAULT 0;
+        UPDATE profiles SET
+            entry_count = (SELECT COUNT(*) FROM items WHERE profile_id = profiles.id),
+            value_bytes = (SELECT COALESCE(SUM(LENGTH(value)), 0) FROM items
+                WHERE profile_id = profiles.id);
+        CREATE TRIGGER profile_usage_insert AFTER INSERT ON items BEGIN
+            UPDATE profiles SET entry_count = entry_count + 1,
+                value_bytes = value_bytes + LENGTH(NEW.value)
+                WHERE id = NEW.profile_id;
+        END;
+        CREATE TRIGGER profile_usage_update AFTER UPDATE OF value ON items BEGIN
+            UPDATE profiles SET value_bytes = value_bytes - LENGTH(OLD.value) + LENGTH(NEW.value)
+                WHERE id = NEW.profile_id;
+        END;
+        CREATE TRIGGER profile_usage_delete AFTER DELETE ON items BEGIN
+            UPDATE profiles SET entry_count = entry_count - 1,
+                value_bytes = value_bytes - LENGTH(OLD.value)
+                WHERE id = OLD.profile_id;
+        END;
+        ",
+    },
+    SchemaMigration {
+        version: 13,
+        description: "bind entry values to their records",
+        // the binding is recorded in the keys of new and re-keyed profiles, so
+        // that the migration only prevents earlier versions from opening the store
+        statements: "-- no schema changes",
+    },
+    SchemaMigration {
+        version: INCREMENTAL_VACUUM_MIGRATION,
+        description: "enable incremental vacuum",
+        // the database is rebuilt before the migration is recorded, as this
+        // cannot be performed within a transaction
+        statements: "-- no schema changes",
+    },
+];
+
+/// The latest schema migration supported by the SQLite backend
+pub fn supported_schema_version() -> i64 {
+    SCHEMA_MIGRATIONS
+        .last()
+        .map(|m| m.version)
+        .unwrap_or_default()
+}
+
+/// The overwriting of deleted content applied by SQLite connections
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SqliteSecureDelete {
+    /// Deleted content is left in place until the page is reused
+    Off,
+    /// Deleted content is overwritten with zeros
+    On,
+    /// Deleted content is overwritten only when this adds no I/O
+    Fast,
+}
+
+impl SqliteSecureDelete {
+    fn as_pragma(&self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::On => "ON",
+            Self::Fast => "FAST",
+        }
+    }
+}
+
+impl FromStr for SqliteSecureDelete {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" | "false" | "0" => Ok(Self::Off),
+            "on" | "true" | "1" => Ok(Self::On),
+            "fast" => Ok(Self::Fast),
+            _ => Err(err_msg!(Unsupported, "Unknown secure delete mode")),
+        }
+    }
+}
 
 /// Configuration options for Sqlite stores
 #[derive(Debug)]
@@ -39,10 +268,29 @@ pub struct SqliteStoreOptions {
     pub(crate) busy_timeout: Duration,
     pub(crate) max_connections: u32,
     pub(crate) min_connections: u32,
+    pub(crate) warm_connections: u32,
     pub(crate) journal_mode: SqliteJournalMode,
     pub(crate) locking_mode: SqliteLockingMode,
     pub(crate) shared_cache: bool,
     pub(crate) synchronous: SqliteSynchronous,
+    pub(crate) cache_size: Option<i64>,
+    pub(crate) mmap_size: Option<u64>,
+    pub(crate) secure_delete: Option<SqliteSecureDelete>,
+    pub(crate) statement_cache_capacity: usize,
+    pub(crate) page_size: usize,
+    pub(crate) slow_query_log: SlowQueryLog,
+    pub(crate) unlock_policy: UnlockPolicy,
+    pub(crate) profile_cipher: ProfileCipher,
+    pub(crate) name_encryption: NameEncryption,
+    pub(crate) expiry_bucket: Option<u32>,
+    pub(crate) max_cached_profiles: Option<usize>,
+    pub(crate) key_cache_ttl: Option<Duration>,
+    pub(crate) strict_key_resolution: bool,
+    pub(crate) preload_profiles: Option<ProfilePreload>,
+    pub(crate) index_profile: IndexProfile,
+    pub(crate) schema_compat: SchemaCompat,
+    pub(crate) auto_create_profiles: bool,
+    pub(crate) upgrade_legacy_config: bool,
 }
 
 impl Default for SqliteStoreOptions {
@@ -53,6 +301,45 @@ impl Default for SqliteStoreOptions {
 
 impl SqliteStoreOptions {
     /// Initialize `SqliteStoreOptions` from a generic set of options
+    ///
+    /// Connection settings are accepted as query parameters of the store URI:
+    /// `journal_mode` (for example `wal` or `delete`), `synchronous` (`off`, `normal`,
+    /// `full` or `extra`), `busy_timeout` in milliseconds, `cache_size` as a number of
+    /// pages or a negative size in KiB, and `mmap_size` in bytes. These are applied
+    /// to each connection when it is opened. A non-zero `mmap_size` enables
+    /// memory-mapped reads of up to that many bytes of the database file, which
+    /// reduces system call overhead for read-mostly deployments with large stores.
+    /// The mapping size is limited by the maximum compiled into SQLite, and has no
+    /// effect on in-memory databases. The `secure_delete` parameter (`on`, `off` or
+    /// `fast`) sets whether SQLite overwrites deleted content with zeros. Records
+    /// removed with `shred` are always overwritten. The `index_profile` parameter selects the
+    /// secondary indexes created when the store is provisioned. Stores whose schema
+    /// was upgraded by a newer library version are refused, unless `schema_compat`
+    /// is set to `read_only`: the store is then opened with writes disabled, except
+    /// for the recording of failed unlock attempts required by the unlock policy.
+    /// When `slow_query_ms` is set, record statements taking longer than this many
+    /// milliseconds are logged with their statement identifier and profile name.
+    /// Setting `slow_query_explain=true` additionally logs the query plans of slow
+    /// scan and count statements, to help diagnose tag filters which miss indexes.
+    /// Setting `auto_create_profiles=true` creates the profile of a session when it
+    /// does not exist on the first insert or replace of a record, using the
+    /// configured profile cipher. Other operations still fail with a `NotFound`
+    /// error until the profile is created, and the option may not be combined with
+    /// read-only mode. Setting `key_cache_ttl` to a number of seconds reloads
+    /// cached profile keys once they reach this age, so that profiles re-keyed or
+    /// removed by other processes are observed. Setting
+    /// `strict_key_resolution=true` instead reads the profile key of each session
+    /// from the database, failing the session when the profile name is ambiguous
+    /// or its key cannot be loaded. Provisioning with `name_encryption=rnd`
+    /// encrypts the categories and names of entries in the store's profiles with
+    /// random nonces, keeping keyed hashes of them as blind indexes for lookups.
+    /// Setting `expiry_bucket` to a number of seconds when provisioning encrypts
+    /// the expiry times of entries, recording them in plaintext only rounded up to
+    /// a multiple of this interval. Scans, entry counts and profile statistics
+    /// then include entries expiring within the current interval, while fetching
+    /// an entry by name checks its exact expiry time. Stores which predate the protection of their configuration
+    /// by a MAC are upgraded to the current format when first unlocked, recording
+    /// the MAC. Setting `upgrade_legacy_config=false` refuses such stores instead.
     pub fn new<'a>(options: impl IntoOptions<'a>) -> Result<Self, Error> {
         let mut opts = options.into_options()?;
         let mut path = opts.host.to_string();
@@ -89,6 +376,14 @@ impl SqliteStoreOptions {
         } else {
             DEFAULT_MIN_CONNECTIONS as u32
         };
+        let warm_connections = if let Some(warm_conn) = opts.query.remove("warm_connections") {
+            warm_conn.parse().map_err(err_map!(
+                Input,
+                "Error parsing 'warm_connections' parameter"
+            ))?
+        } else {
+            0
+        };
         let journal_mode = if let Some(mode) = opts.query.remove("journal_mode") {
             SqliteJournalMode::from_str(&mode)
                 .map_err(err_map!(Input, "Error parsing 'journal_mode' parameter"))?
@@ -112,6 +407,53 @@ impl SqliteStoreOptions {
         } else {
             DEFAULT_SYNCHRONOUS
         };
+        let cache_size = if let Some(size) = opts.query.remove("cache_size") {
+            Some(
+                size.parse()
+                    .map_err(err_map!(Input, "Error parsing 'cache_size' parameter"))?,
+            )
+        } else {
+            None
+        };
+        let mmap_size = if let Some(size) = opts.query.remove("mmap_size") {
+            Some(
+                size.parse()
+                    .map_err(err_map!(Input, "Error parsing 'mmap_size' parameter"))?,
+            )
+        } else {
+            None
+        };
+        let secure_delete = if let Some(mode) = opts.query.remove("secure_delete") {
+            Some(
+                SqliteSecureDelete::from_str(&mode)
+                    .map_err(err_map!(Input, "Error parsing 'secure_delete' parameter"))?,
+            )
+        } else {
+            None
+        };
+        let statement_cache_capacity =
+            if let Some(capacity) = opts.query.remove("statement_cache_capacity") {
+                capacity.parse().map_err(err_map!(
+                    Input,
+                    "Error parsing 'statement_cache_capacity' parameter"
+                ))?
+            } else {
+                DEFAULT_STATEMENT_CACHE_CAPACITY
+            };
+        let page_size = page_size_from_query(&mut opts.query)?;
+        let slow_query_log = SlowQueryLog::from_query(&mut opts.query)?;
+        let unlock_policy = UnlockPolicy::from_query(&mut opts.query)?;
+        let profile_cipher = ProfileCipher::from_query(&mut opts.query)?;
+        let name_encryption = NameEncryption::from_query(&mut opts.query)?;
+        let expiry_bucket = expiry_bucket_from_query(&mut opts.query)?;
+        let max_cached_profiles = max_cached_profiles_from_query(&mut opts.query)?;
+        let key_cache_ttl = key_cache_ttl_from_query(&mut opts.query)?;
+        let strict_key_resolution = strict_key_resolution_from_query(&mut opts.query)?;
+        let preload_profiles = ProfilePreload::from_query(&mut opts.query)?;
+        let index_profile = IndexProfile::from_query(&mut opts.query)?;
+        let schema_compat = SchemaCompat::from_query(&mut opts.query)?;
+        let auto_create_profiles = auto_create_profiles_from_query(&mut opts.query, schema_compat)?;
+        let upgrade_legacy_config = upgrade_legacy_config_from_query(&mut opts.query)?;
 
         Ok(Self {
             in_memory,
@@ -119,15 +461,33 @@ impl SqliteStoreOptions {
             busy_timeout,
             max_connections,
             min_connections,
+            warm_connections,
             journal_mode,
             locking_mode,
             shared_cache,
             synchronous,
+            cache_size,
+            mmap_size,
+            secure_delete,
+            statement_cache_capacity,
+            page_size,
+            slow_query_log,
+            unlock_policy,
+            profile_cipher,
+            name_encryption,
+            expiry_bucket,
+            max_cached_profiles,
+            key_cache_ttl,
+            strict_key_resolution,
+            preload_profiles,
+            index_profile,
+            schema_compat,
+            auto_create_profiles,
+            upgrade_legacy_config,
         })
     }
 
     async fn pool(&self, auto_create: bool) -> std::result::Result<SqlitePool, SqlxError> {
-        #[allow(unused_mut)]
         let mut conn_opts = SqliteConnectOptions::from_str(self.path.as_ref())?
             .create_if_missing(auto_create)
             .auto_vacuum(SqliteAutoVacuum::Incremental)
@@ -135,14 +495,28 @@ impl SqliteStoreOptions {
             .journal_mode(self.journal_mode)
             .locking_mode(self.locking_mode)
             .shared_cache(self.shared_cache)
-            .synchronous(self.synchronous);
+            .synchronous(self.synchronous)
+            .statement_cache_capacity(self.statement_cache_capacity);
+        if let Some(cache_size) = self.cache_size {
+            // a negative value specifies the cache size in KiB rather than pages
+            conn_opts = conn_opts.pragma("cache_size", cache_size.to_string());
+        }
+        if let Some(mmap_size) = self.mmap_size {
+            conn_opts = conn_opts.pragma("mmap_size", mmap_size.to_string());
+        }
+        if let Some(secure_delete) = self.secure_delete {
+            conn_opts = conn_opts.pragma("secure_delete", secure_delete.as_pragma());
+        }
+        if self.schema_compat.is_read_only() {
+            conn_opts = conn_opts.pragma("query_only", "1");
+        }
         #[cfg(feature = "log")]
         {
             conn_opts = conn_opts
                 .log_statements(log::LevelFilter::Debug)
                 .log_slow_statements(log::LevelFilter::Debug, Default::default());
         }
-        SqlitePoolOptions::default()
+        let pool = SqlitePoolOptions::default()
             // maintains at least 1 connection.
             // for an in-memory database this is required to avoid dropping the database,
             // for a file database this signals other instances that the database is in use
@@ -150,7 +524,9 @@ impl SqliteStoreOptions {
             .max_connections(self.max_connections)
             .test_before_acquire(false)
             .connect_with(conn_opts)
-            .await
+            .await?;
+        warm_pool(&pool, self.warm_connections).await?;
+        Ok(pool)
     }
 
     /// Provision a new Sqlite store from these configuration options
@@ -161,6 +537,12 @@ impl SqliteStoreOptions {
         profile: Option<String>,
         recreate: bool,
     ) -> Result<SqliteBackend, Error> {
+        if self.schema_compat.is_read_only() {
+            return Err(err_msg!(
+                Input,
+                "Cannot provision a store in read-only mode"
+            ));
+        }
         if recreate && !self.in_memory {
             try_remove_file(self.path.to_string()).await?;
         }
@@ -185,22 +567,49 @@ impl SqliteStoreOptions {
                     Some(method),
                     pass_key,
                     profile,
+                    &self.unlock_policy,
+                    self.max_cached_profiles,
+                    self.key_cache_ttl,
+                    self.strict_key_resolution,
+                    self.preload_profiles.as_ref(),
+                    self.schema_compat,
+                    self.upgrade_legacy_config,
                     self.path.to_string(),
+                    self.page_size,
+                    self.slow_query_log,
                 )
-                .await;
+                .await
+                .map(|backend| backend.with_auto_create_profiles(self.auto_create_profiles));
             }
         }
         // else: no 'config' table, assume empty database
 
         let default_profile = profile.unwrap_or_else(random_profile_name);
-        let key_cache = init_db(&conn_pool, &default_profile, method, pass_key).await?;
+        let key_cache = init_db(
+            &conn_pool,
+            &default_profile,
+            method,
+            pass_key,
+            self.profile_cipher,
+            self.name_encryption,
+            self.expiry_bucket,
+            &self.unlock_policy,
+            self.max_cached_profiles,
+            self.key_cache_ttl,
+            self.strict_key_resolution,
+            self.index_profile,
+        )
+        .await?;
 
         Ok(SqliteBackend::new(
             conn_pool,
             default_profile,
             key_cache,
             self.path.to_string(),
-        ))
+            self.page_size,
+            self.slow_query_log,
+        )
+        .with_auto_create_profiles(self.auto_create_profiles))
     }
 
     /// Open an existing Sqlite store from this set of configuration options
@@ -215,10 +624,10 @@ impl SqliteStoreOptions {
             Err(SqlxError::Database(db_err)) => {
                 if db_err.code().expect("Expected SQLite error code") == "14" {
                     // SQLITE_CANTOPEN error
-                    Err(err_msg!(
-                        NotFound,
-                        "The requested database path was not found"
-                    ))
+                    Err(
+                        err_msg!(NotFound, "The requested database path was not found")
+                            .with_cause(StoreNotFound),
+                    )
                 } else {
                     Err(err_msg!(Backend, "Error connecting to database pool")
                         .with_cause(SqlxError::Database(db_err)))
@@ -226,7 +635,85 @@ impl SqliteStoreOptions {
             }
             Err(err) => Err(err.into()),
         }?;
-        open_db(conn_pool, method, pass_key, profile, self.path.to_string()).await
+        open_db(
+            conn_pool,
+            method,
+            pass_key,
+            profile,
+            &self.unlock_policy,
+            self.max_cached_profiles,
+            self.key_cache_ttl,
+            self.strict_key_resolution,
+            self.preload_profiles.as_ref(),
+            self.schema_compat,
+            self.upgrade_legacy_config,
+            self.path.to_string(),
+            self.page_size,
+            self.slow_query_log,
+        )
+        .await
+        .map(|backend| backend.with_auto_create_profiles(self.auto_create_profiles))
+    }
+
+    /// Restore a snapshot written by `snapshot_to`, replacing the contents of this store
+    ///
+    /// The snapshot is verified before the store is modified: the integrity of the
+    /// database is checked, and it must open using the given key method, pass key
+    /// and profile. The store must not be in use by other instances. The restored
+    /// store is then opened and returned.
+    pub async fn restore_snapshot(
+        self,
+        snapshot_path: &str,
+        method: Option<StoreKeyMethod>,
+        pass_key: PassKey<'_>,
+        profile: Option<String>,
+    ) -> Result<SqliteBackend, Error> {
+        if self.schema_compat.is_read_only() {
+            return Err(err_msg!(Input, "Cannot restore a store in read-only mode"));
+        }
+        let mut source = snapshot::open_snapshot(snapshot_path).await?;
+        snapshot::check_integrity(&mut source).await?;
+        let mut verify = SqliteStoreOptions::from_path(snapshot_path);
+        verify.schema_compat = SchemaCompat::ReadOnly;
+        verify.upgrade_legacy_config = self.upgrade_legacy_config;
+        verify.journal_mode = SqliteJournalMode::Delete;
+        verify
+            .open(method.clone(), pass_key.as_ref(), profile.clone())
+            .await
+            .map_err(|err| err_msg!(Input, "Error verifying snapshot").with_cause(err))?
+            .close()
+            .await?;
+
+        let conn_pool = self
+            .pool(true)
+            .await
+            .map_err(err_map!(Backend, "Error creating database pool"))?;
+        let mut conn = conn_pool.acquire().await?;
+        let result = snapshot::backup_database(&mut source, conn.as_mut()).await;
+        conn.return_to_pool().await;
+        source
+            .close()
+            .await
+            .map_err(err_map!(Backend, "Error closing snapshot database"))?;
+        result?;
+        open_db(
+            conn_pool,
+            method,
+            pass_key,
+            profile,
+            &self.unlock_policy,
+            self.max_cached_profiles,
+            self.key_cache_ttl,
+            self.strict_key_resolution,
+            self.preload_profiles.as_ref(),
+            self.schema_compat,
+            self.upgrade_legacy_config,
+            self.path.to_string(),
+            self.page_size,
+            self.slow_query_log,
+        )
+        .await
+        .map(|backend| backend.with_auto_create_profiles(self.auto_create_profiles))
     }
 
     /// Remove the Sqlite store defined by these configuration options
@@ -280,21 +767,56 @@ impl<'a> ManageBackend<'a> for SqliteStoreOptions {
     }
 }
 
+/// The secondary indexes on the items and items_tags tables for an index profile
+fn index_statements(index_profile: IndexProfile) -> (&'static str, &'static str) {
+    const TAG_INDEXES: &str = "
+        CREATE INDEX ix_items_tags_name_enc ON items_tags (name, SUBSTR(value, 1, 12)) WHERE plaintext=0;
+        CREATE INDEX ix_items_tags_name_plain ON items_tags (name, value) WHERE plaintext=1;";
+    match index_profile {
+        IndexProfile::Default => ("", TAG_INDEXES),
+        IndexProfile::ScanHeavy => (
+            "
+        CREATE INDEX ix_items_scan ON items (profile_id, kind, category, id);
+        CREATE INDEX ix_items_expiry ON items (expiry) WHERE expiry IS NOT NULL;",
+            TAG_INDEXES,
+        ),
+        IndexProfile::WriteHeavy => ("", ""),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn init_db(
     conn_pool: &SqlitePool,
     profile_name: &str,
     method: StoreKeyMethod,
     pass_key: PassKey<'_>,
+    profile_cipher: ProfileCipher,
+    name_encryption: NameEncryption,
+    expiry_bucket: Option<u32>,
+    unlock_policy: &UnlockPolicy,
+    max_cached_profiles: Option<usize>,
+    key_cache_ttl: Option<Duration>,
+    strict_key_resolution: bool,
+    index_profile: IndexProfile,
 ) -> Result<KeyCache, Error> {
     let (profile_key, enc_profile_key, store_key, store_key_ref) = unblock({
         let pass_key = pass_key.into_owned();
-        move || init_keys(method, pass_key)
+        move || {
+            init_keys(
+                method,
+                pass_key,
+                profile_cipher,
+                name_encryption,
+                expiry_bucket,
+            )
+        }
     })
     .await?;
 
     let mut conn = conn_pool.acquire().await?;
 
-    sqlx::query(
+    let (item_indexes, tag_indexes) = index_statements(index_profile);
+    sqlx::query(&format!(
         r#"
         BEGIN EXCLUSIVE TRANSACTION;
 
@@ -306,7 +828,12 @@ async fn init_db(
         INSERT INTO config (name, value) VALUES
             ("default_profile", ?1),
             ("key", ?2),
-            ("version", "1");
+            ("profile_cipher", ?4),
+            ("name_encryption", ?6),
+            ("expiry_bucket", ?7),
+            ("unlock_policy", ?9),
+            ("provisioned_by", ?5),
+            ("version", ?8);
 
         CREATE TABLE profiles (
             id INTEGER NOT NULL,
@@ -330,6 +857,7 @@ async fn init_db(
                 ON DELETE CASCADE ON UPDATE CASCADE
         );
         CREATE UNIQUE INDEX ix_items_uniq ON items (profile_id, kind, category, name);
+        {item_indexes}
 
         CREATE TABLE items_tags (
             id INTEGER NOT NULL,
@@ -342,20 +870,33 @@ async fn init_db(
                 ON DELETE CASCADE ON UPDATE CASCADE
         );
         CREATE INDEX ix_items_tags_item_id ON items_tags (item_id);
-        CREATE INDEX ix_items_tags_name_enc ON items_tags (name, SUBSTR(value, 1, 12)) WHERE plaintext=0;
-        CREATE INDEX ix_items_tags_name_plain ON items_tags (name, value) WHERE plaintext=1;
+        {tag_indexes}
 
         INSERT INTO profiles (name, profile_key) VALUES (?1, ?3);
 
+        CREATE TABLE schema_migrations (
+            version INTEGER NOT NULL,
+            description TEXT NOT NULL,
+            applied_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (version)
+        );
+
         COMMIT;
     "#,
-    )
+    ))
     .persistent(false)
     .bind(profile_name)
     .bind(store_key_ref)
     .bind(enc_profile_key)
+    .bind(profile_cipher.as_str())
+    .bind(schema_writer())
+    .bind(name_encryption.as_str())
+    .bind(expiry_bucket.map(|bucket| bucket.to_string()))
+    .bind(STORE_VERSION)
+    .bind(unlock_policy.to_config())
     .execute(conn.as_mut())
-    .await.map_err(err_map!(Backend, "Error creating database tables"))?;
+    .await
+    .map_err(err_map!(Backend, "Error creating database tables"))?;
 
     let row = sqlx::query("SELECT id FROM profiles WHERE name = ?1")
         .persistent(false)
@@ -363,34 +904,57 @@ async fn init_db(
         .fetch_one(conn.as_mut())
         .await
         .map_err(err_map!(Backend, "Error checking for existing profile"))?;
+    apply_schema_migrations(conn.as_mut(), SCHEMA_MIGRATIONS, SchemaCompat::Strict).await?;
+    update_config_mac(conn.as_mut(), &store_key, 0).await?;
     conn.return_to_pool().await;
 
     let mut key_cache = KeyCache::new(store_key);
+    key_cache.profile_cipher = profile_cipher;
+    key_cache.name_encryption = name_encryption;
+    key_cache.expiry_bucket = expiry_bucket;
+    key_cache.set_max_size(max_cached_profiles);
+    key_cache.set_ttl(key_cache_ttl);
+    key_cache.set_strict(strict_key_resolution);
     key_cache.add_profile_mut(profile_name.to_string(), row.try_get(0)?, profile_key);
 
     Ok(key_cache)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn open_db(
     conn_pool: SqlitePool,
     method: Option<StoreKeyMethod>,
     pass_key: PassKey<'_>,
     profile: Option<String>,
+    unlock_policy: &UnlockPolicy,
+    max_cached_profiles: Option<usize>,
+    key_cache_ttl: Option<Duration>,
+    strict_key_resolution: bool,
+    preload_profiles: Option<&ProfilePreload>,
+    schema_compat: SchemaCompat,
+    upgrade_legacy_config: bool,
     path: String,
+    page_size: usize,
+    slow_query_log: SlowQueryLog,
 ) -> Result<SqliteBackend, Error> {
     let mut conn = conn_pool.acquire().await?;
     let mut ver_ok = false;
     let mut default_profile: Option<String> = None;
     let mut store_key_ref: Option<String> = None;
+    let mut unlock_failures = UnlockFailures::default();
+    let mut recorded_policy = UnlockPolicy::default();
+    let mut profile_cipher = ProfileCipher::default();
+    let mut name_encryption = NameEncryption::default();
+    let mut expiry_bucket = None;
+    let mut key_generation = None;
+    let mut protected_config = Vec::new();
 
-    let config = sqlx::query(
-        r#"SELECT name, value FROM config
-        WHERE name IN ("default_profile", "key", "version")"#,
-    )
-    .fetch_all(conn.as_mut())
-    .await
-    .map_err(err_map!(Backend, "Error fetching store configuration"))?;
+    let config = sqlx::query("SELECT name, value FROM config")
+        .fetch_all(conn.as_mut())
+        .await
+        .map_err(err_map!(Backend, "Error fetching store configuration"))?;
     for row in config {
+        protected_config.push((row.try_get(0)?, row.try_get(1)?));
         match row.try_get(0)? {
             "default_profile" => {
                 default_profile.replace(row.try_get(1)?);
@@ -398,12 +962,31 @@ async fn open_db(
             "key" => {
                 store_key_ref.replace(row.try_get(1)?);
             }
+            PROFILE_CIPHER_CONFIG => {
+                profile_cipher = ProfileCipher::from_str(row.try_get(1)?)?;
+            }
+            NAME_ENCRYPTION_CONFIG => {
+                name_encryption = NameEncryption::from_str(row.try_get(1)?)?;
+            }
+            EXPIRY_BUCKET_CONFIG => {
+                expiry_bucket = parse_expiry_bucket(row.try_get(1)?)?;
+            }
             "version" => {
-                if row.try_get::<&str, _>(1)? != "1" {
+                let version = row.try_get::<&str, _>(1)?;
+                if version != STORE_VERSION && version != LEGACY_STORE_VERSION {
                     return Err(err_msg!(Unsupported, "Unsupported store version"));
                 }
                 ver_ok = true;
             }
+            UNLOCK_FAILURES_CONFIG => {
+                unlock_failures = UnlockFailures::parse(row.try_get(1)?)?;
+            }
+            UNLOCK_POLICY_CONFIG => {
+                recorded_policy = UnlockPolicy::parse_config(row.try_get(1)?)?;
+            }
+            KEY_GENERATION_CONFIG => {
+                key_generation = row.try_get::<Option<String>, _>(1)?;
+            }
             _ => (),
         }
     }
@@ -413,6 +996,9 @@ async fn open_db(
     let profile = profile
         .or(default_profile)
         .ok_or_else(|| err_msg!(Unsupported, "Default store profile not found"))?;
+    // the policy given when opening the store may only add restrictions
+    let unlock_policy = &recorded_policy.restrict(unlock_policy);
+    unlock_policy.check(&unlock_failures)?;
     let store_key = if let Some(store_key_ref) = store_key_ref {
         let wrap_ref = StoreKeyReference::parse_uri(&store_key_ref)?;
         if let Some(method) = method {
@@ -430,16 +1016,405 @@ async fn open_db(
     };
 
     let mut key_cache = KeyCache::new(store_key);
+    key_cache.profile_cipher = profile_cipher;
+    key_cache.name_encryption = name_encryption;
+    key_cache.expiry_bucket = expiry_bucket;
+    key_cache.set_max_size(max_cached_profiles);
+    key_cache.set_ttl(key_cache_ttl);
+    key_cache.set_strict(strict_key_resolution);
+    key_cache.key_generation = parse_key_generation(key_generation.as_deref())?;
     let row = sqlx::query("SELECT id, profile_key FROM profiles WHERE name = ?1")
         .bind(&profile)
         .fetch_one(conn.as_mut())
         .await?;
     let profile_id = row.try_get(0)?;
-    let profile_key = key_cache.load_key(row.try_get(1)?).await?;
+    let profile_key = match key_cache.load_key(row.try_get(1)?).await {
+        Ok(key) => key,
+        Err(err) if err.kind() == ErrorKind::Encryption => {
+            // the pass key did not match: record the failure and delay the response
+            update_unlock_failures(
+                conn.as_mut(),
+                Some(unlock_failures.increment()),
+                schema_compat.is_read_only(),
+            )
+            .await
+            .map_err(err_map!(Backend, "Error recording unlock failure"))?;
+            conn.return_to_pool().await;
+            sleep(unlock_policy.failure_delay(&unlock_failures)).await;
+            return Err(err);
+        }
+        Err(err) => return Err(err),
+    };
+    if !unlock_failures.is_empty() {
+        update_unlock_failures(conn.as_mut(), None, schema_compat.is_read_only())
+            .await
+            .map_err(err_map!(Backend, "Error resetting unlock failures"))?;
+    }
+    // the configuration is verified once the store key is known to be correct
+    if key_cache
+        .store_key
+        .verify_config_mac(&protected_config, upgrade_legacy_config)?
+        && !schema_compat.is_read_only()
+    {
+        upgrade_config_version(conn.as_mut(), &key_cache).await?;
+    }
+    apply_schema_migrations(conn.as_mut(), SCHEMA_MIGRATIONS, schema_compat).await?;
+    if !schema_compat.is_read_only() {
+        update_namespace_index(conn.as_mut(), &key_cache).await?;
+    }
+    if let Some(preload) = preload_profiles {
+        let rows = sqlx::query("SELECT id, name, profile_key FROM profiles")
+            .fetch_all(conn.as_mut())
+            .await
+            .map_err(err_map!(Backend, "Error fetching profiles to preload"))?;
+        let mut profiles = Vec::with_capacity(rows.len());
+        for row in rows {
+            let name: String = row.try_get(1)?;
+            if name != profile && preload.includes(&name) {
+                profiles.push((name, row.try_get(0)?, row.try_get(2)?));
+            }
+        }
+        key_cache.preload_profiles(profiles).await?;
+    }
     conn.return_to_pool().await;
     key_cache.add_profile_mut(profile.clone(), profile_id, profile_key);
 
-    Ok(SqliteBackend::new(conn_pool, profile, key_cache, path))
+    Ok(SqliteBackend::new(
+        conn_pool,
+        profile,
+        key_cache,
+        path,
+        page_size,
+        slow_query_log,
+    ))
+}
+
+/// Compute the category namespace index of the records written before the
+/// index was added to the store, when scheduled by a schema migration
+///
+/// The records of each profile are updated in batches, each in a separate
+/// transaction, so that an interrupted update resumes when the store is next opened.
+async fn update_namespace_index(
+    conn: &mut SqliteConnection,
+    key_cache: &KeyCache,
+) -> Result<(), Error> {
+    let scheduled = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM config WHERE name = ?1")
+        .bind(NAMESPACE_BACKFILL_CONFIG)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error fetching store configuration"))?
+        > 0;
+    if !scheduled {
+        return Ok(());
+    }
+    let profiles = sqlx::query_as::<_, (i64, Vec<u8>)>(
+        "SELECT id, profile_key FROM profiles
+        WHERE id IN (SELECT profile_id FROM items WHERE namespace IS NULL)",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(err_map!(Backend, "Error fetching profiles"))?;
+    for (pid, enc_key) in profiles {
+        let key = Arc::new(key_cache.load_key(enc_key).await?);
+        loop {
+            let mut txn = conn.begin().await?;
+            let items = sqlx::query_as::<_, EncItemNames>(
+                "SELECT id, kind, category, name, enc_names FROM items
+                WHERE profile_id = ?1 AND namespace IS NULL LIMIT ?2",
+            )
+            .bind(pid)
+            .bind(NAMESPACE_BACKFILL_BATCH_SIZE)
+            .fetch_all(txn.as_mut())
+            .await?;
+            if items.is_empty() {
+                break;
+            }
+            let items = unblock({
+                let key = key.clone();
+                move || backfill_namespaces(&key, pid, items)
+            })
+            .await?;
+            for (id, namespace) in items {
+                sqlx::query("UPDATE items SET namespace = ?1 WHERE id = ?2")
+                    .bind(namespace)
+                    .bind(id)
+                    .execute(txn.as_mut())
+                    .await?;
+            }
+            txn.commit().await?;
+        }
+    }
+    sqlx::query("DELETE FROM config WHERE name = ?1")
+        .bind(NAMESPACE_BACKFILL_CONFIG)
+        .execute(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error updating store configuration"))?;
+    Ok(())
+}
+
+/// Record or reset the count of failed attempts to unlock the store
+///
+/// The count is updated even when the store is opened in read-only mode, so
+/// that the unlock policy cannot be bypassed by opening the store read-only.
+async fn update_unlock_failures(
+    conn: &mut SqliteConnection,
+    failures: Option<String>,
+    read_only: bool,
+) -> Result<(), SqlxError> {
+    if read_only {
+        sqlx::query("PRAGMA query_only = 0")
+            .execute(&mut *conn)
+            .await?;
+    }
+    let result = if let Some(failures) = failures {
+        sqlx::query("INSERT OR REPLACE INTO config (name, value) VALUES (?1, ?2)")
+            .bind(UNLOCK_FAILURES_CONFIG)
+            .bind(failures)
+            .execute(&mut *conn)
+            .await
+    } else {
+        sqlx::query("DELETE FROM config WHERE name = ?1")
+            .bind(UNLOCK_FAILURES_CONFIG)
+            .execute(&mut *conn)
+            .await
+    };
+    if read_only {
+        sqlx::query("PRAGMA query_only = 1")
+            .execute(&mut *conn)
+            .await?;
+    }
+    result?;
+    Ok(())
+}
+
+/// Upgrade a store in the legacy format to the current format, protecting its
+/// configuration with a MAC
+async fn upgrade_config_version(
+    conn: &mut SqliteConnection,
+    key_cache: &KeyCache,
+) -> Result<(), Error> {
+    let mut txn = conn.begin().await?;
+    sqlx::query("UPDATE config SET value = ?1 WHERE name = 'version' AND value = ?2")
+        .bind(STORE_VERSION)
+        .bind(LEGACY_STORE_VERSION)
+        .execute(txn.as_mut())
+        .await
+        .map_err(err_map!(Backend, "Error upgrading store configuration"))?;
+    update_config_mac(txn.as_mut(), &key_cache.store_key, key_cache.key_generation).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Record the MAC of the protected store configuration entries under the store key
+///
+/// The update fails if the recorded store key generation does not match, as
+/// the store key was then replaced by another instance.
+pub(crate) async fn update_config_mac(
+    conn: &mut SqliteConnection,
+    store_key: &StoreKey,
+    key_generation: u64,
+) -> Result<(), Error> {
+    let config = sqlx::query_as::<_, (String, Option<String>)>("SELECT name, value FROM config")
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error fetching store configuration"))?;
+    check_key_generation(
+        key_generation,
+        config
+            .iter()
+            .find(|(name, _)| name == KEY_GENERATION_CONFIG)
+            .and_then(|(_, value)| value.as_deref()),
+    )?;
+    if let Some(mac) = store_key.config_mac(&config)? {
+        sqlx::query("INSERT OR REPLACE INTO config (name, value) VALUES (?1, ?2)")
+            .bind(CONFIG_MAC_CONFIG)
+            .bind(mac)
+            .execute(&mut *conn)
+            .await
+    } else {
+        sqlx::query("DELETE FROM config WHERE name = ?1")
+            .bind(CONFIG_MAC_CONFIG)
+            .execute(&mut *conn)
+            .await
+    }
+    .map_err(err_map!(Backend, "Error updating store configuration MAC"))?;
+    Ok(())
+}
+
+/// Fetch the schema version and format information recorded in the store
+pub(crate) async fn fetch_version_info(conn: &mut SqliteConnection) -> Result<VersionInfo, Error> {
+    let config = sqlx::query_as::<_, (String, Option<String>)>("SELECT name, value FROM config")
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error fetching store configuration"))?;
+    let found = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='schema_migrations'",
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(err_map!(Backend, "Error checking for schema migrations"))?
+        == 1;
+    let schema_version = if found {
+        sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(version) FROM schema_migrations")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(err_map!(
+                Backend,
+                "Error fetching applied schema migrations"
+            ))?
+            .unwrap_or_default()
+    } else {
+        0
+    };
+    VersionInfo::from_config(config, schema_version, supported_schema_version())
+}
+
+/// Apply the pending schema migrations, each in an exclusive transaction
+async fn apply_schema_migrations(
+    conn: &mut SqliteConnection,
+    migrations: &[SchemaMigration],
+    schema_compat: SchemaCompat,
+) -> Result<(), Error> {
+    let found = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='schema_migrations'",
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(err_map!(Backend, "Error checking for schema migrations"))?
+        == 1;
+    let applied = if found {
+        sqlx::query_scalar::<_, i64>("SELECT version FROM schema_migrations")
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(err_map!(
+                Backend,
+                "Error fetching applied schema migrations"
+            ))?
+    } else {
+        Vec::new()
+    };
+    if let Some(version) = newer_schema_version(migrations, &applied) {
+        if schema_compat.is_read_only() {
+            return Ok(());
+        }
+        let writer = sqlx::query_scalar::<_, String>("SELECT value FROM config WHERE name = ?1")
+            .bind(SCHEMA_WRITER_CONFIG)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(err_map!(Backend, "Error fetching store configuration"))?;
+        return Err(newer_schema_error(migrations, version, writer.as_deref()));
+    }
+    let pending = pending_migrations(migrations, &applied)?;
+    if pending.is_empty() || schema_compat.is_read_only() {
+        // avoid writing to the database when the schema is current
+        return Ok(());
+    }
+    if !found {
+        // stores provisioned before the introduction of schema migrations
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER NOT NULL,
+                description TEXT NOT NULL,
+                applied_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (version)
+            )",
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error creating schema migrations table"))?;
+    }
+
+    for migration in pending {
+        if migration.version == INCREMENTAL_VACUUM_MIGRATION {
+            enable_incremental_vacuum(&mut *conn).await?;
+        }
+        sqlx::query("BEGIN IMMEDIATE TRANSACTION")
+            .execute(&mut *conn)
+            .await
+            .map_err(err_map!(Backend, "Error starting schema migration"))?;
+        match apply_schema_migration(&mut *conn, migration).await {
+            Ok(()) => {
+                sqlx::query("COMMIT")
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(err_map!(Backend, "Error committing schema migration"))?;
+            }
+            Err(err) => {
+                sqlx::query("ROLLBACK").execute(&mut *conn).await.ok();
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rebuild the database with incremental vacuum enabled, if necessary
+///
+/// The auto-vacuum mode of an existing database only changes once it is
+/// rebuilt, which copies the entire database and requires exclusive access.
+async fn enable_incremental_vacuum(conn: &mut SqliteConnection) -> Result<(), Error> {
+    let auto_vacuum: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error fetching auto-vacuum mode"))?;
+    // incremental mode is reported as 2
+    if auto_vacuum != 2 {
+        sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+            .execute(&mut *conn)
+            .await
+            .map_err(err_map!(Backend, "Error setting auto-vacuum mode"))?;
+        sqlx::query("VACUUM")
+            .execute(&mut *conn)
+            .await
+            .map_err(err_map!(Backend, "Error rebuilding database"))?;
+    }
+    Ok(())
+}
+
+async fn apply_schema_migration(
+    conn: &mut SqliteConnection,
+    migration: &SchemaMigration,
+) -> Result<(), Error> {
+    // the migration may have been applied by another connection
+    // before the write lock was acquired
+    let applied =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM schema_migrations WHERE version = ?1")
+            .bind(migration.version)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(err_map!(
+                Backend,
+                "Error fetching applied schema migrations"
+            ))?;
+    if applied > 0 {
+        return Ok(());
+    }
+    sqlx::query(migration.statements)
+        .persistent(false)
+        .execute(&mut *conn)
+        .await
+        .map_err(|err| {
+            err_msg!(
+                Backend,
+                "Error applying schema migration {}: {}",
+                migration.version,
+                migration.description
+            )
+            .with_cause(err)
+        })?;
+    sqlx::query("INSERT INTO schema_migrations (version, description) VALUES (?1, ?2)")
+        .bind(migration.version)
+        .bind(migration.description)
+        .execute(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error recording schema migration"))?;
+    sqlx::query("INSERT OR REPLACE INTO config (name, value) VALUES (?1, ?2)")
+        .bind(SCHEMA_WRITER_CONFIG)
+        .bind(schema_writer())
+        .execute(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error recording schema migration"))?;
+    Ok(())
 }
 
 async fn try_remove_file(path: String) -> Result<bool, Error> {
@@ -467,3 +1442,530 @@ async fn try_remove_file(path: String) -> Result<bool, Error> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::{db_utils::PAGE_SIZE, Backend, BackendSession},
+        entry::{EntryKind, EntryOperation},
+        future::block_on,
+        protect::generate_raw_store_key,
+    };
+
+    #[test]
+    fn sqlite_parse_uri() {
+        let opts = SqliteStoreOptions::new(
+            "sqlite://:memory:?busy_timeout=250&max_connections=3&warm_connections=2\
+            &statement_cache_capacity=0\
+            &journal_mode=delete&synchronous=normal&cache_size=-4000&mmap_size=268435456\
+            &secure_delete=fast&slow_query_ms=100&slow_query_explain=true",
+        )
+        .unwrap();
+        assert!(opts.in_memory);
+        assert_eq!(opts.busy_timeout, Duration::from_millis(250));
+        assert_eq!(opts.max_connections, 3);
+        assert_eq!(opts.warm_connections, 2);
+        assert_eq!(opts.statement_cache_capacity, 0);
+        assert_eq!(opts.page_size, PAGE_SIZE);
+        assert_eq!(opts.journal_mode, SqliteJournalMode::Delete);
+        assert_eq!(opts.synchronous, SqliteSynchronous::Normal);
+        assert_eq!(opts.cache_size, Some(-4000));
+        assert_eq!(opts.mmap_size, Some(268435456));
+        assert_eq!(opts.secure_delete, Some(SqliteSecureDelete::Fast));
+        assert_eq!(
+            opts.slow_query_log.threshold,
+            Some(Duration::from_millis(100))
+        );
+        assert!(opts.slow_query_log.explain);
+        assert!(SqliteStoreOptions::new("sqlite://:memory:?slow_query_ms=fast").is_err());
+        assert!(SqliteStoreOptions::new("sqlite://:memory:?slow_query_explain=true").is_err());
+        assert!(SqliteStoreOptions::new("sqlite://:memory:?mmap_size=-1").is_err());
+        assert!(SqliteStoreOptions::new("sqlite://:memory:?journal_mode=other").is_err());
+        assert!(SqliteStoreOptions::new("sqlite://:memory:?secure_delete=maybe").is_err());
+        assert!(SqliteStoreOptions::new("sqlite://:memory:?strict_key_resolution=1x").is_err());
+        assert_eq!(
+            SqliteStoreOptions::new("sqlite://:memory:")
+                .unwrap()
+                .statement_cache_capacity,
+            DEFAULT_STATEMENT_CACHE_CAPACITY
+        );
+        assert!(SqliteStoreOptions::new("sqlite://:memory:?index_profile=other").is_err());
+    }
+
+    #[test]
+    fn sqlite_index_profile() {
+        let key = generate_raw_store_key(None).unwrap();
+        for (profile, expect_scan, expect_tags) in [
+            (IndexProfile::Default, false, true),
+            (IndexProfile::ScanHeavy, true, true),
+            (IndexProfile::WriteHeavy, false, false),
+        ] {
+            let uri = format!("sqlite://:memory:?index_profile={}", profile);
+            let opts = SqliteStoreOptions::new(uri.as_str()).unwrap();
+            assert_eq!(opts.index_profile, profile);
+            let indexes: Vec<String> = block_on(async {
+                let backend = opts
+                    .provision(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                    .await
+                    .unwrap();
+                let indexes =
+                    sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type='index'")
+                        .fetch_all(&backend.conn_pool)
+                        .await
+                        .unwrap();
+                backend.close().await.unwrap();
+                indexes
+            });
+            assert!(indexes.iter().any(|name| name == "ix_items_uniq"));
+            assert_eq!(
+                indexes.iter().any(|name| name == "ix_items_scan"),
+                expect_scan
+            );
+            assert_eq!(
+                indexes.iter().any(|name| name == "ix_items_expiry"),
+                expect_scan
+            );
+            assert_eq!(
+                indexes
+                    .iter()
+                    .any(|name| name == "ix_items_tags_name_plain"),
+                expect_tags
+            );
+        }
+    }
+
+    #[test]
+    fn sqlite_mmap_size() {
+        let key = generate_raw_store_key(None).unwrap();
+        let path = std::env::temp_dir().join(format!("sqlite-mmap-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_string_lossy().into_owned();
+        let opts = SqliteStoreOptions::new(format!("sqlite://{}?mmap_size=1048576", path).as_str())
+            .unwrap();
+        let mmap_size: i64 = block_on(async {
+            let backend = opts
+                .provision(StoreKeyMethod::RawKey, key.as_ref(), None, true)
+                .await
+                .unwrap();
+            let mmap_size = sqlx::query_scalar("PRAGMA mmap_size")
+                .fetch_one(&backend.conn_pool)
+                .await
+                .unwrap();
+            backend.close().await.unwrap();
+            try_remove_file(path).await.unwrap();
+            mmap_size
+        });
+        assert_eq!(mmap_size, 1048576);
+    }
+
+    #[test]
+    fn sqlite_secure_delete() {
+        let key = generate_raw_store_key(None).unwrap();
+        for (mode, expected) in [("off", 0), ("on", 1), ("fast", 2)] {
+            let uri = format!("sqlite://:memory:?secure_delete={}", mode);
+            let opts = SqliteStoreOptions::new(uri.as_str()).unwrap();
+            let secure_delete: i64 = block_on(async {
+                let backend = opts
+                    .provision(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                    .await
+                    .unwrap();
+                let secure_delete = sqlx::query_scalar("PRAGMA secure_delete")
+                    .fetch_one(&backend.conn_pool)
+                    .await
+                    .unwrap();
+                backend.close().await.unwrap();
+                secure_delete
+            });
+            assert_eq!(secure_delete, expected);
+        }
+    }
+
+    async fn versions(conn: &mut SqliteConnection) -> Result<Vec<i64>, SqlxError> {
+        sqlx::query_scalar("SELECT version FROM schema_migrations ORDER BY version")
+            .fetch_all(conn)
+            .await
+    }
+
+    #[test]
+    fn sqlite_schema_migrations() {
+        const MIGRATIONS: &[SchemaMigration] = &[
+            SchemaMigration {
+                version: 1001,
+                description: "add migrated table",
+                statements: "CREATE TABLE migrated (id INTEGER NOT NULL);
+                    INSERT INTO migrated (id) VALUES (1);",
+            },
+            SchemaMigration {
+                version: 1002,
+                description: "add migrated index",
+                statements: "CREATE INDEX ix_migrated ON migrated (id);",
+            },
+        ];
+        const FAILED: SchemaMigration = SchemaMigration {
+            version: 1003,
+            description: "invalid migration",
+            statements: "CREATE TABLE failed (id INTEGER); INSERT INTO missing VALUES (1);",
+        };
+
+        let key = generate_raw_store_key(None).unwrap();
+        block_on(async {
+            let backend = SqliteStoreOptions::new("sqlite://:memory:")
+                .unwrap()
+                .provision(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .unwrap();
+            let mut conn = backend.conn_pool.acquire().await.unwrap();
+            assert_eq!(
+                versions(conn.as_mut()).await.unwrap(),
+                SCHEMA_MIGRATIONS
+                    .iter()
+                    .map(|migration| migration.version)
+                    .collect::<Vec<_>>()
+            );
+
+            // stores provisioned without the migrations table are upgraded
+            sqlx::query("DROP TABLE schema_migrations")
+                .execute(conn.as_mut())
+                .await
+                .unwrap();
+            apply_schema_migrations(conn.as_mut(), &MIGRATIONS[..1], SchemaCompat::Strict)
+                .await
+                .unwrap();
+            assert_eq!(versions(conn.as_mut()).await.unwrap(), vec![1001]);
+
+            // applied migrations are skipped
+            apply_schema_migrations(conn.as_mut(), MIGRATIONS, SchemaCompat::Strict)
+                .await
+                .unwrap();
+            apply_schema_migrations(conn.as_mut(), MIGRATIONS, SchemaCompat::Strict)
+                .await
+                .unwrap();
+            assert_eq!(versions(conn.as_mut()).await.unwrap(), vec![1001, 1002]);
+            let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM migrated")
+                .fetch_one(conn.as_mut())
+                .await
+                .unwrap();
+            assert_eq!(rows, 1);
+
+            // a failed migration is rolled back and not recorded
+            let migrations = [MIGRATIONS[0], MIGRATIONS[1], FAILED];
+            assert!(
+                apply_schema_migrations(conn.as_mut(), &migrations, SchemaCompat::Strict)
+                    .await
+                    .is_err()
+            );
+            assert_eq!(versions(conn.as_mut()).await.unwrap(), vec![1001, 1002]);
+            let failed: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='failed'",
+            )
+            .fetch_one(conn.as_mut())
+            .await
+            .unwrap();
+            assert_eq!(failed, 0);
+
+            conn.return_to_pool().await;
+            backend.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn sqlite_incremental_vacuum_migration() {
+        let key = generate_raw_store_key(None).unwrap();
+        block_on(async {
+            let backend = SqliteStoreOptions::new("sqlite://:memory:")
+                .unwrap()
+                .provision(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .unwrap();
+            let mut conn = backend.conn_pool.acquire().await.unwrap();
+            let auto_vacuum: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+                .fetch_one(conn.as_mut())
+                .await
+                .unwrap();
+            assert_eq!(auto_vacuum, 2);
+
+            // stores provisioned without incremental vacuum are rebuilt
+            sqlx::query("PRAGMA auto_vacuum = NONE; VACUUM;")
+                .execute(conn.as_mut())
+                .await
+                .unwrap();
+            sqlx::query("DELETE FROM schema_migrations WHERE version = ?1")
+                .bind(INCREMENTAL_VACUUM_MIGRATION)
+                .execute(conn.as_mut())
+                .await
+                .unwrap();
+            apply_schema_migrations(conn.as_mut(), SCHEMA_MIGRATIONS, SchemaCompat::Strict)
+                .await
+                .unwrap();
+            let auto_vacuum: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+                .fetch_one(conn.as_mut())
+                .await
+                .unwrap();
+            assert_eq!(auto_vacuum, 2);
+            assert_eq!(
+                versions(conn.as_mut()).await.unwrap().last(),
+                Some(&INCREMENTAL_VACUUM_MIGRATION)
+            );
+
+            conn.return_to_pool().await;
+            backend.close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn sqlite_schema_newer() {
+        let key = generate_raw_store_key(None).unwrap();
+        let path = std::env::temp_dir().join(format!("sqlite-schema-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_string_lossy().into_owned();
+        block_on(async {
+            let backend = SqliteStoreOptions::new(format!("sqlite://{}", path).as_str())
+                .unwrap()
+                .provision(StoreKeyMethod::RawKey, key.as_ref(), None, true)
+                .await
+                .unwrap();
+            let writer: String =
+                sqlx::query_scalar("SELECT value FROM config WHERE name = 'schema_writer'")
+                    .fetch_one(&backend.conn_pool)
+                    .await
+                    .unwrap();
+            assert_eq!(writer, schema_writer());
+            // simulate an upgrade by a newer library version
+            sqlx::query(
+                "INSERT INTO schema_migrations (version, description) VALUES (9999, 'future');
+                UPDATE config SET value = 'askar-storage 99.0.0' WHERE name = 'schema_writer';",
+            )
+            .execute(&backend.conn_pool)
+            .await
+            .unwrap();
+            backend.close().await.unwrap();
+
+            let err = SqliteStoreOptions::new(format!("sqlite://{}", path).as_str())
+                .unwrap()
+                .open(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect_err("Expected newer schema to be refused");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+            assert!(err
+                .message()
+                .unwrap()
+                .contains("version 9999 (written by askar-storage 99.0.0)"));
+
+            let backend = SqliteStoreOptions::new(
+                format!("sqlite://{}?schema_compat=read_only", path).as_str(),
+            )
+            .unwrap()
+            .open(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+            .await
+            .expect("Error opening store in read-only mode");
+            let mut session = backend.session(None, false).unwrap();
+            assert_eq!(session.count(None, None, None).await.unwrap(), 0);
+            session
+                .update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    "name",
+                    Some(b"value"),
+                    None,
+                    None,
+                )
+                .await
+                .expect_err("Expected write to be refused");
+            drop(session);
+            backend.close().await.unwrap();
+
+            assert!(SqliteStoreOptions::new(
+                format!("sqlite://{}?schema_compat=read_only", path).as_str()
+            )
+            .unwrap()
+            .provision(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+            .await
+            .is_err());
+            try_remove_file(path).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn sqlite_namespace_backfill() {
+        assert!(SCHEMA_MIGRATIONS
+            .iter()
+            .any(|migration| migration.statements.contains(NAMESPACE_BACKFILL_CONFIG)));
+
+        let key = generate_raw_store_key(None).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("sqlite-namespace-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_string_lossy().into_owned();
+        let open = |query: &str| {
+            let options = SqliteStoreOptions::new(format!("sqlite://{}{}", path, query).as_str());
+            let key = key.as_ref();
+            async move {
+                options
+                    .unwrap()
+                    .open(Some(StoreKeyMethod::RawKey), key, None)
+                    .await
+                    .unwrap()
+            }
+        };
+        block_on(async {
+            let backend = SqliteStoreOptions::new(format!("sqlite://{}", path).as_str())
+                .unwrap()
+                .provision(StoreKeyMethod::RawKey, key.as_ref(), None, true)
+                .await
+                .unwrap();
+            let mut session = backend.session(None, false).unwrap();
+            for category in ["credential/w3c", "credential/w3c/license", "other"] {
+                session
+                    .update(
+                        EntryKind::Item,
+                        EntryOperation::Insert,
+                        category,
+                        "name",
+                        Some(b"value"),
+                        None,
+                        None,
+                    )
+                    .await
+                    .unwrap();
+            }
+            drop(session);
+            // simulate records written before the namespace index was added
+            sqlx::query(
+                "UPDATE items SET namespace = NULL;
+                INSERT INTO config (name, value) VALUES ('namespace_backfill', '1');",
+            )
+            .execute(&backend.conn_pool)
+            .await
+            .unwrap();
+            let mut session = backend.session(None, false).unwrap();
+            let err = session
+                .count_namespace(None, "credential", None)
+                .await
+                .expect_err("Expected incomplete namespace index error");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+            drop(session);
+            backend.close().await.unwrap();
+
+            // the index is not updated when writes are disabled
+            let backend = open("?schema_compat=read_only").await;
+            let mut session = backend.session(None, false).unwrap();
+            assert!(session
+                .count_namespace(None, "credential", None)
+                .await
+                .is_err());
+            drop(session);
+            backend.close().await.unwrap();
+
+            let backend = open("").await;
+            let mut session = backend.session(None, false).unwrap();
+            assert_eq!(
+                session
+                    .count_namespace(None, "credential", None)
+                    .await
+                    .unwrap(),
+                2
+            );
+            drop(session);
+            let scheduled: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM config WHERE name = ?1")
+                .bind(NAMESPACE_BACKFILL_CONFIG)
+                .fetch_one(&backend.conn_pool)
+                .await
+                .unwrap();
+            assert_eq!(scheduled, 0);
+            backend.close().await.unwrap();
+            try_remove_file(path.clone()).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn sqlite_config_mac() {
+        let key = generate_raw_store_key(None).unwrap();
+        let path = std::env::temp_dir().join(format!("sqlite-config-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_string_lossy().into_owned();
+        let open = |query: &str| {
+            let options = SqliteStoreOptions::new(format!("sqlite://{}{}", path, query).as_str());
+            let key = key.as_ref();
+            async move {
+                options
+                    .unwrap()
+                    .open(Some(StoreKeyMethod::RawKey), key, None)
+                    .await
+            }
+        };
+        block_on(async {
+            let backend = SqliteStoreOptions::new(format!("sqlite://{}", path).as_str())
+                .unwrap()
+                .provision(StoreKeyMethod::RawKey, key.as_ref(), None, true)
+                .await
+                .unwrap();
+            let initial = backend.get_active_profile();
+            let profile = backend.create_profile(None).await.unwrap();
+            backend.set_default_profile(profile.clone()).await.unwrap();
+            backend.close().await.unwrap();
+
+            // configuration changes made through the store are accepted
+            let backend = open("").await.unwrap();
+            assert_eq!(backend.get_active_profile(), profile);
+            sqlx::query("UPDATE config SET value = ?1 WHERE name = 'default_profile'")
+                .bind(&initial)
+                .execute(&backend.conn_pool)
+                .await
+                .unwrap();
+            backend.close().await.unwrap();
+
+            for query in ["", "?schema_compat=read_only"] {
+                let err = open(query)
+                    .await
+                    .expect_err("Expected modified configuration to be refused");
+                assert_eq!(err.kind(), ErrorKind::Encryption);
+            }
+
+            // a missing MAC is refused unless the store is in the legacy format
+            let pool = SqlitePool::connect(format!("sqlite://{}", path).as_str())
+                .await
+                .unwrap();
+            sqlx::query("DELETE FROM config WHERE name = ?1")
+                .bind(CONFIG_MAC_CONFIG)
+                .execute(&pool)
+                .await
+                .unwrap();
+            for query in ["", "?upgrade_legacy_config=true"] {
+                let err = open(query)
+                    .await
+                    .expect_err("Expected missing configuration MAC to be refused");
+                assert_eq!(err.kind(), ErrorKind::Encryption);
+            }
+            sqlx::query("UPDATE config SET value = ?1 WHERE name = 'version'")
+                .bind(LEGACY_STORE_VERSION)
+                .execute(&pool)
+                .await
+                .unwrap();
+            pool.close().await;
+            assert_eq!(
+                open("?upgrade_legacy_config=false")
+                    .await
+                    .expect_err("Expected legacy store to be refused")
+                    .kind(),
+                ErrorKind::Unsupported
+            );
+            let backend = open("").await.unwrap();
+            assert_eq!(backend.get_active_profile(), initial);
+            let mac: Option<String> =
+                sqlx::query_scalar("SELECT value FROM config WHERE name = ?1")
+                    .bind(CONFIG_MAC_CONFIG)
+                    .fetch_optional(&backend.conn_pool)
+                    .await
+                    .unwrap();
+            assert!(mac.is_some());
+            assert_eq!(
+                backend.version_info().await.unwrap().store_version,
+                STORE_VERSION
+            );
+            backend.close().await.unwrap();
+            let backend = open("").await.unwrap();
+            assert_eq!(backend.get_active_profile(), initial);
+            backend.close().await.unwrap();
+
+            try_remove_file(path.clone()).await.unwrap();
+        });
+    }
+}
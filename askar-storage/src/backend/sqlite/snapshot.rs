@@ -0,0 +1,234 @@
+//! Point-in-time snapshots using the SQLite online backup API
+
+use std::{fs::File, os::raw::c_char, path::Path, ptr::NonNull, str::FromStr};
+
+use libsqlite3_sys as ffi;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection},
+    ConnectOptions, Connection,
+};
+
+use crate::{error::Error, future::unblock};
+
+const MAIN_DATABASE: &[u8] = b"main\0";
+const BACKUP_RETRY_MS: i32 = 50;
+const BACKUP_MAX_RETRIES: usize = 200;
+
+/// Copy the database of a live connection into a new database file
+///
+/// The snapshot is written in a single step of the online backup API, and so
+/// reflects the state of the database at a single point in time. The integrity of
+/// the copy is checked before returning. An existing file is never overwritten.
+/// The snapshot uses the rollback journal mode, so that it consists of a single file.
+///
+/// The copy is written to a uniquely named temporary file in the same directory,
+/// which is synced to disk along with its directory before being moved into
+/// place, so that an interrupted snapshot never leaves a partial file at `path`.
+pub(crate) async fn snapshot_to(source: &mut SqliteConnection, path: &str) -> Result<(), Error> {
+    if path.is_empty() || path.starts_with(':') || path.starts_with("file:") {
+        return Err(err_msg!(Input, "Invalid snapshot path"));
+    }
+    if Path::new(path).exists() {
+        return Err(err_msg!(Duplicate, "Snapshot path already exists"));
+    }
+    let temp_path = format!("{}.{}.tmp", path, uuid::Uuid::new_v4().simple());
+    let mut target = SqliteConnectOptions::from_str(&temp_path)?
+        .create_if_missing(true)
+        .connect()
+        .await
+        .map_err(err_map!(Backend, "Error creating snapshot database"))?;
+    let result = async {
+        backup_database(source, &mut target).await?;
+        // the snapshot is kept as a single file, regardless of the store journal mode
+        sqlx::query("PRAGMA journal_mode = DELETE")
+            .execute(&mut target)
+            .await
+            .map_err(err_map!(Backend, "Error updating snapshot journal mode"))?;
+        check_integrity(&mut target).await
+    }
+    .await;
+    let closed = target
+        .close()
+        .await
+        .map_err(err_map!(Backend, "Error closing snapshot database"));
+    let result = match result.and(closed) {
+        Ok(()) => {
+            let path = path.to_string();
+            let temp_path = temp_path.clone();
+            unblock(move || persist_file(&temp_path, &path)).await
+        }
+        Err(err) => Err(err),
+    };
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Sync a completed file to disk and move it into place, syncing its directory
+fn persist_file(temp_path: &str, path: &str) -> Result<(), Error> {
+    File::open(temp_path)
+        .and_then(|file| file.sync_all())
+        .map_err(err_map!(Backend, "Error syncing snapshot file"))?;
+    // unlike a rename, linking never replaces a file created in the meantime
+    std::fs::hard_link(temp_path, path).map_err(|err| {
+        if err.kind() == std::io::ErrorKind::AlreadyExists {
+            err_msg!(Duplicate, "Snapshot path already exists")
+        } else {
+            err_msg!(Backend, "Error moving snapshot file").with_cause(err)
+        }
+    })?;
+    // the snapshot is complete once linked, leaving at most a stale temporary file
+    let _ = std::fs::remove_file(temp_path);
+    sync_parent_dir(Path::new(path))
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<(), Error> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(err_map!(Backend, "Error syncing snapshot directory"))
+}
+
+// directories cannot be opened for syncing on other platforms, where the
+// rename is persisted by the file system
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Open a connection to an existing snapshot in read-only mode
+pub(crate) async fn open_snapshot(path: &str) -> Result<SqliteConnection, Error> {
+    if !Path::new(path).is_file() {
+        return Err(err_msg!(NotFound, "Snapshot not found"));
+    }
+    SqliteConnectOptions::from_str(path)?
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(err_map!(Backend, "Error opening snapshot database"))
+}
+
+/// Run a full integrity check of the database
+pub(crate) async fn check_integrity(conn: &mut SqliteConnection) -> Result<(), Error> {
+    let result: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(err_map!(Backend, "Error checking database integrity"))?;
+    if result.len() == 1 && result[0] == "ok" {
+        Ok(())
+    } else {
+        Err(err_msg!(
+            Backend,
+            "Database integrity check failed: {}",
+            result.join("; ")
+        ))
+    }
+}
+
+/// Replace the main database of `target` with a copy of the main database of `source`
+pub(crate) async fn backup_database(
+    source: &mut SqliteConnection,
+    target: &mut SqliteConnection,
+) -> Result<(), Error> {
+    let mut source = source
+        .lock_handle()
+        .await
+        .map_err(err_map!(Backend, "Error locking source database"))?;
+    let mut target = target
+        .lock_handle()
+        .await
+        .map_err(err_map!(Backend, "Error locking target database"))?;
+    let handles = BackupHandles {
+        source: source.as_raw_handle(),
+        target: target.as_raw_handle(),
+    };
+    unblock(move || handles.run()).await
+}
+
+struct BackupHandles {
+    source: NonNull<ffi::sqlite3>,
+    target: NonNull<ffi::sqlite3>,
+}
+
+// SAFETY: both handles remain locked out of their connection worker threads
+// until the backup has completed, so they are never used concurrently
+unsafe impl Send for BackupHandles {}
+
+impl BackupHandles {
+    fn run(self) -> Result<(), Error> {
+        let main = MAIN_DATABASE.as_ptr() as *const c_char;
+        // SAFETY: the handles are valid open connections (see above), and the
+        // backup object is always released by sqlite3_backup_finish
+        unsafe {
+            let backup =
+                ffi::sqlite3_backup_init(self.target.as_ptr(), main, self.source.as_ptr(), main);
+            if backup.is_null() {
+                return Err(err_msg!(
+                    Backend,
+                    "Error starting database backup: {}",
+                    error_message(self.target)
+                ));
+            }
+            let mut retries = 0;
+            let rc = loop {
+                match ffi::sqlite3_backup_step(backup, -1) {
+                    ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED if retries < BACKUP_MAX_RETRIES => {
+                        retries += 1;
+                        ffi::sqlite3_sleep(BACKUP_RETRY_MS);
+                    }
+                    rc => break rc,
+                }
+            };
+            ffi::sqlite3_backup_finish(backup);
+            if rc == ffi::SQLITE_DONE {
+                Ok(())
+            } else {
+                Err(err_msg!(
+                    Backend,
+                    "Error performing database backup: {}",
+                    ffi::code_to_str(rc)
+                ))
+            }
+        }
+    }
+}
+
+unsafe fn error_message(handle: NonNull<ffi::sqlite3>) -> String {
+    let msg = ffi::sqlite3_errmsg(handle.as_ptr());
+    if msg.is_null() {
+        String::new()
+    } else {
+        std::ffi::CStr::from_ptr(msg).to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn persist_file_keeps_existing() {
+        let dir = std::env::temp_dir();
+        let name = uuid::Uuid::new_v4().simple().to_string();
+        let path = dir.join(format!("snapshot-{}.db", name));
+        let temp_path = dir.join(format!("snapshot-{}.db.tmp", name));
+        let (path, temp_path) = (path.to_str().unwrap(), temp_path.to_str().unwrap());
+        std::fs::write(path, b"existing").unwrap();
+        std::fs::write(temp_path, b"snapshot").unwrap();
+        let err = persist_file(temp_path, path).expect_err("Expected existing file");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+        assert_eq!(std::fs::read(path).unwrap(), b"existing");
+
+        std::fs::remove_file(path).unwrap();
+        persist_file(temp_path, path).expect("Error persisting file");
+        assert_eq!(std::fs::read(path).unwrap(), b"snapshot");
+        assert!(!Path::new(temp_path).exists());
+        std::fs::remove_file(path).unwrap();
+    }
+}
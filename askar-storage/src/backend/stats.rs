@@ -0,0 +1,24 @@
+/// Usage statistics for the records stored within a profile
+///
+/// The statistics are aggregated by the database without decrypting any
+/// records, and are intended for tenant billing and capacity planning.
+/// Sizes refer to the encrypted records, and exclude the overhead of the
+/// database storage format and indexes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ProfileStats {
+    /// The number of item entries
+    pub items: i64,
+    /// The number of key entries
+    pub keys: i64,
+    /// The number of entries which have expired but not yet been purged,
+    /// which are included in the item and key counts
+    pub expired: i64,
+    /// The number of entry tags
+    pub tags: i64,
+    /// The total size in bytes of the stored entry values, as counted
+    /// against the `max_value_bytes` limit of the profile quota
+    pub value_bytes: i64,
+    /// The approximate total size in bytes of the stored entries, including
+    /// their categories, names, values and tags
+    pub storage_bytes: i64,
+}
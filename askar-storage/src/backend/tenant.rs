@@ -0,0 +1,214 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::error::Error;
+
+/// The configuration entry recording the prefix of per-profile schema names
+#[cfg(any(feature = "postgres", feature = "odbc"))]
+pub(crate) const TENANT_SCHEMA_CONFIG: &str = "tenant_schema_prefix";
+
+/// The isolation of profile records within the database
+///
+/// By default the records of all profiles share the same tables. With schema
+/// isolation, the records of each profile are kept in tables within a dedicated
+/// database schema, which is created with the profile and dropped when it is
+/// removed, so that database permissions, quotas and backups may be applied to
+/// each tenant separately. The isolation is selected using the `tenant_isolation`
+/// query parameter of the store URI, and only applies to newly provisioned stores.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TenantIsolation {
+    /// The records of all profiles are kept in shared tables
+    #[default]
+    Shared,
+    /// The records of each profile are kept in a separate schema
+    Schema,
+}
+
+impl TenantIsolation {
+    /// Get the identifier of the isolation mode
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Shared => "shared",
+            Self::Schema => "schema",
+        }
+    }
+
+    /// Extract the isolation mode from the `tenant_isolation` store URI query parameter
+    #[cfg(any(feature = "postgres", feature = "odbc"))]
+    pub(crate) fn from_query(
+        query: &mut std::collections::HashMap<String, String>,
+    ) -> Result<Self, Error> {
+        if let Some(isolation) = query.remove("tenant_isolation") {
+            Self::from_str(&isolation).map_err(err_map!(
+                Input,
+                "Error parsing 'tenant_isolation' parameter"
+            ))
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+impl FromStr for TenantIsolation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shared" => Ok(Self::Shared),
+            "schema" => Ok(Self::Schema),
+            _ => Err(err_msg!(Unsupported, "Unknown tenant isolation")),
+        }
+    }
+}
+
+impl Display for TenantIsolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+pub(crate) use self::schemas::TenantSchemas;
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+mod schemas {
+    use std::sync::Arc;
+
+    #[cfg(any(test, feature = "postgres", feature = "odbc"))]
+    use crate::error::Error;
+    use crate::protect::ProfileId;
+
+    /// The longest prefix for which every schema name fits within the 63 byte
+    /// identifier limit of PostgreSQL
+    #[cfg(any(test, feature = "postgres", feature = "odbc"))]
+    const MAX_PREFIX_LEN: usize = 63 - "_p".len() - 19;
+
+    /// The naming of the per-profile schemas of a store using schema isolation
+    ///
+    /// Schema names are derived from the profile ID rather than the profile name,
+    /// so that renaming a profile does not require its tables to be moved.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub(crate) struct TenantSchemas {
+        prefix: Arc<str>,
+    }
+
+    impl TenantSchemas {
+        #[cfg(any(test, feature = "postgres", feature = "odbc"))]
+        pub fn new(prefix: &str) -> Result<Self, Error> {
+            let mut chars = prefix.chars();
+            if prefix.len() > MAX_PREFIX_LEN
+                || !chars
+                    .next()
+                    .map(|c| c.is_ascii_alphabetic() || c == '_')
+                    .unwrap_or(false)
+                || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(err_msg!(
+                    Input,
+                    "Invalid tenant schema prefix: expected at most {} alphanumeric characters",
+                    MAX_PREFIX_LEN
+                ));
+            }
+            Ok(Self {
+                prefix: prefix.into(),
+            })
+        }
+
+        /// Accessor for the prefix shared by the schema names
+        pub fn prefix(&self) -> &str {
+            &self.prefix
+        }
+
+        /// Get the name of the schema holding the records of a profile
+        pub fn schema(&self, profile_id: ProfileId) -> String {
+            format!("{}_p{}", self.prefix, profile_id)
+        }
+
+        /// Qualify the references to the record tables in a SQL statement with the
+        /// schema of a profile
+        #[cfg(feature = "odbc")]
+        pub fn qualify(&self, sql: &str, profile_id: ProfileId) -> String {
+            qualify_tables(sql, &self.schema(profile_id))
+        }
+    }
+
+    /// Qualify the `items` and `items_tags` table references following a `FROM`,
+    /// `INTO`, `UPDATE` or `JOIN` keyword
+    #[cfg(any(test, feature = "odbc"))]
+    fn qualify_tables(sql: &str, schema: &str) -> String {
+        let mut result = String::with_capacity(sql.len() + schema.len() * 2);
+        let mut prev_word = "";
+        let mut rest = sql;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            let is_table =
+                word.eq_ignore_ascii_case("items") || word.eq_ignore_ascii_case("items_tags");
+            let after_keyword = ["FROM", "INTO", "UPDATE", "JOIN"]
+                .iter()
+                .any(|kw| prev_word.eq_ignore_ascii_case(kw));
+            // skip qualified names such as 'i.items'
+            if is_table && after_keyword && !result.ends_with('.') {
+                result.push_str(schema);
+                result.push('.');
+            }
+            result.push_str(word);
+            prev_word = word;
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn tenant_schema_names() {
+            let schemas = TenantSchemas::new("askar").unwrap();
+            assert_eq!(schemas.schema(12), "askar_p12");
+            assert_eq!(
+                TenantSchemas::new(&"a".repeat(MAX_PREFIX_LEN))
+                    .unwrap()
+                    .schema(i64::MAX)
+                    .len(),
+                63
+            );
+            for invalid in ["", "1abc", "a-b", "a\"b", &"a".repeat(MAX_PREFIX_LEN + 1)] {
+                assert!(TenantSchemas::new(invalid).is_err(), "{}", invalid);
+            }
+        }
+
+        #[test]
+        fn qualify_table_names() {
+            assert_eq!(
+                qualify_tables(
+                    "SELECT i.id, (SELECT name FROM items_tags WHERE item_id = i.id)
+                    FROM items i WHERE i.items = ?",
+                    "t_p1"
+                ),
+                "SELECT i.id, (SELECT name FROM t_p1.items_tags WHERE item_id = i.id)
+                    FROM t_p1.items i WHERE i.items = ?"
+            );
+            assert_eq!(
+                qualify_tables("INSERT INTO items (name) VALUES (?)", "t_p1"),
+                "INSERT INTO t_p1.items (name) VALUES (?)"
+            );
+            assert_eq!(
+                qualify_tables("update items SET value=? WHERE profile_id=?", "t_p1"),
+                "update t_p1.items SET value=? WHERE profile_id=?"
+            );
+            assert_eq!(
+                qualify_tables("SELECT id FROM profiles WHERE name=?", "t_p1"),
+                "SELECT id FROM profiles WHERE name=?"
+            );
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt::Write;
+
+use crate::error::Error;
+
+/// The maximum accepted length of a `tracestate` value
+const MAX_TRACESTATE_LEN: usize = 512;
+
+/// A W3C trace context identifying the request on whose behalf a session
+/// executes its statements
+///
+/// The context is propagated to the database as a SQL comment following the
+/// sqlcommenter conventions, and is included in slow statement log entries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    traceparent: String,
+    tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Create a new trace context from `traceparent` and `tracestate` header values
+    pub fn new(traceparent: &str, tracestate: Option<&str>) -> Result<Self, Error> {
+        if !is_valid_traceparent(traceparent) {
+            return Err(err_msg!(Input, "Invalid traceparent value"));
+        }
+        let tracestate = tracestate.filter(|state| !state.is_empty());
+        if let Some(state) = tracestate {
+            if state.len() > MAX_TRACESTATE_LEN || !state.bytes().all(|c| (0x20..0x7f).contains(&c))
+            {
+                return Err(err_msg!(Input, "Invalid tracestate value"));
+            }
+        }
+        Ok(Self {
+            traceparent: traceparent.to_string(),
+            tracestate: tracestate.map(str::to_string),
+        })
+    }
+
+    /// Accessor for the `traceparent` value
+    pub fn traceparent(&self) -> &str {
+        &self.traceparent
+    }
+
+    /// Accessor for the `tracestate` value
+    pub fn tracestate(&self) -> Option<&str> {
+        self.tracestate.as_deref()
+    }
+
+    /// Accessor for the hex-encoded trace ID
+    pub fn trace_id(&self) -> &str {
+        &self.traceparent[3..35]
+    }
+
+    /// Accessor for the hex-encoded ID of the parent span
+    pub fn parent_id(&self) -> &str {
+        &self.traceparent[36..52]
+    }
+
+    /// Format the context as a SQL comment, with URL-encoded values
+    pub fn sql_comment(&self) -> String {
+        let mut comment = String::with_capacity(64);
+        comment.push_str("/*traceparent='");
+        url_encode(&mut comment, &self.traceparent);
+        if let Some(state) = self.tracestate.as_deref() {
+            comment.push_str("',tracestate='");
+            url_encode(&mut comment, state);
+        }
+        comment.push_str("'*/");
+        comment
+    }
+}
+
+fn is_valid_traceparent(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() < 55 {
+        return false;
+    }
+    let is_hex = |range: std::ops::Range<usize>| {
+        bytes[range]
+            .iter()
+            .all(|c| c.is_ascii_digit() || (b'a'..=b'f').contains(c))
+    };
+    let is_zero = |range: std::ops::Range<usize>| bytes[range].iter().all(|c| *c == b'0');
+    // future versions may append fields, but version 00 has a fixed length
+    let version = &bytes[..2];
+    is_hex(0..2)
+        && version != b"ff"
+        && (bytes.len() == 55 || (version != b"00" && bytes[55] == b'-'))
+        && bytes[2] == b'-'
+        && bytes[35] == b'-'
+        && bytes[52] == b'-'
+        && is_hex(3..35)
+        && !is_zero(3..35)
+        && is_hex(36..52)
+        && !is_zero(36..52)
+        && is_hex(53..55)
+}
+
+fn url_encode(out: &mut String, value: &str) {
+    for c in value.bytes() {
+        if c.is_ascii_alphanumeric() || matches!(c, b'-' | b'.' | b'_' | b'~') {
+            out.push(c as char);
+        } else {
+            write!(out, "%{:02X}", c).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn parse_trace_context() {
+        let ctx = TraceContext::new(TRACEPARENT, Some("congo=t61rcWkgMzE")).unwrap();
+        assert_eq!(ctx.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(ctx.parent_id(), "00f067aa0ba902b7");
+        assert_eq!(ctx.tracestate(), Some("congo=t61rcWkgMzE"));
+        assert_eq!(
+            ctx.sql_comment(),
+            "/*traceparent='00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01',\
+            tracestate='congo%3Dt61rcWkgMzE'*/"
+        );
+
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-00",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ] {
+            assert!(TraceContext::new(invalid, None).is_err(), "{}", invalid);
+        }
+        assert!(TraceContext::new(
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-00",
+            None
+        )
+        .is_ok());
+        assert!(TraceContext::new(TRACEPARENT, Some("a=1\n*/")).is_err());
+        assert!(TraceContext::new(&format!("0é{}", &TRACEPARENT[3..]), None).is_err());
+    }
+}
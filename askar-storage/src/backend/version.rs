@@ -0,0 +1,97 @@
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use std::str::FromStr;
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use super::schema::SCHEMA_WRITER_CONFIG;
+use crate::protect::{NameEncryption, ProfileCipher};
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use crate::{
+    error::Error,
+    protect::{
+        parse_expiry_bucket, store_key_method_uri, EXPIRY_BUCKET_CONFIG, NAME_ENCRYPTION_CONFIG,
+        PENDING_KEY_CONFIG, PROFILE_CIPHER_CONFIG, STORE_KEY_CIPHER,
+    },
+};
+
+/// The name of the config table entry identifying the library version which
+/// provisioned the store
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+const PROVISIONED_BY_CONFIG: &str = "provisioned_by";
+
+/// Version and format information for an opened store
+///
+/// This is intended for the inventory of stores before library upgrades, and
+/// does not include any secret material.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionInfo {
+    /// The version of the store format
+    pub store_version: String,
+    /// The latest schema migration applied to the store, or zero when none
+    /// have been applied
+    pub schema_version: i64,
+    /// The latest schema migration supported by this library version
+    pub supported_schema_version: i64,
+    /// The library version which provisioned the store, when recorded
+    pub provisioned_by: Option<String>,
+    /// The library version which last applied a schema migration, when recorded
+    pub schema_writer: Option<String>,
+    /// The store key method, such as `kdf:argon2i:mod` or `raw`, without
+    /// the key derivation salt
+    pub key_method: String,
+    /// The cipher used to wrap profile keys with the store key
+    pub store_cipher: String,
+    /// The cipher used to encrypt the entries of new profiles
+    pub profile_cipher: ProfileCipher,
+    /// The encryption of entry categories and names in new profiles
+    pub name_encryption: NameEncryption,
+    /// The granularity in seconds of the plaintext expiry times of entries in new
+    /// profiles, when their expiry times are encrypted
+    pub expiry_bucket: Option<u32>,
+    /// Whether a chunked re-key of the store has not yet completed
+    pub rekey_pending: bool,
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+impl VersionInfo {
+    /// Assemble the version information from the entries of the config table
+    pub(crate) fn from_config(
+        config: Vec<(String, Option<String>)>,
+        schema_version: i64,
+        supported_schema_version: i64,
+    ) -> Result<Self, Error> {
+        let mut info = Self {
+            store_version: String::new(),
+            schema_version,
+            supported_schema_version,
+            provisioned_by: None,
+            schema_writer: None,
+            key_method: String::new(),
+            store_cipher: STORE_KEY_CIPHER.to_string(),
+            profile_cipher: ProfileCipher::default(),
+            name_encryption: NameEncryption::default(),
+            expiry_bucket: None,
+            rekey_pending: false,
+        };
+        for (name, value) in config {
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            match name.as_str() {
+                "version" => info.store_version = value,
+                "key" => info.key_method = store_key_method_uri(&value),
+                PROVISIONED_BY_CONFIG => info.provisioned_by = Some(value),
+                PROFILE_CIPHER_CONFIG => info.profile_cipher = ProfileCipher::from_str(&value)?,
+                NAME_ENCRYPTION_CONFIG => info.name_encryption = NameEncryption::from_str(&value)?,
+                EXPIRY_BUCKET_CONFIG => info.expiry_bucket = parse_expiry_bucket(Some(&value))?,
+                PENDING_KEY_CONFIG => info.rekey_pending = true,
+                SCHEMA_WRITER_CONFIG => info.schema_writer = Some(value),
+                _ => (),
+            }
+        }
+        if info.store_version.is_empty() {
+            return Err(err_msg!(Unsupported, "Store version not found"));
+        }
+        Ok(info)
+    }
+}
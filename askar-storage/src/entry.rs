@@ -7,10 +7,16 @@ use std::{
 };
 
 use futures_lite::stream::{Stream, StreamExt};
+use tokio::sync::mpsc;
 use zeroize::Zeroize;
 
 use super::wql;
-use crate::{crypto::buffer::SecretBytes, error::Error};
+use crate::{
+    crypto::buffer::SecretBytes,
+    error::Error,
+    future::spawn_ok,
+    redact::{redact_category, redact_name, redact_tag_name, redaction_policy, Redaction},
+};
 
 pub(crate) fn sorted_tags(tags: &Vec<EntryTag>) -> Vec<&EntryTag> {
     if tags.is_empty() {
@@ -23,7 +29,7 @@ pub(crate) fn sorted_tags(tags: &Vec<EntryTag>) -> Vec<&EntryTag> {
 }
 
 /// A record in the store
-#[derive(Clone, Debug, Eq)]
+#[derive(Clone, Eq)]
 pub struct Entry {
     /// The entry kind discriminator
     pub kind: EntryKind,
@@ -65,6 +71,18 @@ impl Entry {
     }
 }
 
+impl Debug for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("kind", &self.kind)
+            .field("category", &redact_category(&self.category))
+            .field("name", &redact_name(&self.name))
+            .field("value", &self.value)
+            .field("tags", &self.tags)
+            .finish()
+    }
+}
+
 impl PartialEq for Entry {
     fn eq(&self, rhs: &Self) -> bool {
         self.category == rhs.category
@@ -116,6 +134,20 @@ pub enum EntryTag {
 }
 
 impl EntryTag {
+    /// Create a new EntryTag, which is encrypted unless `plaintext` is set
+    pub fn new(name: impl Into<String>, value: impl Into<String>, plaintext: bool) -> Self {
+        if plaintext {
+            Self::Plaintext(name.into(), value.into())
+        } else {
+            Self::Encrypted(name.into(), value.into())
+        }
+    }
+
+    /// Check whether the tag is stored unencrypted
+    pub fn is_plaintext(&self) -> bool {
+        matches!(self, Self::Plaintext(..))
+    }
+
     /// Accessor for the tag name
     pub fn name(&self) -> &str {
         match self {
@@ -164,19 +196,19 @@ impl Debug for EntryTag {
         match self {
             Self::Encrypted(name, value) => f
                 .debug_tuple("Encrypted")
-                .field(&name)
+                .field(&redact_tag_name(name))
                 .field(&value)
                 .finish(),
             Self::Plaintext(name, value) => f
                 .debug_tuple("Plaintext")
-                .field(&name)
+                .field(&redact_tag_name(name))
                 .field(&value)
                 .finish(),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Zeroize)]
 pub(crate) struct EncEntryTag {
     pub name: Vec<u8>,
     pub value: Vec<u8>,
@@ -184,12 +216,25 @@ pub(crate) struct EncEntryTag {
 }
 
 /// A WQL filter used to restrict record queries
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 #[repr(transparent)]
 pub struct TagFilter {
     pub(crate) query: wql::Query,
 }
 
+impl Debug for TagFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // the query is only shown when tag names are not redacted
+        if redaction_policy().tag_name == Redaction::Plain {
+            f.debug_struct("TagFilter")
+                .field("query", &self.query)
+                .finish()
+        } else {
+            f.debug_struct("TagFilter").finish_non_exhaustive()
+        }
+    }
+}
+
 impl TagFilter {
     /// Combine multiple tag filters using the `AND` operator
     #[inline]
@@ -296,6 +341,107 @@ impl TagFilter {
     pub fn into_query(self) -> wql::Query {
         self.query
     }
+
+    /// Validate a JSON tag filter without executing it
+    pub fn validate(query: &str) -> TagFilterDiagnostics {
+        let mut diag = TagFilterDiagnostics::default();
+        match serde_json::from_str::<wql::Query>(query) {
+            Ok(query) => check_query(&query, &mut diag),
+            Err(err) => {
+                // errors in the query structure are not tied to a position
+                let position = (err.line() > 0).then(|| (err.line(), err.column()));
+                diag.error = Some(TagFilterError {
+                    message: err.to_string(),
+                    line: position.map(|p| p.0),
+                    column: position.map(|p| p.1),
+                })
+            }
+        }
+        diag
+    }
+}
+
+/// The result of validating a JSON tag filter
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagFilterDiagnostics {
+    /// The reason the filter could not be parsed, if any
+    pub error: Option<TagFilterError>,
+    /// The tag names referenced by the filter, in order of first appearance
+    pub tags: Vec<String>,
+    /// Clauses which are accepted but unlikely to match as intended
+    pub warnings: Vec<TagFilterWarning>,
+}
+
+impl TagFilterDiagnostics {
+    /// Determine whether the filter was parsed successfully
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A tag filter parsing error
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagFilterError {
+    /// The error message
+    pub message: String,
+    /// The line of the input on which a syntax error was found
+    pub line: Option<usize>,
+    /// The column of the input at which a syntax error was found
+    pub column: Option<usize>,
+}
+
+/// A warning for a tag filter clause
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagFilterWarning {
+    /// The tag name of the clause
+    pub tag: String,
+    /// The warning message
+    pub message: String,
+}
+
+fn check_query(query: &wql::Query, diag: &mut TagFilterDiagnostics) {
+    let add_tag = |diag: &mut TagFilterDiagnostics, name: &str| {
+        if name.is_empty() || name == "~" {
+            diag.warnings.push(TagFilterWarning {
+                tag: name.to_string(),
+                message: "Empty tag name will not match any records".to_string(),
+            });
+        }
+        if !diag.tags.iter().any(|tag| tag == name) {
+            diag.tags.push(name.to_string());
+        }
+    };
+    match query {
+        wql::Query::And(subqueries) | wql::Query::Or(subqueries) => {
+            for subquery in subqueries {
+                check_query(subquery, diag);
+            }
+        }
+        wql::Query::Not(subquery) => check_query(subquery, diag),
+        wql::Query::Eq(name, _) | wql::Query::Neq(name, _) | wql::Query::In(name, _) => {
+            add_tag(diag, name)
+        }
+        wql::Query::Gt(name, _)
+        | wql::Query::Gte(name, _)
+        | wql::Query::Lt(name, _)
+        | wql::Query::Lte(name, _)
+        | wql::Query::Like(name, _) => {
+            add_tag(diag, name);
+            if !name.starts_with('~') {
+                diag.warnings.push(TagFilterWarning {
+                    tag: name.clone(),
+                    message: "Range and LIKE comparisons of encrypted tag values are not \
+                        meaningful, use a plaintext (~) tag"
+                        .to_string(),
+                });
+            }
+        }
+        wql::Query::Exist(names) => {
+            for name in names {
+                add_tag(diag, name);
+            }
+        }
+    }
 }
 
 impl From<wql::Query> for TagFilter {
@@ -314,9 +460,15 @@ impl FromStr for TagFilter {
 }
 
 /// An active record scan of a store backend
+///
+/// Result rows are read from the backend one page at a time. When supported by
+/// the backend, the following page is fetched and decrypted in the background
+/// while the current page is being processed. The number of rows in each page may be configured using the
+/// `page_size` query parameter of the store URI.
 pub struct Scan<'s, T> {
     #[allow(clippy::type_complexity)]
     stream: Option<Pin<Box<dyn Stream<Item = Result<Vec<T>, Error>> + Send + 's>>>,
+    prefetch: Option<mpsc::Receiver<Result<Vec<T>, Error>>>,
     page_size: usize,
 }
 
@@ -327,13 +479,62 @@ impl<'s, T> Scan<'s, T> {
     {
         Self {
             stream: Some(stream.boxed()),
+            prefetch: None,
             page_size,
         }
     }
 
+    /// Create a scan which produces no result rows
+    pub fn empty() -> Self {
+        Self {
+            stream: None,
+            prefetch: None,
+            page_size: 0,
+        }
+    }
+
+    /// Read result pages in a background task, at most one page ahead of
+    /// the pages returned by `fetch_next`
+    pub(crate) fn prefetch(mut self) -> Self
+    where
+        's: 'static,
+        T: Send + 'static,
+    {
+        if let Some(mut stream) = self.stream.take() {
+            let (sender, receiver) = mpsc::channel(1);
+            let page_size = self.page_size;
+            spawn_ok(async move {
+                // capacity is reserved before fetching, so that a page is only
+                // read once the previous page has been taken by the consumer
+                while let Ok(permit) = sender.reserve().await {
+                    match stream.next().await {
+                        Some(page) => {
+                            let last = !matches!(&page, Ok(rows) if rows.len() == page_size);
+                            permit.send(page);
+                            if last {
+                                break;
+                            }
+                        }
+                        None => break,
+                    }
+                }
+            });
+            self.prefetch = Some(receiver);
+        }
+        self
+    }
+
     /// Fetch the next set of result rows
     pub async fn fetch_next(&mut self) -> Result<Option<Vec<T>>, Error> {
-        if let Some(mut s) = self.stream.take() {
+        if let Some(receiver) = self.prefetch.as_mut() {
+            match receiver.recv().await {
+                Some(page) => page.map(Some),
+                None => {
+                    self.prefetch = None;
+                    Ok(None)
+                }
+            }
+        } else if let Some(mut s) = self.stream.take() {
             match s.try_next().await? {
                 Some(val) => {
                     if val.len() == self.page_size {
@@ -356,3 +557,63 @@ impl<S> Debug for Scan<'_, S> {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::future::{block_on, sleep};
+
+    #[test]
+    fn tag_filter_validate() {
+        let diag = TagFilter::validate(
+            r#"{"$or": [{"enctag": "a"}, {"~plain": {"$gt": "1"}}, {"enctag": {"$like": "b%"}}]}"#,
+        );
+        assert!(diag.is_valid());
+        assert_eq!(diag.tags, vec!["enctag", "~plain"]);
+        assert_eq!(diag.warnings.len(), 1);
+        assert_eq!(diag.warnings[0].tag, "enctag");
+
+        let diag = TagFilter::validate(r#"{"tag": {"$gt": 1}}"#);
+        assert!(!diag.is_valid());
+        let error = diag.error.unwrap();
+        assert!(error.message.contains("$gt must be used with string"));
+        assert_eq!(error.line, None);
+
+        let error = TagFilter::validate("{\n  \"tag\": \"a\",\n}")
+            .error
+            .unwrap();
+        assert_eq!((error.line, error.column), (Some(3), Some(1)));
+    }
+
+    #[test]
+    fn scan_prefetch_one_page() {
+        let fetched = Arc::new(AtomicUsize::new(0));
+        let stream = futures_lite::stream::iter(0..5).map({
+            let fetched = fetched.clone();
+            move |page| {
+                fetched.fetch_add(1, Ordering::SeqCst);
+                Ok(vec![page; if page < 4 { 2 } else { 1 }])
+            }
+        });
+        block_on(async move {
+            let mut scan = Scan::new(stream, 2).prefetch();
+            let mut pages = Vec::new();
+            while let Some(page) = scan.fetch_next().await.unwrap() {
+                sleep(Duration::from_millis(20)).await;
+                // the current page and at most one following page have been read
+                assert!(fetched.load(Ordering::SeqCst) <= pages.len() + 2);
+                pages.push(page);
+            }
+            assert_eq!(pages.len(), 5);
+            assert_eq!(pages[4], vec![4]);
+        });
+    }
+}
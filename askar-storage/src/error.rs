@@ -2,10 +2,14 @@ use std::error::Error as StdError;
 use std::fmt::{self, Display, Formatter};
 
 use crate::crypto::{Error as CryptoError, ErrorKind as CryptoErrorKind};
+use crate::entry::EntryKind;
 
 /// The possible kinds of error produced by the crate
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ErrorKind {
+    /// A write operation was rejected because the profile is archived
+    Archived,
+
     /// An unexpected error from the store backend
     Backend,
 
@@ -27,6 +31,9 @@ pub enum ErrorKind {
     /// The requested record was not found
     NotFound,
 
+    /// A write operation would exceed the limits configured for a profile
+    QuotaExceeded,
+
     /// An unexpected error occurred
     Unexpected,
 
@@ -38,6 +45,7 @@ impl ErrorKind {
     /// Convert the error kind to a string reference
     pub fn as_str(&self) -> &'static str {
         match self {
+            Self::Archived => "Archived",
             Self::Backend => "Backend error",
             Self::Busy => "Busy",
             Self::Custom => "Custom error",
@@ -45,6 +53,7 @@ impl ErrorKind {
             Self::Encryption => "Encryption error",
             Self::Input => "Input error",
             Self::NotFound => "Not found",
+            Self::QuotaExceeded => "Quota exceeded",
             Self::Unexpected => "Unexpected error",
             Self::Unsupported => "Unsupported",
         }
@@ -57,12 +66,109 @@ impl Display for ErrorKind {
     }
 }
 
+/// The cause of a `NotFound` error opening a store which does not exist, as
+/// opposed to a missing profile or record within the store
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StoreNotFound;
+
+impl Display for StoreNotFound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("The store does not exist")
+    }
+}
+
+impl StdError for StoreNotFound {}
+
+/// Structured details of the store operation which produced an error
+///
+/// Each field is populated by the innermost layer which is aware of it, as the
+/// error propagates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The name of the store operation, such as `open` or `fetch`
+    pub operation: Option<&'static str>,
+    /// The kind of store backend, such as `sqlite` or `postgres`
+    pub backend: Option<&'static str>,
+    /// Whether a profile name was provided, rather than using the default profile
+    pub has_profile: Option<bool>,
+    /// The kind of entry being accessed
+    pub entry_kind: Option<EntryKind>,
+}
+
+impl ErrorContext {
+    /// Create a new context for a store operation
+    pub fn new(operation: &'static str) -> Self {
+        Self {
+            operation: Some(operation),
+            ..Self::default()
+        }
+    }
+
+    /// Set the kind of store backend
+    pub fn backend(mut self, backend: &'static str) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Set whether a profile name was provided
+    pub fn has_profile(mut self, has_profile: bool) -> Self {
+        self.has_profile = Some(has_profile);
+        self
+    }
+
+    /// Set the kind of entry being accessed
+    pub fn entry_kind(mut self, kind: Option<EntryKind>) -> Self {
+        self.entry_kind = kind;
+        self
+    }
+
+    /// Fill in any fields which are not already set from another context
+    pub fn merge(&mut self, outer: &ErrorContext) {
+        self.operation = self.operation.or(outer.operation);
+        self.backend = self.backend.or(outer.backend);
+        self.has_profile = self.has_profile.or(outer.has_profile);
+        self.entry_kind = self.entry_kind.or(outer.entry_kind);
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        let mut field = |f: &mut Formatter<'_>, name: &str, value: &str| {
+            let result = write!(f, "{}{}: {}", sep, name, value);
+            sep = ", ";
+            result
+        };
+        if let Some(operation) = self.operation {
+            field(f, "operation", operation)?;
+        }
+        if let Some(backend) = self.backend {
+            field(f, "backend", backend)?;
+        }
+        if let Some(has_profile) = self.has_profile {
+            field(f, "profile", if has_profile { "present" } else { "absent" })?;
+        }
+        if let Some(kind) = self.entry_kind {
+            field(
+                f,
+                "entry kind",
+                match kind {
+                    EntryKind::Kms => "kms",
+                    EntryKind::Item => "item",
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
 /// The standard crate error type
 #[derive(Debug)]
 pub struct Error {
     pub(crate) kind: ErrorKind,
     pub(crate) cause: Option<Box<dyn StdError + Send + Sync + 'static>>,
     pub(crate) message: Option<String>,
+    pub(crate) context: Option<Box<ErrorContext>>,
 }
 
 impl Error {
@@ -71,6 +177,7 @@ impl Error {
             kind,
             cause: None,
             message: Some(msg.into()),
+            context: None,
         }
     }
 
@@ -84,6 +191,52 @@ impl Error {
         self.message.as_deref()
     }
 
+    /// Accessor for the details of the database driver error which caused this error
+    pub fn backend_info(&self) -> Option<BackendErrorInfo> {
+        backend_error_info(self)
+    }
+
+    /// Accessor for the context of the store operation which produced this error
+    pub fn context(&self) -> Option<&ErrorContext> {
+        self.context.as_deref()
+    }
+
+    /// Accessor for the name of the store operation which produced this error
+    pub fn operation(&self) -> Option<&'static str> {
+        self.context.as_ref().and_then(|c| c.operation)
+    }
+
+    /// Accessor for the kind of store backend which produced this error
+    pub fn backend_kind(&self) -> Option<&'static str> {
+        self.context.as_ref().and_then(|c| c.backend)
+    }
+
+    /// Determine whether a profile name was provided for the failed operation
+    pub fn has_profile(&self) -> Option<bool> {
+        self.context.as_ref().and_then(|c| c.has_profile)
+    }
+
+    /// Accessor for the kind of entry accessed by the failed operation
+    pub fn entry_kind(&self) -> Option<EntryKind> {
+        self.context.as_ref().and_then(|c| c.entry_kind)
+    }
+
+    /// Determine whether the error reports that the store itself does not exist
+    pub fn is_store_not_found(&self) -> bool {
+        self.kind == ErrorKind::NotFound
+            && self
+                .cause
+                .as_ref()
+                .is_some_and(|cause| cause.is::<StoreNotFound>())
+    }
+
+    /// Determine whether the failed operation may succeed if retried
+    pub fn is_retryable(&self) -> bool {
+        self.kind == ErrorKind::Busy
+            || (self.kind == ErrorKind::Backend
+                && self.backend_info().map(|b| b.transient).unwrap_or(false))
+    }
+
     /// Split the error into its components
     pub fn into_parts(
         self,
@@ -102,6 +255,16 @@ impl Error {
         self.cause = Some(err.into());
         self
     }
+
+    /// Attach the context of a store operation, without replacing any details
+    /// already provided by an inner operation
+    pub fn with_context(mut self, context: ErrorContext) -> Self {
+        match self.context.as_mut() {
+            Some(inner) => inner.merge(&context),
+            None => self.context = Some(Box::new(context)),
+        }
+        self
+    }
 }
 
 impl Display for Error {
@@ -111,6 +274,9 @@ impl Display for Error {
         } else {
             f.write_str(self.kind.as_str())?;
         }
+        if let Some(context) = self.context.as_ref() {
+            write!(f, " ({})", context)?;
+        }
         if let Some(cause) = self.cause.as_ref() {
             write!(f, "\nCaused by: {}", cause)?;
         }
@@ -138,6 +304,7 @@ impl From<ErrorKind> for Error {
             kind,
             cause: None,
             message: None,
+            context: None,
         }
     }
 }
@@ -156,6 +323,181 @@ impl From<sqlx::Error> for Error {
     }
 }
 
+/// A stable classification of database driver errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BackendErrorCode {
+    /// The connection to the database could not be established or was lost
+    ConnectionFailure,
+    /// A unique, foreign key, not-null or check constraint was violated
+    ConstraintViolation,
+    /// The transaction was rolled back due to a serialization failure or deadlock
+    SerializationFailure,
+    /// A lock could not be acquired, or the database was busy
+    LockUnavailable,
+    /// The database server lacked the resources to complete the request
+    InsufficientResources,
+    /// The statement was interrupted, or the database server is shutting down
+    OperatorIntervention,
+    /// The statement timed out, or a pooled connection was not available in time
+    Timeout,
+    /// The account could not be authenticated or lacks the required privileges
+    PermissionDenied,
+    /// The database storage is full, read-only or corrupted
+    StorageFailure,
+    /// The statement was rejected by the database, for example due to a missing table
+    InvalidStatement,
+    /// Any other database error
+    Other,
+}
+
+impl BackendErrorCode {
+    /// Convert the error code to a stable string identifier
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ConnectionFailure => "connection_failure",
+            Self::ConstraintViolation => "constraint_violation",
+            Self::SerializationFailure => "serialization_failure",
+            Self::LockUnavailable => "lock_unavailable",
+            Self::InsufficientResources => "insufficient_resources",
+            Self::OperatorIntervention => "operator_intervention",
+            Self::Timeout => "timeout",
+            Self::PermissionDenied => "permission_denied",
+            Self::StorageFailure => "storage_failure",
+            Self::InvalidStatement => "invalid_statement",
+            Self::Other => "other",
+        }
+    }
+
+    /// Determine whether an operation failing with this code may succeed if retried
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::ConnectionFailure
+                | Self::SerializationFailure
+                | Self::LockUnavailable
+                | Self::InsufficientResources
+                | Self::OperatorIntervention
+                | Self::Timeout
+        )
+    }
+
+    /// Classify a SQLSTATE or SQLite result code
+    pub fn from_sqlstate(code: &str) -> Self {
+        if code.len() != 5 {
+            return Self::from_sqlite_code(code);
+        }
+        match code {
+            "42501" => Self::PermissionDenied,
+            "53100" | "25006" => Self::StorageFailure,
+            "55P03" => Self::LockUnavailable,
+            "57014" | "HYT00" | "HYT01" => Self::Timeout,
+            _ if code.starts_with("XX00") => Self::StorageFailure,
+            _ => match &code[..2] {
+                "08" => Self::ConnectionFailure,
+                "23" => Self::ConstraintViolation,
+                "28" => Self::PermissionDenied,
+                "40" => Self::SerializationFailure,
+                "42" => Self::InvalidStatement,
+                "53" => Self::InsufficientResources,
+                "57" => Self::OperatorIntervention,
+                _ => Self::Other,
+            },
+        }
+    }
+
+    fn from_sqlite_code(code: &str) -> Self {
+        // extended result codes carry the primary result code in the low byte
+        match code.parse::<u32>().map(|code| code & 0xff) {
+            Ok(1) => Self::InvalidStatement,
+            Ok(3 | 23) => Self::PermissionDenied,
+            Ok(5 | 6) => Self::LockUnavailable,
+            Ok(7) => Self::InsufficientResources,
+            Ok(8 | 10 | 11 | 13 | 14 | 26) => Self::StorageFailure,
+            Ok(9) => Self::OperatorIntervention,
+            Ok(19) => Self::ConstraintViolation,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl Display for BackendErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Details of a database driver error found within an error chain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendErrorInfo {
+    /// The classification of the driver error
+    pub code: BackendErrorCode,
+    /// The SQLSTATE or driver-specific error code, when reported by the database
+    pub sqlstate: Option<String>,
+    /// Whether the failure is likely to be transient, such that the operation may be retried
+    pub transient: bool,
+}
+
+impl BackendErrorInfo {
+    fn new(code: BackendErrorCode, sqlstate: Option<String>) -> Self {
+        Self {
+            code,
+            sqlstate,
+            transient: code.is_transient(),
+        }
+    }
+}
+
+/// Find the database driver error within an error chain, if any
+pub fn backend_error_info(err: &(dyn StdError + 'static)) -> Option<BackendErrorInfo> {
+    let mut next = Some(err);
+    while let Some(err) = next {
+        #[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+        if let Some(err) = err.downcast_ref::<sqlx::Error>() {
+            return Some(sqlx_error_info(err));
+        }
+        #[cfg(feature = "odbc")]
+        if let Some(err) = err.downcast_ref::<odbc_api::Error>() {
+            return Some(odbc_error_info(err));
+        }
+        #[cfg(feature = "odbc")]
+        if err.is::<r2d2::Error>() {
+            return Some(BackendErrorInfo::new(BackendErrorCode::Timeout, None));
+        }
+        next = err.source();
+    }
+    None
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite", feature = "odbc"))]
+fn sqlx_error_info(err: &sqlx::Error) -> BackendErrorInfo {
+    match err {
+        sqlx::Error::Database(db_err) => {
+            let sqlstate = db_err.code().map(|code| code.into_owned());
+            let code = sqlstate
+                .as_deref()
+                .map(BackendErrorCode::from_sqlstate)
+                .unwrap_or(BackendErrorCode::Other);
+            BackendErrorInfo::new(code, sqlstate)
+        }
+        sqlx::Error::Io(_) | sqlx::Error::WorkerCrashed => {
+            BackendErrorInfo::new(BackendErrorCode::ConnectionFailure, None)
+        }
+        sqlx::Error::PoolTimedOut => BackendErrorInfo::new(BackendErrorCode::Timeout, None),
+        _ => BackendErrorInfo::new(BackendErrorCode::Other, None),
+    }
+}
+
+#[cfg(feature = "odbc")]
+fn odbc_error_info(err: &odbc_api::Error) -> BackendErrorInfo {
+    match err {
+        odbc_api::Error::Diagnostics { record, .. } => {
+            let sqlstate = record.state.as_str().to_string();
+            BackendErrorInfo::new(BackendErrorCode::from_sqlstate(&sqlstate), Some(sqlstate))
+        }
+        _ => BackendErrorInfo::new(BackendErrorCode::Other, None),
+    }
+}
+
 impl From<CryptoError> for Error {
     fn from(err: CryptoError) -> Self {
         let kind = match err.kind() {
@@ -211,3 +553,69 @@ macro_rules! err_map {
         |err| err_msg!($($params)*).with_cause(err)
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_backend_codes() {
+        for (code, expect, transient) in [
+            ("40001", BackendErrorCode::SerializationFailure, true),
+            ("40P01", BackendErrorCode::SerializationFailure, true),
+            ("08006", BackendErrorCode::ConnectionFailure, true),
+            ("08S01", BackendErrorCode::ConnectionFailure, true),
+            ("55P03", BackendErrorCode::LockUnavailable, true),
+            ("57014", BackendErrorCode::Timeout, true),
+            ("HYT00", BackendErrorCode::Timeout, true),
+            ("57P01", BackendErrorCode::OperatorIntervention, true),
+            ("53300", BackendErrorCode::InsufficientResources, true),
+            ("53100", BackendErrorCode::StorageFailure, false),
+            ("23505", BackendErrorCode::ConstraintViolation, false),
+            ("42501", BackendErrorCode::PermissionDenied, false),
+            ("42P01", BackendErrorCode::InvalidStatement, false),
+            ("XX001", BackendErrorCode::StorageFailure, false),
+            ("22001", BackendErrorCode::Other, false),
+            // SQLite result codes, including extended codes
+            ("5", BackendErrorCode::LockUnavailable, true),
+            ("517", BackendErrorCode::LockUnavailable, true),
+            ("2067", BackendErrorCode::ConstraintViolation, false),
+            ("13", BackendErrorCode::StorageFailure, false),
+            ("1", BackendErrorCode::InvalidStatement, false),
+        ] {
+            let found = BackendErrorCode::from_sqlstate(code);
+            assert_eq!(found, expect, "code {}", code);
+            assert_eq!(found.is_transient(), transient, "code {}", code);
+        }
+    }
+
+    #[test]
+    fn retryable_errors() {
+        assert!(err_msg!(Busy, "Pool exhausted").is_retryable());
+        assert!(!err_msg!(Backend, "Unknown failure").is_retryable());
+        assert!(!err_msg!(Input, "Invalid input").is_retryable());
+        assert_eq!(err_msg!(Backend).backend_info(), None);
+    }
+
+    #[test]
+    fn error_context() {
+        let err = err_msg!(NotFound, "Configuration data not found")
+            .with_context(ErrorContext::new("open").backend("odbc"))
+            .with_context(
+                ErrorContext::new("provision")
+                    .has_profile(false)
+                    .entry_kind(Some(EntryKind::Item)),
+            );
+        assert_eq!(err.operation(), Some("open"));
+        assert_eq!(err.backend_kind(), Some("odbc"));
+        assert_eq!(err.has_profile(), Some(false));
+        assert_eq!(err.entry_kind(), Some(EntryKind::Item));
+        assert_eq!(err.message(), Some("Configuration data not found"));
+        assert_eq!(
+            err.to_string(),
+            "Configuration data not found \
+            (operation: open, backend: odbc, profile: absent, entry kind: item)"
+        );
+        assert_eq!(err_msg!(NotFound).context(), None);
+    }
+}
@@ -1,93 +1,38 @@
-use std::{
-    future::Future,
-    pin::Pin,
-    sync::Arc,
-    thread,
-    time::{Duration, Instant},
+use std::{future::Future, pin::Pin, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use self::native::unblock_all;
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::native::{
+    block_on, configure_blocking_pool, shutdown, sleep, spawn, spawn_ok, system_time, timeout,
+    unblock,
 };
 
-use arc_swap::ArcSwapOption;
-use once_cell::sync::Lazy;
-use tokio::runtime::Runtime;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use self::wasm::unblock_all;
+#[cfg(target_arch = "wasm32")]
+pub use self::wasm::{
+    block_on, configure_blocking_pool, shutdown, sleep, spawn, spawn_ok, system_time, timeout,
+    unblock,
+};
 
 pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
-static RUNTIME: Lazy<ArcSwapOption<Runtime>> = Lazy::new(|| {
-    ArcSwapOption::new(Some(Arc::new(
-        Runtime::new().expect("Error creating tokio runtime"),
-    )))
-});
-
-/// Block the current thread on an async task, when not running inside the scheduler.
-pub fn block_on<R>(f: impl Future<Output = R>) -> R {
-    if let Some(rt) = RUNTIME.load().clone() {
-        rt.block_on(f)
-    } else {
-        panic!("Runtime has been shut down");
-    }
-}
-
-/// Run a blocking task without interrupting the async scheduler.
-#[inline]
-pub async fn unblock<F, T>(f: F) -> T
-where
-    T: Send + 'static,
-    F: FnOnce() -> T + Send + 'static,
-{
-    if let Some(rt) = RUNTIME.load().clone() {
-        rt.spawn_blocking(f)
-            .await
-            .expect("Error running blocking task")
-    } else {
-        panic!("Runtime has been shut down");
-    }
-}
-
-/// Spawn an async task into the runtime.
-#[inline]
-pub fn spawn_ok(fut: impl Future<Output = ()> + Send + 'static) {
-    if let Some(rt) = RUNTIME.load().clone() {
-        rt.spawn(fut);
-    }
-}
-
-/// Wait until a specific duration has passed (used in tests).
-/// This method must be called within `block_on` or a spawned task in order to have
-/// access to the async runtime.
-#[doc(hidden)]
-pub async fn sleep(dur: Duration) {
-    tokio::time::sleep(dur).await
-}
-
-/// Cancel an async task if it does not complete after a timeout (used in tests).
-/// This method must be called within `block_on` or a spawned task in order to have
-/// access to the async runtime.
-#[doc(hidden)]
-pub async fn timeout<R>(dur: Duration, f: impl Future<Output = R>) -> Option<R> {
-    tokio::time::timeout(dur, f).await.ok()
-}
-
-/// Shut down the async runtime.
-#[doc(hidden)]
-pub fn shutdown(max_dur: Duration) {
-    let start = Instant::now();
-    if let Some(rt_swap) = Lazy::get(&RUNTIME) {
-        if let Some(mut rt) = rt_swap.swap(None) {
-            loop {
-                match Arc::try_unwrap(rt) {
-                    Ok(rt) => {
-                        rt.shutdown_timeout(max_dur.saturating_sub(start.elapsed()));
-                        break;
-                    }
-                    Err(new_rt) => {
-                        rt = new_rt;
-                        if start.elapsed() >= max_dur {
-                            break;
-                        }
-                        thread::sleep(Duration::from_millis(1));
-                    }
-                }
-            }
-        }
-    }
+/// Configuration of the thread pool used to run blocking tasks, including
+/// encryption and ODBC operations
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockingPoolConfig {
+    /// The maximum number of threads used to run blocking tasks (default 512)
+    pub max_threads: Option<usize>,
+    /// The time after which an idle blocking thread is stopped (default 10 seconds)
+    pub keep_alive: Option<Duration>,
+    /// The maximum number of blocking tasks which may be running or queued at
+    /// once. When this limit is reached, additional tasks wait asynchronously for
+    /// an earlier task to complete rather than being added to the queue.
+    /// By default the queue is unbounded
+    pub max_pending: Option<usize>,
 }
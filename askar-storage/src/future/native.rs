@@ -0,0 +1,253 @@
+use std::{
+    future::Future,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use arc_swap::ArcSwapOption;
+use once_cell::sync::{Lazy, OnceCell};
+use tokio::{
+    runtime::{Builder, Runtime},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+
+use super::BlockingPoolConfig;
+use crate::error::Error;
+
+static BLOCKING_POOL: OnceCell<BlockingPoolConfig> = OnceCell::new();
+
+static RUNTIME: Lazy<ArcSwapOption<Runtime>> = Lazy::new(|| {
+    let config = BLOCKING_POOL.get_or_init(Default::default);
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(max_threads) = config.max_threads {
+        builder.max_blocking_threads(max_threads);
+    }
+    if let Some(keep_alive) = config.keep_alive {
+        builder.thread_keep_alive(keep_alive);
+    }
+    ArcSwapOption::new(Some(Arc::new(
+        builder.build().expect("Error creating tokio runtime"),
+    )))
+});
+
+static BLOCKING_PERMITS: Lazy<Option<Arc<Semaphore>>> = Lazy::new(|| {
+    BLOCKING_POOL
+        .get_or_init(Default::default)
+        .max_pending
+        .map(|max| Arc::new(Semaphore::new(max.min(Semaphore::MAX_PERMITS))))
+});
+
+/// Configure the thread pool used to run blocking tasks.
+///
+/// This must be called once, before any other asynchronous operation is performed,
+/// as the configuration is applied when the async runtime is started.
+pub fn configure_blocking_pool(config: BlockingPoolConfig) -> Result<(), Error> {
+    if config.max_threads == Some(0) {
+        return Err(err_msg!(
+            Input,
+            "The maximum number of threads must be positive"
+        ));
+    }
+    if config.max_pending == Some(0) {
+        return Err(err_msg!(
+            Input,
+            "The maximum number of pending tasks must be positive"
+        ));
+    }
+    BLOCKING_POOL.set(config).map_err(|_| {
+        err_msg!(
+            Unsupported,
+            "The blocking pool cannot be configured after the async runtime has started"
+        )
+    })
+}
+
+/// Wait for capacity in the blocking pool for a number of tasks, if the number
+/// of pending tasks is limited. The permits are acquired together.
+async fn blocking_permits(count: usize) -> Option<OwnedSemaphorePermit> {
+    let permits = BLOCKING_PERMITS.as_ref()?;
+    permits.clone().acquire_many_owned(count as u32).await.ok()
+}
+
+/// The largest number of tasks which may wait on the blocking pool together.
+fn blocking_batch_size() -> usize {
+    BLOCKING_POOL
+        .get()
+        .and_then(|config| config.max_pending)
+        .unwrap_or(usize::MAX)
+        .min(Semaphore::MAX_PERMITS)
+        .min(u32::MAX as usize)
+}
+
+/// Block the current thread on an async task, when not running inside the scheduler.
+pub fn block_on<R>(f: impl Future<Output = R>) -> R {
+    if let Some(rt) = RUNTIME.load().clone() {
+        rt.block_on(f)
+    } else {
+        panic!("Runtime has been shut down");
+    }
+}
+
+/// Run a blocking task without interrupting the async scheduler.
+#[inline]
+pub async fn unblock<F, T>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    if let Some(rt) = RUNTIME.load().clone() {
+        let permit = blocking_permits(1).await;
+        rt.spawn_blocking(move || {
+            let _permit = permit;
+            f()
+        })
+        .await
+        .expect("Error running blocking task")
+    } else {
+        panic!("Runtime has been shut down");
+    }
+}
+
+/// Run a set of blocking tasks concurrently, returning the results in order.
+pub(crate) async fn unblock_all<F, T>(tasks: Vec<F>) -> Vec<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    if let Some(rt) = RUNTIME.load().clone() {
+        let batch_size = blocking_batch_size();
+        let mut handles = Vec::with_capacity(tasks.len());
+        let mut tasks = tasks.into_iter().peekable();
+        while tasks.peek().is_some() {
+            let batch: Vec<F> = tasks.by_ref().take(batch_size).collect();
+            let mut permits = blocking_permits(batch.len()).await;
+            for f in batch {
+                let permit = permits.as_mut().and_then(|p| p.split(1));
+                handles.push(rt.spawn_blocking(move || {
+                    let _permit = permit;
+                    f()
+                }));
+            }
+        }
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            results.push(handle.await.expect("Error running blocking task"));
+        }
+        results
+    } else {
+        panic!("Runtime has been shut down");
+    }
+}
+
+/// Spawn an async task into the runtime.
+#[inline]
+pub fn spawn_ok(fut: impl Future<Output = ()> + Send + 'static) {
+    if let Some(rt) = RUNTIME.load().clone() {
+        rt.spawn(fut);
+    }
+}
+
+/// Run an async task in the runtime and wait for its result.
+///
+/// The returned future may be polled by any executor, such as one provided by
+/// a foreign language binding. An error is returned if the runtime has been
+/// shut down, or if the task panics or is cancelled.
+pub async fn spawn<T, E>(fut: impl Future<Output = Result<T, E>> + Send + 'static) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<Error> + Send + 'static,
+{
+    let rt = RUNTIME
+        .load()
+        .clone()
+        .ok_or_else(|| err_msg!(Unexpected, "Runtime has been shut down"))?;
+    rt.spawn(fut)
+        .await
+        .map_err(err_map!(Unexpected, "Error running async task"))?
+}
+
+/// Get the current system time.
+#[inline]
+pub fn system_time() -> SystemTime {
+    SystemTime::now()
+}
+
+/// Wait until a specific duration has passed (used in tests).
+/// This method must be called within `block_on` or a spawned task in order to have
+/// access to the async runtime.
+#[doc(hidden)]
+pub async fn sleep(dur: Duration) {
+    tokio::time::sleep(dur).await
+}
+
+/// Cancel an async task if it does not complete after a timeout (used in tests).
+/// This method must be called within `block_on` or a spawned task in order to have
+/// access to the async runtime.
+#[doc(hidden)]
+pub async fn timeout<R>(dur: Duration, f: impl Future<Output = R>) -> Option<R> {
+    tokio::time::timeout(dur, f).await.ok()
+}
+
+/// Shut down the async runtime.
+#[doc(hidden)]
+pub fn shutdown(max_dur: Duration) {
+    let start = Instant::now();
+    if let Some(rt_swap) = Lazy::get(&RUNTIME) {
+        if let Some(mut rt) = rt_swap.swap(None) {
+            loop {
+                match Arc::try_unwrap(rt) {
+                    Ok(rt) => {
+                        rt.shutdown_timeout(max_dur.saturating_sub(start.elapsed()));
+                        break;
+                    }
+                    Err(new_rt) => {
+                        rt = new_rt;
+                        if start.elapsed() >= max_dur {
+                            break;
+                        }
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configure_blocking_pool_validation() {
+        for config in [
+            BlockingPoolConfig {
+                max_threads: Some(0),
+                ..Default::default()
+            },
+            BlockingPoolConfig {
+                max_pending: Some(0),
+                ..Default::default()
+            },
+        ] {
+            assert!(configure_blocking_pool(config).is_err());
+        }
+
+        assert_eq!(block_on(unblock(|| 1)), 1);
+        assert!(configure_blocking_pool(BlockingPoolConfig::default()).is_err());
+    }
+
+    #[test]
+    fn unblock_all_ordered() {
+        let tasks: Vec<_> = (0..100).map(|i| move || i).collect();
+        assert_eq!(block_on(unblock_all(tasks)), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn spawn_task_panic() {
+        let result: Result<(), Error> = block_on(spawn(async { panic!("task panic") }));
+        assert_eq!(result.unwrap_err().kind(), crate::ErrorKind::Unexpected);
+        assert_eq!(block_on(spawn(async { Ok::<_, Error>(1) })).unwrap(), 1);
+    }
+}
@@ -0,0 +1,96 @@
+//! Task execution for WebAssembly targets
+//!
+//! No threads are available, so blocking tasks are run inline and async tasks are
+//! spawned onto the JavaScript event loop.
+
+use std::{
+    future::Future,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures_lite::future::{self, FutureExt};
+
+use super::BlockingPoolConfig;
+use crate::error::Error;
+
+/// Configure the thread pool used to run blocking tasks.
+///
+/// No blocking pool is used on this platform, so this always returns an error.
+pub fn configure_blocking_pool(_config: BlockingPoolConfig) -> Result<(), Error> {
+    Err(err_msg!(
+        Unsupported,
+        "The blocking pool is not available on this platform"
+    ))
+}
+
+/// Poll an async task to completion on the current thread.
+///
+/// The event loop cannot run while the thread is blocked, so this must only be
+/// used for tasks which do not wait on timers or other external events.
+pub fn block_on<R>(f: impl Future<Output = R>) -> R {
+    future::block_on(f)
+}
+
+/// Run a blocking task. The task is run immediately on the current thread.
+#[inline]
+pub async fn unblock<F, T>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    f()
+}
+
+/// Run a set of blocking tasks in order, returning the results.
+pub(crate) async fn unblock_all<F, T>(tasks: Vec<F>) -> Vec<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    tasks.into_iter().map(|f| f()).collect()
+}
+
+/// Spawn an async task onto the event loop.
+#[inline]
+pub fn spawn_ok(fut: impl Future<Output = ()> + Send + 'static) {
+    wasm_bindgen_futures::spawn_local(fut);
+}
+
+/// Run an async task and wait for its result.
+pub async fn spawn<T, E>(fut: impl Future<Output = Result<T, E>> + Send + 'static) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<Error> + Send + 'static,
+{
+    fut.await
+}
+
+/// Get the current system time.
+///
+/// The system clock is not accessible through `std` on this platform, so the
+/// time is read from the JavaScript host.
+pub fn system_time() -> SystemTime {
+    let millis = chrono::Utc::now().timestamp_millis();
+    UNIX_EPOCH + Duration::from_millis(u64::try_from(millis).unwrap_or_default())
+}
+
+/// Wait until a specific duration has passed (used in tests).
+#[doc(hidden)]
+pub async fn sleep(dur: Duration) {
+    gloo_timers::future::sleep(dur).await
+}
+
+/// Cancel an async task if it does not complete after a timeout (used in tests).
+#[doc(hidden)]
+pub async fn timeout<R>(dur: Duration, f: impl Future<Output = R>) -> Option<R> {
+    async { Some(f.await) }
+        .or(async {
+            sleep(dur).await;
+            None
+        })
+        .await
+}
+
+/// Shut down the async runtime. There is no runtime to stop on this platform.
+#[doc(hidden)]
+pub fn shutdown(_max_dur: Duration) {}
@@ -7,7 +7,10 @@ pub use askar_crypto as crypto;
 
 #[macro_use]
 mod error;
-pub use self::error::{Error, ErrorKind};
+pub use self::error::{
+    backend_error_info, BackendErrorCode, BackendErrorInfo, Error, ErrorContext, ErrorKind,
+    StoreNotFound,
+};
 
 #[cfg(test)]
 #[macro_use]
@@ -25,7 +28,7 @@ extern crate log;
 extern crate serde;
 
 pub mod backend;
-pub use self::backend::{Backend, BackendSession, ManageBackend};
+pub use self::backend::{Backend, BackendSession, EntrySession, ManageBackend};
 
 #[cfg(feature = "any")]
 pub mod any;
@@ -44,6 +47,8 @@ pub mod entry;
 #[doc(hidden)]
 pub mod future;
 
+pub mod redact;
+
 #[cfg(all(feature = "migration", feature = "sqlite"))]
 pub mod migration;
 
@@ -54,7 +59,9 @@ mod protect;
 pub use protect::{
     generate_raw_store_key,
     kdf::{Argon2Level, KdfMethod},
-    PassKey, StoreKeyMethod,
+    register_store_key_method, set_pass_key_validator, KeyCacheStats, MemoryLock, NameEncryption,
+    PassKey, PassKeyPolicy, PassKeyValidator, ProfileCipher, StoreKeyMethod, StoreKeyProvider,
+    UnlockPolicy,
 };
 
 mod wql;
@@ -6,20 +6,26 @@ use sqlx::{Connection, Row, SqliteConnection};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-use self::strategy::Strategy;
+use self::strategy::{IndyRow, Strategy};
 use crate::backend::sqlite::SqliteStoreOptions;
-use crate::backend::Backend;
+use crate::backend::{Backend, BackendSession, Progress, ProgressFn, ProgressPhase};
 use crate::crypto::alg::chacha20::{Chacha20Key, C20P};
 use crate::crypto::generic_array::typenum::U32;
-use crate::entry::EncEntryTag;
+use crate::entry::{EncEntryTag, EntryKind, EntryOperation};
 use crate::error::Error;
 use crate::protect::kdf::Argon2Level;
-use crate::protect::{ProfileKey, StoreKey, StoreKeyReference};
+use crate::protect::{ProfileCipher, ProfileKey, StoreKey, StoreKeyReference};
 
 mod strategy;
 
 const CHACHAPOLY_NONCE_LEN: u8 = 12;
 
+/// The identifier of the single profile created by an in-place upgrade
+const MIGRATED_PROFILE_ID: i64 = 1;
+
+/// The number of wallet records read in each batch when validating or importing a wallet
+const IMPORT_BATCH_SIZE: u32 = 100;
+
 #[derive(Deserialize, Debug, Default)]
 pub(crate) struct IndyKeyMetadata {
     keys: Vec<u8>,
@@ -152,7 +158,9 @@ impl IndySdkToAriesAskarMigration {
         debug!("Completed wallet upgrade");
 
         debug!("Re-opening wallet");
-        let db_opts = SqliteStoreOptions::new(self.spec_uri.as_str())?;
+        let mut db_opts = SqliteStoreOptions::new(self.spec_uri.as_str())?;
+        // protect the configuration of the migrated store
+        db_opts.upgrade_legacy_config = true;
         let key_method = upd_key.key_ref.into();
         let db = db_opts
             .open(Some(key_method), self.wallet_key.as_str().into(), None)
@@ -163,6 +171,123 @@ impl IndySdkToAriesAskarMigration {
         Ok(())
     }
 
+    /// Validate the migration without modifying the wallet
+    ///
+    /// The wallet key is verified and each record is decrypted, as for `migrate`,
+    /// but no changes are written. Returns the number of records which would be
+    /// migrated.
+    pub async fn migrate_dry_run(mut self) -> Result<usize, Error> {
+        if self.is_migrated().await? {
+            self.close().await?;
+            return Err(err_msg!(Backend, "Database is already migrated"));
+        }
+
+        let (indy_key, _) = self.fetch_indy_key().await?;
+        let mut count = 0;
+        let mut last_id = 0;
+        while let Some(rows) = self
+            .fetch_items_after::<IndyRow>(last_id, IMPORT_BATCH_SIZE)
+            .await?
+        {
+            for row in rows {
+                last_id = last_id.max(row.id);
+                Strategy::decrypt_item(row, &indy_key)?;
+                count += 1;
+            }
+        }
+        self.conn.close().await?;
+        debug!("Validated {} wallet records", count);
+
+        Ok(count)
+    }
+
+    /// Import the wallet records into an existing store, which may use any backend
+    ///
+    /// Unlike `migrate`, the Indy-SDK wallet is left unchanged. The records are
+    /// decrypted using the wallet key and added to the given profile of the target
+    /// store, or its active profile, along with their encrypted and plaintext tags.
+    /// All of the records are added within a single transaction, so that the target
+    /// store is left unchanged if the import fails. Returns the number of imported
+    /// records.
+    ///
+    /// When `dry_run` is set, the transaction is rolled back rather than committed,
+    /// so that records conflicting with those of the target profile are still
+    /// detected without changing the target store. When provided, `progress` is
+    /// invoked after each batch of records.
+    pub async fn migrate_into<B: Backend>(
+        mut self,
+        target: &B,
+        profile: Option<String>,
+        dry_run: bool,
+        progress: Option<&ProgressFn>,
+    ) -> Result<usize, Error> {
+        if self.is_migrated().await? {
+            self.close().await?;
+            return Err(err_msg!(Backend, "Database is already migrated"));
+        }
+
+        let (indy_key, _) = self.fetch_indy_key().await?;
+        let total = if progress.is_some() {
+            Some(self.count_items().await?)
+        } else {
+            None
+        };
+        let mut session = target.session(profile.clone(), true)?;
+        let result = async {
+            let mut count = 0;
+            let mut last_id = 0;
+            while let Some(rows) = self
+                .fetch_items_after::<IndyRow>(last_id, IMPORT_BATCH_SIZE)
+                .await?
+            {
+                for row in rows {
+                    last_id = last_id.max(row.id);
+                    let item = Strategy::decrypt_item(row, &indy_key)?;
+                    let category = String::from_utf8(item.typ)
+                        .map_err(err_map!(Input, "Record category is not valid UTF-8"))?;
+                    let name = String::from_utf8(item.name)
+                        .map_err(err_map!(Input, "Record name is not valid UTF-8"))?;
+                    session
+                        .update(
+                            EntryKind::Item,
+                            EntryOperation::Insert,
+                            &category,
+                            &name,
+                            Some(item.value.as_deref().unwrap_or_default()),
+                            Some(&item.tags),
+                            None,
+                        )
+                        .await?;
+                    count += 1;
+                }
+                Progress {
+                    phase: ProgressPhase::Migrate,
+                    profile: profile.clone(),
+                    processed: count as i64,
+                    total,
+                }
+                .report(progress);
+            }
+            Result::<_, Error>::Ok(count)
+        }
+        .await;
+        let count = match result {
+            Ok(count) => {
+                session.close(!dry_run).await?;
+                count
+            }
+            Err(err) => {
+                // discard any records added before the failure
+                session.close(false).await.ok();
+                return Err(err);
+            }
+        };
+        self.conn.close().await?;
+        debug!("Imported {} wallet records", count);
+
+        Ok(count)
+    }
+
     #[inline]
     async fn is_migrated(&mut self) -> Result<bool, Error> {
         let res: Option<SqliteRow> =
@@ -265,7 +390,7 @@ impl IndySdkToAriesAskarMigration {
             Some(profile_row) => serde_cbor::from_slice(&profile_row)
                 .map_err(err_map!(Input, "Invalid cbor encoding for profile_key"))?,
             None => {
-                let pk = ProfileKey::new()?;
+                let pk = ProfileKey::new_with_cipher(ProfileCipher::default())?;
                 let enc_pk = key.master.wrap_data(pk.to_bytes()?)?;
                 self.insert_profile(enc_pk.as_slice()).await?;
                 pk
@@ -307,8 +432,10 @@ impl IndySdkToAriesAskarMigration {
             del_ids.push(item.id);
             let ins = sqlx::query(
                 "INSERT INTO items (profile_id, kind, category, name, value)
-            VALUES (1, 2, ?1, ?2, ?3)",
+            VALUES (?1, ?2, ?3, ?4, ?5)",
             )
+            .bind(MIGRATED_PROFILE_ID)
+            .bind(EntryKind::Item as i16)
             .bind(item.category)
             .bind(item.name)
             .bind(item.value)
@@ -356,7 +483,8 @@ impl IndySdkToAriesAskarMigration {
     }
 
     async fn insert_profile(&mut self, key: &[u8]) -> Result<(), Error> {
-        sqlx::query("INSERT INTO profiles (name, profile_key) VALUES (?1, ?2)")
+        sqlx::query("INSERT INTO profiles (id, name, profile_key) VALUES (?1, ?2, ?3)")
+            .bind(MIGRATED_PROFILE_ID)
             .bind(&self.wallet_name)
             .bind(key.to_vec())
             .execute(&mut self.conn)
@@ -365,6 +493,38 @@ impl IndySdkToAriesAskarMigration {
         Ok(())
     }
 
+    async fn count_items(&mut self) -> Result<i64, Error> {
+        Ok(sqlx::query_scalar("SELECT COUNT(*) FROM items")
+            .fetch_one(&mut self.conn)
+            .await?)
+    }
+
+    async fn fetch_items_after<
+        T: Send + Unpin + for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow>,
+    >(
+        &mut self,
+        last_id: u32,
+        limit: u32,
+    ) -> Result<Option<Vec<T>>, Error> {
+        let res = sqlx::query_as(
+            "SELECT i.id, i.type, i.name, i.value, i.key,
+        (SELECT GROUP_CONCAT(HEX(te.name) || ':' || HEX(te.value))
+            FROM tags_encrypted te WHERE te.item_id = i.id) AS tags_enc,
+        (SELECT GROUP_CONCAT(HEX(tp.name) || ':' || HEX(tp.value))
+            FROM tags_plaintext tp WHERE tp.item_id = i.id) AS tags_plain
+        FROM items i WHERE i.id > ?1 ORDER BY i.id LIMIT ?2",
+        )
+        .bind(last_id)
+        .bind(limit)
+        .fetch_all(&mut self.conn)
+        .await?;
+
+        match res.len() {
+            0 => Ok(None),
+            _ => Ok(Some(res)),
+        }
+    }
+
     async fn fetch_pending_items<
         T: Send + Unpin + for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow>,
     >(
@@ -1,27 +1,27 @@
 use super::{
     EncryptionKey, IndyKey, IndySdkToAriesAskarMigration, ProfileKey, UpdatedIndyItem,
-    CHACHAPOLY_NONCE_LEN,
+    CHACHAPOLY_NONCE_LEN, MIGRATED_PROFILE_ID,
 };
 use crate::crypto::buffer::SecretBytes;
 use crate::crypto::encrypt::KeyAeadInPlace;
 use crate::crypto::repr::KeySecretBytes;
-use crate::entry::EntryTag;
+use crate::entry::{EntryKind, EntryTag};
 use crate::protect::EntryEncryptor;
 use crate::Error;
 
 #[derive(Default)]
 pub(crate) struct IndyItem {
     id: u32,
-    typ: Vec<u8>,
-    name: Vec<u8>,
-    value: Option<Vec<u8>>,
-    tags: Vec<EntryTag>,
+    pub typ: Vec<u8>,
+    pub name: Vec<u8>,
+    pub value: Option<Vec<u8>>,
+    pub tags: Vec<EntryTag>,
 }
 
 // TODO: should tags_enc and tags_plain be empty in the example?
 #[derive(sqlx::FromRow, Debug)]
 pub(crate) struct IndyRow {
-    id: u32,
+    pub id: u32,
     #[sqlx(rename = "type")]
     typ: Vec<u8>,
     name: Vec<u8>,
@@ -112,7 +112,13 @@ impl Strategy {
 
     pub fn update_item(item: IndyItem, key: &ProfileKey) -> Result<UpdatedIndyItem, Error> {
         let value = match item.value {
-            Some(v) => key.encrypt_entry_value(&item.typ, &item.name, v.into())?,
+            Some(v) => key.encrypt_entry_value(
+                MIGRATED_PROFILE_ID,
+                EntryKind::Item,
+                &item.typ,
+                &item.name,
+                v.into(),
+            )?,
             None => Default::default(),
         };
 
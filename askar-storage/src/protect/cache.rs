@@ -0,0 +1,342 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use super::{MemoryLock, ProfileId, ProfileKey};
+use crate::{error::Error, future::system_time};
+
+/// Statistics describing the usage of a profile key cache
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyCacheStats {
+    /// The number of cached profile keys
+    pub size: usize,
+    /// The maximum number of cached profile keys, if bounded
+    pub max_size: Option<usize>,
+    /// The number of lookups resolved from the cache
+    pub hits: u64,
+    /// The number of lookups requiring the profile key to be loaded
+    pub misses: u64,
+    /// The number of profile keys evicted to remain within the maximum size
+    pub evictions: u64,
+    /// The number of profile keys discarded after exceeding their time to live
+    pub expirations: u64,
+    /// Whether the memory holding the store key is locked against swapping
+    pub store_key_locked: bool,
+    /// The number of cached profile keys held in locked memory
+    pub locked_keys: usize,
+}
+
+impl KeyCacheStats {
+    /// The fraction of lookups resolved from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Extract the maximum key cache size from the store URI query parameters
+pub(crate) fn max_cached_profiles_from_query(
+    query: &mut HashMap<String, String>,
+) -> Result<Option<usize>, Error> {
+    if let Some(max) = query.remove("max_cached_profiles") {
+        let max = max.parse().map_err(err_map!(
+            Input,
+            "Error parsing 'max_cached_profiles' parameter"
+        ))?;
+        if max == 0 {
+            return Err(err_msg!(
+                Input,
+                "The 'max_cached_profiles' parameter must be positive"
+            ));
+        }
+        Ok(Some(max))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Extract the lifetime of cached profile keys from the store URI query parameters
+///
+/// The `key_cache_ttl` parameter is given in seconds.
+pub(crate) fn key_cache_ttl_from_query(
+    query: &mut HashMap<String, String>,
+) -> Result<Option<Duration>, Error> {
+    if let Some(ttl) = query.remove("key_cache_ttl") {
+        let ttl: u64 = ttl
+            .parse()
+            .map_err(err_map!(Input, "Error parsing 'key_cache_ttl' parameter"))?;
+        if ttl == 0 {
+            return Err(err_msg!(
+                Input,
+                "The 'key_cache_ttl' parameter must be positive"
+            ));
+        }
+        Ok(Some(Duration::from_secs(ttl)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Extract whether profile keys are resolved strictly from the store URI query
+/// parameters
+///
+/// When the `strict_key_resolution` parameter is set, the profile key of each
+/// session is read from the database rather than served from the cache, and any
+/// ambiguity in resolving the profile fails the session.
+pub(crate) fn strict_key_resolution_from_query(
+    query: &mut HashMap<String, String>,
+) -> Result<bool, Error> {
+    if let Some(strict) = query.remove("strict_key_resolution") {
+        strict.parse().map_err(err_map!(
+            Input,
+            "Error parsing 'strict_key_resolution' parameter"
+        ))
+    } else {
+        Ok(false)
+    }
+}
+
+/// The profile keys loaded into the key cache when a store is opened
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ProfilePreload {
+    /// Load the keys of all profiles
+    All,
+    /// Load the keys of the named profiles, ignoring any which do not exist
+    Named(Vec<String>),
+}
+
+impl ProfilePreload {
+    /// Extract the profiles to preload from the `preload_profiles` store URI query
+    /// parameter: either `*` for all profiles or a comma-separated list of names
+    pub fn from_query(query: &mut HashMap<String, String>) -> Result<Option<Self>, Error> {
+        if let Some(preload) = query.remove("preload_profiles") {
+            if preload == "*" {
+                Ok(Some(Self::All))
+            } else {
+                let names: Vec<String> = preload
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                if names.is_empty() {
+                    return Err(err_msg!(
+                        Input,
+                        "The 'preload_profiles' parameter must not be empty"
+                    ));
+                }
+                Ok(Some(Self::Named(names)))
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Check whether the key for a profile should be preloaded
+    pub fn includes(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Named(names) => names.iter().any(|n| n == name),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedProfile {
+    pid: ProfileId,
+    key: Arc<ProfileKey>,
+    lock: MemoryLock,
+    last_used: u64,
+    // only recorded when a time to live is configured. `Instant` is not
+    // available on wasm32, so the system clock is used instead
+    loaded: Option<SystemTime>,
+}
+
+/// A map of profile keys with least-recently-used eviction and optional expiry
+#[derive(Debug, Default)]
+pub(crate) struct ProfileCache {
+    entries: HashMap<String, CachedProfile>,
+    recent: BTreeMap<u64, String>,
+    counter: u64,
+    pub max_size: Option<usize>,
+    pub ttl: Option<Duration>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expirations: u64,
+}
+
+impl ProfileCache {
+    pub fn get(&mut self, name: &str) -> Option<(ProfileId, Arc<ProfileKey>)> {
+        let last_used = self.next_counter();
+        if let (Some(ttl), Some(entry)) = (self.ttl, self.entries.get(name)) {
+            let expired = entry.loaded.map_or(true, |loaded| {
+                // a clock moved backwards also expires the entry
+                system_time()
+                    .duration_since(loaded)
+                    .map_or(true, |age| age >= ttl)
+            });
+            if expired {
+                self.remove(name);
+                self.expirations += 1;
+            }
+        }
+        if let Some(entry) = self.entries.get_mut(name) {
+            self.recent.remove(&entry.last_used);
+            self.recent.insert(last_used, name.to_string());
+            entry.last_used = last_used;
+            self.hits += 1;
+            Some((entry.pid, entry.key.clone()))
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    pub fn insert(&mut self, name: String, pid: ProfileId, key: Arc<ProfileKey>) {
+        let last_used = self.next_counter();
+        let loaded = self.ttl.map(|_| system_time());
+        let lock = MemoryLock::new(&*key);
+        self.recent.insert(last_used, name.clone());
+        if let Some(prev) = self.entries.insert(
+            name,
+            CachedProfile {
+                pid,
+                key,
+                lock,
+                last_used,
+                loaded,
+            },
+        ) {
+            self.recent.remove(&prev.last_used);
+        }
+        if let Some(max_size) = self.max_size {
+            while self.entries.len() > max_size {
+                match pop_first(&mut self.recent) {
+                    Some((_, evict)) => {
+                        self.entries.remove(&evict);
+                        self.evictions += 1;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Check whether a profile is cached with the given identifier and key
+    pub fn contains(&self, name: &str, pid: ProfileId, key: &ProfileKey) -> bool {
+        self.entries
+            .get(name)
+            .is_some_and(|entry| entry.pid == pid && *entry.key == *key)
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        if let Some(prev) = self.entries.remove(name) {
+            self.recent.remove(&prev.last_used);
+        }
+    }
+
+    pub fn stats(&self, store_key_locked: bool) -> KeyCacheStats {
+        KeyCacheStats {
+            size: self.entries.len(),
+            max_size: self.max_size,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            expirations: self.expirations,
+            store_key_locked,
+            locked_keys: self
+                .entries
+                .values()
+                .filter(|entry| entry.lock.is_locked())
+                .count(),
+        }
+    }
+
+    fn next_counter(&mut self) -> u64 {
+        self.counter += 1;
+        self.counter
+    }
+}
+
+// BTreeMap::pop_first requires Rust 1.66
+fn pop_first(map: &mut BTreeMap<u64, String>) -> Option<(u64, String)> {
+    let first = *map.keys().next()?;
+    map.remove(&first).map(|value| (first, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protect::ProfileCipher;
+
+    #[test]
+    fn profile_preload_from_query() {
+        let mut query = HashMap::new();
+        assert_eq!(ProfilePreload::from_query(&mut query).unwrap(), None);
+        query.insert("preload_profiles".to_string(), "*".to_string());
+        let preload = ProfilePreload::from_query(&mut query).unwrap().unwrap();
+        assert_eq!(preload, ProfilePreload::All);
+        assert!(preload.includes("any"));
+        query.insert("preload_profiles".to_string(), "a, b".to_string());
+        let preload = ProfilePreload::from_query(&mut query).unwrap().unwrap();
+        assert!(preload.includes("a") && preload.includes("b") && !preload.includes("c"));
+        query.insert("preload_profiles".to_string(), ",".to_string());
+        assert!(ProfilePreload::from_query(&mut query).is_err());
+    }
+
+    #[test]
+    fn profile_cache_lru() {
+        let key = Arc::new(ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap());
+        let mut cache = ProfileCache {
+            max_size: Some(2),
+            ..Default::default()
+        };
+        cache.insert("a".to_string(), 1, key.clone());
+        cache.insert("b".to_string(), 2, key.clone());
+        assert_eq!(cache.get("a").map(|(pid, _)| pid), Some(1));
+        cache.insert("c".to_string(), 3, key.clone());
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").map(|(pid, _)| pid), Some(1));
+        assert_eq!(cache.get("c").map(|(pid, _)| pid), Some(3));
+        cache.remove("c");
+        assert!(cache.get("c").is_none());
+
+        let stats = cache.stats(false);
+        assert_eq!(stats.size, 1);
+        assert_eq!(stats.max_size, Some(2));
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (3, 2, 1));
+        assert_eq!(stats.hit_rate(), 0.6);
+    }
+
+    #[test]
+    fn profile_cache_ttl() {
+        let key = Arc::new(ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap());
+        let mut cache = ProfileCache {
+            ttl: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        cache.insert("a".to_string(), 1, key);
+        assert_eq!(cache.get("a").map(|(pid, _)| pid), Some(1));
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.get("a").is_none());
+        let stats = cache.stats(false);
+        assert_eq!((stats.size, stats.expirations), (0, 1));
+
+        let mut query = HashMap::new();
+        assert_eq!(key_cache_ttl_from_query(&mut query).unwrap(), None);
+        query.insert("key_cache_ttl".to_string(), "30".to_string());
+        assert_eq!(
+            key_cache_ttl_from_query(&mut query).unwrap(),
+            Some(Duration::from_secs(30))
+        );
+        query.insert("key_cache_ttl".to_string(), "0".to_string());
+        assert!(key_cache_ttl_from_query(&mut query).is_err());
+    }
+}
@@ -47,6 +47,11 @@ impl Level {
         }
     }
 
+    /// Check whether derivation at this level is less costly than at another
+    pub(crate) fn is_weaker_than(&self, other: &Self) -> bool {
+        matches!((self, other), (Self::Interactive, Self::Moderate))
+    }
+
     pub(crate) fn generate_salt(&self) -> ArrayKey<SaltSize> {
         ArrayKey::random()
     }
@@ -58,13 +58,22 @@ impl KdfMethod {
     }
 
     pub(crate) fn derive_new_key(&self, password: &str) -> Result<(StoreKey, String), Error> {
+        let detail = self.generate_detail();
+        let key = self.derive_key(password, &detail)?;
+        Ok((key, detail))
+    }
+
+    /// Check whether this method is less costly to derive than another
+    pub(crate) fn is_weaker_than(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Argon2i(level), Self::Argon2i(other)) => level.is_weaker_than(other),
+        }
+    }
+
+    /// Generate the parameters for a new derivation, such as a random salt
+    pub(crate) fn generate_detail(&self) -> String {
         match self {
-            Self::Argon2i(level) => {
-                let salt = level.generate_salt();
-                let key = level.derive_key(password.as_bytes(), salt.as_ref())?;
-                let detail = format!("?salt={}", salt.as_hex());
-                Ok((key, detail))
-            }
+            Self::Argon2i(level) => format!("?salt={}", level.generate_salt().as_hex()),
         }
     }
 
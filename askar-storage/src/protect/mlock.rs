@@ -0,0 +1,182 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter},
+    mem,
+    sync::{Mutex, MutexGuard},
+};
+
+use once_cell::sync::Lazy;
+
+// The number of live locks covering each locked page. Locks are not counted by
+// the operating system, so a page is only unlocked once no lock refers to it.
+static LOCKED_PAGES: Lazy<Mutex<BTreeMap<usize, usize>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// A best-effort lock preventing the memory of a value from being swapped to disk
+///
+/// The pages holding the value are locked with `mlock` or `VirtualLock` until
+/// the lock is dropped. Locking may fail when it is not supported on the
+/// platform or exceeds the resource limits of the process, in which case the
+/// value is left unlocked. The value must not be moved while the lock is held.
+pub struct MemoryLock {
+    pages: Option<(usize, usize)>,
+}
+
+impl MemoryLock {
+    /// Lock the memory holding a value
+    pub fn new<T: ?Sized>(value: &T) -> Self {
+        let len = mem::size_of_val(value);
+        if len == 0 {
+            return Self { pages: None };
+        }
+        let page = sys::page_size();
+        let addr = value as *const T as *const u8 as usize;
+        let start = addr - addr % page;
+        let count = (addr + len - start).div_ceil(page);
+        let mut locked = locked_pages();
+        for idx in 0..count {
+            let page_addr = start + idx * page;
+            if let Some(refs) = locked.get_mut(&page_addr) {
+                *refs += 1;
+            } else if sys::lock(page_addr, page) {
+                locked.insert(page_addr, 1);
+            } else {
+                release(&mut locked, start, idx, page);
+                return Self { pages: None };
+            }
+        }
+        Self {
+            pages: Some((start, count)),
+        }
+    }
+
+    /// Check whether the memory was successfully locked
+    pub fn is_locked(&self) -> bool {
+        self.pages.is_some()
+    }
+}
+
+impl Debug for MemoryLock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryLock")
+            .field("locked", &self.is_locked())
+            .finish()
+    }
+}
+
+impl Drop for MemoryLock {
+    fn drop(&mut self) {
+        if let Some((start, count)) = self.pages.take() {
+            let mut locked = locked_pages();
+            release(&mut locked, start, count, sys::page_size());
+        }
+    }
+}
+
+// Updates to the page counts cannot be interrupted part way, so a poisoned
+// mutex is recovered rather than panicking, which would abort within `drop`
+fn locked_pages() -> MutexGuard<'static, BTreeMap<usize, usize>> {
+    LOCKED_PAGES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn release(locked: &mut BTreeMap<usize, usize>, start: usize, count: usize, page: usize) {
+    for idx in 0..count {
+        let page_addr = start + idx * page;
+        if let Some(refs) = locked.get_mut(&page_addr) {
+            *refs -= 1;
+            if *refs == 0 {
+                locked.remove(&page_addr);
+                sys::unlock(page_addr, page);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    pub fn page_size() -> usize {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            size as usize
+        } else {
+            4096
+        }
+    }
+
+    pub fn lock(addr: usize, len: usize) -> bool {
+        unsafe { libc::mlock(addr as *const libc::c_void, len) == 0 }
+    }
+
+    pub fn unlock(addr: usize, len: usize) {
+        unsafe { libc::munlock(addr as *const libc::c_void, len) };
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use windows_sys::Win32::System::{
+        Memory::{VirtualLock, VirtualUnlock},
+        SystemInformation::{GetSystemInfo, SYSTEM_INFO},
+    };
+
+    pub fn page_size() -> usize {
+        let mut info: SYSTEM_INFO = unsafe { std::mem::zeroed() };
+        unsafe { GetSystemInfo(&mut info) };
+        if info.dwPageSize > 0 {
+            info.dwPageSize as usize
+        } else {
+            4096
+        }
+    }
+
+    pub fn lock(addr: usize, len: usize) -> bool {
+        unsafe { VirtualLock(addr as *const _, len) != 0 }
+    }
+
+    pub fn unlock(addr: usize, len: usize) {
+        unsafe { VirtualUnlock(addr as *const _, len) };
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    pub fn page_size() -> usize {
+        4096
+    }
+
+    pub fn lock(_addr: usize, _len: usize) -> bool {
+        false
+    }
+
+    pub fn unlock(_addr: usize, _len: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_lock_shared_pages() {
+        // use a page within a private allocation so no other lock may share it
+        let page = sys::page_size();
+        let buf = vec![1u8; page * 2];
+        let offset = (page - buf.as_ptr() as usize % page) % page;
+        let value = &buf[offset..offset + page / 2];
+        let page_addr = value.as_ptr() as usize;
+        let first = MemoryLock::new(value);
+        if !first.is_locked() {
+            // locking may be unavailable or limited in the test environment
+            return;
+        }
+        let second = MemoryLock::new(&value[..32]);
+        assert!(second.is_locked());
+        assert_eq!(LOCKED_PAGES.lock().unwrap().get(&page_addr), Some(&2));
+        drop(first);
+        assert_eq!(LOCKED_PAGES.lock().unwrap().get(&page_addr), Some(&1));
+        drop(second);
+        assert!(LOCKED_PAGES.lock().unwrap().get(&page_addr).is_none());
+        assert!(!MemoryLock::new(&[0u8; 0]).is_locked());
+    }
+}
@@ -1,6 +1,6 @@
 //! Storage encryption
 
-use std::{collections::HashMap, sync::Arc};
+use std::{sync::Arc, time::Duration};
 
 use async_lock::RwLock;
 
@@ -8,18 +8,48 @@ pub mod kdf;
 
 pub mod hmac_key;
 
+mod cache;
+pub use self::cache::KeyCacheStats;
+use self::cache::ProfileCache;
+pub(crate) use self::cache::{
+    key_cache_ttl_from_query, max_cached_profiles_from_query, strict_key_resolution_from_query,
+    ProfilePreload,
+};
+
+mod mlock;
+pub use self::mlock::MemoryLock;
+
 mod pass_key;
-pub use self::pass_key::PassKey;
+pub use self::pass_key::{set_pass_key_validator, PassKey, PassKeyPolicy, PassKeyValidator};
 
 mod profile_key;
-pub use self::profile_key::ProfileKey;
+pub(crate) use self::profile_key::{
+    expiry_bucket_from_query, is_plain_entry_value, parse_expiry_bucket, EXPIRY_BUCKET_CONFIG,
+    NAME_ENCRYPTION_CONFIG, PROFILE_CIPHER_CONFIG,
+};
+pub use self::profile_key::{NameEncryption, ProfileCipher, ProfileKey};
 
 mod store_key;
-pub use self::store_key::{generate_raw_store_key, StoreKey, StoreKeyMethod, StoreKeyReference};
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) use self::store_key::PENDING_KEY_CHECK_CONFIG;
+pub(crate) use self::store_key::PENDING_KEY_CONFIG;
+pub use self::store_key::{
+    generate_raw_store_key, register_store_key_method, StoreKey, StoreKeyMethod, StoreKeyProvider,
+    StoreKeyReference,
+};
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) use self::store_key::{
+    store_key_method_uri, CONFIG_MAC_CONFIG, KEY_GENERATION_CONFIG, LEGACY_STORE_VERSION,
+    STORE_KEY_CIPHER, STORE_VERSION,
+};
+
+mod unlock;
+pub use self::unlock::UnlockPolicy;
+pub(crate) use self::unlock::{UnlockFailures, UNLOCK_FAILURES_CONFIG, UNLOCK_POLICY_CONFIG};
 
 use crate::{
     crypto::buffer::SecretBytes,
-    entry::{EncEntryTag, EntryTag},
+    entry::{EncEntryTag, EntryKind, EntryTag},
     error::Error,
     future::unblock,
 };
@@ -28,18 +58,63 @@ pub type ProfileId = i64;
 
 #[derive(Debug)]
 pub struct KeyCache {
-    profile_info: RwLock<HashMap<String, (ProfileId, Arc<ProfileKey>)>>,
+    profile_info: RwLock<ProfileCache>,
     pub(crate) store_key: Arc<StoreKey>,
+    store_key_lock: MemoryLock,
+    pub(crate) profile_cipher: ProfileCipher,
+    pub(crate) name_encryption: NameEncryption,
+    pub(crate) expiry_bucket: Option<u32>,
+    pub(crate) strict: bool,
+    pub(crate) key_generation: u64,
 }
 
 impl KeyCache {
     pub fn new(store_key: impl Into<Arc<StoreKey>>) -> Self {
+        let store_key = store_key.into();
+        let store_key_lock = MemoryLock::new(&*store_key);
         Self {
-            profile_info: RwLock::new(HashMap::new()),
-            store_key: store_key.into(),
+            profile_info: RwLock::new(ProfileCache::default()),
+            store_key,
+            store_key_lock,
+            profile_cipher: ProfileCipher::default(),
+            name_encryption: NameEncryption::default(),
+            expiry_bucket: None,
+            strict: false,
+            key_generation: 0,
         }
     }
 
+    /// Create an empty cache with the same configuration and a replacement store
+    /// key, advancing the store key generation
+    pub async fn with_store_key(&self, store_key: impl Into<Arc<StoreKey>>) -> Self {
+        let mut cache = Self::new(store_key);
+        cache.profile_cipher = self.profile_cipher;
+        cache.name_encryption = self.name_encryption;
+        cache.expiry_bucket = self.expiry_bucket;
+        cache.strict = self.strict;
+        cache.key_generation = self.key_generation + 1;
+        let info = self.profile_info.read().await;
+        cache.set_max_size(info.max_size);
+        cache.set_ttl(info.ttl);
+        cache
+    }
+
+    /// Limit the number of cached profile keys, evicting the least recently used
+    pub fn set_max_size(&mut self, max_size: Option<usize>) {
+        self.profile_info.get_mut().max_size = max_size;
+    }
+
+    /// Require the profile key of each session to be read from the database,
+    /// failing on any ambiguity in resolving the profile
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Limit the time for which a profile key is cached before it is reloaded
+    pub fn set_ttl(&mut self, ttl: Option<Duration>) {
+        self.profile_info.get_mut().ttl = ttl;
+    }
+
     pub async fn load_key(&self, ciphertext: Vec<u8>) -> Result<ProfileKey, Error> {
         let store_key = self.store_key.clone();
         unblock(move || {
@@ -54,15 +129,101 @@ impl KeyCache {
     pub fn add_profile_mut(&mut self, ident: String, pid: ProfileId, key: ProfileKey) {
         self.profile_info
             .get_mut()
-            .insert(ident, (pid, Arc::new(key)));
+            .insert(ident, pid, Arc::new(key));
+    }
+
+    /// Decrypt a batch of profile keys and add them to the cache
+    pub async fn preload_profiles(
+        &mut self,
+        profiles: Vec<(String, ProfileId, Vec<u8>)>,
+    ) -> Result<(), Error> {
+        let store_key = self.store_key.clone();
+        let keys = unblock(move || {
+            profiles
+                .into_iter()
+                .map(|(ident, pid, ciphertext)| {
+                    let data = store_key
+                        .unwrap_data(ciphertext)
+                        .map_err(err_map!(Encryption, "Error decrypting profile key"))?;
+                    Ok((ident, pid, ProfileKey::from_slice(data.as_ref())?))
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        })
+        .await?;
+        for (ident, pid, key) in keys {
+            self.add_profile_mut(ident, pid, key);
+        }
+        Ok(())
+    }
+
+    /// Re-encrypt a batch of profile keys using a new store key
+    pub async fn rewrap_profile_keys(
+        &self,
+        store_key: Arc<StoreKey>,
+        profiles: Vec<(ProfileId, Vec<u8>)>,
+    ) -> Result<Vec<(ProfileId, Vec<u8>)>, Error> {
+        let prev_key = self.store_key.clone();
+        unblock(move || {
+            profiles
+                .into_iter()
+                .map(|(pid, ciphertext)| {
+                    let data = prev_key
+                        .unwrap_data(ciphertext)
+                        .map_err(err_map!(Encryption, "Error decrypting profile key"))?;
+                    Ok((pid, store_key.wrap_data(data)?))
+                })
+                .collect()
+        })
+        .await
     }
 
     pub async fn add_profile(&self, ident: String, pid: ProfileId, key: Arc<ProfileKey>) {
-        self.profile_info.write().await.insert(ident, (pid, key));
+        self.profile_info.write().await.insert(ident, pid, key);
+    }
+
+    /// Load the profile key from the single profile row matching a name or alias,
+    /// updating the cache to reflect the stored key
+    ///
+    /// The cache is only locked for writing when the stored key differs from the
+    /// cached one, or when it could not be loaded.
+    pub async fn resolve_profile_strict(
+        &self,
+        mut rows: Vec<(ProfileId, String, Vec<u8>)>,
+    ) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
+        if rows.len() > 1 {
+            return Err(err_msg!(Unexpected, "Ambiguous profile name"));
+        }
+        let (pid, name, ciphertext) = rows
+            .pop()
+            .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+        match self.load_key(ciphertext).await {
+            Ok(key) => {
+                let key = Arc::new(key);
+                if !self.profile_info.read().await.contains(&name, pid, &key) {
+                    self.add_profile(name, pid, key.clone()).await;
+                }
+                Ok((pid, key))
+            }
+            Err(err) => {
+                self.remove_profile(&name).await;
+                Err(err)
+            }
+        }
     }
 
     pub async fn get_profile(&self, name: &str) -> Option<(ProfileId, Arc<ProfileKey>)> {
-        self.profile_info.read().await.get(name).cloned()
+        self.profile_info.write().await.get(name)
+    }
+
+    pub async fn remove_profile(&self, name: &str) {
+        self.profile_info.write().await.remove(name);
+    }
+
+    pub async fn stats(&self) -> KeyCacheStats {
+        self.profile_info
+            .read()
+            .await
+            .stats(self.store_key_lock.is_locked())
     }
 }
 
@@ -75,6 +236,18 @@ pub(crate) trait EntryEncryptor {
     fn encrypt_entry_name(&self, name: SecretBytes) -> Result<Vec<u8>, Error>;
     fn encrypt_entry_value(
         &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        value: SecretBytes,
+    ) -> Result<Vec<u8>, Error>;
+    /// Encode an entry value to be stored without encryption, authenticated
+    /// against its entry
+    fn encode_entry_value_plain(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
         category: &[u8],
         name: &[u8],
         value: SecretBytes,
@@ -85,10 +258,20 @@ pub(crate) trait EntryEncryptor {
     fn decrypt_entry_name(&self, enc_name: Vec<u8>) -> Result<String, Error>;
     fn decrypt_entry_value(
         &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
         category: &[u8],
         name: &[u8],
         enc_value: Vec<u8>,
     ) -> Result<SecretBytes, Error>;
+    fn decrypt_entry_value_slice(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        enc_value: &[u8],
+    ) -> Result<SecretBytes, Error>;
     fn decrypt_entry_tags(&self, enc_tags: Vec<EncEntryTag>) -> Result<Vec<EntryTag>, Error>;
 }
 
@@ -103,6 +286,18 @@ impl EntryEncryptor for NullEncryptor {
     }
     fn encrypt_entry_value(
         &self,
+        _profile_id: ProfileId,
+        _kind: EntryKind,
+        _category: &[u8],
+        _name: &[u8],
+        value: SecretBytes,
+    ) -> Result<Vec<u8>, Error> {
+        Ok(value.into_vec())
+    }
+    fn encode_entry_value_plain(
+        &self,
+        _profile_id: ProfileId,
+        _kind: EntryKind,
         _category: &[u8],
         _name: &[u8],
         value: SecretBytes,
@@ -135,12 +330,24 @@ impl EntryEncryptor for NullEncryptor {
     }
     fn decrypt_entry_value(
         &self,
+        _profile_id: ProfileId,
+        _kind: EntryKind,
         _category: &[u8],
         _name: &[u8],
         enc_value: Vec<u8>,
     ) -> Result<SecretBytes, Error> {
         Ok(enc_value.into())
     }
+    fn decrypt_entry_value_slice(
+        &self,
+        _profile_id: ProfileId,
+        _kind: EntryKind,
+        _category: &[u8],
+        _name: &[u8],
+        enc_value: &[u8],
+    ) -> Result<SecretBytes, Error> {
+        Ok(SecretBytes::from_slice(enc_value))
+    }
     fn decrypt_entry_tags(&self, enc_tags: Vec<EncEntryTag>) -> Result<Vec<EntryTag>, Error> {
         enc_tags.into_iter().try_fold(vec![], |mut acc, tag| {
             let name = String::from_utf8(tag.name).map_err(err_map!(Encryption))?;
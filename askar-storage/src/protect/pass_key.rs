@@ -5,8 +5,13 @@ use std::{
     fmt::{self, Debug, Formatter},
     mem::ManuallyDrop,
     ops::Deref,
+    sync::{Arc, RwLock},
 };
 
+use crate::error::Error;
+
+static PASS_KEY_VALIDATOR: RwLock<Option<Arc<dyn PassKeyValidator>>> = RwLock::new(None);
+
 /// A possibly-empty password or key used to derive a store key
 #[derive(Clone, Default)]
 pub struct PassKey<'a>(Option<Cow<'a, str>>);
@@ -22,6 +27,31 @@ impl<'a> PassKey<'a> {
         PassKey(None)
     }
 
+    /// Combine the two secrets required by a split-knowledge store key
+    ///
+    /// The shares are encoded as the byte length of the first share, followed by
+    /// a colon and the concatenation of both shares. Callers outside of Rust may
+    /// construct the same encoding directly.
+    pub fn from_shares(first: &str, second: &str) -> PassKey<'static> {
+        PassKey::from(format!("{}:{}{}", first.len(), first, second))
+    }
+
+    /// Separate the two secrets of a split-knowledge pass key
+    pub(crate) fn shares(&self) -> Result<(&str, &str), Error> {
+        let (len, shares) = self
+            .split_once(':')
+            .ok_or_else(|| err_msg!(Input, "Pass key does not contain two shares"))?;
+        let len: usize = len
+            .parse()
+            .map_err(err_map!(Input, "Invalid pass key share length"))?;
+        match (shares.get(..len), shares.get(len..)) {
+            (Some(first), Some(second)) if !first.is_empty() && !second.is_empty() => {
+                Ok((first, second))
+            }
+            _ => Err(err_msg!(Input, "Pass key does not contain two shares")),
+        }
+    }
+
     pub(crate) fn is_none(&self) -> bool {
         self.0.is_none()
     }
@@ -97,3 +127,129 @@ impl Zeroize for PassKey<'_> {
         }
     }
 }
+
+/// A check applied to pass keys used to derive a new store key
+///
+/// Validation is performed when provisioning or rekeying a store with
+/// `StoreKeyMethod::DeriveKey`, and is applied to each share individually for
+/// `StoreKeyMethod::SplitKey`. Raw keys are not subject to validation.
+pub trait PassKeyValidator: Send + Sync {
+    /// Check the pass key, returning a description of the problem when it is rejected
+    fn validate(&self, pass_key: &str) -> Result<(), String>;
+}
+
+impl<F> PassKeyValidator for F
+where
+    F: Fn(&str) -> Result<(), String> + Send + Sync,
+{
+    fn validate(&self, pass_key: &str) -> Result<(), String> {
+        self(pass_key)
+    }
+}
+
+/// Install the global pass key validator, replacing any existing validator
+///
+/// Passing `None` disables validation.
+pub fn set_pass_key_validator(validator: Option<Arc<dyn PassKeyValidator>>) {
+    *PASS_KEY_VALIDATOR.write().unwrap() = validator;
+}
+
+pub(crate) fn validate_pass_key(pass_key: &PassKey<'_>) -> Result<(), Error> {
+    let validator = PASS_KEY_VALIDATOR.read().unwrap().clone();
+    if let Some(validator) = validator {
+        validator
+            .validate(pass_key)
+            .map_err(|reason| err_msg!(Input, "Pass key rejected: {}", reason))?;
+    }
+    Ok(())
+}
+
+/// A built-in pass key policy based on length and estimated entropy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PassKeyPolicy {
+    /// The minimum number of characters
+    pub min_length: usize,
+    /// The minimum estimated entropy in bits
+    pub min_entropy_bits: u32,
+}
+
+impl PassKeyPolicy {
+    /// Estimate the entropy of a pass key based on its length and character classes
+    ///
+    /// This is an upper bound which assumes characters are selected at random, and
+    /// is only intended to reject short or trivially constructed pass keys.
+    pub fn estimate_entropy(pass_key: &str) -> u32 {
+        let (mut lower, mut upper, mut digit, mut symbol, mut other) =
+            (false, false, false, false, false);
+        for c in pass_key.chars() {
+            match c {
+                'a'..='z' => lower = true,
+                'A'..='Z' => upper = true,
+                '0'..='9' => digit = true,
+                c if c.is_ascii() => symbol = true,
+                _ => other = true,
+            }
+        }
+        let pool = [
+            (lower, 26),
+            (upper, 26),
+            (digit, 10),
+            (symbol, 33),
+            (other, 100),
+        ]
+        .iter()
+        .filter(|(present, _)| *present)
+        .map(|(_, size)| size)
+        .sum::<u32>();
+        if pool == 0 {
+            return 0;
+        }
+        (pass_key.chars().count() as f64 * (pool as f64).log2()) as u32
+    }
+}
+
+impl PassKeyValidator for PassKeyPolicy {
+    fn validate(&self, pass_key: &str) -> Result<(), String> {
+        if pass_key.chars().count() < self.min_length {
+            return Err(format!(
+                "must contain at least {} characters",
+                self.min_length
+            ));
+        }
+        if Self::estimate_entropy(pass_key) < self.min_entropy_bits {
+            return Err(format!(
+                "estimated entropy is below {} bits",
+                self.min_entropy_bits
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pass_key_policy() {
+        let policy = PassKeyPolicy {
+            min_length: 8,
+            min_entropy_bits: 50,
+        };
+        assert!(policy.validate("short").is_err());
+        assert!(policy.validate("aaaaaaaa").is_err());
+        assert!(policy.validate("Tr0ub4dor&3").is_ok());
+        assert_eq!(PassKeyPolicy::estimate_entropy(""), 0);
+        assert_eq!(PassKeyPolicy::estimate_entropy("0000"), 13);
+    }
+
+    #[test]
+    fn pass_key_shares() {
+        let pass = PassKey::from_shares("fïrst:", "second");
+        assert_eq!(&*pass, "7:fïrst:second");
+        assert_eq!(pass.shares().unwrap(), ("fïrst:", "second"));
+        assert!(PassKey::from("pass").shares().is_err());
+        assert!(PassKey::from("4:pass").shares().is_err());
+        assert!(PassKey::from("1:ïpass").shares().is_err());
+    }
+}
@@ -1,22 +1,446 @@
-use serde::{Deserialize, Serialize};
-use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Sha256, Sha512};
 
 use super::hmac_key::{HmacDerive, HmacKey};
-use super::EntryEncryptor;
+use super::{EntryEncryptor, ProfileId};
 use crate::{
     crypto::{
-        alg::chacha20::{Chacha20Key, C20P},
+        alg::{
+            aes::{A256CbcHs512, A256Gcm, AesKey},
+            chacha20::{Chacha20Key, C20P},
+        },
         buffer::{ArrayKey, ResizeBuffer, SecretBytes, WriteBuffer},
         encrypt::{KeyAeadInPlace, KeyAeadMeta},
-        generic_array::typenum::{Unsigned, U32},
+        generic_array::typenum::{Unsigned, U16, U32, U64},
         kdf::FromKeyDerivation,
         repr::KeyGen,
     },
-    entry::{EncEntryTag, EntryTag},
+    entry::{EncEntryTag, EntryKind, EntryTag},
     error::Error,
 };
 
-pub type ProfileKey = ProfileKeyImpl<Chacha20Key<C20P>, HmacKey<Sha256, U32>>;
+type ChaChaProfileKey = ProfileKeyImpl<Chacha20Key<C20P>, HmacKey<Sha256, U32>>;
+type AesGcmProfileKey = ProfileKeyImpl<AesKey<A256Gcm>, HmacKey<Sha256, U32>>;
+type AesCbcHmacProfileKey = ProfileKeyImpl<AesKey<A256CbcHs512>, HmacKey<Sha512, U64>>;
+
+/// The name of the config table entry holding the cipher for new profiles
+pub(crate) const PROFILE_CIPHER_CONFIG: &str = "profile_cipher";
+
+/// The name of the config table entry holding the name encryption of new profiles
+pub(crate) const NAME_ENCRYPTION_CONFIG: &str = "name_encryption";
+
+/// The name of the config table entry holding the expiry bucket of new profiles
+pub(crate) const EXPIRY_BUCKET_CONFIG: &str = "expiry_bucket";
+
+/// The largest expansion of an encrypted input: the AES-CBC-HMAC IV, padding and tag
+const MAX_ENCRYPTED_OVERHEAD: usize = 64;
+
+/// The separator between the segments of a hierarchical category
+pub(crate) const CATEGORY_SEPARATOR: char = '/';
+
+/// The length of the blind index of each category namespace segment
+pub(crate) const NAMESPACE_SEGMENT_LEN: usize = 16;
+
+/// Distinguishes namespace blind indexes from the derivation of searchable nonces.
+/// The leading byte never occurs in UTF-8 text
+const NAMESPACE_DOMAIN: &[u8] = b"\xffnamespace";
+
+/// Distinguish the blind indexes of randomized entry categories and names
+const CATEGORY_INDEX_DOMAIN: &[u8] = b"\xffindex:category";
+const NAME_INDEX_DOMAIN: &[u8] = b"\xffindex:name";
+
+/// Distinguishes encrypted expiry times from the entry values sharing their key
+const EXPIRY_DOMAIN: &[u8] = b"\xffexpiry";
+
+/// Distinguishes the tags of unencrypted entry values from blind indexes
+const PLAIN_VALUE_DOMAIN: &[u8] = b"\xffplain";
+
+/// Marks an entry value stored without encryption. The value follows the prefix
+/// and is itself followed by a tag binding it to the entry
+const PLAIN_VALUE_PREFIX: &[u8] = b"\x00askar:plain\x00";
+
+/// The length of the tag of an unencrypted entry value
+const PLAIN_VALUE_TAG_LEN: usize = 32;
+
+/// Check whether a stored entry value is in the unencrypted format
+pub(crate) fn is_plain_entry_value(value: &[u8]) -> bool {
+    value.len() >= PLAIN_VALUE_PREFIX.len() + PLAIN_VALUE_TAG_LEN
+        && value.starts_with(PLAIN_VALUE_PREFIX)
+}
+
+/// Supported ciphers for the encryption of profile entries
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum ProfileCipher {
+    /// ChaCha20-Poly1305, preferred on platforms without AES hardware support
+    #[default]
+    #[serde(rename = "c20p")]
+    ChaCha20Poly1305,
+    /// AES-256-GCM
+    #[serde(rename = "a256gcm")]
+    Aes256Gcm,
+    /// AES-256-CBC with HMAC-SHA512
+    #[serde(rename = "a256cbchs512")]
+    Aes256CbcHmac,
+}
+
+impl ProfileCipher {
+    /// Get the identifier of the cipher
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ChaCha20Poly1305 => "c20p",
+            Self::Aes256Gcm => "a256gcm",
+            Self::Aes256CbcHmac => "a256cbchs512",
+        }
+    }
+
+    /// Extract the cipher from the `profile_cipher` store URI query parameter
+    pub(crate) fn from_query(query: &mut HashMap<String, String>) -> Result<Self, Error> {
+        if let Some(cipher) = query.remove("profile_cipher") {
+            Self::from_str(&cipher)
+                .map_err(err_map!(Input, "Error parsing 'profile_cipher' parameter"))
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+impl FromStr for ProfileCipher {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "c20p" => Ok(Self::ChaCha20Poly1305),
+            "a256gcm" => Ok(Self::Aes256Gcm),
+            "a256cbchs512" => Ok(Self::Aes256CbcHmac),
+            _ => Err(err_msg!(Unsupported, "Unknown profile cipher")),
+        }
+    }
+}
+
+impl Display for ProfileCipher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Supported methods for the encryption of entry categories and names
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum NameEncryption {
+    /// Encrypt with a nonce derived from the plaintext, so that the ciphertext
+    /// itself may be searched
+    #[default]
+    #[serde(rename = "det")]
+    Deterministic,
+    /// Encrypt with a random nonce, storing a keyed hash of the plaintext as a
+    /// separate blind index for searches
+    ///
+    /// Equal categories and names no longer produce equal ciphertexts, and the
+    /// fixed-length blind index does not reveal the length of the plaintext.
+    #[serde(rename = "rnd")]
+    Randomized,
+}
+
+impl NameEncryption {
+    /// Get the identifier of the name encryption method
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Deterministic => "det",
+            Self::Randomized => "rnd",
+        }
+    }
+
+    /// Check whether names are encrypted deterministically
+    pub fn is_deterministic(&self) -> bool {
+        *self == Self::Deterministic
+    }
+
+    /// Extract the method from the `name_encryption` store URI query parameter
+    pub(crate) fn from_query(query: &mut HashMap<String, String>) -> Result<Self, Error> {
+        if let Some(method) = query.remove("name_encryption") {
+            Self::from_str(&method)
+                .map_err(err_map!(Input, "Error parsing 'name_encryption' parameter"))
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+impl FromStr for NameEncryption {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "det" => Ok(Self::Deterministic),
+            "rnd" => Ok(Self::Randomized),
+            _ => Err(err_msg!(Unsupported, "Unknown name encryption method")),
+        }
+    }
+}
+
+impl Display for NameEncryption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Extract the expiry bucket from the `expiry_bucket` store URI query parameter
+///
+/// The bucket is given in seconds. When set, the expiry times of entries in new
+/// profiles are encrypted, and only rounded up to the end of their bucket in
+/// plaintext.
+pub(crate) fn expiry_bucket_from_query(
+    query: &mut HashMap<String, String>,
+) -> Result<Option<u32>, Error> {
+    if let Some(bucket) = query.remove("expiry_bucket") {
+        let bucket: u32 = bucket
+            .parse()
+            .map_err(err_map!(Input, "Error parsing 'expiry_bucket' parameter"))?;
+        if bucket == 0 {
+            return Err(err_msg!(
+                Input,
+                "The 'expiry_bucket' parameter must be positive"
+            ));
+        }
+        Ok(Some(bucket))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parse the expiry bucket recorded in the store configuration
+pub(crate) fn parse_expiry_bucket(value: Option<&str>) -> Result<Option<u32>, Error> {
+    value
+        .map(|bucket| match bucket.parse() {
+            Ok(bucket) if bucket > 0 => Ok(bucket),
+            _ => Err(err_msg!(Unsupported, "Invalid expiry bucket")),
+        })
+        .transpose()
+}
+
+/// The keys required to encrypt and decrypt the entries of a profile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileKey {
+    ChaCha20Poly1305(ChaChaProfileKey),
+    Aes256Gcm(AesGcmProfileKey),
+    Aes256CbcHmac(AesCbcHmacProfileKey),
+}
+
+macro_rules! with_profile_key {
+    ($slf:expr, $key:ident => $body:expr) => {
+        match $slf {
+            ProfileKey::ChaCha20Poly1305($key) => $body,
+            ProfileKey::Aes256Gcm($key) => $body,
+            ProfileKey::Aes256CbcHmac($key) => $body,
+        }
+    };
+}
+
+impl ProfileKey {
+    /// Generate a new profile key for a specific cipher
+    pub fn new_with_cipher(cipher: ProfileCipher) -> Result<Self, Error> {
+        Self::new_with_options(cipher, NameEncryption::default(), None)
+    }
+
+    /// Generate a new profile key for a specific cipher, name encryption method
+    /// and expiry bucket
+    pub fn new_with_options(
+        cipher: ProfileCipher,
+        name_encryption: NameEncryption,
+        expiry_bucket: Option<u32>,
+    ) -> Result<Self, Error> {
+        let mut key = match cipher {
+            ProfileCipher::ChaCha20Poly1305 => Self::ChaCha20Poly1305(ProfileKeyImpl::new()?),
+            ProfileCipher::Aes256Gcm => Self::Aes256Gcm(ProfileKeyImpl::new()?),
+            ProfileCipher::Aes256CbcHmac => Self::Aes256CbcHmac(ProfileKeyImpl::new()?),
+        };
+        with_profile_key!(&mut key, k => {
+            k.name_encryption = name_encryption;
+            k.expiry_bucket = expiry_bucket;
+        });
+        Ok(key)
+    }
+
+    /// Get the cipher used for entry encryption
+    pub fn cipher(&self) -> ProfileCipher {
+        match self {
+            Self::ChaCha20Poly1305(_) => ProfileCipher::ChaCha20Poly1305,
+            Self::Aes256Gcm(_) => ProfileCipher::Aes256Gcm,
+            Self::Aes256CbcHmac(_) => ProfileCipher::Aes256CbcHmac,
+        }
+    }
+
+    /// Get the method used for the encryption of entry categories and names
+    pub fn name_encryption(&self) -> NameEncryption {
+        with_profile_key!(self, key => key.name_encryption)
+    }
+
+    /// Get the granularity in seconds of the plaintext expiry times of entries,
+    /// when expiry times are encrypted
+    pub fn expiry_bucket(&self) -> Option<u32> {
+        with_profile_key!(self, key => key.expiry_bucket)
+    }
+
+    /// Create a copy of this key with a new random tag HMAC key
+    ///
+    /// Only the encrypted tag names and values, which serve as the search index,
+    /// must be re-encrypted under the resulting key.
+    pub fn with_new_tags_hmac_key(&self) -> Result<Self, Error> {
+        let mut key = Self::from_slice(self.to_bytes()?.as_ref())?;
+        with_profile_key!(&mut key, k => k.tags_hmac_key = KeyGen::random()?);
+        Ok(key)
+    }
+
+    pub fn to_bytes(&self) -> Result<SecretBytes, Error> {
+        serde_cbor::to_vec(self)
+            .map(SecretBytes::from)
+            .map_err(err_map!(Unexpected, "Error serializing profile key"))
+    }
+
+    pub fn from_slice(input: &[u8]) -> Result<Self, Error> {
+        serde_cbor::from_slice(input).map_err(err_map!(Unsupported, "Invalid profile key"))
+    }
+
+    pub fn encrypt_tag_name(&self, name: SecretBytes) -> Result<Vec<u8>, Error> {
+        with_profile_key!(self, key => key.encrypt_tag_name(name))
+    }
+
+    pub fn encrypt_tag_value(&self, value: SecretBytes) -> Result<Vec<u8>, Error> {
+        with_profile_key!(self, key => key.encrypt_tag_value(value))
+    }
+
+    pub fn decrypt_tag_name(&self, enc_tag_name: Vec<u8>) -> Result<SecretBytes, Error> {
+        with_profile_key!(self, key => key.decrypt_tag_name(enc_tag_name))
+    }
+
+    pub fn decrypt_tag_value(&self, enc_tag_value: Vec<u8>) -> Result<SecretBytes, Error> {
+        with_profile_key!(self, key => key.decrypt_tag_value(enc_tag_value))
+    }
+
+    /// Compute the blind index of the namespaces containing a category
+    ///
+    /// The index concatenates a keyed hash of each leading path of the category,
+    /// so that the index of a namespace is a prefix of the index of every category
+    /// within it.
+    pub fn category_namespace(&self, category: &str) -> Result<Vec<u8>, Error> {
+        with_profile_key!(self, key => key.category_namespace(category))
+    }
+
+    /// Encrypt the category and name of an entry with a random nonce
+    ///
+    /// Returns `None` unless the key uses randomized name encryption, in which
+    /// case the encrypted category and name columns hold blind indexes and the
+    /// result must be stored alongside them.
+    ///
+    /// The ciphertext is bound to the profile, kind and category index of the
+    /// entry.
+    pub fn encrypt_entry_names(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        with_profile_key!(self, key => key.encrypt_entry_names(profile_id, kind, category, name))
+    }
+
+    /// Decrypt the category and name of an entry from the stored columns
+    ///
+    /// The randomized ciphertext is used when present, otherwise the category and
+    /// name columns are decrypted directly.
+    pub fn decrypt_entry_names(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        enc_category: Vec<u8>,
+        enc_name: Vec<u8>,
+        enc_names: Option<Vec<u8>>,
+    ) -> Result<(String, String), Error> {
+        if let Some(enc_names) = enc_names {
+            with_profile_key!(self, key => key.decrypt_entry_names(profile_id, kind, &enc_category, enc_names))
+        } else {
+            Ok((
+                self.decrypt_entry_category(enc_category)?,
+                self.decrypt_entry_name(enc_name)?,
+            ))
+        }
+    }
+
+    /// Encrypt the expiry time of an entry, in milliseconds since the epoch
+    ///
+    /// The expiry time is bound to the stored (encrypted) category and name of
+    /// the entry, so that it may be checked before they are decrypted.
+    pub fn encrypt_entry_expiry(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        expiry_ms: i64,
+    ) -> Result<Vec<u8>, Error> {
+        with_profile_key!(self, key => key.encrypt_entry_expiry(profile_id, kind, category, name, expiry_ms))
+    }
+
+    /// Decrypt the expiry time of an entry, in milliseconds since the epoch
+    pub fn decrypt_entry_expiry(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        enc_expiry: &[u8],
+    ) -> Result<i64, Error> {
+        with_profile_key!(self, key => key.decrypt_entry_expiry(profile_id, kind, category, name, enc_expiry))
+    }
+}
+
+/// The serialized form of a profile key using a cipher other than the default
+#[derive(Serialize)]
+#[serde(bound(serialize = "Key: Serialize, HmacKey: Serialize"))]
+struct TaggedProfileKey<'a, Key, HmacKey> {
+    alg: ProfileCipher,
+    #[serde(flatten)]
+    key: &'a ProfileKeyImpl<Key, HmacKey>,
+}
+
+impl Serialize for ProfileKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let alg = self.cipher();
+        match self {
+            // keys using the default cipher retain the original format
+            Self::ChaCha20Poly1305(key) => key.serialize(serializer),
+            Self::Aes256Gcm(key) => TaggedProfileKey { alg, key }.serialize(serializer),
+            Self::Aes256CbcHmac(key) => TaggedProfileKey { alg, key }.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ProfileKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde_cbor::{value::from_value, Value};
+
+        let value = Value::deserialize(deserializer)?;
+        let cipher = match &value {
+            Value::Map(map) => match map.get(&Value::Text("alg".to_string())) {
+                Some(Value::Text(alg)) => ProfileCipher::from_str(alg).map_err(D::Error::custom)?,
+                Some(_) => return Err(D::Error::custom("Invalid profile key cipher")),
+                None => ProfileCipher::ChaCha20Poly1305,
+            },
+            _ => return Err(D::Error::custom("Invalid profile key")),
+        };
+        match cipher {
+            ProfileCipher::ChaCha20Poly1305 => from_value(value).map(Self::ChaCha20Poly1305),
+            ProfileCipher::Aes256Gcm => from_value(value).map(Self::Aes256Gcm),
+            ProfileCipher::Aes256CbcHmac => from_value(value).map(Self::Aes256CbcHmac),
+        }
+        .map_err(D::Error::custom)
+    }
+}
 
 /// A record combining the keys required to encrypt and decrypt storage entries
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -38,6 +462,16 @@ pub struct ProfileKeyImpl<Key, HmacKey> {
     pub tag_value_key: Key,
     #[serde(rename = "thk")]
     pub tags_hmac_key: HmacKey,
+    #[serde(
+        default,
+        rename = "nenc",
+        skip_serializing_if = "NameEncryption::is_deterministic"
+    )]
+    pub name_encryption: NameEncryption,
+    #[serde(default, rename = "vad", skip_serializing_if = "std::ops::Not::not")]
+    pub bind_values: bool,
+    #[serde(default, rename = "ebkt", skip_serializing_if = "Option::is_none")]
+    pub expiry_bucket: Option<u32>,
 }
 
 impl<Key, HmacKey> ProfileKeyImpl<Key, HmacKey>
@@ -53,26 +487,13 @@ where
             tag_name_key: KeyGen::random()?,
             tag_value_key: KeyGen::random()?,
             tags_hmac_key: KeyGen::random()?,
+            name_encryption: NameEncryption::default(),
+            bind_values: true,
+            expiry_bucket: None,
         })
     }
 }
 
-impl<Key, HmacKey> ProfileKeyImpl<Key, HmacKey>
-where
-    Key: Serialize + for<'de> Deserialize<'de>,
-    HmacKey: Serialize + for<'de> Deserialize<'de>,
-{
-    pub fn to_bytes(&self) -> Result<SecretBytes, Error> {
-        serde_cbor::to_vec(self)
-            .map(SecretBytes::from)
-            .map_err(err_map!(Unexpected, "Error serializing profile key"))
-    }
-
-    pub fn from_slice(input: &[u8]) -> Result<Self, Error> {
-        serde_cbor::from_slice(input).map_err(err_map!(Unsupported, "Invalid profile key"))
-    }
-}
-
 impl<Key, HmacKey> ProfileKeyImpl<Key, HmacKey>
 where
     Key: KeyAeadInPlace + KeyAeadMeta + FromKeyDerivation,
@@ -96,14 +517,15 @@ where
         Ok(buffer.into_vec())
     }
 
-    fn encrypt(mut buffer: SecretBytes, enc_key: &Key) -> Result<Vec<u8>, Error> {
+    fn encrypt(mut buffer: SecretBytes, enc_key: &Key, aad: &[u8]) -> Result<Vec<u8>, Error> {
         let nonce = ArrayKey::<Key::NonceSize>::random();
-        enc_key.encrypt_in_place(&mut buffer, nonce.as_ref(), &[])?;
+        enc_key.encrypt_in_place(&mut buffer, nonce.as_ref(), aad)?;
         buffer.buffer_insert(0, nonce.as_ref())?;
         Ok(buffer.into_vec())
     }
 
-    fn decrypt(ciphertext: Vec<u8>, enc_key: &Key) -> Result<SecretBytes, Error> {
+    /// Decrypt a value in place, reusing the allocation of the ciphertext
+    fn decrypt(ciphertext: Vec<u8>, enc_key: &Key, aad: &[u8]) -> Result<SecretBytes, Error> {
         let nonce_len = Key::NonceSize::USIZE;
         if ciphertext.len() < nonce_len {
             return Err(err_msg!(Encryption, "invalid encrypted value"));
@@ -111,7 +533,20 @@ where
         let mut buffer = SecretBytes::from(ciphertext);
         let nonce = ArrayKey::<Key::NonceSize>::from_slice(&buffer.as_ref()[..nonce_len]);
         buffer.buffer_remove(0..nonce_len)?;
-        enc_key.decrypt_in_place(&mut buffer, nonce.as_ref(), &[])?;
+        enc_key.decrypt_in_place(&mut buffer, nonce.as_ref(), aad)?;
+        Ok(buffer)
+    }
+
+    /// Decrypt a borrowed value directly into a new buffer, avoiding an
+    /// intermediate copy of the ciphertext
+    fn decrypt_slice(ciphertext: &[u8], enc_key: &Key, aad: &[u8]) -> Result<SecretBytes, Error> {
+        let nonce_len = Key::NonceSize::USIZE;
+        if ciphertext.len() < nonce_len {
+            return Err(err_msg!(Encryption, "invalid encrypted value"));
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(nonce_len);
+        let mut buffer = SecretBytes::from_slice(ciphertext);
+        enc_key.decrypt_in_place(&mut buffer, nonce, aad)?;
         Ok(buffer)
     }
 
@@ -127,6 +562,76 @@ where
         ))?)
     }
 
+    /// Encode the associated data binding an entry value to its record
+    ///
+    /// Values encrypted under keys which predate the binding use no
+    /// associated data.
+    fn value_aad(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+    ) -> Vec<u8> {
+        if !self.bind_values {
+            return Vec::new();
+        }
+        let mut aad = Vec::with_capacity(17 + category.len() + name.len());
+        aad.extend_from_slice(&profile_id.to_be_bytes());
+        aad.push(kind as u8);
+        aad.extend_from_slice(&(category.len() as u32).to_be_bytes());
+        aad.extend_from_slice(category);
+        aad.extend_from_slice(&(name.len() as u32).to_be_bytes());
+        aad.extend_from_slice(name);
+        aad
+    }
+
+    /// Compute the tag binding an unencrypted value to its entry
+    fn plain_value_tag(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        value: &[u8],
+    ) -> Result<ArrayKey<U32>, Error> {
+        Ok(ArrayKey::<U32>::from_key_derivation(
+            self.item_hmac_key.hmac_deriver(&[
+                PLAIN_VALUE_DOMAIN,
+                &profile_id.to_be_bytes(),
+                &[kind as u8],
+                &(category.len() as u32).to_be_bytes(),
+                category,
+                &(name.len() as u32).to_be_bytes(),
+                name,
+                value,
+            ]),
+        )?)
+    }
+
+    /// Verify and extract an unencrypted entry value, returning `None` if the
+    /// stored value is not in the unencrypted format or fails verification
+    fn open_plain_value(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        stored: &[u8],
+    ) -> Result<Option<SecretBytes>, Error> {
+        if !is_plain_entry_value(stored) {
+            return Ok(None);
+        }
+        let (value, tag) = stored[PLAIN_VALUE_PREFIX.len()..]
+            .split_at(stored.len() - PLAIN_VALUE_PREFIX.len() - PLAIN_VALUE_TAG_LEN);
+        let expected = self.plain_value_tag(profile_id, kind, category, name, value)?;
+        if expected == ArrayKey::from_slice(tag) {
+            Ok(Some(SecretBytes::from_slice(value)))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn encrypt_tag_name(&self, name: SecretBytes) -> Result<Vec<u8>, Error> {
         Self::encrypt_searchable(name, &self.tag_name_key, &self.tags_hmac_key)
     }
@@ -136,11 +641,127 @@ where
     }
 
     pub fn decrypt_tag_name(&self, enc_tag_name: Vec<u8>) -> Result<SecretBytes, Error> {
-        Self::decrypt(enc_tag_name, &self.tag_name_key)
+        Self::decrypt(enc_tag_name, &self.tag_name_key, &[])
     }
 
     pub fn decrypt_tag_value(&self, enc_tag_value: Vec<u8>) -> Result<SecretBytes, Error> {
-        Self::decrypt(enc_tag_value, &self.tag_value_key)
+        Self::decrypt(enc_tag_value, &self.tag_value_key, &[])
+    }
+
+    pub fn category_namespace(&self, category: &str) -> Result<Vec<u8>, Error> {
+        let mut index = Vec::with_capacity(
+            NAMESPACE_SEGMENT_LEN * (category.matches(CATEGORY_SEPARATOR).count() + 1),
+        );
+        let ends = category
+            .match_indices(CATEGORY_SEPARATOR)
+            .map(|(pos, _)| pos)
+            .chain(Some(category.len()));
+        for end in ends {
+            let segment = ArrayKey::<U16>::from_key_derivation(
+                self.item_hmac_key
+                    .hmac_deriver(&[NAMESPACE_DOMAIN, &category.as_bytes()[..end]]),
+            )?;
+            index.extend_from_slice(segment.as_ref());
+        }
+        Ok(index)
+    }
+
+    /// Compute the fixed-length blind index of a category or name
+    fn blind_index(&self, domain: &[u8], input: SecretBytes) -> Result<Vec<u8>, Error> {
+        let index = ArrayKey::<U32>::from_key_derivation(
+            self.item_hmac_key.hmac_deriver(&[domain, input.as_ref()]),
+        )?;
+        Ok(index.as_ref().to_vec())
+    }
+
+    /// Encode the associated data binding the randomized encryption of the
+    /// category and name of an entry to its profile, kind and category index
+    fn names_aad(profile_id: ProfileId, kind: EntryKind, enc_category: &[u8]) -> Vec<u8> {
+        let mut aad = Vec::with_capacity(13 + enc_category.len());
+        aad.extend_from_slice(&profile_id.to_be_bytes());
+        aad.push(kind as u8);
+        aad.extend_from_slice(&(enc_category.len() as u32).to_be_bytes());
+        aad.extend_from_slice(enc_category);
+        aad
+    }
+
+    pub fn encrypt_entry_names(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        if self.name_encryption.is_deterministic() {
+            return Ok(None);
+        }
+        let enc_category =
+            self.blind_index(CATEGORY_INDEX_DOMAIN, SecretBytes::from_slice(category))?;
+        let aad = Self::names_aad(profile_id, kind, &enc_category);
+        let mut buffer =
+            SecretBytes::with_capacity(Self::encrypted_size(4 + category.len() + name.len()));
+        buffer.buffer_write(&(category.len() as u32).to_be_bytes())?;
+        buffer.buffer_write(category)?;
+        buffer.buffer_write(name)?;
+        Ok(Some(Self::encrypt(buffer, &self.name_key, &aad)?))
+    }
+
+    pub fn decrypt_entry_names(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        enc_category: &[u8],
+        enc_names: Vec<u8>,
+    ) -> Result<(String, String), Error> {
+        let aad = Self::names_aad(profile_id, kind, enc_category);
+        let mut names = Self::decrypt(enc_names, &self.name_key, &aad)?.into_vec();
+        if names.len() < 4 {
+            return Err(err_msg!(Encryption, "Invalid encrypted entry names"));
+        }
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&names[..4]);
+        let category_len = u32::from_be_bytes(len) as usize;
+        if names.len() - 4 < category_len {
+            return Err(err_msg!(Encryption, "Invalid encrypted entry names"));
+        }
+        let name = names.split_off(4 + category_len);
+        names.drain(..4);
+        Ok((decode_utf8(names)?, decode_utf8(name)?))
+    }
+
+    pub fn encrypt_entry_expiry(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        expiry_ms: i64,
+    ) -> Result<Vec<u8>, Error> {
+        let value_key = self.derive_value_key(category, name)?;
+        let mut aad = EXPIRY_DOMAIN.to_vec();
+        aad.extend(self.value_aad(profile_id, kind, category, name));
+        let mut buffer = SecretBytes::with_capacity(Self::encrypted_size(8));
+        buffer.buffer_write(&expiry_ms.to_be_bytes())?;
+        Self::encrypt(buffer, &value_key, &aad)
+    }
+
+    pub fn decrypt_entry_expiry(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        enc_expiry: &[u8],
+    ) -> Result<i64, Error> {
+        let value_key = self.derive_value_key(category, name)?;
+        let mut aad = EXPIRY_DOMAIN.to_vec();
+        aad.extend(self.value_aad(profile_id, kind, category, name));
+        let expiry = Self::decrypt_slice(enc_expiry, &value_key, &aad)?;
+        let expiry: [u8; 8] = expiry
+            .as_ref()
+            .try_into()
+            .map_err(|_| err_msg!(Encryption, "Invalid encrypted expiry"))?;
+        Ok(i64::from_be_bytes(expiry))
     }
 }
 
@@ -152,6 +773,9 @@ impl<Key: PartialEq, HmacKey: PartialEq> PartialEq for ProfileKeyImpl<Key, HmacK
             && self.tag_name_key == other.tag_name_key
             && self.tag_value_key == other.tag_value_key
             && self.tags_hmac_key == other.tags_hmac_key
+            && self.name_encryption == other.name_encryption
+            && self.bind_values == other.bind_values
+            && self.expiry_bucket == other.expiry_bucket
     }
 }
 impl<Key: PartialEq, HmacKey: PartialEq> Eq for ProfileKeyImpl<Key, HmacKey> {}
@@ -168,77 +792,214 @@ where
     }
 
     fn encrypt_entry_category(&self, category: SecretBytes) -> Result<Vec<u8>, Error> {
-        Self::encrypt_searchable(category, &self.category_key, &self.item_hmac_key)
+        match self.name_encryption {
+            NameEncryption::Deterministic => {
+                Self::encrypt_searchable(category, &self.category_key, &self.item_hmac_key)
+            }
+            NameEncryption::Randomized => self.blind_index(CATEGORY_INDEX_DOMAIN, category),
+        }
     }
 
     fn encrypt_entry_name(&self, name: SecretBytes) -> Result<Vec<u8>, Error> {
-        Self::encrypt_searchable(name, &self.name_key, &self.item_hmac_key)
+        match self.name_encryption {
+            NameEncryption::Deterministic => {
+                Self::encrypt_searchable(name, &self.name_key, &self.item_hmac_key)
+            }
+            NameEncryption::Randomized => self.blind_index(NAME_INDEX_DOMAIN, name),
+        }
     }
 
     fn encrypt_entry_value(
         &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
         category: &[u8],
         name: &[u8],
         value: SecretBytes,
     ) -> Result<Vec<u8>, Error> {
         let value_key = self.derive_value_key(category, name)?;
-        Self::encrypt(value, &value_key)
+        let aad = self.value_aad(profile_id, kind, category, name);
+        Self::encrypt(value, &value_key, &aad)
+    }
+
+    fn encode_entry_value_plain(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        value: SecretBytes,
+    ) -> Result<Vec<u8>, Error> {
+        let tag = self.plain_value_tag(profile_id, kind, category, name, value.as_ref())?;
+        let mut stored =
+            Vec::with_capacity(PLAIN_VALUE_PREFIX.len() + value.len() + PLAIN_VALUE_TAG_LEN);
+        stored.extend_from_slice(PLAIN_VALUE_PREFIX);
+        stored.extend_from_slice(value.as_ref());
+        stored.extend_from_slice(tag.as_ref());
+        Ok(stored)
     }
 
     fn decrypt_entry_category(&self, enc_category: Vec<u8>) -> Result<String, Error> {
-        decode_utf8(Self::decrypt(enc_category, &self.category_key)?.into_vec())
+        if !self.name_encryption.is_deterministic() {
+            return Err(err_msg!(
+                Encryption,
+                "Entry category is stored as a blind index"
+            ));
+        }
+        decode_utf8(Self::decrypt(enc_category, &self.category_key, &[])?.into_vec())
     }
 
     fn decrypt_entry_name(&self, enc_name: Vec<u8>) -> Result<String, Error> {
-        decode_utf8(Self::decrypt(enc_name, &self.name_key)?.into_vec())
+        if !self.name_encryption.is_deterministic() {
+            return Err(err_msg!(
+                Encryption,
+                "Entry name is stored as a blind index"
+            ));
+        }
+        decode_utf8(Self::decrypt(enc_name, &self.name_key, &[])?.into_vec())
     }
 
     fn decrypt_entry_value(
         &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
         category: &[u8],
         name: &[u8],
         enc_value: Vec<u8>,
     ) -> Result<SecretBytes, Error> {
+        if let Some(value) = self.open_plain_value(profile_id, kind, category, name, &enc_value)? {
+            return Ok(value);
+        }
         let value_key = self.derive_value_key(category, name)?;
-        Self::decrypt(enc_value, &value_key)
+        let aad = self.value_aad(profile_id, kind, category, name);
+        Self::decrypt(enc_value, &value_key, &aad)
+    }
+
+    fn decrypt_entry_value_slice(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        enc_value: &[u8],
+    ) -> Result<SecretBytes, Error> {
+        if let Some(value) = self.open_plain_value(profile_id, kind, category, name, enc_value)? {
+            return Ok(value);
+        }
+        let value_key = self.derive_value_key(category, name)?;
+        let aad = self.value_aad(profile_id, kind, category, name);
+        Self::decrypt_slice(enc_value, &value_key, &aad)
     }
 
     fn encrypt_entry_tags(&self, tags: Vec<EntryTag>) -> Result<Vec<EncEntryTag>, Error> {
-        tags.into_iter()
-            .map(|tag| match tag {
-                EntryTag::Plaintext(name, value) => {
-                    let name = self.encrypt_tag_name(name.into())?;
-                    Ok(EncEntryTag {
-                        name,
-                        value: value.into_bytes(),
-                        plaintext: true,
-                    })
-                }
-                EntryTag::Encrypted(name, value) => {
-                    let name = self.encrypt_tag_name(name.into())?;
-                    let value = self.encrypt_tag_value(value.into())?;
-                    Ok(EncEntryTag {
-                        name,
-                        value,
-                        plaintext: false,
-                    })
-                }
-            })
-            .collect()
+        // input buffers are sized for the encrypted output to avoid reallocation
+        let mut enc_tags = Vec::with_capacity(tags.len());
+        for tag in tags {
+            enc_tags.push(match tag {
+                EntryTag::Plaintext(name, value) => EncEntryTag {
+                    name: self.encrypt_tag_name(Self::prepare_input(name.as_bytes()))?,
+                    value: value.into_bytes(),
+                    plaintext: true,
+                },
+                EntryTag::Encrypted(name, value) => EncEntryTag {
+                    name: self.encrypt_tag_name(Self::prepare_input(name.as_bytes()))?,
+                    value: self.encrypt_tag_value(Self::prepare_input(value.as_bytes()))?,
+                    plaintext: false,
+                },
+            });
+        }
+        Ok(enc_tags)
     }
 
     fn decrypt_entry_tags(&self, enc_tags: Vec<EncEntryTag>) -> Result<Vec<EntryTag>, Error> {
-        enc_tags.into_iter().try_fold(vec![], |mut acc, tag| {
+        let mut tags = Vec::with_capacity(enc_tags.len());
+        for tag in enc_tags {
             let name = decode_utf8(self.decrypt_tag_name(tag.name)?.into_vec())?;
-            acc.push(if tag.plaintext {
+            tags.push(if tag.plaintext {
                 let value = decode_utf8(tag.value)?;
                 EntryTag::Plaintext(name, value)
             } else {
                 let value = decode_utf8(self.decrypt_tag_value(tag.value)?.into_vec())?;
                 EntryTag::Encrypted(name, value)
             });
-            Result::Ok(acc)
-        })
+        }
+        Ok(tags)
+    }
+}
+
+impl EntryEncryptor for ProfileKey {
+    fn prepare_input(input: &[u8]) -> SecretBytes {
+        let mut buf = SecretBytes::with_capacity(input.len() + MAX_ENCRYPTED_OVERHEAD);
+        buf.buffer_write(input).unwrap();
+        buf
+    }
+
+    fn encrypt_entry_category(&self, category: SecretBytes) -> Result<Vec<u8>, Error> {
+        with_profile_key!(self, key => key.encrypt_entry_category(category))
+    }
+
+    fn encrypt_entry_name(&self, name: SecretBytes) -> Result<Vec<u8>, Error> {
+        with_profile_key!(self, key => key.encrypt_entry_name(name))
+    }
+
+    fn encrypt_entry_value(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        value: SecretBytes,
+    ) -> Result<Vec<u8>, Error> {
+        with_profile_key!(self, key => key.encrypt_entry_value(profile_id, kind, category, name, value))
+    }
+
+    fn encode_entry_value_plain(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        value: SecretBytes,
+    ) -> Result<Vec<u8>, Error> {
+        with_profile_key!(self, key => key.encode_entry_value_plain(profile_id, kind, category, name, value))
+    }
+
+    fn encrypt_entry_tags(&self, tags: Vec<EntryTag>) -> Result<Vec<EncEntryTag>, Error> {
+        with_profile_key!(self, key => key.encrypt_entry_tags(tags))
+    }
+
+    fn decrypt_entry_category(&self, enc_category: Vec<u8>) -> Result<String, Error> {
+        with_profile_key!(self, key => key.decrypt_entry_category(enc_category))
+    }
+
+    fn decrypt_entry_name(&self, enc_name: Vec<u8>) -> Result<String, Error> {
+        with_profile_key!(self, key => key.decrypt_entry_name(enc_name))
+    }
+
+    fn decrypt_entry_value(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        enc_value: Vec<u8>,
+    ) -> Result<SecretBytes, Error> {
+        with_profile_key!(self, key => key.decrypt_entry_value(profile_id, kind, category, name, enc_value))
+    }
+
+    fn decrypt_entry_value_slice(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        category: &[u8],
+        name: &[u8],
+        enc_value: &[u8],
+    ) -> Result<SecretBytes, Error> {
+        with_profile_key!(self, key => key.decrypt_entry_value_slice(profile_id, kind, category, name, enc_value))
+    }
+
+    fn decrypt_entry_tags(&self, enc_tags: Vec<EncEntryTag>) -> Result<Vec<EntryTag>, Error> {
+        with_profile_key!(self, key => key.decrypt_entry_tags(enc_tags))
     }
 }
 
@@ -254,7 +1015,7 @@ mod tests {
 
     #[test]
     fn encrypt_entry_round_trip() {
-        let key = ProfileKey::new().unwrap();
+        let key = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
         let test_record = Entry::new(
             EntryKind::Item,
             "category",
@@ -273,6 +1034,8 @@ mod tests {
             .unwrap();
         let enc_value = key
             .encrypt_entry_value(
+                1,
+                EntryKind::Item,
                 test_record.category.as_bytes(),
                 test_record.name.as_bytes(),
                 test_record.value.clone(),
@@ -288,6 +1051,8 @@ mod tests {
             key.decrypt_entry_category(enc_category).unwrap(),
             key.decrypt_entry_name(enc_name).unwrap(),
             key.decrypt_entry_value(
+                1,
+                EntryKind::Item,
                 test_record.category.as_bytes(),
                 test_record.name.as_bytes(),
                 enc_value,
@@ -298,25 +1063,341 @@ mod tests {
         assert_eq!(test_record, cmp_record);
     }
 
+    #[test]
+    fn category_namespace_prefix() {
+        let key = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
+        let ns = key.category_namespace("credential/w3c").unwrap();
+        let cat = key
+            .category_namespace("credential/w3c/driverlicense")
+            .unwrap();
+        assert_eq!(ns.len(), NAMESPACE_SEGMENT_LEN * 2);
+        assert_eq!(cat.len(), NAMESPACE_SEGMENT_LEN * 3);
+        assert_eq!(&cat[..ns.len()], &ns[..]);
+
+        let other = key.category_namespace("credential/w3cx").unwrap();
+        assert_eq!(
+            &other[..NAMESPACE_SEGMENT_LEN],
+            &ns[..NAMESPACE_SEGMENT_LEN]
+        );
+        assert_ne!(&other[..], &ns[..]);
+
+        let other_key = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
+        assert_ne!(other_key.category_namespace("credential/w3c").unwrap(), ns);
+    }
+
     #[test]
     fn check_encrypt_searchable() {
         let input = SecretBytes::from(&b"hello"[..]);
         let key = Chacha20Key::<C20P>::random().unwrap();
         let hmac_key = HmacKey::random().unwrap();
-        let enc1 = ProfileKey::encrypt_searchable(input.clone(), &key, &hmac_key).unwrap();
-        let enc2 = ProfileKey::encrypt_searchable(input.clone(), &key, &hmac_key).unwrap();
-        let enc3 = ProfileKey::encrypt(input.clone(), &key).unwrap();
+        let enc1 = ChaChaProfileKey::encrypt_searchable(input.clone(), &key, &hmac_key).unwrap();
+        let enc2 = ChaChaProfileKey::encrypt_searchable(input.clone(), &key, &hmac_key).unwrap();
+        let enc3 = ChaChaProfileKey::encrypt(input.clone(), &key, &[]).unwrap();
         assert_eq!(&enc1, &enc2);
         assert_ne!(&enc1, &enc3);
-        let dec = ProfileKey::decrypt(enc1, &key).unwrap();
+        let dec = ChaChaProfileKey::decrypt(enc1, &key, &[]).unwrap();
         assert_eq!(dec, input);
     }
 
     #[test]
     fn serialize_round_trip() {
-        let key = ProfileKey::new().unwrap();
+        let key = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
         let key_cbor = serde_cbor::to_vec(&key).unwrap();
         let key_cmp = serde_cbor::from_slice(&key_cbor).unwrap();
         assert_eq!(key, key_cmp);
     }
+
+    #[test]
+    fn cipher_round_trip() {
+        for cipher in [
+            ProfileCipher::ChaCha20Poly1305,
+            ProfileCipher::Aes256Gcm,
+            ProfileCipher::Aes256CbcHmac,
+        ] {
+            let key = ProfileKey::new_with_cipher(cipher).unwrap();
+            let key_cmp = ProfileKey::from_slice(key.to_bytes().unwrap().as_ref()).unwrap();
+            assert_eq!(key_cmp.cipher(), cipher);
+            assert_eq!(key, key_cmp);
+
+            let enc_value = key
+                .encrypt_entry_value(
+                    1,
+                    EntryKind::Item,
+                    b"category",
+                    b"name",
+                    ProfileKey::prepare_input(b"value"),
+                )
+                .unwrap();
+            assert_eq!(
+                key_cmp
+                    .decrypt_entry_value_slice(1, EntryKind::Item, b"category", b"name", &enc_value)
+                    .unwrap(),
+                &b"value"[..]
+            );
+            assert!(key_cmp
+                .decrypt_entry_value_slice(
+                    1,
+                    EntryKind::Item,
+                    b"category",
+                    b"name",
+                    &enc_value[..4]
+                )
+                .is_err());
+            assert_eq!(
+                key_cmp
+                    .decrypt_entry_value(1, EntryKind::Item, b"category", b"name", enc_value)
+                    .unwrap(),
+                &b"value"[..]
+            );
+            let tags = vec![EntryTag::Encrypted("name".to_string(), "value".to_string())];
+            let enc_tags = key.encrypt_entry_tags(tags.clone()).unwrap();
+            assert_eq!(key.encrypt_entry_tags(tags.clone()).unwrap(), enc_tags);
+            assert_eq!(key_cmp.decrypt_entry_tags(enc_tags).unwrap(), tags);
+        }
+    }
+
+    #[test]
+    fn bound_values() {
+        let key = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
+        assert!(with_profile_key!(&key, k => k.bind_values));
+        let enc_value = key
+            .encrypt_entry_value(
+                1,
+                EntryKind::Item,
+                b"category",
+                b"name",
+                ProfileKey::prepare_input(b"value"),
+            )
+            .unwrap();
+        assert_eq!(
+            key.decrypt_entry_value_slice(1, EntryKind::Item, b"category", b"name", &enc_value)
+                .unwrap(),
+            &b"value"[..]
+        );
+        assert!(key
+            .decrypt_entry_value_slice(2, EntryKind::Item, b"category", b"name", &enc_value)
+            .is_err());
+        assert!(key
+            .decrypt_entry_value_slice(1, EntryKind::Kms, b"category", b"name", &enc_value)
+            .is_err());
+
+        // keys serialized before the binding was introduced use no associated data
+        let mut legacy = key.clone();
+        with_profile_key!(&mut legacy, k => k.bind_values = false);
+        let legacy = ProfileKey::from_slice(legacy.to_bytes().unwrap().as_ref()).unwrap();
+        assert!(!with_profile_key!(&legacy, k => k.bind_values));
+        assert!(legacy
+            .decrypt_entry_value_slice(1, EntryKind::Item, b"category", b"name", &enc_value)
+            .is_err());
+        let enc_value = legacy
+            .encrypt_entry_value(
+                1,
+                EntryKind::Item,
+                b"category",
+                b"name",
+                ProfileKey::prepare_input(b"value"),
+            )
+            .unwrap();
+        assert_eq!(
+            legacy
+                .decrypt_entry_value_slice(2, EntryKind::Kms, b"category", b"name", &enc_value)
+                .unwrap(),
+            &b"value"[..]
+        );
+    }
+
+    #[test]
+    fn plain_values() {
+        let key = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
+        let stored = key
+            .encode_entry_value_plain(
+                1,
+                EntryKind::Item,
+                b"category",
+                b"name",
+                ProfileKey::prepare_input(b"value"),
+            )
+            .unwrap();
+        assert!(is_plain_entry_value(&stored));
+        assert_eq!(&stored[PLAIN_VALUE_PREFIX.len()..][..5], b"value");
+        assert_eq!(
+            key.decrypt_entry_value(1, EntryKind::Item, b"category", b"name", stored.clone())
+                .unwrap(),
+            &b"value"[..]
+        );
+        assert!(key
+            .decrypt_entry_value_slice(1, EntryKind::Item, b"category", b"other", &stored)
+            .is_err());
+        assert!(key
+            .decrypt_entry_value_slice(2, EntryKind::Item, b"category", b"name", &stored)
+            .is_err());
+        let mut tampered = stored.clone();
+        tampered[PLAIN_VALUE_PREFIX.len()] ^= 1;
+        assert!(key
+            .decrypt_entry_value_slice(1, EntryKind::Item, b"category", b"name", &tampered)
+            .is_err());
+        let other = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
+        assert!(other
+            .decrypt_entry_value_slice(1, EntryKind::Item, b"category", b"name", &stored)
+            .is_err());
+
+        let enc_value = key
+            .encrypt_entry_value(
+                1,
+                EntryKind::Item,
+                b"category",
+                b"name",
+                ProfileKey::prepare_input(b"value"),
+            )
+            .unwrap();
+        assert!(!is_plain_entry_value(&enc_value));
+    }
+
+    #[test]
+    fn randomized_names_round_trip() {
+        let key = ProfileKey::new_with_options(
+            ProfileCipher::default(),
+            NameEncryption::Randomized,
+            None,
+        )
+        .unwrap();
+        let key_cmp = ProfileKey::from_slice(key.to_bytes().unwrap().as_ref()).unwrap();
+        assert_eq!(key_cmp.name_encryption(), NameEncryption::Randomized);
+        assert_eq!(key, key_cmp);
+
+        let enc_category = key
+            .encrypt_entry_category(ProfileKey::prepare_input(b"category"))
+            .unwrap();
+        let enc_name = key
+            .encrypt_entry_name(ProfileKey::prepare_input(b"name"))
+            .unwrap();
+        assert_eq!(enc_category.len(), 32);
+        assert_eq!(
+            key.encrypt_entry_category(ProfileKey::prepare_input(b"category"))
+                .unwrap(),
+            enc_category
+        );
+        assert_ne!(
+            key.encrypt_entry_name(ProfileKey::prepare_input(b"category"))
+                .unwrap(),
+            enc_category
+        );
+        assert!(key.decrypt_entry_category(enc_category.clone()).is_err());
+
+        let enc_names = key
+            .encrypt_entry_names(1, EntryKind::Item, b"category", b"name")
+            .unwrap();
+        assert!(enc_names.is_some());
+        assert_ne!(
+            key.encrypt_entry_names(1, EntryKind::Item, b"category", b"name")
+                .unwrap(),
+            enc_names
+        );
+        assert_eq!(
+            key_cmp
+                .decrypt_entry_names(
+                    1,
+                    EntryKind::Item,
+                    enc_category.clone(),
+                    enc_name.clone(),
+                    enc_names.clone()
+                )
+                .unwrap(),
+            ("category".to_string(), "name".to_string())
+        );
+        // the names are bound to the profile, kind and category index
+        assert!(key
+            .decrypt_entry_names(
+                2,
+                EntryKind::Item,
+                enc_category.clone(),
+                enc_name.clone(),
+                enc_names.clone()
+            )
+            .is_err());
+        assert!(key
+            .decrypt_entry_names(
+                1,
+                EntryKind::Kms,
+                enc_category.clone(),
+                enc_name.clone(),
+                enc_names.clone()
+            )
+            .is_err());
+        let other_category = key
+            .encrypt_entry_category(ProfileKey::prepare_input(b"other"))
+            .unwrap();
+        assert!(key
+            .decrypt_entry_names(1, EntryKind::Item, other_category, enc_name, enc_names)
+            .is_err());
+
+        let det_key = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
+        assert_eq!(det_key.name_encryption(), NameEncryption::Deterministic);
+        assert!(det_key
+            .encrypt_entry_names(1, EntryKind::Item, b"category", b"name")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn encrypted_expiry_round_trip() {
+        let key = ProfileKey::new_with_options(
+            ProfileCipher::default(),
+            NameEncryption::default(),
+            Some(60),
+        )
+        .unwrap();
+        let key_cmp = ProfileKey::from_slice(key.to_bytes().unwrap().as_ref()).unwrap();
+        assert_eq!(key_cmp.expiry_bucket(), Some(60));
+        assert_eq!(key, key_cmp);
+
+        let enc_expiry = key
+            .encrypt_entry_expiry(1, EntryKind::Item, b"category", b"name", 1_234_567)
+            .unwrap();
+        assert_eq!(
+            key_cmp
+                .decrypt_entry_expiry(1, EntryKind::Item, b"category", b"name", &enc_expiry)
+                .unwrap(),
+            1_234_567
+        );
+        // the expiry time is bound to its entry and separated from entry values
+        assert!(key
+            .decrypt_entry_expiry(1, EntryKind::Item, b"category", b"other", &enc_expiry)
+            .is_err());
+        assert!(key
+            .decrypt_entry_expiry(2, EntryKind::Item, b"category", b"name", &enc_expiry)
+            .is_err());
+        assert!(key
+            .decrypt_entry_value_slice(1, EntryKind::Item, b"category", b"name", &enc_expiry)
+            .is_err());
+
+        let det_key = ProfileKey::new_with_cipher(ProfileCipher::default()).unwrap();
+        assert_eq!(det_key.expiry_bucket(), None);
+    }
+
+    #[test]
+    fn rotate_tags_hmac_key() {
+        let key = ProfileKey::new_with_cipher(ProfileCipher::Aes256Gcm).unwrap();
+        let rotated = key.with_new_tags_hmac_key().unwrap();
+        assert_eq!(rotated.cipher(), ProfileCipher::Aes256Gcm);
+        assert_ne!(key, rotated);
+
+        let enc_name = key
+            .encrypt_entry_name(ProfileKey::prepare_input(b"name"))
+            .unwrap();
+        assert_eq!(
+            rotated
+                .encrypt_entry_name(ProfileKey::prepare_input(b"name"))
+                .unwrap(),
+            enc_name
+        );
+        let tags = vec![
+            EntryTag::Encrypted("name".to_string(), "value".to_string()),
+            EntryTag::Plaintext("plain".to_string(), "text".to_string()),
+        ];
+        let enc_tags = key.encrypt_entry_tags(tags.clone()).unwrap();
+        assert_eq!(enc_tags.len(), 2);
+        assert_ne!(rotated.encrypt_entry_tags(tags.clone()).unwrap(), enc_tags);
+        assert_eq!(rotated.decrypt_entry_tags(enc_tags).unwrap(), tags);
+    }
 }
@@ -1,11 +1,23 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use once_cell::sync::Lazy;
+
+use sha2::Sha256;
+
+use super::hmac_key::{HmacDerive, HmacKey};
 use super::kdf::KdfMethod;
 
-use super::pass_key::PassKey;
+use super::pass_key::{validate_pass_key, PassKey};
 use crate::{
     crypto::{
         alg::chacha20::{Chacha20Key, C20P},
         buffer::{ArrayKey, ResizeBuffer, SecretBytes},
         encrypt::{KeyAeadInPlace, KeyAeadMeta},
+        generic_array::typenum::U32,
+        kdf::FromKeyDerivation,
         random::RandomDet,
         repr::{KeyGen, KeyMeta, KeySecretBytes},
     },
@@ -15,11 +27,110 @@ use crate::{
 pub const PREFIX_KDF: &str = "kdf";
 pub const PREFIX_RAW: &str = "raw";
 pub const PREFIX_NONE: &str = "none";
+pub const PREFIX_SPLIT: &str = "split";
 
 pub type StoreKeyType = Chacha20Key<C20P>;
 
 type StoreKeyNonce = ArrayKey<<StoreKeyType as KeyAeadMeta>::NonceSize>;
 
+static STORE_KEY_PROVIDERS: Lazy<RwLock<BTreeMap<String, Arc<dyn StoreKeyProvider>>>> =
+    Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// A source of store wrapping keys for a custom store key method
+///
+/// Providers are registered under a URI prefix, and are used for any store key
+/// method or stored key reference URI beginning with that prefix. The raw keys
+/// returned by the provider must be 32 bytes in length.
+pub trait StoreKeyProvider: Send + Sync {
+    /// Create a new store key for a method URI, returning the raw key and the
+    /// reference URI to be persisted in the store configuration
+    fn create_key(
+        &self,
+        method: &str,
+        pass_key: PassKey<'_>,
+    ) -> Result<(SecretBytes, String), Error>;
+
+    /// Load an existing store key from its reference URI
+    fn load_key(&self, reference: &str, pass_key: PassKey<'_>) -> Result<SecretBytes, Error>;
+}
+
+/// Register a provider for a custom store key method prefix
+pub fn register_store_key_method(
+    prefix: &str,
+    provider: Arc<dyn StoreKeyProvider>,
+) -> Result<(), Error> {
+    if prefix.is_empty() || prefix.contains(':') {
+        return Err(err_msg!(Input, "Invalid store key method prefix"));
+    }
+    if [PREFIX_KDF, PREFIX_RAW, PREFIX_NONE, PREFIX_SPLIT].contains(&prefix) {
+        return Err(err_msg!(
+            Input,
+            "Cannot replace a built-in store key method"
+        ));
+    }
+    STORE_KEY_PROVIDERS
+        .write()
+        .unwrap()
+        .insert(prefix.to_string(), provider);
+    Ok(())
+}
+
+/// Derive a split-knowledge store key by combining keys derived from each share
+fn derive_split_key(
+    method: &KdfMethod,
+    pass_key: &PassKey<'_>,
+    detail: &str,
+) -> Result<StoreKey, Error> {
+    let (first, second) = pass_key.shares()?;
+    if first == second {
+        return Err(err_msg!(Input, "Split key shares must be distinct"));
+    }
+    let first = method.derive_key(first, detail)?;
+    let second = method.derive_key(second, detail)?;
+    match (first.0, second.0) {
+        (Some(first), Some(second)) => first.with_secret_bytes(|first| {
+            second.with_secret_bytes(|second| {
+                ArrayKey::<<StoreKeyType as KeyMeta>::KeySize>::temp(|key| {
+                    for (k, (a, b)) in key.iter_mut().zip(
+                        first
+                            .unwrap_or_default()
+                            .iter()
+                            .zip(second.unwrap_or_default()),
+                    ) {
+                        *k = a ^ b;
+                    }
+                    Ok(StoreKey::from(StoreKeyType::from_secret_bytes(&*key)?))
+                })
+            })
+        }),
+        _ => Err(err_msg!(Unexpected, "Missing derived key share")),
+    }
+}
+
+fn decode_split_method(uri: &str) -> Result<(KdfMethod, String), Error> {
+    match uri.split_once(':') {
+        Some((PREFIX_SPLIT, kdf)) => KdfMethod::decode(kdf),
+        None if uri == PREFIX_SPLIT => Ok((KdfMethod::Argon2i(Default::default()), String::new())),
+        _ => Err(err_msg!(Unsupported, "Invalid split key method")),
+    }
+}
+
+fn uri_prefix(uri: &str) -> &str {
+    uri.split(':').next().unwrap_or_default()
+}
+
+fn custom_provider(uri: &str) -> Option<Arc<dyn StoreKeyProvider>> {
+    STORE_KEY_PROVIDERS
+        .read()
+        .unwrap()
+        .get(uri_prefix(uri))
+        .cloned()
+}
+
+fn custom_store_key(uri: &str) -> Result<Arc<dyn StoreKeyProvider>, Error> {
+    custom_provider(uri).ok_or_else(|| err_msg!(Unsupported, "Unregistered store key method"))
+}
+
 /// Create a new raw (non-derived) store key
 pub fn generate_raw_store_key(seed: Option<&[u8]>) -> Result<PassKey<'static>, Error> {
     let key = if let Some(seed) = seed {
@@ -75,6 +186,9 @@ impl StoreKey {
     pub fn unwrap_data(&self, ciphertext: Vec<u8>) -> Result<SecretBytes, Error> {
         match &self.0 {
             Some(key) => {
+                if ciphertext.len() < StoreKeyNonce::SIZE {
+                    return Err(err_msg!(Encryption, "Invalid length for wrapped data"));
+                }
                 let nonce = StoreKeyNonce::from_slice(&ciphertext[..StoreKeyNonce::SIZE]);
                 let mut buffer = SecretBytes::from(ciphertext);
                 buffer.buffer_remove(0..StoreKeyNonce::SIZE)?;
@@ -85,6 +199,118 @@ impl StoreKey {
         }
     }
 
+    /// Compute a keyed hash of a message under the store key, in order to detect
+    /// modifications to unencrypted store records
+    ///
+    /// Returns `None` when no store key is in effect.
+    pub fn mac(&self, domain: &[u8], message: &[u8]) -> Result<Option<ArrayKey<U32>>, Error> {
+        match &self.0 {
+            Some(key) => key.with_secret_bytes(|sk| {
+                let hmac_key = HmacKey::<Sha256, U32>::from_slice(sk.unwrap_or_default())?;
+                Ok(Some(ArrayKey::from_key_derivation(
+                    hmac_key.hmac_deriver(&[domain, message]),
+                )?))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Compute the MAC of the protected entries of the store configuration,
+    /// encoded as a hex string
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub(crate) fn config_mac(
+        &self,
+        config: &[(String, Option<String>)],
+    ) -> Result<Option<String>, Error> {
+        Ok(self
+            .protected_config_mac(config)?
+            .map(|mac| hex::encode(mac.as_ref())))
+    }
+
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    fn protected_config_mac(
+        &self,
+        config: &[(String, Option<String>)],
+    ) -> Result<Option<ArrayKey<U32>>, Error> {
+        let mut message = Vec::new();
+        let names: &[&str] = if is_legacy_store_version(config) {
+            &LEGACY_PROTECTED_CONFIG
+        } else {
+            &PROTECTED_CONFIG
+        };
+        for name in names {
+            // distinguish missing entries from null and empty values
+            message.extend_from_slice(&(name.len() as u32).to_be_bytes());
+            message.extend_from_slice(name.as_bytes());
+            match config.iter().find(|(n, _)| n == name) {
+                Some((_, Some(value))) => {
+                    message.push(2);
+                    message.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                    message.extend_from_slice(value.as_bytes());
+                }
+                Some((_, None)) => message.push(1),
+                None => message.push(0),
+            }
+        }
+        self.mac(CONFIG_MAC_DOMAIN, &message)
+    }
+
+    /// Verify the recorded MAC of the protected entries of the store configuration
+    ///
+    /// A missing MAC is only accepted for stores in the legacy format, which
+    /// predates the protection of the configuration, and only when
+    /// `legacy_upgrade` is set. Returns `true` when the store is in the legacy
+    /// format and its configuration should be upgraded. Legacy stores are
+    /// reported with an `Unsupported` error when the upgrade is disabled.
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub(crate) fn verify_config_mac(
+        &self,
+        config: &[(String, Option<String>)],
+        legacy_upgrade: bool,
+    ) -> Result<bool, Error> {
+        let legacy = is_legacy_store_version(config);
+        let found = config
+            .iter()
+            .find(|(name, _)| name == CONFIG_MAC_CONFIG)
+            .and_then(|(_, value)| value.as_deref())
+            .map(|mac| hex::decode(mac).unwrap_or_default());
+        match (self.protected_config_mac(config)?, found) {
+            (Some(mac), Some(found)) => {
+                if found.len() == mac.len() && ArrayKey::from_slice(&found) == mac {
+                    Ok(legacy)
+                } else {
+                    Err(err_msg!(
+                        Encryption,
+                        "Store configuration integrity check failed"
+                    ))
+                }
+            }
+            (Some(_), None) if legacy && legacy_upgrade => Ok(true),
+            (Some(_), None) if legacy => Err(err_msg!(
+                Unsupported,
+                "Store configuration is in the legacy format and upgrade_legacy_config=false is set"
+            )),
+            (Some(_), None) => Err(err_msg!(
+                Encryption,
+                "Store configuration integrity check failed: no MAC is recorded"
+            )),
+            (None, Some(_)) => Err(err_msg!(
+                Encryption,
+                "Store configuration integrity check failed"
+            )),
+            (None, None) => Ok(legacy),
+        }
+    }
+
+    /// Compute a check value identifying the store key, so that a key later
+    /// resolved from a pass key may be compared without decrypting any records
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    pub(crate) fn key_check(&self) -> Result<Option<String>, Error> {
+        Ok(self
+            .mac(KEY_CHECK_DOMAIN, &[])?
+            .map(|check| hex::encode(check.as_ref())))
+    }
+
     pub fn to_passkey(&self) -> PassKey<'static> {
         if let Some(key) = self.0.as_ref() {
             PassKey::from(key.with_secret_bytes(|sk| bs58::encode(sk.unwrap()).into_string()))
@@ -111,6 +337,14 @@ pub enum StoreKeyMethod {
     RawKey,
     /// No wrapping key in effect
     Unprotected,
+    /// Wrap using a key from a registered `StoreKeyProvider`, given the method URI
+    Custom(String),
+    /// Derive a new wrapping key from two independent secrets, requiring both to unlock
+    ///
+    /// The pass key must be constructed with `PassKey::from_shares`. A key is derived
+    /// from each share using the key derivation function, and the results are combined
+    /// by XOR, such that neither share reveals any information about the store key.
+    SplitKey(KdfMethod),
 }
 
 impl StoreKeyMethod {
@@ -126,6 +360,11 @@ impl StoreKeyMethod {
                 Ok(Self::DeriveKey(method))
             }
             PREFIX_NONE => Ok(Self::Unprotected),
+            PREFIX_SPLIT => {
+                let (method, _) = decode_split_method(uri)?;
+                Ok(Self::SplitKey(method))
+            }
+            _ if custom_provider(prefix).is_some() => Ok(Self::Custom(uri.to_string())),
             _ => Err(err_msg!(Unsupported, "Invalid store key method")),
         }
     }
@@ -139,6 +378,7 @@ impl StoreKeyMethod {
             // Self::ExistingManagedKey(String) => unimplemented!(),
             Self::DeriveKey(method) => {
                 if !pass_key.is_none() {
+                    validate_pass_key(&pass_key)?;
                     let (key, detail) = method.derive_new_key(&pass_key)?;
                     let key_ref = StoreKeyReference::DeriveKey(*method, detail);
                     Ok((key, key_ref))
@@ -155,6 +395,27 @@ impl StoreKeyMethod {
                 Ok((key, StoreKeyReference::RawKey))
             }
             Self::Unprotected => Ok((StoreKey::empty(), StoreKeyReference::Unprotected)),
+            Self::Custom(uri) => {
+                let (key, key_ref) = custom_store_key(uri)?.create_key(uri, pass_key)?;
+                if uri_prefix(&key_ref) != uri_prefix(uri) {
+                    return Err(err_msg!(
+                        Unexpected,
+                        "Store key reference does not match the method prefix"
+                    ));
+                }
+                Ok((
+                    StoreKey::from(StoreKeyType::from_secret_bytes(&key)?),
+                    StoreKeyReference::Custom(key_ref),
+                ))
+            }
+            Self::SplitKey(method) => {
+                let (first, second) = pass_key.shares()?;
+                validate_pass_key(&PassKey::from(first))?;
+                validate_pass_key(&PassKey::from(second))?;
+                let detail = method.generate_detail();
+                let key = derive_split_key(method, &pass_key, &detail)?;
+                Ok((key, StoreKeyReference::SplitKey(*method, detail)))
+            }
         }
     }
 }
@@ -171,16 +432,104 @@ impl From<StoreKeyReference> for StoreKeyMethod {
             StoreKeyReference::DeriveKey(method, _) => Self::DeriveKey(method),
             StoreKeyReference::RawKey => Self::RawKey,
             StoreKeyReference::Unprotected => Self::Unprotected,
+            StoreKeyReference::Custom(uri) => Self::Custom(uri),
+            StoreKeyReference::SplitKey(method, _) => Self::SplitKey(method),
         }
     }
 }
 
+/// The identifier of the cipher used to wrap profile keys with the store key
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) const STORE_KEY_CIPHER: &str = "c20p";
+
+/// Get the store key method of a store key reference URI, excluding the key
+/// derivation salt and any other key details
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) fn store_key_method_uri(key_ref: &str) -> String {
+    match StoreKeyReference::parse_uri(key_ref) {
+        Ok(StoreKeyReference::DeriveKey(method, _)) => method.encode(None),
+        Ok(StoreKeyReference::SplitKey(method, _)) => {
+            format!("{}:{}", PREFIX_SPLIT, method.encode(None))
+        }
+        _ => uri_prefix(key_ref).to_string(),
+    }
+}
+
+/// The name of the config table entry holding the store key reference of an
+/// incomplete chunked re-key
+pub(crate) const PENDING_KEY_CONFIG: &str = "key_pending";
+
+/// The name of the config table entry holding the check value of the store key
+/// of an incomplete chunked re-key
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) const PENDING_KEY_CHECK_CONFIG: &str = "key_pending_check";
+
+/// The name of the config table entry holding the generation of the store key,
+/// which is advanced whenever the store key is replaced
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) const KEY_GENERATION_CONFIG: &str = "key_generation";
+
+/// The name of the config table entry holding the MAC of the protected entries
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) const CONFIG_MAC_CONFIG: &str = "config_mac";
+
+/// The store format version recorded by this library, requiring a MAC of
+/// the protected configuration entries
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) const STORE_VERSION: &str = "2";
+
+/// The store format version which predates the protection of the configuration
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub(crate) const LEGACY_STORE_VERSION: &str = "1";
+
+/// The config table entries which determine how a store is unlocked and which
+/// profile is opened, protected against modification by the configuration MAC
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+const PROTECTED_CONFIG: [&str; 9] = [
+    "default_profile",
+    super::EXPIRY_BUCKET_CONFIG,
+    "key",
+    super::NAME_ENCRYPTION_CONFIG,
+    super::PROFILE_CIPHER_CONFIG,
+    "tenant_index_profile",
+    "tenant_schema_prefix",
+    super::UNLOCK_POLICY_CONFIG,
+    "version",
+];
+
+/// The config table entries protected by the MAC of stores in the legacy format
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+const LEGACY_PROTECTED_CONFIG: [&str; 6] = [
+    "default_profile",
+    super::EXPIRY_BUCKET_CONFIG,
+    "key",
+    super::NAME_ENCRYPTION_CONFIG,
+    super::PROFILE_CIPHER_CONFIG,
+    "version",
+];
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+fn is_legacy_store_version(config: &[(String, Option<String>)]) -> bool {
+    config
+        .iter()
+        .any(|(name, value)| name == "version" && value.as_deref() == Some(LEGACY_STORE_VERSION))
+}
+
+/// Distinguishes the configuration MAC from other keyed hashes of the store key
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+const CONFIG_MAC_DOMAIN: &[u8] = b"\xffconfig";
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+const KEY_CHECK_DOMAIN: &[u8] = b"\xffkey_check";
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum StoreKeyReference {
     // ManagedKey(String),
     DeriveKey(KdfMethod, String),
     RawKey,
     Unprotected,
+    Custom(String),
+    SplitKey(KdfMethod, String),
 }
 
 impl StoreKeyReference {
@@ -194,6 +543,11 @@ impl StoreKeyReference {
                 Ok(Self::DeriveKey(method, detail))
             }
             PREFIX_NONE => Ok(Self::Unprotected),
+            PREFIX_SPLIT => {
+                let (method, detail) = decode_split_method(uri)?;
+                Ok(Self::SplitKey(method, detail))
+            }
+            _ if custom_provider(prefix).is_some() => Ok(Self::Custom(uri.to_string())),
             _ => Err(err_msg!(
                 Unsupported,
                 "Invalid store key method for reference"
@@ -209,6 +563,12 @@ impl StoreKeyReference {
             }
             Self::RawKey => *method == StoreKeyMethod::RawKey,
             Self::Unprotected => *method == StoreKeyMethod::Unprotected,
+            Self::Custom(uri) => {
+                matches!(method, StoreKeyMethod::Custom(m) if uri_prefix(m) == uri_prefix(uri))
+            }
+            Self::SplitKey(kdf_method, _detail) => {
+                matches!(method, StoreKeyMethod::SplitKey(m) if m == kdf_method)
+            }
         }
     }
 
@@ -218,6 +578,31 @@ impl StoreKeyReference {
             Self::DeriveKey(method, detail) => method.encode(Some(detail.as_str())),
             Self::RawKey => PREFIX_RAW.to_string(),
             Self::Unprotected => PREFIX_NONE.to_string(),
+            Self::Custom(uri) => uri,
+            Self::SplitKey(method, detail) => {
+                format!("{}:{}", PREFIX_SPLIT, method.encode(Some(detail.as_str())))
+            }
+        }
+    }
+
+    /// Derive a replacement store key from the same pass key when the key
+    /// derivation method is weaker than `target`
+    pub(crate) fn upgrade_kdf(
+        &self,
+        target: KdfMethod,
+        pass_key: PassKey<'_>,
+    ) -> Result<Option<(StoreKey, StoreKeyReference)>, Error> {
+        match self {
+            Self::DeriveKey(method, _) if method.is_weaker_than(&target) => {
+                let (key, detail) = target.derive_new_key(&pass_key)?;
+                Ok(Some((key, Self::DeriveKey(target, detail))))
+            }
+            Self::SplitKey(method, _) if method.is_weaker_than(&target) => {
+                let detail = target.generate_detail();
+                let key = derive_split_key(&target, &pass_key, &detail)?;
+                Ok(Some((key, Self::SplitKey(target, detail))))
+            }
+            _ => Ok(None),
         }
     }
 
@@ -239,6 +624,11 @@ impl StoreKeyReference {
                 }
             }
             Self::Unprotected => Ok(StoreKey::empty()),
+            Self::Custom(uri) => {
+                let key = custom_store_key(uri)?.load_key(uri, pass_key)?;
+                Ok(StoreKey::from(StoreKeyType::from_secret_bytes(&key)?))
+            }
+            Self::SplitKey(method, detail) => derive_split_key(method, &pass_key, detail),
         }
     }
 }
@@ -246,7 +636,7 @@ impl StoreKeyReference {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::error::ErrorKind;
+    use crate::{error::ErrorKind, protect::kdf::Argon2Level};
 
     #[test]
     fn protection_method_parse() {
@@ -315,6 +705,135 @@ mod tests {
         assert!(unwrapped_err.is_err());
     }
 
+    #[test]
+    fn derived_key_upgrade_kdf() {
+        let target = KdfMethod::Argon2i(Argon2Level::Moderate);
+        let key_ref = StoreKeyReference::parse_uri(
+            "kdf:argon2i:13:mod?salt=a553cfb9c558b5c11c78efcfa06f3e29",
+        )
+        .expect("Error parsing derived key ref");
+        assert!(key_ref
+            .upgrade_kdf(target, "pass".into())
+            .expect("Error checking key upgrade")
+            .is_none());
+
+        let key_ref = StoreKeyReference::parse_uri(
+            "kdf:argon2i:13:int?salt=a553cfb9c558b5c11c78efcfa06f3e29",
+        )
+        .expect("Error parsing derived key ref");
+        let (key, upd_ref) = key_ref
+            .upgrade_kdf(target, "pass".into())
+            .expect("Error upgrading key")
+            .expect("Expected key upgrade");
+        let upd_uri = upd_ref.into_uri();
+        assert!(upd_uri.starts_with("kdf:argon2i:13:mod?salt="));
+        let upd_ref = StoreKeyReference::parse_uri(&upd_uri).expect("Error parsing upgraded ref");
+        let wrapped = key
+            .wrap_data((&b"test data"[..]).into())
+            .expect("Error wrapping input");
+        let unwrapped = upd_ref
+            .resolve("pass".into())
+            .expect("Error deriving upgraded key")
+            .unwrap_data(wrapped)
+            .expect("Error unwrapping data");
+        assert_eq!(unwrapped, &b"test data"[..]);
+    }
+
+    #[test]
+    fn custom_key_provider() {
+        struct TestProvider;
+
+        impl StoreKeyProvider for TestProvider {
+            fn create_key(
+                &self,
+                _method: &str,
+                pass_key: PassKey<'_>,
+            ) -> Result<(SecretBytes, String), Error> {
+                Ok((
+                    self.load_key("", pass_key)?,
+                    "test-provider:key-1".to_string(),
+                ))
+            }
+
+            fn load_key(
+                &self,
+                _reference: &str,
+                pass_key: PassKey<'_>,
+            ) -> Result<SecretBytes, Error> {
+                let mut key = [0u8; 32];
+                key[..pass_key.len()].copy_from_slice(pass_key.as_bytes());
+                Ok(SecretBytes::from_slice(&key))
+            }
+        }
+
+        assert!(register_store_key_method("raw", Arc::new(TestProvider)).is_err());
+        register_store_key_method("test-provider", Arc::new(TestProvider))
+            .expect("Error registering provider");
+
+        let input = b"test data";
+        let method = StoreKeyMethod::parse_uri("test-provider:new").expect("Error parsing method");
+        assert_eq!(
+            method,
+            StoreKeyMethod::Custom("test-provider:new".to_string())
+        );
+        let (key, key_ref) = method
+            .resolve(PassKey::from("pass"))
+            .expect("Error creating custom key");
+        let wrapped = key
+            .wrap_data((&input[..]).into())
+            .expect("Error wrapping input");
+        let key_uri = key_ref.into_uri();
+        assert_eq!(key_uri, "test-provider:key-1");
+
+        let key_ref = StoreKeyReference::parse_uri(&key_uri).expect("Error parsing reference");
+        assert!(key_ref.compare_method(&method));
+        let key = key_ref
+            .resolve(PassKey::from("pass"))
+            .expect("Error loading custom key");
+        let unwrapped = key.unwrap_data(wrapped).expect("Error unwrapping data");
+        assert_eq!(unwrapped, &input[..]);
+    }
+
+    #[test]
+    fn split_key_wrap() {
+        let input = b"test data";
+        assert_eq!(
+            StoreKeyMethod::parse_uri("split"),
+            Ok(StoreKeyMethod::SplitKey(KdfMethod::Argon2i(
+                Default::default()
+            )))
+        );
+        let method =
+            StoreKeyMethod::parse_uri("split:kdf:argon2i:int").expect("Error parsing method");
+        assert!(method.resolve(PassKey::from("pass")).is_err());
+        assert!(method
+            .resolve(PassKey::from_shares("pass", "pass"))
+            .is_err());
+        let (key, key_ref) = method
+            .resolve(PassKey::from_shares("first", "second"))
+            .expect("Error deriving new split key");
+        let wrapped = key
+            .wrap_data((&input[..]).into())
+            .expect("Error wrapping input");
+        let key_uri = key_ref.into_uri();
+        assert!(key_uri.starts_with("split:kdf:argon2i:13:int?salt="));
+
+        let key_ref = StoreKeyReference::parse_uri(&key_uri).expect("Error parsing split key ref");
+        assert!(key_ref.compare_method(&method));
+        let key = key_ref
+            .resolve(PassKey::from_shares("first", "second"))
+            .expect("Error deriving existing split key");
+        let unwrapped = key
+            .unwrap_data(wrapped.clone())
+            .expect("Error unwrapping data");
+        assert_eq!(unwrapped, &input[..]);
+
+        let check_one_share = key_ref
+            .resolve(PassKey::from_shares("first", "other"))
+            .expect("Error deriving comparison key");
+        assert!(check_one_share.unwrap_data(wrapped).is_err());
+    }
+
     #[test]
     fn raw_key_seed_lengths() {
         // 'short' is less than 32 bytes
@@ -381,4 +900,80 @@ mod tests {
             .expect("Error unwrapping unprotected");
         assert_eq!(unwrapped, &input[..]);
     }
+
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    #[test]
+    fn config_mac_verify() {
+        let key = StoreKey::random().unwrap();
+        let mut config = vec![
+            ("default_profile".to_string(), Some("default".to_string())),
+            ("key".to_string(), Some("raw".to_string())),
+            ("version".to_string(), Some(STORE_VERSION.to_string())),
+        ];
+        assert_eq!(
+            key.verify_config_mac(&config, true).unwrap_err().kind(),
+            ErrorKind::Encryption
+        );
+        let mac = key.config_mac(&config).unwrap().unwrap();
+        config.push((CONFIG_MAC_CONFIG.to_string(), Some(mac)));
+        assert!(!key.verify_config_mac(&config, false).unwrap());
+
+        // unprotected entries may change
+        config.push(("schema_writer".to_string(), Some("other".to_string())));
+        assert!(!key.verify_config_mac(&config, false).unwrap());
+
+        let other_key = StoreKey::random().unwrap();
+        assert_eq!(
+            other_key
+                .verify_config_mac(&config, false)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Encryption
+        );
+        config[0].1 = Some("other".to_string());
+        assert_eq!(
+            key.verify_config_mac(&config, false).unwrap_err().kind(),
+            ErrorKind::Encryption
+        );
+        config[0].1 = None;
+        assert!(key.verify_config_mac(&config, false).is_err());
+        config.push((
+            "tenant_schema_prefix".to_string(),
+            Some("other".to_string()),
+        ));
+        assert!(key.verify_config_mac(&config, false).is_err());
+
+        // no MAC is recorded for unprotected stores
+        assert_eq!(StoreKey::empty().config_mac(&config).unwrap(), None);
+    }
+
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    #[test]
+    fn config_mac_legacy() {
+        let key = StoreKey::random().unwrap();
+        let mut config = vec![
+            ("default_profile".to_string(), Some("default".to_string())),
+            ("key".to_string(), Some("raw".to_string())),
+            (
+                "version".to_string(),
+                Some(LEGACY_STORE_VERSION.to_string()),
+            ),
+        ];
+        // a missing MAC is only accepted for an explicit upgrade
+        assert!(key.verify_config_mac(&config, false).is_err());
+        assert!(key.verify_config_mac(&config, true).unwrap());
+
+        // legacy stores with a MAC are upgraded without tenant entries protected
+        let mac = key.config_mac(&config).unwrap().unwrap();
+        config.push((CONFIG_MAC_CONFIG.to_string(), Some(mac)));
+        config.push((
+            "tenant_schema_prefix".to_string(),
+            Some("other".to_string()),
+        ));
+        assert!(key.verify_config_mac(&config, false).unwrap());
+
+        // the legacy format cannot be substituted for the current version
+        config[2].1 = Some(STORE_VERSION.to_string());
+        assert!(key.verify_config_mac(&config, true).is_err());
+    }
 }
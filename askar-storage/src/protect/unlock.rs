@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::{error::Error, future::system_time};
+
+/// The name of the config table entry tracking failed unlock attempts
+pub(crate) const UNLOCK_FAILURES_CONFIG: &str = "unlock_failures";
+
+/// The name of the config table entry holding the unlock policy of the store
+pub(crate) const UNLOCK_POLICY_CONFIG: &str = "unlock_policy";
+
+const MAX_UNLOCK_DELAY: Duration = Duration::from_secs(30);
+
+/// Limits applied to repeated attempts to open a store with an incorrect pass key
+///
+/// Failed attempts are always recorded in the store configuration. Once
+/// `max_attempts` consecutive failures have been recorded, further attempts are
+/// rejected until `lockout` has elapsed since the most recent failure. In addition,
+/// each failure is answered only after `delay` multiplied by the current failure count.
+///
+/// The policy given when a store is provisioned is recorded in its configuration,
+/// which is protected by the configuration MAC. The policy given when opening the
+/// store may only make this stricter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UnlockPolicy {
+    /// The number of consecutive failures triggering a lockout, or zero to disable
+    pub max_attempts: u32,
+    /// The length of a lockout following the most recent failure
+    pub lockout: Duration,
+    /// The delay applied per recorded failure before reporting an unlock error
+    pub delay: Duration,
+}
+
+impl UnlockPolicy {
+    /// Extract the policy from the store URI query parameters
+    ///
+    /// Supported parameters are `max_unlock_attempts`, `unlock_lockout` (in seconds),
+    /// and `unlock_delay` (in milliseconds).
+    pub(crate) fn from_query(query: &mut HashMap<String, String>) -> Result<Self, Error> {
+        let max_attempts = if let Some(max) = query.remove("max_unlock_attempts") {
+            max.parse().map_err(err_map!(
+                Input,
+                "Error parsing 'max_unlock_attempts' parameter"
+            ))?
+        } else {
+            0
+        };
+        let lockout = if let Some(secs) = query.remove("unlock_lockout") {
+            Duration::from_secs(
+                secs.parse()
+                    .map_err(err_map!(Input, "Error parsing 'unlock_lockout' parameter"))?,
+            )
+        } else {
+            Duration::ZERO
+        };
+        let delay = if let Some(ms) = query.remove("unlock_delay") {
+            Duration::from_millis(
+                ms.parse()
+                    .map_err(err_map!(Input, "Error parsing 'unlock_delay' parameter"))?,
+            )
+        } else {
+            Duration::ZERO
+        };
+        Ok(Self {
+            max_attempts,
+            lockout,
+            delay,
+        })
+    }
+
+    /// Parse a policy recorded in the store configuration
+    pub(crate) fn parse_config(value: &str) -> Result<Self, Error> {
+        let mut parts = value.splitn(3, ':');
+        let mut next = || {
+            parts
+                .next()
+                .and_then(|part| part.parse::<u64>().ok())
+                .ok_or_else(|| err_msg!(Unexpected, "Invalid unlock policy record"))
+        };
+        let max_attempts = u32::try_from(next()?)
+            .map_err(|_| err_msg!(Unexpected, "Invalid unlock policy record"))?;
+        let lockout = Duration::from_secs(next()?);
+        let delay = Duration::from_millis(next()?);
+        Ok(Self {
+            max_attempts,
+            lockout,
+            delay,
+        })
+    }
+
+    /// Encode the policy to be recorded in the store configuration
+    pub(crate) fn to_config(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.max_attempts,
+            self.lockout.as_secs(),
+            self.delay.as_millis()
+        )
+    }
+
+    /// Combine the recorded policy with another, taking the stricter of each limit
+    pub(crate) fn restrict(&self, other: &Self) -> Self {
+        let max_attempts = match (self.max_attempts, other.max_attempts) {
+            (0, max) | (max, 0) => max,
+            (a, b) => a.min(b),
+        };
+        Self {
+            max_attempts,
+            lockout: self.lockout.max(other.lockout),
+            delay: self.delay.max(other.delay),
+        }
+    }
+
+    /// Reject the unlock attempt if the store is currently locked out
+    pub(crate) fn check(&self, failures: &UnlockFailures) -> Result<(), Error> {
+        if self.max_attempts > 0 && failures.count >= self.max_attempts {
+            let unlock_at = failures.last_failure.saturating_add(self.lockout.as_secs());
+            if unix_time() < unlock_at {
+                return Err(err_msg!(
+                    Busy,
+                    "Store is locked following repeated unlock failures"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The delay to apply before reporting a failed unlock attempt
+    pub(crate) fn failure_delay(&self, failures: &UnlockFailures) -> Duration {
+        self.delay
+            .saturating_mul(failures.count)
+            .min(MAX_UNLOCK_DELAY)
+    }
+}
+
+/// The record of consecutive failed unlock attempts
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct UnlockFailures {
+    pub count: u32,
+    pub last_failure: u64,
+}
+
+impl UnlockFailures {
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let (count, last) = value
+            .split_once(':')
+            .ok_or_else(|| err_msg!(Unexpected, "Invalid unlock failure record"))?;
+        Ok(Self {
+            count: count
+                .parse()
+                .map_err(err_map!(Unexpected, "Invalid unlock failure count"))?,
+            last_failure: last
+                .parse()
+                .map_err(err_map!(Unexpected, "Invalid unlock failure timestamp"))?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Record a new failure, returning the updated config value
+    pub fn increment(&mut self) -> String {
+        self.count = self.count.saturating_add(1);
+        self.last_failure = unix_time();
+        format!("{}:{}", self.count, self.last_failure)
+    }
+}
+
+fn unix_time() -> u64 {
+    system_time()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlock_policy_lockout() {
+        let mut query = HashMap::new();
+        query.insert("max_unlock_attempts".to_string(), "2".to_string());
+        query.insert("unlock_lockout".to_string(), "60".to_string());
+        query.insert("unlock_delay".to_string(), "100".to_string());
+        let policy = UnlockPolicy::from_query(&mut query).unwrap();
+        assert!(query.is_empty());
+
+        let mut failures = UnlockFailures::default();
+        assert!(policy.check(&failures).is_ok());
+        failures.increment();
+        assert!(policy.check(&failures).is_ok());
+        let value = failures.increment();
+        assert_eq!(UnlockFailures::parse(&value).unwrap(), failures);
+        assert_eq!(policy.failure_delay(&failures), Duration::from_millis(200));
+        assert!(policy.check(&failures).is_err());
+
+        failures.last_failure -= 61;
+        assert!(policy.check(&failures).is_ok());
+    }
+
+    #[test]
+    fn unlock_policy_config() {
+        let recorded = UnlockPolicy {
+            max_attempts: 5,
+            lockout: Duration::from_secs(60),
+            delay: Duration::from_millis(100),
+        };
+        let value = recorded.to_config();
+        assert_eq!(UnlockPolicy::parse_config(&value).unwrap(), recorded);
+        assert!(UnlockPolicy::parse_config("5:60").is_err());
+
+        // the policy given when opening can only make the recorded policy stricter
+        assert_eq!(recorded.restrict(&UnlockPolicy::default()), recorded);
+        let looser = UnlockPolicy {
+            max_attempts: 10,
+            lockout: Duration::from_secs(1),
+            delay: Duration::ZERO,
+        };
+        assert_eq!(recorded.restrict(&looser), recorded);
+        let stricter = UnlockPolicy {
+            max_attempts: 2,
+            lockout: Duration::from_secs(600),
+            delay: Duration::from_millis(500),
+        };
+        assert_eq!(recorded.restrict(&stricter), stricter);
+        assert_eq!(UnlockPolicy::default().restrict(&stricter), stricter);
+    }
+}
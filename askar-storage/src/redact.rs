@@ -0,0 +1,208 @@
+//! Redaction of record metadata in logs and `Debug` output
+//!
+//! Record values are never included in `Debug` output, but the categories, names
+//! and tag names of records are shown in full by default. A process-wide
+//! [`RedactionPolicy`] installed with [`set_redaction_policy`] determines how each
+//! of these is rendered, so that metadata patterns are not leaked into log
+//! aggregation systems.
+
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    str::FromStr,
+    sync::RwLock,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+static REDACTION_POLICY: RwLock<RedactionPolicy> = RwLock::new(RedactionPolicy::plain());
+
+/// The number of bytes of the SHA-256 digest included in a hashed value
+const HASH_PREFIX_LEN: usize = 8;
+
+/// The rendering applied to a class of record metadata
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Redaction {
+    /// The value is shown in full
+    #[default]
+    Plain,
+    /// The value is replaced by a prefix of its SHA-256 digest, allowing related
+    /// log entries to be correlated. Note that the digest is not keyed, so short or
+    /// predictable values may be recovered by guessing.
+    Hashed,
+    /// Only the given number of leading characters are shown
+    Truncated(usize),
+    /// The value is omitted entirely
+    Omitted,
+}
+
+impl FromStr for Redaction {
+    type Err = Error;
+
+    /// Parse a redaction from `plain`, `hashed`, `omitted` or `truncated:<len>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "hashed" => Ok(Self::Hashed),
+            "omitted" => Ok(Self::Omitted),
+            _ => match s.strip_prefix("truncated:").map(str::parse) {
+                Some(Ok(len)) => Ok(Self::Truncated(len)),
+                _ => Err(err_msg!(Input, "Invalid redaction: {}", s)),
+            },
+        }
+    }
+}
+
+/// The process-wide policy for rendering record metadata
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RedactionPolicy {
+    /// The rendering of record categories
+    pub category: Redaction,
+    /// The rendering of record and key names
+    pub name: Redaction,
+    /// The rendering of tag names
+    pub tag_name: Redaction,
+}
+
+impl RedactionPolicy {
+    /// A policy showing all metadata in full
+    pub const fn plain() -> Self {
+        Self::all(Redaction::Plain)
+    }
+
+    /// A policy applying the same rendering to all metadata
+    pub const fn all(redaction: Redaction) -> Self {
+        Self {
+            category: redaction,
+            name: redaction,
+            tag_name: redaction,
+        }
+    }
+}
+
+/// Install the process-wide redaction policy, replacing the existing policy
+pub fn set_redaction_policy(policy: RedactionPolicy) {
+    *REDACTION_POLICY.write().unwrap() = policy;
+}
+
+/// Get the current process-wide redaction policy
+pub fn redaction_policy() -> RedactionPolicy {
+    *REDACTION_POLICY.read().unwrap()
+}
+
+/// Render a record category according to the current policy
+pub fn redact_category(category: &str) -> Redacted<'_> {
+    Redacted::new(category, redaction_policy().category)
+}
+
+/// Render a record or key name according to the current policy
+pub fn redact_name(name: &str) -> Redacted<'_> {
+    Redacted::new(name, redaction_policy().name)
+}
+
+/// Render a tag name according to the current policy
+pub fn redact_tag_name(tag_name: &str) -> Redacted<'_> {
+    Redacted::new(tag_name, redaction_policy().tag_name)
+}
+
+/// A metadata value formatted according to a redaction policy
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Redacted<'a> {
+    value: &'a str,
+    redaction: Redaction,
+}
+
+impl<'a> Redacted<'a> {
+    /// Create a new instance applying a specific redaction to a value
+    pub fn new(value: &'a str, redaction: Redaction) -> Self {
+        Self { value, redaction }
+    }
+}
+
+impl Display for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.redaction {
+            Redaction::Plain => f.write_str(self.value),
+            Redaction::Hashed => {
+                f.write_str("#")?;
+                for b in &Sha256::digest(self.value.as_bytes())[..HASH_PREFIX_LEN] {
+                    write!(f, "{:02x}", b)?;
+                }
+                Ok(())
+            }
+            Redaction::Truncated(len) => match self.value.char_indices().nth(len) {
+                Some((end, _)) => write!(f, "{}...", &self.value[..end]),
+                None => f.write_str(self.value),
+            },
+            Redaction::Omitted => f.write_str("<redacted>"),
+        }
+    }
+}
+
+impl Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.redaction {
+            Redaction::Plain => Debug::fmt(self.value, f),
+            Redaction::Truncated(_) => Debug::fmt(&self.to_string(), f),
+            Redaction::Hashed | Redaction::Omitted => Display::fmt(self, f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_format() {
+        let value = "did:example:123";
+        assert_eq!(Redacted::new(value, Redaction::Plain).to_string(), value);
+        assert_eq!(
+            format!("{:?}", Redacted::new(value, Redaction::Plain)),
+            "\"did:example:123\""
+        );
+        let hashed = Redacted::new(value, Redaction::Hashed).to_string();
+        assert_eq!(hashed.len(), 1 + HASH_PREFIX_LEN * 2);
+        assert!(hashed.starts_with('#'));
+        assert_eq!(Redacted::new(value, Redaction::Hashed).to_string(), hashed);
+        assert_ne!(
+            Redacted::new("other", Redaction::Hashed).to_string(),
+            hashed
+        );
+        assert_eq!(
+            Redacted::new(value, Redaction::Truncated(4)).to_string(),
+            "did:..."
+        );
+        assert_eq!(
+            format!("{:?}", Redacted::new(value, Redaction::Truncated(4))),
+            "\"did:...\""
+        );
+        assert_eq!(
+            Redacted::new("dïd", Redaction::Truncated(2)).to_string(),
+            "dï..."
+        );
+        assert_eq!(
+            Redacted::new("did", Redaction::Truncated(4)).to_string(),
+            "did"
+        );
+        assert_eq!(
+            format!("{:?}", Redacted::new(value, Redaction::Omitted)),
+            "<redacted>"
+        );
+    }
+
+    #[test]
+    fn parse_redaction() {
+        assert_eq!("plain".parse::<Redaction>().unwrap(), Redaction::Plain);
+        assert_eq!("hashed".parse::<Redaction>().unwrap(), Redaction::Hashed);
+        assert_eq!("omitted".parse::<Redaction>().unwrap(), Redaction::Omitted);
+        assert_eq!(
+            "truncated:6".parse::<Redaction>().unwrap(),
+            Redaction::Truncated(6)
+        );
+        for invalid in ["", "hash", "truncated", "truncated:-1"] {
+            assert!(invalid.parse::<Redaction>().is_err(), "{}", invalid);
+        }
+    }
+}
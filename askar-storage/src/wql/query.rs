@@ -195,7 +195,7 @@ mod serde_support {
             let v = JsonValue::deserialize(deserializer)?;
 
             match v {
-                JsonValue::Object(map) => parse_query(map).map_err(de::Error::missing_field),
+                JsonValue::Object(map) => parse_query(map).map_err(de::Error::custom),
                 JsonValue::Array(array) => {
                     // cast old restrictions format to wql
                     let mut res: Vec<JsonValue> = Vec::new();
@@ -218,7 +218,7 @@ mod serde_support {
 
                     parse_query(map).map_err(de::Error::custom)
                 }
-                _ => Err(de::Error::missing_field(
+                _ => Err(de::Error::custom(
                     "Restriction must be either object or array",
                 )),
             }
@@ -18,6 +18,11 @@ macro_rules! backend_tests {
             $run(super::utils::db_create_remove_profile)
         }
 
+        #[test]
+        fn ensure_profile() {
+            $run(super::utils::db_ensure_profile)
+        }
+
         #[test]
         fn list_profiles() {
             $run(super::utils::db_list_profiles)
@@ -28,6 +33,56 @@ macro_rules! backend_tests {
             $run(super::utils::db_get_set_default_profile)
         }
 
+        #[test]
+        fn list_profiles_page() {
+            $run(super::utils::db_list_profiles_page)
+        }
+
+        #[test]
+        fn rename_profile() {
+            $run(super::utils::db_rename_profile)
+        }
+
+        #[test]
+        fn profile_metadata() {
+            $run(super::utils::db_profile_metadata)
+        }
+
+        #[test]
+        fn remove_profiles() {
+            $run(super::utils::db_remove_profiles)
+        }
+
+        #[test]
+        fn profile_quota() {
+            $run(super::utils::db_profile_quota)
+        }
+
+        #[test]
+        fn profile_stats() {
+            $run(super::utils::db_profile_stats)
+        }
+
+        #[test]
+        fn profile_archived() {
+            $run(super::utils::db_profile_archived)
+        }
+
+        #[test]
+        fn clone_profile() {
+            $run(super::utils::db_clone_profile)
+        }
+
+        #[test]
+        fn profile_aliases() {
+            $run(super::utils::db_profile_aliases)
+        }
+
+        #[test]
+        fn profile_expiry_policy() {
+            $run(super::utils::db_profile_expiry_policy)
+        }
+
         #[test]
         fn fetch_fail() {
             $run(super::utils::db_fetch_fail)
@@ -38,6 +93,11 @@ macro_rules! backend_tests {
             $run(super::utils::db_insert_fetch)
         }
 
+        #[test]
+        fn fetch_without_tags() {
+            $run(super::utils::db_fetch_without_tags)
+        }
+
         #[test]
         fn insert_duplicate() {
             $run(super::utils::db_insert_duplicate)
@@ -63,6 +123,11 @@ macro_rules! backend_tests {
             $run(super::utils::db_replace_missing)
         }
 
+        #[test]
+        fn insert_unencrypted() {
+            $run(super::utils::db_insert_unencrypted)
+        }
+
         #[test]
         fn count() {
             $run(super::utils::db_count)
@@ -78,11 +143,26 @@ macro_rules! backend_tests {
             $run(super::utils::db_scan)
         }
 
+        #[test]
+        fn category_namespace() {
+            $run(super::utils::db_category_namespace)
+        }
+
         #[test]
         fn remove_all() {
             $run(super::utils::db_remove_all)
         }
 
+        #[test]
+        fn rekey_profile() {
+            $run(super::utils::db_rekey_profile)
+        }
+
+        #[test]
+        fn rotate_tag_key() {
+            $run(super::utils::db_rotate_tag_key)
+        }
+
         #[test]
         fn txn_rollback() {
             $run(super::utils::db_txn_rollback)
@@ -117,6 +197,16 @@ macro_rules! backend_tests {
         fn db_import() {
             $run(super::utils::db_import_scan)
         }
+
+        #[test]
+        fn import_conflicts() {
+            $run(super::utils::db_import_conflicts)
+        }
+
+        #[test]
+        fn version_info() {
+            $run(super::utils::db_version_info)
+        }
     };
 }
 
@@ -127,10 +217,17 @@ fn log_init() {
 #[cfg(feature = "sqlite")]
 mod sqlite {
     use askar_storage::any::{into_any_backend, AnyBackend};
-    use askar_storage::backend::copy_store;
     use askar_storage::backend::sqlite::SqliteStoreOptions;
+    use askar_storage::backend::{
+        backup_changes, copy_profile, copy_store, replicate, restore_changes, RekeyProgress,
+        ReplicationCheckpoint,
+    };
     use askar_storage::future::block_on;
-    use askar_storage::{generate_raw_store_key, Backend, ManageBackend, StoreKeyMethod};
+    use askar_storage::{
+        entry::{Entry, EntryKind, EntryOperation, EntryTag, TagFilter},
+        generate_raw_store_key, Argon2Level, Backend, BackendSession, ErrorKind, KdfMethod,
+        ManageBackend, StoreKeyMethod,
+    };
     use std::{future::Future, path::Path};
 
     use super::*;
@@ -225,51 +322,202 @@ mod sqlite {
     }
 
     #[test]
-    fn copy_db() {
+    fn open_legacy_store() {
+        // provisioned by a release predating the protection of the store configuration
+        const LEGACY_DB: &str = "./tests/askar_store_v1_sqlite.db";
+        const LEGACY_KEY: &str = "9kV13piHzyfh7NrmUFyLeKvXQ4n5GnCB6j3AfzTUxigC";
         log_init();
-        let fname_source = format!("sqlite-copy-{}.db", uuid::Uuid::new_v4());
-        let url_target = format!("sqlite://sqlite-copy-{}.db", uuid::Uuid::new_v4());
-        let key_source = generate_raw_store_key(None).expect("Error creating raw key");
-        let key_target = generate_raw_store_key(None).expect("Error creating raw key");
+        let fname = format!("sqlite-legacy-{}.db", uuid::Uuid::new_v4());
+        std::fs::copy(LEGACY_DB, &fname).expect("Error copying legacy store");
 
         block_on(async move {
-            let source = SqliteStoreOptions::new(fname_source.as_str())
+            let err =
+                SqliteStoreOptions::new(format!("{}?upgrade_legacy_config=false", fname).as_str())
+                    .expect("Error initializing sqlite store options")
+                    .open_backend(Some(StoreKeyMethod::RawKey), LEGACY_KEY.into(), None)
+                    .await
+                    .expect_err("Expected legacy store to be refused");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+            for _ in 0..2 {
+                let store = SqliteStoreOptions::new(fname.as_str())
+                    .expect("Error initializing sqlite store options")
+                    .open_backend(Some(StoreKeyMethod::RawKey), LEGACY_KEY.into(), None)
+                    .await
+                    .expect("Error opening legacy store");
+                assert_eq!(store.get_active_profile(), "legacy");
+                assert_eq!(
+                    store
+                        .version_info()
+                        .await
+                        .expect("Error fetching version information")
+                        .store_version,
+                    "2"
+                );
+                let mut conn = store.session(None, false).expect("Error starting session");
+                let row = conn
+                    .fetch(EntryKind::Item, "category", "name", false)
+                    .await
+                    .expect("Error fetching row")
+                    .expect("Row required");
+                assert_eq!(row.value.as_ref(), b"value");
+                assert_eq!(
+                    row.tags,
+                    vec![EntryTag::Encrypted("t1".to_string(), "v1".to_string())]
+                );
+                drop(conn);
+                store.close().await.expect("Error closing sqlite store");
+            }
+
+            SqliteStoreOptions::new(fname.as_str())
                 .expect("Error initializing sqlite store options")
-                .provision_backend(StoreKeyMethod::RawKey, key_source.as_ref(), None, false)
-                .await
-                .expect("Error provisioning sqlite store");
-            let profile = source
-                .get_default_profile()
+                .remove_backend()
                 .await
-                .expect("Error fetching default profile");
+                .expect("Error removing sqlite store");
+        })
+    }
 
-            let target = copy_store(
-                &source,
-                url_target.as_str(),
-                StoreKeyMethod::RawKey,
-                key_target.as_ref(),
-                false,
+    #[test]
+    fn rekey_chunked_resume() {
+        log_init();
+        let fname = format!("sqlite-rekey-chunked-{}.db", uuid::Uuid::new_v4());
+        let key1 = generate_raw_store_key(None).expect("Error creating raw key");
+        let key2 = generate_raw_store_key(None).expect("Error creating raw key");
+        let key3 = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key1.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let mut profiles = vec![store.get_active_profile()];
+            for _ in 0..4 {
+                profiles.push(
+                    store
+                        .create_profile(None)
+                        .await
+                        .expect("Error creating profile"),
+                );
+            }
+            let mut conn = store
+                .session(Some(profiles[4].clone()), false)
+                .expect("Error starting session");
+            conn.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name",
+                Some(b"value"),
+                None,
+                None,
             )
             .await
-            .expect("Error copying store");
-            target.close().await.expect("Error closing copied store");
+            .expect("Error inserting test row");
+            drop(conn);
+            store.close().await.expect("Error closing store");
 
-            source.close().await.expect("Error closing store");
-            SqliteStoreOptions::new(fname_source.as_str())
+            // interrupt the re-key after the first batch
+            let mut store = SqliteStoreOptions::new(fname.as_str())
                 .expect("Error initializing sqlite store options")
-                .remove_backend()
+                .open_backend(Some(StoreKeyMethod::RawKey), key1.as_ref(), None)
                 .await
-                .expect("Error removing sqlite store");
+                .expect("Error opening sqlite store");
+            let mut reports = Vec::new();
+            let err = store
+                .rekey_chunked(StoreKeyMethod::RawKey, key2.as_ref(), 2, &mut |report| {
+                    reports.push(report);
+                    Err(ErrorKind::Custom.into())
+                })
+                .await
+                .expect_err("Expected re-key to be interrupted");
+            assert_eq!(err.kind(), ErrorKind::Custom);
+            assert_eq!(
+                reports,
+                vec![RekeyProgress {
+                    completed: 2,
+                    total: 5
+                }]
+            );
+            store.close().await.expect("Error closing store");
 
-            let copied = SqliteStoreOptions::new(url_target.as_str())
+            // the store remains usable with the previous key
+            let mut store = SqliteStoreOptions::new(fname.as_str())
                 .expect("Error initializing sqlite store options")
-                .open_backend(Some(StoreKeyMethod::RawKey), key_target.as_ref(), None)
+                .open_backend(Some(StoreKeyMethod::RawKey), key1.as_ref(), None)
                 .await
-                .expect("Error opening copied store");
-            assert_eq!(copied.get_active_profile(), profile);
-            copied.close().await.expect("Error closing store");
+                .expect("Error opening sqlite store");
+            let mut conn = store
+                .session(Some(profiles[4].clone()), false)
+                .expect("Error starting session");
+            assert!(conn
+                .fetch(EntryKind::Item, "category", "name", false)
+                .await
+                .expect("Error fetching test row")
+                .is_some());
+            drop(conn);
 
-            SqliteStoreOptions::new(url_target.as_str())
+            // the pass key is checked against the recorded key check value,
+            // even when no profile key has been re-encrypted
+            let pool = sqlx::sqlite::SqlitePool::connect(format!("sqlite://{}", fname).as_str())
+                .await
+                .expect("Error connecting to database");
+            sqlx::query("UPDATE profiles SET profile_key_pending = NULL")
+                .execute(&pool)
+                .await
+                .expect("Error clearing pending profile keys");
+            pool.close().await;
+            let err = store
+                .rekey_chunked(StoreKeyMethod::RawKey, key3.as_ref(), 2, &mut |_| Ok(()))
+                .await
+                .expect_err("Expected pass key mismatch");
+            assert_eq!(err.kind(), ErrorKind::Encryption);
+
+            let mut reports = Vec::new();
+            store
+                .rekey_chunked(StoreKeyMethod::RawKey, key2.as_ref(), 2, &mut |report| {
+                    reports.push(report);
+                    Ok(())
+                })
+                .await
+                .expect("Error resuming re-key");
+            assert_eq!(
+                reports.iter().map(|r| r.completed).collect::<Vec<_>>(),
+                vec![2, 4, 5]
+            );
+            let mut conn = store
+                .session(Some(profiles[4].clone()), false)
+                .expect("Error starting session");
+            assert!(conn
+                .fetch(EntryKind::Item, "category", "name", false)
+                .await
+                .expect("Error fetching test row")
+                .is_some());
+            drop(conn);
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key1.as_ref(), None)
+                .await
+                .expect_err("Expected previous key to be rejected");
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key2.as_ref(), None)
+                .await
+                .expect("Error opening rekeyed store");
+            let mut conn = store
+                .session(Some(profiles[4].clone()), false)
+                .expect("Error starting session");
+            assert!(conn
+                .fetch(EntryKind::Item, "category", "name", false)
+                .await
+                .expect("Error fetching test row")
+                .is_some());
+            drop(conn);
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
                 .expect("Error initializing sqlite store options")
                 .remove_backend()
                 .await
@@ -278,111 +526,1948 @@ mod sqlite {
     }
 
     #[test]
-    fn txn_contention_file() {
+    fn key_cache_bounded() {
         log_init();
-        let fname = format!("sqlite-contention-{}.db", uuid::Uuid::new_v4());
+        let fname = format!("sqlite-cache-{}.db", uuid::Uuid::new_v4());
+        let bounded_uri = format!("{}?max_cached_profiles=2", fname);
         let key = generate_raw_store_key(None).expect("Error creating raw key");
 
         block_on(async move {
-            let store = SqliteStoreOptions::new(fname.as_str())
+            SqliteStoreOptions::new(format!("{}?max_cached_profiles=0", fname).as_str())
+                .expect_err("Expected invalid cache size");
+
+            let store = SqliteStoreOptions::new(bounded_uri.as_str())
                 .expect("Error initializing sqlite store options")
-                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, true)
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
                 .await
                 .expect("Error provisioning sqlite store");
+            let mut profiles = vec![store.get_active_profile()];
+            for _ in 0..3 {
+                profiles.push(
+                    store
+                        .create_profile(None)
+                        .await
+                        .expect("Error creating profile"),
+                );
+            }
+            for _ in 0..2 {
+                for profile in profiles.iter() {
+                    let mut session = store
+                        .session(Some(profile.clone()), false)
+                        .expect("Error starting session");
+                    assert_eq!(
+                        session
+                            .count(None, None, None)
+                            .await
+                            .expect("Error counting entries"),
+                        0
+                    );
+                    drop(session);
+                }
+            }
+            let stats = store.key_cache_stats().await.expect("Expected cache stats");
+            assert_eq!(stats.size, 2);
+            assert_eq!(stats.max_size, Some(2));
+            assert!(stats.evictions > 0);
 
-            let db = into_any_backend(store);
-            super::utils::db_txn_contention(db.clone()).await;
-            db.close().await.expect("Error closing sqlite store");
+            store
+                .remove_profile(profiles[3].clone())
+                .await
+                .expect("Error removing profile");
+            let stats = store.key_cache_stats().await.expect("Expected cache stats");
+            assert_eq!(stats.size, 1);
 
+            store.close().await.expect("Error closing store");
             SqliteStoreOptions::new(fname.as_str())
                 .expect("Error initializing sqlite store options")
                 .remove_backend()
                 .await
                 .expect("Error removing sqlite store");
-        });
+        })
     }
 
-    #[cfg(feature = "stress_test")]
     #[test]
-    fn stress_test() {
+    fn key_cache_invalidation() {
         log_init();
-        use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
-        use std::str::FromStr;
-        let conn_opts = SqliteConnectOptions::from_str("sqlite:test.db")
-            .unwrap()
-            .create_if_missing(true);
-        // .shared_cache(true);
+        let fname = format!("sqlite-invalidate-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
         block_on(async move {
-            let pool = SqlitePoolOptions::default()
-                // maintains at least 1 connection.
-                // for an in-memory database this is required to avoid dropping the database,
-                // for a file database this signals other instances that the database is in use
-                .min_connections(1)
-                .max_connections(5)
-                .test_before_acquire(false)
-                .connect_with(conn_opts)
-                .await
-                .unwrap();
+            SqliteStoreOptions::new(format!("{}?key_cache_ttl=0", fname).as_str())
+                .expect_err("Expected invalid cache lifetime");
 
-            let mut conn = pool.begin().await.unwrap();
-            sqlx::query("CREATE TABLE test (name TEXT)")
-                .execute(&mut conn)
-                .await
-                .unwrap();
-            sqlx::query("INSERT INTO test (name) VALUES ('test')")
-                .execute(&mut conn)
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
                 .await
-                .unwrap();
-            conn.commit().await.unwrap();
-
-            const TASKS: usize = 25;
-            const COUNT: usize = 1000;
+                .expect("Error provisioning sqlite store");
+            let profile = store.get_active_profile();
+            let mut conn = store
+                .session(Some(profile.clone()), false)
+                .expect("Error starting session");
+            conn.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name",
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect("Error inserting test row");
+            drop(conn);
 
-            async fn fetch(pool: SqlitePool) -> Result<(), &'static str> {
-                // try to avoid panics in this section, as they will be raised on a tokio worker thread
-                for _ in 0..COUNT {
-                    let mut txn = pool.acquire().await.expect("Acquire error");
-                    sqlx::query("BEGIN IMMEDIATE")
-                        .execute(&mut txn)
-                        .await
-                        .expect("Transaction error");
-                    let _ = sqlx::query("SELECT * FROM test")
-                        .fetch_one(&mut txn)
-                        .await
-                        .expect("Error fetching row");
-                    sqlx::query("COMMIT")
-                        .execute(&mut txn)
+            let other = SqliteStoreOptions::new(format!("{}?key_cache_ttl=1", fname).as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect("Error opening sqlite store");
+            let fetch = |store: &AnyBackend| {
+                let store = store.clone();
+                let profile = profile.clone();
+                async move {
+                    let mut conn = store
+                        .session(Some(profile), false)
+                        .expect("Error starting session");
+                    conn.fetch(EntryKind::Item, "category", "name", false)
                         .await
-                        .expect("Commit error");
+                        .expect("Error fetching test row")
+                        .is_some()
                 }
-                Ok(())
-            }
+            };
+            let store = into_any_backend(store);
+            let other = into_any_backend(other);
+            assert!(fetch(&other).await);
 
-            let mut tasks = vec![];
-            for _ in 0..TASKS {
-                tasks.push(tokio::spawn(fetch(pool.clone())));
-            }
+            // the key cached by the other instance is stale once the profile is re-keyed
+            store
+                .rekey_profile(profile.clone())
+                .await
+                .expect("Error re-keying profile");
+            assert!(!fetch(&other).await);
+            other.invalidate_profile_key(profile.clone()).await;
+            assert!(fetch(&other).await);
 
-            for task in tasks {
-                if let Err(s) = task.await.unwrap() {
-                    panic!("Error in concurrent update task: {}", s);
-                }
-            }
-        });
+            store
+                .rekey_profile(profile.clone())
+                .await
+                .expect("Error re-keying profile");
+            assert!(!fetch(&other).await);
+            std::thread::sleep(std::time::Duration::from_millis(1100));
+            assert!(fetch(&other).await);
+            let stats = other.key_cache_stats().await.expect("Expected cache stats");
+            assert_eq!(stats.expirations, 1);
+
+            other.close().await.expect("Error closing store");
+            store.close().await.expect("Error closing store");
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
     }
 
-    fn with_sqlite_in_memory<F, G>(f: F)
-    where
-        F: FnOnce(AnyBackend) -> G,
-        G: Future<Output = ()>,
-    {
+    #[test]
+    fn auto_create_profiles() {
         log_init();
-        let key = generate_raw_store_key(None).expect("Error generating store key");
+        let fname = format!("sqlite-autocreate-{}.db", uuid::Uuid::new_v4());
+        let auto_uri = format!("{}?auto_create_profiles=true", fname);
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
         block_on(async move {
-            let db = into_any_backend(
-                SqliteStoreOptions::in_memory()
-                    .provision(StoreKeyMethod::RawKey, key, None, false)
-                    .await
+            SqliteStoreOptions::new(format!("{}?auto_create_profiles=maybe", fname).as_str())
+                .expect_err("Expected invalid auto_create_profiles parameter");
+            SqliteStoreOptions::new(format!("{}&schema_compat=read_only", auto_uri).as_str())
+                .expect_err("Expected auto_create_profiles to be refused in read-only mode");
+
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let mut conn = store
+                .session(Some("tenant".to_string()), false)
+                .expect("Error starting session");
+            let err = conn
+                .count(None, None, None)
+                .await
+                .expect_err("Expected missing profile");
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+            drop(conn);
+            store.close().await.expect("Error closing store");
+
+            let store = SqliteStoreOptions::new(auto_uri.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect("Error opening sqlite store");
+            let mut conn = store
+                .session(Some("tenant".to_string()), false)
+                .expect("Error starting session");
+            conn.ping().await.expect("Error pinging session");
+            // reads do not create the profile
+            let err = conn
+                .count(None, None, None)
+                .await
+                .expect_err("Expected missing profile");
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+            assert!(!conn.take_profile_created());
+            assert!(!store
+                .list_profiles()
+                .await
+                .expect("Error listing profiles")
+                .contains(&"tenant".to_string()));
+            conn.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name",
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect("Error inserting test row");
+            assert!(conn.take_profile_created());
+            assert!(!conn.take_profile_created());
+            drop(conn);
+            let mut conn = store
+                .session(Some("tenant".to_string()), false)
+                .expect("Error starting session");
+            assert_eq!(
+                conn.count(None, None, None)
+                    .await
+                    .expect("Error counting entries"),
+                1
+            );
+            drop(conn);
+
+            // a profile created within a transaction is discarded on roll-back
+            let mut txn = store
+                .session(Some("discarded".to_string()), true)
+                .expect("Error starting transaction");
+            txn.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name",
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect("Error inserting test row");
+            assert_eq!(
+                txn.count(None, None, None)
+                    .await
+                    .expect("Error counting entries"),
+                1
+            );
+            txn.close(false).await.expect("Error rolling back");
+
+            let profiles = store.list_profiles().await.expect("Error listing profiles");
+            assert!(profiles.contains(&"tenant".to_string()));
+            assert!(!profiles.contains(&"discarded".to_string()));
+
+            store.close().await.expect("Error closing store");
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn key_cache_preload() {
+        log_init();
+        let fname = format!("sqlite-preload-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let mut profiles = Vec::new();
+            for _ in 0..3 {
+                profiles.push(
+                    store
+                        .create_profile(None)
+                        .await
+                        .expect("Error creating profile"),
+                );
+            }
+            store.close().await.expect("Error closing store");
+
+            for (preload, size) in [
+                ("*".to_string(), 4),
+                (format!("{},missing", profiles[0]), 2),
+            ] {
+                let store = SqliteStoreOptions::new(
+                    format!("{}?preload_profiles={}", fname, preload).as_str(),
+                )
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect("Error opening sqlite store");
+                let stats = store.key_cache_stats().await.expect("Expected cache stats");
+                assert_eq!(stats.size, size);
+                let mut session = store
+                    .session(Some(profiles[0].clone()), false)
+                    .expect("Error starting session");
+                session
+                    .count(None, None, None)
+                    .await
+                    .expect("Error counting entries");
+                drop(session);
+                let stats = store.key_cache_stats().await.expect("Expected cache stats");
+                assert_eq!((stats.hits, stats.misses), (1, 0));
+                store.close().await.expect("Error closing store");
+            }
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn pool_status() {
+        log_init();
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(
+                "sqlite://:memory:?max_connections=4&min_connections=1&warm_connections=3",
+            )
+            .expect("Error initializing sqlite store options")
+            .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+            .await
+            .expect("Error provisioning sqlite store");
+
+            let mut session = store.session(None, false).expect("Error starting session");
+            session
+                .count(None, None, None)
+                .await
+                .expect("Error counting entries");
+            let status = store.pool_status().expect("Expected pool status");
+            assert_eq!(status.max_size, 4);
+            assert!(status.size >= 3);
+            assert!(status.active >= 1);
+            assert!(status.acquired >= 1);
+            assert_eq!(status.failures, 0);
+            assert!(status.acquire_p50.is_some());
+            drop(session);
+
+            store.close().await.expect("Error closing store");
+        })
+    }
+
+    #[test]
+    fn copy_profile_batch() {
+        log_init();
+        block_on(async move {
+            let source = SqliteStoreOptions::in_memory()
+                .provision(
+                    StoreKeyMethod::RawKey,
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error provisioning sqlite store");
+            let target = SqliteStoreOptions::in_memory()
+                .provision(
+                    StoreKeyMethod::RawKey,
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error provisioning sqlite store");
+
+            let test_rows = (0..300)
+                .map(|idx| {
+                    Entry::new(
+                        EntryKind::Item,
+                        "category",
+                        format!("name-{:03}", idx),
+                        format!("value-{}", idx),
+                        vec![EntryTag::Encrypted(
+                            "t1".to_string(),
+                            format!("v{}", idx % 7),
+                        )],
+                    )
+                })
+                .collect::<Vec<_>>();
+            let mut conn = source.session(None, true).expect("Error creating session");
+            for row in test_rows.iter() {
+                conn.update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    &row.category,
+                    &row.name,
+                    Some(&row.value),
+                    Some(row.tags.as_slice()),
+                    None,
+                )
+                .await
+                .expect("Error inserting test row");
+            }
+            conn.close(true).await.expect("Error committing session");
+
+            copy_profile(&source, &target, &source.get_active_profile(), "copy")
+                .await
+                .expect("Error copying profile");
+
+            let mut conn = target
+                .session(Some("copy".to_string()), false)
+                .expect("Error creating session");
+            let mut rows = conn
+                .fetch_all(
+                    Some(EntryKind::Item),
+                    None,
+                    Some(TagFilter::is_eq("t1", "v3")),
+                    None,
+                    None,
+                    false,
+                    false,
+                )
+                .await
+                .expect("Error fetching rows");
+            rows.sort_by(|a, b| a.name.cmp(&b.name));
+            let expected = test_rows
+                .iter()
+                .filter(|row| row.tags[0].value() == "v3")
+                .cloned()
+                .collect::<Vec<_>>();
+            assert_eq!(rows, expected);
+            assert_eq!(
+                conn.count(None, None, None)
+                    .await
+                    .expect("Error counting rows"),
+                test_rows.len() as i64
+            );
+            drop(conn);
+
+            source.close().await.expect("Error closing store");
+            target.close().await.expect("Error closing store");
+        })
+    }
+
+    #[test]
+    fn replicate_resume() {
+        log_init();
+        block_on(async move {
+            let source = SqliteStoreOptions::new("sqlite://:memory:?page_size=10")
+                .unwrap()
+                .provision(
+                    StoreKeyMethod::RawKey,
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error provisioning sqlite store");
+            let target = SqliteStoreOptions::in_memory()
+                .provision(
+                    StoreKeyMethod::RawKey,
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error provisioning sqlite store");
+            let other = source
+                .create_profile(Some("other".to_string()))
+                .await
+                .expect("Error creating profile");
+
+            for (profile, count) in [(source.get_active_profile(), 25), (other, 5)] {
+                let mut conn = source
+                    .session(Some(profile), true)
+                    .expect("Error creating session");
+                for idx in 0..count {
+                    let kind = if idx % 5 == 0 {
+                        EntryKind::Kms
+                    } else {
+                        EntryKind::Item
+                    };
+                    conn.update(
+                        kind,
+                        EntryOperation::Insert,
+                        "category",
+                        &format!("name-{}", idx),
+                        Some(b"value"),
+                        Some(&[EntryTag::Plaintext("t1".to_string(), idx.to_string())]),
+                        None,
+                    )
+                    .await
+                    .expect("Error inserting test row");
+                }
+                conn.close(true).await.expect("Error committing session");
+            }
+
+            // interrupt the replication after the second batch, resuming from
+            // the first to simulate a checkpoint which was not persisted
+            let mut resume = None;
+            let err = replicate(&source, &target, None, |checkpoint| {
+                match checkpoint.total {
+                    10 => {
+                        resume.replace(checkpoint.clone());
+                        Ok(())
+                    }
+                    20 => Err(ErrorKind::Custom.into()),
+                    _ => Ok(()),
+                }
+            })
+            .await
+            .expect_err("Expected replication to be interrupted");
+            assert_eq!(err.kind(), ErrorKind::Custom);
+            let resume = resume.expect("Expected checkpoint");
+            assert!(!resume.is_complete());
+            assert_eq!(resume.copied, 10);
+            assert!(resume.last_id.is_some());
+
+            // modify the source before resuming: no records may be skipped
+            let mut conn = source.session(None, true).expect("Error creating session");
+            conn.update(
+                EntryKind::Item,
+                EntryOperation::Remove,
+                "category",
+                "name-1",
+                None,
+                None,
+                None,
+            )
+            .await
+            .expect("Error removing test row");
+            conn.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name-25",
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect("Error inserting test row");
+            conn.close(true).await.expect("Error committing session");
+
+            let mut checkpoints: Vec<ReplicationCheckpoint> = Vec::new();
+            let result = replicate(&source, &target, Some(resume), |checkpoint| {
+                checkpoints.push(checkpoint.clone());
+                Ok(())
+            })
+            .await
+            .expect("Error resuming replication");
+            assert!(result.is_complete());
+            assert_eq!(result.total, 31);
+            assert_eq!(checkpoints.last(), Some(&result));
+
+            for (profile, count) in [(source.get_active_profile(), 26), ("other".to_string(), 5)] {
+                let mut conn = target
+                    .session(Some(profile), false)
+                    .expect("Error creating session");
+                assert_eq!(
+                    conn.count(None, None, None)
+                        .await
+                        .expect("Error counting rows"),
+                    count
+                );
+                let key = conn
+                    .fetch(EntryKind::Kms, "category", "name-0", false)
+                    .await
+                    .expect("Error fetching row")
+                    .expect("Expected replicated key");
+                assert_eq!(
+                    key.tags,
+                    vec![EntryTag::Plaintext("t1".to_string(), "0".to_string())]
+                );
+                assert!(conn
+                    .fetch(EntryKind::Item, "category", "name-4", false)
+                    .await
+                    .expect("Error fetching row")
+                    .is_some());
+            }
+            let mut conn = target
+                .session(Some(source.get_active_profile()), false)
+                .expect("Error creating session");
+            for (kind, name) in [(EntryKind::Kms, "name-10"), (EntryKind::Item, "name-25")] {
+                assert!(conn
+                    .fetch(kind, "category", name, false)
+                    .await
+                    .expect("Error fetching row")
+                    .is_some());
+            }
+            conn.close(false).await.expect("Error closing session");
+            assert_eq!(
+                target
+                    .get_default_profile()
+                    .await
+                    .expect("Error fetching default profile"),
+                source.get_active_profile()
+            );
+
+            source.close().await.expect("Error closing store");
+            target.close().await.expect("Error closing store");
+        })
+    }
+
+    #[test]
+    fn replicate_empty() {
+        log_init();
+        block_on(async move {
+            let source = SqliteStoreOptions::in_memory()
+                .provision(
+                    StoreKeyMethod::RawKey,
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error provisioning sqlite store");
+            let target = SqliteStoreOptions::in_memory()
+                .provision(
+                    StoreKeyMethod::RawKey,
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error provisioning sqlite store");
+            assert!(source
+                .remove_profile(source.get_active_profile())
+                .await
+                .expect("Error removing profile"));
+
+            assert!(!ReplicationCheckpoint::default().is_complete());
+            let result = replicate(&source, &target, None, |_| Ok(()))
+                .await
+                .expect("Error replicating store");
+            assert!(result.is_complete());
+            assert!(result.completed.is_empty());
+            assert_eq!(result.total, 0);
+
+            source.close().await.expect("Error closing store");
+            target.close().await.expect("Error closing store");
+        })
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        log_init();
+        let fname = format!("sqlite-snapshot-{}.db", uuid::Uuid::new_v4());
+        let fname_snapshot = format!("sqlite-snapshot-{}.db", uuid::Uuid::new_v4());
+        let fname_target = format!("sqlite-snapshot-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let mut conn = store.session(None, false).expect("Error creating session");
+            for name in ["name-0", "name-1", "name-2"] {
+                if name == "name-2" {
+                    store
+                        .snapshot_to(fname_snapshot.clone())
+                        .await
+                        .expect("Error writing snapshot");
+                }
+                conn.update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    name,
+                    Some(b"value"),
+                    None,
+                    None,
+                )
+                .await
+                .expect("Error inserting test row");
+            }
+            conn.close(false).await.expect("Error closing session");
+            let err = store
+                .snapshot_to(fname_snapshot.clone())
+                .await
+                .expect_err("Expected existing snapshot path to be refused");
+            assert_eq!(err.kind(), ErrorKind::Duplicate);
+            // the snapshot is written to a temporary file which is renamed into place
+            let temp_prefix = format!("{}.", fname_snapshot);
+            assert!(!std::fs::read_dir(".")
+                .expect("Error listing directory")
+                .any(|entry| entry
+                    .expect("Error listing directory")
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&temp_prefix)));
+
+            let err = SqliteStoreOptions::new(fname_target.as_str())
+                .expect("Error initializing sqlite store options")
+                .restore_snapshot(
+                    &fname_snapshot,
+                    Some(StoreKeyMethod::RawKey),
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                )
+                .await
+                .expect_err("Expected restore with the wrong key to fail");
+            assert_eq!(err.kind(), ErrorKind::Input);
+            assert_eq!(Path::new(&fname_target).exists(), false);
+
+            let restored = SqliteStoreOptions::new(fname_target.as_str())
+                .expect("Error initializing sqlite store options")
+                .restore_snapshot(
+                    &fname_snapshot,
+                    Some(StoreKeyMethod::RawKey),
+                    key.as_ref(),
+                    None,
+                )
+                .await
+                .expect("Error restoring snapshot");
+            let mut conn = restored
+                .session(None, false)
+                .expect("Error creating session");
+            assert_eq!(
+                conn.count(None, None, None)
+                    .await
+                    .expect("Error counting rows"),
+                2
+            );
+            conn.close(false).await.expect("Error closing session");
+            restored.close().await.expect(ERR_CLOSE);
+            store.close().await.expect(ERR_CLOSE);
+
+            for fname in [fname, fname_snapshot, fname_target] {
+                SqliteStoreOptions::new(fname.as_str())
+                    .expect("Error initializing sqlite store options")
+                    .remove_backend()
+                    .await
+                    .expect("Error removing sqlite store");
+            }
+        })
+    }
+
+    #[test]
+    fn backup_incremental() {
+        log_init();
+        block_on(async move {
+            let source = SqliteStoreOptions::in_memory()
+                .provision(
+                    StoreKeyMethod::RawKey,
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error provisioning sqlite store");
+            let target = SqliteStoreOptions::in_memory()
+                .provision(
+                    StoreKeyMethod::RawKey,
+                    generate_raw_store_key(None).unwrap(),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error provisioning sqlite store");
+            let other = source
+                .create_profile(Some("other".to_string()))
+                .await
+                .expect("Error creating profile");
+            let backup_key = generate_raw_store_key(None).unwrap();
+
+            async fn write<B: Backend>(
+                store: &B,
+                profile: &str,
+                operation: EntryOperation,
+                name: &str,
+                value: &[u8],
+            ) {
+                let mut conn = store
+                    .session(Some(profile.to_string()), true)
+                    .expect("Error creating session");
+                conn.update(
+                    EntryKind::Item,
+                    operation,
+                    "category",
+                    name,
+                    Some(value),
+                    Some(&[EntryTag::Encrypted("t1".to_string(), name.to_string())]),
+                    None,
+                )
+                .await
+                .expect("Error updating test row");
+                conn.close(true).await.expect("Error committing session");
+            }
+
+            let default = source.get_active_profile();
+            for name in ["name-0", "name-1", "name-2"] {
+                write(&source, &default, EntryOperation::Insert, name, b"value").await;
+            }
+            write(&source, &other, EntryOperation::Insert, "name-0", b"value").await;
+
+            let mut segment = Vec::new();
+            let full = backup_changes(
+                &source,
+                0,
+                StoreKeyMethod::RawKey,
+                backup_key.as_ref(),
+                &mut segment,
+                None,
+            )
+            .await
+            .expect("Error writing backup segment");
+            assert_eq!(full.changes, 4);
+            let err = restore_changes(
+                &target,
+                segment.as_slice(),
+                generate_raw_store_key(None).unwrap(),
+                0,
+                None,
+            )
+            .await
+            .expect_err("Expected restore with the wrong key to fail");
+            assert_eq!(err.kind(), ErrorKind::Encryption);
+            let info = restore_changes(&target, segment.as_slice(), backup_key.as_ref(), 0, None)
+                .await
+                .expect("Error restoring backup segment");
+            assert_eq!(info, full);
+
+            write(
+                &source,
+                &default,
+                EntryOperation::Replace,
+                "name-0",
+                b"updated",
+            )
+            .await;
+            write(&source, &default, EntryOperation::Remove, "name-1", b"").await;
+            write(
+                &source,
+                &default,
+                EntryOperation::Insert,
+                "name-3",
+                b"value",
+            )
+            .await;
+            // re-keying a profile does not change its records
+            source
+                .rekey_profile(other.clone())
+                .await
+                .expect("Error re-keying profile");
+            write(&source, &other, EntryOperation::Insert, "name-1", b"value").await;
+
+            let mut segment = Vec::new();
+            let incr = backup_changes(
+                &source,
+                full.until,
+                StoreKeyMethod::RawKey,
+                backup_key.as_ref(),
+                &mut segment,
+                None,
+            )
+            .await
+            .expect("Error writing backup segment");
+            assert_eq!((incr.since, incr.changes), (full.until, 4));
+            let err = restore_changes(&target, segment.as_slice(), backup_key.as_ref(), 0, None)
+                .await
+                .expect_err("Expected out of order restore to fail");
+            assert_eq!(err.kind(), ErrorKind::Input);
+            restore_changes(
+                &target,
+                segment.as_slice(),
+                backup_key.as_ref(),
+                full.until,
+                None,
+            )
+            .await
+            .expect("Error restoring backup segment");
+
+            let mut conn = target
+                .session(Some(default), false)
+                .expect("Error creating session");
+            let rows = conn
+                .fetch_all(Some(EntryKind::Item), None, None, None, None, false, false)
+                .await
+                .expect("Error fetching rows");
+            let mut names = rows.iter().map(|row| row.name.as_str()).collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(names, vec!["name-0", "name-2", "name-3"]);
+            let row = conn
+                .fetch(EntryKind::Item, "category", "name-0", false)
+                .await
+                .expect("Error fetching row")
+                .expect("Expected restored row");
+            assert_eq!(row.value.as_ref(), b"updated");
+            assert_eq!(
+                row.tags,
+                vec![EntryTag::Encrypted("t1".to_string(), "name-0".to_string())]
+            );
+            conn.close(false).await.expect("Error closing session");
+            let mut conn = target
+                .session(Some(other.clone()), false)
+                .expect("Error creating session");
+            assert_eq!(
+                conn.count(None, None, None)
+                    .await
+                    .expect("Error counting rows"),
+                2
+            );
+            conn.close(false).await.expect("Error closing session");
+
+            // removals are discarded once the checkpoint is stored
+            assert_eq!(
+                source
+                    .prune_changes(incr.until)
+                    .await
+                    .expect("Error pruning changes"),
+                1
+            );
+            let changes = source
+                .scan_changes(0, incr.until, 100)
+                .await
+                .expect("Error scanning changes");
+            assert_eq!(changes.len(), 5);
+            assert!(changes.iter().all(|change| !change.removed));
+
+            // removed profiles are not recorded
+            source
+                .remove_profile(other)
+                .await
+                .expect("Error removing profile");
+            let empty = backup_changes(
+                &source,
+                incr.until,
+                StoreKeyMethod::RawKey,
+                backup_key.as_ref(),
+                &mut Vec::new(),
+                None,
+            )
+            .await
+            .expect("Error writing backup segment");
+            assert_eq!(empty.changes, 0);
+
+            source.close().await.expect("Error closing store");
+            target.close().await.expect("Error closing store");
+        })
+    }
+
+    #[test]
+    fn scan_page_size() {
+        log_init();
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+        block_on(async move {
+            SqliteStoreOptions::new("sqlite://:memory:?page_size=0")
+                .expect_err("Expected invalid page size");
+            let db = SqliteStoreOptions::new("sqlite://:memory:?page_size=5")
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+
+            let mut conn = db.session(None, true).expect("Error creating session");
+            for idx in 0..12 {
+                conn.update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    &format!("name-{}", idx),
+                    Some(b"value"),
+                    None,
+                    None,
+                )
+                .await
+                .expect("Error inserting test row");
+            }
+            conn.close(true).await.expect("Error committing session");
+
+            let mut scan = db
+                .scan(None, None, None, None, None, None, None, false)
+                .await
+                .expect("Error starting scan");
+            let mut pages = vec![];
+            while let Some(rows) = scan.fetch_next().await.expect("Error fetching scan rows") {
+                pages.push(rows.len());
+            }
+            assert_eq!(pages, vec![5, 5, 2]);
+
+            db.close().await.expect("Error closing store");
+        })
+    }
+
+    #[test]
+    fn unlock_lockout() {
+        log_init();
+        let fname = format!("sqlite-lockout-{}.db", uuid::Uuid::new_v4());
+        let locked_uri = format!("{}?max_unlock_attempts=2&unlock_lockout=60", fname);
+        let key1 = generate_raw_store_key(None).expect("Error creating raw key");
+        let key2 = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key1.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            store.close().await.expect("Error closing store");
+
+            for _ in 0..2 {
+                let err = SqliteStoreOptions::new(locked_uri.as_str())
+                    .expect("Error initializing sqlite store options")
+                    .open_backend(None, key2.as_ref(), None)
+                    .await
+                    .expect_err("Expected unlock failure");
+                assert_eq!(err.kind(), ErrorKind::Encryption);
+            }
+            let err = SqliteStoreOptions::new(locked_uri.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(None, key1.as_ref(), None)
+                .await
+                .expect_err("Expected store lockout");
+            assert_eq!(err.kind(), ErrorKind::Busy);
+
+            // without a lockout policy, a successful unlock resets the failure count
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(None, key1.as_ref(), None)
+                .await
+                .expect("Error opening store")
+                .close()
+                .await
+                .expect("Error closing store");
+            SqliteStoreOptions::new(locked_uri.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(None, key1.as_ref(), None)
+                .await
+                .expect("Error opening store after reset")
+                .close()
+                .await
+                .expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn unlock_lockout_recorded() {
+        log_init();
+        let fname = format!("sqlite-lockout-rec-{}.db", uuid::Uuid::new_v4());
+        let key1 = generate_raw_store_key(None).expect("Error creating raw key");
+        let key2 = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(
+                format!("{}?max_unlock_attempts=2&unlock_lockout=60", fname).as_str(),
+            )
+            .expect("Error initializing sqlite store options")
+            .provision_backend(StoreKeyMethod::RawKey, key1.as_ref(), None, false)
+            .await
+            .expect("Error provisioning sqlite store");
+            store.close().await.expect("Error closing store");
+
+            // the policy recorded when provisioning applies without URI parameters
+            for _ in 0..2 {
+                let err = SqliteStoreOptions::new(fname.as_str())
+                    .expect("Error initializing sqlite store options")
+                    .open_backend(None, key2.as_ref(), None)
+                    .await
+                    .expect_err("Expected unlock failure");
+                assert_eq!(err.kind(), ErrorKind::Encryption);
+            }
+            for uri in [
+                fname.clone(),
+                format!("{}?max_unlock_attempts=10&unlock_lockout=0", fname),
+            ] {
+                let err = SqliteStoreOptions::new(uri.as_str())
+                    .expect("Error initializing sqlite store options")
+                    .open_backend(None, key1.as_ref(), None)
+                    .await
+                    .expect_err("Expected store lockout");
+                assert_eq!(err.kind(), ErrorKind::Busy);
+            }
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn unlock_lockout_read_only() {
+        log_init();
+        let fname = format!("sqlite-lockout-ro-{}.db", uuid::Uuid::new_v4());
+        let ro_uri = format!(
+            "{}?schema_compat=read_only&max_unlock_attempts=2&unlock_lockout=60",
+            fname
+        );
+        let key1 = generate_raw_store_key(None).expect("Error creating raw key");
+        let key2 = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key1.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            store.close().await.expect("Error closing store");
+
+            // failed attempts are recorded even though writes are otherwise disabled
+            for _ in 0..2 {
+                let err = SqliteStoreOptions::new(ro_uri.as_str())
+                    .expect("Error initializing sqlite store options")
+                    .open_backend(None, key2.as_ref(), None)
+                    .await
+                    .expect_err("Expected unlock failure");
+                assert_eq!(err.kind(), ErrorKind::Encryption);
+            }
+            let err = SqliteStoreOptions::new(ro_uri.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(None, key1.as_ref(), None)
+                .await
+                .expect_err("Expected store lockout");
+            assert_eq!(err.kind(), ErrorKind::Busy);
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn profile_cipher() {
+        log_init();
+        let fname = format!("sqlite-cipher-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        SqliteStoreOptions::new(format!("{}?profile_cipher=unknown", fname).as_str())
+            .expect_err("Expected invalid cipher error");
+
+        block_on(async move {
+            let store =
+                SqliteStoreOptions::new(format!("{}?profile_cipher=a256gcm", fname).as_str())
+                    .expect("Error initializing sqlite store options")
+                    .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                    .await
+                    .expect("Error provisioning sqlite store");
+            let profile = store
+                .create_profile(None)
+                .await
+                .expect("Error creating profile");
+            for name in [None, Some(profile.clone())] {
+                let mut sess = store.session(name, false).expect("Error starting session");
+                sess.update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    "name",
+                    Some(b"value"),
+                    Some(&[EntryTag::Encrypted("t1".to_string(), "v1".to_string())]),
+                    None,
+                )
+                .await
+                .expect("Error inserting test row");
+                sess.close(true).await.expect("Error closing session");
+            }
+            store.close().await.expect("Error closing store");
+
+            // the cipher is recorded by the store and does not need to be provided again
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(None, key.as_ref(), None)
+                .await
+                .expect("Error opening store");
+            for name in [None, Some(profile)] {
+                let mut sess = store.session(name, false).expect("Error starting session");
+                let rows = sess
+                    .fetch_all(
+                        Some(EntryKind::Item),
+                        Some("category"),
+                        Some(TagFilter::is_eq("t1", "v1")),
+                        None,
+                        None,
+                        false,
+                        false,
+                    )
+                    .await
+                    .expect("Error fetching test rows");
+                assert_eq!(rows.len(), 1);
+                assert_eq!(rows[0].value, &b"value"[..]);
+                sess.close(false).await.expect("Error closing session");
+            }
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn randomized_names() {
+        log_init();
+        let fname = format!("sqlite-names-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        SqliteStoreOptions::new(format!("{}?name_encryption=unknown", fname).as_str())
+            .expect_err("Expected invalid name encryption error");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(format!("{}?name_encryption=rnd", fname).as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let profile = store.get_active_profile();
+            let mut sess = store.session(None, false).expect("Error starting session");
+            for name in ["name1", "name2"] {
+                sess.update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    name,
+                    Some(b"value"),
+                    Some(&[EntryTag::Encrypted("t1".to_string(), "v1".to_string())]),
+                    None,
+                )
+                .await
+                .expect("Error inserting test row");
+            }
+            sess.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name1",
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect_err("Expected duplicate entry error");
+            sess.close(true).await.expect("Error closing session");
+            store.close().await.expect("Error closing store");
+
+            // the name encryption is recorded by the store and applies after rekeying
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(None, key.as_ref(), None)
+                .await
+                .expect("Error opening store");
+            store
+                .rekey_profile(profile)
+                .await
+                .expect("Error rekeying profile");
+            let mut sess = store.session(None, false).expect("Error starting session");
+            let row = sess
+                .fetch(EntryKind::Item, "category", "name2", false)
+                .await
+                .expect("Error fetching test row")
+                .expect("Expected test row");
+            assert_eq!(row.name, "name2");
+            assert_eq!(row.value, &b"value"[..]);
+            for category in [None, Some("category")] {
+                let mut rows = sess
+                    .fetch_all(
+                        Some(EntryKind::Item),
+                        category,
+                        Some(TagFilter::is_eq("t1", "v1")),
+                        None,
+                        None,
+                        false,
+                        false,
+                    )
+                    .await
+                    .expect("Error fetching test rows");
+                rows.sort_by(|a, b| a.name.cmp(&b.name));
+                assert_eq!(rows.len(), 2);
+                assert_eq!(rows[0].category, "category");
+                assert_eq!(rows[0].name, "name1");
+                assert_eq!(rows[1].name, "name2");
+            }
+            sess.update(
+                EntryKind::Item,
+                EntryOperation::Remove,
+                "category",
+                "name1",
+                None,
+                None,
+                None,
+            )
+            .await
+            .expect("Error removing test row");
+            assert_eq!(
+                sess.count(Some(EntryKind::Item), Some("category"), None)
+                    .await
+                    .expect("Error counting test rows"),
+                1
+            );
+            sess.close(true).await.expect("Error closing session");
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn bound_values() {
+        use sqlx::sqlite::SqlitePool;
+
+        log_init();
+        let fname = format!("sqlite-bound-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let mut sess = store.session(None, false).expect("Error starting session");
+            for (kind, value) in [(EntryKind::Kms, b"key"), (EntryKind::Item, b"val")] {
+                sess.update(
+                    kind,
+                    EntryOperation::Insert,
+                    "category",
+                    "name",
+                    Some(value),
+                    None,
+                    None,
+                )
+                .await
+                .expect("Error inserting test row");
+            }
+            sess.close(true).await.expect("Error closing session");
+
+            // a value copied between records of a different kind is refused
+            let pool = SqlitePool::connect(format!("sqlite://{}", fname).as_str())
+                .await
+                .expect("Error connecting to database");
+            sqlx::query(
+                "UPDATE items SET value = (SELECT value FROM items WHERE kind = ?1)
+                WHERE kind = ?2",
+            )
+            .bind(EntryKind::Kms as i16)
+            .bind(EntryKind::Item as i16)
+            .execute(&pool)
+            .await
+            .expect("Error updating test row");
+            pool.close().await;
+
+            let mut sess = store.session(None, false).expect("Error starting session");
+            let err = sess
+                .fetch(EntryKind::Item, "category", "name", false)
+                .await
+                .expect_err("Expected spliced value to be refused");
+            assert_eq!(err.kind(), ErrorKind::Encryption);
+            let row = sess
+                .fetch(EntryKind::Kms, "category", "name", false)
+                .await
+                .expect("Error fetching test row")
+                .expect("Expected test row");
+            assert_eq!(row.value, &b"key"[..]);
+            sess.close(false).await.expect("Error closing session");
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn encrypted_expiry() {
+        use sqlx::sqlite::SqlitePool;
+
+        log_init();
+        let fname = format!("sqlite-expiry-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        SqliteStoreOptions::new(format!("{}?expiry_bucket=0", fname).as_str())
+            .expect_err("Expected invalid expiry bucket error");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(format!("{}?expiry_bucket=3600", fname).as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let profile = store.get_active_profile();
+            let mut sess = store.session(None, false).expect("Error starting session");
+            for (name, expiry_ms) in [("short", 1), ("long", 60_000)] {
+                sess.update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    name,
+                    Some(b"value"),
+                    None,
+                    Some(expiry_ms),
+                )
+                .await
+                .expect("Error inserting test row");
+            }
+            sess.close(true).await.expect("Error closing session");
+            std::thread::sleep(std::time::Duration::from_millis(50));
+
+            // only the bucket of the expiry time is stored in plaintext
+            let pool = SqlitePool::connect(format!("sqlite://{}", fname).as_str())
+                .await
+                .expect("Error connecting to database");
+            let rows: Vec<(i64, Option<Vec<u8>>)> = sqlx::query_as(
+                "SELECT CAST(STRFTIME('%s', expiry) AS INTEGER) % 3600, enc_expiry FROM items",
+            )
+            .fetch_all(&pool)
+            .await
+            .expect("Error fetching test rows");
+            pool.close().await;
+            assert_eq!(rows.len(), 2);
+            for (offset, enc_expiry) in rows {
+                assert_eq!(offset, 0);
+                assert!(enc_expiry.is_some());
+            }
+
+            store
+                .rekey_profile(profile)
+                .await
+                .expect("Error rekeying profile");
+            let mut sess = store.session(None, false).expect("Error starting session");
+            assert!(sess
+                .fetch(EntryKind::Item, "category", "short", false)
+                .await
+                .expect("Error fetching test row")
+                .is_none());
+            assert!(sess
+                .fetch(EntryKind::Item, "category", "long", false)
+                .await
+                .expect("Error fetching test row")
+                .is_some());
+            // scans filter on the bucketed expiry time, consistently with
+            // their offset and limit
+            let rows = sess
+                .fetch_all(
+                    Some(EntryKind::Item),
+                    Some("category"),
+                    None,
+                    None,
+                    None,
+                    false,
+                    false,
+                )
+                .await
+                .expect("Error fetching test rows");
+            assert_eq!(rows.len(), 2);
+            let mut scan = store
+                .scan(
+                    None,
+                    Some(EntryKind::Item),
+                    Some("category".to_string()),
+                    None,
+                    Some(1),
+                    Some(1),
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error starting scan");
+            let rows = scan
+                .fetch_next()
+                .await
+                .expect("Error fetching scan rows")
+                .expect("Expected scan rows");
+            assert_eq!(rows.len(), 1);
+            sess.close(false).await.expect("Error closing session");
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn strict_key_resolution() {
+        use sqlx::sqlite::SqlitePool;
+
+        log_init();
+        let fname = format!("sqlite-strict-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        SqliteStoreOptions::new(format!("{}?strict_key_resolution=maybe", fname).as_str())
+            .expect_err("Expected invalid strict_key_resolution error");
+
+        block_on(async move {
+            let store =
+                SqliteStoreOptions::new(format!("{}?strict_key_resolution=true", fname).as_str())
+                    .expect("Error initializing sqlite store options")
+                    .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                    .await
+                    .expect("Error provisioning sqlite store");
+            let default_profile = store.get_active_profile();
+            for name in ["other", "broken"] {
+                store
+                    .create_profile(Some(name.to_string()))
+                    .await
+                    .expect("Error creating profile");
+            }
+            let mut sess = store
+                .session(Some("other".to_string()), false)
+                .expect("Error starting session");
+            sess.count(Some(EntryKind::Item), None, None)
+                .await
+                .expect("Error resolving profile");
+            sess.close(false).await.expect("Error closing session");
+
+            // make the profile name ambiguous and the profile key undecryptable
+            let pool = SqlitePool::connect(format!("sqlite://{}", fname).as_str())
+                .await
+                .expect("Error connecting to database");
+            sqlx::query(
+                "INSERT INTO profile_aliases (alias, profile_id)
+                SELECT 'other', id FROM profiles WHERE name = ?1",
+            )
+            .bind(&default_profile)
+            .execute(&pool)
+            .await
+            .expect("Error inserting profile alias");
+            sqlx::query("UPDATE profiles SET profile_key = X'00' WHERE name = 'broken'")
+                .execute(&pool)
+                .await
+                .expect("Error updating profile key");
+            pool.close().await;
+
+            // the cached profile key is not used
+            for name in ["other", "broken"] {
+                let mut sess = store
+                    .session(Some(name.to_string()), false)
+                    .expect("Error starting session");
+                sess.count(Some(EntryKind::Item), None, None)
+                    .await
+                    .expect_err("Expected profile resolution error");
+                sess.close(false).await.expect("Error closing session");
+            }
+            let mut sess = store
+                .session(Some("missing".to_string()), false)
+                .expect("Error starting session");
+            let err = sess
+                .count(Some(EntryKind::Item), None, None)
+                .await
+                .expect_err("Expected profile not found error");
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+            sess.close(false).await.expect("Error closing session");
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn kdf_upgrade() {
+        log_init();
+        let fname = format!("sqlite-kdf-upgrade-{}.db", uuid::Uuid::new_v4());
+        let weak_method = StoreKeyMethod::parse_uri("kdf:argon2i:int").unwrap();
+        let strong_method = StoreKeyMethod::parse_uri("kdf:argon2i:mod").unwrap();
+        let int_level = KdfMethod::Argon2i(Argon2Level::Interactive);
+        let mod_level = KdfMethod::Argon2i(Argon2Level::Moderate);
+
+        block_on(async move {
+            let mut store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(weak_method.clone(), "pass".into(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let other = store
+                .create_profile(None)
+                .await
+                .expect("Error creating profile");
+            let mut conn = store
+                .session(Some(other.clone()), false)
+                .expect("Error starting session");
+            conn.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name",
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect("Error inserting test row");
+            drop(conn);
+            let stale = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(weak_method.clone()), "pass".into(), None)
+                .await
+                .expect("Error opening sqlite store");
+
+            // the key derivation is unchanged when it is not weaker than the target
+            assert!(!store
+                .upgrade_kdf(int_level, "pass".into())
+                .await
+                .expect("Error upgrading store key"));
+            let err = store
+                .upgrade_kdf(mod_level, "other".into())
+                .await
+                .expect_err("Expected pass key mismatch");
+            assert_eq!(err.kind(), ErrorKind::Encryption);
+            assert!(store
+                .upgrade_kdf(mod_level, "pass".into())
+                .await
+                .expect("Error upgrading store key"));
+            let mut conn = store
+                .session(Some(other.clone()), false)
+                .expect("Error starting session");
+            let found = conn
+                .fetch(EntryKind::Item, "category", "name", false)
+                .await
+                .expect("Error fetching test row");
+            assert_eq!(found.map(|e| e.value), Some(b"value".to_vec().into()));
+            drop(conn);
+            store
+                .create_profile(None)
+                .await
+                .expect("Error creating profile");
+            store.close().await.expect("Error closing store");
+
+            // an instance holding the previous store key may not wrap profile keys
+            let err = stale
+                .create_profile(None)
+                .await
+                .expect_err("Expected stale store key error");
+            assert_eq!(err.kind(), ErrorKind::Busy);
+            stale.close().await.expect("Error closing store");
+
+            // the store key is now derived using the stronger method
+            let err = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(weak_method), "pass".into(), None)
+                .await
+                .expect_err("Expected store key method mismatch");
+            assert_eq!(err.kind(), ErrorKind::Input);
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(strong_method), "pass".into(), None)
+                .await
+                .expect("Error opening sqlite store");
+            let mut conn = store
+                .session(Some(other), false)
+                .expect("Error starting session");
+            let count = conn
+                .count(Some(EntryKind::Item), None, None)
+                .await
+                .expect("Error counting test rows");
+            assert_eq!(count, 1);
+            drop(conn);
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn kdf_upgrade_rekey_pending() {
+        log_init();
+        let fname = format!("sqlite-kdf-upgrade-pending-{}.db", uuid::Uuid::new_v4());
+        let weak_method = StoreKeyMethod::parse_uri("kdf:argon2i:int").unwrap();
+
+        block_on(async move {
+            let mut store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(weak_method.clone(), "pass".into(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            for _ in 0..2 {
+                store
+                    .create_profile(None)
+                    .await
+                    .expect("Error creating profile");
+            }
+            store
+                .rekey_chunked(weak_method.clone(), "new".into(), 1, &mut |_| {
+                    Err(ErrorKind::Custom.into())
+                })
+                .await
+                .expect_err("Expected re-key to be interrupted");
+
+            let err = store
+                .upgrade_kdf(KdfMethod::Argon2i(Argon2Level::Moderate), "pass".into())
+                .await
+                .expect_err("Expected pending re-key error");
+            assert_eq!(err.kind(), ErrorKind::Busy);
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn shred_entry() {
+        use sqlx::sqlite::SqlitePool;
+
+        log_init();
+        let fname = format!("sqlite-shred-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let mut sess = store.session(None, false).expect("Error starting session");
+            let value = vec![7u8; 64 * 1024];
+            for name in ["shred", "keep"] {
+                sess.update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    name,
+                    Some(&value),
+                    Some(&[EntryTag::Encrypted("t1".to_string(), "v1".to_string())]),
+                    None,
+                )
+                .await
+                .expect("Error inserting test row");
+            }
+            sess.shred(EntryKind::Item, "category", "shred")
+                .await
+                .expect("Error shredding test row");
+            let err = sess
+                .shred(EntryKind::Item, "category", "shred")
+                .await
+                .expect_err("Expected missing entry error");
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+            assert!(sess
+                .fetch(EntryKind::Item, "category", "shred", false)
+                .await
+                .expect("Error fetching test row")
+                .is_none());
+            let row = sess
+                .fetch(EntryKind::Item, "category", "keep", true)
+                .await
+                .expect("Error fetching test row")
+                .expect("Expected test row");
+            assert_eq!(row.value, value);
+            assert_eq!(row.tags.len(), 1);
+            sess.close(false).await.expect("Error closing session");
+
+            // the pages freed by the removal are returned to the file system
+            let pool = SqlitePool::connect(format!("sqlite://{}", fname).as_str())
+                .await
+                .expect("Error connecting to database");
+            let free_pages: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+                .fetch_one(&pool)
+                .await
+                .expect("Error fetching free page count");
+            pool.close().await;
+            assert_eq!(free_pages, 0);
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn copy_db() {
+        log_init();
+        let fname_source = format!("sqlite-copy-{}.db", uuid::Uuid::new_v4());
+        let url_target = format!("sqlite://sqlite-copy-{}.db", uuid::Uuid::new_v4());
+        let key_source = generate_raw_store_key(None).expect("Error creating raw key");
+        let key_target = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let source = SqliteStoreOptions::new(fname_source.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key_source.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            let profile = source
+                .get_default_profile()
+                .await
+                .expect("Error fetching default profile");
+
+            let target = copy_store(
+                &source,
+                url_target.as_str(),
+                StoreKeyMethod::RawKey,
+                key_target.as_ref(),
+                false,
+            )
+            .await
+            .expect("Error copying store");
+            target.close().await.expect("Error closing copied store");
+
+            source.close().await.expect("Error closing store");
+            SqliteStoreOptions::new(fname_source.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+
+            let copied = SqliteStoreOptions::new(url_target.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key_target.as_ref(), None)
+                .await
+                .expect("Error opening copied store");
+            assert_eq!(copied.get_active_profile(), profile);
+            copied.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(url_target.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn txn_contention_file() {
+        log_init();
+        let fname = format!("sqlite-contention-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, true)
+                .await
+                .expect("Error provisioning sqlite store");
+
+            let db = into_any_backend(store);
+            super::utils::db_txn_contention(db.clone()).await;
+            db.close().await.expect("Error closing sqlite store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        });
+    }
+
+    #[cfg(feature = "stress_test")]
+    #[test]
+    fn stress_test() {
+        log_init();
+        use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+        use std::str::FromStr;
+        let conn_opts = SqliteConnectOptions::from_str("sqlite:test.db")
+            .unwrap()
+            .create_if_missing(true);
+        // .shared_cache(true);
+        block_on(async move {
+            let pool = SqlitePoolOptions::default()
+                // maintains at least 1 connection.
+                // for an in-memory database this is required to avoid dropping the database,
+                // for a file database this signals other instances that the database is in use
+                .min_connections(1)
+                .max_connections(5)
+                .test_before_acquire(false)
+                .connect_with(conn_opts)
+                .await
+                .unwrap();
+
+            let mut conn = pool.begin().await.unwrap();
+            sqlx::query("CREATE TABLE test (name TEXT)")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO test (name) VALUES ('test')")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            conn.commit().await.unwrap();
+
+            const TASKS: usize = 25;
+            const COUNT: usize = 1000;
+
+            async fn fetch(pool: SqlitePool) -> Result<(), &'static str> {
+                // try to avoid panics in this section, as they will be raised on a tokio worker thread
+                for _ in 0..COUNT {
+                    let mut txn = pool.acquire().await.expect("Acquire error");
+                    sqlx::query("BEGIN IMMEDIATE")
+                        .execute(&mut txn)
+                        .await
+                        .expect("Transaction error");
+                    let _ = sqlx::query("SELECT * FROM test")
+                        .fetch_one(&mut txn)
+                        .await
+                        .expect("Error fetching row");
+                    sqlx::query("COMMIT")
+                        .execute(&mut txn)
+                        .await
+                        .expect("Commit error");
+                }
+                Ok(())
+            }
+
+            let mut tasks = vec![];
+            for _ in 0..TASKS {
+                tasks.push(tokio::spawn(fetch(pool.clone())));
+            }
+
+            for task in tasks {
+                if let Err(s) = task.await.unwrap() {
+                    panic!("Error in concurrent update task: {}", s);
+                }
+            }
+        });
+    }
+
+    fn with_sqlite_in_memory<F, G>(f: F)
+    where
+        F: FnOnce(AnyBackend) -> G,
+        G: Future<Output = ()>,
+    {
+        log_init();
+        let key = generate_raw_store_key(None).expect("Error generating store key");
+        block_on(async move {
+            let db = into_any_backend(
+                SqliteStoreOptions::in_memory()
+                    .provision(StoreKeyMethod::RawKey, key, None, false)
+                    .await
                     .expect("Error provisioning sqlite store"),
             );
             f(db.clone()).await;
@@ -444,3 +2529,36 @@ mod postgres {
 
     backend_tests!(with_postgres);
 }
+
+#[cfg(feature = "pg_test")]
+mod postgres_tenant_schemas {
+    use askar_storage::any::AnyBackend;
+    use askar_storage::backend::postgres::TestDB;
+    use askar_storage::future::block_on;
+    use std::future::Future;
+
+    use super::*;
+
+    fn with_postgres<F, G>(f: F)
+    where
+        F: FnOnce(AnyBackend) -> G,
+        G: Future<Output = ()>,
+    {
+        let db_url = match std::env::var("POSTGRES_URL") {
+            Ok(p) if !p.is_empty() => p,
+            _ => panic!("'POSTGRES_URL' must be defined"),
+        };
+        let sep = if db_url.contains('?') { '&' } else { '?' };
+        let db_url = format!("{}{}tenant_isolation=schema", db_url, sep);
+        log_init();
+        block_on(async move {
+            let db = TestDB::provision(db_url.as_str())
+                .await
+                .expect("Error provisioning postgres test database");
+            f(db.backend()).await;
+            db.close().await.expect(ERR_CLOSE);
+        })
+    }
+
+    backend_tests!(with_postgres);
+}
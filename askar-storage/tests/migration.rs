@@ -2,24 +2,30 @@
 
 use std::path::PathBuf;
 
+use askar_storage::backend::sqlite::SqliteStoreOptions;
+use askar_storage::backend::{Backend, BackendSession, ManageBackend};
 use askar_storage::future::block_on;
 use askar_storage::migration::IndySdkToAriesAskarMigration;
-use askar_storage::Error;
+use askar_storage::{generate_raw_store_key, Error, StoreKeyMethod};
 
 const DB_TEMPLATE_PATH: &str = "./tests/indy_wallet_sqlite.db";
 const DB_UPGRADE_PATH: &str = "./tests/indy_wallet_sqlite_upgraded.db";
+const DB_IMPORT_PATH: &str = "./tests/indy_wallet_sqlite_import.db";
+
+const WALLET_NAME: &str = "walletwallet.0";
+const WALLET_KEY: &str = "GfwU1DC7gEZNs3w41tjBiZYj7BNToDoFEqKY6wZXqs1A";
 
 /// Create a copy of the input DB for migration
-fn prepare_db() {
+fn prepare_db(path: &str) {
     let tpl_paths = [
         PathBuf::from(DB_TEMPLATE_PATH),
         PathBuf::from(format!("{}-shm", DB_TEMPLATE_PATH)),
         PathBuf::from(format!("{}-wal", DB_TEMPLATE_PATH)),
     ];
     let upd_paths = [
-        PathBuf::from(DB_UPGRADE_PATH),
-        PathBuf::from(format!("{}-shm", DB_UPGRADE_PATH)),
-        PathBuf::from(format!("{}-wal", DB_UPGRADE_PATH)),
+        PathBuf::from(path),
+        PathBuf::from(format!("{}-shm", path)),
+        PathBuf::from(format!("{}-wal", path)),
     ];
     for (tpl, upd) in tpl_paths.iter().zip(upd_paths) {
         if tpl.exists() {
@@ -32,13 +38,11 @@ fn prepare_db() {
 
 #[test]
 fn test_sqlite_migration() {
-    prepare_db();
+    prepare_db(DB_UPGRADE_PATH);
 
     let res = block_on(async {
-        let wallet_name = "walletwallet.0";
-        let wallet_key = "GfwU1DC7gEZNs3w41tjBiZYj7BNToDoFEqKY6wZXqs1A";
         let migrator =
-            IndySdkToAriesAskarMigration::connect(DB_UPGRADE_PATH, wallet_name, wallet_key, "RAW")
+            IndySdkToAriesAskarMigration::connect(DB_UPGRADE_PATH, WALLET_NAME, WALLET_KEY, "RAW")
                 .await?;
         migrator.migrate().await?;
         Result::<_, Error>::Ok(())
@@ -47,3 +51,81 @@ fn test_sqlite_migration() {
     // We still need some indication if something returned with an error
     res.expect("Migration failed");
 }
+
+#[test]
+fn test_sqlite_migration_into_store() {
+    prepare_db(DB_IMPORT_PATH);
+
+    block_on(async {
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+        let store = SqliteStoreOptions::in_memory()
+            .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+            .await
+            .expect("Error provisioning target store");
+
+        let migrator =
+            IndySdkToAriesAskarMigration::connect(DB_IMPORT_PATH, WALLET_NAME, "other", "RAW")
+                .await
+                .expect("Error opening wallet");
+        migrator
+            .migrate_into(&store, None, false, None)
+            .await
+            .expect_err("Expected wallet key mismatch");
+
+        let migrator =
+            IndySdkToAriesAskarMigration::connect(DB_IMPORT_PATH, WALLET_NAME, WALLET_KEY, "RAW")
+                .await
+                .expect("Error opening wallet");
+        let dry_run_count = migrator
+            .migrate_into(&store, None, true, None)
+            .await
+            .expect("Error validating wallet import");
+        let mut session = store.session(None, false).expect("Error starting session");
+        assert_eq!(
+            session
+                .count(None, None, None)
+                .await
+                .expect("Error counting records"),
+            0
+        );
+        drop(session);
+
+        let migrator =
+            IndySdkToAriesAskarMigration::connect(DB_IMPORT_PATH, WALLET_NAME, WALLET_KEY, "RAW")
+                .await
+                .expect("Error opening wallet");
+        let count = migrator
+            .migrate_into(&store, None, false, None)
+            .await
+            .expect("Error importing wallet");
+        assert_eq!(count, dry_run_count);
+        let mut session = store.session(None, false).expect("Error starting session");
+        assert_eq!(
+            session
+                .count(None, None, None)
+                .await
+                .expect("Error counting records"),
+            count as i64
+        );
+        drop(session);
+        store.close().await.expect("Error closing store");
+
+        // the source wallet is unchanged and may be imported again
+        let migrator =
+            IndySdkToAriesAskarMigration::connect(DB_IMPORT_PATH, WALLET_NAME, WALLET_KEY, "RAW")
+                .await
+                .expect("Error opening wallet");
+        assert_eq!(
+            migrator
+                .migrate_dry_run()
+                .await
+                .expect("Error validating wallet migration"),
+            count
+        );
+        let migrator =
+            IndySdkToAriesAskarMigration::connect(DB_IMPORT_PATH, WALLET_NAME, WALLET_KEY, "RAW")
+                .await
+                .expect("Error opening wallet");
+        migrator.migrate().await.expect("Error migrating wallet");
+    });
+}
@@ -1,5 +1,10 @@
 use askar_storage::{
     any::AnyBackend,
+    backend::{
+        clone_profile, import_profile, ConflictResolution, ExpiryRule, ImportConflict,
+        ImportStrategy, ProfileExpiryPolicy, ProfileFilter, ProfileQuota, ProfileStats, Progress,
+        ProgressPhase,
+    },
     entry::{Entry, EntryKind, EntryOperation, EntryTag, TagFilter},
     Backend, BackendSession, ErrorKind,
 };
@@ -99,6 +104,125 @@ pub async fn db_insert_fetch(db: AnyBackend) {
     assert_eq!(rows[0], test_row);
 }
 
+pub async fn db_fetch_without_tags(db: AnyBackend) {
+    let test_row = Entry::new(
+        EntryKind::Item,
+        "category",
+        "name",
+        "value",
+        vec![
+            EntryTag::Encrypted("t1".to_string(), "v1".to_string()),
+            EntryTag::Plaintext("t2".to_string(), "v2".to_string()),
+        ],
+    );
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    let row = conn
+        .fetch_without_tags(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row.value, test_row.value);
+    assert!(row.tags.is_empty());
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row, test_row);
+}
+
+pub async fn db_insert_unencrypted(db: AnyBackend) {
+    let test_row = Entry::new(
+        EntryKind::Item,
+        "category",
+        "name",
+        "value",
+        vec![EntryTag::Encrypted("t1".to_string(), "v1".to_string())],
+    );
+    let profile = db.get_active_profile();
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    conn.update_unencrypted(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        &test_row.value,
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row, test_row);
+
+    // removal is only supported by the regular update
+    let err = conn
+        .update_unencrypted(
+            EntryKind::Item,
+            EntryOperation::Remove,
+            &test_row.category,
+            &test_row.name,
+            &[],
+            None,
+            None,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+
+    let test_row = Entry::new(EntryKind::Item, "category", "name", "new value", Vec::new());
+    conn.update_unencrypted(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        &test_row.category,
+        &test_row.name,
+        &test_row.value,
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_REPLACE);
+    drop(conn);
+
+    db.rekey_profile(profile)
+        .await
+        .expect("Error rekeying profile");
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    let rows = conn
+        .fetch_all(
+            Some(EntryKind::Item),
+            Some(&test_row.category),
+            None,
+            None,
+            None,
+            false,
+            false,
+        )
+        .await
+        .expect(ERR_FETCH_ALL);
+    assert_eq!(rows, vec![test_row]);
+}
+
 pub async fn db_insert_duplicate(db: AnyBackend) {
     let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
 
@@ -519,6 +643,99 @@ pub async fn db_scan(db: AnyBackend) {
     assert_eq!(rows, None);
 }
 
+pub async fn db_category_namespace(db: AnyBackend) {
+    let categories = [
+        "credential/w3c",
+        "credential/w3c/driverlicense",
+        "credential/w3c/passport",
+        "credential/w3cx",
+        "credential/anoncreds",
+        "other",
+    ];
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    for (idx, category) in categories.iter().enumerate() {
+        let tags = vec![EntryTag::Encrypted(
+            "even".to_string(),
+            (idx % 2 == 0).to_string(),
+        )];
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            category,
+            "name",
+            Some(b"value"),
+            Some(tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+    // the namespace index is retained when replacing a record
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        "credential/w3c/passport",
+        "name",
+        Some(b"replaced"),
+        Some(&[EntryTag::Encrypted("even".to_string(), "true".to_string())]),
+        None,
+    )
+    .await
+    .expect(ERR_REPLACE);
+
+    let count = conn
+        .count_namespace(Some(EntryKind::Item), "credential/w3c", None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 3);
+    let count = conn
+        .count_namespace(Some(EntryKind::Item), "credential", None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 5);
+    let count = conn
+        .count_namespace(
+            Some(EntryKind::Item),
+            "credential/w3c",
+            Some(TagFilter::is_eq("even", "true")),
+        )
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 2);
+    let count = conn
+        .count_namespace(Some(EntryKind::Item), "credential/w3c/other", None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 0);
+    let err = conn
+        .count_namespace(Some(EntryKind::Item), "", None)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+    drop(conn);
+
+    let mut scan = db
+        .scan_namespace(
+            None,
+            Some(EntryKind::Item),
+            "credential/w3c".to_string(),
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+        .await
+        .expect(ERR_SCAN);
+    let mut found = Vec::new();
+    while let Some(rows) = scan.fetch_next().await.expect(ERR_SCAN_NEXT) {
+        found.extend(rows.into_iter().map(|row| row.category));
+    }
+    found.sort();
+    assert_eq!(found, &categories[..3]);
+}
+
 pub async fn db_remove_all(db: AnyBackend) {
     let test_rows = vec![
         Entry::new(
@@ -585,6 +802,138 @@ pub async fn db_remove_all(db: AnyBackend) {
     assert_eq!(removed, 2);
 }
 
+pub async fn db_rekey_profile(db: AnyBackend) {
+    let test_row = Entry::new(
+        EntryKind::Item,
+        "category",
+        "name",
+        "value",
+        vec![
+            EntryTag::Encrypted("t1".to_string(), "v1".to_string()),
+            EntryTag::Plaintext("t2".to_string(), "v2".to_string()),
+        ],
+    );
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    let profile = db.get_active_profile();
+    db.rekey_profile(profile)
+        .await
+        .expect("Error rekeying profile");
+    db.rekey_profile("not a profile".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row, test_row);
+    let rows = conn
+        .fetch_all(
+            Some(EntryKind::Item),
+            Some(&test_row.category),
+            Some(TagFilter::all_of(vec![
+                TagFilter::is_eq("t1", "v1"),
+                TagFilter::is_eq("~t2", "v2"),
+            ])),
+            None,
+            None,
+            false,
+            false,
+        )
+        .await
+        .expect(ERR_FETCH_ALL);
+    assert_eq!(rows, vec![test_row]);
+
+    // the category namespace index is computed using the new profile key
+    let count = conn
+        .count_namespace(Some(EntryKind::Item), "category", None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 1);
+}
+
+pub async fn db_rotate_tag_key(db: AnyBackend) {
+    let test_rows = (0..3)
+        .map(|idx| {
+            Entry::new(
+                EntryKind::Item,
+                "category",
+                format!("name-{}", idx),
+                "value",
+                vec![
+                    EntryTag::Encrypted("t1".to_string(), format!("v{}", idx % 2)),
+                    EntryTag::Plaintext("t2".to_string(), "v2".to_string()),
+                ],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    for row in test_rows.iter() {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &row.category,
+            &row.name,
+            Some(&row.value),
+            Some(row.tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+    drop(conn);
+
+    let profile = db.get_active_profile();
+    db.rotate_tag_key(profile)
+        .await
+        .expect("Error rotating tag key");
+    db.rotate_tag_key("not a profile".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    let mut rows = conn
+        .fetch_all(
+            Some(EntryKind::Item),
+            Some("category"),
+            Some(TagFilter::all_of(vec![
+                TagFilter::is_eq("t1", "v0"),
+                TagFilter::is_eq("~t2", "v2"),
+            ])),
+            None,
+            None,
+            false,
+            false,
+        )
+        .await
+        .expect(ERR_FETCH_ALL);
+    rows.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(rows, vec![test_rows[0].clone(), test_rows[2].clone()]);
+    let row = conn
+        .fetch(EntryKind::Item, "category", "name-1", false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row, test_rows[1]);
+}
+
 pub async fn db_txn_rollback(db: AnyBackend) {
     let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
 
@@ -836,24 +1185,846 @@ pub async fn db_txn_contention(db: AnyBackend) {
     assert_eq!(row.value, format!("{}", TASKS * INC).as_bytes());
 }
 
-pub async fn db_list_profiles(db: AnyBackend) {
-    let p_active = db.get_active_profile();
-    assert_eq!(vec![p_active.clone()], db.list_profiles().await.unwrap());
+pub async fn db_ensure_profile(db: AnyBackend) {
+    const TASKS: usize = 8;
 
-    let p_new = db.create_profile(None).await.unwrap();
-    let mut profs = vec![p_active, p_new];
-    profs.sort();
-    let mut found = db.list_profiles().await.unwrap();
+    let mut tasks = vec![];
+    for _ in 0..TASKS {
+        let db = db.clone();
+        tasks.push(spawn(async move {
+            db.ensure_profile("shared".to_string()).await
+        }));
+    }
+    let mut created = 0;
+    for task in tasks {
+        if task.await.unwrap().expect("Error ensuring profile") {
+            created += 1;
+        }
+    }
+    assert_eq!(created, 1);
+    assert!(!db
+        .ensure_profile("shared".to_string())
+        .await
+        .expect("Error ensuring profile"));
+
+    let mut conn = db
+        .session(Some("shared".to_string()), false)
+        .expect(ERR_SESSION);
+    assert_eq!(conn.count(None, None, None).await.expect(ERR_COUNT), 0);
+    drop(conn);
+
+    let err = db
+        .create_profile(Some("shared".to_string()))
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+}
+
+pub async fn db_list_profiles(db: AnyBackend) {
+    let p_active = db.get_active_profile();
+    assert_eq!(vec![p_active.clone()], db.list_profiles().await.unwrap());
+
+    let p_new = db.create_profile(None).await.unwrap();
+    let mut profs = vec![p_active, p_new];
+    profs.sort();
+    let mut found = db.list_profiles().await.unwrap();
     found.sort();
     assert_eq!(profs, found);
 }
 
-pub async fn db_get_set_default_profile(db: AnyBackend) {
-    let p_default = db.get_default_profile().await.unwrap();
-    let p_new = db.create_profile(None).await.unwrap();
-    assert_ne!(p_new, p_default);
-    db.set_default_profile(p_new.clone()).await.unwrap();
-    assert_eq!(db.get_default_profile().await.unwrap(), p_new);
+pub async fn db_get_set_default_profile(db: AnyBackend) {
+    let p_default = db.get_default_profile().await.unwrap();
+    let p_new = db.create_profile(None).await.unwrap();
+    assert_ne!(p_new, p_default);
+    db.set_default_profile(p_new.clone()).await.unwrap();
+    assert_eq!(db.get_default_profile().await.unwrap(), p_new);
+}
+
+pub async fn db_list_profiles_page(db: AnyBackend) {
+    let mut profs = vec![db.get_active_profile()];
+    for _ in 0..4 {
+        profs.push(db.create_profile(None).await.expect(ERR_PROFILE));
+    }
+    profs.sort();
+    assert_eq!(db.list_profiles_page(0, 2).await.unwrap(), profs[..2]);
+    assert_eq!(db.list_profiles_page(2, 2).await.unwrap(), profs[2..4]);
+    assert_eq!(db.list_profiles_page(4, 2).await.unwrap(), profs[4..]);
+    assert!(db.list_profiles_page(5, 2).await.unwrap().is_empty());
+}
+
+pub async fn db_rename_profile(db: AnyBackend) {
+    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+
+    let profile = db.create_profile(None).await.expect(ERR_PROFILE);
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+    db.set_default_profile(profile.clone()).await.unwrap();
+
+    assert!(db
+        .rename_profile(profile.clone(), "renamed".to_string())
+        .await
+        .expect("Error renaming profile"));
+    assert_eq!(db.get_default_profile().await.unwrap(), "renamed");
+    let mut conn = db
+        .session(Some("renamed".to_string()), false)
+        .expect(ERR_SESSION);
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row, test_row);
+    drop(conn);
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    conn.ping()
+        .await
+        .expect_err("Expected connection to renamed profile to fail");
+    drop(conn);
+
+    assert!(!db
+        .rename_profile(profile, "other".to_string())
+        .await
+        .expect("Error renaming profile"));
+    let err = db
+        .rename_profile("renamed".to_string(), db.get_active_profile())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+    let err = db
+        .rename_profile(db.get_active_profile(), "other".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+}
+
+pub async fn db_profile_metadata(db: AnyBackend) {
+    let profile = db.get_active_profile();
+    assert_eq!(
+        db.get_profile_metadata(profile.clone()).await.unwrap(),
+        None
+    );
+    db.set_profile_metadata(profile.clone(), Some("{\"tenant\":1}".to_string()))
+        .await
+        .expect("Error setting profile metadata");
+    assert_eq!(
+        db.get_profile_metadata(profile.clone()).await.unwrap(),
+        Some("{\"tenant\":1}".to_string())
+    );
+    db.set_profile_metadata(profile.clone(), None)
+        .await
+        .expect("Error clearing profile metadata");
+    assert_eq!(db.get_profile_metadata(profile).await.unwrap(), None);
+
+    let err = db
+        .get_profile_metadata("not a profile".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let err = db
+        .set_profile_metadata("not a profile".to_string(), None)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_remove_profiles(db: AnyBackend) {
+    let active = db.get_active_profile();
+    for idx in 0..5 {
+        let name = format!("tenant-{}", idx);
+        db.create_profile(Some(name.clone()))
+            .await
+            .expect(ERR_PROFILE);
+        let mut conn = db.session(Some(name.clone()), false).expect(ERR_SESSION);
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            "name",
+            Some(b"value"),
+            None,
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+        conn.close(false).await.expect(ERR_COMMIT);
+        if idx % 2 == 1 {
+            db.set_profile_metadata(name, Some(r#"{"group":"trial"}"#.to_string()))
+                .await
+                .expect("Error setting profile metadata");
+        }
+    }
+    db.create_profile(Some("other".to_string()))
+        .await
+        .expect(ERR_PROFILE);
+    db.set_profile_metadata(
+        "other".to_string(),
+        Some(r#"{"group":"trial"}"#.to_string()),
+    )
+    .await
+    .expect("Error setting profile metadata");
+
+    let filter = ProfileFilter::default()
+        .with_name_prefix("tenant-")
+        .with_metadata("group", "trial");
+    let mut removed = db
+        .remove_profiles(filter, 100, None)
+        .await
+        .expect("Error removing profiles");
+    removed.sort();
+    assert_eq!(removed, vec!["tenant-1", "tenant-3"]);
+
+    let reports = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let handler_reports = reports.clone();
+    let mut removed = db
+        .remove_profiles(
+            ProfileFilter::default().with_name_prefix("tenant-"),
+            2,
+            Some(&move |report: &Progress| handler_reports.lock().unwrap().push(report.clone())),
+        )
+        .await
+        .expect("Error removing profiles");
+    removed.sort();
+    assert_eq!(removed, vec!["tenant-0", "tenant-2", "tenant-4"]);
+    let reports = reports.lock().unwrap().clone();
+    assert!(reports.len() >= 2);
+    assert!(reports
+        .iter()
+        .all(|report| report.phase == ProgressPhase::Remove));
+    assert_eq!(reports.last().unwrap().processed, 3);
+
+    let removed = db
+        .remove_profiles(ProfileFilter::default().with_name_prefix(&active), 10, None)
+        .await
+        .expect("Error removing profiles");
+    assert!(removed.is_empty());
+    let mut profiles = db.list_profiles().await.expect("Error listing profiles");
+    profiles.sort();
+    let mut expected = vec![active, "other".to_string()];
+    expected.sort();
+    assert_eq!(profiles, expected);
+
+    db.create_profile(Some("tenant-0".to_string()))
+        .await
+        .expect(ERR_PROFILE);
+    let mut conn = db
+        .session(Some("tenant-0".to_string()), false)
+        .expect(ERR_SESSION);
+    assert_eq!(conn.count(None, None, None).await.expect(ERR_COUNT), 0);
+    drop(conn);
+
+    let err = db
+        .remove_profiles(ProfileFilter::default(), 10, None)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+}
+
+pub async fn db_profile_quota(db: AnyBackend) {
+    let profile = db.create_profile(None).await.expect(ERR_PROFILE);
+    assert_eq!(
+        db.get_profile_quota(profile.clone()).await.unwrap(),
+        ProfileQuota::default()
+    );
+    let quota = ProfileQuota {
+        max_entries: Some(2),
+        max_value_bytes: None,
+    };
+    db.set_profile_quota(profile.clone(), quota)
+        .await
+        .expect("Error setting profile quota");
+    assert_eq!(db.get_profile_quota(profile.clone()).await.unwrap(), quota);
+
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    for name in ["a", "b"] {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            name,
+            Some(b"value"),
+            None,
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+    let err = conn
+        .update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            "c",
+            Some(b"value"),
+            None,
+            None,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::QuotaExceeded);
+    // replacing an entry does not add to the count
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        "category",
+        "a",
+        Some(b"new value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_REPLACE);
+    // inserting a duplicate entry is not reported as exceeding the quota
+    let err = conn
+        .update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            "b",
+            Some(b"value"),
+            None,
+            None,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+    // removing an entry releases its usage
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        "category",
+        "b",
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Error removing test row");
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "b",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    assert_eq!(
+        conn.count(Some(EntryKind::Item), Some("category"), None)
+            .await
+            .expect(ERR_COUNT),
+        2
+    );
+
+    db.set_profile_quota(
+        profile.clone(),
+        ProfileQuota {
+            max_entries: None,
+            max_value_bytes: Some(150),
+        },
+    )
+    .await
+    .expect("Error setting profile quota");
+    let err = conn
+        .update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            "c",
+            Some(&[0u8; 150]),
+            None,
+            None,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::QuotaExceeded);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "c",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    db.set_profile_quota(profile.clone(), ProfileQuota::default())
+        .await
+        .expect("Error clearing profile quota");
+    let err = db
+        .set_profile_quota(
+            profile.clone(),
+            ProfileQuota {
+                max_entries: Some(-1),
+                max_value_bytes: None,
+            },
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+    let err = db
+        .get_profile_quota("not a profile".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let err = db
+        .set_profile_quota("not a profile".to_string(), ProfileQuota::default())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_profile_stats(db: AnyBackend) {
+    let profile = db.create_profile(None).await.expect(ERR_PROFILE);
+    assert_eq!(
+        db.profile_stats(profile.clone()).await.unwrap(),
+        ProfileStats::default()
+    );
+
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    let tags = [
+        EntryTag::Encrypted("enc".to_string(), "a".to_string()),
+        EntryTag::Plaintext("plain".to_string(), "b".to_string()),
+    ];
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "item",
+        Some(b"value"),
+        Some(&tags),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "expired",
+        Some(b"value"),
+        None,
+        Some(-1000),
+    )
+    .await
+    .expect(ERR_INSERT);
+    conn.update(
+        EntryKind::Kms,
+        EntryOperation::Insert,
+        "key",
+        "key",
+        Some(b"key value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    let stats = db.profile_stats(profile.clone()).await.unwrap();
+    assert_eq!(stats.items, 2);
+    assert_eq!(stats.keys, 1);
+    assert_eq!(stats.expired, 1);
+    assert_eq!(stats.tags, 2);
+    assert!(stats.value_bytes > 0);
+    assert!(stats.storage_bytes > stats.value_bytes);
+
+    // the statistics of other profiles are not affected
+    let other = db.create_profile(None).await.expect(ERR_PROFILE);
+    assert_eq!(
+        db.profile_stats(other).await.unwrap(),
+        ProfileStats::default()
+    );
+
+    let err = db
+        .profile_stats("not a profile".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_profile_archived(db: AnyBackend) {
+    let profile = db.create_profile(None).await.expect(ERR_PROFILE);
+    assert!(db.list_archived_profiles().await.unwrap().is_empty());
+
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "name",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    db.set_profile_archived(profile.clone(), true)
+        .await
+        .expect("Error archiving profile");
+    assert_eq!(
+        db.list_archived_profiles().await.unwrap(),
+        vec![profile.clone()]
+    );
+
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    let row = conn
+        .fetch(EntryKind::Item, "category", "name", false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row.value.as_ref(), b"value");
+    for operation in [
+        EntryOperation::Insert,
+        EntryOperation::Replace,
+        EntryOperation::Remove,
+    ] {
+        let err = conn
+            .update(
+                EntryKind::Item,
+                operation,
+                "category",
+                if operation == EntryOperation::Insert {
+                    "other"
+                } else {
+                    "name"
+                },
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect_err(ERR_REQ_ERR);
+        assert_eq!(err.kind(), ErrorKind::Archived);
+    }
+    let err = conn
+        .remove_all(None, None, None)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Archived);
+    drop(conn);
+
+    db.set_profile_archived(profile.clone(), false)
+        .await
+        .expect("Error restoring profile");
+    assert!(db.list_archived_profiles().await.unwrap().is_empty());
+    let mut conn = db.session(Some(profile), false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        "category",
+        "name",
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Error removing test row");
+    drop(conn);
+
+    let err = db
+        .set_profile_archived("not a profile".to_string(), true)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_clone_profile(db: AnyBackend) {
+    let source = db.create_profile(None).await.expect(ERR_PROFILE);
+    let test_row = Entry::new(
+        EntryKind::Item,
+        "category",
+        "name",
+        "value",
+        vec![
+            EntryTag::Encrypted("t1".to_string(), "v1".to_string()),
+            EntryTag::Plaintext("t2".to_string(), "v2".to_string()),
+        ],
+    );
+    let mut conn = db.session(Some(source.clone()), false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    conn.update(
+        EntryKind::Kms,
+        EntryOperation::Insert,
+        "key",
+        "key",
+        Some(b"key value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    clone_profile(&db, &source, "clone")
+        .await
+        .expect("Error cloning profile");
+
+    let mut conn = db
+        .session(Some("clone".to_string()), false)
+        .expect(ERR_SESSION);
+    let row = conn
+        .fetch(EntryKind::Item, "category", "name", false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row, test_row);
+    let rows = conn
+        .fetch_all(
+            Some(EntryKind::Item),
+            None,
+            Some(TagFilter::is_eq("t1", "v1")),
+            None,
+            None,
+            false,
+            false,
+        )
+        .await
+        .expect(ERR_FETCH_ALL);
+    assert_eq!(rows, vec![test_row]);
+    assert!(conn
+        .fetch(EntryKind::Kms, "key", "key", false)
+        .await
+        .expect(ERR_FETCH)
+        .is_some());
+    drop(conn);
+
+    let err = clone_profile(&db, &source, "clone")
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+    let err = clone_profile(&db, "not a profile", "other")
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(!db.list_profiles().await.unwrap().contains(&"other".into()));
+}
+
+pub async fn db_profile_aliases(db: AnyBackend) {
+    let profile = db.create_profile(None).await.expect(ERR_PROFILE);
+    let other = db.create_profile(None).await.expect(ERR_PROFILE);
+    assert!(db
+        .list_profile_aliases(profile.clone())
+        .await
+        .unwrap()
+        .is_empty());
+
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "name",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    db.add_profile_alias(profile.clone(), "label-b".to_string())
+        .await
+        .expect("Error adding profile alias");
+    db.add_profile_alias(profile.clone(), "label-a".to_string())
+        .await
+        .expect("Error adding profile alias");
+    assert_eq!(
+        db.list_profile_aliases(profile.clone()).await.unwrap(),
+        vec!["label-a".to_string(), "label-b".to_string()]
+    );
+
+    // sessions opened using an alias share the records of the profile
+    let mut conn = db
+        .session(Some("label-a".to_string()), false)
+        .expect(ERR_SESSION);
+    let row = conn
+        .fetch(EntryKind::Item, "category", "name", false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row.value.as_ref(), b"value");
+    drop(conn);
+    let mut conn = db
+        .session(Some("label-b".to_string()), true)
+        .expect(ERR_TRANSACTION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "name2",
+        Some(b"value2"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    conn.close(true).await.expect(ERR_COMMIT);
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    assert_eq!(conn.count(None, None, None).await.expect(ERR_COUNT), 2);
+    drop(conn);
+
+    let err = db
+        .add_profile_alias(other.clone(), "label-a".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+    let err = db
+        .add_profile_alias(other.clone(), profile.clone())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+    let err = db
+        .add_profile_alias("not a profile".to_string(), "label-c".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    assert!(db
+        .remove_profile_alias("label-a".to_string())
+        .await
+        .expect("Error removing profile alias"));
+    assert!(!db
+        .remove_profile_alias("label-a".to_string())
+        .await
+        .expect("Error removing profile alias"));
+    let mut conn = db
+        .session(Some("label-a".to_string()), false)
+        .expect(ERR_SESSION);
+    let err = conn.count(None, None, None).await.expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    drop(conn);
+
+    // aliases are removed along with the profile
+    assert!(db
+        .remove_profile(profile)
+        .await
+        .expect("Error removing profile"));
+    db.add_profile_alias(other.clone(), "label-b".to_string())
+        .await
+        .expect("Error adding profile alias");
+    assert_eq!(
+        db.list_profile_aliases(other).await.unwrap(),
+        vec!["label-b".to_string()]
+    );
+}
+
+pub async fn db_profile_expiry_policy(db: AnyBackend) {
+    let profile = db.create_profile(None).await.expect(ERR_PROFILE);
+    assert!(db
+        .get_profile_expiry_policy(profile.clone())
+        .await
+        .expect("Error fetching expiry policy")
+        .is_empty());
+
+    let policy = ProfileExpiryPolicy {
+        rules: vec![
+            ExpiryRule::new(1)
+                .with_kind(EntryKind::Item)
+                .with_category("message"),
+            ExpiryRule::new(3_600_000).with_category("session"),
+        ],
+    };
+    db.set_profile_expiry_policy(profile.clone(), policy.clone())
+        .await
+        .expect("Error updating expiry policy");
+    assert_eq!(
+        db.get_profile_expiry_policy(profile.clone())
+            .await
+            .expect("Error fetching expiry policy"),
+        policy
+    );
+
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    for (kind, category, name, expiry_ms) in [
+        (EntryKind::Item, "message", "default", None),
+        (EntryKind::Item, "message", "explicit", Some(3_600_000)),
+        (EntryKind::Item, "other", "default", None),
+        (EntryKind::Kms, "message", "default", None),
+        (EntryKind::Kms, "session", "default", None),
+    ] {
+        conn.update(
+            kind,
+            EntryOperation::Insert,
+            category,
+            name,
+            Some(b"value"),
+            None,
+            expiry_ms,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+    drop(conn);
+
+    // expiry timestamps may be compared with a resolution of one second
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let stats = db.profile_stats(profile.clone()).await.unwrap();
+    assert_eq!(stats.expired, 1);
+    let mut conn = db.session(Some(profile.clone()), false).expect(ERR_SESSION);
+    assert!(conn
+        .fetch(EntryKind::Item, "message", "default", false)
+        .await
+        .expect(ERR_FETCH)
+        .is_none());
+    assert!(conn
+        .fetch(EntryKind::Item, "message", "explicit", false)
+        .await
+        .expect(ERR_FETCH)
+        .is_some());
+    drop(conn);
+
+    db.set_profile_expiry_policy(profile.clone(), ProfileExpiryPolicy::default())
+        .await
+        .expect("Error updating expiry policy");
+    assert!(db
+        .get_profile_expiry_policy(profile.clone())
+        .await
+        .expect("Error fetching expiry policy")
+        .is_empty());
+
+    let err = db
+        .set_profile_expiry_policy(
+            profile,
+            ProfileExpiryPolicy {
+                rules: vec![ExpiryRule::new(0)],
+            },
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+    let err = db
+        .get_profile_expiry_policy("not a profile".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
 }
 
 pub async fn db_import_scan(db: AnyBackend) {
@@ -924,3 +2095,188 @@ pub async fn db_import_scan(db: AnyBackend) {
     let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT);
     assert_eq!(rows, None);
 }
+
+pub async fn db_import_conflicts(db: AnyBackend) {
+    async fn insert(db: &AnyBackend, profile: &str, name: &str, value: &str) {
+        let mut conn = db
+            .session(Some(profile.to_string()), false)
+            .expect(ERR_SESSION);
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            name,
+            Some(value.as_bytes()),
+            None,
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+        conn.close(false).await.expect(ERR_COMMIT);
+    }
+
+    async fn fetch_value(db: &AnyBackend, profile: &str, name: &str) -> String {
+        let mut conn = db
+            .session(Some(profile.to_string()), false)
+            .expect(ERR_SESSION);
+        let row = conn
+            .fetch(EntryKind::Item, "category", name, false)
+            .await
+            .expect(ERR_FETCH)
+            .expect(ERR_REQ_ROW);
+        conn.close(false).await.expect(ERR_COMMIT);
+        row.value
+            .as_opt_str()
+            .expect("Expected string value")
+            .to_string()
+    }
+
+    let source = db.create_profile(None).await.expect(ERR_PROFILE);
+    let target = db.create_profile(None).await.expect(ERR_PROFILE);
+    insert(&db, &source, "a", "imported").await;
+    insert(&db, &source, "b", "imported").await;
+    insert(&db, &target, "a", "existing").await;
+
+    let report = import_profile(&db, &db, &source, &target, ImportStrategy::Fail, true, None)
+        .await
+        .expect("Error importing profile");
+    assert!(report.dry_run);
+    assert_eq!(report.imported, 1);
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(report.conflicts[0].resolution, ConflictResolution::Failed);
+    let report = import_profile(
+        &db,
+        &db,
+        &source,
+        "missing",
+        ImportStrategy::Fail,
+        true,
+        None,
+    )
+    .await
+    .expect("Error importing profile");
+    assert_eq!((report.imported, report.conflicts.len()), (2, 0));
+    assert!(!db
+        .list_profiles()
+        .await
+        .expect("Error listing profiles")
+        .contains(&"missing".to_string()));
+
+    let err = import_profile(
+        &db,
+        &db,
+        &source,
+        &target,
+        ImportStrategy::Fail,
+        false,
+        None,
+    )
+    .await
+    .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+    let mut conn = db.session(Some(target.clone()), false).expect(ERR_SESSION);
+    assert_eq!(conn.count(None, None, None).await.expect(ERR_COUNT), 1);
+    conn.close(false).await.expect(ERR_COMMIT);
+
+    let last_progress = std::sync::Arc::new(std::sync::Mutex::new(None));
+    let handler_progress = last_progress.clone();
+    let report = import_profile(
+        &db,
+        &db,
+        &source,
+        &target,
+        ImportStrategy::Skip,
+        false,
+        Some(&move |progress: &Progress| {
+            *handler_progress.lock().unwrap() = Some(progress.clone())
+        }),
+    )
+    .await
+    .expect("Error importing profile");
+    assert_eq!(report.imported, 1);
+    assert_eq!(
+        last_progress.lock().unwrap().clone(),
+        Some(Progress {
+            phase: ProgressPhase::Import,
+            profile: Some(target.clone()),
+            processed: 2,
+            total: Some(2),
+        })
+    );
+    assert_eq!(
+        report.conflicts,
+        vec![ImportConflict {
+            kind: EntryKind::Item,
+            category: "category".to_string(),
+            name: "a".to_string(),
+            resolution: ConflictResolution::Skipped,
+        }]
+    );
+    assert_eq!(fetch_value(&db, &target, "a").await, "existing");
+    assert_eq!(fetch_value(&db, &target, "b").await, "imported");
+
+    insert(&db, &target, "c", "existing").await;
+    let report = import_profile(
+        &db,
+        &db,
+        &source,
+        &target,
+        ImportStrategy::Overwrite,
+        false,
+        None,
+    )
+    .await
+    .expect("Error importing profile");
+    assert_eq!(report.imported, 2);
+    assert!(report
+        .conflicts
+        .iter()
+        .all(|c| c.resolution == ConflictResolution::Overwritten));
+    assert_eq!(fetch_value(&db, &target, "a").await, "imported");
+    assert_eq!(fetch_value(&db, &target, "c").await, "existing");
+
+    let report = import_profile(
+        &db,
+        &db,
+        &source,
+        &target,
+        ImportStrategy::Rename,
+        false,
+        None,
+    )
+    .await
+    .expect("Error importing profile");
+    assert_eq!(report.imported, 2);
+    let mut renamed = report
+        .conflicts
+        .iter()
+        .map(|c| c.resolution.clone())
+        .collect::<Vec<_>>();
+    renamed.sort_by_key(|r| format!("{:?}", r));
+    assert_eq!(
+        renamed,
+        vec![
+            ConflictResolution::Renamed("a-1".to_string()),
+            ConflictResolution::Renamed("b-1".to_string())
+        ]
+    );
+    assert_eq!(fetch_value(&db, &target, "a-1").await, "imported");
+    let mut conn = db.session(Some(target), false).expect(ERR_SESSION);
+    assert_eq!(conn.count(None, None, None).await.expect(ERR_COUNT), 5);
+    conn.close(false).await.expect(ERR_COMMIT);
+}
+
+pub async fn db_version_info(db: AnyBackend) {
+    let info = db
+        .version_info()
+        .await
+        .expect("Error fetching version info");
+    assert_eq!(info.store_version, "2");
+    assert!(info.schema_version > 0);
+    assert_eq!(info.schema_version, info.supported_schema_version);
+    let provisioned_by = info.provisioned_by.expect("Expected provisioning version");
+    assert!(provisioned_by.starts_with("askar-storage "));
+    assert_eq!(info.schema_writer, Some(provisioned_by));
+    assert_eq!(info.store_cipher, "c20p");
+    assert!(!info.rekey_pending);
+}
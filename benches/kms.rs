@@ -0,0 +1,94 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use aries_askar::kms::{KeyAlg, LocalKey};
+
+/// The size of the message signed or encrypted by each benchmark
+const MSG_SIZE: usize = 1024;
+
+const SIGNING_ALGS: &[&str] = &["ed25519", "k256", "p256", "p384"];
+
+const AEAD_ALGS: &[&str] = &[
+    "a128gcm",
+    "a256gcm",
+    "a128cbchs256",
+    "a256cbchs512",
+    "c20p",
+    "xc20p",
+];
+
+fn generate_key(alg: &str) -> LocalKey {
+    let alg: KeyAlg = alg.parse().expect("Error parsing key algorithm");
+    LocalKey::generate_with_rng(alg, false).expect("Error creating key")
+}
+
+fn kms_benchmarks(c: &mut Criterion) {
+    let message = vec![0x42u8; MSG_SIZE];
+
+    let mut group = c.benchmark_group("kms/sign");
+    for &alg in SIGNING_ALGS {
+        let key = generate_key(alg);
+        group.bench_with_input(BenchmarkId::from_parameter(alg), &key, |b, key| {
+            b.iter(|| {
+                black_box(
+                    key.sign_message(&message, None)
+                        .expect("Error signing message"),
+                )
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("kms/verify");
+    for &alg in SIGNING_ALGS {
+        let key = generate_key(alg);
+        let sig = key
+            .sign_message(&message, None)
+            .expect("Error signing message");
+        group.bench_with_input(BenchmarkId::from_parameter(alg), &key, |b, key| {
+            b.iter(|| {
+                assert!(key
+                    .verify_signature(&message, &sig, None)
+                    .expect("Error verifying signature"))
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("kms/encrypt");
+    for &alg in AEAD_ALGS {
+        let key = generate_key(alg);
+        group.bench_with_input(BenchmarkId::from_parameter(alg), &key, |b, key| {
+            b.iter(|| {
+                black_box(
+                    key.aead_encrypt(&message, &[], &[])
+                        .expect("Error encrypting message"),
+                )
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("kms/decrypt");
+    for &alg in AEAD_ALGS {
+        let key = generate_key(alg);
+        let enc = key
+            .aead_encrypt(&message, &[], &[])
+            .expect("Error encrypting message");
+        group.bench_with_input(BenchmarkId::from_parameter(alg), &key, |b, key| {
+            b.iter(|| {
+                black_box(
+                    key.aead_decrypt(&enc, enc.nonce(), &[])
+                        .expect("Error decrypting message"),
+                )
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = benchmarks;
+    config = Criterion::default();
+    targets = kms_benchmarks
+);
+criterion_main!(benchmarks);
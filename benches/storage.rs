@@ -0,0 +1,176 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use aries_askar::{
+    entry::{EntryTag, TagFilter},
+    future::block_on,
+    Store, StoreKeyMethod,
+};
+
+const ERR_RAW_KEY: &str = "Error creating raw store key";
+const ERR_SESSION: &str = "Error creating store session";
+const ERR_OPEN: &str = "Error opening test store instance";
+const ERR_CLOSE: &str = "Error closing test store instance";
+
+const ROOT_SEED: [u8; 32] = [0x55; 32];
+
+/// The number of records populated before running the read benchmarks
+const RECORD_COUNT: u64 = 1_000;
+/// The number of distinct values for each tag of the populated records
+const TAG_VALUES: u64 = 10;
+
+const CATEGORY: &str = "bench";
+
+/// Collect the backends to benchmark: an in-memory SQLite store, and a Postgres
+/// store when the `POSTGRES_URL` environment variable is defined
+fn backends() -> Vec<(&'static str, String)> {
+    let mut backends = vec![("sqlite", "sqlite://:memory:".to_string())];
+    match std::env::var("POSTGRES_URL") {
+        Ok(p) if !p.is_empty() => backends.push(("postgres", p)),
+        _ => (),
+    }
+    backends
+}
+
+/// Initialize a clean DB for benchmarking
+fn initialize_database(db_url: &str) -> Store {
+    block_on(async {
+        let pass_key = Store::new_raw_key(Some(&ROOT_SEED)).expect(ERR_RAW_KEY);
+        Store::provision(
+            db_url,
+            StoreKeyMethod::RawKey,
+            pass_key,
+            Some("askar-bench".to_string()),
+            true,
+        )
+        .await
+        .expect(ERR_OPEN)
+    })
+}
+
+fn record_tags(index: u64) -> Vec<EntryTag> {
+    vec![
+        EntryTag::Encrypted("enc".to_string(), (index % TAG_VALUES).to_string()),
+        EntryTag::Plaintext("plain".to_string(), (index % TAG_VALUES).to_string()),
+    ]
+}
+
+/// Insert `RECORD_COUNT` records with encrypted and plaintext tags
+fn populate_database(db: &Store) {
+    block_on(async {
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for index in 0..RECORD_COUNT {
+            conn.insert(
+                CATEGORY,
+                &format!("record-{}", index),
+                &[0x42; 256],
+                Some(&record_tags(index)),
+                None,
+            )
+            .await
+            .expect("Error inserting record");
+        }
+    });
+}
+
+fn storage_benchmarks(c: &mut Criterion) {
+    for (backend, db_url) in backends() {
+        let db = initialize_database(&db_url);
+        populate_database(&db);
+        let mut group = c.benchmark_group(format!("storage/{}", backend));
+
+        let counter = AtomicU64::new(0);
+        group.bench_function("insert", |b| {
+            b.iter(|| {
+                block_on(async {
+                    let index = counter.fetch_add(1, Ordering::Relaxed);
+                    let mut conn = db.session(None).await.expect(ERR_SESSION);
+                    conn.insert(
+                        "bench-insert",
+                        &format!("insert-{}", index),
+                        &[0x42; 256],
+                        Some(&record_tags(index)),
+                        None,
+                    )
+                    .await
+                    .expect("Error inserting record");
+                })
+            });
+        });
+
+        group.bench_function("fetch", |b| {
+            b.iter(|| {
+                block_on(async {
+                    let mut conn = db.session(None).await.expect(ERR_SESSION);
+                    black_box(
+                        conn.fetch(CATEGORY, "record-0", false)
+                            .await
+                            .expect("Error fetching record")
+                            .expect("Row required"),
+                    );
+                })
+            });
+        });
+
+        for tag_name in ["enc", "~plain"] {
+            group.bench_with_input(
+                BenchmarkId::new("fetch_all_tag_filter", tag_name),
+                tag_name,
+                |b, tag_name| {
+                    b.iter(|| {
+                        block_on(async {
+                            let mut conn = db.session(None).await.expect(ERR_SESSION);
+                            let rows = conn
+                                .fetch_all(
+                                    Some(CATEGORY),
+                                    Some(TagFilter::is_eq(tag_name, "1")),
+                                    None,
+                                    None,
+                                    false,
+                                    false,
+                                )
+                                .await
+                                .expect("Error fetching records");
+                            assert_eq!(rows.len() as u64, RECORD_COUNT / TAG_VALUES);
+                        })
+                    });
+                },
+            );
+        }
+
+        group.bench_function("scan", |b| {
+            b.iter(|| {
+                block_on(async {
+                    let mut scan = db
+                        .scan(
+                            None,
+                            Some(CATEGORY.to_string()),
+                            None,
+                            None,
+                            None,
+                            None,
+                            false,
+                        )
+                        .await
+                        .expect("Error starting scan");
+                    let mut count = 0;
+                    while let Some(rows) = scan.fetch_next().await.expect("Error scanning") {
+                        count += rows.len() as u64;
+                    }
+                    assert_eq!(count, RECORD_COUNT);
+                })
+            });
+        });
+
+        group.finish();
+        block_on(async { db.close().await.expect(ERR_CLOSE) });
+    }
+}
+
+criterion_group!(
+    name = benchmarks;
+    config = Criterion::default().sample_size(50);
+    targets = storage_benchmarks
+);
+criterion_main!(benchmarks);
@@ -0,0 +1,12 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/askar.proto");
+        let fds = protox::compile(["askar.proto"], ["proto"]).expect("Error parsing protobuf");
+        tonic_build::configure()
+            .compile_fds(fds)
+            .expect("Error generating gRPC services");
+    }
+}
@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use aries_askar::crypto::alg::AesTypes;
+use aries_askar::kms::{unwrap_key_jwe, KeyAlg, LocalKey};
+
+fuzz_target!(|data: &str| {
+    // exercise both the AES key wrap and ECDH-ES key management paths
+    let aes_key = LocalKey::from_secret_bytes(KeyAlg::Aes(AesTypes::A256Kw), &[1u8; 32])
+        .expect("Error creating unwrapping key");
+    let _ = unwrap_key_jwe(&aes_key, data);
+    let x25519_key = LocalKey::from_secret_bytes(KeyAlg::X25519, &[2u8; 32])
+        .expect("Error creating unwrapping key");
+    let _ = unwrap_key_jwe(&x25519_key, data);
+});
@@ -27,12 +27,20 @@ enum ErrorCode
   NotFound = 6,
   Unexpected = 7,
   Unsupported = 8,
+  Cancelled = 9,
+  QuotaExceeded = 10,
+  Archived = 11,
   Custom = 100,
 };
 #ifndef __cplusplus
 typedef int64_t ErrorCode;
 #endif // __cplusplus
 
+/**
+ * A token used to cancel in-flight operations started over FFI
+ */
+typedef struct CancelToken CancelToken;
+
 typedef struct FfiResultList_Entry FfiResultList_Entry;
 
 typedef struct FfiResultList_KeyEntry FfiResultList_KeyEntry;
@@ -55,6 +63,12 @@ typedef struct SecretBuffer {
 
 typedef struct FfiResultList_Entry FfiEntryList;
 
+typedef struct ArcHandle_CancelToken {
+  const struct CancelToken *_0;
+} ArcHandle_CancelToken;
+
+typedef struct ArcHandle_CancelToken CancelHandle;
+
 typedef struct ArcHandle_FfiEntryList {
   const FfiEntryList *_0;
 } ArcHandle_FfiEntryList;
@@ -243,8 +257,18 @@ extern "C" {
 
 void askar_buffer_free(struct SecretBuffer buffer);
 
+ErrorCode askar_cancel_token_cancel(CancelHandle handle);
+
+ErrorCode askar_cancel_token_create(CancelHandle *out);
+
+void askar_cancel_token_free(CancelHandle handle);
+
+char *askar_capabilities(void);
+
 void askar_clear_custom_logger(void);
 
+char *askar_crypto_acceleration(void);
+
 ErrorCode askar_entry_list_count(EntryListHandle handle, int32_t *count);
 
 void askar_entry_list_free(EntryListHandle handle);
@@ -263,6 +287,8 @@ ErrorCode askar_entry_list_get_value(EntryListHandle handle,
 
 ErrorCode askar_get_current_error(const char **error_json_p);
 
+ErrorCode askar_get_current_error_details(const char **details_json_p);
+
 ErrorCode askar_key_aead_decrypt(LocalKeyHandle handle,
                                  struct ByteBuffer ciphertext,
                                  struct ByteBuffer nonce,
@@ -342,6 +368,10 @@ ErrorCode askar_key_entry_list_get_name(KeyEntryListHandle handle,
                                         int32_t index,
                                         const char **name);
 
+ErrorCode askar_key_entry_list_get_origin(KeyEntryListHandle handle,
+                                          int32_t index,
+                                          const char **origin);
+
 ErrorCode askar_key_entry_list_get_tags(KeyEntryListHandle handle,
                                         int32_t index,
                                         const char **tags);
@@ -428,6 +458,29 @@ ErrorCode askar_migrate_indy_sdk(FfiStr spec_uri,
                                  void (*cb)(CallbackId cb_id, ErrorCode err),
                                  CallbackId cb_id);
 
+ErrorCode askar_migrate_indy_sdk_into_store(FfiStr spec_uri,
+                                            FfiStr wallet_name,
+                                            FfiStr wallet_key,
+                                            FfiStr kdf_level,
+                                            StoreHandle handle,
+                                            FfiStr profile,
+                                            void (*cb)(CallbackId cb_id, ErrorCode err, int64_t count),
+                                            CallbackId cb_id);
+
+ErrorCode askar_migrate_indy_sdk_into_store_v2(FfiStr spec_uri,
+                                               FfiStr wallet_name,
+                                               FfiStr wallet_key,
+                                               FfiStr kdf_level,
+                                               StoreHandle handle,
+                                               FfiStr profile,
+                                               CancelHandle cancel,
+                                               void (*cb)(CallbackId cb_id, ErrorCode err, int64_t count),
+                                               CallbackId cb_id);
+
+ErrorCode askar_scan_cursor(ScanHandle handle,
+                            void (*cb)(CallbackId cb_id, ErrorCode err, const char *cursor),
+                            CallbackId cb_id);
+
 ErrorCode askar_scan_free(ScanHandle handle);
 
 ErrorCode askar_scan_next(ScanHandle handle, void (*cb)(CallbackId cb_id,
@@ -440,9 +493,43 @@ ErrorCode askar_scan_start(StoreHandle handle,
                            FfiStr tag_filter,
                            int64_t offset,
                            int64_t limit,
+                           FfiStr order_by,
+                           int8_t descending,
                            void (*cb)(CallbackId cb_id, ErrorCode err, ScanHandle handle),
                            CallbackId cb_id);
 
+ErrorCode askar_scan_start_v2(StoreHandle handle,
+                              FfiStr profile,
+                              FfiStr category,
+                              FfiStr tag_filter,
+                              int64_t offset,
+                              int64_t limit,
+                              FfiStr order_by,
+                              int8_t descending,
+                              FfiStr cursor,
+                              void (*cb)(CallbackId cb_id, ErrorCode err, ScanHandle handle),
+                              CallbackId cb_id);
+
+ErrorCode askar_scan_start_v3(StoreHandle handle,
+                              FfiStr profile,
+                              FfiStr category,
+                              FfiStr tag_filter,
+                              int64_t offset,
+                              int64_t limit,
+                              FfiStr order_by,
+                              int8_t descending,
+                              FfiStr cursor,
+                              CancelHandle cancel,
+                              void (*cb)(CallbackId cb_id, ErrorCode err, ScanHandle handle),
+                              CallbackId cb_id);
+
+ErrorCode askar_scan_stream(ScanHandle handle,
+                            int32_t window,
+                            void (*cb)(CallbackId cb_id, ErrorCode err, EntryListHandle results),
+                            CallbackId cb_id);
+
+ErrorCode askar_scan_stream_ack(ScanHandle handle, int32_t pages);
+
 ErrorCode askar_session_close(SessionHandle handle,
                               int8_t commit,
                               void (*cb)(CallbackId cb_id, ErrorCode err),
@@ -461,6 +548,12 @@ ErrorCode askar_session_fetch(SessionHandle handle,
                               void (*cb)(CallbackId cb_id, ErrorCode err, EntryListHandle results),
                               CallbackId cb_id);
 
+ErrorCode askar_session_export_wrapped_key(SessionHandle handle,
+                                           FfiStr name,
+                                           LocalKeyHandle wrapping_key,
+                                           void (*cb)(CallbackId cb_id, ErrorCode err, const char *wrapped),
+                                           CallbackId cb_id);
+
 ErrorCode askar_session_fetch_all(SessionHandle handle,
                                   FfiStr category,
                                   FfiStr tag_filter,
@@ -490,6 +583,18 @@ ErrorCode askar_session_fetch_key(SessionHandle handle,
                                              KeyEntryListHandle results),
                                   CallbackId cb_id);
 
+ErrorCode askar_session_import_wrapped_key(SessionHandle handle,
+                                           FfiStr name,
+                                           FfiStr alg,
+                                           struct ByteBuffer wrapped,
+                                           LocalKeyHandle unwrapping_key,
+                                           FfiStr unwrap_key_name,
+                                           FfiStr metadata,
+                                           FfiStr tags,
+                                           int64_t expiry_ms,
+                                           void (*cb)(CallbackId cb_id, ErrorCode err),
+                                           CallbackId cb_id);
+
 ErrorCode askar_session_insert_key(SessionHandle handle,
                                    LocalKeyHandle key_handle,
                                    FfiStr name,
@@ -516,6 +621,14 @@ ErrorCode askar_session_start(StoreHandle handle,
                               void (*cb)(CallbackId cb_id, ErrorCode err, SessionHandle handle),
                               CallbackId cb_id);
 
+ErrorCode askar_session_start_v2(StoreHandle handle,
+                                 FfiStr profile,
+                                 int8_t as_transaction,
+                                 FfiStr traceparent,
+                                 FfiStr tracestate,
+                                 void (*cb)(CallbackId cb_id, ErrorCode err, SessionHandle handle),
+                                 CallbackId cb_id);
+
 ErrorCode askar_session_update(SessionHandle handle,
                                int8_t operation,
                                FfiStr category,
@@ -526,6 +639,17 @@ ErrorCode askar_session_update(SessionHandle handle,
                                void (*cb)(CallbackId cb_id, ErrorCode err),
                                CallbackId cb_id);
 
+ErrorCode askar_session_update_v2(SessionHandle handle,
+                                  int8_t operation,
+                                  FfiStr category,
+                                  FfiStr name,
+                                  struct ByteBuffer value,
+                                  FfiStr tags,
+                                  int64_t expiry_ms,
+                                  int8_t unencrypted,
+                                  void (*cb)(CallbackId cb_id, ErrorCode err),
+                                  CallbackId cb_id);
+
 ErrorCode askar_session_update_key(SessionHandle handle,
                                    FfiStr name,
                                    FfiStr metadata,
@@ -534,6 +658,8 @@ ErrorCode askar_session_update_key(SessionHandle handle,
                                    void (*cb)(CallbackId cb_id, ErrorCode err),
                                    CallbackId cb_id);
 
+ErrorCode askar_set_blocking_pool(int64_t max_threads, int64_t keep_alive_ms, int64_t max_pending);
+
 ErrorCode askar_set_custom_logger(const void *context,
                                   LogCallback log,
                                   struct Option_EnabledCallback enabled,
@@ -544,6 +670,8 @@ ErrorCode askar_set_default_logger(void);
 
 ErrorCode askar_set_max_log_level(int32_t max_level);
 
+ErrorCode askar_set_redaction_policy(FfiStr category, FfiStr name, FfiStr tag_name);
+
 ErrorCode askar_store_close(StoreHandle handle,
                             void (*cb)(CallbackId cb_id, ErrorCode err),
                             CallbackId cb_id);
@@ -556,6 +684,15 @@ ErrorCode askar_store_copy(StoreHandle handle,
                            void (*cb)(CallbackId cb_id, ErrorCode err, StoreHandle handle),
                            CallbackId cb_id);
 
+ErrorCode askar_store_copy_v2(StoreHandle handle,
+                              FfiStr target_uri,
+                              FfiStr key_method,
+                              FfiStr pass_key,
+                              int8_t recreate,
+                              CancelHandle cancel,
+                              void (*cb)(CallbackId cb_id, ErrorCode err, StoreHandle handle),
+                              CallbackId cb_id);
+
 ErrorCode askar_store_create_profile(StoreHandle handle,
                                      FfiStr profile,
                                      void (*cb)(CallbackId cb_id,
@@ -563,6 +700,11 @@ ErrorCode askar_store_create_profile(StoreHandle handle,
                                                 const char *result_p),
                                      CallbackId cb_id);
 
+ErrorCode askar_store_ensure_profile(StoreHandle handle,
+                                     FfiStr profile,
+                                     void (*cb)(CallbackId cb_id, ErrorCode err, int8_t created),
+                                     CallbackId cb_id);
+
 ErrorCode askar_store_generate_raw_key(struct ByteBuffer seed, const char **out);
 
 ErrorCode askar_store_get_default_profile(StoreHandle handle,
@@ -571,16 +713,50 @@ ErrorCode askar_store_get_default_profile(StoreHandle handle,
                                                      const char *profile),
                                           CallbackId cb_id);
 
+ErrorCode askar_store_get_profile_metadata(StoreHandle handle,
+                                           FfiStr profile,
+                                           void (*cb)(CallbackId cb_id,
+                                                      ErrorCode err,
+                                                      const char *metadata),
+                                           CallbackId cb_id);
+
 ErrorCode askar_store_get_profile_name(StoreHandle handle,
                                        void (*cb)(CallbackId cb_id, ErrorCode err, const char *name),
                                        CallbackId cb_id);
 
+/**
+ * Get the limits on the records stored within a profile
+ *
+ * The limits are provided to the callback as a JSON object with the integer
+ * or null properties `max_entries` and `max_value_bytes`.
+ */
+ErrorCode askar_store_get_profile_quota(StoreHandle handle,
+                                        FfiStr profile,
+                                        void (*cb)(CallbackId cb_id,
+                                                   ErrorCode err,
+                                                   const char *quota),
+                                        CallbackId cb_id);
+
+ErrorCode askar_store_insert_batch(StoreHandle handle,
+                                   FfiStr profile,
+                                   struct ByteBuffer entries,
+                                   void (*cb)(CallbackId cb_id, ErrorCode err, int64_t count),
+                                   CallbackId cb_id);
+
 ErrorCode askar_store_list_profiles(StoreHandle handle,
                                     void (*cb)(CallbackId cb_id,
                                                ErrorCode err,
                                                StringListHandle results),
                                     CallbackId cb_id);
 
+ErrorCode askar_store_list_profiles_page(StoreHandle handle,
+                                         int64_t offset,
+                                         int64_t limit,
+                                         void (*cb)(CallbackId cb_id,
+                                                    ErrorCode err,
+                                                    StringListHandle results),
+                                         CallbackId cb_id);
+
 ErrorCode askar_store_open(FfiStr spec_uri,
                            FfiStr key_method,
                            FfiStr pass_key,
@@ -588,6 +764,22 @@ ErrorCode askar_store_open(FfiStr spec_uri,
                            void (*cb)(CallbackId cb_id, ErrorCode err, StoreHandle handle),
                            CallbackId cb_id);
 
+/**
+ * Open a store, optionally upgrading the derivation of its wrapping key
+ *
+ * When `kdf_upgrade` is a key derivation method such as `kdf:argon2i:mod` and
+ * the wrapping key of the store is derived using a weaker method, the key is
+ * derived again from the same pass key once the store is unlocked. Opening the
+ * store fails if the upgrade fails.
+ */
+ErrorCode askar_store_open_v2(FfiStr spec_uri,
+                              FfiStr key_method,
+                              FfiStr pass_key,
+                              FfiStr profile,
+                              FfiStr kdf_upgrade,
+                              void (*cb)(CallbackId cb_id, ErrorCode err, StoreHandle handle),
+                              CallbackId cb_id);
+
 ErrorCode askar_store_provision(FfiStr spec_uri,
                                 FfiStr key_method,
                                 FfiStr pass_key,
@@ -602,6 +794,18 @@ ErrorCode askar_store_rekey(StoreHandle handle,
                             void (*cb)(CallbackId cb_id, ErrorCode err),
                             CallbackId cb_id);
 
+ErrorCode askar_store_rekey_profile(StoreHandle handle,
+                                    FfiStr profile,
+                                    void (*cb)(CallbackId cb_id, ErrorCode err),
+                                    CallbackId cb_id);
+
+ErrorCode askar_store_rekey_v2(StoreHandle handle,
+                               FfiStr key_method,
+                               FfiStr pass_key,
+                               CancelHandle cancel,
+                               void (*cb)(CallbackId cb_id, ErrorCode err),
+                               CallbackId cb_id);
+
 ErrorCode askar_store_remove(FfiStr spec_uri,
                              void (*cb)(CallbackId cb_id, ErrorCode err, int8_t),
                              CallbackId cb_id);
@@ -611,17 +815,50 @@ ErrorCode askar_store_remove_profile(StoreHandle handle,
                                      void (*cb)(CallbackId cb_id, ErrorCode err, int8_t removed),
                                      CallbackId cb_id);
 
+ErrorCode askar_store_rename_profile(StoreHandle handle,
+                                     FfiStr from_profile,
+                                     FfiStr to_profile,
+                                     void (*cb)(CallbackId cb_id, ErrorCode err, int8_t renamed),
+                                     CallbackId cb_id);
+
+ErrorCode askar_store_rotate_tag_key(StoreHandle handle,
+                                    FfiStr profile,
+                                    void (*cb)(CallbackId cb_id, ErrorCode err),
+                                    CallbackId cb_id);
+
 ErrorCode askar_store_set_default_profile(StoreHandle handle,
                                           FfiStr profile,
                                           void (*cb)(CallbackId cb_id, ErrorCode err),
                                           CallbackId cb_id);
 
+ErrorCode askar_store_set_profile_metadata(StoreHandle handle,
+                                           FfiStr profile,
+                                           FfiStr metadata,
+                                           void (*cb)(CallbackId cb_id, ErrorCode err),
+                                           CallbackId cb_id);
+
+/**
+ * Replace the limits on the records stored within a profile
+ *
+ * A negative limit removes the corresponding limit.
+ */
+ErrorCode askar_store_set_profile_quota(StoreHandle handle,
+                                        FfiStr profile,
+                                        int64_t max_entries,
+                                        int64_t max_value_bytes,
+                                        void (*cb)(CallbackId cb_id, ErrorCode err),
+                                        CallbackId cb_id);
+
+ErrorCode askar_store_set_pass_key_policy(int32_t min_length, int32_t min_entropy_bits);
+
 ErrorCode askar_string_list_count(StringListHandle handle, int32_t *count);
 
 void askar_string_list_free(StringListHandle handle);
 
 ErrorCode askar_string_list_get_item(StringListHandle handle, int32_t index, const char **item);
 
+ErrorCode askar_tag_filter_validate(FfiStr tag_filter, const char **diagnostics_json_p);
+
 void askar_terminate(void);
 
 char *askar_version(void);
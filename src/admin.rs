@@ -0,0 +1,646 @@
+//! An HTTP API for administrative operations on a store
+//!
+//! The API is intended for operations tooling: it supports provisioning, profile
+//! management, statistics, health checks, snapshot exports and re-keying, but not
+//! record access. Each request must include an API key in the `x-api-key` header.
+//! The server does not support TLS, and only listens on a loopback address: it
+//! should be exposed to remote operators through an authenticating proxy.
+
+use std::{
+    collections::HashSet,
+    future::Future,
+    net::SocketAddr,
+    path::{Path as FilePath, PathBuf},
+    sync::Arc,
+};
+
+use async_lock::RwLock;
+use axum::{
+    extract::{Path, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    error::{Error, ErrorKind},
+    server::{parse_key_method, parse_token_hash, token_hash},
+    store::{PassKey, Store, StoreKeyMethod},
+};
+
+/// Configuration for the administrative HTTP server
+#[derive(Clone, Debug, Deserialize)]
+pub struct AdminConfig {
+    /// The address to listen on, which must be a loopback address
+    pub listen: SocketAddr,
+    /// The database URL of the managed store
+    pub store_uri: String,
+    /// The store key method used to open or provision the store
+    #[serde(default)]
+    pub key_method: Option<String>,
+    /// The directory in which store snapshots are written. Exports are disabled
+    /// when absent
+    #[serde(default)]
+    pub export_dir: Option<PathBuf>,
+    /// The hex-encoded SHA-256 hashes of the accepted API keys
+    pub api_keys_sha256: Vec<String>,
+}
+
+/// The key method and pass key used to provision or open the managed store
+#[derive(Debug)]
+struct StoreCredentials {
+    key_method: Option<StoreKeyMethod>,
+    pass_key: PassKey<'static>,
+}
+
+/// The shared state of the administrative API
+#[derive(Debug)]
+pub struct AdminState {
+    store_uri: String,
+    credentials: RwLock<StoreCredentials>,
+    export_dir: Option<PathBuf>,
+    api_keys: HashSet<String>,
+    store: RwLock<Option<Store>>,
+}
+
+impl AdminState {
+    /// Create the API state, opening the managed store if it has been provisioned
+    pub async fn new(config: AdminConfig, pass_key: PassKey<'_>) -> Result<Self, Error> {
+        let mut api_keys = HashSet::with_capacity(config.api_keys_sha256.len());
+        for hash in config.api_keys_sha256 {
+            let hash =
+                parse_token_hash(&hash).ok_or_else(|| err_msg!(Input, "Invalid API key hash"))?;
+            api_keys.insert(hash);
+        }
+        if api_keys.is_empty() {
+            return Err(err_msg!(Input, "At least one API key must be configured"));
+        }
+        let key_method = parse_key_method(config.key_method.as_deref())?;
+        let pass_key = pass_key.into_owned();
+        let store = match Store::open(
+            &config.store_uri,
+            key_method.clone(),
+            pass_key.as_ref(),
+            None,
+        )
+        .await
+        {
+            Ok(store) => Some(store),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            store_uri: config.store_uri,
+            credentials: RwLock::new(StoreCredentials {
+                key_method,
+                pass_key,
+            }),
+            export_dir: config.export_dir,
+            api_keys,
+            store: RwLock::new(store),
+        })
+    }
+
+    fn check_api_key(&self, api_key: &str) -> bool {
+        self.api_keys.contains(&token_hash(api_key))
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = match self.kind() {
+            ErrorKind::Archived => StatusCode::LOCKED,
+            ErrorKind::Busy => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorKind::Duplicate => StatusCode::CONFLICT,
+            ErrorKind::Input => StatusCode::BAD_REQUEST,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::QuotaExceeded => StatusCode::INSUFFICIENT_STORAGE,
+            ErrorKind::Unsupported => StatusCode::NOT_IMPLEMENTED,
+            ErrorKind::Backend
+            | ErrorKind::Cancelled
+            | ErrorKind::Custom
+            | ErrorKind::Encryption
+            | ErrorKind::Unexpected => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        // the cause is not included, as it may expose details of the backend
+        let body = json!({
+            "error": self.kind().as_str(),
+            "message": self.message().unwrap_or(self.kind().as_str()),
+        });
+        (status, Json(body)).into_response()
+    }
+}
+
+type AppState = State<Arc<AdminState>>;
+
+fn provisioned(store: &Option<Store>) -> Result<&Store, Error> {
+    store
+        .as_ref()
+        .ok_or_else(|| err_msg!(NotFound, "The store has not been provisioned"))
+}
+
+async fn require_api_key(State(state): AppState, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get("x-api-key")
+        .and_then(|value| value.to_str().ok())
+        .map(|api_key| state.check_api_key(api_key))
+        .unwrap_or(false);
+    if authorized {
+        next.run(request).await
+    } else {
+        let body = json!({ "error": "Unauthorized", "message": "Invalid API key" });
+        (StatusCode::UNAUTHORIZED, Json(body)).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct ProvisionRequest {
+    #[serde(default)]
+    profile: Option<String>,
+}
+
+async fn provision(
+    State(state): AppState,
+    Json(req): Json<ProvisionRequest>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let mut guard = state.store.write().await;
+    // an existing store is never replaced through the API
+    if guard.is_some() {
+        return Err(err_msg!(Duplicate, "The store is already provisioned"));
+    }
+    let credentials = state.credentials.read().await;
+    let store = Store::provision(
+        &state.store_uri,
+        credentials.key_method.clone().unwrap_or_default(),
+        credentials.pass_key.as_ref(),
+        req.profile,
+        false,
+    )
+    .await?;
+    let profile = store.get_default_profile().await?;
+    *guard = Some(store);
+    Ok((StatusCode::CREATED, Json(json!({ "default": profile }))))
+}
+
+async fn list_profiles(State(state): AppState) -> Result<Json<Value>, Error> {
+    let guard = state.store.read().await;
+    let store = provisioned(&guard)?;
+    let profiles = store.list_profiles().await?;
+    let default = store.get_default_profile().await?;
+    Ok(Json(json!({ "profiles": profiles, "default": default })))
+}
+
+#[derive(Deserialize)]
+struct ProfileRequest {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+async fn create_profile(
+    State(state): AppState,
+    Json(req): Json<ProfileRequest>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let guard = state.store.read().await;
+    let name = provisioned(&guard)?.create_profile(req.name).await?;
+    Ok((StatusCode::CREATED, Json(json!({ "name": name }))))
+}
+
+async fn remove_profile(
+    State(state): AppState,
+    Path(name): Path<String>,
+) -> Result<StatusCode, Error> {
+    let guard = state.store.read().await;
+    if provisioned(&guard)?.remove_profile(name).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(err_msg!(NotFound, "Profile not found"))
+    }
+}
+
+async fn set_default_profile(
+    State(state): AppState,
+    Json(req): Json<ProfileRequest>,
+) -> Result<StatusCode, Error> {
+    let name = req
+        .name
+        .ok_or_else(|| err_msg!(Input, "Profile name must be provided"))?;
+    let guard = state.store.read().await;
+    let store = provisioned(&guard)?;
+    if !store.list_profiles().await?.contains(&name) {
+        return Err(err_msg!(NotFound, "Profile not found"));
+    }
+    store.set_default_profile(name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn stats(State(state): AppState) -> Result<Json<Value>, Error> {
+    let guard = state.store.read().await;
+    let store = provisioned(&guard)?;
+    let version = store.version_info().await?;
+    let profiles = store.list_profiles().await?.len();
+    let key_cache = store.key_cache_stats().await.map(|stats| {
+        json!({
+            "size": stats.size,
+            "max_size": stats.max_size,
+            "hits": stats.hits,
+            "misses": stats.misses,
+            "evictions": stats.evictions,
+            "expirations": stats.expirations,
+            "hit_rate": stats.hit_rate(),
+            "store_key_locked": stats.store_key_locked,
+            "locked_keys": stats.locked_keys,
+        })
+    });
+    let pool = store.pool_status().map(|status| {
+        let millis = |dur: Option<std::time::Duration>| dur.map(|d| d.as_secs_f64() * 1000.0);
+        json!({
+            "size": status.size,
+            "idle": status.idle,
+            "active": status.active,
+            "max_size": status.max_size,
+            "acquired": status.acquired,
+            "waits": status.waits,
+            "failures": status.failures,
+            "acquire_p50_ms": millis(status.acquire_p50),
+            "acquire_p95_ms": millis(status.acquire_p95),
+            "acquire_p99_ms": millis(status.acquire_p99),
+        })
+    });
+    Ok(Json(json!({
+        "version": {
+            "store_version": version.store_version,
+            "schema_version": version.schema_version,
+            "supported_schema_version": version.supported_schema_version,
+            "provisioned_by": version.provisioned_by,
+            "schema_writer": version.schema_writer,
+            "key_method": version.key_method,
+            "store_cipher": version.store_cipher,
+            "profile_cipher": version.profile_cipher.as_str(),
+            "name_encryption": version.name_encryption.as_str(),
+            "expiry_bucket": version.expiry_bucket,
+            "rekey_pending": version.rekey_pending,
+        },
+        "profiles": profiles,
+        "key_cache": key_cache,
+        "pool": pool,
+    })))
+}
+
+async fn health(State(state): AppState) -> Result<(StatusCode, Json<Value>), Error> {
+    let guard = state.store.read().await;
+    let report = provisioned(&guard)?.health().await;
+    let components = report
+        .components
+        .iter()
+        .map(|component| {
+            json!({
+                "name": component.name,
+                "status": component.status.as_str(),
+                "latency_ms": component.latency.as_secs_f64() * 1000.0,
+                "message": component.message,
+            })
+        })
+        .collect::<Vec<_>>();
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((
+        status,
+        Json(json!({
+            "status": report.status.as_str(),
+            "components": components,
+        })),
+    ))
+}
+
+#[derive(Deserialize)]
+struct ExportRequest {
+    name: String,
+}
+
+async fn export(
+    State(state): AppState,
+    Json(req): Json<ExportRequest>,
+) -> Result<(StatusCode, Json<Value>), Error> {
+    let export_dir = state
+        .export_dir
+        .as_ref()
+        .ok_or_else(|| err_msg!(Unsupported, "Exports are not enabled"))?;
+    // only plain file names are accepted, to keep exports within the directory
+    let file_name = FilePath::new(&req.name).file_name();
+    if req.name.is_empty() || file_name.map(|f| f != req.name.as_str()).unwrap_or(true) {
+        return Err(err_msg!(Input, "Invalid export file name"));
+    }
+    let path = export_dir.join(&req.name);
+    if path.exists() {
+        return Err(err_msg!(Duplicate, "The export file already exists"));
+    }
+    let path = path
+        .to_str()
+        .ok_or_else(|| err_msg!(Input, "Invalid export file name"))?;
+    let guard = state.store.read().await;
+    provisioned(&guard)?.snapshot_to(path).await?;
+    Ok((StatusCode::CREATED, Json(json!({ "name": req.name }))))
+}
+
+#[derive(Deserialize)]
+struct RekeyRequest {
+    key_method: String,
+    #[serde(default)]
+    pass_key: Option<String>,
+}
+
+async fn rekey(State(state): AppState, Json(req): Json<RekeyRequest>) -> Result<StatusCode, Error> {
+    let key_method = StoreKeyMethod::parse_uri(&req.key_method)?;
+    let pass_key = PassKey::from(req.pass_key.as_deref()).into_owned();
+    let mut guard = state.store.write().await;
+    guard
+        .as_mut()
+        .ok_or_else(|| err_msg!(NotFound, "The store has not been provisioned"))?
+        .rekey(key_method.clone(), pass_key.as_ref())
+        .await?;
+    *state.credentials.write().await = StoreCredentials {
+        key_method: Some(key_method.clone()),
+        pass_key: pass_key.clone(),
+    };
+    // the store is reopened to confirm that it is accessible using the new key,
+    // and the current instance is kept if it cannot be reopened
+    let store = Store::open(&state.store_uri, Some(key_method), pass_key, None).await?;
+    if let Some(previous) = guard.replace(store) {
+        if let Err(err) = previous.close().await {
+            warn!(
+                "Error closing the store instance replaced after re-keying: {}",
+                err
+            );
+        }
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Build the router for the administrative API
+pub fn router(state: Arc<AdminState>) -> Router {
+    Router::new()
+        .route("/provision", post(provision))
+        .route("/profiles", get(list_profiles).post(create_profile))
+        .route("/profiles/default", put(set_default_profile))
+        .route("/profiles/:name", delete(remove_profile))
+        .route("/stats", get(stats))
+        .route("/health", get(health))
+        .route("/export", post(export))
+        .route("/rekey", post(rekey))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_api_key,
+        ))
+        .with_state(state)
+}
+
+/// Serve the administrative API until `shutdown` completes
+pub async fn serve(
+    config: AdminConfig,
+    pass_key: PassKey<'_>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), Error> {
+    let listen = config.listen;
+    if !listen.ip().is_loopback() {
+        return Err(err_msg!(
+            Input,
+            "The administrative API must listen on a loopback address"
+        ));
+    }
+    let state = Arc::new(AdminState::new(config, pass_key).await?);
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .map_err(err_map!(Input, "Error binding to listen address"))?;
+    let result = axum::serve(listener, router(state.clone()))
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(err_map!(Unexpected, "Error running HTTP server"));
+    if let Some(store) = state.store.write().await.take() {
+        store.close().await?;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::future::block_on;
+
+    fn test_state() -> Arc<AdminState> {
+        test_state_with_uri("sqlite://:memory:")
+    }
+
+    fn test_state_with_uri(store_uri: &str) -> Arc<AdminState> {
+        let api_key = token_hash("admin-key");
+        Arc::new(AdminState {
+            store_uri: store_uri.to_string(),
+            credentials: RwLock::new(StoreCredentials {
+                key_method: Some(StoreKeyMethod::RawKey),
+                pass_key: Store::new_raw_key(None).unwrap(),
+            }),
+            export_dir: None,
+            api_keys: HashSet::from([api_key]),
+            store: RwLock::new(None),
+        })
+    }
+
+    #[test]
+    fn check_api_key() {
+        let state = test_state();
+        assert!(state.check_api_key("admin-key"));
+        assert!(!state.check_api_key("other-key"));
+    }
+
+    #[test]
+    fn provision_and_manage_profiles() {
+        block_on(async {
+            let state = test_state();
+            let err = list_profiles(State(state.clone())).await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+
+            let (status, _) = provision(
+                State(state.clone()),
+                Json(ProvisionRequest {
+                    profile: Some("main".to_string()),
+                }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(status, StatusCode::CREATED);
+            let err = provision(
+                State(state.clone()),
+                Json(ProvisionRequest { profile: None }),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Duplicate);
+
+            let (status, Json(health)) = health(State(state.clone())).await.unwrap();
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(health["status"], "healthy");
+
+            let (status, _) = create_profile(
+                State(state.clone()),
+                Json(ProfileRequest {
+                    name: Some("other".to_string()),
+                }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(status, StatusCode::CREATED);
+            set_default_profile(
+                State(state.clone()),
+                Json(ProfileRequest {
+                    name: Some("other".to_string()),
+                }),
+            )
+            .await
+            .unwrap();
+            let Json(profiles) = list_profiles(State(state.clone())).await.unwrap();
+            assert_eq!(profiles["default"], "other");
+            assert_eq!(profiles["profiles"].as_array().unwrap().len(), 2);
+
+            let status = remove_profile(State(state.clone()), Path("main".to_string()))
+                .await
+                .unwrap();
+            assert_eq!(status, StatusCode::NO_CONTENT);
+            let err = remove_profile(State(state.clone()), Path("main".to_string()))
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+
+            let Json(stats) = stats(State(state.clone())).await.unwrap();
+            assert_eq!(stats["profiles"], 1);
+            assert_eq!(stats["version"]["key_method"], "raw");
+
+            let err = export(
+                State(state),
+                Json(ExportRequest {
+                    name: "snapshot.db".to_string(),
+                }),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+        });
+    }
+
+    #[test]
+    fn rekey_reopens_store() {
+        block_on(async {
+            let path =
+                std::env::temp_dir().join(format!("askar-admin-{}.db", rand::random::<u64>()));
+            let store_uri = format!("sqlite://{}", path.display());
+            let state = test_state_with_uri(&store_uri);
+            let (status, _) = provision(
+                State(state.clone()),
+                Json(ProvisionRequest { profile: None }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(status, StatusCode::CREATED);
+
+            let pass_key = Store::new_raw_key(None).unwrap();
+            let status = rekey(
+                State(state.clone()),
+                Json(RekeyRequest {
+                    key_method: "raw".to_string(),
+                    pass_key: Some(pass_key.to_string()),
+                }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(status, StatusCode::NO_CONTENT);
+            let (status, _) = health(State(state.clone())).await.unwrap();
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(
+                state.credentials.read().await.pass_key.as_ref(),
+                pass_key.as_ref()
+            );
+
+            if let Some(store) = state.store.write().await.take() {
+                store.close().await.unwrap();
+            }
+            Store::open(&store_uri, Some(StoreKeyMethod::RawKey), pass_key, None)
+                .await
+                .expect("Error opening store with the new key")
+                .close()
+                .await
+                .unwrap();
+            Store::remove(&store_uri).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn rekey_keeps_store_on_error() {
+        block_on(async {
+            let path =
+                std::env::temp_dir().join(format!("askar-admin-{}.db", rand::random::<u64>()));
+            let state = test_state_with_uri(&format!("sqlite://{}", path.display()));
+            // the store cannot be reopened, as it is not held in the configured database
+            let store = Store::provision(
+                "sqlite://:memory:",
+                StoreKeyMethod::RawKey,
+                Store::new_raw_key(None).unwrap(),
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+            *state.store.write().await = Some(store);
+
+            let err = rekey(
+                State(state.clone()),
+                Json(RekeyRequest {
+                    key_method: "raw".to_string(),
+                    pass_key: Some("invalid".to_string()),
+                }),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Input);
+            assert!(state.store.read().await.is_some());
+
+            let pass_key = Store::new_raw_key(None).unwrap();
+            let err = rekey(
+                State(state.clone()),
+                Json(RekeyRequest {
+                    key_method: "raw".to_string(),
+                    pass_key: Some(pass_key.to_string()),
+                }),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+            let (status, _) = health(State(state.clone())).await.unwrap();
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(
+                state.credentials.read().await.pass_key.as_ref(),
+                pass_key.as_ref()
+            );
+
+            let store = state.store.write().await.take();
+            store.unwrap().close().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn serve_requires_loopback() {
+        let config = AdminConfig {
+            listen: "0.0.0.0:0".parse().unwrap(),
+            store_uri: "sqlite://:memory:".to_string(),
+            key_method: None,
+            export_dir: None,
+            api_keys_sha256: vec![token_hash("admin-key")],
+        };
+        let err = block_on(serve(config, PassKey::empty(), async {})).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Input);
+    }
+}
@@ -0,0 +1,201 @@
+//! Audit trail support for security-relevant store and key operations
+//!
+//! Auditing is disabled until a sink is registered with [`set_audit_sink`]. Once
+//! installed, the sink receives an [`AuditRecord`] for every audited operation,
+//! whether or not the operation succeeded.
+
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::SystemTime,
+};
+
+use crate::{error::Error, future::system_time};
+
+static AUDIT_SINK: RwLock<Option<Arc<dyn AuditSink>>> = RwLock::new(None);
+static AUDIT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// A receiver for audit records
+///
+/// Implementations are invoked synchronously on the calling task and should avoid
+/// blocking for long periods, for example by forwarding records to a channel.
+pub trait AuditSink: Send + Sync {
+    /// Handle a single audit record
+    fn record(&self, record: &AuditRecord);
+}
+
+impl<F> AuditSink for F
+where
+    F: Fn(&AuditRecord) + Send + Sync,
+{
+    fn record(&self, record: &AuditRecord) {
+        self(record)
+    }
+}
+
+/// Install the global audit sink, replacing any existing sink
+///
+/// Passing `None` disables auditing.
+pub fn set_audit_sink(sink: Option<Arc<dyn AuditSink>>) {
+    *AUDIT_SINK.write().unwrap() = sink;
+}
+
+/// The category of an audited operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AuditOperation {
+    /// A store was provisioned
+    Provision,
+    /// A store was opened
+    Open,
+    /// The store wrapping key was replaced
+    Rekey,
+    /// The tag search key of a profile was replaced
+    RotateTagKey,
+    /// A profile was created
+    CreateProfile,
+    /// A profile was removed
+    RemoveProfile,
+    /// A profile was renamed
+    RenameProfile,
+    /// A profile was copied into a new profile
+    CloneProfile,
+    /// A profile was archived
+    ArchiveProfile,
+    /// An archived profile was restored
+    UnarchiveProfile,
+    /// An alias was registered for a profile
+    AddProfileAlias,
+    /// A profile alias was removed
+    RemoveProfileAlias,
+    /// A key was added to the store
+    CreateKey,
+    /// A key was removed from the store
+    RemoveKey,
+    /// Secret key material was exported
+    ExportKey,
+    /// A message was signed
+    Sign,
+    /// Multiple entries were removed from a profile
+    RemoveAll,
+    /// Decrypted records were exported
+    ExportPlaintext,
+    /// An encrypted backup segment was written
+    Backup,
+    /// An encrypted backup segment was applied
+    Restore,
+}
+
+impl AuditOperation {
+    /// Convert the operation to a string reference
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Provision => "provision",
+            Self::Open => "open",
+            Self::Rekey => "rekey",
+            Self::RotateTagKey => "rotate_tag_key",
+            Self::CreateProfile => "create_profile",
+            Self::RemoveProfile => "remove_profile",
+            Self::RenameProfile => "rename_profile",
+            Self::CloneProfile => "clone_profile",
+            Self::ArchiveProfile => "archive_profile",
+            Self::UnarchiveProfile => "unarchive_profile",
+            Self::AddProfileAlias => "add_profile_alias",
+            Self::RemoveProfileAlias => "remove_profile_alias",
+            Self::CreateKey => "create_key",
+            Self::RemoveKey => "remove_key",
+            Self::ExportKey => "export_key",
+            Self::Sign => "sign",
+            Self::RemoveAll => "remove_all",
+            Self::ExportPlaintext => "export_plaintext",
+            Self::Backup => "backup",
+            Self::Restore => "restore",
+        }
+    }
+}
+
+impl Display for AuditOperation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A structured record of an audited operation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// A process-wide sequence number, used to detect gaps in the audit trail
+    pub sequence: u64,
+    /// The time at which the operation completed
+    pub timestamp: SystemTime,
+    /// The actor performing the operation, as assigned by `Store::set_audit_actor`
+    pub actor: Option<String>,
+    /// The operation performed
+    pub operation: AuditOperation,
+    /// The store profile affected by the operation, when applicable
+    pub profile: Option<String>,
+    /// The subject of the operation, such as a key name or record category
+    pub target: Option<String>,
+    /// The file read or written by the operation, such as a snapshot
+    pub path: Option<String>,
+    /// The error kind when the operation failed
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// Determine whether the audited operation completed successfully
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Report the outcome of an operation to the audit sink, if one is registered
+pub(crate) fn audit<T>(
+    operation: AuditOperation,
+    actor: Option<&str>,
+    profile: Option<&str>,
+    target: Option<&str>,
+    result: &Result<T, Error>,
+) {
+    audit_record(operation, actor, profile, target, None, result)
+}
+
+/// Report the outcome of an operation on a file to the audit sink, if one is registered
+pub(crate) fn audit_path<T>(
+    operation: AuditOperation,
+    actor: Option<&str>,
+    profile: Option<&str>,
+    path: &str,
+    result: &Result<T, Error>,
+) {
+    audit_record(operation, actor, profile, None, Some(path), result)
+}
+
+fn audit_record<T>(
+    operation: AuditOperation,
+    actor: Option<&str>,
+    profile: Option<&str>,
+    target: Option<&str>,
+    path: Option<&str>,
+    result: &Result<T, Error>,
+) {
+    let sink = match AUDIT_SINK.read().unwrap().as_ref() {
+        Some(sink) => sink.clone(),
+        None => return,
+    };
+    let record = AuditRecord {
+        sequence: AUDIT_SEQUENCE.fetch_add(1, Ordering::SeqCst),
+        timestamp: system_time(),
+        actor: actor.map(str::to_string),
+        operation,
+        profile: profile.map(str::to_string),
+        target: target.map(str::to_string),
+        path: path.map(str::to_string),
+        error: result
+            .as_ref()
+            .err()
+            .map(|err| err.kind().as_str().to_string()),
+    };
+    sink.record(&record);
+}
@@ -0,0 +1,42 @@
+//! Serve the administrative HTTP API for an Askar store
+//!
+//! Usage: `askar-admin <config.json>`. The store pass key is read from the
+//! `ASKAR_PASS_KEY` environment variable.
+
+use std::{env, fs, process};
+
+use aries_askar::{
+    admin::{serve, AdminConfig},
+    PassKey,
+};
+
+#[tokio::main]
+async fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: askar-admin <config.json>");
+            process::exit(2);
+        }
+    };
+    let config: AdminConfig = match fs::read(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|data| serde_json::from_slice(&data).map_err(|err| err.to_string()))
+    {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error loading configuration: {err}");
+            process::exit(1);
+        }
+    };
+    let pass_key = env::var("ASKAR_PASS_KEY").ok();
+    env::remove_var("ASKAR_PASS_KEY");
+
+    let shutdown = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+    if let Err(err) = serve(config, PassKey::from(pass_key.as_deref()), shutdown).await {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,5 @@
+//! Generate Kotlin and Swift bindings for the UniFFI interfaces
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,243 @@
+//! Builder-style provisioning and opening of stores
+//!
+//! A [`StoreBuilder`] collects the options for provisioning or opening a
+//! store, in place of the positional arguments of [`Store::provision`] and
+//! [`Store::open`]. Pool tuning options are added to the query of the store
+//! URI, unless the URI already sets them.
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    error::Error,
+    hooks::StoreHook,
+    storage::Options,
+    store::{KdfMethod, PassKey, Store, StoreKeyMethod},
+};
+
+/// Options for provisioning or opening a store
+pub struct StoreBuilder<'a> {
+    db_url: String,
+    key_method: Option<StoreKeyMethod>,
+    pass_key: PassKey<'a>,
+    profile: Option<String>,
+    recreate: bool,
+    create_missing: bool,
+    kdf_upgrade: Option<KdfMethod>,
+    options: Vec<(String, String)>,
+    audit_actor: Option<String>,
+    hooks: Vec<Arc<dyn StoreHook>>,
+}
+
+impl<'a> StoreBuilder<'a> {
+    /// Start building a store for a database URL
+    pub fn new(db_url: impl Into<String>) -> Self {
+        Self {
+            db_url: db_url.into(),
+            key_method: None,
+            pass_key: PassKey::empty(),
+            profile: None,
+            recreate: false,
+            create_missing: false,
+            kdf_upgrade: None,
+            options: Vec::new(),
+            audit_actor: None,
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Set the method used to protect the store key
+    ///
+    /// When provisioning, the default method derives the store key from the
+    /// pass key using Argon2i. When opening, the method recorded in the store
+    /// is used if none is given.
+    pub fn with_key_method(mut self, key_method: StoreKeyMethod) -> Self {
+        self.key_method = Some(key_method);
+        self
+    }
+
+    /// Set the pass key used to unlock the store
+    pub fn with_pass_key(mut self, pass_key: PassKey<'a>) -> Self {
+        self.pass_key = pass_key;
+        self
+    }
+
+    /// Set the profile used by the store instance
+    ///
+    /// When provisioning, this is also the default profile of the store.
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Remove any existing store when provisioning
+    pub fn with_recreate(mut self, recreate: bool) -> Self {
+        self.recreate = recreate;
+        self
+    }
+
+    /// Provision the store when opening a store which does not exist
+    pub fn with_create_missing(mut self, create_missing: bool) -> Self {
+        self.create_missing = create_missing;
+        self
+    }
+
+    /// Upgrade the key derivation of an existing store once it is unlocked
+    ///
+    /// When the wrapping key of the store is derived using a weaker method than
+    /// `method`, it is derived again from the same pass key, as by
+    /// [`Store::upgrade_kdf`]. Opening the store fails if the upgrade fails.
+    pub fn with_kdf_upgrade(mut self, method: KdfMethod) -> Self {
+        self.kdf_upgrade = Some(method);
+        self
+    }
+
+    /// Limit the number of connections held by the pool of the store
+    pub fn with_max_connections(self, max_connections: u32) -> Self {
+        self.with_option("max_connections", max_connections.to_string())
+    }
+
+    /// Set the number of connections kept open by the pool of the store
+    pub fn with_min_connections(self, min_connections: u32) -> Self {
+        self.with_option("min_connections", min_connections.to_string())
+    }
+
+    /// Set the time to wait for a connection to the database, in whole seconds
+    ///
+    /// This is supported by the Postgres and ODBC backends.
+    pub fn with_connect_timeout(self, timeout: Duration) -> Self {
+        self.with_option("connect_timeout", timeout.as_secs().to_string())
+    }
+
+    /// Set the time after which idle connections are closed, in whole seconds
+    ///
+    /// This is supported by the Postgres and ODBC backends.
+    pub fn with_idle_timeout(self, timeout: Duration) -> Self {
+        self.with_option("idle_timeout", timeout.as_secs().to_string())
+    }
+
+    /// Add a query parameter to the store URI, unless the URI already sets it
+    ///
+    /// This accepts any of the options supported by the backend of the store.
+    pub fn with_option(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        let value = value.into();
+        if let Some(option) = self.options.iter_mut().find(|(n, _)| *n == name) {
+            option.1 = value;
+        } else {
+            self.options.push((name, value));
+        }
+        self
+    }
+
+    /// Assign the actor reported in audit records for the store
+    pub fn with_audit_actor(mut self, actor: impl Into<String>) -> Self {
+        self.audit_actor = Some(actor.into());
+        self
+    }
+
+    /// Register a hook on the store
+    pub fn with_hook(mut self, hook: Arc<dyn StoreHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Provision a new store
+    pub async fn provision(self) -> Result<Store, Error> {
+        let uri = self.uri()?;
+        let store = Store::provision(
+            &uri,
+            self.key_method.unwrap_or_default(),
+            self.pass_key,
+            self.profile,
+            self.recreate,
+        )
+        .await?;
+        Ok(Self::configure(store, self.audit_actor, self.hooks))
+    }
+
+    /// Open an existing store
+    ///
+    /// If the store does not exist and `create_missing` is set, then the store
+    /// is provisioned instead. A missing profile within an existing store is
+    /// still reported as an error.
+    pub async fn open(self) -> Result<Store, Error> {
+        let uri = self.uri()?;
+        let store = match Store::open(
+            &uri,
+            self.key_method.clone(),
+            self.pass_key.as_ref(),
+            self.profile.clone(),
+        )
+        .await
+        {
+            Ok(mut store) => {
+                if let Some(method) = self.kdf_upgrade {
+                    store.upgrade_kdf(method, self.pass_key).await?;
+                }
+                store
+            }
+            Err(err) if self.create_missing && err.is_store_not_found() => {
+                Store::provision(
+                    &uri,
+                    self.key_method.unwrap_or_default(),
+                    self.pass_key,
+                    self.profile,
+                    false,
+                )
+                .await?
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(Self::configure(store, self.audit_actor, self.hooks))
+    }
+
+    fn uri(&self) -> Result<String, Error> {
+        if self.options.is_empty() {
+            return Ok(self.db_url.clone());
+        }
+        Ok(Options::extend_uri_query(
+            &self.db_url,
+            self.options
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        )?)
+    }
+
+    fn configure(
+        mut store: Store,
+        audit_actor: Option<String>,
+        hooks: Vec<Arc<dyn StoreHook>>,
+    ) -> Store {
+        store.set_audit_actor(audit_actor);
+        for hook in hooks {
+            store.add_hook(hook);
+        }
+        store
+    }
+}
+
+impl Debug for StoreBuilder<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // the database URL and options may include account passwords
+        let options = self
+            .options
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        f.debug_struct("StoreBuilder")
+            .field("key_method", &self.key_method)
+            .field("pass_key", &self.pass_key)
+            .field("profile", &self.profile)
+            .field("recreate", &self.recreate)
+            .field("create_missing", &self.create_missing)
+            .field("kdf_upgrade", &self.kdf_upgrade)
+            .field("options", &options)
+            .field("audit_actor", &self.audit_actor)
+            .field("hooks", &self.hooks.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -2,17 +2,28 @@ use std::error::Error as StdError;
 use std::fmt::{self, Display, Formatter};
 
 use crate::crypto::{Error as CryptoError, ErrorKind as CryptoErrorKind};
-use crate::storage::{Error as StorageError, ErrorKind as StorageErrorKind};
+use crate::storage::{
+    backend_error_info, entry::EntryKind, BackendErrorInfo, Error as StorageError,
+    ErrorKind as StorageErrorKind, StoreNotFound,
+};
+
+pub use crate::storage::ErrorContext;
 
 /// The possible kinds of error produced by the crate
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ErrorKind {
+    /// A write operation was rejected because the profile is archived
+    Archived,
+
     /// An unexpected error from the store backend
     Backend,
 
     /// The store backend was too busy to handle the request
     Busy,
 
+    /// The operation was cancelled before it completed
+    Cancelled,
+
     /// A custom error type for external integrations
     Custom,
 
@@ -28,6 +39,9 @@ pub enum ErrorKind {
     /// The requested record was not found
     NotFound,
 
+    /// A write operation would exceed the limits configured for a profile
+    QuotaExceeded,
+
     /// An unexpected error occurred
     Unexpected,
 
@@ -39,13 +53,16 @@ impl ErrorKind {
     /// Convert the error kind to a string reference
     pub fn as_str(&self) -> &'static str {
         match self {
+            Self::Archived => "Archived",
             Self::Backend => "Backend error",
             Self::Busy => "Busy",
+            Self::Cancelled => "Cancelled",
             Self::Custom => "Custom error",
             Self::Duplicate => "Duplicate",
             Self::Encryption => "Encryption error",
             Self::Input => "Input error",
             Self::NotFound => "Not found",
+            Self::QuotaExceeded => "Quota exceeded",
             Self::Unexpected => "Unexpected error",
             Self::Unsupported => "Unsupported",
         }
@@ -64,6 +81,7 @@ pub struct Error {
     pub(crate) kind: ErrorKind,
     pub(crate) cause: Option<Box<dyn StdError + Send + Sync + 'static>>,
     pub(crate) message: Option<String>,
+    pub(crate) context: Option<Box<ErrorContext>>,
 }
 
 impl Error {
@@ -72,6 +90,7 @@ impl Error {
             kind,
             cause: None,
             message: Some(msg.into()),
+            context: None,
         }
     }
 
@@ -85,6 +104,52 @@ impl Error {
         self.message.as_deref()
     }
 
+    /// Accessor for the details of the database driver error which caused this error
+    pub fn backend_info(&self) -> Option<BackendErrorInfo> {
+        backend_error_info(self)
+    }
+
+    /// Accessor for the context of the store operation which produced this error
+    pub fn context(&self) -> Option<&ErrorContext> {
+        self.context.as_deref()
+    }
+
+    /// Accessor for the name of the store operation which produced this error
+    pub fn operation(&self) -> Option<&'static str> {
+        self.context.as_ref().and_then(|c| c.operation)
+    }
+
+    /// Accessor for the kind of store backend which produced this error
+    pub fn backend_kind(&self) -> Option<&'static str> {
+        self.context.as_ref().and_then(|c| c.backend)
+    }
+
+    /// Determine whether a profile name was provided for the failed operation
+    pub fn has_profile(&self) -> Option<bool> {
+        self.context.as_ref().and_then(|c| c.has_profile)
+    }
+
+    /// Accessor for the kind of entry accessed by the failed operation
+    pub fn entry_kind(&self) -> Option<EntryKind> {
+        self.context.as_ref().and_then(|c| c.entry_kind)
+    }
+
+    /// Determine whether the error reports that the store itself does not exist
+    pub fn is_store_not_found(&self) -> bool {
+        self.kind == ErrorKind::NotFound
+            && self
+                .cause
+                .as_ref()
+                .is_some_and(|cause| cause.is::<StoreNotFound>())
+    }
+
+    /// Determine whether the failed operation may succeed if retried
+    pub fn is_retryable(&self) -> bool {
+        self.kind == ErrorKind::Busy
+            || (self.kind == ErrorKind::Backend
+                && self.backend_info().map(|b| b.transient).unwrap_or(false))
+    }
+
     pub(crate) fn with_cause<T: Into<Box<dyn StdError + Send + Sync + 'static>>>(
         mut self,
         err: T,
@@ -101,6 +166,9 @@ impl Display for Error {
         } else {
             f.write_str(self.kind.as_str())?;
         }
+        if let Some(context) = self.context.as_ref() {
+            write!(f, " ({})", context)?;
+        }
         if let Some(cause) = self.cause.as_ref() {
             write!(f, "\nCaused by: {}", cause)?;
         }
@@ -128,6 +196,7 @@ impl From<ErrorKind> for Error {
             kind,
             cause: None,
             message: None,
+            context: None,
         }
     }
 }
@@ -151,8 +220,10 @@ impl From<CryptoError> for Error {
 
 impl From<StorageError> for Error {
     fn from(err: StorageError) -> Self {
+        let context = err.context().copied().map(Box::new);
         let (kind, cause, message) = err.into_parts();
         let kind = match kind {
+            StorageErrorKind::Archived => ErrorKind::Archived,
             StorageErrorKind::Backend => ErrorKind::Backend,
             StorageErrorKind::Busy => ErrorKind::Busy,
             StorageErrorKind::Custom => ErrorKind::Custom,
@@ -160,6 +231,7 @@ impl From<StorageError> for Error {
             StorageErrorKind::Encryption => ErrorKind::Encryption,
             StorageErrorKind::Input => ErrorKind::Input,
             StorageErrorKind::NotFound => ErrorKind::NotFound,
+            StorageErrorKind::QuotaExceeded => ErrorKind::QuotaExceeded,
             StorageErrorKind::Unexpected => ErrorKind::Unexpected,
             StorageErrorKind::Unsupported => ErrorKind::Unsupported,
         };
@@ -167,6 +239,7 @@ impl From<StorageError> for Error {
             kind,
             cause,
             message,
+            context,
         }
     }
 }
@@ -0,0 +1,120 @@
+use std::fmt;
+
+use serde::{
+    de::{Error as SerdeError, Visitor},
+    Deserialize, Deserializer,
+};
+
+use super::tags::EntryTagSet;
+use crate::{crypto::buffer::SecretBytes, error::Error};
+
+/// A record of a batch insert, decoded from a CBOR map
+///
+/// The `value` may be a byte string or a text string, and the optional `tags`
+/// map follows the format accepted for JSON-encoded tags.
+#[derive(Deserialize)]
+pub(crate) struct BatchEntry {
+    pub category: String,
+    pub name: String,
+    pub value: BatchValue,
+    #[serde(default)]
+    pub tags: Option<EntryTagSet<'static>>,
+    #[serde(default)]
+    pub expiry_ms: Option<i64>,
+}
+
+/// Decode a CBOR array of batch entries
+pub(crate) fn decode_batch(data: &[u8]) -> Result<Vec<BatchEntry>, Error> {
+    serde_cbor::from_slice(data).map_err(err_map!(Input, "Error decoding entry batch"))
+}
+
+/// The value of a batch entry
+pub(crate) struct BatchValue(pub SecretBytes);
+
+impl<'de> Deserialize<'de> for BatchValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ValueVisitor;
+
+        impl<'d> Visitor<'d> for ValueVisitor {
+            type Value = BatchValue;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a byte string or text string")
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                Ok(BatchValue(SecretBytes::from_slice(value)))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                Ok(BatchValue(SecretBytes::from_slice(value.as_bytes())))
+            }
+        }
+
+        deserializer.deserialize_bytes(ValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_cbor::Value;
+
+    use super::*;
+    use crate::entry::EntryTag;
+
+    #[test]
+    fn decode_entry_batch() {
+        let text = |s: &str| Value::Text(s.to_owned());
+        let first = BTreeMap::from([
+            (text("category"), text("cat")),
+            (text("name"), text("a")),
+            (text("value"), Value::Bytes(b"value-a".to_vec())),
+            (
+                text("tags"),
+                Value::Map(BTreeMap::from([
+                    (text("enc"), text("x")),
+                    (text("~plain"), Value::Array(vec![text("y"), text("z")])),
+                ])),
+            ),
+            (text("expiry_ms"), Value::Integer(1000)),
+        ]);
+        let second = BTreeMap::from([
+            (text("category"), text("cat")),
+            (text("name"), text("b")),
+            (text("value"), text("value-b")),
+        ]);
+        let data =
+            serde_cbor::to_vec(&Value::Array(vec![Value::Map(first), Value::Map(second)])).unwrap();
+
+        let batch = decode_batch(&data).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].value.0.as_ref(), b"value-a");
+        assert_eq!(
+            batch[0].tags.clone().map(EntryTagSet::into_vec),
+            Some(vec![
+                EntryTag::Encrypted("enc".to_owned(), "x".to_owned()),
+                EntryTag::Plaintext("plain".to_owned(), "y".to_owned()),
+                EntryTag::Plaintext("plain".to_owned(), "z".to_owned()),
+            ])
+        );
+        assert_eq!(batch[0].expiry_ms, Some(1000));
+        assert_eq!(
+            (batch[1].name.as_str(), batch[1].value.0.as_ref()),
+            ("b", &b"value-b"[..])
+        );
+        assert!(batch[1].tags.is_none());
+
+        assert!(decode_batch(b"invalid").is_err());
+    }
+}
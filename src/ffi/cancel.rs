@@ -0,0 +1,143 @@
+use std::{
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use async_lock::Semaphore;
+
+use super::{handle::ArcHandle, ErrorCode};
+use crate::error::Error;
+
+/// A token used to cancel in-flight operations started over FFI
+#[derive(Debug)]
+pub struct CancelToken {
+    cancelled: AtomicBool,
+    // a permit is added when the token is cancelled, and returned by each waiter
+    notify: Semaphore,
+}
+
+pub type CancelHandle = ArcHandle<CancelToken>;
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self {
+            cancelled: AtomicBool::new(false),
+            notify: Semaphore::new(0),
+        }
+    }
+
+    /// Request the cancellation of all operations using this token
+    pub fn cancel(&self) {
+        if !self.cancelled.swap(true, Ordering::AcqRel) {
+            self.notify.add_permits(1);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Return a `Cancelled` error if the token has been cancelled
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_cancelled() {
+            Err(err_msg!(Cancelled, "Operation cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn cancelled(&self) -> Error {
+        drop(self.notify.acquire().await);
+        err_msg!(Cancelled, "Operation cancelled")
+    }
+
+    /// Run a future to completion, unless the token is cancelled first
+    pub async fn run<T>(&self, fut: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+        self.check()?;
+        futures_lite::future::or(fut, async { Err(self.cancelled().await) }).await
+    }
+}
+
+impl CancelHandle {
+    /// Load the token for an optional cancellation handle
+    pub fn load_opt(&self) -> Option<std::sync::Arc<CancelToken>> {
+        self.load().ok()
+    }
+}
+
+/// Run a future to completion, unless the optional token is cancelled first
+pub async fn run_cancellable<T>(
+    token: Option<&CancelToken>,
+    fut: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    match token {
+        Some(token) => token.run(fut).await,
+        None => fut.await,
+    }
+}
+
+/// Create a new cancellation token
+///
+/// The token may be passed to operations which accept a cancellation handle, and
+/// must be released using `askar_cancel_token_free`.
+#[no_mangle]
+pub extern "C" fn askar_cancel_token_create(out: *mut CancelHandle) -> ErrorCode {
+    catch_err! {
+        trace!("Create cancellation token");
+        check_useful_c_ptr!(out);
+        unsafe { *out = CancelHandle::create(CancelToken::new()) };
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Cancel the operations associated with a cancellation token
+///
+/// Pending operations complete with a `Cancelled` error, as do any further
+/// operations started with the token.
+#[no_mangle]
+pub extern "C" fn askar_cancel_token_cancel(handle: CancelHandle) -> ErrorCode {
+    catch_err! {
+        trace!("Cancel token");
+        handle.load()?.cancel();
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_cancel_token_free(handle: CancelHandle) {
+    handle.remove();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use futures_lite::future::zip;
+
+    use super::*;
+    use crate::{
+        error::ErrorKind,
+        future::{block_on, sleep},
+    };
+
+    #[test]
+    fn cancel_pending_operations() {
+        let token = Arc::new(CancelToken::new());
+        let pending = || {
+            token.run(async {
+                sleep(Duration::from_secs(30)).await;
+                Ok(())
+            })
+        };
+        let ((first, second), ()) = block_on(zip(zip(pending(), pending()), async {
+            sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        }));
+        assert_eq!(first.unwrap_err().kind(), ErrorKind::Cancelled);
+        assert_eq!(second.unwrap_err().kind(), ErrorKind::Cancelled);
+        assert_eq!(
+            block_on(token.run(async { Ok(()) })).unwrap_err().kind(),
+            ErrorKind::Cancelled
+        );
+    }
+}
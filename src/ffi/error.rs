@@ -1,5 +1,7 @@
 use crate::error::{Error, ErrorKind};
+use crate::storage::entry::EntryKind;
 
+use std::cell::RefCell;
 use std::os::raw::c_char;
 use std::sync::RwLock;
 
@@ -9,6 +11,49 @@ use once_cell::sync::Lazy;
 
 static LAST_ERROR: Lazy<RwLock<Option<Error>>> = Lazy::new(|| RwLock::new(None));
 
+thread_local! {
+    static LAST_ERROR_DETAILS: RefCell<Option<ErrorDetails>> = const { RefCell::new(None) };
+}
+
+/// Structured details of the last error raised on a thread
+#[derive(Debug, Serialize)]
+struct ErrorDetails {
+    code: usize,
+    kind: ErrorCode,
+    message: String,
+    backend_code: Option<&'static str>,
+    sqlstate: Option<String>,
+    operation: Option<&'static str>,
+    store_operation: Option<&'static str>,
+    backend: Option<&'static str>,
+    has_profile: Option<bool>,
+    entry_kind: Option<&'static str>,
+    retryable: bool,
+}
+
+impl ErrorDetails {
+    fn new(err: &Error, operation: Option<&'static str>) -> Self {
+        let kind = ErrorCode::from(err.kind());
+        let backend = err.backend_info();
+        Self {
+            code: kind as usize,
+            kind,
+            message: err.to_string(),
+            backend_code: backend.as_ref().map(|b| b.code.as_str()),
+            sqlstate: backend.and_then(|b| b.sqlstate),
+            operation,
+            store_operation: err.operation(),
+            backend: err.backend_kind(),
+            has_profile: err.has_profile(),
+            entry_kind: err.entry_kind().map(|kind| match kind {
+                EntryKind::Kms => "kms",
+                EntryKind::Item => "item",
+            }),
+            retryable: err.is_retryable(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize)]
 #[repr(i64)]
 pub enum ErrorCode {
@@ -21,19 +66,25 @@ pub enum ErrorCode {
     NotFound = 6,
     Unexpected = 7,
     Unsupported = 8,
+    Cancelled = 9,
+    QuotaExceeded = 10,
+    Archived = 11,
     Custom = 100,
 }
 
 impl From<ErrorKind> for ErrorCode {
     fn from(kind: ErrorKind) -> ErrorCode {
         match kind {
+            ErrorKind::Archived => ErrorCode::Archived,
             ErrorKind::Backend => ErrorCode::Backend,
             ErrorKind::Busy => ErrorCode::Busy,
+            ErrorKind::Cancelled => ErrorCode::Cancelled,
             ErrorKind::Custom => ErrorCode::Custom,
             ErrorKind::Duplicate => ErrorCode::Duplicate,
             ErrorKind::Encryption => ErrorCode::Encryption,
             ErrorKind::Input => ErrorCode::Input,
             ErrorKind::NotFound => ErrorCode::NotFound,
+            ErrorKind::QuotaExceeded => ErrorCode::QuotaExceeded,
             ErrorKind::Unexpected => ErrorCode::Unexpected,
             ErrorKind::Unsupported => ErrorCode::Unsupported,
         }
@@ -59,6 +110,30 @@ pub extern "C" fn askar_get_current_error(error_json_p: *mut *const c_char) -> E
     ErrorCode::Success
 }
 
+/// Get structured details of the last error raised on the calling thread as JSON.
+///
+/// For asynchronous operations this must be called from within the callback. The
+/// result contains the error `code`, `kind` and `message`, the `backend_code` and
+/// database `sqlstate` when known, the name of the FFI `operation`, the context of
+/// the failed store operation (`store_operation`, `backend`, `has_profile` and
+/// `entry_kind`) when known, and whether the error is `retryable`.
+#[no_mangle]
+pub extern "C" fn askar_get_current_error_details(details_json_p: *mut *const c_char) -> ErrorCode {
+    trace!("askar_get_current_error_details");
+
+    let details = rust_string_to_c(get_current_error_details_json());
+    unsafe { *details_json_p = details };
+
+    ErrorCode::Success
+}
+
+pub fn get_current_error_details_json() -> String {
+    match LAST_ERROR_DETAILS.with(|details| details.borrow_mut().take()) {
+        Some(details) => serde_json::to_string(&details).unwrap(),
+        None => r#"{"code":0,"message":null}"#.to_owned(),
+    }
+}
+
 pub fn get_current_error_json() -> String {
     #[derive(Serialize)]
     struct ErrorJson {
@@ -76,11 +151,60 @@ pub fn get_current_error_json() -> String {
 }
 
 pub fn set_last_error(error: Option<Error>) -> ErrorCode {
+    record_last_error(error, None)
+}
+
+/// Record the last error along with the name of the FFI operation which produced it
+pub fn set_operation_error(operation: &'static str, error: Error) -> ErrorCode {
+    record_last_error(Some(error), Some(operation))
+}
+
+fn record_last_error(error: Option<Error>, operation: Option<&'static str>) -> ErrorCode {
     trace!("askar_set_last_error");
     let code = match error.as_ref() {
         Some(err) => err.kind.into(),
         None => ErrorCode::Success,
     };
+    let details = error.as_ref().map(|err| ErrorDetails::new(err, operation));
+    LAST_ERROR_DETAILS.with(|last| *last.borrow_mut() = details);
     *LAST_ERROR.write().unwrap() = error;
     code
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_details_json() {
+        set_operation_error("askar_test", err_msg!(Busy, "Pool exhausted"));
+        let details: serde_json::Value =
+            serde_json::from_str(&get_current_error_details_json()).unwrap();
+        assert_eq!(
+            details,
+            serde_json::json!({
+                "code": 2,
+                "kind": "Busy",
+                "message": "Pool exhausted",
+                "backend_code": null,
+                "sqlstate": null,
+                "operation": "askar_test",
+                "store_operation": null,
+                "backend": null,
+                "has_profile": null,
+                "entry_kind": null,
+                "retryable": true,
+            })
+        );
+        assert_eq!(
+            get_current_error_details_json(),
+            r#"{"code":0,"message":null}"#
+        );
+
+        set_last_error(Some(err_msg!(Input, "Invalid tag filter")));
+        let details: serde_json::Value =
+            serde_json::from_str(&get_current_error_details_json()).unwrap();
+        assert_eq!(details["operation"], serde_json::Value::Null);
+        assert_eq!(details["retryable"], false);
+    }
+}
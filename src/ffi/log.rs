@@ -3,11 +3,13 @@ use std::os::raw::{c_char, c_void};
 use std::ptr;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use ffi_support::FfiStr;
 use log::{LevelFilter, Metadata, Record};
 use once_cell::sync::OnceCell;
 
 use super::error::ErrorCode;
 use crate::error::Error;
+use crate::storage::redact::{set_redaction_policy, Redaction, RedactionPolicy};
 
 static LOGGER: OnceCell<CustomLogger> = OnceCell::new();
 
@@ -151,6 +153,28 @@ pub extern "C" fn askar_set_max_log_level(max_level: i32) -> ErrorCode {
     }
 }
 
+/// Set the process-wide policy for rendering record categories, names and tag
+/// names in log messages. Each is one of `plain`, `hashed`, `omitted` or
+/// `truncated:<len>`, and defaults to `plain` when not provided.
+#[no_mangle]
+pub extern "C" fn askar_set_redaction_policy(
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    tag_name: FfiStr<'_>,
+) -> ErrorCode {
+    catch_err! {
+        let parse = |value: FfiStr<'_>| -> Result<Redaction, Error> {
+            Ok(value.as_opt_str().map(str::parse).transpose()?.unwrap_or_default())
+        };
+        set_redaction_policy(RedactionPolicy {
+            category: parse(category)?,
+            name: parse(name)?,
+            tag_name: parse(tag_name)?,
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
 fn get_level_filter(max_level: i32) -> Result<LevelFilter, Error> {
     Ok(match max_level {
         -1 => {
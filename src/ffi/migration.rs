@@ -4,7 +4,9 @@ use crate::storage::future::spawn_ok;
 use crate::storage::migration::IndySdkToAriesAskarMigration;
 
 use super::{
-    error::{set_last_error, ErrorCode},
+    cancel::{run_cancellable, CancelHandle},
+    error::{set_operation_error, ErrorCode},
+    store::StoreHandle,
     CallbackId, EnsureCallback,
 };
 
@@ -18,6 +20,9 @@ use super::{
 /// 4. Initialize a profile
 /// 5. Update the items from the indy-sdk
 /// 6. Clean up (drop tables and add a version of "1")
+///
+/// The migration modifies the wallet in place and does not accept a cancellation token,
+/// as stopping it part way would leave the wallet unusable.
 #[no_mangle]
 pub extern "C" fn askar_migrate_indy_sdk(
     spec_uri: FfiStr<'_>,
@@ -38,7 +43,7 @@ pub extern "C" fn askar_migrate_indy_sdk(
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(_) => cb(cb_id, ErrorCode::Success),
-                Err(err) => cb(cb_id, set_last_error(Some(err))),
+                Err(err) => cb(cb_id, set_operation_error("askar_migrate_indy_sdk", err)),
         });
 
         spawn_ok(async move {
@@ -52,3 +57,75 @@ pub extern "C" fn askar_migrate_indy_sdk(
         Ok(ErrorCode::Success)
     )
 }
+
+/// Import the records of an indy-sdk sqlite wallet into a profile of an open store,
+/// which may use any backend including ODBC. The wallet itself is left unchanged.
+/// The callback receives the number of imported records.
+#[no_mangle]
+pub extern "C" fn askar_migrate_indy_sdk_into_store(
+    spec_uri: FfiStr<'_>,
+    wallet_name: FfiStr<'_>,
+    wallet_key: FfiStr<'_>,
+    kdf_level: FfiStr<'_>,
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, count: i64)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    askar_migrate_indy_sdk_into_store_v2(
+        spec_uri,
+        wallet_name,
+        wallet_key,
+        kdf_level,
+        handle,
+        profile,
+        CancelHandle::invalid(),
+        cb,
+        cb_id,
+    )
+}
+
+/// Import the records of an indy-sdk sqlite wallet into a profile of an open store,
+/// optionally using a cancellation token. A cancelled import may leave some of the
+/// records in the target profile.
+#[no_mangle]
+pub extern "C" fn askar_migrate_indy_sdk_into_store_v2(
+    spec_uri: FfiStr<'_>,
+    wallet_name: FfiStr<'_>,
+    wallet_key: FfiStr<'_>,
+    kdf_level: FfiStr<'_>,
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    cancel: CancelHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, count: i64)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err!(
+        trace!("Import sqlite wallet from indy-sdk structure into store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let spec_uri = spec_uri.into_opt_string().ok_or_else(|| err_msg!("No provision spec URI provided"))?;
+        let wallet_name = wallet_name.into_opt_string().ok_or_else(|| err_msg!("No wallet name provided"))?;
+        let wallet_key = wallet_key.into_opt_string().ok_or_else(|| err_msg!("No wallet key provided"))?;
+        let kdf_level = kdf_level.into_opt_string().ok_or_else(|| err_msg!("No KDF level provided"))?;
+        let profile = profile.into_opt_string();
+        let cancel = cancel.load_opt();
+
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(count) => cb(cb_id, ErrorCode::Success, count as i64),
+                Err(err) => cb(cb_id, set_operation_error("askar_migrate_indy_sdk_into_store_v2", err), 0),
+        });
+
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                run_cancellable(
+                    cancel.as_deref(),
+                    store.migrate_indy_sdk(&spec_uri, &wallet_name, &wallet_key, &kdf_level, profile, false),
+                ).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    )
+}
@@ -10,6 +10,30 @@ static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 pub static LIB_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The version of the FFI interface, incremented when the signature or
+/// behaviour of an existing function changes incompatibly
+pub const ABI_VERSION: u32 = 1;
+
+/// The key algorithms supported by the library
+const KEY_ALGORITHMS: &[KeyAlg] = &[
+    KeyAlg::Aes(AesTypes::A128Gcm),
+    KeyAlg::Aes(AesTypes::A256Gcm),
+    KeyAlg::Aes(AesTypes::A128CbcHs256),
+    KeyAlg::Aes(AesTypes::A256CbcHs512),
+    KeyAlg::Aes(AesTypes::A128Kw),
+    KeyAlg::Aes(AesTypes::A256Kw),
+    KeyAlg::Bls12_381(BlsCurves::G1),
+    KeyAlg::Bls12_381(BlsCurves::G2),
+    KeyAlg::Bls12_381(BlsCurves::G1G2),
+    KeyAlg::Chacha20(Chacha20Types::C20P),
+    KeyAlg::Chacha20(Chacha20Types::XC20P),
+    KeyAlg::Ed25519,
+    KeyAlg::X25519,
+    KeyAlg::EcCurve(EcCurves::Secp256k1),
+    KeyAlg::EcCurve(EcCurves::Secp256r1),
+    KeyAlg::EcCurve(EcCurves::Secp384r1),
+];
+
 #[macro_use]
 mod handle;
 use self::handle::ResourceHandle;
@@ -17,6 +41,8 @@ use self::handle::ResourceHandle;
 #[macro_use]
 mod macros;
 
+mod batch;
+mod cancel;
 mod error;
 mod key;
 mod log;
@@ -29,7 +55,14 @@ mod tags;
 mod migration;
 
 use self::error::ErrorCode;
-use crate::error::Error;
+use crate::{
+    crypto::{
+        alg::{AesTypes, BlsCurves, Chacha20Types, EcCurves, KeyAlg},
+        backend::KeyBackend,
+    },
+    error::Error,
+    future::BlockingPoolConfig,
+};
 
 pub type CallbackId = i64;
 
@@ -70,7 +103,109 @@ pub extern "C" fn askar_terminate() {
     crate::future::shutdown(Duration::from_secs(5));
 }
 
+/// Configure the thread pool used for blocking operations. This must be called once,
+/// before any other asynchronous operation, and negative values select the defaults.
+#[no_mangle]
+pub extern "C" fn askar_set_blocking_pool(
+    max_threads: i64,
+    keep_alive_ms: i64,
+    max_pending: i64,
+) -> ErrorCode {
+    catch_err! {
+        let limit = |value: i64| (value >= 0).then_some(value as usize);
+        crate::future::configure_blocking_pool(BlockingPoolConfig {
+            max_threads: limit(max_threads),
+            keep_alive: (keep_alive_ms >= 0).then(|| Duration::from_millis(keep_alive_ms as u64)),
+            max_pending: limit(max_pending),
+        })?;
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Report the hardware acceleration used by each cryptographic primitive as a
+/// JSON object, with values such as `aes-ni` or `portable`.
+#[no_mangle]
+pub extern "C" fn askar_crypto_acceleration() -> *mut c_char {
+    let accel = crate::crypto::accel::crypto_acceleration();
+    let report = serde_json::json!({
+        "aes": accel.aes.as_str(),
+        "polyval": accel.polyval.as_str(),
+        "chacha20": accel.chacha20.as_str(),
+        "sha256": accel.sha256.as_str(),
+        "sha512": accel.sha512.as_str(),
+    });
+    rust_string_to_c(report.to_string())
+}
+
+/// Report the capabilities of the library as a JSON object, including the FFI
+/// ABI version, the supported key algorithms and key backends, the storage
+/// backends compiled in along with their schema versions, and optional features.
+#[no_mangle]
+pub extern "C" fn askar_capabilities() -> *mut c_char {
+    rust_string_to_c(capabilities().to_string())
+}
+
+fn capabilities() -> serde_json::Value {
+    let mut storage_backends = serde_json::Map::new();
+    #[cfg(feature = "sqlite")]
+    storage_backends.insert(
+        "sqlite".to_string(),
+        serde_json::json!({
+            "schema_version": crate::storage::sqlite::supported_schema_version(),
+        }),
+    );
+    #[cfg(feature = "postgres")]
+    storage_backends.insert(
+        "postgres".to_string(),
+        serde_json::json!({
+            "schema_version": crate::storage::postgres::supported_schema_version(),
+        }),
+    );
+    #[cfg(feature = "odbc")]
+    storage_backends.insert("odbc".to_string(), serde_json::json!({}));
+
+    let mut key_backends: Vec<&str> = vec![KeyBackend::Software.into()];
+    if cfg!(feature = "mobile_secure_element") {
+        key_backends.push(KeyBackend::SecureElement.into());
+    }
+
+    serde_json::json!({
+        "version": LIB_VERSION,
+        "abi_version": ABI_VERSION,
+        "key_algorithms": KEY_ALGORITHMS.iter().map(KeyAlg::as_str).collect::<Vec<_>>(),
+        "key_backends": key_backends,
+        "storage_backends": storage_backends,
+        "features": {
+            "force_soft": cfg!(feature = "force_soft"),
+            "jemalloc": cfg!(feature = "jemalloc"),
+            "logger": cfg!(feature = "logger"),
+            "migration": cfg!(feature = "migration"),
+            "plaintext_export": cfg!(feature = "plaintext_export"),
+        },
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn askar_version() -> *mut c_char {
     rust_string_to_c(LIB_VERSION.to_owned())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn capabilities_key_algorithms() {
+        let caps = capabilities();
+        assert_eq!(caps["abi_version"], ABI_VERSION);
+        let algs = caps["key_algorithms"].as_array().unwrap();
+        assert_eq!(algs.len(), KEY_ALGORITHMS.len());
+        for alg in algs {
+            KeyAlg::from_str(alg.as_str().unwrap()).unwrap();
+        }
+        #[cfg(feature = "sqlite")]
+        assert!(caps["storage_backends"]["sqlite"]["schema_version"].as_i64() > Some(0));
+    }
+}
@@ -208,6 +208,29 @@ pub extern "C" fn askar_key_entry_list_get_metadata(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_key_entry_list_get_origin(
+    handle: KeyEntryListHandle,
+    index: i32,
+    origin: *mut *const c_char,
+) -> ErrorCode {
+    catch_err! {
+        check_useful_c_ptr!(origin);
+        let results = handle.load()?;
+        let entry = results.get_row(index)?;
+        if let Some(o) = entry.origin() {
+            let origin_json = serde_json::to_vec(o)
+                .map_err(err_map!(Unexpected, "Error encoding key origin"))?;
+            let origin_json = CString::new(origin_json)
+                .map_err(err_map!(Unexpected, "Invalid key origin encoding"))?;
+            unsafe { *origin = origin_json.into_raw() };
+        } else {
+            unsafe { *origin = ptr::null() };
+        }
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_key_entry_list_get_tags(
     handle: KeyEntryListHandle,
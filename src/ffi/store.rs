@@ -1,12 +1,25 @@
-use std::{collections::BTreeMap, ffi::CString, os::raw::c_char, ptr, str::FromStr, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    ffi::CString,
+    os::raw::c_char,
+    ptr,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
 
 use askar_storage::backend::OrderBy;
-use async_lock::{Mutex as TryMutex, MutexGuardArc as TryMutexGuard, RwLock};
+use async_lock::{Mutex as TryMutex, MutexGuardArc as TryMutexGuard, RwLock, Semaphore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use ffi_support::{rust_string_to_c, ByteBuffer, FfiStr};
 use once_cell::sync::Lazy;
 
 use super::{
-    error::set_last_error,
+    batch::decode_batch,
+    cancel::{run_cancellable, CancelHandle, CancelToken},
+    error::set_operation_error,
     key::LocalKeyHandle,
     result_list::{
         EntryListHandle, FfiEntryList, FfiKeyEntryList, KeyEntryListHandle, StringListHandle,
@@ -19,8 +32,11 @@ use crate::{
     error::Error,
     ffi::result_list::FfiStringList,
     future::spawn_ok,
-    kms::KeyReference,
-    store::{PassKey, Session, Store, StoreKeyMethod},
+    kms::{KeyAlg, KeyReference, UnwrappingKey, WrappedKey},
+    store::{
+        set_pass_key_validator, PassKey, PassKeyPolicy, ProfileQuota, Session, Store,
+        StoreKeyMethod, TraceContext,
+    },
 };
 
 new_sequence_handle!(StoreHandle, FFI_STORE_COUNTER);
@@ -31,8 +47,128 @@ static FFI_STORES: Lazy<RwLock<BTreeMap<StoreHandle, Store>>> =
     Lazy::new(|| RwLock::new(BTreeMap::new()));
 static FFI_SESSIONS: Lazy<StoreResourceMap<SessionHandle, Session>> =
     Lazy::new(StoreResourceMap::new);
-static FFI_SCANS: Lazy<StoreResourceMap<ScanHandle, Scan<'static, Entry>>> =
-    Lazy::new(StoreResourceMap::new);
+static FFI_SCANS: Lazy<StoreResourceMap<ScanHandle, FfiScan>> = Lazy::new(StoreResourceMap::new);
+static FFI_SCAN_STREAMS: Lazy<Mutex<BTreeMap<ScanHandle, Arc<ScanStream>>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+const SCAN_CURSOR_VERSION: &str = "2";
+
+/// The number of profile keys re-encrypted in each batch of a cancellable re-key
+const CANCELLABLE_REKEY_CHUNK_SIZE: usize = 64;
+
+/// The largest window of a streaming scan, and the most pages acknowledged at once
+const MAX_SCAN_STREAM_PAGES: i32 = 1024;
+
+/// An active scan, tracking the last record fetched for the issuing of cursor tokens
+struct FfiScan {
+    scan: Scan<'static, (i64, Entry)>,
+    ordering: ScanOrdering,
+    last_id: Option<i64>,
+    cancel: Option<Arc<CancelToken>>,
+}
+
+impl FfiScan {
+    async fn fetch_next(&mut self) -> Result<Option<Vec<Entry>>, Error> {
+        let cancel = self.cancel.clone();
+        let rows = run_cancellable(cancel.as_deref(), async {
+            Ok(self.scan.fetch_next().await?)
+        })
+        .await?;
+        Ok(rows.map(|rows| {
+            if let Some((id, _)) = rows.last() {
+                self.last_id.replace(*id);
+            }
+            rows.into_iter().map(|(_, entry)| entry).collect()
+        }))
+    }
+}
+
+/// The delivery state of a scan in streaming mode
+struct ScanStream {
+    /// The number of pages which may be delivered before further acknowledgement
+    credits: Semaphore,
+    closed: AtomicBool,
+}
+
+impl ScanStream {
+    fn new(window: usize) -> Self {
+        Self {
+            credits: Semaphore::new(window),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Stop the delivery of pages, waking the stream task if it is waiting for credit
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.credits.add_permits(1);
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+}
+
+// the streams remain usable if a thread panicked while holding the lock, as
+// a panic must not be raised across the FFI boundary
+fn scan_streams() -> MutexGuard<'static, BTreeMap<ScanHandle, Arc<ScanStream>>> {
+    FFI_SCAN_STREAMS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn close_scan_stream(handle: ScanHandle) {
+    if let Some(stream) = scan_streams().remove(&handle) {
+        stream.close();
+    }
+}
+
+/// The record ordering of a scan, which must be consistent when resuming from a cursor
+///
+/// Scans are ordered by record identifier, so that a cursor resumes after the last
+/// record fetched regardless of records inserted or removed in the meantime.
+#[derive(Debug, PartialEq, Eq)]
+struct ScanOrdering {
+    descending: bool,
+}
+
+impl ScanOrdering {
+    fn cursor_prefix(&self) -> String {
+        format!(
+            "{}:{}:",
+            SCAN_CURSOR_VERSION,
+            if self.descending { "desc" } else { "asc" }
+        )
+    }
+
+    /// Encode an opaque cursor token for the last record identifier fetched
+    fn encode_cursor(&self, last_id: Option<i64>) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}{}",
+            self.cursor_prefix(),
+            last_id.map(|id| id.to_string()).unwrap_or_default()
+        ))
+    }
+
+    /// Decode a cursor token into the last record identifier fetched
+    fn decode_cursor(&self, cursor: &str) -> Result<Option<i64>, Error> {
+        let decoded = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|dec| String::from_utf8(dec).ok())
+            .ok_or_else(|| err_msg!(Input, "Invalid scan cursor"))?;
+        let last_id = decoded
+            .strip_prefix(self.cursor_prefix().as_str())
+            .ok_or_else(|| err_msg!(Input, "Scan cursor does not match the scan ordering"))?;
+        if last_id.is_empty() {
+            return Ok(None);
+        }
+        match last_id.parse::<i64>() {
+            Ok(last_id) => Ok(Some(last_id)),
+            _ => Err(err_msg!(Input, "Invalid scan cursor")),
+        }
+    }
+}
 
 impl StoreHandle {
     pub async fn create(value: Store) -> Self {
@@ -106,8 +242,9 @@ where
             .await)
     }
 
-    pub async fn remove_all(&self, store: StoreHandle) -> Result<(), Error> {
+    pub async fn remove_all(&self, store: StoreHandle) -> Result<Vec<K>, Error> {
         let mut guard = self.map.write().await;
+        let mut removed = Vec::new();
         let mut pos = K::from(0usize);
         let mut found;
         loop {
@@ -121,11 +258,12 @@ where
             }
             if found {
                 guard.remove(&pos);
+                removed.push(pos);
             } else {
                 break;
             }
         }
-        Ok(())
+        Ok(removed)
     }
 }
 
@@ -146,6 +284,29 @@ pub extern "C" fn askar_store_generate_raw_key(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_store_set_pass_key_policy(
+    min_length: i32,
+    min_entropy_bits: i32,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Set pass key policy");
+        if min_length < 0 || min_entropy_bits < 0 {
+            return Err(err_msg!("Invalid pass key policy"));
+        }
+        let policy = PassKeyPolicy {
+            min_length: min_length as usize,
+            min_entropy_bits: min_entropy_bits as u32,
+        };
+        if policy == PassKeyPolicy::default() {
+            set_pass_key_validator(None);
+        } else {
+            set_pass_key_validator(Some(Arc::new(policy)));
+        }
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_store_provision(
     spec_uri: FfiStr<'_>,
@@ -172,7 +333,7 @@ pub extern "C" fn askar_store_provision(
                     debug!("Provisioned store {}", sid);
                     cb(cb_id, ErrorCode::Success, sid)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), StoreHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_provision", err), StoreHandle::invalid()),
             }
         );
         spawn_ok(async move {
@@ -200,6 +361,34 @@ pub extern "C" fn askar_store_open(
     profile: FfiStr<'_>,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: StoreHandle)>,
     cb_id: CallbackId,
+) -> ErrorCode {
+    askar_store_open_v2(
+        spec_uri,
+        key_method,
+        pass_key,
+        profile,
+        // SAFETY: a null pointer is accepted as an absent string
+        unsafe { FfiStr::from_raw(ptr::null()) },
+        cb,
+        cb_id,
+    )
+}
+
+/// Open a store, optionally upgrading the derivation of its wrapping key
+///
+/// When `kdf_upgrade` is a key derivation method such as `kdf:argon2i:mod` and
+/// the wrapping key of the store is derived using a weaker method, the key is
+/// derived again from the same pass key once the store is unlocked. Opening the
+/// store fails if the upgrade fails.
+#[no_mangle]
+pub extern "C" fn askar_store_open_v2(
+    spec_uri: FfiStr<'_>,
+    key_method: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    profile: FfiStr<'_>,
+    kdf_upgrade: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: StoreHandle)>,
+    cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
         trace!("Open store");
@@ -209,6 +398,13 @@ pub extern "C" fn askar_store_open(
             Some(method) => Some(StoreKeyMethod::parse_uri(method)?),
             None => None
         };
+        let kdf_upgrade = match kdf_upgrade.as_opt_str() {
+            Some(method) => match StoreKeyMethod::parse_uri(method)? {
+                StoreKeyMethod::DeriveKey(method) => Some(method),
+                _ => return Err(err_msg!(Input, "Invalid key derivation method for upgrade")),
+            },
+            None => None
+        };
         let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
         let profile = profile.into_opt_string();
         let cb = EnsureCallback::new(move |result|
@@ -217,17 +413,20 @@ pub extern "C" fn askar_store_open(
                     debug!("Opened store {}", sid);
                     cb(cb_id, ErrorCode::Success, sid)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), StoreHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_open_v2", err), StoreHandle::invalid()),
             }
         );
         spawn_ok(async move {
             let result = async {
-                let store = Store::open (
+                let mut store = Store::open (
                 spec_uri.as_str(),
                     key_method,
-                    pass_key,
+                    pass_key.as_ref(),
                     profile
                 ).await?;
+                if let Some(method) = kdf_upgrade {
+                    store.upgrade_kdf(method, pass_key).await?;
+                }
                 Ok(StoreHandle::create(store).await)
             }.await;
             cb.resolve(result);
@@ -249,7 +448,7 @@ pub extern "C" fn askar_store_remove(
         let cb = EnsureCallback::new(move |result: Result<bool,Error>|
             match result {
                 Ok(removed) => cb(cb_id, ErrorCode::Success, removed as i8),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_remove", err), 0),
             }
         );
         spawn_ok(async move {
@@ -274,7 +473,7 @@ pub extern "C" fn askar_store_create_profile(
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(name) => cb(cb_id, ErrorCode::Success, rust_string_to_c(name)),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_create_profile", err), ptr::null()),
             }
         );
         spawn_ok(async move {
@@ -289,6 +488,34 @@ pub extern "C" fn askar_store_create_profile(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_store_ensure_profile(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, created: i8)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Ensure profile");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(created) => cb(cb_id, ErrorCode::Success, created as i8),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_ensure_profile", err), 0),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.ensure_profile(profile).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_store_get_profile_name(
     handle: StoreHandle,
@@ -301,7 +528,7 @@ pub extern "C" fn askar_store_get_profile_name(
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(name) => cb(cb_id, ErrorCode::Success, rust_string_to_c(name)),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null_mut()),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_get_profile_name", err), ptr::null_mut()),
             }
         );
         spawn_ok(async move {
@@ -330,7 +557,7 @@ pub extern "C" fn askar_store_list_profiles(
                     let res = StringListHandle::create(FfiStringList::from(rows));
                     cb(cb_id, ErrorCode::Success, res)
                 },
-                Err(err) => cb(cb_id, set_last_error(Some(err)), StringListHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_list_profiles", err), StringListHandle::invalid()),
             }
         );
         spawn_ok(async move {
@@ -359,7 +586,7 @@ pub extern "C" fn askar_store_remove_profile(
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(removed) => cb(cb_id, ErrorCode::Success, removed as i8),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_remove_profile", err), 0),
             }
         );
         spawn_ok(async move {
@@ -373,6 +600,260 @@ pub extern "C" fn askar_store_remove_profile(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_store_list_profiles_page(
+    handle: StoreHandle,
+    offset: i64,
+    limit: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: StringListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("List profiles page");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        if offset < 0 || limit < 0 {
+            return Err(err_msg!(Input, "Offset and limit must not be negative"));
+        }
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(rows) => {
+                    let res = StringListHandle::create(FfiStringList::from(rows));
+                    cb(cb_id, ErrorCode::Success, res)
+                },
+                Err(err) => cb(cb_id, set_operation_error("askar_store_list_profiles_page", err), StringListHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.list_profiles_page(offset, limit).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_rename_profile(
+    handle: StoreHandle,
+    from_profile: FfiStr<'_>,
+    to_profile: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, renamed: i8)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Rename profile");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let from_profile = from_profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let to_profile = to_profile.into_opt_string().ok_or_else(|| err_msg!("New profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(renamed) => cb(cb_id, ErrorCode::Success, renamed as i8),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_rename_profile", err), 0),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.rename_profile(from_profile, to_profile).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_get_profile_metadata(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, metadata: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Get profile metadata");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<Option<String>, Error>|
+            match result {
+                Ok(Some(metadata)) => cb(cb_id, ErrorCode::Success, rust_string_to_c(metadata)),
+                Ok(None) => cb(cb_id, ErrorCode::Success, ptr::null()),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_get_profile_metadata", err), ptr::null()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.get_profile_metadata(profile).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_set_profile_metadata(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    metadata: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Set profile metadata");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let metadata = metadata.into_opt_string();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_set_profile_metadata", err)),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.set_profile_metadata(profile, metadata).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Get the limits on the records stored within a profile
+///
+/// The limits are provided to the callback as a JSON object with the integer
+/// or null properties `max_entries` and `max_value_bytes`.
+#[no_mangle]
+pub extern "C" fn askar_store_get_profile_quota(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, quota: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Get profile quota");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<ProfileQuota, Error>|
+            match result {
+                Ok(quota) => {
+                    let quota = serde_json::json!({
+                        "max_entries": quota.max_entries,
+                        "max_value_bytes": quota.max_value_bytes,
+                    });
+                    cb(cb_id, ErrorCode::Success, rust_string_to_c(quota.to_string()))
+                }
+                Err(err) => cb(cb_id, set_operation_error("askar_store_get_profile_quota", err), ptr::null()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.get_profile_quota(profile).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Replace the limits on the records stored within a profile
+///
+/// A negative limit removes the corresponding limit.
+#[no_mangle]
+pub extern "C" fn askar_store_set_profile_quota(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    max_entries: i64,
+    max_value_bytes: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Set profile quota");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let quota = ProfileQuota {
+            max_entries: Some(max_entries).filter(|max| *max >= 0),
+            max_value_bytes: Some(max_value_bytes).filter(|max| *max >= 0),
+        };
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_set_profile_quota", err)),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.set_profile_quota(profile, quota).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_rekey_profile(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Re-key profile");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_rekey_profile", err)),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.rekey_profile(profile).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_rotate_tag_key(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Rotate profile tag key");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_rotate_tag_key", err)),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.rotate_tag_key(profile).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_store_get_default_profile(
     handle: StoreHandle,
@@ -386,7 +867,7 @@ pub extern "C" fn askar_store_get_default_profile(
             match result {
                 Ok(name) => cb(cb_id, ErrorCode::Success,
                     CString::new(name.as_str()).unwrap().into_raw() as *const c_char),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_get_default_profile", err), ptr::null()),
             }
         );
         spawn_ok(async move {
@@ -414,7 +895,7 @@ pub extern "C" fn askar_store_set_default_profile(
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(_) => cb(cb_id, ErrorCode::Success),
-                Err(err) => cb(cb_id, set_last_error(Some(err))),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_set_default_profile", err)),
             }
         );
         spawn_ok(async move {
@@ -435,6 +916,31 @@ pub extern "C" fn askar_store_rekey(
     pass_key: FfiStr<'_>,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
+) -> ErrorCode {
+    askar_store_rekey_v2(
+        handle,
+        key_method,
+        pass_key,
+        CancelHandle::invalid(),
+        cb,
+        cb_id,
+    )
+}
+
+/// Replace the wrapping key of a store, optionally using a cancellation token
+///
+/// When a cancellation token is provided, the profile keys are re-encrypted in
+/// batches and the operation stops between batches once cancelled. A cancelled
+/// re-key leaves the store usable with the current key, and is resumed by
+/// calling this method again with the same key method and pass key.
+#[no_mangle]
+pub extern "C" fn askar_store_rekey_v2(
+    handle: StoreHandle,
+    key_method: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    cancel: CancelHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
         trace!("Re-key store");
@@ -444,16 +950,28 @@ pub extern "C" fn askar_store_rekey(
             None => StoreKeyMethod::default()
         };
         let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let cancel = cancel.load_opt();
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(_) => cb(cb_id, ErrorCode::Success),
-                Err(err) => cb(cb_id, set_last_error(Some(err))),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_rekey_v2", err)),
             }
         );
         spawn_ok(async move {
             let result = async {
+                if let Some(cancel) = cancel.as_deref() {
+                    cancel.check()?;
+                }
                 let mut store = handle.remove().await?;
-                let result = store.rekey(key_method, pass_key.as_ref()).await;
+                let result = match cancel {
+                    Some(cancel) => store.rekey_chunked(
+                        key_method,
+                        pass_key.as_ref(),
+                        CANCELLABLE_REKEY_CHUNK_SIZE,
+                        |_| cancel.check(),
+                    ).await,
+                    None => store.rekey(key_method, pass_key.as_ref()).await,
+                };
                 handle.replace(store).await;
                 result
             }.await;
@@ -472,28 +990,108 @@ pub extern "C" fn askar_store_copy(
     recreate: i8,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: StoreHandle)>,
     cb_id: CallbackId,
+) -> ErrorCode {
+    askar_store_copy_v2(
+        handle,
+        target_uri,
+        key_method,
+        pass_key,
+        recreate,
+        CancelHandle::invalid(),
+        cb,
+        cb_id,
+    )
+}
+
+/// Copy a store to a new database, optionally using a cancellation token
+///
+/// A cancelled copy may leave a partially populated target database.
+#[no_mangle]
+pub extern "C" fn askar_store_copy_v2(
+    handle: StoreHandle,
+    target_uri: FfiStr<'_>,
+    key_method: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    recreate: i8,
+    cancel: CancelHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: StoreHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Copy store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let target_uri = target_uri.into_opt_string().ok_or_else(|| err_msg!("No target URI provided"))?;
+        let key_method = match key_method.as_opt_str() {
+            Some(method) => StoreKeyMethod::parse_uri(method)?,
+            None => StoreKeyMethod::default()
+        };
+        let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let cancel = cancel.load_opt();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(handle) => cb(cb_id, ErrorCode::Success, handle),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_copy_v2", err), StoreHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async move {
+                let store = handle.load().await?;
+                let copied = run_cancellable(
+                    cancel.as_deref(),
+                    store.copy_to(target_uri.as_str(), key_method, pass_key.as_ref(), recreate != 0),
+                ).await?;
+                debug!("Copied store {}", handle);
+                Ok(StoreHandle::create(copied).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Insert a CBOR-encoded array of entries in a single transaction
+///
+/// Each entry is a map containing the `category`, `name` and `value` of a record,
+/// with optional `tags` and `expiry_ms`. No entries are inserted if any fails.
+#[no_mangle]
+pub extern "C" fn askar_store_insert_batch(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    entries: ByteBuffer,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, count: i64)>,
+    cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Copy store");
+        trace!("Insert entry batch");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let target_uri = target_uri.into_opt_string().ok_or_else(|| err_msg!("No target URI provided"))?;
-        let key_method = match key_method.as_opt_str() {
-            Some(method) => StoreKeyMethod::parse_uri(method)?,
-            None => StoreKeyMethod::default()
-        };
-        let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let profile = profile.into_opt_string();
+        let entries = entries.as_slice().to_vec();
         let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(handle) => cb(cb_id, ErrorCode::Success, handle),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), StoreHandle::invalid()),
+                Ok(count) => cb(cb_id, ErrorCode::Success, count),
+                Err(err) => cb(cb_id, set_operation_error("askar_store_insert_batch", err), 0),
             }
         );
         spawn_ok(async move {
-            let result = async move {
+            let result = async {
+                let entries = decode_batch(&entries)?;
                 let store = handle.load().await?;
-                let copied = store.copy_to(target_uri.as_str(), key_method, pass_key.as_ref(), recreate != 0).await?;
-                debug!("Copied store {}", handle);
-                Ok(StoreHandle::create(copied).await)
+                let mut txn = store.transaction(profile).await?;
+                for entry in entries.iter() {
+                    let tags = entry.tags.clone().map(EntryTagSet::into_vec);
+                    if let Err(err) = txn.insert(
+                        &entry.category,
+                        &entry.name,
+                        entry.value.0.as_ref(),
+                        tags.as_deref(),
+                        entry.expiry_ms,
+                    ).await {
+                        txn.rollback().await?;
+                        return Err(err);
+                    }
+                }
+                txn.commit().await?;
+                Ok(entries.len() as i64)
             }.await;
             cb.resolve(result);
         });
@@ -513,7 +1111,7 @@ pub extern "C" fn askar_store_close(
             EnsureCallback::new(move |result|
                 match result {
                     Ok(_) => cb(cb_id, ErrorCode::Success),
-                    Err(err) => cb(cb_id, set_last_error(Some(err))),
+                    Err(err) => cb(cb_id, set_operation_error("askar_store_close", err)),
                 }
             )
         });
@@ -524,7 +1122,9 @@ pub extern "C" fn askar_store_close(
                 // to avoid blocking unnecessarily due to handles that simply haven't
                 // been dropped yet (this will invalidate associated handles)
                 FFI_SESSIONS.remove_all(handle).await?;
-                FFI_SCANS.remove_all(handle).await?;
+                for scan in FFI_SCANS.remove_all(handle).await? {
+                    close_scan_stream(scan);
+                }
                 store.close().await?;
                 debug!("Closed store {}", handle);
                 Ok(())
@@ -553,11 +1153,82 @@ pub extern "C" fn askar_scan_start(
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: ScanHandle)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
-    let order_by_str = order_by.as_opt_str().map(|s| s.to_lowercase());
-    let order_by = match order_by_str.as_deref() {
-        Some("id") => Some(OrderBy::Id),
+    askar_scan_start_v2(
+        handle,
+        profile,
+        category,
+        tag_filter,
+        offset,
+        limit,
+        order_by,
+        descending,
+        // SAFETY: a null pointer is accepted as an absent string
+        unsafe { FfiStr::from_raw(ptr::null()) },
+        cb,
+        cb_id,
+    )
+}
+
+/// Start a scan, optionally resuming from a cursor token
+///
+/// Records are returned in order of their identifiers. A cursor token is obtained
+/// from `askar_scan_cursor` and may only be combined with the ordering of the scan
+/// which produced it: the scan resumes after the last record fetched, even when
+/// records have since been inserted or removed. The `offset` must be zero when a
+/// cursor is provided, and `limit` applies to the records following the cursor.
+#[no_mangle]
+pub extern "C" fn askar_scan_start_v2(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    category: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    offset: i64,
+    limit: i64,
+    order_by: FfiStr<'_>,
+    descending: i8,
+    cursor: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: ScanHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    askar_scan_start_v3(
+        handle,
+        profile,
+        category,
+        tag_filter,
+        offset,
+        limit,
+        order_by,
+        descending,
+        cursor,
+        CancelHandle::invalid(),
+        cb,
+        cb_id,
+    )
+}
+
+/// Start a scan, optionally resuming from a cursor token and using a cancellation token
+///
+/// Once the token is cancelled, pending and subsequent fetches from the scan
+/// complete with a `Cancelled` error.
+#[no_mangle]
+pub extern "C" fn askar_scan_start_v3(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    category: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    offset: i64,
+    limit: i64,
+    order_by: FfiStr<'_>,
+    descending: i8,
+    cursor: FfiStr<'_>,
+    cancel: CancelHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: ScanHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    // scans are always ordered by record identifier, for the issuing of cursors
+    match order_by.as_opt_str().map(|s| s.to_lowercase()).as_deref() {
+        Some("id") | None => (),
         Some(_) => return ErrorCode::Unsupported,
-        None => None,
     };
     let descending = descending != 0; // Convert to bool
 
@@ -567,20 +1238,32 @@ pub extern "C" fn askar_scan_start(
         let profile = profile.into_opt_string();
         let category = category.into_opt_string();
         let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let ordering = ScanOrdering { descending };
+        let cancel = cancel.load_opt();
+        let after = match cursor.as_opt_str() {
+            Some(_) if offset > 0 => {
+                return Err(err_msg!(Input, "Scan offset cannot be combined with a cursor"))
+            }
+            Some(cursor) => ordering.decode_cursor(cursor)?,
+            None => None,
+        };
         let cb = EnsureCallback::new(move |result: Result<ScanHandle,Error>|
             match result {
                 Ok(scan_handle) => {
                     debug!("Started scan {} on store {}", scan_handle, handle);
                     cb(cb_id, ErrorCode::Success, scan_handle)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), ScanHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_scan_start_v3", err), ScanHandle::invalid()),
             }
         );
         spawn_ok(async move {
             let result = async {
                 let store = handle.load().await?;
-                let scan = store.scan(profile, category, tag_filter, Some(offset), if limit < 0 { None }else {Some(limit)}, order_by, descending).await?;
-                Ok(FFI_SCANS.insert(handle, scan).await)
+                let scan = run_cancellable(
+                    cancel.as_deref(),
+                    store.scan_after(profile, category, tag_filter, after, Some(offset.max(0)), if limit < 0 { None }else {Some(limit)}, descending),
+                ).await?;
+                Ok(FFI_SCANS.insert(handle, FfiScan { scan, ordering, last_id: after, cancel }).await)
             }.await;
             cb.resolve(result);
         });
@@ -604,14 +1287,126 @@ pub extern "C" fn askar_scan_next(
                     cb(cb_id, ErrorCode::Success, results)
                 },
                 Ok(None) => cb(cb_id, ErrorCode::Success, EntryListHandle::invalid()),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_scan_next", err), EntryListHandle::invalid()),
             }
         );
         spawn_ok(async move {
             let result = async {
                 let mut scan = FFI_SCANS.borrow(handle).await?;
-                let entries = scan.fetch_next().await?;
-                Ok(entries)
+                scan.fetch_next().await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Deliver the pages of a scan to a callback as they are fetched
+///
+/// The callback is invoked with each page of results, followed by a final call
+/// with an invalid `EntryListHandle` when the scan is complete, fails, or is freed.
+/// No more than `window` pages are delivered before they are acknowledged using
+/// `askar_scan_stream_ack`, where `window` is between 1 and 1024. `askar_scan_next`
+/// must not be used on a streaming scan.
+#[no_mangle]
+pub extern "C" fn askar_scan_stream(
+    handle: ScanHandle,
+    window: i32,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Scan stream start");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        if !(1..=MAX_SCAN_STREAM_PAGES).contains(&window) {
+            return Err(err_msg!(Input, "Invalid scan stream window"));
+        }
+        let stream = Arc::new(ScanStream::new(window as usize));
+        {
+            let mut streams = scan_streams();
+            if streams.contains_key(&handle) {
+                return Err(err_msg!(Busy, "Scan is already streaming"));
+            }
+            streams.insert(handle, stream.clone());
+        }
+        let done = EnsureCallback::new(move |result: Result<(), Error>|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success, EntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_scan_stream", err), EntryListHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                loop {
+                    stream.credits.acquire().await.forget();
+                    if stream.is_closed() {
+                        break;
+                    }
+                    let entries = {
+                        let mut scan = FFI_SCANS.borrow(handle).await?;
+                        scan.fetch_next().await?
+                    };
+                    match entries {
+                        Some(entries) => {
+                            let results = EntryListHandle::create(FfiEntryList::from(entries));
+                            cb(cb_id, ErrorCode::Success, results);
+                        }
+                        None => break,
+                    }
+                }
+                Ok(())
+            }.await;
+            {
+                let mut streams = scan_streams();
+                if streams.get(&handle).map(|s| Arc::ptr_eq(s, &stream)).unwrap_or(false) {
+                    streams.remove(&handle);
+                }
+            }
+            debug!("Finished streaming scan {}", handle);
+            done.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Acknowledge pages delivered by `askar_scan_stream`, allowing more to be sent
+///
+/// Between 1 and 1024 pages may be acknowledged at once. Acknowledging a scan
+/// which is no longer streaming has no effect.
+#[no_mangle]
+pub extern "C" fn askar_scan_stream_ack(handle: ScanHandle, pages: i32) -> ErrorCode {
+    catch_err! {
+        trace!("Scan stream acknowledge");
+        if !(1..=MAX_SCAN_STREAM_PAGES).contains(&pages) {
+            return Err(err_msg!(Input, "Invalid number of pages"));
+        }
+        if let Some(stream) = scan_streams().get(&handle) {
+            stream.credits.add_permits(pages as usize);
+        }
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Get a cursor token for resuming a scan after the records fetched so far
+#[no_mangle]
+pub extern "C" fn askar_scan_cursor(
+    handle: ScanHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, cursor: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Scan cursor");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(cursor) => cb(cb_id, ErrorCode::Success, rust_string_to_c(cursor)),
+                Err(err) => cb(cb_id, set_operation_error("askar_scan_cursor", err), ptr::null_mut()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let scan = FFI_SCANS.borrow(handle).await?;
+                Ok(scan.ordering.encode_cursor(scan.last_id))
             }.await;
             cb.resolve(result);
         });
@@ -623,6 +1418,7 @@ pub extern "C" fn askar_scan_next(
 pub extern "C" fn askar_scan_free(handle: ScanHandle) -> ErrorCode {
     catch_err! {
         trace!("Close scan");
+        close_scan_stream(handle);
         spawn_ok(async move {
             // the Scan may have been removed due to the Store being closed
             if let Some(scan) = FFI_SCANS.remove(handle).await {
@@ -654,7 +1450,7 @@ pub extern "C" fn askar_session_start(
                     debug!("Started session {} on store {} (txn: {})", sess_handle, handle, as_transaction != 0);
                     cb(cb_id, ErrorCode::Success, sess_handle)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), SessionHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_start", err), SessionHandle::invalid()),
             }
         );
         spawn_ok(async move {
@@ -673,6 +1469,49 @@ pub extern "C" fn askar_session_start(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_session_start_v2(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    as_transaction: i8,
+    traceparent: FfiStr<'_>,
+    tracestate: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: SessionHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Session start");
+        let profile = profile.into_opt_string();
+        let trace_context = traceparent
+            .as_opt_str()
+            .map(|traceparent| TraceContext::new(traceparent, tracestate.as_opt_str()))
+            .transpose()?;
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<SessionHandle,Error>|
+            match result {
+                Ok(sess_handle) => {
+                    debug!("Started session {} on store {} (txn: {})", sess_handle, handle, as_transaction != 0);
+                    cb(cb_id, ErrorCode::Success, sess_handle)
+                }
+                Err(err) => cb(cb_id, set_operation_error("askar_session_start_v2", err), SessionHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                let session = if as_transaction == 0 {
+                    store.session_with_trace(profile, trace_context).await?
+                } else {
+                    store.transaction_with_trace(profile, trace_context).await?
+                };
+                Ok(FFI_SESSIONS.insert(handle, session).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_session_count(
     handle: SessionHandle,
@@ -689,7 +1528,7 @@ pub extern "C" fn askar_session_count(
         let cb = EnsureCallback::new(move |result: Result<i64,Error>|
             match result {
                 Ok(count) => cb(cb_id, ErrorCode::Success, count),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_count", err), 0),
             }
         );
         spawn_ok(async move {
@@ -724,7 +1563,7 @@ pub extern "C" fn askar_session_fetch(
                     cb(cb_id, ErrorCode::Success, results)
                 },
                 Ok(None) => cb(cb_id, ErrorCode::Success, EntryListHandle::invalid()),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_fetch", err), EntryListHandle::invalid()),
             }
         );
         spawn_ok(async move {
@@ -770,7 +1609,7 @@ pub extern "C" fn askar_session_fetch_all(
                     let results = EntryListHandle::create(FfiEntryList::from(rows));
                     cb(cb_id, ErrorCode::Success, results)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_fetch_all", err), EntryListHandle::invalid()),
             }
         );
         spawn_ok(async move {
@@ -802,7 +1641,7 @@ pub extern "C" fn askar_session_remove_all(
                 Ok(removed) => {
                     cb(cb_id, ErrorCode::Success, removed)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_remove_all", err), 0),
             }
         );
         spawn_ok(async move {
@@ -827,6 +1666,65 @@ pub extern "C" fn askar_session_update(
     expiry_ms: i64,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
+) -> ErrorCode {
+    session_update(
+        "askar_session_update",
+        handle,
+        operation,
+        category,
+        name,
+        value,
+        tags,
+        expiry_ms,
+        false,
+        cb,
+        cb_id,
+    )
+}
+
+/// Update a record, storing an inserted or replaced value without encryption
+/// when `unencrypted` is non-zero
+#[no_mangle]
+pub extern "C" fn askar_session_update_v2(
+    handle: SessionHandle,
+    operation: i8,
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    value: ByteBuffer,
+    tags: FfiStr<'_>,
+    expiry_ms: i64,
+    unencrypted: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    session_update(
+        "askar_session_update_v2",
+        handle,
+        operation,
+        category,
+        name,
+        value,
+        tags,
+        expiry_ms,
+        unencrypted != 0,
+        cb,
+        cb_id,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn session_update(
+    method: &'static str,
+    handle: SessionHandle,
+    operation: i8,
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    value: ByteBuffer,
+    tags: FfiStr<'_>,
+    expiry_ms: i64,
+    unencrypted: bool,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
         trace!("Update store");
@@ -834,7 +1732,8 @@ pub extern "C" fn askar_session_update(
         let operation = match operation {
             0 => EntryOperation::Insert,
             1 => EntryOperation::Replace,
-            2 => EntryOperation::Remove,
+            2 if !unencrypted => EntryOperation::Remove,
+            2 => return Err(err_msg!(Input, "Unencrypted values may only be inserted or replaced")),
             _ => return Err(err_msg!("Invalid update operation"))
         };
         let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
@@ -849,7 +1748,7 @@ pub extern "C" fn askar_session_update(
         } else {
             None
         };
-        let expiry_ms = if expiry_ms < 0 {
+        let expiry = if expiry_ms < 0 {
             None
         } else {
             Some(expiry_ms)
@@ -857,13 +1756,17 @@ pub extern "C" fn askar_session_update(
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(_) => cb(cb_id, ErrorCode::Success),
-                Err(err) => cb(cb_id, set_last_error(Some(err))),
+                Err(err) => cb(cb_id, set_operation_error(method, err)),
             }
         );
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.update(operation, &category, &name, Some(value.as_slice()), tags.as_deref(), expiry_ms).await
+                match (operation, unencrypted) {
+                    (EntryOperation::Insert, true) => session.insert_unencrypted(&category, &name, &value, tags.as_deref(), expiry).await,
+                    (EntryOperation::Replace, true) => session.replace_unencrypted(&category, &name, &value, tags.as_deref(), expiry).await,
+                    _ => session.update(operation, &category, &name, Some(value.as_slice()), tags.as_deref(), expiry).await,
+                }
             }.await;
             cb.resolve(result);
         });
@@ -897,7 +1800,7 @@ pub extern "C" fn askar_session_insert_key(
         } else {
             None
         };
-        let expiry_ms = if expiry_ms < 0 {
+        let expiry = if expiry_ms < 0 {
             None
         } else {
             Some(expiry_ms)
@@ -907,7 +1810,7 @@ pub extern "C" fn askar_session_insert_key(
                 Ok(_) => {
                     cb(cb_id, ErrorCode::Success)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err))),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_insert_key", err)),
             }
         );
 
@@ -922,7 +1825,119 @@ pub extern "C" fn askar_session_insert_key(
                     metadata.as_deref(),
                     reference,
                     tags.as_deref(),
-                    expiry_ms,
+                    expiry,
+                ).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_export_wrapped_key(
+    handle: SessionHandle,
+    name: FfiStr<'_>,
+    wrapping_key: LocalKeyHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, wrapped: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Export wrapped key");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
+        let wrapping_key = wrapping_key.load()?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(wrapped) => cb(cb_id, ErrorCode::Success, rust_string_to_c(wrapped)),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_export_wrapped_key", err), ptr::null_mut()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.export_key_wrapped(name.as_str(), &wrapping_key).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_import_wrapped_key(
+    handle: SessionHandle,
+    name: FfiStr<'_>,
+    alg: FfiStr<'_>,
+    wrapped: ByteBuffer,
+    unwrapping_key: LocalKeyHandle,
+    unwrap_key_name: FfiStr<'_>,
+    metadata: FfiStr<'_>,
+    tags: FfiStr<'_>,
+    expiry_ms: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Import wrapped key");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
+        // a key algorithm is required for RFC 3394 wrapped keys, otherwise a JWE is expected
+        let alg = alg.as_opt_str().map(KeyAlg::from_str).transpose()?;
+        let wrapped = wrapped.as_slice().to_vec();
+        // the unwrapping key is either provided as a handle or stored in the profile
+        let unwrap_key_name = unwrap_key_name.into_opt_string();
+        let unwrapping_key = match unwrap_key_name {
+            Some(_) if unwrapping_key.validate().is_ok() => {
+                return Err(err_msg!("Only one of an unwrapping key or unwrapping key name may be provided"));
+            }
+            Some(_) => None,
+            None => Some(unwrapping_key.load()?),
+        };
+        let metadata = metadata.into_opt_string();
+        let tags = if let Some(tags) = tags.as_opt_str() {
+            Some(
+                serde_json::from_str::<EntryTagSet<'static>>(tags)
+                    .map_err(err_map!("Error decoding tags"))?
+                    .into_vec(),
+            )
+        } else {
+            None
+        };
+        let expiry = if expiry_ms < 0 {
+            None
+        } else {
+            Some(expiry_ms)
+        };
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => {
+                    cb(cb_id, ErrorCode::Success)
+                }
+                Err(err) => cb(cb_id, set_operation_error("askar_session_import_wrapped_key", err)),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let wrapped = match alg {
+                    Some(alg) => WrappedKey::KeyWrap(alg, wrapped.as_slice()),
+                    None => WrappedKey::Jwe(
+                        std::str::from_utf8(&wrapped).map_err(err_map!(Input, "Invalid JWE encoding"))?
+                    ),
+                };
+                let unwrapping_key = match (&unwrapping_key, &unwrap_key_name) {
+                    (Some(key), _) => UnwrappingKey::Local(key),
+                    (None, Some(key_name)) => UnwrappingKey::Stored(key_name),
+                    (None, None) => return Err(err_msg!("No unwrapping key provided")),
+                };
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.import_key_wrapped(
+                    name.as_str(),
+                    wrapped,
+                    unwrapping_key,
+                    metadata.as_deref(),
+                    tags.as_deref(),
+                    expiry,
                 ).await
             }.await;
             cb.resolve(result);
@@ -953,7 +1968,7 @@ pub extern "C" fn askar_session_fetch_key(
                 Ok(None) => {
                     cb(cb_id, ErrorCode::Success, KeyEntryListHandle::invalid())
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), KeyEntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_fetch_key", err), KeyEntryListHandle::invalid()),
             }
         );
 
@@ -996,7 +2011,7 @@ pub extern "C" fn askar_session_fetch_all_keys(
                     let results = KeyEntryListHandle::create(FfiKeyEntryList::from(entries));
                     cb(cb_id, ErrorCode::Success, results)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), KeyEntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_fetch_all_keys", err), KeyEntryListHandle::invalid()),
             }
         );
 
@@ -1041,7 +2056,7 @@ pub extern "C" fn askar_session_update_key(
         } else {
             None
         };
-        let expiry_ms = if expiry_ms < 0 {
+        let expiry = if expiry_ms < 0 {
             None
         } else {
             Some(expiry_ms)
@@ -1051,7 +2066,7 @@ pub extern "C" fn askar_session_update_key(
                 Ok(_) => {
                     cb(cb_id, ErrorCode::Success)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err))),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_update_key", err)),
             }
         );
 
@@ -1062,7 +2077,7 @@ pub extern "C" fn askar_session_update_key(
                     &name,
                     metadata.as_deref(),
                     tags.as_deref(),
-                    expiry_ms,
+                    expiry,
 
                 ).await
             }.await;
@@ -1088,7 +2103,7 @@ pub extern "C" fn askar_session_remove_key(
                 Ok(_) => {
                     cb(cb_id, ErrorCode::Success)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err))),
+                Err(err) => cb(cb_id, set_operation_error("askar_session_remove_key", err)),
             }
         );
 
@@ -1119,7 +2134,7 @@ pub extern "C" fn askar_session_close(
                 match result {
                     Ok(_) => cb(cb_id, ErrorCode::Success),
                     Err(err) => {
-                        cb(cb_id, set_last_error(Some(err)))
+                        cb(cb_id, set_operation_error("askar_session_close", err))
                     }
                 }
             )
@@ -1152,3 +2167,69 @@ pub extern "C" fn askar_session_close(
         Ok(ErrorCode::Success)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_cursor_round_trip() {
+        let ordering = ScanOrdering { descending: true };
+        let cursor = ordering.encode_cursor(Some(25));
+        assert_eq!(ordering.decode_cursor(&cursor).unwrap(), Some(25));
+        let cursor_start = ordering.encode_cursor(None);
+        assert_eq!(ordering.decode_cursor(&cursor_start).unwrap(), None);
+
+        let other = ScanOrdering { descending: false };
+        assert!(other.decode_cursor(&cursor).is_err());
+        assert!(ordering.decode_cursor("invalid").is_err());
+        assert!(ordering
+            .decode_cursor(&URL_SAFE_NO_PAD.encode("2:desc:x"))
+            .is_err());
+        // offset-based cursors of the previous version are rejected
+        assert!(ordering
+            .decode_cursor(&URL_SAFE_NO_PAD.encode("1:id:desc:25"))
+            .is_err());
+    }
+
+    #[test]
+    fn scan_stream_close_releases_waiter() {
+        crate::future::block_on(async {
+            let stream = ScanStream::new(1);
+            stream.credits.acquire().await.forget();
+            assert!(stream.credits.try_acquire().is_none());
+            stream.close();
+            stream.credits.acquire().await.forget();
+            assert!(stream.is_closed());
+        });
+    }
+
+    #[test]
+    fn scan_stream_validates_pages() {
+        extern "C" fn cb(_cb_id: CallbackId, _err: ErrorCode, _results: EntryListHandle) {}
+
+        let handle = ScanHandle::next();
+        for window in [-1, 0, MAX_SCAN_STREAM_PAGES + 1] {
+            assert_eq!(
+                askar_scan_stream(handle, window, Some(cb), 0),
+                ErrorCode::Input
+            );
+        }
+        for pages in [-1, 0, MAX_SCAN_STREAM_PAGES + 1] {
+            assert_eq!(askar_scan_stream_ack(handle, pages), ErrorCode::Input);
+        }
+        assert_eq!(askar_scan_stream_ack(handle, 1), ErrorCode::Success);
+    }
+
+    #[test]
+    fn scan_stream_ack_poisoned() {
+        std::thread::spawn(|| {
+            let _streams = FFI_SCAN_STREAMS.lock().unwrap();
+            panic!("poison the lock");
+        })
+        .join()
+        .unwrap_err();
+        let handle = ScanHandle::next();
+        assert_eq!(askar_scan_stream_ack(handle, 1), ErrorCode::Success);
+    }
+}
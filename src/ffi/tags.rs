@@ -1,4 +1,6 @@
-use std::{borrow::Cow, fmt};
+use std::{borrow::Cow, fmt, os::raw::c_char};
+
+use ffi_support::{rust_string_to_c, FfiStr};
 
 use serde::{
     de::{Error as SerdeError, MapAccess, SeqAccess, Visitor},
@@ -6,7 +8,8 @@ use serde::{
     Deserialize, Deserializer, Serialize, Serializer,
 };
 
-use crate::entry::EntryTag;
+use super::ErrorCode;
+use crate::entry::{EntryTag, TagFilter};
 
 /// A wrapper type used for managing (de)serialization of tags
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -191,6 +194,48 @@ impl Serialize for EntryTagSet<'_> {
     }
 }
 
+/// Validate a JSON tag filter without executing a query
+///
+/// The result is a JSON object containing a `valid` flag, the tag names referenced
+/// by the filter, warnings for clauses unlikely to match as intended, and for an
+/// invalid filter the `error` message along with the position of any syntax error.
+#[no_mangle]
+pub extern "C" fn askar_tag_filter_validate(
+    tag_filter: FfiStr<'_>,
+    diagnostics_json_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Validate tag filter");
+        check_useful_c_ptr!(diagnostics_json_p);
+        let tag_filter = tag_filter.as_opt_str().ok_or_else(|| err_msg!("No tag filter provided"))?;
+        let diagnostics = tag_filter_diagnostics_json(tag_filter);
+        unsafe { *diagnostics_json_p = rust_string_to_c(diagnostics) };
+        Ok(ErrorCode::Success)
+    }
+}
+
+fn tag_filter_diagnostics_json(tag_filter: &str) -> String {
+    let diag = TagFilter::validate(tag_filter);
+    let warnings: Vec<_> = diag
+        .warnings
+        .iter()
+        .map(|warn| serde_json::json!({ "tag": warn.tag, "message": warn.message }))
+        .collect();
+    let mut result = serde_json::json!({
+        "valid": diag.is_valid(),
+        "tags": diag.tags,
+        "warnings": warnings,
+    });
+    if let Some(error) = diag.error {
+        result["error"] = serde_json::json!({
+            "message": error.message,
+            "line": error.line,
+            "column": error.column,
+        });
+    }
+    result.to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,4 +252,21 @@ mod tests {
         let tags2 = serde_json::from_str(&ser).unwrap();
         assert_eq!(tags, tags2);
     }
+
+    #[test]
+    fn validate_tag_filter() {
+        let diag: serde_json::Value = serde_json::from_str(&tag_filter_diagnostics_json(
+            r#"{"~score": {"$gte": "5"}, "name": {"$like": "a%"}}"#,
+        ))
+        .unwrap();
+        assert_eq!(diag["valid"], true);
+        assert_eq!(diag["tags"], serde_json::json!(["name", "~score"]));
+        assert_eq!(diag["warnings"][0]["tag"], "name");
+        assert!(diag.get("error").is_none());
+
+        let diag: serde_json::Value =
+            serde_json::from_str(&tag_filter_diagnostics_json(r#"{"$not": []}"#)).unwrap();
+        assert_eq!(diag["valid"], false);
+        assert_eq!(diag["error"]["line"], serde_json::Value::Null);
+    }
 }
@@ -0,0 +1,138 @@
+use std::str::FromStr;
+
+use tonic::{Request, Response, Status};
+
+use super::{
+    convert_tags,
+    proto::{self, kms_service_server::KmsService},
+    AskarService,
+};
+use crate::{
+    crypto::alg::KeyAlg,
+    entry::TagFilter,
+    error::Error,
+    kms::{KeyEntry, LocalKey},
+    store::Session,
+};
+
+fn key_entry(entry: &KeyEntry) -> Result<proto::KeyEntry, Error> {
+    let key = entry.load_local_key()?;
+    Ok(proto::KeyEntry {
+        name: entry.name().to_string(),
+        algorithm: entry.algorithm().map(str::to_string),
+        metadata: entry.metadata().map(str::to_string),
+        tags: entry.tags_as_slice().iter().map(Into::into).collect(),
+        public_jwk: key.to_jwk_public(None)?,
+    })
+}
+
+async fn load_key(session: &mut Session, name: &str) -> Result<LocalKey, Error> {
+    session
+        .fetch_key(name, false)
+        .await?
+        .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?
+        .load_local_key()
+}
+
+#[tonic::async_trait]
+impl KmsService for AskarService {
+    async fn create_key(
+        &self,
+        request: Request<proto::CreateKeyRequest>,
+    ) -> Result<Response<proto::KeyEntry>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let alg = KeyAlg::from_str(&req.algorithm).map_err(Error::from)?;
+        let key = LocalKey::generate_with_rng(alg, false)?;
+        let tags = convert_tags(req.tags);
+        session
+            .insert_key(
+                &req.name,
+                &key,
+                req.metadata.as_deref(),
+                None,
+                tags.as_deref(),
+                req.expiry_ms,
+            )
+            .await?;
+        Ok(Response::new(proto::KeyEntry {
+            name: req.name,
+            algorithm: Some(alg.as_str().to_string()),
+            metadata: req.metadata,
+            tags: tags.unwrap_or_default().iter().map(Into::into).collect(),
+            public_jwk: key.to_jwk_public(None)?,
+        }))
+    }
+
+    async fn fetch_key(
+        &self,
+        request: Request<proto::FetchKeyRequest>,
+    ) -> Result<Response<proto::KeyEntry>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let entry = session
+            .fetch_key(&req.name, false)
+            .await?
+            .ok_or_else(|| Status::not_found("Key entry not found"))?;
+        Ok(Response::new(key_entry(&entry)?))
+    }
+
+    async fn fetch_all_keys(
+        &self,
+        request: Request<proto::FetchAllKeysRequest>,
+    ) -> Result<Response<proto::FetchAllKeysResponse>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let tag_filter = req
+            .tag_filter
+            .as_deref()
+            .map(TagFilter::from_str)
+            .transpose()
+            .map_err(Error::from)?;
+        let entries = session
+            .fetch_all_keys(
+                req.algorithm.as_deref(),
+                req.thumbprint.as_deref(),
+                tag_filter,
+                req.limit,
+                false,
+            )
+            .await?;
+        let keys = entries
+            .iter()
+            .map(key_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Response::new(proto::FetchAllKeysResponse { keys }))
+    }
+
+    async fn remove_key(
+        &self,
+        request: Request<proto::RemoveKeyRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let mut session = self.session(&request).await?;
+        session.remove_key(&request.into_inner().name).await?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn sign_message(
+        &self,
+        request: Request<proto::SignMessageRequest>,
+    ) -> Result<Response<proto::SignMessageResponse>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let key = load_key(&mut session, &req.name).await?;
+        let signature = key.sign_message(&req.message, req.sig_type.as_deref())?;
+        Ok(Response::new(proto::SignMessageResponse { signature }))
+    }
+
+    async fn verify_signature(
+        &self,
+        request: Request<proto::VerifySignatureRequest>,
+    ) -> Result<Response<proto::VerifySignatureResponse>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let key = load_key(&mut session, &req.name).await?;
+        let valid = key.verify_signature(&req.message, &req.signature, req.sig_type.as_deref())?;
+        Ok(Response::new(proto::VerifySignatureResponse { valid }))
+    }
+}
@@ -0,0 +1,331 @@
+//! A gRPC service exposing the store and key management interfaces
+//!
+//! Each request must carry a bearer token in the `authorization` metadata. The
+//! token identifies a tenant, and all operations are performed within the store
+//! profile assigned to that tenant.
+
+use std::{collections::HashMap, future::Future, net::SocketAddr, path::PathBuf, sync::Arc};
+
+use tonic::{
+    service::Interceptor,
+    transport::{Identity, Server, ServerTlsConfig},
+    Code, Request, Status,
+};
+
+use crate::{
+    entry::EntryTag,
+    error::{Error, ErrorKind},
+    server::{parse_key_method, parse_token_hash, token_hash},
+    store::{PassKey, Session, Store},
+};
+
+mod kms;
+mod store;
+
+/// Generated protocol buffer messages and service definitions
+#[allow(missing_docs, missing_debug_implementations, clippy::all)]
+pub mod proto {
+    tonic::include_proto!("askar.v1");
+}
+
+/// Configuration for the gRPC server
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerConfig {
+    /// The address to listen on
+    pub listen: SocketAddr,
+    /// The database URL of an existing store
+    pub store_uri: String,
+    /// The store key method, if not the default
+    #[serde(default)]
+    pub key_method: Option<String>,
+    /// TLS settings. When absent, connections are not encrypted, and the server
+    /// only listens on a loopback address
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// The tenants permitted to access the store
+    pub tenants: Vec<TenantConfig>,
+}
+
+/// TLS settings for the gRPC server
+#[derive(Clone, Debug, Deserialize)]
+pub struct TlsConfig {
+    /// The path to a PEM-encoded certificate chain
+    pub cert: PathBuf,
+    /// The path to a PEM-encoded private key
+    pub key: PathBuf,
+}
+
+/// The registration of a tenant of the gRPC server
+#[derive(Clone, Debug, Deserialize)]
+pub struct TenantConfig {
+    /// The tenant identifier
+    pub id: String,
+    /// The store profile assigned to the tenant, defaulting to the identifier
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// The hex-encoded SHA-256 hash of the bearer token issued to the tenant
+    pub token_sha256: String,
+}
+
+/// An authenticated tenant of the gRPC server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tenant {
+    /// The tenant identifier
+    pub id: String,
+    /// The store profile assigned to the tenant
+    pub profile: String,
+}
+
+/// An interceptor which resolves the tenant for each request from its bearer token
+#[derive(Clone, Debug)]
+pub struct TenantAuth {
+    tenants: Arc<HashMap<String, Arc<Tenant>>>,
+}
+
+impl TenantAuth {
+    /// Create a new interceptor for a set of registered tenants
+    pub fn new(tenants: &[TenantConfig]) -> Result<Self, Error> {
+        let mut by_token = HashMap::with_capacity(tenants.len());
+        for tenant in tenants {
+            let hash = parse_token_hash(&tenant.token_sha256)
+                .ok_or_else(|| err_msg!(Input, "Invalid token hash for tenant '{}'", tenant.id))?;
+            let profile = tenant.profile.clone().unwrap_or_else(|| tenant.id.clone());
+            let entry = Arc::new(Tenant {
+                id: tenant.id.clone(),
+                profile,
+            });
+            if by_token.insert(hash, entry).is_some() {
+                return Err(err_msg!(
+                    Input,
+                    "Duplicate token hash for tenant '{}'",
+                    tenant.id
+                ));
+            }
+        }
+        Ok(Self {
+            tenants: Arc::new(by_token),
+        })
+    }
+
+    /// Get the registered tenants
+    pub fn tenants(&self) -> impl Iterator<Item = &Tenant> {
+        self.tenants.values().map(AsRef::as_ref)
+    }
+
+    /// Resolve a tenant from a bearer token
+    pub fn authenticate(&self, token: &str) -> Option<Arc<Tenant>> {
+        self.tenants.get(&token_hash(token)).cloned()
+    }
+}
+
+impl Interceptor for TenantAuth {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+        let tenant = self
+            .authenticate(token)
+            .ok_or_else(|| Status::unauthenticated("Invalid bearer token"))?;
+        request.extensions_mut().insert(tenant);
+        Ok(request)
+    }
+}
+
+/// The gRPC service implementation, shared by the store and key management services
+#[derive(Clone, Debug)]
+pub struct AskarService {
+    store: Store,
+}
+
+impl AskarService {
+    /// Create a new service for an opened store
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    /// Start a session on the profile of the tenant making a request
+    async fn session<T>(&self, request: &Request<T>) -> Result<Session, Status> {
+        let tenant = request_tenant(request).ok_or_else(unauthenticated)?;
+        Ok(self.store.session(Some(tenant.profile.clone())).await?)
+    }
+
+    /// Start a transaction on the profile of the tenant making a request
+    async fn transaction<T>(&self, request: &Request<T>) -> Result<Session, Status> {
+        let tenant = request_tenant(request).ok_or_else(unauthenticated)?;
+        Ok(self.store.transaction(Some(tenant.profile.clone())).await?)
+    }
+}
+
+fn request_tenant<T>(request: &Request<T>) -> Option<Arc<Tenant>> {
+    request.extensions().get::<Arc<Tenant>>().cloned()
+}
+
+fn unauthenticated() -> Status {
+    Status::unauthenticated("Request is not authenticated")
+}
+
+impl From<Error> for Status {
+    fn from(err: Error) -> Self {
+        let code = match err.kind() {
+            ErrorKind::Archived => Code::FailedPrecondition,
+            ErrorKind::Busy => Code::Unavailable,
+            ErrorKind::Backend if err.is_retryable() => Code::Unavailable,
+            ErrorKind::Cancelled => Code::Cancelled,
+            ErrorKind::Duplicate => Code::AlreadyExists,
+            ErrorKind::Input => Code::InvalidArgument,
+            ErrorKind::NotFound => Code::NotFound,
+            ErrorKind::QuotaExceeded => Code::ResourceExhausted,
+            ErrorKind::Unsupported => Code::Unimplemented,
+            ErrorKind::Backend
+            | ErrorKind::Custom
+            | ErrorKind::Encryption
+            | ErrorKind::Unexpected => Code::Internal,
+        };
+        // the cause is not included, as it may expose details of the backend
+        Status::new(code, err.message().unwrap_or(err.kind().as_str()))
+    }
+}
+
+impl From<proto::EntryTag> for EntryTag {
+    fn from(tag: proto::EntryTag) -> Self {
+        if tag.plaintext {
+            Self::Plaintext(tag.name, tag.value)
+        } else {
+            Self::Encrypted(tag.name, tag.value)
+        }
+    }
+}
+
+impl From<&EntryTag> for proto::EntryTag {
+    fn from(tag: &EntryTag) -> Self {
+        match tag {
+            EntryTag::Encrypted(name, value) => Self {
+                name: name.clone(),
+                value: value.clone(),
+                plaintext: false,
+            },
+            EntryTag::Plaintext(name, value) => Self {
+                name: name.clone(),
+                value: value.clone(),
+                plaintext: true,
+            },
+        }
+    }
+}
+
+fn convert_tags(tags: Vec<proto::EntryTag>) -> Option<Vec<EntryTag>> {
+    if tags.is_empty() {
+        None
+    } else {
+        Some(tags.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Open the configured store and serve requests until `shutdown` completes
+///
+/// Without TLS settings, the server must listen on a loopback address.
+pub async fn serve(
+    config: ServerConfig,
+    pass_key: PassKey<'_>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Error> {
+    if config.tls.is_none() && !config.listen.ip().is_loopback() {
+        return Err(err_msg!(
+            Input,
+            "The gRPC server must use TLS unless it listens on a loopback address"
+        ));
+    }
+    let auth = TenantAuth::new(&config.tenants)?;
+    let key_method = parse_key_method(config.key_method.as_deref())?;
+    let store = Store::open(&config.store_uri, key_method, pass_key, None).await?;
+
+    for tenant in auth.tenants() {
+        store.ensure_profile(tenant.profile.clone()).await?;
+    }
+
+    let mut server = Server::builder();
+    if let Some(tls) = config.tls.as_ref() {
+        let cert =
+            std::fs::read(&tls.cert).map_err(err_map!(Input, "Error reading TLS certificate"))?;
+        let key = std::fs::read(&tls.key).map_err(err_map!(Input, "Error reading TLS key"))?;
+        server = server
+            .tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))
+            .map_err(err_map!(Input, "Invalid TLS configuration"))?;
+    }
+
+    let service = AskarService::new(store.clone());
+    let result = server
+        .add_service(
+            proto::store_service_server::StoreServiceServer::with_interceptor(
+                service.clone(),
+                auth.clone(),
+            ),
+        )
+        .add_service(proto::kms_service_server::KmsServiceServer::with_interceptor(service, auth))
+        .serve_with_shutdown(config.listen, shutdown)
+        .await
+        .map_err(err_map!(Unexpected, "Error running gRPC server"));
+    store.close().await?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tenant_auth_resolves_token() {
+        let token_sha256 = token_hash("secret");
+        let auth = TenantAuth::new(&[TenantConfig {
+            id: "acme".to_string(),
+            profile: None,
+            token_sha256: token_sha256.to_ascii_uppercase(),
+        }])
+        .expect("Error creating tenant auth");
+        assert_eq!(
+            auth.authenticate("secret").as_deref(),
+            Some(&Tenant {
+                id: "acme".to_string(),
+                profile: "acme".to_string()
+            })
+        );
+        assert!(auth.authenticate("other").is_none());
+
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer secret".parse().unwrap());
+        let request = auth.clone().call(request).expect("Error authenticating");
+        assert_eq!(request_tenant(&request).unwrap().id, "acme");
+
+        let status = auth.clone().call(Request::new(())).unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+
+    #[test]
+    fn tenant_auth_rejects_invalid_hash() {
+        let err = TenantAuth::new(&[TenantConfig {
+            id: "acme".to_string(),
+            profile: None,
+            token_sha256: "1234".to_string(),
+        }])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Input);
+    }
+
+    #[test]
+    fn serve_requires_tls_or_loopback() {
+        let config = ServerConfig {
+            listen: "0.0.0.0:0".parse().unwrap(),
+            store_uri: "sqlite://:memory:".to_string(),
+            key_method: None,
+            tls: None,
+            tenants: vec![],
+        };
+        let err = crate::future::block_on(serve(config, PassKey::empty(), async {})).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Input);
+    }
+}
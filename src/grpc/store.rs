@@ -0,0 +1,262 @@
+use std::str::FromStr;
+
+use tonic::{Request, Response, Status};
+
+use super::{
+    convert_tags,
+    proto::{self, batch_operation::Kind, store_service_server::StoreService},
+    AskarService,
+};
+use crate::{
+    entry::{Entry, EntryOperation, TagFilter},
+    error::Error,
+};
+
+fn parse_tag_filter(tag_filter: Option<&str>) -> Result<Option<TagFilter>, Error> {
+    Ok(tag_filter.map(TagFilter::from_str).transpose()?)
+}
+
+impl From<Entry> for proto::Entry {
+    fn from(entry: Entry) -> Self {
+        Self {
+            tags: entry.tags.iter().map(Into::into).collect(),
+            category: entry.category,
+            name: entry.name,
+            value: entry.value.to_vec(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl StoreService for AskarService {
+    async fn count(
+        &self,
+        request: Request<proto::CountRequest>,
+    ) -> Result<Response<proto::CountResponse>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let tag_filter = parse_tag_filter(req.tag_filter.as_deref())?;
+        let count = session.count(req.category.as_deref(), tag_filter).await?;
+        Ok(Response::new(proto::CountResponse { count }))
+    }
+
+    async fn fetch(
+        &self,
+        request: Request<proto::FetchRequest>,
+    ) -> Result<Response<proto::FetchResponse>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let entry = session.fetch(&req.category, &req.name, false).await?;
+        Ok(Response::new(proto::FetchResponse {
+            entry: entry.map(Into::into),
+        }))
+    }
+
+    async fn fetch_all(
+        &self,
+        request: Request<proto::FetchAllRequest>,
+    ) -> Result<Response<proto::FetchAllResponse>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let tag_filter = parse_tag_filter(req.tag_filter.as_deref())?;
+        let entries = session
+            .fetch_all(
+                req.category.as_deref(),
+                tag_filter,
+                req.limit,
+                None,
+                false,
+                false,
+            )
+            .await?;
+        Ok(Response::new(proto::FetchAllResponse {
+            entries: entries.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn insert(
+        &self,
+        request: Request<proto::UpdateRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let tags = convert_tags(req.tags);
+        session
+            .insert(
+                &req.category,
+                &req.name,
+                &req.value,
+                tags.as_deref(),
+                req.expiry_ms,
+            )
+            .await?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn replace(
+        &self,
+        request: Request<proto::UpdateRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let tags = convert_tags(req.tags);
+        session
+            .replace(
+                &req.category,
+                &req.name,
+                &req.value,
+                tags.as_deref(),
+                req.expiry_ms,
+            )
+            .await?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn remove(
+        &self,
+        request: Request<proto::RemoveRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        session.remove(&req.category, &req.name).await?;
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn remove_all(
+        &self,
+        request: Request<proto::RemoveAllRequest>,
+    ) -> Result<Response<proto::CountResponse>, Status> {
+        let mut session = self.session(&request).await?;
+        let req = request.into_inner();
+        let tag_filter = parse_tag_filter(req.tag_filter.as_deref())?;
+        let count = session
+            .remove_all(req.category.as_deref(), tag_filter)
+            .await?;
+        Ok(Response::new(proto::CountResponse { count }))
+    }
+
+    async fn batch(
+        &self,
+        request: Request<proto::BatchRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let mut txn = self.transaction(&request).await?;
+        for op in request.into_inner().operations {
+            let operation = match Kind::try_from(op.kind) {
+                Ok(Kind::Insert) => EntryOperation::Insert,
+                Ok(Kind::Replace) => EntryOperation::Replace,
+                Ok(Kind::Remove) => EntryOperation::Remove,
+                Err(_) => return Err(Status::invalid_argument("Unknown batch operation")),
+            };
+            let value = (operation != EntryOperation::Remove).then_some(op.value.as_slice());
+            let tags = convert_tags(op.tags);
+            // the transaction is rolled back when dropped
+            txn.update(
+                operation,
+                &op.category,
+                &op.name,
+                value,
+                tags.as_deref(),
+                op.expiry_ms,
+            )
+            .await?;
+        }
+        txn.commit().await?;
+        Ok(Response::new(proto::Empty {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        future::block_on,
+        grpc::Tenant,
+        store::{Store, StoreKeyMethod},
+    };
+
+    fn tenant_request<T>(profile: &str, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.extensions_mut().insert(Arc::new(Tenant {
+            id: profile.to_string(),
+            profile: profile.to_string(),
+        }));
+        request
+    }
+
+    #[test]
+    fn tenant_profile_isolation() {
+        block_on(async {
+            let pass_key = Store::new_raw_key(None).unwrap();
+            let store = Store::provision(
+                "sqlite://:memory:",
+                StoreKeyMethod::RawKey,
+                pass_key,
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+            store.create_profile(Some("a".to_string())).await.unwrap();
+            store.create_profile(Some("b".to_string())).await.unwrap();
+            let service = AskarService::new(store);
+
+            service
+                .batch(tenant_request(
+                    "a",
+                    proto::BatchRequest {
+                        operations: vec![
+                            proto::BatchOperation {
+                                kind: Kind::Insert.into(),
+                                category: "cat".to_string(),
+                                name: "one".to_string(),
+                                value: b"1".to_vec(),
+                                ..Default::default()
+                            },
+                            proto::BatchOperation {
+                                kind: Kind::Insert.into(),
+                                category: "cat".to_string(),
+                                name: "two".to_string(),
+                                value: b"2".to_vec(),
+                                ..Default::default()
+                            },
+                        ],
+                    },
+                ))
+                .await
+                .expect("Error applying batch");
+
+            let count = |profile: &str| {
+                service.count(tenant_request(
+                    profile,
+                    proto::CountRequest {
+                        category: Some("cat".to_string()),
+                        tag_filter: None,
+                    },
+                ))
+            };
+            assert_eq!(count("a").await.unwrap().into_inner().count, 2);
+            assert_eq!(count("b").await.unwrap().into_inner().count, 0);
+
+            let fetched = service
+                .fetch(tenant_request(
+                    "b",
+                    proto::FetchRequest {
+                        category: "cat".to_string(),
+                        name: "one".to_string(),
+                    },
+                ))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(fetched.entry, None);
+
+            let status = service
+                .count(Request::new(proto::CountRequest::default()))
+                .await
+                .unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        });
+    }
+}
@@ -0,0 +1,194 @@
+//! Callbacks for store operations
+//!
+//! Hooks are registered on a [`Store`](crate::Store) using
+//! [`Store::add_hook`](crate::Store::add_hook), and apply to the sessions it
+//! subsequently creates. They are notified of successful operations only, allowing
+//! embedding applications to invalidate caches, trigger replication or perform
+//! custom auditing. Changes made within a transaction are reported once the
+//! transaction has been committed, and are discarded on rollback.
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
+
+use crate::storage::redact::{redact_category, redact_name};
+
+/// A receiver for notifications of store operations
+///
+/// All methods have empty default implementations, so that implementations need
+/// only handle the operations of interest. Hooks are invoked synchronously on the
+/// calling task and should avoid blocking for long periods.
+pub trait StoreHook: Send + Sync {
+    /// A record was inserted
+    fn on_insert(&self, profile: &str, category: &str, name: &str) {
+        let _ = (profile, category, name);
+    }
+
+    /// The value and tags of a record were replaced
+    fn on_replace(&self, profile: &str, category: &str, name: &str) {
+        let _ = (profile, category, name);
+    }
+
+    /// A record was removed
+    fn on_remove(&self, profile: &str, category: &str, name: &str) {
+        let _ = (profile, category, name);
+    }
+
+    /// The records matching a category and tag filter were removed
+    fn on_remove_all(&self, profile: &str, category: Option<&str>, removed: i64) {
+        let _ = (profile, category, removed);
+    }
+
+    /// A key was added to the store
+    fn on_insert_key(&self, profile: &str, name: &str) {
+        let _ = (profile, name);
+    }
+
+    /// The metadata and tags of a stored key were replaced
+    fn on_update_key(&self, profile: &str, name: &str) {
+        let _ = (profile, name);
+    }
+
+    /// A key was removed from the store
+    fn on_remove_key(&self, profile: &str, name: &str) {
+        let _ = (profile, name);
+    }
+
+    /// A message was signed using a stored key
+    fn on_key_sign(&self, profile: &str, name: &str) {
+        let _ = (profile, name);
+    }
+
+    /// A profile was created
+    fn on_create_profile(&self, profile: &str) {
+        let _ = profile;
+    }
+
+    /// A profile and all of its records were removed
+    fn on_remove_profile(&self, profile: &str) {
+        let _ = profile;
+    }
+}
+
+/// A store operation reported to the registered hooks
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) enum HookEvent {
+    Insert {
+        category: String,
+        name: String,
+    },
+    Replace {
+        category: String,
+        name: String,
+    },
+    Remove {
+        category: String,
+        name: String,
+    },
+    RemoveAll {
+        category: Option<String>,
+        removed: i64,
+    },
+    InsertKey {
+        name: String,
+    },
+    UpdateKey {
+        name: String,
+    },
+    RemoveKey {
+        name: String,
+    },
+    KeySign {
+        name: String,
+    },
+    CreateProfile,
+    RemoveProfile,
+}
+
+impl Debug for HookEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Insert { category, name } => f
+                .debug_struct("Insert")
+                .field("category", &redact_category(category))
+                .field("name", &redact_name(name))
+                .finish(),
+            Self::Replace { category, name } => f
+                .debug_struct("Replace")
+                .field("category", &redact_category(category))
+                .field("name", &redact_name(name))
+                .finish(),
+            Self::Remove { category, name } => f
+                .debug_struct("Remove")
+                .field("category", &redact_category(category))
+                .field("name", &redact_name(name))
+                .finish(),
+            Self::RemoveAll { category, removed } => f
+                .debug_struct("RemoveAll")
+                .field("category", &category.as_deref().map(redact_category))
+                .field("removed", removed)
+                .finish(),
+            Self::InsertKey { name } => f
+                .debug_struct("InsertKey")
+                .field("name", &redact_name(name))
+                .finish(),
+            Self::UpdateKey { name } => f
+                .debug_struct("UpdateKey")
+                .field("name", &redact_name(name))
+                .finish(),
+            Self::RemoveKey { name } => f
+                .debug_struct("RemoveKey")
+                .field("name", &redact_name(name))
+                .finish(),
+            Self::KeySign { name } => f
+                .debug_struct("KeySign")
+                .field("name", &redact_name(name))
+                .finish(),
+            Self::CreateProfile => f.write_str("CreateProfile"),
+            Self::RemoveProfile => f.write_str("RemoveProfile"),
+        }
+    }
+}
+
+/// The hooks registered on a store instance
+#[derive(Clone, Default)]
+pub(crate) struct HookList(Vec<Arc<dyn StoreHook>>);
+
+impl HookList {
+    pub fn push(&mut self, hook: Arc<dyn StoreHook>) {
+        self.0.push(hook);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Notify each hook of an operation on a profile
+    pub fn dispatch(&self, profile: &str, event: &HookEvent) {
+        for hook in self.0.iter() {
+            match event {
+                HookEvent::Insert { category, name } => hook.on_insert(profile, category, name),
+                HookEvent::Replace { category, name } => hook.on_replace(profile, category, name),
+                HookEvent::Remove { category, name } => hook.on_remove(profile, category, name),
+                HookEvent::RemoveAll { category, removed } => {
+                    hook.on_remove_all(profile, category.as_deref(), *removed)
+                }
+                HookEvent::InsertKey { name } => hook.on_insert_key(profile, name),
+                HookEvent::UpdateKey { name } => hook.on_update_key(profile, name),
+                HookEvent::RemoveKey { name } => hook.on_remove_key(profile, name),
+                HookEvent::KeySign { name } => hook.on_key_sign(profile, name),
+                HookEvent::CreateProfile => hook.on_create_profile(profile),
+                HookEvent::RemoveProfile => hook.on_remove_profile(profile),
+            }
+        }
+    }
+}
+
+impl Debug for HookList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HookList")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
@@ -1,10 +1,14 @@
-use super::local_key::LocalKey;
+use super::{local_key::LocalKey, remote::RemoteKeyHandle};
 use crate::{
     crypto::{alg::AnyKey, alg::KeyAlg, buffer::SecretBytes, jwk::FromJwk},
     entry::{Entry, EntryTag},
     error::Error,
+    storage::redact::redact_name,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    str::FromStr,
 };
-use std::str::FromStr;
 
 /// Key reference variant
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -12,15 +16,28 @@ pub enum KeyReference {
     /// Stored in a mobile secure element
     MobileSecureElement,
 
+    /// Held by a registered remote key service
+    Remote {
+        /// The name of the remote key service
+        service: String,
+        /// The key identifier within the service
+        key_id: String,
+    },
+
     /// Any other reference as fallback
     Any(String),
 }
 
 impl From<&str> for KeyReference {
     fn from(value: &str) -> Self {
-        match value {
-            "mobile_secure_element" => Self::MobileSecureElement,
-            any => Self::Any(String::from(any)),
+        let mut parts = value.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("mobile_secure_element"), None, None) => Self::MobileSecureElement,
+            (Some("remote"), Some(service), Some(key_id)) => Self::Remote {
+                service: String::from(service),
+                key_id: String::from(key_id),
+            },
+            _ => Self::Any(String::from(value)),
         }
     }
 }
@@ -29,11 +46,66 @@ impl From<KeyReference> for String {
     fn from(key_reference: KeyReference) -> Self {
         match key_reference {
             KeyReference::MobileSecureElement => String::from("mobile_secure_element"),
+            KeyReference::Remote { service, key_id } => format!("remote:{}:{}", service, key_id),
             KeyReference::Any(s) => s,
         }
     }
 }
 
+/// The means by which a key was created
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Generated locally from a random source
+    Generated,
+
+    /// Imported from existing key material
+    Imported,
+
+    /// Derived from a seed or by key agreement
+    Derived,
+
+    /// Generated within a hardware security module or secure element
+    Hardware,
+
+    /// Held by a remote key service
+    Remote,
+}
+
+impl KeySource {
+    /// Convert the key source to a string reference
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Generated => "generated",
+            Self::Imported => "imported",
+            Self::Derived => "derived",
+            Self::Hardware => "hardware",
+            Self::Remote => "remote",
+        }
+    }
+}
+
+/// Attestation metadata describing the origin of a key
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyOrigin {
+    /// The means by which the key was created
+    pub source: KeySource,
+
+    /// The firmware version of the hardware device holding the key, if known
+    #[serde(default, rename = "fw", skip_serializing_if = "Option::is_none")]
+    pub firmware_version: Option<String>,
+}
+
+impl KeyOrigin {
+    /// Create a new key origin with the given source
+    pub fn new(source: KeySource) -> Self {
+        Self {
+            source,
+            firmware_version: None,
+        }
+    }
+}
+
 /// Parameters defining a stored key
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct KeyParams {
@@ -50,6 +122,10 @@ pub struct KeyParams {
     /// - Stored as a key id for hardware-backed keys
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub data: Option<SecretBytes>,
+
+    /// The recorded origin of the key, absent for keys stored by earlier versions
+    #[serde(default, rename = "orig", skip_serializing_if = "Option::is_none")]
+    pub origin: Option<KeyOrigin>,
 }
 
 impl KeyParams {
@@ -76,7 +152,7 @@ impl KeyParams {
 }
 
 /// A stored key entry
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct KeyEntry {
     /// The key entry identifier
     pub(crate) name: String,
@@ -101,6 +177,11 @@ impl KeyEntry {
         self.params.metadata.as_ref().map(String::as_ref)
     }
 
+    /// Accessor for the recorded key origin
+    pub fn origin(&self) -> Option<&KeyOrigin> {
+        self.params.origin.as_ref()
+    }
+
     /// Accessor for the key identity
     pub fn name(&self) -> &str {
         self.name.as_str()
@@ -116,6 +197,21 @@ impl KeyEntry {
         self.params.reference.is_none()
     }
 
+    /// Create a handle for a key held by a remote key service
+    ///
+    /// The public key is loaded from the stored entry, and the remote key service
+    /// is only contacted when a private key operation is performed.
+    pub fn load_remote_key(&self) -> Result<RemoteKeyHandle, Error> {
+        match &self.params.reference {
+            Some(KeyReference::Remote { service, key_id }) => Ok(RemoteKeyHandle::new(
+                service,
+                key_id,
+                self.load_local_key()?,
+            )),
+            _ => Err(err_msg!(Input, "Key entry does not refer to a remote key")),
+        }
+    }
+
     pub(crate) fn from_entry(entry: Entry) -> Result<Self, Error> {
         let params = KeyParams::from_slice(&entry.value)?;
         let mut alg = None;
@@ -160,11 +256,16 @@ impl KeyEntry {
                         .as_ref()
                         .ok_or(err_msg!(Input, "Algorithm is required to get key by id"))?;
                     let alg = KeyAlg::from_str(alg)?;
-                    Ok(LocalKey::from_id(alg, &id)?)
+                    let mut key = LocalKey::from_id(alg, &id)?;
+                    if self.params.origin.is_some() {
+                        key.origin = self.params.origin.clone();
+                    }
+                    Ok(key)
                 }
                 _ => Ok(LocalKey {
                     inner: Box::<AnyKey>::from_jwk_slice(key_data.as_ref())?,
                     ephemeral: false,
+                    origin: self.params.origin.clone(),
                 }),
             }
         } else {
@@ -173,6 +274,18 @@ impl KeyEntry {
     }
 }
 
+impl Debug for KeyEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyEntry")
+            .field("name", &redact_name(&self.name))
+            .field("params", &self.params)
+            .field("alg", &self.alg)
+            .field("thumbprints", &self.thumbprints)
+            .field("tags", &self.tags)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,9 +296,43 @@ mod tests {
             metadata: Some("meta".to_string()),
             reference: None,
             data: Some(SecretBytes::from(vec![0, 0, 0, 0])),
+            origin: Some(KeyOrigin {
+                source: KeySource::Hardware,
+                firmware_version: Some("1.2".to_string()),
+            }),
         };
         let enc_params = params.to_bytes().unwrap();
         let p2 = KeyParams::from_slice(&enc_params).unwrap();
         assert_eq!(p2, params);
     }
+
+    #[test]
+    fn key_reference_remote() {
+        let reference = KeyReference::from("remote:signer:key:1");
+        assert_eq!(
+            reference,
+            KeyReference::Remote {
+                service: "signer".to_string(),
+                key_id: "key:1".to_string(),
+            }
+        );
+        assert_eq!(String::from(reference), "remote:signer:key:1");
+        assert_eq!(
+            KeyReference::from("remote:signer"),
+            KeyReference::Any("remote:signer".to_string())
+        );
+    }
+
+    #[test]
+    fn key_params_without_origin() {
+        let params = KeyParams {
+            metadata: None,
+            reference: None,
+            data: Some(SecretBytes::from(vec![0, 0, 0, 0])),
+            origin: None,
+        };
+        let enc_params = params.to_bytes().unwrap();
+        let p2 = KeyParams::from_slice(&enc_params).unwrap();
+        assert_eq!(p2.origin, None);
+    }
 }
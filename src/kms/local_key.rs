@@ -1,7 +1,10 @@
 use std::borrow::Cow;
 use std::str::FromStr;
 
-use super::enc::{Encrypted, ToDecrypt};
+use super::{
+    enc::{Encrypted, ToDecrypt},
+    entry::{KeyOrigin, KeySource},
+};
 pub use crate::crypto::{
     alg::KeyAlg,
     backend::KeyBackend,
@@ -9,6 +12,7 @@ pub use crate::crypto::{
     encrypt::KeyAeadParams,
 };
 use crate::{
+    audit::{audit, AuditOperation},
     crypto::{
         alg::{bls::BlsKeyGen, AnyKey, AnyKeyCreate, BlsCurves},
         encrypt::KeyAeadInPlace,
@@ -30,19 +34,28 @@ use crate::crypto::alg::p256_hardware::P256HardwareKeyPair;
 pub struct LocalKey {
     pub(crate) inner: Box<AnyKey>,
     pub(crate) ephemeral: bool,
+    pub(crate) origin: Option<KeyOrigin>,
 }
 
 impl LocalKey {
     /// Create a new random key or keypair
     pub fn generate_with_rng(alg: KeyAlg, ephemeral: bool) -> Result<Self, Error> {
         let inner = Box::<AnyKey>::random(alg)?;
-        Ok(Self { inner, ephemeral })
+        Ok(Self {
+            inner,
+            ephemeral,
+            origin: Some(KeyOrigin::new(KeySource::Generated)),
+        })
     }
 
     /// Create a new random keypair backed by hardware
     pub fn generate_for_hardware(alg: KeyAlg, ephemeral: bool) -> Result<Self, Error> {
         let inner = Box::<AnyKey>::generate_for_hardware(alg)?;
-        Ok(Self { inner, ephemeral })
+        Ok(Self {
+            inner,
+            ephemeral,
+            origin: Some(KeyOrigin::new(KeySource::Hardware)),
+        })
     }
 
     /// Get a local key by id
@@ -51,6 +64,7 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            origin: Some(KeyOrigin::new(KeySource::Hardware)),
         })
     }
 
@@ -69,6 +83,7 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            origin: Some(KeyOrigin::new(KeySource::Derived)),
         })
     }
 
@@ -78,6 +93,7 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            origin: Some(KeyOrigin::new(KeySource::Imported)),
         })
     }
 
@@ -87,6 +103,7 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            origin: Some(KeyOrigin::new(KeySource::Imported)),
         })
     }
 
@@ -96,6 +113,7 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            origin: Some(KeyOrigin::new(KeySource::Imported)),
         })
     }
 
@@ -110,12 +128,15 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            origin: Some(KeyOrigin::new(KeySource::Imported)),
         })
     }
 
     /// Export the raw bytes of the private key
     pub fn to_secret_bytes(&self) -> Result<SecretBytes, Error> {
-        Ok(self.inner.to_secret_bytes()?)
+        let result = self.inner.to_secret_bytes().map_err(Error::from);
+        self.audit(AuditOperation::ExportKey, &result);
+        result
     }
 
     /// Derive a new key from a Diffie-Hellman exchange between this keypair and a public key
@@ -124,6 +145,7 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: self.ephemeral || pk.ephemeral,
+            origin: Some(KeyOrigin::new(KeySource::Derived)),
         })
     }
 
@@ -135,6 +157,7 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            origin: Some(KeyOrigin::new(KeySource::Derived)),
         })
     }
 
@@ -142,6 +165,16 @@ impl LocalKey {
         Ok(self.inner.to_jwk_secret(None)?)
     }
 
+    /// Accessor for the recorded origin of the key, if known
+    pub fn origin(&self) -> Option<&KeyOrigin> {
+        self.origin.as_ref()
+    }
+
+    /// Replace the recorded origin of the key, for instance to add device attestation details
+    pub fn set_origin(&mut self, origin: Option<KeyOrigin>) {
+        self.origin = origin;
+    }
+
     /// Accessor for the key algorithm
     pub fn algorithm(&self) -> KeyAlg {
         self.inner.algorithm()
@@ -154,7 +187,9 @@ impl LocalKey {
 
     /// Get the JWK representation for this private key or keypair
     pub fn to_jwk_secret(&self) -> Result<SecretBytes, Error> {
-        Ok(self.inner.to_jwk_secret(None)?)
+        let result = self.inner.to_jwk_secret(None).map_err(Error::from);
+        self.audit(AuditOperation::ExportKey, &result);
+        result
     }
 
     /// Get the JWK thumbprint for this key or keypair
@@ -182,6 +217,7 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: self.ephemeral,
+            origin: self.origin.clone(),
         })
     }
 
@@ -250,13 +286,17 @@ impl LocalKey {
 
     /// Sign a message with this private signing key
     pub fn sign_message(&self, message: &[u8], sig_type: Option<&str>) -> Result<Vec<u8>, Error> {
-        let mut sig = Vec::new();
-        self.inner.write_signature(
-            message,
-            sig_type.map(SignatureType::from_str).transpose()?,
-            &mut sig,
-        )?;
-        Ok(sig)
+        let result = sig_type
+            .map(SignatureType::from_str)
+            .transpose()
+            .and_then(|sig_type| {
+                let mut sig = Vec::new();
+                self.inner.write_signature(message, sig_type, &mut sig)?;
+                Ok(sig)
+            })
+            .map_err(Error::from);
+        self.audit(AuditOperation::Sign, &result);
+        result
     }
 
     /// Verify a message signature with this private signing key or public verification key
@@ -298,6 +338,16 @@ impl LocalKey {
         Self::from_secret_bytes(alg, buf.as_ref())
     }
 
+    fn audit<T>(&self, operation: AuditOperation, result: &Result<T, Error>) {
+        audit(
+            operation,
+            None,
+            None,
+            Some(self.algorithm().as_str()),
+            result,
+        )
+    }
+
     /// Check whether the key is hardware backed by checking the type id of the underlying
     /// structure
     pub fn is_hardware_backed(&self) -> bool {
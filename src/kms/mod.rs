@@ -19,11 +19,17 @@ pub use self::envelope::{
 };
 
 mod entry;
-pub use self::entry::{KeyEntry, KeyParams, KeyReference};
+pub use self::entry::{KeyEntry, KeyOrigin, KeyParams, KeyReference, KeySource};
 
 mod local_key;
 pub use self::local_key::{KeyAlg, KeyBackend, LocalKey};
 
+mod remote;
+pub use self::remote::{register_remote_key_service, RemoteKey, RemoteKeyHandle, RemoteKeyService};
+
+mod wrap;
+pub use self::wrap::{unwrap_key_jwe, wrap_key_jwe, UnwrappingKey, WrappedKey};
+
 /// Supported categories of KMS entries
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Zeroize)]
 pub(crate) enum KmsCategory {
@@ -0,0 +1,232 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter},
+    sync::{Arc, RwLock},
+};
+
+use super::{
+    entry::{KeyOrigin, KeySource},
+    local_key::{KeyAlg, LocalKey},
+};
+use crate::{
+    audit::{audit, AuditOperation},
+    crypto::buffer::SecretBytes,
+    error::Error,
+    future::BoxFuture,
+};
+
+static REMOTE_KEY_SERVICES: RwLock<BTreeMap<String, Arc<dyn RemoteKeyService>>> =
+    RwLock::new(BTreeMap::new());
+static REMOTE_KEY_CACHE: RwLock<BTreeMap<String, Arc<dyn RemoteKey>>> =
+    RwLock::new(BTreeMap::new());
+
+/// A private key held by an external signing service
+///
+/// Implementations forward each operation to the service, for example over REST
+/// or gRPC, such that the private key material is never present on the agent host.
+/// Operations return futures so that requests to the service do not block the
+/// async runtime.
+pub trait RemoteKey: Send + Sync {
+    /// Accessor for the key algorithm
+    fn algorithm(&self) -> KeyAlg;
+
+    /// Fetch the compact representation of the public key
+    fn public_bytes(&self) -> BoxFuture<'_, Result<Vec<u8>, Error>>;
+
+    /// Sign a message with the remote private key
+    fn sign_message<'a>(
+        &'a self,
+        message: &'a [u8],
+        sig_type: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Vec<u8>, Error>>;
+
+    /// Perform a Diffie-Hellman exchange with a public key, returning the shared secret
+    fn key_exchange<'a>(
+        &'a self,
+        _public: &'a LocalKey,
+    ) -> BoxFuture<'a, Result<SecretBytes, Error>> {
+        Box::pin(async {
+            Err(err_msg!(
+                Unsupported,
+                "Key agreement is not supported by the remote key"
+            ))
+        })
+    }
+}
+
+/// A connection to an external signing service
+pub trait RemoteKeyService: Send + Sync {
+    /// Resolve a key identifier into a handle for the remote key
+    fn load_key<'a>(&'a self, key_id: &'a str) -> BoxFuture<'a, Result<Arc<dyn RemoteKey>, Error>>;
+}
+
+/// Register a remote key service under a name, replacing any existing service
+pub fn register_remote_key_service(
+    name: &str,
+    service: Arc<dyn RemoteKeyService>,
+) -> Result<(), Error> {
+    if name.is_empty() || name.contains(':') {
+        return Err(err_msg!(Input, "Invalid remote key service name"));
+    }
+    REMOTE_KEY_SERVICES
+        .write()
+        .unwrap()
+        .insert(name.to_string(), service);
+    let prefix = format!("{}:", name);
+    REMOTE_KEY_CACHE
+        .write()
+        .unwrap()
+        .retain(|cache_key, _| !cache_key.starts_with(&prefix));
+    Ok(())
+}
+
+/// A stored key whose private key operations are delegated to a remote key service
+///
+/// The public key is held locally, so that verification does not require
+/// contacting the service. Remote handles are cached between operations. When a
+/// remote operation fails the error is returned to the caller without falling
+/// back to any local key material, and the cached handle is discarded so that the
+/// next operation resolves the key through the service again.
+pub struct RemoteKeyHandle {
+    service: String,
+    key_id: String,
+    public: LocalKey,
+}
+
+impl RemoteKeyHandle {
+    /// Resolve a key through a registered remote key service
+    pub async fn load(service: &str, key_id: &str) -> Result<Self, Error> {
+        let result = async {
+            let remote = resolve_remote_key(service, key_id).await?;
+            let pk = remote.public_bytes().await?;
+            LocalKey::from_public_bytes(remote.algorithm(), &pk)
+        }
+        .await;
+        let mut public = evict_on_error(service, key_id, result)?;
+        public.origin = Some(KeyOrigin::new(KeySource::Remote));
+        Ok(Self::new(service, key_id, public))
+    }
+
+    pub(crate) fn new(service: &str, key_id: &str, public: LocalKey) -> Self {
+        Self {
+            service: service.to_string(),
+            key_id: key_id.to_string(),
+            public,
+        }
+    }
+
+    /// Accessor for the name of the remote key service
+    pub fn service(&self) -> &str {
+        self.service.as_str()
+    }
+
+    /// Accessor for the key identifier within the remote key service
+    pub fn key_id(&self) -> &str {
+        self.key_id.as_str()
+    }
+
+    /// Accessor for the key algorithm
+    pub fn algorithm(&self) -> KeyAlg {
+        self.public.algorithm()
+    }
+
+    /// Accessor for the public key
+    pub fn public_key(&self) -> &LocalKey {
+        &self.public
+    }
+
+    /// Sign a message using the remote key service
+    pub async fn sign_message(
+        &self,
+        message: &[u8],
+        sig_type: Option<&str>,
+    ) -> Result<Vec<u8>, Error> {
+        let result = match self.remote().await {
+            Ok(remote) => remote.sign_message(message, sig_type).await,
+            Err(err) => Err(err),
+        };
+        let result = self.check(result);
+        audit(
+            AuditOperation::Sign,
+            None,
+            None,
+            Some(cache_key(&self.service, &self.key_id).as_str()),
+            &result,
+        );
+        result
+    }
+
+    /// Verify a message signature using the locally held public key
+    pub fn verify_signature(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        sig_type: Option<&str>,
+    ) -> Result<bool, Error> {
+        self.public.verify_signature(message, signature, sig_type)
+    }
+
+    /// Derive a new key from a Diffie-Hellman exchange performed by the remote key service
+    pub async fn to_key_exchange(&self, alg: KeyAlg, pk: &LocalKey) -> Result<LocalKey, Error> {
+        let result = match self.remote().await {
+            Ok(remote) => remote.key_exchange(pk).await,
+            Err(err) => Err(err),
+        };
+        let secret = self.check(result)?;
+        let mut key = LocalKey::from_secret_bytes(alg, secret.as_ref())?;
+        key.ephemeral = pk.ephemeral;
+        key.origin = Some(KeyOrigin::new(KeySource::Derived));
+        Ok(key)
+    }
+
+    async fn remote(&self) -> Result<Arc<dyn RemoteKey>, Error> {
+        resolve_remote_key(&self.service, &self.key_id).await
+    }
+
+    fn check<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        evict_on_error(&self.service, &self.key_id, result)
+    }
+}
+
+impl Debug for RemoteKeyHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteKeyHandle")
+            .field("service", &self.service)
+            .field("key_id", &self.key_id)
+            .field("alg", &self.algorithm())
+            .finish()
+    }
+}
+
+fn cache_key(service: &str, key_id: &str) -> String {
+    format!("{}:{}", service, key_id)
+}
+
+async fn resolve_remote_key(service: &str, key_id: &str) -> Result<Arc<dyn RemoteKey>, Error> {
+    let cache_key = cache_key(service, key_id);
+    if let Some(remote) = REMOTE_KEY_CACHE.read().unwrap().get(&cache_key) {
+        return Ok(remote.clone());
+    }
+    let service = REMOTE_KEY_SERVICES
+        .read()
+        .unwrap()
+        .get(service)
+        .cloned()
+        .ok_or_else(|| err_msg!(Unsupported, "Unregistered remote key service"))?;
+    let remote = service.load_key(key_id).await?;
+    REMOTE_KEY_CACHE
+        .write()
+        .unwrap()
+        .insert(cache_key, remote.clone());
+    Ok(remote)
+}
+
+fn evict_on_error<T>(service: &str, key_id: &str, result: Result<T, Error>) -> Result<T, Error> {
+    if result.is_err() {
+        REMOTE_KEY_CACHE
+            .write()
+            .unwrap()
+            .remove(&cache_key(service, key_id));
+    }
+    result
+}
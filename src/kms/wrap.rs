@@ -0,0 +1,223 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+
+use super::{envelope::derive_key_ecdh_es, local_key::LocalKey};
+use crate::{
+    crypto::{
+        alg::{AesTypes, KeyAlg},
+        jwk::ToJwk,
+    },
+    error::Error,
+};
+
+const ALG_ECDH_ES_A256KW: &str = "ECDH-ES+A256KW";
+const ENC_A256GCM: &str = "A256GCM";
+const CTY_JWK: &str = "jwk+json";
+
+/// The protected header of a wrapped key JWE
+#[derive(Deserialize, Serialize)]
+struct WrapHeader {
+    alg: String,
+    enc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cty: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    epk: Option<serde_json::Value>,
+}
+
+/// Externally wrapped key material
+#[derive(Clone, Copy, Debug)]
+pub enum WrappedKey<'a> {
+    /// A JWE in compact serialization with a secret JWK payload
+    Jwe(&'a str),
+    /// The secret bytes of a key of the given algorithm, wrapped according to RFC 3394
+    KeyWrap(KeyAlg, &'a [u8]),
+}
+
+impl WrappedKey<'_> {
+    /// Recover the wrapped key using the corresponding unwrapping key
+    pub fn unwrap(&self, unwrapping_key: &LocalKey) -> Result<LocalKey, Error> {
+        match self {
+            Self::Jwe(jwe) => unwrap_key_jwe(unwrapping_key, jwe),
+            Self::KeyWrap(alg, ciphertext) => {
+                if !is_key_wrap(unwrapping_key.algorithm()) {
+                    return Err(err_msg!(
+                        Input,
+                        "AES key wrap requires an A128KW or A256KW unwrapping key"
+                    ));
+                }
+                unwrapping_key.unwrap_key(*alg, *ciphertext, &[])
+            }
+        }
+    }
+}
+
+/// The key used to recover wrapped key material on import
+#[derive(Clone, Copy, Debug)]
+pub enum UnwrappingKey<'a> {
+    /// A key instance provided by the caller
+    Local(&'a LocalKey),
+    /// The name of a key stored in the current profile
+    Stored(&'a str),
+}
+
+impl<'a> From<&'a LocalKey> for UnwrappingKey<'a> {
+    fn from(key: &'a LocalKey) -> Self {
+        Self::Local(key)
+    }
+}
+
+impl<'a> From<&'a str> for UnwrappingKey<'a> {
+    fn from(name: &'a str) -> Self {
+        Self::Stored(name)
+    }
+}
+
+/// Wrap a key for backup or escrow, producing a JWE in compact serialization
+///
+/// The JWE payload is the secret JWK of `key`, encrypted with A256GCM under a
+/// random content encryption key. That key is wrapped with `wrapping_key` directly
+/// for AES key wrap keys (A128KW or A256KW), or with ECDH-ES+A256KW for X25519
+/// and elliptic curve keys, in which case only the public key is required.
+pub fn wrap_key_jwe(wrapping_key: &LocalKey, key: &LocalKey) -> Result<String, Error> {
+    let kek_alg = wrapping_key.algorithm();
+    let (alg, ephem_key) = match kek_alg {
+        KeyAlg::Aes(AesTypes::A128Kw) | KeyAlg::Aes(AesTypes::A256Kw) => {
+            (kek_alg.as_str().to_ascii_uppercase(), None)
+        }
+        KeyAlg::X25519 | KeyAlg::EcCurve(_) => (
+            ALG_ECDH_ES_A256KW.to_string(),
+            Some(LocalKey::generate_with_rng(kek_alg, true)?),
+        ),
+        _ => {
+            return Err(err_msg!(
+                Unsupported,
+                "Unsupported key algorithm for key wrapping"
+            ))
+        }
+    };
+    let epk = ephem_key
+        .as_ref()
+        .map(|ephem| ephem.to_jwk_public(None))
+        .transpose()?
+        .map(|jwk| serde_json::from_str(&jwk))
+        .transpose()
+        .map_err(err_map!(Unexpected, "Error encoding ephemeral key"))?;
+    let header = serde_json::to_vec(&WrapHeader {
+        alg: alg.clone(),
+        enc: ENC_A256GCM.to_string(),
+        cty: Some(CTY_JWK.to_string()),
+        epk,
+    })
+    .map_err(err_map!(Unexpected, "Error encoding JWE header"))?;
+    let header = URL_SAFE_NO_PAD.encode(header);
+
+    let derived_kek;
+    let kek = if let Some(ephem_key) = ephem_key.as_ref() {
+        derived_kek = derive_key_ecdh_es(
+            KeyAlg::Aes(AesTypes::A256Kw),
+            ephem_key,
+            wrapping_key,
+            alg.as_bytes(),
+            &[],
+            &[],
+            false,
+        )?;
+        &derived_kek
+    } else {
+        wrapping_key
+    };
+
+    let cek = LocalKey::generate_with_rng(KeyAlg::Aes(AesTypes::A256Gcm), true)?;
+    let enc_cek = kek.wrap_key(&cek, &[])?;
+    let jwk = key.inner.to_jwk_secret(None)?;
+    let enc = cek.aead_encrypt(jwk.as_ref(), &[], header.as_bytes())?;
+
+    Ok(format!(
+        "{}.{}.{}.{}.{}",
+        header,
+        URL_SAFE_NO_PAD.encode(enc_cek.ciphertext()),
+        URL_SAFE_NO_PAD.encode(enc.nonce()),
+        URL_SAFE_NO_PAD.encode(enc.ciphertext()),
+        URL_SAFE_NO_PAD.encode(enc.tag()),
+    ))
+}
+
+/// Unwrap a key produced by [`wrap_key_jwe`] or a compatible JWE encoder
+pub fn unwrap_key_jwe(unwrapping_key: &LocalKey, jwe: &str) -> Result<LocalKey, Error> {
+    let mut parts = jwe.trim().split('.');
+    let (header_b64, enc_cek, nonce, ciphertext, tag) = match (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) {
+        (Some(h), Some(k), Some(n), Some(c), Some(t), None) => (h, k, n, c, t),
+        _ => return Err(err_msg!(Input, "Invalid JWE compact serialization")),
+    };
+    let decode = |part: &str| {
+        URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(err_map!(Input, "Invalid base64 encoding in JWE"))
+    };
+    let header_bytes = decode(header_b64)?;
+    let header: WrapHeader = serde_json::from_slice(&header_bytes)
+        .map_err(err_map!(Input, "Invalid JWE protected header"))?;
+    if header.enc != ENC_A256GCM {
+        return Err(err_msg!(Unsupported, "Unsupported JWE content encryption"));
+    }
+
+    let kek_alg = unwrapping_key.algorithm();
+    let derived_kek;
+    let kek = match header.alg.as_str() {
+        ALG_ECDH_ES_A256KW => {
+            if !matches!(kek_alg, KeyAlg::X25519 | KeyAlg::EcCurve(_)) {
+                return Err(err_msg!(Input, "Unwrapping key does not support ECDH-ES"));
+            }
+            let epk = header
+                .epk
+                .ok_or_else(|| err_msg!(Input, "Missing ephemeral key in JWE header"))?;
+            let ephem_key = LocalKey::from_jwk(&epk.to_string())?;
+            derived_kek = derive_key_ecdh_es(
+                KeyAlg::Aes(AesTypes::A256Kw),
+                &ephem_key,
+                unwrapping_key,
+                header.alg.as_bytes(),
+                &[],
+                &[],
+                true,
+            )?;
+            &derived_kek
+        }
+        alg if alg.eq_ignore_ascii_case(kek_alg.as_str()) && is_key_wrap(kek_alg) => unwrapping_key,
+        _ => {
+            return Err(err_msg!(
+                Unsupported,
+                "Unsupported JWE key management algorithm"
+            ))
+        }
+    };
+
+    let cek = kek.unwrap_key(
+        KeyAlg::Aes(AesTypes::A256Gcm),
+        decode(enc_cek)?.as_slice(),
+        &[],
+    )?;
+    let (ciphertext, tag) = (decode(ciphertext)?, decode(tag)?);
+    let jwk = cek.aead_decrypt(
+        (ciphertext.as_slice(), tag.as_slice()),
+        &decode(nonce)?,
+        header_b64.as_bytes(),
+    )?;
+    LocalKey::from_jwk_slice(jwk.as_ref())
+}
+
+#[inline]
+fn is_key_wrap(alg: KeyAlg) -> bool {
+    matches!(
+        alg,
+        KeyAlg::Aes(AesTypes::A128Kw) | KeyAlg::Aes(AesTypes::A256Kw)
+    )
+}
@@ -5,7 +5,7 @@
 
 #[macro_use]
 mod error;
-pub use self::error::{Error, ErrorKind};
+pub use self::error::{Error, ErrorContext, ErrorKind};
 
 #[cfg(any(test, feature = "logger"))]
 #[macro_use]
@@ -24,7 +24,45 @@ pub use askar_storage::future;
 #[cfg(feature = "ffi")]
 mod ffi;
 
+#[cfg(feature = "admin")]
+pub mod admin;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+#[cfg(any(feature = "admin", feature = "grpc"))]
+mod server;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!("aries_askar");
+
+#[cfg(feature = "uniffi")]
+mod uniffi_api;
+
+pub mod acapy;
+
+pub mod audit;
+
+mod builder;
+pub use builder::StoreBuilder;
+
+pub mod hooks;
+
 pub mod kms;
 
+mod manager;
+pub use manager::{StoreId, StoreManager};
+
+mod shared_session;
+pub use shared_session::SharedSession;
+
 mod store;
-pub use store::{entry, PassKey, Session, Store, StoreKeyMethod};
+pub use store::{
+    entry, redact, register_store_key_method, set_pass_key_validator, Argon2Level,
+    BackupSegmentInfo, ComponentHealth, ConflictResolution, EntryExpiry, ExpiryRule, HealthReport,
+    HealthStatus, ImportConflict, ImportReport, ImportStrategy, IntoEntryExpiry, KdfMethod,
+    KeyCacheStats, PassKey, PassKeyPolicy, PassKeyValidator, PoolStatus, ProfileExpiryPolicy,
+    ProfileFilter, ProfileQuota, ProfileStats, Progress, ProgressFn, ProgressPhase, RekeyProgress,
+    ReplicationCheckpoint, Session, SessionScope, Store, StoreKeyMethod, StoreKeyProvider,
+    TraceContext, ValueFormat, VersionInfo,
+};
@@ -0,0 +1,266 @@
+//! Tracking of multiple open stores
+//!
+//! A [`StoreManager`] opens stores on behalf of services which front many
+//! separate wallets, and looks them up by identifier. All stores share the
+//! process-wide pool of blocking threads used for cryptographic operations,
+//! which may be sized using `future::configure_blocking_pool`. Connection pool and key
+//! cache limits given as default options are applied to each store opened by
+//! the manager, bounding the resources held by a large number of stores.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Display, Formatter},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use async_lock::RwLock;
+
+use crate::{
+    error::Error,
+    hooks::StoreHook,
+    storage::Options,
+    store::{PassKey, Store, StoreKeyMethod},
+};
+
+/// The identifier of a store tracked by a `StoreManager`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StoreId(pub u64);
+
+impl Display for StoreId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+#[derive(Clone)]
+struct ManagedStore {
+    uri: Option<String>,
+    store: Store,
+}
+
+/// A collection of open stores, identified by `StoreId`
+pub struct StoreManager {
+    stores: RwLock<BTreeMap<StoreId, ManagedStore>>,
+    next_id: AtomicU64,
+    max_stores: Option<usize>,
+    default_options: Vec<(String, String)>,
+    audit_actor: Option<String>,
+    hooks: Vec<Arc<dyn StoreHook>>,
+}
+
+impl Default for StoreManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StoreManager {
+    /// Create an empty store manager
+    pub fn new() -> Self {
+        Self {
+            stores: RwLock::new(BTreeMap::new()),
+            next_id: AtomicU64::new(1),
+            max_stores: None,
+            default_options: Vec::new(),
+            audit_actor: None,
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Limit the number of stores which may be open at once
+    pub fn with_max_stores(mut self, max_stores: usize) -> Self {
+        self.max_stores = Some(max_stores);
+        self
+    }
+
+    /// Add a query parameter to the URI of each store opened by the manager,
+    /// unless the URI already sets it
+    ///
+    /// For example, `max_connections` and `max_cached_profiles` bound the
+    /// connections and profile keys held by each store.
+    pub fn with_default_option(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.default_options.push((name.into(), value.into()));
+        self
+    }
+
+    /// Assign the actor reported in audit records for the stores opened by the
+    /// manager
+    pub fn with_audit_actor(mut self, actor: impl Into<String>) -> Self {
+        self.audit_actor = Some(actor.into());
+        self
+    }
+
+    /// Register a hook on each store subsequently opened by the manager
+    pub fn with_hook(mut self, hook: Arc<dyn StoreHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Provision a new store and track it
+    pub async fn provision(
+        &self,
+        db_url: &str,
+        key_method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+        profile: Option<String>,
+        recreate: bool,
+    ) -> Result<StoreId, Error> {
+        self.check_capacity().await?;
+        let uri = self.apply_defaults(db_url)?;
+        let store = Store::provision(&uri, key_method, pass_key, profile, recreate).await?;
+        self.track(Some(uri), store).await
+    }
+
+    /// Open an existing store and track it
+    pub async fn open(
+        &self,
+        db_url: &str,
+        key_method: Option<StoreKeyMethod>,
+        pass_key: PassKey<'_>,
+        profile: Option<String>,
+    ) -> Result<StoreId, Error> {
+        self.check_capacity().await?;
+        let uri = self.apply_defaults(db_url)?;
+        let store = Store::open(&uri, key_method, pass_key, profile).await?;
+        self.track(Some(uri), store).await
+    }
+
+    /// Track a store opened by the caller
+    ///
+    /// The hooks and audit actor of the manager are not applied to the store.
+    pub async fn insert(&self, store: Store) -> Result<StoreId, Error> {
+        let mut stores = self.stores.write().await;
+        if self.at_capacity(stores.len()) {
+            return Err(err_msg!(Busy, "The maximum number of stores are open"));
+        }
+        let id = StoreId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        stores.insert(id, ManagedStore { uri: None, store });
+        Ok(id)
+    }
+
+    /// Get a tracked store by its identifier
+    pub async fn get(&self, id: StoreId) -> Result<Store, Error> {
+        self.stores
+            .read()
+            .await
+            .get(&id)
+            .map(|managed| managed.store.clone())
+            .ok_or_else(|| err_msg!(NotFound, "Store not found"))
+    }
+
+    /// Find a tracked store opened by the manager using a given database URL
+    ///
+    /// The URL is compared after applying the default options of the manager.
+    pub async fn find(&self, db_url: &str) -> Result<Option<StoreId>, Error> {
+        let uri = self.apply_defaults(db_url)?;
+        Ok(self
+            .stores
+            .read()
+            .await
+            .iter()
+            .find(|(_, managed)| managed.uri.as_deref() == Some(uri.as_str()))
+            .map(|(id, _)| *id))
+    }
+
+    /// Get the identifiers of the tracked stores, in the order they were added
+    pub async fn ids(&self) -> Vec<StoreId> {
+        self.stores.read().await.keys().copied().collect()
+    }
+
+    /// Get the number of tracked stores
+    pub async fn len(&self) -> usize {
+        self.stores.read().await.len()
+    }
+
+    /// Check whether no stores are tracked
+    pub async fn is_empty(&self) -> bool {
+        self.stores.read().await.is_empty()
+    }
+
+    /// Stop tracking a store and close it, returning `false` if it was not found
+    ///
+    /// Copies of the store obtained from the manager are closed as well.
+    pub async fn close(&self, id: StoreId) -> Result<bool, Error> {
+        let managed = self.stores.write().await.remove(&id);
+        if let Some(managed) = managed {
+            managed.store.close().await?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Close all tracked stores
+    ///
+    /// Every store is closed even when an error occurs, and the first error
+    /// encountered is returned.
+    pub async fn close_all(&self) -> Result<(), Error> {
+        let stores = std::mem::take(&mut *self.stores.write().await);
+        let mut result = Ok(());
+        for (_, managed) in stores {
+            if let Err(err) = managed.store.close().await {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+        result
+    }
+
+    fn at_capacity(&self, count: usize) -> bool {
+        self.max_stores.map(|max| count >= max).unwrap_or(false)
+    }
+
+    async fn check_capacity(&self) -> Result<(), Error> {
+        if self.at_capacity(self.stores.read().await.len()) {
+            Err(err_msg!(Busy, "The maximum number of stores are open"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn apply_defaults(&self, db_url: &str) -> Result<String, Error> {
+        if self.default_options.is_empty() {
+            return Ok(db_url.to_string());
+        }
+        let mut opts = Options::parse_uri(db_url)?;
+        for (name, value) in self.default_options.iter() {
+            if !opts.query.contains_key(name) {
+                opts.query.insert(name.clone(), value.clone());
+            }
+        }
+        Ok(opts.into_uri())
+    }
+
+    async fn track(&self, uri: Option<String>, mut store: Store) -> Result<StoreId, Error> {
+        store.set_audit_actor(self.audit_actor.clone());
+        for hook in self.hooks.iter() {
+            store.add_hook(hook.clone());
+        }
+        let mut stores = self.stores.write().await;
+        if self.at_capacity(stores.len()) {
+            drop(stores);
+            store.close().await?;
+            return Err(err_msg!(Busy, "The maximum number of stores are open"));
+        }
+        let id = StoreId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        stores.insert(id, ManagedStore { uri, store });
+        Ok(id)
+    }
+}
+
+impl Debug for StoreManager {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreManager")
+            .field("max_stores", &self.max_stores)
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
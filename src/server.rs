@@ -0,0 +1,41 @@
+//! Configuration support shared by the administrative and gRPC servers
+
+use sha2::{Digest, Sha256};
+
+use crate::{crypto::buffer::HexRepr, error::Error, store::StoreKeyMethod};
+
+/// Normalize a configured hex-encoded SHA-256 hash of an access token
+///
+/// Returns `None` if the value is not a valid hash.
+pub(crate) fn parse_token_hash(hash: &str) -> Option<String> {
+    let hash = hash.to_ascii_lowercase();
+    if hash.len() == 64 && hash.bytes().all(|c| c.is_ascii_hexdigit()) {
+        Some(hash)
+    } else {
+        None
+    }
+}
+
+/// Compute the hash of a presented access token, for comparison with the
+/// configured hashes
+pub(crate) fn token_hash(token: &str) -> String {
+    HexRepr(Sha256::digest(token.as_bytes())).to_string()
+}
+
+/// Parse the optional store key method of a server configuration
+pub(crate) fn parse_key_method(method: Option<&str>) -> Result<Option<StoreKeyMethod>, Error> {
+    Ok(method.map(StoreKeyMethod::parse_uri).transpose()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_hash_parse() {
+        let hash = token_hash("secret");
+        assert_eq!(parse_token_hash(&hash.to_ascii_uppercase()), Some(hash));
+        assert!(parse_token_hash("1234").is_none());
+        assert!(parse_token_hash(&"g".repeat(64)).is_none());
+    }
+}
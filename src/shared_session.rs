@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use async_lock::Mutex;
+
+use crate::{
+    error::Error,
+    future::{spawn, spawn_ok, BoxFuture},
+    store::Session,
+};
+
+/// A session handle which may be shared with a foreign runtime
+///
+/// Operations are executed on the library runtime. The session is released
+/// once it is committed or rolled back, after which operations return an
+/// `Input` error. A session which is dropped without being released is rolled
+/// back on the library runtime, as foreign objects may be finalized on any thread.
+#[derive(Debug)]
+pub struct SharedSession(Arc<Mutex<Option<Session>>>);
+
+impl SharedSession {
+    /// Wrap an active session or transaction
+    pub fn new(session: Session) -> Self {
+        Self(Arc::new(Mutex::new(Some(session))))
+    }
+
+    /// Run an operation on the active session in the library runtime
+    pub async fn run<F, T>(&self, f: F) -> Result<T, Error>
+    where
+        F: for<'s> FnOnce(&'s mut Session) -> BoxFuture<'s, Result<T, Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let session = self.0.clone();
+        spawn(async move {
+            let mut guard = session.lock().await;
+            let session = guard
+                .as_mut()
+                .ok_or_else(|| err_msg!(Input, "Session has been closed"))?;
+            f(session).await
+        })
+        .await
+    }
+
+    /// Release the session, committing any pending transaction if requested
+    pub async fn release(&self, commit: bool) -> Result<(), Error> {
+        let session = self.0.clone();
+        spawn(async move {
+            match session.lock().await.take() {
+                Some(session) if commit => session.commit().await,
+                Some(session) => session.rollback().await,
+                None => Err(err_msg!(Input, "Session has been closed")),
+            }
+        })
+        .await
+    }
+}
+
+impl Drop for SharedSession {
+    fn drop(&mut self) {
+        // an operation in progress holds its own reference to the session
+        if let Some(session) = self.0.try_lock().and_then(|mut guard| guard.take()) {
+            spawn_ok(async move { drop(session) });
+        }
+    }
+}
@@ -1,25 +1,68 @@
-use askar_storage::backend::{copy_profile, OrderBy};
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use askar_storage::backend::{
+    backup_changes, clone_profile, copy_profile, export_records, import_profile, replicate,
+    restore_changes, OrderBy,
+};
 
 use crate::{
-    error::Error,
-    kms::{KeyEntry, KeyParams, KeyReference, KmsCategory, LocalKey},
+    acapy::AcaPySubWallet,
+    audit::{audit, audit_path, AuditOperation},
+    builder::StoreBuilder,
+    error::{Error, ErrorKind},
+    future::system_time,
+    hooks::{HookEvent, HookList, StoreHook},
+    kms::{
+        wrap_key_jwe, KeyEntry, KeyParams, KeyReference, KmsCategory, LocalKey, RemoteKeyHandle,
+        SecretBytes, UnwrappingKey, WrappedKey,
+    },
     storage::{
         any::{AnyBackend, AnyBackendSession},
-        backend::{Backend, BackendSession, ManageBackend},
+        backend::{Backend, BackendSession, EntrySession, ManageBackend},
         entry::{Entry, EntryKind, EntryOperation, EntryTag, Scan, TagFilter},
         generate_raw_store_key,
     },
 };
 
-pub use crate::storage::{entry, PassKey, StoreKeyMethod};
+pub use crate::storage::{
+    backend::{
+        BackupSegmentInfo, ConflictResolution, ExpiryRule, ImportConflict, ImportReport,
+        ImportStrategy, PoolStatus, ProfileExpiryPolicy, ProfileFilter, ProfileQuota, ProfileStats,
+        Progress, ProgressFn, ProgressPhase, RekeyProgress, ReplicationCheckpoint, TraceContext,
+        VersionInfo,
+    },
+    entry, redact, register_store_key_method, set_pass_key_validator, Argon2Level, KdfMethod,
+    KeyCacheStats, PassKey, PassKeyPolicy, PassKeyValidator, StoreKeyMethod, StoreKeyProvider,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 /// An instance of an opened store
-pub struct Store(AnyBackend);
+pub struct Store {
+    inner: AnyBackend,
+    profile: Option<Arc<str>>,
+    audit_actor: Option<Arc<str>>,
+    progress: Option<Arc<ProgressFn>>,
+    hooks: HookList,
+    exclude_archived: bool,
+}
 
 impl Store {
     pub(crate) fn new(inner: AnyBackend) -> Self {
-        Self(inner)
+        Self {
+            inner,
+            profile: None,
+            audit_actor: None,
+            progress: None,
+            hooks: HookList::default(),
+            exclude_archived: false,
+        }
     }
 
     /// Provision a new store instance using a database URL
@@ -30,10 +73,22 @@ impl Store {
         profile: Option<String>,
         recreate: bool,
     ) -> Result<Self, Error> {
-        let backend = db_url
+        let result = db_url
             .provision_backend(key_method, pass_key, profile, recreate)
-            .await?;
-        Ok(Self::new(backend))
+            .await
+            .map_err(Error::from);
+        let profile = result
+            .as_ref()
+            .ok()
+            .map(|backend| backend.get_active_profile());
+        audit(
+            AuditOperation::Provision,
+            None,
+            profile.as_deref(),
+            None,
+            &result,
+        );
+        Ok(Self::new(result?))
     }
 
     /// Open a store instance from a database URL
@@ -43,8 +98,28 @@ impl Store {
         pass_key: PassKey<'_>,
         profile: Option<String>,
     ) -> Result<Self, Error> {
-        let backend = db_url.open_backend(key_method, pass_key, profile).await?;
-        Ok(Self::new(backend))
+        let result = db_url
+            .open_backend(key_method, pass_key, profile)
+            .await
+            .map_err(Error::from);
+        let profile = result
+            .as_ref()
+            .ok()
+            .map(|backend| backend.get_active_profile());
+        audit(
+            AuditOperation::Open,
+            None,
+            profile.as_deref(),
+            None,
+            &result,
+        );
+        Ok(Self::new(result?))
+    }
+
+    /// Start building a store instance for a database URL, as an alternative
+    /// to `provision` and `open`
+    pub fn builder<'a>(db_url: impl Into<String>) -> StoreBuilder<'a> {
+        StoreBuilder::new(db_url)
     }
 
     /// Remove a store instance using a database URL
@@ -57,19 +132,89 @@ impl Store {
         Ok(generate_raw_store_key(seed)?)
     }
 
+    /// Assign the actor reported in audit records for operations on this instance
+    pub fn set_audit_actor(&mut self, actor: Option<String>) {
+        self.audit_actor = actor.map(Into::into);
+    }
+
+    /// Assign a callback receiving progress reports from long-running operations
+    /// on this instance
+    ///
+    /// Progress is reported by exports, imports, backups, migrations, re-keying,
+    /// copies to other stores and bulk profile removal.
+    pub fn set_progress_handler(&mut self, handler: Option<Arc<ProgressFn>>) {
+        self.progress = handler;
+    }
+
+    /// Register a hook receiving notifications of operations on this instance
+    ///
+    /// The hook applies to profile management and to sessions subsequently created
+    /// from this instance. Copies of the store made before the hook is registered
+    /// are not affected.
+    pub fn add_hook(&mut self, hook: Arc<dyn StoreHook>) {
+        self.hooks.push(hook);
+    }
+
     /// Get the default profile name used when starting a scan or a session
     pub fn get_active_profile(&self) -> String {
-        self.0.get_active_profile()
+        match self.profile.as_deref() {
+            Some(profile) => profile.to_string(),
+            None => self.inner.get_active_profile(),
+        }
+    }
+
+    /// Assign the profile used by this instance when starting a scan or a session
+    /// without naming a profile
+    ///
+    /// Copies of the store share the backend and its connection pool, but each
+    /// has its own active profile. When `None` is given, the profile selected when
+    /// opening the store is used.
+    pub fn set_active_profile(&mut self, profile: Option<String>) {
+        self.profile = profile.map(Into::into);
+    }
+
+    /// Create a copy of the store using a different active profile
+    ///
+    /// The profile is not checked for existence until it is used.
+    pub fn with_active_profile(&self, profile: impl Into<String>) -> Self {
+        let mut store = self.clone();
+        store.set_active_profile(Some(profile.into()));
+        store
+    }
+
+    /// Exclude archived profiles from the scans started by this instance
+    ///
+    /// When enabled, scans against an archived profile produce no records.
+    pub fn set_exclude_archived(&mut self, exclude: bool) {
+        self.exclude_archived = exclude;
+    }
+
+    /// Check whether a scan against a profile is excluded by this instance
+    async fn is_scan_excluded(&self, profile: Option<&str>) -> Result<bool, Error> {
+        if !self.exclude_archived {
+            return Ok(false);
+        }
+        let profile = match profile {
+            Some(profile) => profile.to_string(),
+            None => self.get_active_profile(),
+        };
+        Ok(self.list_archived_profiles().await?.contains(&profile))
+    }
+
+    /// Resolve an optional profile name, defaulting to the profile assigned to
+    /// this instance
+    fn resolve_profile(&self, profile: Option<String>) -> Option<String> {
+        profile.or_else(|| self.profile.as_deref().map(str::to_string))
     }
 
     /// Get the default profile name used when opening the Store
     pub async fn get_default_profile(&self) -> Result<String, Error> {
-        Ok(self.0.get_default_profile().await?)
+        Ok(self.inner.get_default_profile().await?)
     }
 
     /// Set the default profile name used when opening the Store
     pub async fn set_default_profile(&self, profile: String) -> Result<(), Error> {
-        Ok(self.0.set_default_profile(profile).await?)
+        Ok(self.inner.set_default_profile(profile).await?)
     }
 
     /// Replace the wrapping key on a store
@@ -78,7 +223,112 @@ impl Store {
         method: StoreKeyMethod,
         pass_key: PassKey<'_>,
     ) -> Result<(), Error> {
-        Ok(self.0.rekey(method, pass_key).await?)
+        let total = match self.progress {
+            Some(_) => Some(self.inner.list_profiles().await?.len() as i64),
+            None => None,
+        };
+        Progress {
+            phase: ProgressPhase::Rekey,
+            profile: None,
+            processed: 0,
+            total,
+        }
+        .report(self.progress.as_deref());
+        let result = self
+            .inner
+            .rekey(method, pass_key)
+            .await
+            .map_err(Error::from);
+        if result.is_ok() {
+            Progress {
+                phase: ProgressPhase::Rekey,
+                profile: None,
+                processed: total.unwrap_or_default(),
+                total,
+            }
+            .report(self.progress.as_deref());
+        }
+        audit(
+            AuditOperation::Rekey,
+            self.audit_actor.as_deref(),
+            None,
+            None,
+            &result,
+        );
+        result
+    }
+
+    /// Derive the wrapping key of a store again from the same pass key when its
+    /// key derivation is weaker than `method`
+    ///
+    /// Returns `false` when the wrapping key is unchanged. Other instances of the
+    /// store must be reopened once the key is upgraded, and the upgrade is refused
+    /// while a chunked re-key is pending.
+    pub async fn upgrade_kdf(
+        &mut self,
+        method: KdfMethod,
+        pass_key: PassKey<'_>,
+    ) -> Result<bool, Error> {
+        let result = self
+            .inner
+            .upgrade_kdf(method, pass_key)
+            .await
+            .map_err(Error::from);
+        audit(
+            AuditOperation::Rekey,
+            self.audit_actor.as_deref(),
+            None,
+            None,
+            &result,
+        );
+        result
+    }
+
+    /// Replace the wrapping key on a store, re-encrypting profile keys in batches
+    ///
+    /// Each batch of up to `chunk_size` profile keys is committed separately while
+    /// the store remains usable with the current key, and the new key takes effect
+    /// once all profiles are processed. An interrupted re-key is resumed by calling
+    /// this method again with the same key method and pass key. `progress` is
+    /// invoked after each batch, and returning an error stops the operation.
+    pub async fn rekey_chunked(
+        &mut self,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+        chunk_size: usize,
+        mut progress: impl FnMut(RekeyProgress) -> Result<(), Error> + Send,
+    ) -> Result<(), Error> {
+        let mut stopped = None;
+        let handler = self.progress.clone();
+        let result = self
+            .inner
+            .rekey_chunked(method, pass_key, chunk_size, &mut |report| {
+                Progress {
+                    phase: ProgressPhase::Rekey,
+                    profile: None,
+                    processed: report.completed,
+                    total: Some(report.total),
+                }
+                .report(handler.as_deref());
+                progress(report).map_err(|err| {
+                    stopped.replace(err);
+                    askar_storage::ErrorKind::Custom.into()
+                })
+            })
+            .await
+            .map_err(Error::from);
+        let result = match stopped {
+            Some(err) => Err(err),
+            None => result,
+        };
+        audit(
+            AuditOperation::Rekey,
+            self.audit_actor.as_deref(),
+            None,
+            None,
+            &result,
+        );
+        result
     }
 
     /// Copy to a new store instance using a database URL
@@ -91,28 +341,824 @@ impl Store {
     ) -> Result<Self, Error> {
         let default_profile = self.get_default_profile().await?;
         let profile_ids = self.list_profiles().await?;
+        let mut counts = Vec::with_capacity(profile_ids.len());
+        if self.progress.is_some() {
+            for profile in &profile_ids {
+                let mut session = self.inner.session(Some(profile.clone()), false)?;
+                counts.push(session.count(None, None, None).await?);
+                session.close(false).await?;
+            }
+        }
+        let total = counts.iter().sum::<i64>();
         let target = target_url
             .provision_backend(key_method, pass_key, Some(default_profile), recreate)
             .await?;
-        for profile in profile_ids {
-            copy_profile(&self.0, &target, &profile, &profile).await?;
+        let mut processed = 0;
+        for (idx, profile) in profile_ids.into_iter().enumerate() {
+            copy_profile(&self.inner, &target, &profile, &profile).await?;
+            processed += counts.get(idx).copied().unwrap_or_default();
+            Progress {
+                phase: ProgressPhase::Copy,
+                profile: Some(profile),
+                processed,
+                total: Some(total),
+            }
+            .report(self.progress.as_deref());
         }
         Ok(Self::new(target))
     }
 
+    /// Replicate all profiles and records to another store, resumably
+    ///
+    /// The target store may use a different backend. `progress` is invoked with a
+    /// checkpoint after each batch of records is committed, and returning an error
+    /// stops the replication. The last reported checkpoint may be passed in order
+    /// to resume an interrupted replication.
+    pub async fn replicate_to(
+        &self,
+        target: &Store,
+        checkpoint: Option<ReplicationCheckpoint>,
+        mut progress: impl FnMut(&ReplicationCheckpoint) -> Result<(), Error> + Send,
+    ) -> Result<ReplicationCheckpoint, Error> {
+        let mut stopped = None;
+        let result = replicate(&self.inner, &target.inner, checkpoint, |checkpoint| {
+            Progress {
+                phase: ProgressPhase::Copy,
+                profile: checkpoint.profile.clone(),
+                processed: checkpoint.total,
+                total: None,
+            }
+            .report(self.progress.as_deref());
+            progress(checkpoint).map_err(|err| {
+                stopped.replace(err);
+                askar_storage::ErrorKind::Custom.into()
+            })
+        })
+        .await;
+        match stopped {
+            Some(err) => Err(err),
+            None => Ok(result?),
+        }
+    }
+
+    /// Write an encrypted backup segment of the records changed since a checkpoint
+    ///
+    /// Pass a checkpoint of zero for a full backup, and the `until` value of the
+    /// returned segment info as the checkpoint for the following segment. The
+    /// segment is encrypted with a key resolved from `key_method` and `pass_key`.
+    /// Record expiry times and removed profiles are not included.
+    pub async fn backup_changes(
+        &self,
+        since: i64,
+        key_method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+        writer: impl std::io::Write,
+    ) -> Result<BackupSegmentInfo, Error> {
+        let result = backup_changes(
+            &self.inner,
+            since,
+            key_method,
+            pass_key,
+            writer,
+            self.progress.as_deref(),
+        )
+        .await
+        .map_err(Error::from);
+        audit(
+            AuditOperation::Backup,
+            self.audit_actor.as_deref(),
+            None,
+            None,
+            &result,
+        );
+        result
+    }
+
+    /// Write an encrypted export of the records matching a category and tag filter
+    ///
+    /// The records of each of `profiles` are included, or those of every profile
+    /// when no profiles are given. The export is encrypted with a key resolved
+    /// from `key_method` and `pass_key`, and may be imported into another store
+    /// using `restore_changes` with a checkpoint of zero. Key entries and record
+    /// expiry times are not included.
+    pub async fn export_encrypted(
+        &self,
+        profiles: Option<Vec<String>>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        key_method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+        writer: impl std::io::Write,
+    ) -> Result<BackupSegmentInfo, Error> {
+        let profile = match profiles.as_deref() {
+            Some([profile]) => Some(profile.clone()),
+            _ => None,
+        };
+        let result = export_records(
+            &self.inner,
+            profiles,
+            Some(EntryKind::Item),
+            category.clone(),
+            tag_filter,
+            key_method,
+            pass_key,
+            writer,
+            self.progress.as_deref(),
+        )
+        .await
+        .map_err(Error::from);
+        audit(
+            AuditOperation::Backup,
+            self.audit_actor.as_deref(),
+            profile.as_deref(),
+            category.as_deref(),
+            &result,
+        );
+        result
+    }
+
+    /// Discard the record removals included in backup segments up to the
+    /// checkpoint `until`
+    ///
+    /// The change log retains an entry for each removed record, so that the
+    /// removal is included in later segments. Once the segment ending at `until`
+    /// has been stored, these entries may be discarded: segments starting from
+    /// an earlier checkpoint then no longer report the removals. Returns the
+    /// number of discarded entries.
+    pub async fn prune_backup_changes(&self, until: i64) -> Result<i64, Error> {
+        Ok(self.inner.prune_changes(until).await?)
+    }
+
+    /// Apply an encrypted backup segment written by `backup_changes`
+    ///
+    /// Segments must be applied in order: `checkpoint` is the `until` value of
+    /// the last segment applied to this store, or zero when restoring a full
+    /// backup. A segment which fails to apply may safely be applied again.
+    pub async fn restore_changes(
+        &self,
+        reader: impl std::io::Read,
+        pass_key: PassKey<'_>,
+        checkpoint: i64,
+    ) -> Result<BackupSegmentInfo, Error> {
+        let result = restore_changes(
+            &self.inner,
+            reader,
+            pass_key,
+            checkpoint,
+            self.progress.as_deref(),
+        )
+        .await
+        .map_err(Error::from);
+        audit(
+            AuditOperation::Restore,
+            self.audit_actor.as_deref(),
+            None,
+            None,
+            &result,
+        );
+        result
+    }
+
+    /// Write a consistent copy of the store to a new file while it remains in use
+    ///
+    /// This is currently supported by the SQLite backend, using the SQLite online
+    /// backup API. The snapshot may be restored using `Store::restore_snapshot`.
+    pub async fn snapshot_to(&self, path: &str) -> Result<(), Error> {
+        let result = self
+            .inner
+            .snapshot_to(path.to_string())
+            .await
+            .map_err(Error::from);
+        audit_path(
+            AuditOperation::Backup,
+            self.audit_actor.as_deref(),
+            None,
+            path,
+            &result,
+        );
+        result
+    }
+
+    /// Restore a SQLite store from a snapshot written by `snapshot_to`
+    ///
+    /// The snapshot is verified before the store at `db_url` is replaced: its
+    /// integrity is checked, and it must open using the given key method, pass key
+    /// and profile. The store must not be in use. The restored store is returned.
+    #[cfg(feature = "sqlite")]
+    pub async fn restore_snapshot(
+        db_url: &str,
+        snapshot_path: &str,
+        key_method: Option<StoreKeyMethod>,
+        pass_key: PassKey<'_>,
+        profile: Option<String>,
+    ) -> Result<Self, Error> {
+        use crate::storage::{any::into_any_backend, sqlite::SqliteStoreOptions, IntoOptions};
+
+        let result = async {
+            let opts = db_url.into_options()?;
+            if opts.scheme != "sqlite" {
+                return Err(err_msg!(
+                    Unsupported,
+                    "Snapshots are only supported for SQLite stores"
+                ));
+            }
+            let backend = SqliteStoreOptions::new(opts)?
+                .restore_snapshot(snapshot_path, key_method, pass_key, profile)
+                .await?;
+            Ok(into_any_backend(backend))
+        }
+        .await;
+        let profile = result
+            .as_ref()
+            .ok()
+            .map(|backend| backend.get_active_profile());
+        audit_path(
+            AuditOperation::Restore,
+            None,
+            profile.as_deref(),
+            snapshot_path,
+            &result,
+        );
+        Ok(Self::new(result?))
+    }
+
+    /// Import the records of an Indy-SDK SQLite wallet into a profile of this store
+    ///
+    /// The wallet is left unchanged, and the store may use any backend. The records
+    /// are written in a single transaction, so that no records are imported when
+    /// the operation fails. Returns the number of imported records. When `dry_run`
+    /// is set, the records are validated against the target profile without being
+    /// written
+    #[cfg(all(feature = "migration", feature = "sqlite"))]
+    pub async fn migrate_indy_sdk(
+        &self,
+        spec_uri: &str,
+        wallet_name: &str,
+        wallet_key: &str,
+        kdf_level: &str,
+        profile: Option<String>,
+        dry_run: bool,
+    ) -> Result<usize, Error> {
+        let migrator = crate::storage::migration::IndySdkToAriesAskarMigration::connect(
+            spec_uri,
+            wallet_name,
+            wallet_key,
+            kdf_level,
+        )
+        .await?;
+        Ok(migrator
+            .migrate_into(
+                &self.inner,
+                self.resolve_profile(profile),
+                dry_run,
+                self.progress.as_deref(),
+            )
+            .await?)
+    }
+
+    /// Import the records of a profile of another store into a profile of this store
+    ///
+    /// The target profile is created if necessary. Records which already exist in
+    /// the target profile are handled according to `strategy`, and are listed in
+    /// the returned report along with any keys using algorithms not supported by
+    /// this build. The records are written in a single transaction. When `dry_run`
+    /// is set, the report describes the changes which would be made, and the
+    /// store is left unchanged.
+    pub async fn import_profile(
+        &self,
+        source: &Store,
+        from_profile: &str,
+        to_profile: &str,
+        strategy: ImportStrategy,
+        dry_run: bool,
+    ) -> Result<ImportReport, Error> {
+        let unsupported_keys = source.unsupported_keys(from_profile).await?;
+        let mut report = import_profile(
+            &source.inner,
+            &self.inner,
+            from_profile,
+            to_profile,
+            strategy,
+            dry_run,
+            self.progress.as_deref(),
+        )
+        .await?;
+        report.unsupported_keys = unsupported_keys;
+        Ok(report)
+    }
+
+    /// List the keys of a profile which cannot be loaded by this build
+    async fn unsupported_keys(&self, profile: &str) -> Result<Vec<String>, Error> {
+        let mut scan = self
+            .inner
+            .scan(
+                Some(profile.to_string()),
+                Some(EntryKind::Kms),
+                Some(KmsCategory::CryptoKey.as_str().to_string()),
+                None,
+                None,
+                None,
+                None,
+                false,
+            )
+            .await?;
+        let mut names = Vec::new();
+        while let Some(rows) = scan.fetch_next().await? {
+            for entry in rows {
+                let name = entry.name.clone();
+                match KeyEntry::from_entry(entry).and_then(|key| key.load_local_key()) {
+                    Err(err) if err.kind() == ErrorKind::Unsupported => names.push(name),
+                    _ => (),
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Import ACA-Py sub-wallets held in separate stores into profiles of this store
+    ///
+    /// The records of the default profile of each sub-wallet are copied into the
+    /// profile named by the sub-wallet description, which must be empty or absent.
+    /// The sub-wallet stores are left unchanged. Returns the names of the imported
+    /// profiles
+    pub async fn import_acapy_sub_wallets(
+        &self,
+        wallets: Vec<AcaPySubWallet>,
+    ) -> Result<Vec<String>, Error> {
+        let mut profiles = Vec::with_capacity(wallets.len());
+        for wallet in wallets {
+            let source =
+                Store::open(&wallet.uri, Some(wallet.key_method), wallet.pass_key, None).await?;
+            let result = match source.get_default_profile().await {
+                Ok(profile) => copy_profile(&source.inner, &self.inner, &profile, &wallet.profile)
+                    .await
+                    .map_err(Error::from),
+                Err(err) => Err(err),
+            };
+            source.close().await?;
+            result?;
+            profiles.push(wallet.profile);
+        }
+        Ok(profiles)
+    }
+
+    /// Import the sub-wallets of an ACA-Py `askar-profile` multitenant store
+    ///
+    /// Each profile of the source store is copied into a profile of the same name
+    /// (the ACA-Py wallet ID), which must be empty or absent. The base wallet held
+    /// in the default profile of the source store is only copied when `include_base`
+    /// is set. Returns the names of the imported profiles
+    pub async fn import_acapy_profiles(
+        &self,
+        source: &Store,
+        include_base: bool,
+    ) -> Result<Vec<String>, Error> {
+        let base_profile = source.get_default_profile().await?;
+        let mut profiles = Vec::new();
+        for profile in source.list_profiles().await? {
+            if profile == base_profile && !include_base {
+                continue;
+            }
+            copy_profile(&source.inner, &self.inner, &profile, &profile).await?;
+            profiles.push(profile);
+        }
+        Ok(profiles)
+    }
+
     /// Create a new profile with the given profile name
+    ///
+    /// When a profile with the given name already exists, including one created by
+    /// a concurrent caller, the existing profile is returned.
     pub async fn create_profile(&self, name: Option<String>) -> Result<String, Error> {
-        Ok(self.0.create_profile(name).await?)
+        if let Some(name) = name {
+            self.ensure_profile(name.clone()).await?;
+            return Ok(name);
+        }
+        let result = self.inner.create_profile(None).await.map_err(Error::from);
+        audit(
+            AuditOperation::CreateProfile,
+            self.audit_actor.as_deref(),
+            result.as_deref().ok(),
+            None,
+            &result,
+        );
+        if let Ok(name) = result.as_deref() {
+            self.hooks.dispatch(name, &HookEvent::CreateProfile);
+        }
+        result
+    }
+
+    /// Create a profile with the given name unless it already exists
+    ///
+    /// Returns `true` if the profile was created. This is safe to call concurrently
+    /// for the same name from multiple processes sharing the store, for example when
+    /// provisioning tenant profiles at startup.
+    pub async fn ensure_profile(&self, name: String) -> Result<bool, Error> {
+        let result = self
+            .inner
+            .ensure_profile(name.clone())
+            .await
+            .map_err(Error::from);
+        if !matches!(result, Ok(false)) {
+            audit(
+                AuditOperation::CreateProfile,
+                self.audit_actor.as_deref(),
+                Some(&name),
+                None,
+                &result,
+            );
+        }
+        if let Ok(true) = result {
+            self.hooks.dispatch(&name, &HookEvent::CreateProfile);
+        }
+        result
     }
 
     /// Get the details of all store profiles
     pub async fn list_profiles(&self) -> Result<Vec<String>, Error> {
-        Ok(self.0.list_profiles().await?)
+        Ok(self.inner.list_profiles().await?)
+    }
+
+    /// Get a page of the store profile names, in ascending order of name
+    pub async fn list_profiles_page(&self, offset: i64, limit: i64) -> Result<Vec<String>, Error> {
+        Ok(self.inner.list_profiles_page(offset, limit).await?)
     }
 
-    /// Remove an existing profile with the given profile namestore.r
+    /// Remove an existing profile with the given profile name
     pub async fn remove_profile(&self, name: String) -> Result<bool, Error> {
-        Ok(self.0.remove_profile(name).await?)
+        let result = self
+            .inner
+            .remove_profile(name.clone())
+            .await
+            .map_err(Error::from);
+        audit(
+            AuditOperation::RemoveProfile,
+            self.audit_actor.as_deref(),
+            Some(&name),
+            None,
+            &result,
+        );
+        if let Ok(true) = result {
+            self.hooks.dispatch(&name, &HookEvent::RemoveProfile);
+        }
+        result
+    }
+
+    /// Remove the profiles selected by a filter along with their records,
+    /// returning the names of the removed profiles
+    ///
+    /// Profiles are selected by a name prefix and/or metadata properties, and are
+    /// removed in batches of up to `batch_size`, each batch in its own transaction.
+    /// Progress is reported to the assigned handler after each batch. When an
+    /// error occurs, the batches already processed remain removed. The active
+    /// profile of the store is never removed.
+    pub async fn remove_profiles(
+        &self,
+        filter: ProfileFilter,
+        batch_size: usize,
+    ) -> Result<Vec<String>, Error> {
+        let result = self
+            .inner
+            .remove_profiles(filter, batch_size, self.progress.as_deref())
+            .await
+            .map_err(Error::from);
+        match result.as_ref() {
+            Ok(removed) => {
+                for name in removed {
+                    audit(
+                        AuditOperation::RemoveProfile,
+                        self.audit_actor.as_deref(),
+                        Some(name),
+                        None,
+                        &result,
+                    );
+                    self.hooks.dispatch(name, &HookEvent::RemoveProfile);
+                }
+            }
+            Err(_) => audit(
+                AuditOperation::RemoveProfile,
+                self.audit_actor.as_deref(),
+                None,
+                None,
+                &result,
+            ),
+        }
+        result
+    }
+
+    /// Rename an existing profile, returning `false` if it was not found
+    ///
+    /// The default profile setting is updated when it refers to the renamed
+    /// profile. The active profile of the store may not be renamed.
+    pub async fn rename_profile(&self, from: String, to: String) -> Result<bool, Error> {
+        let result = self
+            .inner
+            .rename_profile(from.clone(), to.clone())
+            .await
+            .map_err(Error::from);
+        audit(
+            AuditOperation::RenameProfile,
+            self.audit_actor.as_deref(),
+            Some(&from),
+            Some(&to),
+            &result,
+        );
+        result
+    }
+
+    /// Create a new profile containing a copy of the entries and keys of an existing profile
+    ///
+    /// The copy is encrypted under a newly generated profile key, and may be used
+    /// for staging copies of a tenant wallet or to provision tenants from a template
+    /// profile. The target profile must not already exist.
+    pub async fn clone_profile(&self, from: String, to: String) -> Result<(), Error> {
+        let result = clone_profile(&self.inner, &from, &to)
+            .await
+            .map_err(Error::from);
+        audit(
+            AuditOperation::CloneProfile,
+            self.audit_actor.as_deref(),
+            Some(&from),
+            Some(&to),
+            &result,
+        );
+        result
+    }
+
+    /// Get the metadata associated with a profile
+    pub async fn get_profile_metadata(&self, name: String) -> Result<Option<String>, Error> {
+        Ok(self.inner.get_profile_metadata(name).await?)
+    }
+
+    /// Replace or clear the metadata associated with a profile
+    ///
+    /// Profile metadata is stored unencrypted, and should not contain sensitive values.
+    pub async fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<String>,
+    ) -> Result<(), Error> {
+        Ok(self.inner.set_profile_metadata(name, metadata).await?)
+    }
+
+    /// Get the limits on the records stored within a profile
+    pub async fn get_profile_quota(&self, name: String) -> Result<ProfileQuota, Error> {
+        Ok(self.inner.get_profile_quota(name).await?)
+    }
+
+    /// Replace the limits on the records stored within a profile
+    ///
+    /// Subsequent inserts and replacements which would exceed a limit fail
+    /// with a [`QuotaExceeded`](crate::ErrorKind::QuotaExceeded) error.
+    pub async fn set_profile_quota(&self, name: String, quota: ProfileQuota) -> Result<(), Error> {
+        Ok(self.inner.set_profile_quota(name, quota).await?)
+    }
+
+    /// Get the default expiry policy of a profile
+    pub async fn get_profile_expiry_policy(
+        &self,
+        name: String,
+    ) -> Result<ProfileExpiryPolicy, Error> {
+        Ok(self.inner.get_profile_expiry_policy(name).await?)
+    }
+
+    /// Replace the default expiry policy of a profile
+    ///
+    /// Entries subsequently inserted or replaced without an expiry are given the
+    /// lifetime of the first matching rule, allowing retention limits to be
+    /// enforced for categories such as messages and session state.
+    pub async fn set_profile_expiry_policy(
+        &self,
+        name: String,
+        policy: ProfileExpiryPolicy,
+    ) -> Result<(), Error> {
+        Ok(self.inner.set_profile_expiry_policy(name, policy).await?)
+    }
+
+    /// Mark a profile as archived, or restore an archived profile
+    ///
+    /// The entries of an archived profile may still be read, but inserting,
+    /// replacing or removing its entries fails with an
+    /// [`Archived`](crate::ErrorKind::Archived) error. Archived profiles are
+    /// retained in backups and exports, and may be excluded from scans using
+    /// `set_exclude_archived`.
+    pub async fn set_profile_archived(&self, name: String, archived: bool) -> Result<(), Error> {
+        let result = self
+            .inner
+            .set_profile_archived(name.clone(), archived)
+            .await
+            .map_err(Error::from);
+        audit(
+            if archived {
+                AuditOperation::ArchiveProfile
+            } else {
+                AuditOperation::UnarchiveProfile
+            },
+            self.audit_actor.as_deref(),
+            Some(&name),
+            None,
+            &result,
+        );
+        result
+    }
+
+    /// List the names of the archived profiles, in ascending order of name
+    ///
+    /// This may be used to exclude archived profiles when scanning the records
+    /// of each profile in the store.
+    pub async fn list_archived_profiles(&self) -> Result<Vec<String>, Error> {
+        Ok(self.inner.list_archived_profiles().await?)
+    }
+
+    /// Register an alternate name for a profile
+    ///
+    /// Sessions may be opened using the alias in place of the profile name, for
+    /// example to address a tenant by both an internal identifier and a label.
+    /// The alias must not match the name of an existing profile or another
+    /// alias, and is removed along with the profile.
+    pub async fn add_profile_alias(&self, name: String, alias: String) -> Result<(), Error> {
+        let result = self
+            .inner
+            .add_profile_alias(name.clone(), alias.clone())
+            .await
+            .map_err(Error::from);
+        audit(
+            AuditOperation::AddProfileAlias,
+            self.audit_actor.as_deref(),
+            Some(&name),
+            Some(&alias),
+            &result,
+        );
+        result
+    }
+
+    /// Remove a profile alias, returning `false` if it was not found
+    pub async fn remove_profile_alias(&self, alias: String) -> Result<bool, Error> {
+        let result = self
+            .inner
+            .remove_profile_alias(alias.clone())
+            .await
+            .map_err(Error::from);
+        audit(
+            AuditOperation::RemoveProfileAlias,
+            self.audit_actor.as_deref(),
+            None,
+            Some(&alias),
+            &result,
+        );
+        result
+    }
+
+    /// List the aliases of a profile, in ascending order
+    pub async fn list_profile_aliases(&self, name: String) -> Result<Vec<String>, Error> {
+        Ok(self.inner.list_profile_aliases(name).await?)
+    }
+
+    /// Get the usage statistics for the records stored within a profile
+    ///
+    /// The entry counts and sizes are aggregated by the database, without
+    /// decrypting the profile records.
+    pub async fn profile_stats(&self, name: String) -> Result<ProfileStats, Error> {
+        Ok(self.inner.profile_stats(name).await?)
+    }
+
+    /// Replace the encryption key for a single profile
+    ///
+    /// All records within the profile are re-encrypted under a newly generated
+    /// profile key in a single transaction.
+    pub async fn rekey_profile(&self, name: String) -> Result<(), Error> {
+        let result = self
+            .inner
+            .rekey_profile(name.clone())
+            .await
+            .map_err(Error::from);
+        audit(
+            AuditOperation::Rekey,
+            self.audit_actor.as_deref(),
+            Some(&name),
+            None,
+            &result,
+        );
+        result
+    }
+
+    /// Replace the tag search key for a single profile
+    ///
+    /// Every tag name and value stored for the profile is re-encrypted under the
+    /// new key within a single transaction, while record values are left unchanged.
+    /// Existing tags cannot be queried using the previous key once the operation
+    /// completes, so sessions opened against the profile beforehand should be closed.
+    pub async fn rotate_tag_key(&self, name: String) -> Result<(), Error> {
+        let result = self
+            .inner
+            .rotate_tag_key(name.clone())
+            .await
+            .map_err(Error::from);
+        audit(
+            AuditOperation::RotateTagKey,
+            self.audit_actor.as_deref(),
+            Some(&name),
+            None,
+            &result,
+        );
+        result
+    }
+
+    /// Get the schema version, provisioning library version, store key method and
+    /// ciphers of the store
+    ///
+    /// No secret material is included, so the result may be collected for an
+    /// inventory of stores ahead of library upgrades.
+    pub async fn version_info(&self) -> Result<VersionInfo, Error> {
+        Ok(self.inner.version_info().await?)
+    }
+
+    /// Get usage statistics for the profile key cache
+    ///
+    /// Returns `None` if the backend does not maintain a key cache. The maximum
+    /// number of cached profile keys may be set using the `max_cached_profiles`
+    /// query parameter of the store URI, and the number of seconds for which a
+    /// key is cached using the `key_cache_ttl` parameter.
+    pub async fn key_cache_stats(&self) -> Option<KeyCacheStats> {
+        self.inner.key_cache_stats().await
+    }
+
+    /// Discard the cached key of a profile, so that it is reloaded on next use
+    ///
+    /// Call this when the profile has been re-keyed or removed by another process
+    /// sharing the store. Sessions already opened against the profile are not
+    /// affected.
+    pub async fn invalidate_profile_key(&self, name: String) {
+        self.inner.invalidate_profile_key(name).await
+    }
+
+    /// Get the current state of the backend connection pool
+    ///
+    /// The result includes the number of open, idle and active connections along
+    /// with counters and latency percentiles for connection acquisitions. Returns
+    /// `None` if the backend does not maintain a connection pool.
+    pub fn pool_status(&self) -> Option<PoolStatus> {
+        self.inner.pool_status()
+    }
+
+    /// Check the availability of the store
+    ///
+    /// A connection is checked out from the pool, a trivial query is executed
+    /// against the active profile and the state of the profile key cache is
+    /// inspected. The status and latency of each check are reported along with
+    /// the overall status, which is suitable for use in readiness probes.
+    /// Components not supported by the backend are omitted.
+    pub async fn health(&self) -> HealthReport {
+        let mut components = Vec::with_capacity(3);
+
+        if let Some(pool) = self.inner.pool_status() {
+            let start = system_time();
+            let result = self.inner.check_pool().await;
+            components.push(match result {
+                Err(err) => ComponentHealth::unhealthy("pool", start, err.into()),
+                // an exhausted pool may still supply connections as they are released
+                Ok(()) if pool.idle == 0 && pool.size >= pool.max_size => ComponentHealth::new(
+                    "pool",
+                    HealthStatus::Degraded,
+                    start,
+                    Some("The connection pool is exhausted".to_string()),
+                ),
+                Ok(()) => ComponentHealth::new("pool", HealthStatus::Healthy, start, None),
+            });
+        }
+
+        let start = system_time();
+        let result = async {
+            let mut session = self.inner.session(None, false)?;
+            let result = session.ping().await;
+            session.close(false).await?;
+            result
+        }
+        .await;
+        components.push(match result {
+            Err(err) => ComponentHealth::unhealthy("query", start, err.into()),
+            Ok(()) => ComponentHealth::new("query", HealthStatus::Healthy, start, None),
+        });
+
+        let start = system_time();
+        if let Some(stats) = self.inner.key_cache_stats().await {
+            let full = stats
+                .max_size
+                .map(|max_size| stats.size >= max_size)
+                .unwrap_or(false);
+            components.push(if full && stats.evictions > 0 {
+                ComponentHealth::new(
+                    "key_cache",
+                    HealthStatus::Degraded,
+                    start,
+                    Some("The profile key cache is full and evicting keys".to_string()),
+                )
+            } else {
+                ComponentHealth::new("key_cache", HealthStatus::Healthy, start, None)
+            });
+        }
+
+        HealthReport::new(components)
     }
 
     /// Create a new scan instance against the store
@@ -128,8 +1174,12 @@ impl Store {
         order_by: Option<OrderBy>,
         descending: bool,
     ) -> Result<Scan<'static, Entry>, Error> {
+        let profile = self.resolve_profile(profile);
+        if self.is_scan_excluded(profile.as_deref()).await? {
+            return Ok(Scan::empty());
+        }
         Ok(self
-            .0
+            .inner
             .scan(
                 profile,
                 Some(EntryKind::Item),
@@ -143,31 +1193,267 @@ impl Store {
             .await?)
     }
 
+    /// Create a new scan instance which resumes after a record identifier
+    ///
+    /// Records are returned in order of their row identifiers, each paired with its
+    /// identifier. Passing the identifier of the last record received as `after`
+    /// continues the scan from the following record, unaffected by records which
+    /// were inserted or removed in the meantime.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn scan_after(
+        &self,
+        profile: Option<String>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        after: Option<i64>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        descending: bool,
+    ) -> Result<Scan<'static, (i64, Entry)>, Error> {
+        let profile = self.resolve_profile(profile);
+        if self.is_scan_excluded(profile.as_deref()).await? {
+            return Ok(Scan::empty());
+        }
+        Ok(self
+            .inner
+            .scan_after(
+                profile,
+                Some(EntryKind::Item),
+                category,
+                tag_filter,
+                after,
+                offset,
+                limit,
+                descending,
+            )
+            .await?)
+    }
+
+    /// Create a new scan instance against the records whose category lies within
+    /// a namespace
+    ///
+    /// Categories are divided into segments by `/`: the namespace `credential/w3c`
+    /// selects records in the category `credential/w3c` and in any category
+    /// beginning with `credential/w3c/`, such as `credential/w3c/driverlicense`.
+    /// The namespace index of records written before the store schema supported
+    /// namespaces is computed when the store is next opened for writing: until
+    /// then, namespace queries against their profile fail.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn scan_namespace(
+        &self,
+        profile: Option<String>,
+        namespace: &str,
+        tag_filter: Option<TagFilter>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+    ) -> Result<Scan<'static, Entry>, Error> {
+        let profile = self.resolve_profile(profile);
+        if self.is_scan_excluded(profile.as_deref()).await? {
+            return Ok(Scan::empty());
+        }
+        Ok(self
+            .inner
+            .scan_namespace(
+                profile,
+                Some(EntryKind::Item),
+                namespace.to_string(),
+                tag_filter,
+                offset,
+                limit,
+                order_by,
+                descending,
+            )
+            .await?)
+    }
+
+    /// Write the decrypted records matching a category and tag filter as JSON Lines
+    ///
+    /// **This exposes the unencrypted contents of the store**, and is only
+    /// available with the `plaintext_export` feature. It is intended for
+    /// debugging and for migration to other systems. Each line contains the
+    /// `category`, `name` and `tags` of a record, and its `value` when this is
+    /// valid UTF-8 or `value_base64` otherwise. Tags are represented as in tag
+    /// filters, with plaintext tag names prefixed by `~`. Key entries are not
+    /// included. Returns the number of exported records
+    #[cfg(feature = "plaintext_export")]
+    pub async fn export_plaintext(
+        &self,
+        profile: Option<String>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        mut writer: impl std::io::Write,
+    ) -> Result<usize, Error> {
+        #[cfg(feature = "logger")]
+        warn!("Exporting decrypted store records");
+        let profile = self.resolve_profile(profile);
+        let result = async {
+            let total = match self.progress {
+                Some(_) => {
+                    let mut session = self.inner.session(profile.clone(), false)?;
+                    let count = session
+                        .count(
+                            Some(EntryKind::Item),
+                            category.as_deref(),
+                            tag_filter.clone(),
+                        )
+                        .await?;
+                    session.close(false).await?;
+                    Some(count)
+                }
+                None => None,
+            };
+            let mut scan = self
+                .scan(
+                    profile.clone(),
+                    category.clone(),
+                    tag_filter,
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .await?;
+            let mut count = 0;
+            while let Some(rows) = scan.fetch_next().await? {
+                for entry in rows {
+                    serde_json::to_writer(&mut writer, &plaintext_export_line(&entry))
+                        .map_err(err_map!(Unexpected, "Error writing exported record"))?;
+                    writer
+                        .write_all(b"\n")
+                        .map_err(err_map!(Unexpected, "Error writing exported record"))?;
+                    count += 1;
+                }
+                Progress {
+                    phase: ProgressPhase::Export,
+                    profile: profile.clone(),
+                    processed: count as i64,
+                    total,
+                }
+                .report(self.progress.as_deref());
+            }
+            writer
+                .flush()
+                .map_err(err_map!(Unexpected, "Error writing exported record"))?;
+            Ok(count)
+        }
+        .await;
+        audit(
+            AuditOperation::ExportPlaintext,
+            self.audit_actor.as_deref(),
+            profile.as_deref(),
+            category.as_deref(),
+            &result,
+        );
+        result
+    }
+
     /// Create a new session against the store
+    ///
+    /// When the store URI sets `auto_create_profiles=true`, a session may be opened
+    /// for a profile which does not exist. The profile is created by the first
+    /// insert or replace of a record in the session, which is audited and reported
+    /// to the registered hooks as a profile creation. Other operations fail with a
+    /// `NotFound` error until then. Within a transaction, the profile is discarded
+    /// if the transaction is rolled back.
     pub async fn session(&self, profile: Option<String>) -> Result<Session, Error> {
-        let mut sess = Session::new(self.0.session(profile, false)?);
+        self.session_with_trace(profile, None).await
+    }
+
+    /// Create a new session against the store, propagating a trace context to
+    /// the statements it executes
+    ///
+    /// The PostgreSQL backend appends the context to each record statement as a
+    /// SQL comment, allowing the database activity to be correlated with the
+    /// originating request. All database backends include the trace ID in slow
+    /// statement log entries.
+    pub async fn session_with_trace(
+        &self,
+        profile: Option<String>,
+        trace: Option<TraceContext>,
+    ) -> Result<Session, Error> {
+        let mut sess = self.new_session(profile, false, trace)?;
         if let Err(e) = sess.ping().await {
-            sess.0.close(false).await?;
+            sess.inner.close(false).await?;
             Err(e)
         } else {
             Ok(sess)
         }
     }
 
+    /// Create a new session against the store, restricted to a subset of records
+    ///
+    /// Any operation on records outside of the scope fails with an `Input` error.
+    /// The scope of the session cannot be widened, so it may be handed to less
+    /// trusted code in place of an unrestricted session.
+    pub async fn session_with_scope(
+        &self,
+        profile: Option<String>,
+        scope: SessionScope,
+    ) -> Result<Session, Error> {
+        let mut sess = self.session(profile).await?;
+        sess.scope = Some(scope);
+        Ok(sess)
+    }
+
     /// Create a new transaction session against the store
     pub async fn transaction(&self, profile: Option<String>) -> Result<Session, Error> {
-        let mut txn = Session::new(self.0.session(profile, true)?);
+        self.transaction_with_trace(profile, None).await
+    }
+
+    /// Create a new transaction session against the store, propagating a trace
+    /// context to the statements it executes
+    pub async fn transaction_with_trace(
+        &self,
+        profile: Option<String>,
+        trace: Option<TraceContext>,
+    ) -> Result<Session, Error> {
+        let mut txn = self.new_session(profile, true, trace)?;
         if let Err(e) = txn.ping().await {
-            txn.0.close(false).await?;
+            txn.inner.close(false).await?;
             Err(e)
         } else {
             Ok(txn)
         }
     }
 
+    /// Create a new transaction session against the store, restricted to a subset
+    /// of records
+    pub async fn transaction_with_scope(
+        &self,
+        profile: Option<String>,
+        scope: SessionScope,
+    ) -> Result<Session, Error> {
+        let mut txn = self.transaction(profile).await?;
+        txn.scope = Some(scope);
+        Ok(txn)
+    }
+
+    fn new_session(
+        &self,
+        profile: Option<String>,
+        transaction: bool,
+        trace: Option<TraceContext>,
+    ) -> Result<Session, Error> {
+        let profile = self.resolve_profile(profile);
+        let profile_name = profile
+            .clone()
+            .unwrap_or_else(|| self.inner.get_active_profile());
+        let mut inner = self.inner.session(profile, transaction)?;
+        inner.set_trace_context(trace);
+        Ok(Session::new(
+            inner,
+            profile_name,
+            self.audit_actor.clone(),
+            self.hooks.clone(),
+            transaction,
+        ))
+    }
+
     /// Close the store instance, waiting for any shutdown procedures to complete.
     pub async fn close(self) -> Result<(), Error> {
-        Ok(self.0.close().await?)
+        Ok(self.inner.close().await?)
     }
 }
 
@@ -179,11 +1465,102 @@ impl From<AnyBackend> for Store {
 
 /// An active connection to the store backend
 #[derive(Debug)]
-pub struct Session(AnyBackendSession);
+pub struct Session {
+    inner: AnyBackendSession,
+    profile: String,
+    audit_actor: Option<Arc<str>>,
+    entry_cache: Option<EntryCache>,
+    hooks: HookList,
+    transaction: bool,
+    pending_events: Vec<HookEvent>,
+    scope: Option<SessionScope>,
+}
 
 impl Session {
-    pub(crate) fn new(inner: AnyBackendSession) -> Self {
-        Self(inner)
+    pub(crate) fn new(
+        inner: AnyBackendSession,
+        profile: String,
+        audit_actor: Option<Arc<str>>,
+        hooks: HookList,
+        transaction: bool,
+    ) -> Self {
+        Self {
+            inner,
+            profile,
+            audit_actor,
+            entry_cache: None,
+            hooks,
+            transaction,
+            pending_events: Vec::new(),
+            scope: None,
+        }
+    }
+
+    /// Get the scope restricting the records accessible to the session, if any
+    pub fn scope(&self) -> Option<&SessionScope> {
+        self.scope.as_ref()
+    }
+
+    /// Ensure that records of the given kind and category are within the scope of
+    /// the session
+    fn check_scope(&self, kind: EntryKind, category: Option<&str>) -> Result<(), Error> {
+        match self.scope.as_ref() {
+            Some(scope) if !scope.allows(kind, category) => Err(err_msg!(
+                Input,
+                "Operation is outside the scope of the session"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Enable or disable the cache of fetched records for this session
+    ///
+    /// When enabled, up to `max_entries` records returned by `fetch` and `fetch_key`
+    /// are retained in decrypted form, so that repeated lookups of the same record do
+    /// not query and decrypt it again. Any update made through the session removes
+    /// the affected record from the cache. Changes made by other sessions are not
+    /// observed while a record remains cached, and records which expire while cached
+    /// continue to be returned, so the cache is only suitable for short-lived sessions.
+    /// Passing `None` disables and clears the cache.
+    pub fn set_entry_cache(&mut self, max_entries: Option<usize>) {
+        self.entry_cache = max_entries.filter(|max| *max > 0).map(EntryCache::new);
+    }
+
+    /// Replace the trace context propagated to the statements executed by the session
+    pub fn set_trace_context(&mut self, trace: Option<TraceContext>) {
+        self.inner.set_trace_context(trace);
+    }
+
+    fn audit<T>(&self, operation: AuditOperation, target: Option<&str>, result: &Result<T, Error>) {
+        audit(
+            operation,
+            self.audit_actor.as_deref(),
+            Some(&self.profile),
+            target,
+            result,
+        )
+    }
+
+    /// Notify the registered hooks of a change, deferring the notification until
+    /// commit when in a transaction
+    fn notify(&mut self, event: HookEvent) {
+        if self.hooks.is_empty() {
+            return;
+        }
+        if self.transaction {
+            self.pending_events.push(event);
+        } else {
+            self.hooks.dispatch(&self.profile, &event);
+        }
+    }
+
+    /// Record the creation of the session profile by the preceding update, for
+    /// stores which create missing profiles on demand
+    fn check_profile_created(&mut self) {
+        if self.inner.take_profile_created() {
+            self.audit(AuditOperation::CreateProfile, None, &Ok::<_, Error>(()));
+            self.notify(HookEvent::CreateProfile);
+        }
     }
 
     /// Count the number of entries for a given record category
@@ -192,12 +1569,28 @@ impl Session {
         category: Option<&str>,
         tag_filter: Option<TagFilter>,
     ) -> Result<i64, Error> {
+        self.check_scope(EntryKind::Item, category)?;
         Ok(self
-            .0
+            .inner
             .count(Some(EntryKind::Item), category, tag_filter)
             .await?)
     }
 
+    /// Count the number of entries whose category lies within a namespace
+    ///
+    /// See `Store::scan_namespace` for the selection of categories.
+    pub async fn count_namespace(
+        &mut self,
+        namespace: &str,
+        tag_filter: Option<TagFilter>,
+    ) -> Result<i64, Error> {
+        self.check_scope(EntryKind::Item, None)?;
+        Ok(self
+            .inner
+            .count_namespace(Some(EntryKind::Item), namespace, tag_filter)
+            .await?)
+    }
+
     /// Retrieve the current record at `(category, name)`.
     ///
     /// Specify `for_update` when in a transaction to create an update lock on the
@@ -208,9 +1601,53 @@ impl Session {
         name: &str,
         for_update: bool,
     ) -> Result<Option<Entry>, Error> {
+        self.fetch_entry(EntryKind::Item, category, name, for_update)
+            .await
+    }
+
+    /// Retrieve the current record at `(category, name)`, deserializing its
+    /// value from the given format.
+    ///
+    /// The value must have been stored in the same format, for instance by
+    /// `insert_serialized`.
+    pub async fn fetch_as<T: DeserializeOwned>(
+        &mut self,
+        category: &str,
+        name: &str,
+        format: ValueFormat,
+        for_update: bool,
+    ) -> Result<Option<T>, Error> {
+        self.fetch(category, name, for_update)
+            .await?
+            .map(|entry| format.deserialize(entry.value.as_ref()))
+            .transpose()
+    }
+
+    /// Retrieve the current record at `(category, name)` without its tags.
+    ///
+    /// The tags of the record are neither loaded nor decrypted, which avoids
+    /// unnecessary work when only the value is required. The returned entry
+    /// always has an empty list of tags
+    pub async fn fetch_without_tags(
+        &mut self,
+        category: &str,
+        name: &str,
+        for_update: bool,
+    ) -> Result<Option<Entry>, Error> {
+        self.check_scope(EntryKind::Item, Some(category))?;
+        if !for_update {
+            if let Some(mut entry) = self
+                .entry_cache
+                .as_mut()
+                .and_then(|cache| cache.get(EntryKind::Item, category, name))
+            {
+                entry.tags.clear();
+                return Ok(Some(entry));
+            }
+        }
         Ok(self
-            .0
-            .fetch(EntryKind::Item, category, name, for_update)
+            .inner
+            .fetch_without_tags(EntryKind::Item, category, name, for_update)
             .await?)
     }
 
@@ -228,8 +1665,9 @@ impl Session {
         descending: bool,
         for_update: bool,
     ) -> Result<Vec<Entry>, Error> {
+        self.check_scope(EntryKind::Item, category)?;
         Ok(self
-            .0
+            .inner
             .fetch_all(
                 Some(EntryKind::Item),
                 category,
@@ -249,59 +1687,154 @@ impl Session {
         name: &str,
         value: &[u8],
         tags: Option<&[EntryTag]>,
-        expiry_ms: Option<i64>,
+        expiry: impl IntoEntryExpiry,
     ) -> Result<(), Error> {
-        Ok(self
-            .0
-            .update(
-                EntryKind::Item,
-                EntryOperation::Insert,
-                category,
-                name,
-                Some(value),
-                tags,
-                expiry_ms,
-            )
-            .await?)
+        self.update_entry(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            category,
+            name,
+            Some(value),
+            tags,
+            expiry.into_entry_expiry().map(|expiry| expiry.to_millis()),
+        )
+        .await
+    }
+
+    /// Insert a new record into the store, serializing its value in the given
+    /// format
+    pub async fn insert_serialized<T: Serialize + ?Sized>(
+        &mut self,
+        category: &str,
+        name: &str,
+        value: &T,
+        format: ValueFormat,
+        tags: Option<&[EntryTag]>,
+        expiry: impl IntoEntryExpiry,
+    ) -> Result<(), Error> {
+        let value = format.serialize(value)?;
+        self.insert(category, name, value.as_ref(), tags, expiry)
+            .await
     }
 
     /// Remove a record from the store
     pub async fn remove(&mut self, category: &str, name: &str) -> Result<(), Error> {
-        Ok(self
-            .0
-            .update(
-                EntryKind::Item,
-                EntryOperation::Remove,
-                category,
-                name,
-                None,
-                None,
-                None,
-            )
-            .await?)
+        self.update_entry(
+            EntryKind::Item,
+            EntryOperation::Remove,
+            category,
+            name,
+            None,
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Remove a record from the store, overwriting its stored value and tags
+    /// so that they cannot be recovered from the database file
+    ///
+    /// The freed pages are then released and the write-ahead log is truncated,
+    /// so that no copy of the record remains. When called within a transaction,
+    /// these steps are skipped and the previous content may persist in the
+    /// write-ahead log until it is next checkpointed.
+    ///
+    /// This is supported by SQLite stores only.
+    pub async fn shred(&mut self, category: &str, name: &str) -> Result<(), Error> {
+        self.check_scope(EntryKind::Item, Some(category))?;
+        if let Some(cache) = self.entry_cache.as_mut() {
+            cache.remove(EntryKind::Item, category, name);
+        }
+        self.inner.shred(EntryKind::Item, category, name).await?;
+        self.notify(HookEvent::Remove {
+            category: category.to_string(),
+            name: name.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Replace the value and tags of a record in the store
+    pub async fn replace(
+        &mut self,
+        category: &str,
+        name: &str,
+        value: &[u8],
+        tags: Option<&[EntryTag]>,
+        expiry: impl IntoEntryExpiry,
+    ) -> Result<(), Error> {
+        self.update_entry(
+            EntryKind::Item,
+            EntryOperation::Replace,
+            category,
+            name,
+            Some(value),
+            tags,
+            expiry.into_entry_expiry().map(|expiry| expiry.to_millis()),
+        )
+        .await
+    }
+
+    /// Replace the value and tags of a record in the store, serializing its
+    /// value in the given format
+    pub async fn replace_serialized<T: Serialize + ?Sized>(
+        &mut self,
+        category: &str,
+        name: &str,
+        value: &T,
+        format: ValueFormat,
+        tags: Option<&[EntryTag]>,
+        expiry: impl IntoEntryExpiry,
+    ) -> Result<(), Error> {
+        let value = format.serialize(value)?;
+        self.replace(category, name, value.as_ref(), tags, expiry)
+            .await
     }
 
-    /// Replace the value and tags of a record in the store
-    pub async fn replace(
+    /// Insert a new record into the store, storing its value without encryption
+    ///
+    /// The category, name and tags of the record are protected as usual, and
+    /// the value is authenticated against the record. This avoids the cost of
+    /// encryption for large values which are not sensitive, such as cached
+    /// public ledger data. The value is returned from fetches and scans in the
+    /// same manner as an encrypted value.
+    pub async fn insert_unencrypted(
         &mut self,
         category: &str,
         name: &str,
         value: &[u8],
         tags: Option<&[EntryTag]>,
-        expiry_ms: Option<i64>,
+        expiry: impl IntoEntryExpiry,
     ) -> Result<(), Error> {
-        Ok(self
-            .0
-            .update(
-                EntryKind::Item,
-                EntryOperation::Replace,
-                category,
-                name,
-                Some(value),
-                tags,
-                expiry_ms,
-            )
-            .await?)
+        self.update_unencrypted(
+            EntryOperation::Insert,
+            category,
+            name,
+            value,
+            tags,
+            expiry.into_entry_expiry().map(|expiry| expiry.to_millis()),
+        )
+        .await
+    }
+
+    /// Replace the value and tags of a record in the store, storing the value
+    /// without encryption
+    pub async fn replace_unencrypted(
+        &mut self,
+        category: &str,
+        name: &str,
+        value: &[u8],
+        tags: Option<&[EntryTag]>,
+        expiry: impl IntoEntryExpiry,
+    ) -> Result<(), Error> {
+        self.update_unencrypted(
+            EntryOperation::Replace,
+            category,
+            name,
+            value,
+            tags,
+            expiry.into_entry_expiry().map(|expiry| expiry.to_millis()),
+        )
+        .await
     }
 
     /// Remove all records in the store matching a given `category` and `tag_filter`
@@ -310,10 +1843,23 @@ impl Session {
         category: Option<&str>,
         tag_filter: Option<TagFilter>,
     ) -> Result<i64, Error> {
-        Ok(self
-            .0
+        self.check_scope(EntryKind::Item, category)?;
+        if let Some(cache) = self.entry_cache.as_mut() {
+            cache.clear();
+        }
+        let result = self
+            .inner
             .remove_all(Some(EntryKind::Item), category, tag_filter)
-            .await?)
+            .await
+            .map_err(Error::from);
+        self.audit(AuditOperation::RemoveAll, category, &result);
+        if let Ok(removed) = result {
+            self.notify(HookEvent::RemoveAll {
+                category: category.map(str::to_string),
+                removed,
+            });
+        }
+        result
     }
 
     /// Perform a record update
@@ -327,20 +1873,18 @@ impl Session {
         name: &str,
         value: Option<&[u8]>,
         tags: Option<&[EntryTag]>,
-        expiry_ms: Option<i64>,
+        expiry: impl IntoEntryExpiry,
     ) -> Result<(), Error> {
-        Ok(self
-            .0
-            .update(
-                EntryKind::Item,
-                operation,
-                category,
-                name,
-                value,
-                tags,
-                expiry_ms,
-            )
-            .await?)
+        self.update_entry(
+            EntryKind::Item,
+            operation,
+            category,
+            name,
+            value,
+            tags,
+            expiry.into_entry_expiry().map(|expiry| expiry.to_millis()),
+        )
+        .await
     }
 
     /// Insert a local key instance into the store
@@ -351,10 +1895,12 @@ impl Session {
         metadata: Option<&str>,
         reference: Option<KeyReference>,
         tags: Option<&[EntryTag]>,
-        expiry_ms: Option<i64>,
+        expiry: impl IntoEntryExpiry,
     ) -> Result<(), Error> {
         let data = if key.is_hardware_backed() {
             key.inner.key_id()?
+        } else if matches!(reference, Some(KeyReference::Remote { .. })) {
+            key.to_jwk_public(None)?.into()
         } else {
             key.encode()?
         };
@@ -362,6 +1908,7 @@ impl Session {
             metadata: metadata.map(str::to_string),
             reference,
             data: Some(data),
+            origin: key.origin.clone(),
         };
         let value = params.to_bytes()?;
         let mut ins_tags = Vec::with_capacity(10);
@@ -378,18 +1925,100 @@ impl Session {
                 ins_tags.push(t.map_ref(|k, v| (format!("user:{}", k), v.to_string())));
             }
         }
-        self.0
-            .update(
+        let result = self
+            .update_entry(
                 EntryKind::Kms,
                 EntryOperation::Insert,
                 KmsCategory::CryptoKey.as_str(),
                 name,
                 Some(value.as_ref()),
                 Some(ins_tags.as_slice()),
-                expiry_ms,
+                expiry.into_entry_expiry().map(|expiry| expiry.to_millis()),
             )
-            .await?;
-        Ok(())
+            .await;
+        self.audit(AuditOperation::CreateKey, Some(name), &result);
+        result
+    }
+
+    /// Export a stored key wrapped under a dedicated wrapping key
+    ///
+    /// The result is a JWE in compact serialization, as produced by
+    /// [`wrap_key_jwe`](crate::kms::wrap_key_jwe), suitable for backup or escrow of
+    /// an individual key.
+    pub async fn export_key_wrapped(
+        &mut self,
+        name: &str,
+        wrapping_key: &LocalKey,
+    ) -> Result<String, Error> {
+        let result = async {
+            let entry = self
+                .fetch_key(name, false)
+                .await?
+                .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
+            wrap_key_jwe(wrapping_key, &entry.load_local_key()?)
+        }
+        .await;
+        self.audit(AuditOperation::ExportKey, Some(name), &result);
+        result
+    }
+
+    /// Import wrapped key material into the store
+    ///
+    /// The key may have been produced by `export_key_wrapped`, or wrapped by an
+    /// external key management system. It is recovered using either a key instance
+    /// or the name of a key stored in the current profile, so that the plaintext
+    /// key material is never passed through the API.
+    pub async fn import_key_wrapped(
+        &mut self,
+        name: &str,
+        wrapped: WrappedKey<'_>,
+        unwrapping_key: impl Into<UnwrappingKey<'_>>,
+        metadata: Option<&str>,
+        tags: Option<&[EntryTag]>,
+        expiry: impl IntoEntryExpiry,
+    ) -> Result<(), Error> {
+        let key = match unwrapping_key.into() {
+            UnwrappingKey::Local(unwrap_key) => wrapped.unwrap(unwrap_key)?,
+            UnwrappingKey::Stored(unwrap_key_name) => {
+                let unwrap_key = self
+                    .fetch_key(unwrap_key_name, false)
+                    .await?
+                    .ok_or_else(|| err_msg!(NotFound, "Unwrapping key not found"))?
+                    .load_local_key()?;
+                wrapped.unwrap(&unwrap_key)?
+            }
+        };
+        self.insert_key(name, &key, metadata, None, tags, expiry)
+            .await
+    }
+
+    /// Insert a reference to a key held by a registered remote key service
+    ///
+    /// The public key is fetched from the service and stored alongside the key
+    /// metadata. Use [`KeyEntry::load_remote_key`] to perform operations with the key.
+    pub async fn insert_remote_key(
+        &mut self,
+        name: &str,
+        service: &str,
+        key_id: &str,
+        metadata: Option<&str>,
+        tags: Option<&[EntryTag]>,
+        expiry: impl IntoEntryExpiry,
+    ) -> Result<(), Error> {
+        let key = RemoteKeyHandle::load(service, key_id).await?;
+        let reference = KeyReference::Remote {
+            service: service.to_string(),
+            key_id: key_id.to_string(),
+        };
+        self.insert_key(
+            name,
+            key.public_key(),
+            metadata,
+            Some(reference),
+            tags,
+            expiry,
+        )
+        .await
     }
 
     /// Fetch an existing key from the store
@@ -403,8 +2032,7 @@ impl Session {
     ) -> Result<Option<KeyEntry>, Error> {
         Ok(
             if let Some(row) = self
-                .0
-                .fetch(
+                .fetch_entry(
                     EntryKind::Kms,
                     KmsCategory::CryptoKey.as_str(),
                     name,
@@ -450,8 +2078,9 @@ impl Session {
         } else {
             Some(TagFilter::all_of(query_parts))
         };
+        self.check_scope(EntryKind::Kms, Some(KmsCategory::CryptoKey.as_str()))?;
         let rows = self
-            .0
+            .inner
             .fetch_all(
                 Some(EntryKind::Kms),
                 Some(KmsCategory::CryptoKey.as_str()),
@@ -471,9 +2100,8 @@ impl Session {
 
     /// Remove an existing key from the store
     pub async fn remove_key(&mut self, name: &str) -> Result<(), Error> {
-        Ok(self
-            .0
-            .update(
+        let result = self
+            .update_entry(
                 EntryKind::Kms,
                 EntryOperation::Remove,
                 KmsCategory::CryptoKey.as_str(),
@@ -482,7 +2110,9 @@ impl Session {
                 None,
                 None,
             )
-            .await?)
+            .await;
+        self.audit(AuditOperation::RemoveKey, Some(name), &result);
+        result
     }
 
     /// Replace the metadata and tags on an existing key in the store
@@ -491,11 +2121,12 @@ impl Session {
         name: &str,
         metadata: Option<&str>,
         tags: Option<&[EntryTag]>,
-        expiry_ms: Option<i64>,
+        expiry: impl IntoEntryExpiry,
     ) -> Result<(), Error> {
+        self.check_scope(EntryKind::Kms, Some(KmsCategory::CryptoKey.as_str()))?;
         let row = self
-            .0
-            .fetch(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name, true)
+            .inner
+            .fetch_entry(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name, true)
             .await?
             .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
 
@@ -515,33 +2146,543 @@ impl Session {
             }
         }
 
-        self.0
-            .update(
-                EntryKind::Kms,
-                EntryOperation::Replace,
-                KmsCategory::CryptoKey.as_str(),
+        self.update_entry(
+            EntryKind::Kms,
+            EntryOperation::Replace,
+            KmsCategory::CryptoKey.as_str(),
+            name,
+            Some(value.as_ref()),
+            Some(upd_tags.as_slice()),
+            expiry.into_entry_expiry().map(|expiry| expiry.to_millis()),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sign a message using a stored key
+    ///
+    /// Keys held by a remote key service are used through the registered service.
+    /// Registered hooks are notified of the signing operation, which is not
+    /// deferred when in a transaction.
+    pub async fn sign_message(
+        &mut self,
+        name: &str,
+        message: &[u8],
+        sig_type: Option<&str>,
+    ) -> Result<Vec<u8>, Error> {
+        let entry = self
+            .fetch_key(name, false)
+            .await?
+            .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
+        let signature = match entry.params.reference {
+            Some(KeyReference::Remote { .. }) => {
+                entry
+                    .load_remote_key()?
+                    .sign_message(message, sig_type)
+                    .await?
+            }
+            _ => entry.load_local_key()?.sign_message(message, sig_type)?,
+        };
+        self.hooks.dispatch(
+            &self.profile,
+            &HookEvent::KeySign {
+                name: name.to_string(),
+            },
+        );
+        Ok(signature)
+    }
+
+    async fn fetch_entry(
+        &mut self,
+        kind: EntryKind,
+        category: &str,
+        name: &str,
+        for_update: bool,
+    ) -> Result<Option<Entry>, Error> {
+        self.check_scope(kind, Some(category))?;
+        if !for_update {
+            if let Some(entry) = self
+                .entry_cache
+                .as_mut()
+                .and_then(|cache| cache.get(kind, category, name))
+            {
+                return Ok(Some(entry));
+            }
+        }
+        let entry = self
+            .inner
+            .fetch_entry(kind, category, name, for_update)
+            .await?;
+        if let (Some(cache), Some(entry)) = (self.entry_cache.as_mut(), entry.as_ref()) {
+            cache.insert(entry.clone());
+        }
+        Ok(entry)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn update_entry(
+        &mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &str,
+        name: &str,
+        value: Option<&[u8]>,
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        self.check_scope(kind, Some(category))?;
+        if let Some(cache) = self.entry_cache.as_mut() {
+            cache.remove(kind, category, name);
+        }
+        let result = self
+            .inner
+            .update_entry(kind, operation, category, name, value, tags, expiry_ms)
+            .await;
+        self.check_profile_created();
+        result?;
+        let (category, name) = (category.to_string(), name.to_string());
+        self.notify(match (kind, operation) {
+            (EntryKind::Item, EntryOperation::Insert) => HookEvent::Insert { category, name },
+            (EntryKind::Item, EntryOperation::Replace) => HookEvent::Replace { category, name },
+            (EntryKind::Item, EntryOperation::Remove) => HookEvent::Remove { category, name },
+            (EntryKind::Kms, EntryOperation::Insert) => HookEvent::InsertKey { name },
+            (EntryKind::Kms, EntryOperation::Replace) => HookEvent::UpdateKey { name },
+            (EntryKind::Kms, EntryOperation::Remove) => HookEvent::RemoveKey { name },
+        });
+        Ok(())
+    }
+
+    async fn update_unencrypted(
+        &mut self,
+        operation: EntryOperation,
+        category: &str,
+        name: &str,
+        value: &[u8],
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        self.check_scope(EntryKind::Item, Some(category))?;
+        if let Some(cache) = self.entry_cache.as_mut() {
+            cache.remove(EntryKind::Item, category, name);
+        }
+        let result = self
+            .inner
+            .update_unencrypted(
+                EntryKind::Item,
+                operation,
+                category,
                 name,
-                Some(value.as_ref()),
-                Some(upd_tags.as_slice()),
+                value,
+                tags,
                 expiry_ms,
             )
-            .await?;
-
+            .await;
+        self.check_profile_created();
+        result?;
+        let (category, name) = (category.to_string(), name.to_string());
+        self.notify(if operation == EntryOperation::Insert {
+            HookEvent::Insert { category, name }
+        } else {
+            HookEvent::Replace { category, name }
+        });
         Ok(())
     }
 
     /// Test the connection to the store
     pub async fn ping(&mut self) -> Result<(), Error> {
-        Ok(self.0.ping().await?)
+        Ok(self.inner.ping().await?)
     }
 
     /// Commit the pending transaction
     pub async fn commit(mut self) -> Result<(), Error> {
-        Ok(self.0.close(true).await?)
+        self.inner.close(true).await?;
+        for event in self.pending_events.drain(..) {
+            self.hooks.dispatch(&self.profile, &event);
+        }
+        Ok(())
     }
 
     /// Roll back the pending transaction
     pub async fn rollback(mut self) -> Result<(), Error> {
-        Ok(self.0.close(false).await?)
+        Ok(self.inner.close(false).await?)
+    }
+}
+
+/// Format a record as a JSON object for `Store::export_plaintext`
+#[cfg(feature = "plaintext_export")]
+fn plaintext_export_line(entry: &Entry) -> serde_json::Value {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::{Map, Value};
+
+    let mut tags = Map::new();
+    for tag in entry.tags.iter() {
+        let (name, value) = match tag {
+            EntryTag::Encrypted(name, value) => (name.clone(), value),
+            EntryTag::Plaintext(name, value) => (format!("~{}", name), value),
+        };
+        match tags.get_mut(&name) {
+            Some(Value::Array(values)) => values.push(value.as_str().into()),
+            Some(prev) => *prev = Value::Array(vec![prev.take(), value.as_str().into()]),
+            None => {
+                tags.insert(name, value.as_str().into());
+            }
+        }
+    }
+    let mut line = Map::new();
+    line.insert("category".to_string(), entry.category.as_str().into());
+    line.insert("name".to_string(), entry.name.as_str().into());
+    match std::str::from_utf8(entry.value.as_ref()) {
+        Ok(value) => line.insert("value".to_string(), value.into()),
+        Err(_) => line.insert(
+            "value_base64".to_string(),
+            STANDARD.encode(entry.value.as_ref()).into(),
+        ),
+    };
+    line.insert("tags".to_string(), Value::Object(tags));
+    Value::Object(line)
+}
+
+/// The status of a store component reported by a health check
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HealthStatus {
+    /// The component is operating normally
+    Healthy,
+    /// The component is available but under pressure
+    Degraded,
+    /// The component is not available
+    Unhealthy,
+}
+
+impl HealthStatus {
+    /// Get a string representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+/// The result of checking a single store component
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentHealth {
+    /// The name of the component: `pool`, `query` or `key_cache`
+    pub name: &'static str,
+    /// The status of the component
+    pub status: HealthStatus,
+    /// The time taken to perform the check
+    pub latency: Duration,
+    /// A description of the problem when the component is not healthy
+    pub message: Option<String>,
+}
+
+impl ComponentHealth {
+    fn new(
+        name: &'static str,
+        status: HealthStatus,
+        start: SystemTime,
+        message: Option<String>,
+    ) -> Self {
+        Self {
+            name,
+            status,
+            latency: system_time().duration_since(start).unwrap_or_default(),
+            message,
+        }
+    }
+
+    fn unhealthy(name: &'static str, start: SystemTime, err: Error) -> Self {
+        // the cause is not included, as it may expose details of the backend
+        let message = err.message().unwrap_or(err.kind().as_str()).to_string();
+        Self::new(name, HealthStatus::Unhealthy, start, Some(message))
+    }
+}
+
+/// The aggregated result of a store health check
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthReport {
+    /// The least healthy status of the checked components
+    pub status: HealthStatus,
+    /// The results of the individual checks
+    pub components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    fn new(components: Vec<ComponentHealth>) -> Self {
+        let status = components
+            .iter()
+            .map(|c| c.status)
+            .max()
+            .unwrap_or(HealthStatus::Healthy);
+        Self { status, components }
+    }
+
+    /// Determine whether the store is able to serve requests
+    pub fn is_ready(&self) -> bool {
+        self.status != HealthStatus::Unhealthy
+    }
+
+    /// Get the result for a named component, if it was checked
+    pub fn component(&self, name: &str) -> Option<&ComponentHealth> {
+        self.components.iter().find(|c| c.name == name)
+    }
+}
+
+/// The expiry time of a record
+///
+/// Records past their expiry time are not returned by fetches and scans. An
+/// expiry may be given as a number of milliseconds, a `Duration` or a
+/// `SystemTime`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntryExpiry(ExpiryTime);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum ExpiryTime {
+    Millis(i64),
+    At(SystemTime),
+}
+
+impl EntryExpiry {
+    /// Expire a record after a period of time
+    pub fn after(duration: Duration) -> Self {
+        Self::from_millis(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
+    }
+
+    /// Expire a record at a point in time
+    pub fn at(time: SystemTime) -> Self {
+        Self(ExpiryTime::At(time))
+    }
+
+    /// Expire a record after a number of milliseconds
+    ///
+    /// A negative number results in a record which has already expired.
+    pub fn from_millis(millis: i64) -> Self {
+        Self(ExpiryTime::Millis(millis))
+    }
+
+    /// Get the number of milliseconds from now until the expiry time
+    pub fn to_millis(&self) -> i64 {
+        let millis = |duration: Duration| i64::try_from(duration.as_millis()).unwrap_or(i64::MAX);
+        match self.0 {
+            ExpiryTime::Millis(millis) => millis,
+            ExpiryTime::At(time) => match time.duration_since(system_time()) {
+                Ok(remain) => millis(remain),
+                Err(err) => -millis(err.duration()),
+            },
+        }
+    }
+}
+
+impl From<i64> for EntryExpiry {
+    fn from(millis: i64) -> Self {
+        Self::from_millis(millis)
+    }
+}
+
+impl From<Duration> for EntryExpiry {
+    fn from(duration: Duration) -> Self {
+        Self::after(duration)
+    }
+}
+
+impl From<SystemTime> for EntryExpiry {
+    fn from(time: SystemTime) -> Self {
+        Self::at(time)
+    }
+}
+
+/// A trait implemented by types that can be converted into an optional record
+/// expiry
+///
+/// An expiry may be given as an `EntryExpiry`, a `Duration` or a `SystemTime`,
+/// or as a number of milliseconds. An `Option<i64>` is accepted for no expiry.
+pub trait IntoEntryExpiry {
+    /// Convert self into an optional record expiry
+    fn into_entry_expiry(self) -> Option<EntryExpiry>;
+}
+
+impl IntoEntryExpiry for EntryExpiry {
+    fn into_entry_expiry(self) -> Option<EntryExpiry> {
+        Some(self)
+    }
+}
+
+impl IntoEntryExpiry for Option<i64> {
+    fn into_entry_expiry(self) -> Option<EntryExpiry> {
+        self.map(EntryExpiry::from_millis)
+    }
+}
+
+impl IntoEntryExpiry for i64 {
+    fn into_entry_expiry(self) -> Option<EntryExpiry> {
+        Some(EntryExpiry::from_millis(self))
+    }
+}
+
+impl IntoEntryExpiry for Duration {
+    fn into_entry_expiry(self) -> Option<EntryExpiry> {
+        Some(EntryExpiry::after(self))
+    }
+}
+
+impl IntoEntryExpiry for SystemTime {
+    fn into_entry_expiry(self) -> Option<EntryExpiry> {
+        Some(EntryExpiry::at(self))
+    }
+}
+
+/// The encoding of a record value serialized by a session
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValueFormat {
+    /// A JSON document
+    Json,
+    /// A CBOR document
+    Cbor,
+}
+
+impl ValueFormat {
+    /// Get a string representation of the format
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Cbor => "cbor",
+        }
+    }
+
+    fn serialize<T: Serialize + ?Sized>(&self, value: &T) -> Result<SecretBytes, Error> {
+        match self {
+            Self::Json => serde_json::to_vec(value)
+                .map_err(|e| err_msg!(Input, "Error serializing JSON value: {}", e)),
+            Self::Cbor => serde_cbor::to_vec(&value)
+                .map_err(|e| err_msg!(Input, "Error serializing CBOR value: {}", e)),
+        }
+        .map(SecretBytes::from)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, value: &[u8]) -> Result<T, Error> {
+        match self {
+            Self::Json => serde_json::from_slice(value)
+                .map_err(|e| err_msg!(Input, "Error deserializing JSON value: {}", e)),
+            Self::Cbor => serde_cbor::from_slice(value)
+                .map_err(|e| err_msg!(Input, "Error deserializing CBOR value: {}", e)),
+        }
+    }
+}
+
+/// A restriction of a session to a subset of the records of a profile
+///
+/// An empty scope permits no operations on records. Categories added to the scope
+/// apply to records of the `Item` kind, while adding a kind permits access to all
+/// of its records. Operations not naming a category, such as a count across all
+/// categories, require the whole kind to be permitted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SessionScope {
+    kinds: Vec<EntryKind>,
+    categories: Vec<String>,
+}
+
+impl SessionScope {
+    /// Create an empty scope
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Permit access to all records of a given kind
+    pub fn with_kind(mut self, kind: EntryKind) -> Self {
+        if !self.kinds.contains(&kind) {
+            self.kinds.push(kind);
+        }
+        self
+    }
+
+    /// Permit access to the records within a category
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    /// Check whether the records of a given kind and category are within the scope
+    pub fn allows(&self, kind: EntryKind, category: Option<&str>) -> bool {
+        self.kinds.contains(&kind)
+            || (kind == EntryKind::Item
+                && category
+                    .map(|category| self.categories.iter().any(|c| c == category))
+                    .unwrap_or(false))
+    }
+}
+
+/// A bounded cache of decrypted records fetched within a session
+struct EntryCache {
+    entries: HashMap<(EntryKind, String, String), (u64, Entry)>,
+    max_entries: usize,
+    counter: u64,
+}
+
+impl EntryCache {
+    fn new(max_entries: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_entries,
+            counter: 0,
+        }
+    }
+
+    fn get(&mut self, kind: EntryKind, category: &str, name: &str) -> Option<Entry> {
+        self.counter += 1;
+        let counter = self.counter;
+        self.entries
+            .get_mut(&(kind, category.to_string(), name.to_string()))
+            .map(|(last_used, entry)| {
+                *last_used = counter;
+                entry.clone()
+            })
+    }
+
+    fn insert(&mut self, entry: Entry) {
+        self.counter += 1;
+        let key = (entry.kind, entry.category.clone(), entry.name.clone());
+        self.entries.insert(key, (self.counter, entry));
+        if self.entries.len() > self.max_entries {
+            // the cache is expected to be small, so a linear search is sufficient
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn remove(&mut self, kind: EntryKind, category: &str, name: &str) {
+        self.entries
+            .remove(&(kind, category.to_string(), name.to_string()));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Debug for Store {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Store")
+            .field("inner", &self.inner)
+            .field("audit_actor", &self.audit_actor)
+            .field("progress", &self.progress.is_some())
+            .field("hooks", &self.hooks)
+            .finish()
+    }
+}
+
+impl Debug for EntryCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EntryCache")
+            .field("len", &self.entries.len())
+            .field("max_entries", &self.max_entries)
+            .finish()
     }
 }
@@ -0,0 +1,59 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::error::{Error, ErrorKind};
+
+/// An error raised by the exported interfaces, by error kind
+#[derive(Debug, uniffi::Error)]
+pub enum AskarError {
+    Archived { message: String },
+    Backend { message: String },
+    Busy { message: String },
+    Cancelled { message: String },
+    Custom { message: String },
+    Duplicate { message: String },
+    Encryption { message: String },
+    Input { message: String },
+    NotFound { message: String },
+    QuotaExceeded { message: String },
+    Unexpected { message: String },
+    Unsupported { message: String },
+}
+
+impl From<Error> for AskarError {
+    fn from(err: Error) -> Self {
+        let message = err.to_string();
+        match err.kind() {
+            ErrorKind::Archived => Self::Archived { message },
+            ErrorKind::Backend => Self::Backend { message },
+            ErrorKind::Busy => Self::Busy { message },
+            ErrorKind::Cancelled => Self::Cancelled { message },
+            ErrorKind::Custom => Self::Custom { message },
+            ErrorKind::Duplicate => Self::Duplicate { message },
+            ErrorKind::Encryption => Self::Encryption { message },
+            ErrorKind::Input => Self::Input { message },
+            ErrorKind::NotFound => Self::NotFound { message },
+            ErrorKind::QuotaExceeded => Self::QuotaExceeded { message },
+            ErrorKind::Unexpected => Self::Unexpected { message },
+            ErrorKind::Unsupported => Self::Unsupported { message },
+        }
+    }
+}
+
+impl Display for AskarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Archived { message }
+            | Self::Backend { message }
+            | Self::Busy { message }
+            | Self::Cancelled { message }
+            | Self::Custom { message }
+            | Self::Duplicate { message }
+            | Self::Encryption { message }
+            | Self::Input { message }
+            | Self::NotFound { message }
+            | Self::QuotaExceeded { message }
+            | Self::Unexpected { message }
+            | Self::Unsupported { message } => f.write_str(message),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::{str::FromStr, sync::Arc};
+
+use super::error::AskarError;
+use crate::{
+    crypto::alg::KeyAlg,
+    error::Error,
+    kms::{KeyEntry, LocalKey},
+};
+
+/// A key instance held in memory
+#[derive(Debug, uniffi::Object)]
+pub struct AskarKey {
+    pub(super) key: LocalKey,
+}
+
+impl AskarKey {
+    pub(super) fn new(key: LocalKey) -> Arc<Self> {
+        Arc::new(Self { key })
+    }
+}
+
+#[uniffi::export]
+impl AskarKey {
+    /// Generate a new random key for an algorithm
+    #[uniffi::constructor]
+    pub fn generate(alg: String, ephemeral: bool) -> Result<Arc<Self>, AskarError> {
+        let alg = KeyAlg::from_str(&alg).map_err(Error::from)?;
+        Ok(Self::new(LocalKey::generate_with_rng(alg, ephemeral)?))
+    }
+
+    /// Load a key from a public or secret JWK
+    #[uniffi::constructor]
+    pub fn from_jwk(jwk: String) -> Result<Arc<Self>, AskarError> {
+        Ok(Self::new(LocalKey::from_jwk(&jwk)?))
+    }
+
+    /// Load a public key from its byte representation
+    #[uniffi::constructor]
+    pub fn from_public_bytes(alg: String, public: Vec<u8>) -> Result<Arc<Self>, AskarError> {
+        let alg = KeyAlg::from_str(&alg).map_err(Error::from)?;
+        Ok(Self::new(LocalKey::from_public_bytes(alg, &public)?))
+    }
+
+    /// Get the algorithm of the key
+    pub fn algorithm(&self) -> String {
+        self.key.algorithm().as_str().to_string()
+    }
+
+    /// Get the public JWK of the key
+    pub fn jwk_public(&self) -> Result<String, AskarError> {
+        Ok(self.key.to_jwk_public(None)?)
+    }
+
+    /// Get the JWK thumbprint of the key
+    pub fn jwk_thumbprint(&self) -> Result<String, AskarError> {
+        Ok(self.key.to_jwk_thumbprint(None)?)
+    }
+
+    /// Get the byte representation of the public key
+    pub fn public_bytes(&self) -> Result<Vec<u8>, AskarError> {
+        Ok(self.key.to_public_bytes()?.to_vec())
+    }
+
+    /// Sign a message using the key
+    pub fn sign_message(
+        &self,
+        message: Vec<u8>,
+        sig_type: Option<String>,
+    ) -> Result<Vec<u8>, AskarError> {
+        Ok(self.key.sign_message(&message, sig_type.as_deref())?)
+    }
+
+    /// Verify a message signature using the key
+    pub fn verify_signature(
+        &self,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+        sig_type: Option<String>,
+    ) -> Result<bool, AskarError> {
+        Ok(self
+            .key
+            .verify_signature(&message, &signature, sig_type.as_deref())?)
+    }
+}
+
+/// A key stored in a store profile
+#[derive(Debug, uniffi::Record)]
+pub struct AskarKeyEntry {
+    /// The key name
+    pub name: String,
+    /// The key algorithm, if known
+    pub algorithm: Option<String>,
+    /// The key metadata
+    pub metadata: Option<String>,
+    /// The key tags
+    pub tags: Vec<super::store::AskarEntryTag>,
+    /// The loaded key
+    pub key: Arc<AskarKey>,
+}
+
+impl TryFrom<KeyEntry> for AskarKeyEntry {
+    type Error = AskarError;
+
+    fn try_from(entry: KeyEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: entry.name().to_string(),
+            algorithm: entry.algorithm().map(str::to_string),
+            metadata: entry.metadata().map(str::to_string),
+            tags: entry.tags_as_slice().iter().map(Into::into).collect(),
+            key: AskarKey::new(entry.load_local_key()?),
+        })
+    }
+}
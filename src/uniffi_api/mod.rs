@@ -0,0 +1,10 @@
+//! Store, session and key interfaces exported through UniFFI
+//!
+//! These interfaces are used to generate Kotlin and Swift bindings with native
+//! async support, as an alternative to the callback-based C interface. Async
+//! methods are executed on the library runtime, and their results may be awaited
+//! from any foreign executor.
+
+mod error;
+mod key;
+mod store;
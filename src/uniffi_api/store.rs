@@ -0,0 +1,484 @@
+use std::{str::FromStr, sync::Arc};
+
+use super::{
+    error::AskarError,
+    key::{AskarKey, AskarKeyEntry},
+};
+use crate::{
+    entry::{Entry, EntryTag, TagFilter},
+    error::Error,
+    future::{spawn, BoxFuture},
+    kms::KeyReference,
+    store::{PassKey, Session, Store, StoreKeyMethod},
+    SharedSession,
+};
+
+/// A tag on a store record
+#[derive(Debug, uniffi::Record)]
+pub struct AskarEntryTag {
+    /// The tag name
+    pub name: String,
+    /// The tag value
+    pub value: String,
+    /// Whether the tag is stored unencrypted, allowing range queries
+    pub plaintext: bool,
+}
+
+impl From<&EntryTag> for AskarEntryTag {
+    fn from(tag: &EntryTag) -> Self {
+        Self {
+            name: tag.name().to_string(),
+            value: tag.value().to_string(),
+            plaintext: tag.is_plaintext(),
+        }
+    }
+}
+
+impl From<AskarEntryTag> for EntryTag {
+    fn from(tag: AskarEntryTag) -> Self {
+        Self::new(tag.name, tag.value, tag.plaintext)
+    }
+}
+
+/// A record in a store profile
+#[derive(Debug, uniffi::Record)]
+pub struct AskarEntry {
+    /// The record category
+    pub category: String,
+    /// The record name
+    pub name: String,
+    /// The record value
+    pub value: Vec<u8>,
+    /// The record tags
+    pub tags: Vec<AskarEntryTag>,
+}
+
+impl From<Entry> for AskarEntry {
+    fn from(entry: Entry) -> Self {
+        Self {
+            tags: entry.tags.iter().map(Into::into).collect(),
+            category: entry.category,
+            name: entry.name,
+            value: entry.value.to_vec(),
+        }
+    }
+}
+
+fn parse_tag_filter(tag_filter: Option<String>) -> Result<Option<TagFilter>, Error> {
+    Ok(tag_filter.as_deref().map(TagFilter::from_str).transpose()?)
+}
+
+fn convert_tags(tags: Option<Vec<AskarEntryTag>>) -> Option<Vec<EntryTag>> {
+    tags.map(|tags| tags.into_iter().map(Into::into).collect())
+}
+
+/// Generate a new raw store key
+#[uniffi::export]
+pub fn generate_raw_store_key(seed: Option<Vec<u8>>) -> Result<String, AskarError> {
+    let key = Store::new_raw_key(seed.as_deref())?;
+    Ok(key.to_string())
+}
+
+/// Remove a store instance using a database URL
+#[uniffi::export]
+pub async fn remove_store(spec_uri: String) -> Result<bool, AskarError> {
+    Ok(spawn(async move { Store::remove(&spec_uri).await }).await?)
+}
+
+/// An opened store instance
+#[derive(Debug, uniffi::Object)]
+pub struct AskarStore {
+    store: Store,
+}
+
+#[uniffi::export]
+impl AskarStore {
+    /// Provision a new store instance using a database URL
+    #[uniffi::constructor]
+    pub async fn provision(
+        spec_uri: String,
+        key_method: Option<String>,
+        pass_key: Option<String>,
+        profile: Option<String>,
+        recreate: bool,
+    ) -> Result<Arc<Self>, AskarError> {
+        let store = spawn(async move {
+            let key_method = match key_method.as_deref() {
+                Some(method) => StoreKeyMethod::parse_uri(method)?,
+                None => StoreKeyMethod::default(),
+            };
+            let pass_key = PassKey::from(pass_key.as_deref());
+            Store::provision(&spec_uri, key_method, pass_key, profile, recreate).await
+        })
+        .await?;
+        Ok(Arc::new(Self { store }))
+    }
+
+    /// Open an existing store instance using a database URL
+    #[uniffi::constructor]
+    pub async fn open(
+        spec_uri: String,
+        key_method: Option<String>,
+        pass_key: Option<String>,
+        profile: Option<String>,
+    ) -> Result<Arc<Self>, AskarError> {
+        let store = spawn(async move {
+            let key_method = key_method
+                .as_deref()
+                .map(StoreKeyMethod::parse_uri)
+                .transpose()?;
+            let pass_key = PassKey::from(pass_key.as_deref());
+            Store::open(&spec_uri, key_method, pass_key, profile).await
+        })
+        .await?;
+        Ok(Arc::new(Self { store }))
+    }
+
+    /// Get the name of the profile used by default
+    pub fn get_profile_name(&self) -> String {
+        self.store.get_active_profile()
+    }
+
+    /// Create a new profile in the store
+    pub async fn create_profile(&self, name: Option<String>) -> Result<String, AskarError> {
+        let store = self.store.clone();
+        Ok(spawn(async move { store.create_profile(name).await }).await?)
+    }
+
+    /// Get the names of all profiles in the store
+    pub async fn list_profiles(&self) -> Result<Vec<String>, AskarError> {
+        let store = self.store.clone();
+        Ok(spawn(async move { store.list_profiles().await }).await?)
+    }
+
+    /// Remove an existing profile and its records
+    pub async fn remove_profile(&self, name: String) -> Result<bool, AskarError> {
+        let store = self.store.clone();
+        Ok(spawn(async move { store.remove_profile(name).await }).await?)
+    }
+
+    /// Create a new session against the store
+    pub async fn session(&self, profile: Option<String>) -> Result<Arc<AskarSession>, AskarError> {
+        let store = self.store.clone();
+        let session = spawn(async move { store.session(profile).await }).await?;
+        Ok(AskarSession::new(session))
+    }
+
+    /// Create a new transaction against the store
+    pub async fn transaction(
+        &self,
+        profile: Option<String>,
+    ) -> Result<Arc<AskarSession>, AskarError> {
+        let store = self.store.clone();
+        let session = spawn(async move { store.transaction(profile).await }).await?;
+        Ok(AskarSession::new(session))
+    }
+
+    /// Close the store instance, waiting for any shutdown procedures to complete
+    pub async fn close(&self) -> Result<(), AskarError> {
+        let store = self.store.clone();
+        Ok(spawn(async move { store.close().await }).await?)
+    }
+}
+
+/// An active session or transaction against a store
+///
+/// The session is released once it is closed, committed or rolled back, after
+/// which its methods return an error.
+#[derive(Debug, uniffi::Object)]
+pub struct AskarSession {
+    session: SharedSession,
+}
+
+impl AskarSession {
+    fn new(session: Session) -> Arc<Self> {
+        Arc::new(Self {
+            session: SharedSession::new(session),
+        })
+    }
+
+    /// Run an operation on the active session in the library runtime
+    async fn run<F, T>(&self, f: F) -> Result<T, AskarError>
+    where
+        F: for<'s> FnOnce(&'s mut Session) -> BoxFuture<'s, Result<T, Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        Ok(self.session.run(f).await?)
+    }
+
+    /// Release the session, committing any pending transaction if requested
+    async fn release(&self, commit: bool) -> Result<(), AskarError> {
+        Ok(self.session.release(commit).await?)
+    }
+}
+
+#[uniffi::export]
+impl AskarSession {
+    /// Count the number of records matching a category and tag filter
+    pub async fn count(
+        &self,
+        category: Option<String>,
+        tag_filter: Option<String>,
+    ) -> Result<i64, AskarError> {
+        let tag_filter = parse_tag_filter(tag_filter)?;
+        self.run(move |s| Box::pin(async move { s.count(category.as_deref(), tag_filter).await }))
+            .await
+    }
+
+    /// Fetch a record by category and name
+    pub async fn fetch(
+        &self,
+        category: String,
+        name: String,
+        for_update: bool,
+    ) -> Result<Option<AskarEntry>, AskarError> {
+        self.run(move |s| {
+            Box::pin(async move {
+                let entry = s.fetch(&category, &name, for_update).await?;
+                Ok(entry.map(AskarEntry::from))
+            })
+        })
+        .await
+    }
+
+    /// Fetch all records matching a category and tag filter
+    pub async fn fetch_all(
+        &self,
+        category: Option<String>,
+        tag_filter: Option<String>,
+        limit: Option<i64>,
+        for_update: bool,
+    ) -> Result<Vec<AskarEntry>, AskarError> {
+        let tag_filter = parse_tag_filter(tag_filter)?;
+        self.run(move |s| {
+            Box::pin(async move {
+                let entries = s
+                    .fetch_all(
+                        category.as_deref(),
+                        tag_filter,
+                        limit,
+                        None,
+                        false,
+                        for_update,
+                    )
+                    .await?;
+                Ok(entries.into_iter().map(AskarEntry::from).collect())
+            })
+        })
+        .await
+    }
+
+    /// Insert a new record
+    pub async fn insert(
+        &self,
+        category: String,
+        name: String,
+        value: Vec<u8>,
+        tags: Option<Vec<AskarEntryTag>>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), AskarError> {
+        let tags = convert_tags(tags);
+        self.run(move |s| {
+            Box::pin(async move {
+                s.insert(&category, &name, &value, tags.as_deref(), expiry_ms)
+                    .await
+            })
+        })
+        .await
+    }
+
+    /// Replace the value and tags of an existing record
+    pub async fn replace(
+        &self,
+        category: String,
+        name: String,
+        value: Vec<u8>,
+        tags: Option<Vec<AskarEntryTag>>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), AskarError> {
+        let tags = convert_tags(tags);
+        self.run(move |s| {
+            Box::pin(async move {
+                s.replace(&category, &name, &value, tags.as_deref(), expiry_ms)
+                    .await
+            })
+        })
+        .await
+    }
+
+    /// Remove a record by category and name
+    pub async fn remove(&self, category: String, name: String) -> Result<(), AskarError> {
+        self.run(move |s| Box::pin(async move { s.remove(&category, &name).await }))
+            .await
+    }
+
+    /// Remove all records matching a category and tag filter
+    pub async fn remove_all(
+        &self,
+        category: Option<String>,
+        tag_filter: Option<String>,
+    ) -> Result<i64, AskarError> {
+        let tag_filter = parse_tag_filter(tag_filter)?;
+        self.run(move |s| {
+            Box::pin(async move { s.remove_all(category.as_deref(), tag_filter).await })
+        })
+        .await
+    }
+
+    /// Insert a new key
+    pub async fn insert_key(
+        &self,
+        name: String,
+        key: Arc<AskarKey>,
+        metadata: Option<String>,
+        tags: Option<Vec<AskarEntryTag>>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), AskarError> {
+        let tags = convert_tags(tags);
+        self.run(move |s| {
+            Box::pin(async move {
+                let reference = key
+                    .key
+                    .is_hardware_backed()
+                    .then_some(KeyReference::MobileSecureElement);
+                s.insert_key(
+                    &name,
+                    &key.key,
+                    metadata.as_deref(),
+                    reference,
+                    tags.as_deref(),
+                    expiry_ms,
+                )
+                .await
+            })
+        })
+        .await
+    }
+
+    /// Fetch a key by name
+    pub async fn fetch_key(
+        &self,
+        name: String,
+        for_update: bool,
+    ) -> Result<Option<AskarKeyEntry>, AskarError> {
+        let entry = self
+            .run(move |s| Box::pin(async move { s.fetch_key(&name, for_update).await }))
+            .await?;
+        entry.map(AskarKeyEntry::try_from).transpose()
+    }
+
+    /// Fetch all keys matching an algorithm, thumbprint and tag filter
+    pub async fn fetch_all_keys(
+        &self,
+        algorithm: Option<String>,
+        thumbprint: Option<String>,
+        tag_filter: Option<String>,
+        limit: Option<i64>,
+        for_update: bool,
+    ) -> Result<Vec<AskarKeyEntry>, AskarError> {
+        let tag_filter = parse_tag_filter(tag_filter)?;
+        let entries = self
+            .run(move |s| {
+                Box::pin(async move {
+                    s.fetch_all_keys(
+                        algorithm.as_deref(),
+                        thumbprint.as_deref(),
+                        tag_filter,
+                        limit,
+                        for_update,
+                    )
+                    .await
+                })
+            })
+            .await?;
+        entries.into_iter().map(AskarKeyEntry::try_from).collect()
+    }
+
+    /// Remove a key by name
+    pub async fn remove_key(&self, name: String) -> Result<(), AskarError> {
+        self.run(move |s| Box::pin(async move { s.remove_key(&name).await }))
+            .await
+    }
+
+    /// Commit the pending transaction and release the session
+    pub async fn commit(&self) -> Result<(), AskarError> {
+        self.release(true).await
+    }
+
+    /// Roll back the pending transaction and release the session
+    pub async fn rollback(&self) -> Result<(), AskarError> {
+        self.release(false).await
+    }
+
+    /// Release the session without committing
+    pub async fn close(&self) -> Result<(), AskarError> {
+        self.release(false).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::future::block_on;
+
+    #[test]
+    fn store_session_round_trip() {
+        block_on(async {
+            let pass_key = generate_raw_store_key(None).unwrap();
+            let store = AskarStore::provision(
+                "sqlite://:memory:".to_string(),
+                Some("raw".to_string()),
+                Some(pass_key),
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+
+            let txn = store.transaction(None).await.unwrap();
+            txn.insert(
+                "category".to_string(),
+                "name".to_string(),
+                b"value".to_vec(),
+                Some(vec![AskarEntryTag {
+                    name: "tag".to_string(),
+                    value: "a".to_string(),
+                    plaintext: true,
+                }]),
+                None,
+            )
+            .await
+            .unwrap();
+            let key = AskarKey::generate("ed25519".to_string(), false).unwrap();
+            txn.insert_key("key".to_string(), key.clone(), None, None, None)
+                .await
+                .unwrap();
+            txn.commit().await.unwrap();
+            assert!(matches!(
+                txn.count(None, None).await,
+                Err(AskarError::Input { .. })
+            ));
+
+            let session = store.session(None).await.unwrap();
+            let rows = session
+                .fetch_all(None, Some(r#"{"~tag": "a"}"#.to_string()), None, false)
+                .await
+                .unwrap();
+            assert_eq!(rows.len(), 1);
+            assert_eq!(rows[0].value, b"value");
+            assert!(rows[0].tags[0].plaintext);
+            let entry = session
+                .fetch_key("key".to_string(), false)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                entry.key.jwk_thumbprint().unwrap(),
+                key.jwk_thumbprint().unwrap()
+            );
+            session.close().await.unwrap();
+
+            store.close().await.unwrap();
+        });
+    }
+}
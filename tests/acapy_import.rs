@@ -0,0 +1,107 @@
+use aries_askar::{acapy::AcaPySubWallet, future::block_on, Store, StoreKeyMethod};
+
+mod utils;
+use self::utils::provision_memory_store;
+
+const ERR_RAW_KEY: &str = "Error creating raw store key";
+const ERR_SESSION: &str = "Error creating store session";
+const ERR_OPEN: &str = "Error opening test store instance";
+const ERR_CLOSE: &str = "Error closing test store instance";
+
+async fn count_records(store: &Store, profile: &str) -> i64 {
+    let mut conn = store
+        .session(Some(profile.to_string()))
+        .await
+        .expect(ERR_SESSION);
+    conn.count(None, None)
+        .await
+        .expect("Error counting records")
+}
+
+#[test]
+fn import_sub_wallets() {
+    let storage_path = std::env::temp_dir().join(format!("acapy-{}", rand::random::<u64>()));
+    let storage_path = storage_path.to_string_lossy().into_owned();
+    block_on(async {
+        let mut wallets = Vec::new();
+        for (name, records) in [("tenant-a", 2), ("tenant-b", 3)] {
+            let wallet_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+            std::fs::create_dir_all(format!("{}/{}", storage_path, name))
+                .expect("Error creating wallet directory");
+            let wallet = AcaPySubWallet::sqlite(&storage_path, name, &wallet_key, Some("RAW"))
+                .expect("Error describing sub-wallet");
+            let sub_wallet = Store::provision(
+                &format!("sqlite://{}/{}/sqlite.db", storage_path, name),
+                StoreKeyMethod::RawKey,
+                wallet_key,
+                Some(name.to_string()),
+                false,
+            )
+            .await
+            .expect(ERR_OPEN);
+            let mut conn = sub_wallet.session(None).await.expect(ERR_SESSION);
+            for idx in 0..records {
+                conn.insert("connection", &format!("conn-{}", idx), b"{}", None, None)
+                    .await
+                    .expect("Error inserting record");
+            }
+            drop(conn);
+            sub_wallet.close().await.expect(ERR_CLOSE);
+            wallets.push(if name == "tenant-b" {
+                wallet.with_profile("wallet-id-b")
+            } else {
+                wallet
+            });
+        }
+
+        let target = provision_memory_store(None).await;
+        let profiles = target
+            .import_acapy_sub_wallets(wallets)
+            .await
+            .expect("Error importing sub-wallets");
+        assert_eq!(profiles, vec!["tenant-a", "wallet-id-b"]);
+        assert_eq!(count_records(&target, "tenant-a").await, 2);
+        assert_eq!(count_records(&target, "wallet-id-b").await, 3);
+        target.close().await.expect(ERR_CLOSE);
+    });
+    std::fs::remove_dir_all(storage_path).expect("Error removing storage path");
+}
+
+#[test]
+fn import_profiles() {
+    block_on(async {
+        let source = provision_memory_store(Some("multitenant_sub_wallet".to_string())).await;
+        for profile in ["multitenant_sub_wallet", "wallet-id-a", "wallet-id-b"] {
+            if profile != "multitenant_sub_wallet" {
+                source
+                    .create_profile(Some(profile.to_string()))
+                    .await
+                    .expect("Error creating profile");
+            }
+            let mut conn = source
+                .session(Some(profile.to_string()))
+                .await
+                .expect(ERR_SESSION);
+            conn.insert("wallet_record", profile, b"{}", None, None)
+                .await
+                .expect("Error inserting record");
+        }
+
+        let target = provision_memory_store(None).await;
+        let mut profiles = target
+            .import_acapy_profiles(&source, false)
+            .await
+            .expect("Error importing profiles");
+        profiles.sort();
+        assert_eq!(profiles, vec!["wallet-id-a", "wallet-id-b"]);
+        assert_eq!(count_records(&target, "wallet-id-a").await, 1);
+        assert!(!target
+            .list_profiles()
+            .await
+            .expect("Error listing profiles")
+            .contains(&"multitenant_sub_wallet".to_string()));
+
+        source.close().await.expect(ERR_CLOSE);
+        target.close().await.expect(ERR_CLOSE);
+    });
+}
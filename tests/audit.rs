@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex};
+
+use aries_askar::{
+    audit::{set_audit_sink, AuditOperation, AuditRecord},
+    future::block_on,
+    kms::{KeyAlg, LocalKey},
+};
+
+mod utils;
+use self::utils::provision_memory_store;
+
+const ERR_SESSION: &str = "Error creating store session";
+const ERR_CLOSE: &str = "Error closing test store instance";
+
+#[test]
+fn audit_records() {
+    let records = Arc::new(Mutex::new(Vec::<AuditRecord>::new()));
+    let sink_records = records.clone();
+    set_audit_sink(Some(Arc::new(move |record: &AuditRecord| {
+        sink_records.lock().unwrap().push(record.clone())
+    })));
+
+    block_on(async {
+        let mut db = provision_memory_store(None).await;
+        db.set_audit_actor(Some("tester".to_string()));
+        let profile = db.get_active_profile();
+
+        let keypair =
+            LocalKey::generate_with_rng(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("testkey", &keypair, None, None, None, None)
+            .await
+            .expect("Error inserting key");
+        conn.insert_key("testkey", &keypair, None, None, None, None)
+            .await
+            .expect_err("Expected duplicate key error");
+        keypair
+            .sign_message(b"message", None)
+            .expect("Error signing message");
+        conn.remove_all(Some("testcat"), None)
+            .await
+            .expect("Error removing entries");
+        drop(conn);
+        db.snapshot_to(":invalid")
+            .await
+            .expect_err("Expected invalid snapshot path error");
+
+        db.close().await.expect(ERR_CLOSE);
+
+        let records = records.lock().unwrap();
+        let ops: Vec<_> = records.iter().map(|r| r.operation).collect();
+        assert_eq!(
+            ops,
+            vec![
+                AuditOperation::Provision,
+                AuditOperation::CreateKey,
+                AuditOperation::CreateKey,
+                AuditOperation::Sign,
+                AuditOperation::RemoveAll,
+                AuditOperation::Backup,
+            ]
+        );
+        assert_eq!(records[0].actor, None);
+        assert_eq!(records[1].actor.as_deref(), Some("tester"));
+        assert_eq!(records[1].profile.as_deref(), Some(profile.as_str()));
+        assert_eq!(records[1].target.as_deref(), Some("testkey"));
+        assert_eq!(records[1].path, None);
+        assert!(records[1].is_success());
+        assert_eq!(records[2].error.as_deref(), Some("Duplicate"));
+        assert_eq!(records[5].target, None);
+        assert_eq!(records[5].path.as_deref(), Some(":invalid"));
+        assert_eq!(records[5].error.as_deref(), Some("Input error"));
+        assert!(records.windows(2).all(|w| w[0].sequence < w[1].sequence));
+    });
+
+    set_audit_sink(None);
+}
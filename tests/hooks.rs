@@ -0,0 +1,201 @@
+use std::sync::{Arc, Mutex};
+
+use aries_askar::{
+    future::block_on,
+    hooks::StoreHook,
+    kms::{KeyAlg, LocalKey},
+    Store, StoreKeyMethod,
+};
+
+mod utils;
+use self::utils::provision_memory_store;
+
+const ERR_RAW_KEY: &str = "Error creating raw store key";
+const ERR_SESSION: &str = "Error creating store session";
+const ERR_OPEN: &str = "Error opening test store instance";
+const ERR_CLOSE: &str = "Error closing test store instance";
+
+#[derive(Default)]
+struct RecordHook(Mutex<Vec<String>>);
+
+impl RecordHook {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl StoreHook for RecordHook {
+    fn on_insert(&self, _profile: &str, category: &str, name: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("insert {}/{}", category, name));
+    }
+
+    fn on_replace(&self, _profile: &str, category: &str, name: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("replace {}/{}", category, name));
+    }
+
+    fn on_remove(&self, _profile: &str, category: &str, name: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("remove {}/{}", category, name));
+    }
+
+    fn on_remove_all(&self, _profile: &str, category: Option<&str>, removed: i64) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("remove_all {:?} {}", category, removed));
+    }
+
+    fn on_insert_key(&self, _profile: &str, name: &str) {
+        self.0.lock().unwrap().push(format!("insert_key {}", name));
+    }
+
+    fn on_remove_key(&self, _profile: &str, name: &str) {
+        self.0.lock().unwrap().push(format!("remove_key {}", name));
+    }
+
+    fn on_key_sign(&self, _profile: &str, name: &str) {
+        self.0.lock().unwrap().push(format!("key_sign {}", name));
+    }
+
+    fn on_create_profile(&self, profile: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("create_profile {}", profile));
+    }
+}
+
+#[test]
+fn store_hooks() {
+    block_on(async {
+        let mut db = provision_memory_store(None).await;
+        let hook = Arc::new(RecordHook::default());
+        db.add_hook(hook.clone());
+
+        db.create_profile(Some("other".to_string()))
+            .await
+            .expect("Error creating profile");
+        assert_eq!(hook.take(), vec!["create_profile other"]);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("cat", "a", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        conn.insert("cat", "a", b"value", None, None)
+            .await
+            .expect_err("Expected duplicate row error");
+        conn.replace("cat", "a", b"other", None, None)
+            .await
+            .expect("Error replacing row");
+        conn.remove("cat", "a").await.expect("Error removing row");
+        let keypair =
+            LocalKey::generate_with_rng(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        conn.insert_key("key", &keypair, None, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let sig = conn
+            .sign_message("key", b"message", None)
+            .await
+            .expect("Error signing message");
+        assert!(keypair
+            .verify_signature(b"message", &sig, None)
+            .expect("Error verifying signature"));
+        conn.remove_key("key").await.expect("Error removing key");
+        drop(conn);
+        assert_eq!(
+            hook.take(),
+            vec![
+                "insert cat/a",
+                "replace cat/a",
+                "remove cat/a",
+                "insert_key key",
+                "key_sign key",
+                "remove_key key",
+            ]
+        );
+
+        // changes within a transaction are reported on commit only
+        let mut txn = db.transaction(None).await.expect(ERR_SESSION);
+        txn.insert("cat", "b", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        txn.rollback()
+            .await
+            .expect("Error rolling back transaction");
+        let mut txn = db.transaction(None).await.expect(ERR_SESSION);
+        txn.insert("cat", "c", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        txn.remove_all(Some("cat"), None)
+            .await
+            .expect("Error removing rows");
+        assert!(hook.take().is_empty());
+        txn.commit().await.expect("Error committing transaction");
+        assert_eq!(
+            hook.take(),
+            vec!["insert cat/c", "remove_all Some(\"cat\") 1"]
+        );
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn auto_create_profile_hooks() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let mut db = Store::provision(
+            "sqlite://:memory:?auto_create_profiles=true",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+        let hook = Arc::new(RecordHook::default());
+        db.add_hook(hook.clone());
+
+        let mut conn = db
+            .session(Some("tenant".to_string()))
+            .await
+            .expect(ERR_SESSION);
+        conn.fetch("cat", "a", false)
+            .await
+            .expect_err("Expected missing profile");
+        assert!(hook.take().is_empty());
+        conn.insert("cat", "a", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        conn.insert("cat", "b", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        drop(conn);
+        assert_eq!(
+            hook.take(),
+            vec!["create_profile tenant", "insert cat/a", "insert cat/b"]
+        );
+
+        // a profile created within a transaction is reported on commit only
+        let mut txn = db
+            .transaction(Some("other".to_string()))
+            .await
+            .expect(ERR_SESSION);
+        txn.insert("cat", "a", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        assert!(hook.take().is_empty());
+        txn.commit().await.expect("Error committing transaction");
+        assert_eq!(hook.take(), vec!["create_profile other", "insert cat/a"]);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
@@ -0,0 +1,35 @@
+use aries_askar::{
+    entry::{Entry, EntryKind, EntryTag, TagFilter},
+    redact::{set_redaction_policy, Redaction, RedactionPolicy},
+};
+
+#[test]
+fn redact_entry_debug() {
+    let entry = Entry::new(
+        EntryKind::Item,
+        "credential",
+        "did:example:123",
+        "secret-data",
+        vec![EntryTag::Encrypted(
+            "schema_id".to_string(),
+            "1".to_string(),
+        )],
+    );
+    let filter = TagFilter::is_eq("schema_id", "1");
+    let debug = format!("{:?} {:?}", entry, filter);
+    for visible in ["credential", "did:example:123", "schema_id"] {
+        assert!(debug.contains(visible), "{}", debug);
+    }
+
+    set_redaction_policy(RedactionPolicy {
+        category: Redaction::Truncated(4),
+        name: Redaction::Hashed,
+        tag_name: Redaction::Omitted,
+    });
+    let debug = format!("{:?} {:?}", entry, filter);
+    set_redaction_policy(RedactionPolicy::default());
+    assert!(debug.contains("\"cred...\""), "{}", debug);
+    for hidden in ["credential", "did:example:123", "schema_id", "secret-data"] {
+        assert!(!debug.contains(hidden), "{}", debug);
+    }
+}
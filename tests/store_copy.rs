@@ -1,28 +1,23 @@
+use std::sync::{Arc, Mutex};
+
 use aries_askar::{
     future::block_on,
     kms::{KeyAlg, LocalKey},
-    Store, StoreKeyMethod,
+    ConflictResolution, ImportStrategy, Progress, ProgressPhase, Store, StoreKeyMethod,
 };
 
+mod utils;
+use self::utils::provision_memory_store;
+
 const ERR_RAW_KEY: &str = "Error creating raw store key";
 const ERR_SESSION: &str = "Error creating store session";
-const ERR_OPEN: &str = "Error opening test store instance";
 const ERR_REQ_ROW: &str = "Row required";
 const ERR_CLOSE: &str = "Error closing test store instance";
 
 #[test]
 fn store_copy() {
     block_on(async {
-        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
-        let db = Store::provision(
-            "sqlite://:memory:",
-            StoreKeyMethod::RawKey,
-            pass_key,
-            None,
-            true,
-        )
-        .await
-        .expect(ERR_OPEN);
+        let db = provision_memory_store(None).await;
 
         let keypair =
             LocalKey::generate_with_rng(KeyAlg::Ed25519, false).expect("Error creating keypair");
@@ -76,3 +71,113 @@ fn store_copy() {
         db.close().await.expect(ERR_CLOSE);
     })
 }
+
+#[test]
+fn import_profile_key_conflict() {
+    block_on(async {
+        let mut stores = Vec::new();
+        for _ in 0..2 {
+            let store = provision_memory_store(None).await;
+            let keypair = LocalKey::generate_with_rng(KeyAlg::Ed25519, false)
+                .expect("Error creating keypair");
+            let mut conn = store.session(None).await.expect(ERR_SESSION);
+            conn.insert_key("testkey", &keypair, None, None, None, None)
+                .await
+                .expect("Error inserting key");
+            drop(conn);
+            stores.push(store);
+        }
+        let (source, target) = (&stores[0], &stores[1]);
+        let from_profile = source.get_default_profile().await.expect(ERR_SESSION);
+        let to_profile = target.get_default_profile().await.expect(ERR_SESSION);
+
+        let report = target
+            .import_profile(
+                source,
+                &from_profile,
+                &to_profile,
+                ImportStrategy::Fail,
+                true,
+            )
+            .await
+            .expect("Error importing profile");
+        assert!(report.dry_run);
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.conflicts[0].resolution, ConflictResolution::Failed);
+        assert!(report.unsupported_keys.is_empty());
+
+        let report = target
+            .import_profile(
+                source,
+                &from_profile,
+                &to_profile,
+                ImportStrategy::Rename,
+                false,
+            )
+            .await
+            .expect("Error importing profile");
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].name, "testkey");
+        assert_eq!(
+            report.conflicts[0].resolution,
+            ConflictResolution::Renamed("testkey-1".to_string())
+        );
+
+        let mut conn = target.session(None).await.expect(ERR_SESSION);
+        let mut names = conn
+            .fetch_all_keys(None, None, None, None, false)
+            .await
+            .expect("Error fetching keys")
+            .iter()
+            .map(|key| key.name().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["testkey", "testkey-1"]);
+        drop(conn);
+
+        for store in stores {
+            store.close().await.expect(ERR_CLOSE);
+        }
+    })
+}
+
+#[test]
+fn store_copy_progress() {
+    block_on(async {
+        let mut db = provision_memory_store(None).await;
+        let reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+        let handler_reports = reports.clone();
+        db.set_progress_handler(Some(Arc::new(move |progress: &Progress| {
+            handler_reports.lock().unwrap().push(progress.clone())
+        })));
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for name in ["a", "b", "c"] {
+            conn.insert("testcat", name, b"value", None, None)
+                .await
+                .expect("Error inserting row");
+        }
+        drop(conn);
+
+        let pass_key_copy = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let copied = db
+            .copy_to(
+                "sqlite://:memory:",
+                StoreKeyMethod::RawKey,
+                pass_key_copy,
+                true,
+            )
+            .await
+            .expect("Error copying store");
+        copied.close().await.expect(ERR_CLOSE);
+
+        let reports = reports.lock().unwrap().clone();
+        let last = reports.last().expect("Expected progress report");
+        assert_eq!(last.phase, ProgressPhase::Copy);
+        assert_eq!((last.processed, last.total), (3, Some(3)));
+        assert!(last.profile.is_some());
+
+        db.close().await.expect(ERR_CLOSE);
+    });
+}
@@ -0,0 +1,173 @@
+use aries_askar::{
+    entry::{EntryTag, TagFilter},
+    future::block_on,
+    Store, StoreKeyMethod,
+};
+
+mod utils;
+use self::utils::provision_memory_store;
+
+const ERR_INSERT: &str = "Error inserting row";
+const ERR_RAW_KEY: &str = "Error creating raw store key";
+const ERR_SESSION: &str = "Error creating store session";
+const ERR_CLOSE: &str = "Error closing test store instance";
+
+#[cfg(feature = "plaintext_export")]
+#[test]
+fn export_plaintext() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let tags = [
+            EntryTag::Encrypted("enc".to_string(), "a".to_string()),
+            EntryTag::Encrypted("enc".to_string(), "b".to_string()),
+            EntryTag::Plaintext("plain".to_string(), "c".to_string()),
+        ];
+        conn.insert("cat", "text", b"value", Some(&tags), None)
+            .await
+            .expect(ERR_INSERT);
+        conn.insert("cat", "binary", &[0xff, 0x00], None, None)
+            .await
+            .expect(ERR_INSERT);
+        conn.insert("other", "text", b"value", None, None)
+            .await
+            .expect(ERR_INSERT);
+        drop(conn);
+
+        let mut output = Vec::new();
+        let count = db
+            .export_plaintext(None, Some("cat".to_string()), None, &mut output)
+            .await
+            .expect("Error exporting records");
+        assert_eq!(count, 2);
+        let mut lines: Vec<serde_json::Value> = output
+            .split(|c| *c == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).expect("Invalid JSON line"))
+            .collect();
+        lines.sort_by_key(|line| line["name"].as_str().map(str::to_string));
+        assert_eq!(
+            lines,
+            vec![
+                serde_json::json!({
+                    "category": "cat", "name": "binary", "value_base64": "/wA=", "tags": {}
+                }),
+                serde_json::json!({
+                    "category": "cat", "name": "text", "value": "value",
+                    "tags": {"enc": ["a", "b"], "~plain": "c"}
+                }),
+            ]
+        );
+
+        let mut output = Vec::new();
+        let count = db
+            .export_plaintext(
+                None,
+                None,
+                Some(TagFilter::is_eq("~plain", "c")),
+                &mut output,
+            )
+            .await
+            .expect("Error exporting records");
+        assert_eq!(count, 1);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn export_encrypted_filtered() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+        let tenant = db
+            .create_profile(Some("tenant".to_string()))
+            .await
+            .expect("Error creating profile");
+        let default = db
+            .get_default_profile()
+            .await
+            .expect("Error fetching profile");
+
+        for profile in [None, Some(tenant.clone())] {
+            let mut conn = db.session(profile).await.expect(ERR_SESSION);
+            let tags = [EntryTag::Plaintext(
+                "state".to_string(),
+                "active".to_string(),
+            )];
+            conn.insert("credential", "cred-1", b"value", None, None)
+                .await
+                .expect(ERR_INSERT);
+            conn.insert("credential", "cred-2", b"value", None, None)
+                .await
+                .expect(ERR_INSERT);
+            conn.insert("connection", "conn-1", b"value", Some(&tags), None)
+                .await
+                .expect(ERR_INSERT);
+            conn.insert("connection", "conn-2", b"value", None, None)
+                .await
+                .expect(ERR_INSERT);
+        }
+
+        let export_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let mut output = Vec::new();
+        let info = db
+            .export_encrypted(
+                Some(vec![tenant.clone()]),
+                Some("credential".to_string()),
+                None,
+                StoreKeyMethod::RawKey,
+                export_key.as_ref(),
+                &mut output,
+            )
+            .await
+            .expect("Error exporting records");
+        assert_eq!((info.changes, info.until), (2, 0));
+        let mut connections = Vec::new();
+        let info = db
+            .export_encrypted(
+                None,
+                Some("connection".to_string()),
+                Some(TagFilter::is_eq("~state", "active")),
+                StoreKeyMethod::RawKey,
+                export_key.as_ref(),
+                &mut connections,
+            )
+            .await
+            .expect("Error exporting records");
+        assert_eq!(info.changes, 2);
+        db.close().await.expect(ERR_CLOSE);
+
+        let target = provision_memory_store(None).await;
+        target
+            .restore_changes(output.as_slice(), export_key.as_ref(), 0)
+            .await
+            .expect("Error importing records");
+        target
+            .restore_changes(connections.as_slice(), export_key.as_ref(), 0)
+            .await
+            .expect("Error importing records");
+
+        let mut conn = target.session(Some(tenant)).await.expect(ERR_SESSION);
+        let mut names = conn
+            .fetch_all(None, None, None, None, false, false)
+            .await
+            .expect("Error fetching rows")
+            .iter()
+            .map(|row| row.name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["conn-1", "cred-1", "cred-2"]);
+        drop(conn);
+        let mut conn = target.session(Some(default)).await.expect(ERR_SESSION);
+        let rows = conn
+            .fetch_all(None, None, None, None, false, false)
+            .await
+            .expect("Error fetching rows");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].name, "conn-1");
+        drop(conn);
+
+        target.close().await.expect(ERR_CLOSE);
+    })
+}
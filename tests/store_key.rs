@@ -1,28 +1,29 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
 use aries_askar::{
-    future::block_on,
-    kms::{KeyAlg, LocalKey},
-    Store, StoreKeyMethod,
+    crypto::alg::{AesTypes, EcCurves},
+    future::{block_on, BoxFuture},
+    kms::{
+        register_remote_key_service, wrap_key_jwe, KeyAlg, KeySource, LocalKey, RemoteKey,
+        RemoteKeyService, WrappedKey,
+    },
+    Error, ErrorKind,
 };
 
-const ERR_RAW_KEY: &str = "Error creating raw store key";
+mod utils;
+use self::utils::provision_memory_store;
+
 const ERR_SESSION: &str = "Error creating store session";
-const ERR_OPEN: &str = "Error opening test store instance";
 const ERR_REQ_ROW: &str = "Row required";
 const ERR_CLOSE: &str = "Error closing test store instance";
 
 #[test]
 fn keypair_create_fetch() {
     block_on(async {
-        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
-        let db = Store::provision(
-            "sqlite://:memory:",
-            StoreKeyMethod::RawKey,
-            pass_key,
-            None,
-            true,
-        )
-        .await
-        .expect(ERR_OPEN);
+        let db = provision_memory_store(None).await;
 
         let keypair =
             LocalKey::generate_with_rng(KeyAlg::Ed25519, false).expect("Error creating keypair");
@@ -50,3 +51,227 @@ fn keypair_create_fetch() {
         db.close().await.expect(ERR_CLOSE);
     })
 }
+
+#[test]
+fn keypair_export_import_wrapped() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let keypair =
+            LocalKey::generate_with_rng(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("testkey", &keypair, None, None, None, None)
+            .await
+            .expect("Error inserting key");
+
+        for wrap_alg in [
+            KeyAlg::Aes(AesTypes::A256Kw),
+            KeyAlg::X25519,
+            KeyAlg::EcCurve(EcCurves::Secp256r1),
+        ] {
+            let wrap_key =
+                LocalKey::generate_with_rng(wrap_alg, false).expect("Error creating wrapping key");
+            let wrapped = conn
+                .export_key_wrapped("testkey", &wrap_key)
+                .await
+                .expect("Error exporting wrapped key");
+
+            let other_key =
+                LocalKey::generate_with_rng(wrap_alg, false).expect("Error creating wrapping key");
+            let wrapped = WrappedKey::Jwe(&wrapped);
+            conn.import_key_wrapped("imported", wrapped, &other_key, None, None, None)
+                .await
+                .expect_err("Expected unwrap failure");
+
+            conn.import_key_wrapped("imported", wrapped, &wrap_key, None, None, None)
+                .await
+                .expect("Error importing wrapped key");
+            let found = conn
+                .fetch_key("imported", false)
+                .await
+                .expect("Error fetching key")
+                .expect(ERR_REQ_ROW);
+            assert_eq!(found.origin().map(|o| o.source), Some(KeySource::Imported));
+            let imported = found.load_local_key().expect("Error loading key");
+            assert_eq!(
+                imported.to_jwk_thumbprint(None).unwrap(),
+                keypair.to_jwk_thumbprint(None).unwrap()
+            );
+            conn.remove_key("imported")
+                .await
+                .expect("Error removing key");
+        }
+
+        conn.export_key_wrapped("missing", &keypair)
+            .await
+            .expect_err("Expected missing key error");
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_import_wrapped_stored() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let kek = LocalKey::generate_with_rng(KeyAlg::Aes(AesTypes::A256Kw), false)
+            .expect("Error creating wrapping key");
+        let keypair =
+            LocalKey::generate_with_rng(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("kek", &kek, None, None, None, None)
+            .await
+            .expect("Error inserting key");
+
+        let rfc3394 = kek.wrap_key(&keypair, &[]).expect("Error wrapping key");
+        let jwe = wrap_key_jwe(&kek, &keypair).expect("Error wrapping key");
+        for (name, wrapped) in [
+            (
+                "keywrap",
+                WrappedKey::KeyWrap(KeyAlg::Ed25519, rfc3394.ciphertext()),
+            ),
+            ("jwe", WrappedKey::Jwe(&jwe)),
+        ] {
+            conn.import_key_wrapped(name, wrapped, "missing", None, None, None)
+                .await
+                .expect_err("Expected missing unwrapping key");
+            conn.import_key_wrapped(name, wrapped, "kek", None, None, None)
+                .await
+                .expect("Error inserting wrapped key");
+            let found = conn
+                .fetch_key(name, false)
+                .await
+                .expect("Error fetching key")
+                .expect(ERR_REQ_ROW);
+            assert_eq!(found.algorithm(), Some(KeyAlg::Ed25519.as_str()));
+            assert_eq!(
+                found
+                    .load_local_key()
+                    .expect("Error loading key")
+                    .to_jwk_thumbprint(None)
+                    .unwrap(),
+                keypair.to_jwk_thumbprint(None).unwrap()
+            );
+        }
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn remote_key_delegation() {
+    struct TestSigner {
+        key: LocalKey,
+        available: Arc<AtomicBool>,
+    }
+
+    impl RemoteKey for TestSigner {
+        fn algorithm(&self) -> KeyAlg {
+            self.key.algorithm()
+        }
+
+        fn public_bytes(&self) -> BoxFuture<'_, Result<Vec<u8>, Error>> {
+            Box::pin(async { Ok(self.key.to_public_bytes()?.into_vec()) })
+        }
+
+        fn sign_message<'a>(
+            &'a self,
+            message: &'a [u8],
+            sig_type: Option<&'a str>,
+        ) -> BoxFuture<'a, Result<Vec<u8>, Error>> {
+            Box::pin(async move {
+                if !self.available.load(Ordering::SeqCst) {
+                    return Err(ErrorKind::Backend.into());
+                }
+                self.key.sign_message(message, sig_type)
+            })
+        }
+    }
+
+    struct TestService {
+        available: Arc<AtomicBool>,
+        loads: AtomicUsize,
+    }
+
+    impl RemoteKeyService for TestService {
+        fn load_key<'a>(
+            &'a self,
+            key_id: &'a str,
+        ) -> BoxFuture<'a, Result<Arc<dyn RemoteKey>, Error>> {
+            Box::pin(async move {
+                self.loads.fetch_add(1, Ordering::SeqCst);
+                let signer: Arc<dyn RemoteKey> = Arc::new(TestSigner {
+                    key: LocalKey::from_seed(KeyAlg::Ed25519, key_id.as_bytes(), None)?,
+                    available: self.available.clone(),
+                });
+                Ok(signer)
+            })
+        }
+    }
+
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let service = Arc::new(TestService {
+            available: Arc::new(AtomicBool::new(true)),
+            loads: AtomicUsize::new(0),
+        });
+        register_remote_key_service("test-signer", service.clone())
+            .expect("Error registering remote key service");
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_remote_key(
+            "remote",
+            "test-signer",
+            "issuer-key-0000000000000000000000",
+            Some("meta"),
+            None,
+            None,
+        )
+        .await
+        .expect("Error inserting remote key");
+        let found = conn
+            .fetch_key("remote", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        assert!(!found.is_local());
+        assert_eq!(found.algorithm(), Some(KeyAlg::Ed25519.as_str()));
+        assert_eq!(found.origin().map(|o| o.source), Some(KeySource::Remote));
+
+        let key = found.load_remote_key().expect("Error loading remote key");
+        let sig = key
+            .sign_message(b"message", None)
+            .await
+            .expect("Error signing message");
+        assert!(key
+            .verify_signature(b"message", &sig, None)
+            .expect("Error verifying signature"));
+        key.sign_message(b"other", None)
+            .await
+            .expect("Error signing message");
+        assert_eq!(service.loads.load(Ordering::SeqCst), 1);
+
+        service.available.store(false, Ordering::SeqCst);
+        let err = key
+            .sign_message(b"message", None)
+            .await
+            .expect_err("Expected signer failure");
+        assert_eq!(err.kind(), ErrorKind::Backend);
+        assert!(key
+            .verify_signature(b"message", &sig, None)
+            .expect("Error verifying signature"));
+
+        service.available.store(true, Ordering::SeqCst);
+        key.sign_message(b"message", None)
+            .await
+            .expect("Error signing message");
+        assert_eq!(service.loads.load(Ordering::SeqCst), 2);
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
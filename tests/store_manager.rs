@@ -0,0 +1,116 @@
+use aries_askar::{future::block_on, ErrorKind, Store, StoreKeyMethod, StoreManager};
+
+mod utils;
+use self::utils::provision_memory_store;
+
+const ERR_RAW_KEY: &str = "Error creating raw store key";
+const ERR_PROVISION: &str = "Error provisioning managed store";
+const ERR_GET: &str = "Error getting managed store";
+const ERR_SESSION: &str = "Error creating store session";
+
+#[test]
+fn store_manager_tracking() {
+    block_on(async {
+        let manager = StoreManager::new()
+            .with_max_stores(2)
+            .with_default_option("max_connections", "2");
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+            let id = manager
+                .provision(
+                    "sqlite://:memory:",
+                    StoreKeyMethod::RawKey,
+                    pass_key,
+                    None,
+                    true,
+                )
+                .await
+                .expect(ERR_PROVISION);
+            ids.push(id);
+        }
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(manager.ids().await, ids);
+
+        // each store holds its own entries
+        let store = manager.get(ids[0]).await.expect(ERR_GET);
+        let mut conn = store.session(None).await.expect(ERR_SESSION);
+        conn.insert("cat", "name", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        drop(conn);
+        let store = manager.get(ids[1]).await.expect(ERR_GET);
+        let mut conn = store.session(None).await.expect(ERR_SESSION);
+        assert_eq!(
+            conn.count(Some("cat"), None)
+                .await
+                .expect("Error counting rows"),
+            0
+        );
+        drop(conn);
+
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let err = manager
+            .provision(
+                "sqlite://:memory:",
+                StoreKeyMethod::RawKey,
+                pass_key,
+                None,
+                true,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Busy);
+
+        assert!(manager.close(ids[0]).await.expect("Error closing store"));
+        assert!(!manager.close(ids[0]).await.expect("Error closing store"));
+        let err = manager.get(ids[0]).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(manager.len().await, 1);
+
+        manager.close_all().await.expect("Error closing stores");
+        assert!(manager.is_empty().await);
+    });
+}
+
+#[test]
+fn store_manager_find() {
+    block_on(async {
+        let manager = StoreManager::new().with_default_option("max_connections", "2");
+        let db_url = "sqlite://sqlite-manager-find.db";
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let id = manager
+            .provision(db_url, StoreKeyMethod::RawKey, pass_key.clone(), None, true)
+            .await
+            .expect(ERR_PROVISION);
+        assert_eq!(
+            manager.find(db_url).await.expect("Error finding store"),
+            Some(id)
+        );
+        assert_eq!(
+            manager
+                .find("sqlite://sqlite-manager-other.db")
+                .await
+                .expect("Error finding store"),
+            None
+        );
+
+        manager.close(id).await.expect("Error closing store");
+        let id = manager
+            .open(db_url, Some(StoreKeyMethod::RawKey), pass_key, None)
+            .await
+            .expect("Error opening managed store");
+        assert_eq!(
+            manager.find(db_url).await.expect("Error finding store"),
+            Some(id)
+        );
+
+        let store = provision_memory_store(None).await;
+        let inserted = manager.insert(store).await.expect("Error inserting store");
+        assert_eq!(manager.ids().await, vec![id, inserted]);
+
+        manager.close_all().await.expect("Error closing stores");
+        Store::remove(db_url).await.expect("Error removing store");
+    });
+}
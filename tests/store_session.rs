@@ -0,0 +1,513 @@
+use std::time::{Duration, SystemTime};
+
+use aries_askar::{
+    entry::EntryKind,
+    future::block_on,
+    kms::{KeyAlg, LocalKey},
+    EntryExpiry, ErrorKind, HealthStatus, SessionScope, Store, StoreKeyMethod, TraceContext,
+    ValueFormat,
+};
+use serde::{Deserialize, Serialize};
+
+mod utils;
+use self::utils::provision_memory_store;
+
+const ERR_SESSION: &str = "Error creating store session";
+const ERR_FETCH: &str = "Error fetching row";
+const ERR_REQ_ROW: &str = "Row required";
+const ERR_CLOSE: &str = "Error closing test store instance";
+
+#[test]
+fn session_entry_cache() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.set_entry_cache(Some(2));
+        for name in ["a", "b", "c"] {
+            conn.insert("cat", name, b"value", None, None)
+                .await
+                .expect("Error inserting row");
+        }
+        for name in ["a", "b", "c"] {
+            conn.fetch("cat", name, false)
+                .await
+                .expect(ERR_FETCH)
+                .expect(ERR_REQ_ROW);
+        }
+
+        // records updated by another session are served from the cache,
+        // with the exception of the evicted record
+        let mut other = db.session(None).await.expect(ERR_SESSION);
+        for name in ["a", "b", "c"] {
+            other
+                .replace("cat", name, b"other", None, None)
+                .await
+                .expect("Error replacing row");
+        }
+        drop(other);
+        let fetch_value =
+            |row: Option<aries_askar::entry::Entry>| row.expect(ERR_REQ_ROW).value.to_vec();
+        assert_eq!(
+            fetch_value(conn.fetch("cat", "a", false).await.expect(ERR_FETCH)),
+            b"other".to_vec()
+        );
+        assert_eq!(
+            fetch_value(conn.fetch("cat", "c", false).await.expect(ERR_FETCH)),
+            b"value".to_vec()
+        );
+        assert_eq!(
+            fetch_value(conn.fetch("cat", "c", true).await.expect(ERR_FETCH)),
+            b"other".to_vec()
+        );
+
+        // updates made within the session invalidate the cached record
+        conn.replace("cat", "c", b"updated", None, None)
+            .await
+            .expect("Error replacing row");
+        assert_eq!(
+            fetch_value(conn.fetch("cat", "c", false).await.expect(ERR_FETCH)),
+            b"updated".to_vec()
+        );
+        conn.remove("cat", "c").await.expect("Error removing row");
+        assert!(conn
+            .fetch("cat", "c", false)
+            .await
+            .expect(ERR_FETCH)
+            .is_none());
+
+        conn.set_entry_cache(None);
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn store_version_info() {
+    block_on(async {
+        let key_method = StoreKeyMethod::parse_uri("kdf:argon2i:int").expect("Invalid key method");
+        let db = Store::provision(
+            "sqlite://:memory:",
+            key_method.clone(),
+            "test password".into(),
+            None,
+            true,
+        )
+        .await
+        .expect("Error provisioning store");
+        let info = db
+            .version_info()
+            .await
+            .expect("Error fetching version info");
+        assert_eq!(
+            StoreKeyMethod::parse_uri(&info.key_method).expect("Invalid key method"),
+            key_method
+        );
+        assert_eq!(info.store_cipher, "c20p");
+        assert_eq!(info.schema_version, info.supported_schema_version);
+        assert!(info.provisioned_by.is_some());
+        db.close().await.expect("Error closing store");
+    })
+}
+
+#[test]
+fn store_health() {
+    block_on(async {
+        let db = provision_memory_store(Some("main".to_string())).await;
+
+        let report = db.health().await;
+        assert_eq!(report.status, HealthStatus::Healthy);
+        assert!(report.is_ready());
+        for name in ["pool", "query", "key_cache"] {
+            let component = report.component(name).expect("Missing health component");
+            assert_eq!(component.status, HealthStatus::Healthy);
+            assert!(component.message.is_none());
+        }
+
+        // the query check fails once the active profile has been removed
+        let other = db
+            .create_profile(Some("other".to_string()))
+            .await
+            .expect("Error creating profile");
+        db.set_default_profile(other)
+            .await
+            .expect("Error setting default profile");
+        assert!(db
+            .remove_profile("main".to_string())
+            .await
+            .expect("Error removing profile"));
+        let report = db.health().await;
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+        assert!(!report.is_ready());
+        let query = report.component("query").expect("Missing health component");
+        assert_eq!(query.status, HealthStatus::Unhealthy);
+        assert!(query.message.is_some());
+        assert_eq!(
+            report.component("pool").map(|c| c.status),
+            Some(HealthStatus::Healthy)
+        );
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn session_trace_context() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        assert!(TraceContext::new("00-invalid", None).is_err());
+        let trace = TraceContext::new(
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            Some("congo=t61rcWkgMzE"),
+        )
+        .expect("Error creating trace context");
+        let mut txn = db
+            .transaction_with_trace(None, Some(trace.clone()))
+            .await
+            .expect(ERR_SESSION);
+        txn.insert("cat", "name", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        txn.commit().await.expect("Error committing transaction");
+
+        let mut conn = db
+            .session_with_trace(None, Some(trace))
+            .await
+            .expect(ERR_SESSION);
+        assert!(conn
+            .fetch("cat", "name", false)
+            .await
+            .expect(ERR_FETCH)
+            .is_some());
+        conn.set_trace_context(None);
+        assert_eq!(
+            conn.count(Some("cat"), None)
+                .await
+                .expect("Error counting rows"),
+            1
+        );
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn session_error_context() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let err = db
+            .session(Some("missing".to_string()))
+            .await
+            .expect_err("Expected missing profile error");
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(err.operation(), Some("ping"));
+        assert_eq!(err.backend_kind(), Some("sqlite"));
+        assert_eq!(err.has_profile(), Some(true));
+        assert_eq!(err.entry_kind(), None);
+        assert!(err
+            .to_string()
+            .contains("(operation: ping, backend: sqlite, profile: present)"));
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("cat", "name", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        let err = conn
+            .insert("cat", "name", b"value", None, None)
+            .await
+            .expect_err("Expected duplicate row error");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+        assert_eq!(err.operation(), Some("insert"));
+        assert_eq!(err.has_profile(), Some(false));
+        assert_eq!(err.entry_kind(), Some(EntryKind::Item));
+        drop(conn);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn session_scope() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("secret", "name", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        drop(conn);
+
+        let scope = SessionScope::new().with_category("connection");
+        let mut conn = db
+            .session_with_scope(None, scope.clone())
+            .await
+            .expect(ERR_SESSION);
+        assert_eq!(conn.scope(), Some(&scope));
+        conn.insert("connection", "name", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        assert!(conn
+            .fetch("connection", "name", false)
+            .await
+            .expect(ERR_FETCH)
+            .is_some());
+        assert_eq!(
+            conn.count(Some("connection"), None)
+                .await
+                .expect("Error counting rows"),
+            1
+        );
+
+        let check_denied = |result: Result<(), aries_askar::Error>| {
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::Input)
+        };
+        check_denied(conn.fetch("secret", "name", false).await.map(|_| ()));
+        check_denied(conn.remove("secret", "name").await);
+        check_denied(conn.count(None, None).await.map(|_| ()));
+        check_denied(conn.remove_all(Some("secret"), None).await.map(|_| ()));
+        check_denied(
+            conn.fetch_all(None, None, None, None, false, false)
+                .await
+                .map(|_| ()),
+        );
+        let keypair =
+            LocalKey::generate_with_rng(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        check_denied(
+            conn.insert_key("key", &keypair, None, None, None, None)
+                .await,
+        );
+        check_denied(conn.fetch_key("key", false).await.map(|_| ()));
+        drop(conn);
+
+        let mut txn = db
+            .transaction_with_scope(None, SessionScope::new().with_kind(EntryKind::Kms))
+            .await
+            .expect(ERR_SESSION);
+        txn.insert_key("key", &keypair, None, None, None, None)
+            .await
+            .expect("Error inserting key");
+        check_denied(txn.fetch("connection", "name", false).await.map(|_| ()));
+        txn.commit().await.expect("Error committing transaction");
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn session_active_profile() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+        let default_profile = db.get_active_profile();
+        db.create_profile(Some("tenant".to_string()))
+            .await
+            .expect("Error creating profile");
+
+        let tenant = db.with_active_profile("tenant");
+        assert_eq!(tenant.get_active_profile(), "tenant");
+        assert_eq!(db.get_active_profile(), default_profile);
+
+        let mut conn = tenant.session(None).await.expect(ERR_SESSION);
+        conn.insert("cat", "name", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        drop(conn);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        assert!(conn
+            .fetch("cat", "name", false)
+            .await
+            .expect(ERR_FETCH)
+            .is_none());
+        drop(conn);
+        let mut conn = db
+            .session(Some("tenant".to_string()))
+            .await
+            .expect(ERR_SESSION);
+        conn.fetch("cat", "name", false)
+            .await
+            .expect(ERR_FETCH)
+            .expect(ERR_REQ_ROW);
+        drop(conn);
+
+        let mut reset = tenant.clone();
+        reset.set_active_profile(None);
+        assert_eq!(reset.get_active_profile(), default_profile);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn store_exclude_archived() {
+    block_on(async {
+        let mut db = provision_memory_store(None).await;
+        let profile = db.get_active_profile();
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("cat", "name", b"value", None, None)
+            .await
+            .expect("Error inserting row");
+        drop(conn);
+        db.set_profile_archived(profile.clone(), true)
+            .await
+            .expect("Error archiving profile");
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let err = conn
+            .remove("cat", "name")
+            .await
+            .expect_err("Expected archived profile error");
+        assert_eq!(err.kind(), ErrorKind::Archived);
+        drop(conn);
+
+        async fn count_rows(db: &Store, profile: &str) -> usize {
+            let mut scan = db
+                .scan(
+                    Some(profile.to_string()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .await
+                .expect("Error starting scan");
+            let mut rows = 0;
+            while let Some(page) = scan.fetch_next().await.expect("Error fetching rows") {
+                rows += page.len();
+            }
+            rows
+        }
+        assert_eq!(count_rows(&db, &profile).await, 1);
+        db.set_exclude_archived(true);
+        assert_eq!(count_rows(&db, &profile).await, 0);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn session_serialized_values() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        label: String,
+        count: u32,
+    }
+
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let record = Record {
+            label: "first".to_string(),
+            count: 1,
+        };
+        for (name, format) in [("json", ValueFormat::Json), ("cbor", ValueFormat::Cbor)] {
+            conn.insert_serialized("cat", name, &record, format, None, None)
+                .await
+                .expect("Error inserting row");
+            let found: Record = conn
+                .fetch_as("cat", name, format, false)
+                .await
+                .expect(ERR_FETCH)
+                .expect(ERR_REQ_ROW);
+            assert_eq!(found, record);
+        }
+        let row = conn
+            .fetch("cat", "json", false)
+            .await
+            .expect(ERR_FETCH)
+            .expect(ERR_REQ_ROW);
+        assert_eq!(row.value.as_ref(), br#"{"label":"first","count":1}"#);
+
+        let updated = Record {
+            label: "second".to_string(),
+            count: 2,
+        };
+        conn.replace_serialized("cat", "cbor", &updated, ValueFormat::Cbor, None, None)
+            .await
+            .expect("Error replacing row");
+        let found: Option<Record> = conn
+            .fetch_as("cat", "cbor", ValueFormat::Cbor, false)
+            .await
+            .expect(ERR_FETCH);
+        assert_eq!(found, Some(updated));
+
+        // values are not decoded from a different format
+        let err = conn
+            .fetch_as::<Record>("cat", "json", ValueFormat::Cbor, false)
+            .await
+            .expect_err("Expected format mismatch");
+        assert_eq!(err.kind(), ErrorKind::Input);
+
+        let missing: Option<Record> = conn
+            .fetch_as("cat", "missing", ValueFormat::Json, false)
+            .await
+            .expect(ERR_FETCH);
+        assert!(missing.is_none());
+        drop(conn);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn session_entry_expiry() {
+    block_on(async {
+        let db = provision_memory_store(None).await;
+
+        let now = SystemTime::now();
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for (name, expiry) in [
+            ("after", EntryExpiry::after(Duration::from_secs(60))),
+            ("at", EntryExpiry::at(now + Duration::from_secs(60))),
+            ("past", EntryExpiry::at(now - Duration::from_secs(60))),
+            ("elapsed", EntryExpiry::from_millis(-1000)),
+        ] {
+            conn.insert("cat", name, b"value", None, expiry)
+                .await
+                .expect("Error inserting row");
+        }
+        for (name, found) in [
+            ("after", true),
+            ("at", true),
+            ("past", false),
+            ("elapsed", false),
+        ] {
+            let row = conn.fetch("cat", name, false).await.expect(ERR_FETCH);
+            assert_eq!(row.is_some(), found, "unexpected result for {}", name);
+        }
+
+        // an optional expiry in milliseconds is accepted as before
+        conn.insert("cat", "millis", b"value", None, Some(60_000))
+            .await
+            .expect("Error inserting row");
+        assert!(conn
+            .fetch("cat", "millis", false)
+            .await
+            .expect(ERR_FETCH)
+            .is_some());
+        conn.replace("cat", "millis", b"value", None, None)
+            .await
+            .expect("Error replacing row");
+        assert!(conn
+            .fetch("cat", "millis", false)
+            .await
+            .expect(ERR_FETCH)
+            .is_some());
+
+        conn.replace("cat", "after", b"value", None, now - Duration::from_secs(1))
+            .await
+            .expect("Error replacing row");
+        assert!(conn
+            .fetch("cat", "after", false)
+            .await
+            .expect(ERR_FETCH)
+            .is_none());
+        drop(conn);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
@@ -0,0 +1,17 @@
+use aries_askar::{Store, StoreKeyMethod};
+
+const ERR_RAW_KEY: &str = "Error creating raw store key";
+const ERR_OPEN: &str = "Error opening test store instance";
+
+/// Provision an in-memory SQLite store protected by a new raw key
+pub async fn provision_memory_store(profile: Option<String>) -> Store {
+    Store::provision(
+        "sqlite://:memory:",
+        StoreKeyMethod::RawKey,
+        Store::new_raw_key(None).expect(ERR_RAW_KEY),
+        profile,
+        true,
+    )
+    .await
+    .expect(ERR_OPEN)
+}
@@ -3,6 +3,8 @@ export enum KdfMethod {
   None = 'none',
   Argon2IMod = 'kdf:argon2i:mod',
   Argon2IInt = 'kdf:argon2i:int',
+  SplitArgon2IMod = 'split:kdf:argon2i:mod',
+  SplitArgon2IInt = 'split:kdf:argon2i:int',
 }
 
 export class StoreKeyMethod {
@@ -1,6 +1,6 @@
 """aries-askar Python wrapper library"""
 
-from .bindings import Encrypted, version
+from .bindings import Encrypted, capabilities, validate_tag_filter, version
 from .error import AskarError, AskarErrorCode
 from .key import Key
 from .store import Entry, EntryList, KeyEntry, KeyEntryList, Session, Store
@@ -9,8 +9,10 @@ from . import crypto_box
 from . import ecdh
 
 __all__ = (
+    "capabilities",
     "crypto_box",
     "ecdh",
+    "validate_tag_filter",
     "version",
     "AskarError",
     "AskarErrorCode",
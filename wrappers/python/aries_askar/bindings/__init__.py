@@ -50,6 +50,24 @@ def set_max_log_level(level: Union[str, int, None]):
     get_library().set_max_log_level(level)
 
 
+def set_redaction_policy(
+    category: Optional[str] = None,
+    name: Optional[str] = None,
+    tag_name: Optional[str] = None,
+):
+    """Set how record categories, names and tag names are shown in log messages.
+
+    Each may be `plain` (the default), `hashed`, `omitted` or `truncated:<len>`.
+    """
+    invoke(
+        "askar_set_redaction_policy",
+        (FfiStr, FfiStr, FfiStr),
+        category,
+        name,
+        tag_name,
+    )
+
+
 def invoke(name, argtypes, *args):
     """Perform a synchronous library function call."""
     get_library().invoke(name, argtypes, *args)
@@ -72,11 +90,28 @@ def generate_raw_key(seed: Union[str, bytes] = None) -> str:
     return str(key)
 
 
+def capabilities() -> dict:
+    """Get the capabilities of the installed library."""
+    return get_library().capabilities()
+
+
 def version() -> str:
     """Get the version of the installed library."""
     return get_library().version()
 
 
+def validate_tag_filter(tag_filter: Union[str, dict]) -> dict:
+    """Validate a tag filter without executing a query, returning diagnostics."""
+    diagnostics = StrBuffer()
+    invoke(
+        "askar_tag_filter_validate",
+        (FfiJson, POINTER(StrBuffer)),
+        tag_filter,
+        byref(diagnostics),
+    )
+    return json.loads(str(diagnostics))
+
+
 async def store_open(
     uri: str,
     key_method: Optional[str] = None,
@@ -128,6 +163,20 @@ async def store_create_profile(handle: StoreHandle, name: Optional[str] = None)
     )
 
 
+async def store_ensure_profile(handle: StoreHandle, name: str) -> bool:
+    """Create a profile in a Store unless it already exists."""
+    return (
+        await invoke_async(
+            "askar_store_ensure_profile",
+            (StoreHandle, FfiStr),
+            handle,
+            name,
+            return_type=c_int8,
+        )
+        != 0
+    )
+
+
 async def store_get_profile_name(handle: StoreHandle) -> str:
     """Get the name of the selected Store instance profile."""
     return str(
@@ -176,6 +225,76 @@ async def store_remove_profile(handle: StoreHandle, name: str) -> bool:
     )
 
 
+async def store_rename_profile(handle: StoreHandle, name: str, new_name: str) -> bool:
+    """Rename an existing profile in a Store."""
+    return (
+        await invoke_async(
+            "askar_store_rename_profile",
+            (StoreHandle, FfiStr, FfiStr),
+            handle,
+            name,
+            new_name,
+            return_type=c_int8,
+        )
+        != 0
+    )
+
+
+async def store_get_profile_metadata(handle: StoreHandle, name: str) -> Optional[str]:
+    """Get the metadata associated with a Store profile."""
+    return (
+        await invoke_async(
+            "askar_store_get_profile_metadata",
+            (StoreHandle, FfiStr),
+            handle,
+            name,
+            return_type=StrBuffer,
+        )
+    ).opt_str()
+
+
+async def store_set_profile_metadata(
+    handle: StoreHandle, name: str, metadata: Optional[str]
+):
+    """Replace or clear the metadata associated with a Store profile."""
+    await invoke_async(
+        "askar_store_set_profile_metadata",
+        (StoreHandle, FfiStr, FfiStr),
+        handle,
+        name,
+        metadata,
+    )
+
+
+async def store_get_profile_quota(handle: StoreHandle, name: str) -> dict:
+    """Get the limits on the records stored within a Store profile."""
+    quota = await invoke_async(
+        "askar_store_get_profile_quota",
+        (StoreHandle, FfiStr),
+        handle,
+        name,
+        return_type=StrBuffer,
+    )
+    return json.loads(str(quota))
+
+
+async def store_set_profile_quota(
+    handle: StoreHandle,
+    name: str,
+    max_entries: Optional[int],
+    max_value_bytes: Optional[int],
+):
+    """Replace the limits on the records stored within a Store profile."""
+    await invoke_async(
+        "askar_store_set_profile_quota",
+        (StoreHandle, FfiStr, c_int64, c_int64),
+        handle,
+        name,
+        -1 if max_entries is None else max_entries,
+        -1 if max_value_bytes is None else max_value_bytes,
+    )
+
+
 async def store_list_profiles(handle: StoreHandle) -> Sequence[str]:
     """List the profile identifiers present in a Store."""
     handle = await invoke_async(
@@ -184,6 +303,25 @@ async def store_list_profiles(handle: StoreHandle) -> Sequence[str]:
         handle,
         return_type=StringListHandle,
     )
+    return _string_list_values(handle)
+
+
+async def store_list_profiles_page(
+    handle: StoreHandle, offset: int, limit: int
+) -> Sequence[str]:
+    """List a page of the profile identifiers present in a Store, in order of name."""
+    handle = await invoke_async(
+        "askar_store_list_profiles_page",
+        (StoreHandle, c_int64, c_int64),
+        handle,
+        offset,
+        limit,
+        return_type=StringListHandle,
+    )
+    return _string_list_values(handle)
+
+
+def _string_list_values(handle: StringListHandle) -> Sequence[str]:
     count = c_int32()
     invoke(
         "askar_string_list_count",
@@ -256,9 +394,24 @@ async def store_remove(uri: str) -> bool:
 
 
 async def session_start(
-    handle: StoreHandle, profile: Optional[str] = None, as_transaction: bool = False
+    handle: StoreHandle,
+    profile: Optional[str] = None,
+    as_transaction: bool = False,
+    traceparent: Optional[str] = None,
+    tracestate: Optional[str] = None,
 ) -> SessionHandle:
     """Start a new session with an open Store."""
+    if traceparent:
+        return await invoke_async(
+            "askar_session_start_v2",
+            (StoreHandle, FfiStr, c_int8, FfiStr, FfiStr),
+            handle,
+            profile,
+            as_transaction,
+            traceparent,
+            tracestate,
+            return_type=SessionHandle,
+        )
     handle = await invoke_async(
         "askar_session_start",
         (StoreHandle, FfiStr, c_int8),
@@ -353,8 +506,31 @@ async def session_update(
     value: Optional[Union[str, bytes]] = None,
     tags: Optional[dict] = None,
     expiry_ms: Optional[int] = None,
+    unencrypted: bool = False,
 ):
     """Update a Store by inserting, updating, or removing a record."""
+    if unencrypted:
+        return await invoke_async(
+            "askar_session_update_v2",
+            (
+                SessionHandle,
+                c_int8,
+                FfiStr,
+                FfiStr,
+                FfiByteBuffer,
+                FfiTagsJson,
+                c_int64,
+                c_int8,
+            ),
+            handle,
+            operation.value,
+            category,
+            name,
+            value,
+            tags,
+            -1 if expiry_ms is None else expiry_ms,
+            unencrypted,
+        )
     return await invoke_async(
         "askar_session_update",
         (SessionHandle, c_int8, FfiStr, FfiStr, FfiByteBuffer, FfiTagsJson, c_int64),
@@ -461,11 +637,22 @@ async def scan_start(
     limit: Optional[int] = None,
     order_by: Optional[str] = None,
     descending: bool = False,
+    cursor: Optional[str] = None,
 ) -> ScanHandle:
     """Create a new Scan against the Store."""
     return await invoke_async(
-        "askar_scan_start",
-        (StoreHandle, FfiStr, FfiStr, FfiJson, c_int64, c_int64, FfiStr, c_int8),
+        "askar_scan_start_v2",
+        (
+            StoreHandle,
+            FfiStr,
+            FfiStr,
+            FfiJson,
+            c_int64,
+            c_int64,
+            FfiStr,
+            c_int8,
+            FfiStr,
+        ),
         handle,
         profile,
         category,
@@ -474,10 +661,23 @@ async def scan_start(
         limit if limit is not None else -1,
         order_by,
         descending,
+        cursor,
         return_type=ScanHandle,
     )
 
 
+async def scan_cursor(handle: ScanHandle) -> str:
+    """Get a cursor token for resuming a Scan after the rows fetched so far."""
+    return str(
+        await invoke_async(
+            "askar_scan_cursor",
+            (ScanHandle,),
+            handle,
+            return_type=StrBuffer,
+        )
+    )
+
+
 async def scan_next(handle: ScanHandle) -> EntryListHandle:
     return await invoke_async(
         "askar_scan_next", (ScanHandle,), handle, return_type=EntryListHandle
@@ -274,6 +274,7 @@ class LibLoad:
         Args:
             expect: Return a default error message if none is found
         """
+        details = self._get_current_error_details()
         err_json = StrBuffer()
         method = self.method(
             "askar_get_current_error", (POINTER(StrBuffer),), restype=c_int64
@@ -286,12 +287,29 @@ class LibLoad:
                 msg = None
             if msg and "message" in msg and "code" in msg:
                 return AskarError(
-                    AskarErrorCode(msg["code"]), msg["message"], msg.get("extra")
+                    AskarErrorCode(msg["code"]),
+                    msg["message"],
+                    msg.get("extra"),
+                    details,
                 )
             if not expect:
                 return None
         return AskarError(AskarErrorCode.WRAPPER, "Unknown error")
 
+    def _get_current_error_details(self) -> Optional[dict]:
+        """Get the structured details of the last error on the current thread."""
+        details_json = StrBuffer()
+        method = self.method(
+            "askar_get_current_error_details", (POINTER(StrBuffer),), restype=c_int64
+        )
+        if not method or method(byref(details_json)):
+            return None
+        try:
+            details = json.loads(details_json.value)
+        except json.JSONDecodeError:
+            return None
+        return details if details.get("code") else None
+
     def method(self, name, argtypes, *, restype=None):
         """Access a method of the library."""
         method = self._methods.get(name)
@@ -373,6 +391,18 @@ class Lib:
         set_level = _convert_log_level(level)
         self.invoke("askar_set_max_log_level", (c_int32,), set_level)
 
+    def capabilities(self) -> dict:
+        """Get the capabilities of the installed library."""
+        return json.loads(
+            str(
+                self.loaded.method(
+                    "askar_capabilities",
+                    None,
+                    restype=StrBuffer,
+                )()
+            )
+        )
+
     def version(self) -> str:
         """Get the version of the installed library."""
         return str(
@@ -1,6 +1,7 @@
 """Error classes."""
 
 from enum import IntEnum
+from typing import Optional
 
 
 class AskarErrorCode(IntEnum):
@@ -13,12 +14,62 @@ class AskarErrorCode(IntEnum):
     NOT_FOUND = 6
     UNEXPECTED = 7
     UNSUPPORTED = 8
+    CANCELLED = 9
+    QUOTA_EXCEEDED = 10
+    ARCHIVED = 11
     WRAPPER = 99
     CUSTOM = 100
 
 
 class AskarError(Exception):
-    def __init__(self, code: AskarErrorCode, message: str, extra: str = None):
+    def __init__(
+        self,
+        code: AskarErrorCode,
+        message: str,
+        extra: str = None,
+        details: dict = None,
+    ):
         super().__init__(message)
         self.code = code
         self.extra = extra
+        self.details = details or {}
+
+    @property
+    def backend_code(self) -> Optional[str]:
+        """The classification of the database error, such as `lock_unavailable`."""
+        return self.details.get("backend_code")
+
+    @property
+    def sqlstate(self) -> Optional[str]:
+        """The database SQLSTATE or driver error code, when known."""
+        return self.details.get("sqlstate")
+
+    @property
+    def operation(self) -> Optional[str]:
+        """The name of the library operation which produced the error."""
+        return self.details.get("operation")
+
+    @property
+    def store_operation(self) -> Optional[str]:
+        """The name of the store operation which failed, such as `fetch`."""
+        return self.details.get("store_operation")
+
+    @property
+    def backend(self) -> Optional[str]:
+        """The kind of store backend, such as `sqlite` or `postgres`."""
+        return self.details.get("backend")
+
+    @property
+    def has_profile(self) -> Optional[bool]:
+        """Whether a profile name was provided for the failed operation."""
+        return self.details.get("has_profile")
+
+    @property
+    def entry_kind(self) -> Optional[str]:
+        """The kind of entry accessed by the failed operation, `kms` or `item`."""
+        return self.details.get("entry_kind")
+
+    @property
+    def retryable(self) -> bool:
+        """Whether the operation may succeed if retried."""
+        return bool(self.details.get("retryable"))
@@ -249,6 +249,7 @@ class Scan:
         limit: int = None,
         order_by: Optional[str] = None,
         descending: bool = False,
+        cursor: Optional[str] = None,
     ):
         """Initialize the Scan instance."""
         self._params = (
@@ -260,6 +261,7 @@ class Scan:
             limit,
             order_by,
             descending,
+            cursor,
         )
         self._handle: ScanHandle = None
         self._buffer: IterEntryList = None
@@ -285,6 +287,7 @@ class Scan:
                 limit,
                 order_by,
                 descending,
+                cursor,
             ) = self._params
             self._params = None
             if not store.handle:
@@ -300,6 +303,7 @@ class Scan:
                 limit,
                 order_by,
                 descending,
+                cursor,
             )
             list_handle = await bindings.scan_next(self._handle)
             self._buffer = iter(EntryList(list_handle)) if list_handle else None
@@ -319,6 +323,20 @@ class Scan:
             rows.append(row)
         return rows
 
+    async def cursor(self) -> str:
+        """Get a cursor token for resuming the scan after the rows fetched so far.
+
+        The cursor is only available once all rows of the fetched pages have been
+        consumed, such as after `fetch_all` for a scan with a limit.
+        """
+        if self._handle is None:
+            raise AskarError(AskarErrorCode.WRAPPER, "Scan has not been started")
+        if self._buffer and self._buffer._pos < self._buffer._len:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cursor is only available at a page boundary"
+            )
+        return await bindings.scan_cursor(self._handle)
+
     def __repr__(self) -> str:
         """Format the scan instance as a string."""
         return f"<Scan(handle={self._handle})>"
@@ -400,10 +418,20 @@ class Store:
         Create a new profile in the store.
 
         Returns the name of the profile, which is automatically
-        generated if not provided.
+        generated if not provided. An existing profile with the
+        given name is returned unchanged.
         """
         return await bindings.store_create_profile(self._handle, name)
 
+    async def ensure_profile(self, name: str) -> bool:
+        """
+        Create a profile in the store unless it already exists.
+
+        Returns `True` if the profile was created. This may be called
+        concurrently for the same name by multiple processes.
+        """
+        return await bindings.store_ensure_profile(self._handle, name)
+
     async def get_profile_name(self) -> str:
         """Accessor for the currently selected profile name."""
         return await bindings.store_get_profile_name(self._handle)
@@ -420,10 +448,60 @@ class Store:
         """Remove a profile from the store."""
         return await bindings.store_remove_profile(self._handle, name)
 
+    async def rename_profile(self, name: str, new_name: str) -> bool:
+        """
+        Rename a profile in the store.
+
+        The default profile setting is updated if it refers to the renamed profile.
+        Returns `False` if the profile was not found.
+        """
+        return await bindings.store_rename_profile(self._handle, name, new_name)
+
+    async def get_profile_metadata(self, name: str) -> Optional[str]:
+        """Accessor for the metadata associated with a profile."""
+        return await bindings.store_get_profile_metadata(self._handle, name)
+
+    async def set_profile_metadata(self, name: str, metadata: Optional[str]):
+        """
+        Setter for the metadata associated with a profile.
+
+        Profile metadata is stored unencrypted.
+        """
+        await bindings.store_set_profile_metadata(self._handle, name, metadata)
+
+    async def get_profile_quota(self, name: str) -> dict:
+        """
+        Accessor for the limits on the records stored within a profile.
+
+        The result contains the `max_entries` and `max_value_bytes` limits,
+        which are `None` when not configured.
+        """
+        return await bindings.store_get_profile_quota(self._handle, name)
+
+    async def set_profile_quota(
+        self,
+        name: str,
+        max_entries: Optional[int] = None,
+        max_value_bytes: Optional[int] = None,
+    ):
+        """
+        Setter for the limits on the records stored within a profile.
+
+        Inserts and replacements which would exceed a limit raise an
+        `AskarError` with the code `QUOTA_EXCEEDED`.
+        """
+        await bindings.store_set_profile_quota(
+            self._handle, name, max_entries, max_value_bytes
+        )
+
     async def list_profiles(self) -> Sequence[str]:
         """List the profile identifiers present in the store."""
         return await bindings.store_list_profiles(self._handle)
 
+    async def list_profiles_page(self, offset: int, limit: int) -> Sequence[str]:
+        """List a page of the profile identifiers in the store, in order of name."""
+        return await bindings.store_list_profiles_page(self._handle, offset, limit)
+
     async def rekey(
         self,
         key_method: str = None,
@@ -457,19 +535,54 @@ class Store:
         profile: str = None,
         order_by: Optional[str] = None,
         descending: bool = False,
+        cursor: Optional[str] = None,
     ) -> Scan:
         """Start a new record scan."""
         return Scan(
-            self, profile, category, tag_filter, offset, limit, order_by, descending
+            self,
+            profile,
+            category,
+            tag_filter,
+            offset,
+            limit,
+            order_by,
+            descending,
+            cursor,
         )
 
-    def session(self, profile: str = None) -> "OpenSession":
-        """Open a new session on the store without starting a transaction."""
-        return OpenSession(self._handle, profile, False)
+    def session(
+        self, profile: str = None, *, traceparent: str = None, tracestate: str = None
+    ) -> "OpenSession":
+        """Open a new session on the store without starting a transaction.
 
-    def transaction(self, profile: str = None, *, autocommit=None) -> "OpenSession":
+        A W3C `traceparent` and optional `tracestate` may be provided in order to
+        propagate the trace context of a request to the storage backend.
+        """
+        return OpenSession(
+            self._handle,
+            profile,
+            False,
+            traceparent=traceparent,
+            tracestate=tracestate,
+        )
+
+    def transaction(
+        self,
+        profile: str = None,
+        *,
+        autocommit=None,
+        traceparent: str = None,
+        tracestate: str = None,
+    ) -> "OpenSession":
         """Open a new transactional session on the store."""
-        return OpenSession(self._handle, profile, True, autocommit)
+        return OpenSession(
+            self._handle,
+            profile,
+            True,
+            autocommit,
+            traceparent=traceparent,
+            tracestate=tracestate,
+        )
 
     async def close(self, *, remove: bool = False) -> bool:
         """Close and free the pool instance."""
@@ -575,14 +688,27 @@ class Session:
         tags: dict = None,
         expiry_ms: int = None,
         value_json=None,
+        *,
+        unencrypted: bool = False,
     ):
-        """Insert a new record into the store."""
+        """Insert a new record into the store.
+
+        When `unencrypted` is set, the value is stored without encryption while
+        the category, name and tags remain protected.
+        """
         if not self._handle:
             raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
         if value is None and value_json is not None:
             value = json.dumps(value_json)
         await bindings.session_update(
-            self._handle, EntryOperation.INSERT, category, name, value, tags, expiry_ms
+            self._handle,
+            EntryOperation.INSERT,
+            category,
+            name,
+            value,
+            tags,
+            expiry_ms,
+            unencrypted,
         )
 
     async def replace(
@@ -593,14 +719,27 @@ class Session:
         tags: dict = None,
         expiry_ms: int = None,
         value_json=None,
+        *,
+        unencrypted: bool = False,
     ):
-        """Replace a record in the store matching a category and name."""
+        """Replace a record in the store matching a category and name.
+
+        When `unencrypted` is set, the value is stored without encryption while
+        the category, name and tags remain protected.
+        """
         if not self._handle:
             raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
         if value is None and value_json is not None:
             value = json.dumps(value_json)
         await bindings.session_update(
-            self._handle, EntryOperation.REPLACE, category, name, value, tags, expiry_ms
+            self._handle,
+            EntryOperation.REPLACE,
+            category,
+            name,
+            value,
+            tags,
+            expiry_ms,
+            unencrypted,
         )
 
     async def remove(
@@ -746,12 +885,17 @@ class OpenSession:
         profile: Optional[str],
         is_txn: bool,
         autocommit: Optional[bool] = None,
+        *,
+        traceparent: Optional[str] = None,
+        tracestate: Optional[str] = None,
     ):
         """Initialize the OpenSession instance."""
         self._store = store
         self._profile = profile
         self._is_txn = is_txn
         self._autocommit = autocommit
+        self._traceparent = traceparent
+        self._tracestate = tracestate
         self._session: Session = None
 
     @property
@@ -769,7 +913,13 @@ class OpenSession:
             raise AskarError(AskarErrorCode.WRAPPER, "Session already opened")
         return Session(
             self._store,
-            await bindings.session_start(self._store, self._profile, self._is_txn),
+            await bindings.session_start(
+                self._store,
+                self._profile,
+                self._is_txn,
+                self._traceparent,
+                self._tracestate,
+            ),
             self._is_txn,
             self._autocommit,
         )
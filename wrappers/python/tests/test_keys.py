@@ -7,6 +7,7 @@ from aries_askar import (
     KeyAlg,
     Key,
     SeedMethod,
+    capabilities,
 )
 
 
@@ -16,6 +17,15 @@ def test_get_supported_backends():
     assert backends == [str(KeyBackend.Software)]
 
 
+def test_capabilities():
+    caps = capabilities()
+
+    assert caps["abi_version"] >= 1
+    assert set(caps["key_algorithms"]) == {alg.value for alg in KeyAlg}
+    assert caps["key_backends"] == Key.get_supported_backends()
+    assert "sqlite" in caps["storage_backends"]
+
+
 def test_aes_cbc_hmac():
     key = Key.generate(KeyAlg.A128CBC_HS256)
     assert key.algorithm == KeyAlg.A128CBC_HS256
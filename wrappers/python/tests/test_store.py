@@ -6,9 +6,11 @@ import pytest_asyncio
 
 from aries_askar import (
     AskarError,
+    AskarErrorCode,
     KeyAlg,
     Key,
     Store,
+    validate_tag_filter,
 )
 
 
@@ -81,6 +83,29 @@ async def test_insert_update(store: Store):
         assert found is None
 
 
+@mark.asyncio
+async def test_insert_unencrypted(store: Store):
+    async with store as session:
+        await session.insert(
+            TEST_ENTRY["category"],
+            TEST_ENTRY["name"],
+            TEST_ENTRY["value"],
+            TEST_ENTRY["tags"],
+            unencrypted=True,
+        )
+        found = await session.fetch(TEST_ENTRY["category"], TEST_ENTRY["name"])
+        assert dict(found) == TEST_ENTRY
+
+        await session.replace(
+            TEST_ENTRY["category"],
+            TEST_ENTRY["name"],
+            b"new_value",
+            unencrypted=True,
+        )
+        found = await session.fetch(TEST_ENTRY["category"], TEST_ENTRY["name"])
+        assert found.value == b"new_value"
+
+
 @mark.asyncio
 async def test_remove_all(store: Store):
     async with store as session:
@@ -133,6 +158,47 @@ async def test_scan(store: Store):
     assert len(rows) == 1 and dict(rows[0]) == TEST_ENTRY
 
 
+def test_validate_tag_filter():
+    result = validate_tag_filter({"~plaintag": {"$gt": "a"}, "enctag": "b"})
+    assert result["valid"]
+    assert set(result["tags"]) == {"~plaintag", "enctag"}
+    assert not result["warnings"]
+
+    result = validate_tag_filter({"enctag": {"$like": "b%"}})
+    assert result["valid"]
+    assert [w["tag"] for w in result["warnings"]] == ["enctag"]
+
+    result = validate_tag_filter('{"enctag": ')
+    assert not result["valid"]
+    assert result["error"]["line"] == 1
+
+
+@mark.asyncio
+async def test_scan_cursor(store: Store):
+    async with store as session:
+        for idx in range(5):
+            await session.insert("cursor", f"name-{idx}", b"value")
+
+    scan = store.scan("cursor", limit=2, order_by="id")
+    rows = await scan.fetch_all()
+    assert [row.name for row in rows] == ["name-0", "name-1"]
+    cursor = await scan.cursor()
+
+    # Removing a record which was already fetched does not affect the cursor
+    async with store as session:
+        await session.remove("cursor", "name-0")
+
+    scan = store.scan("cursor", limit=2, order_by="id", cursor=cursor)
+    rows = await scan.fetch_all()
+    assert [row.name for row in rows] == ["name-2", "name-3"]
+
+    # A cursor cannot be used with a different ordering
+    with raises(AskarError):
+        await store.scan(
+            "cursor", order_by="id", descending=True, cursor=cursor
+        ).fetch_all()
+
+
 @mark.asyncio
 async def test_txn_basic(store: Store):
     async with store.transaction() as txn:
@@ -331,8 +397,8 @@ async def test_profile(store: Store):
             ) == 1
         await store_2.close()
 
-    with raises(AskarError, match="Duplicate"):
-        _ = await store.create_profile(profile)
+    assert await store.create_profile(profile) == profile
+    assert not await store.ensure_profile(profile)
 
     # check profile is still usable
     async with store.session(profile) as session:
@@ -370,6 +436,48 @@ async def test_profile(store: Store):
     assert (await store.get_default_profile()) == profile
 
 
+@mark.asyncio
+async def test_profile_management(store: Store):
+    active_profile = await store.get_profile_name()
+    profile = await store.create_profile()
+    await store.set_default_profile(profile)
+
+    assert await store.rename_profile(profile, "tenant-a")
+    assert not await store.rename_profile(profile, "tenant-b")
+    assert (await store.get_default_profile()) == "tenant-a"
+    with raises(AskarError, match="Duplicate") as exc_info:
+        await store.rename_profile("tenant-a", active_profile)
+    assert exc_info.value.operation == "askar_store_rename_profile"
+    assert not exc_info.value.retryable
+
+    assert (await store.get_profile_metadata("tenant-a")) is None
+    await store.set_profile_metadata("tenant-a", '{"label": "A"}')
+    assert (await store.get_profile_metadata("tenant-a")) == '{"label": "A"}'
+    await store.set_profile_metadata("tenant-a", None)
+    assert (await store.get_profile_metadata("tenant-a")) is None
+
+    profiles = sorted([active_profile, "tenant-a"])
+    assert (await store.list_profiles_page(0, 1)) == profiles[:1]
+    assert (await store.list_profiles_page(1, 1)) == profiles[1:]
+
+
+@mark.asyncio
+async def test_profile_quota(store: Store):
+    profile = await store.create_profile()
+    assert (await store.get_profile_quota(profile)) == {
+        "max_entries": None,
+        "max_value_bytes": None,
+    }
+    await store.set_profile_quota(profile, max_entries=1)
+    assert (await store.get_profile_quota(profile))["max_entries"] == 1
+
+    async with store.session(profile) as session:
+        await session.insert("category", "a", b"value")
+        with raises(AskarError) as excinfo:
+            await session.insert("category", "b", b"value")
+        assert excinfo.value.code == AskarErrorCode.QUOTA_EXCEEDED
+
+
 @mark.asyncio
 async def test_copy(store: Store):
     async with store as session: